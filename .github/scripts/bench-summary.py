@@ -0,0 +1,70 @@
+#!/usr/bin/env python
+
+import argparse
+import json
+import os
+import sys
+
+parser = argparse.ArgumentParser(
+        description='Summarize the results of `cargo bench` in JSON, and compare them with a baseline summary',
+        )
+
+parser.add_argument('--criterion-dir', default='target/criterion', help='The output directory of criterion')
+parser.add_argument('--output', help='Write the summary to this file instead of stdout')
+parser.add_argument('--baseline', help='A summary written by this script to compare with')
+parser.add_argument('--threshold', type=float, default=5.0,
+        help='Fail if the mean time of a benchmark increases by more than this percentage over the baseline')
+
+args = parser.parse_args()
+
+def load_json(path):
+    with open(path) as f:
+        return json.load(f)
+
+# Criterion keeps the latest result of each benchmark in `<benchmark>/new`.  All the times are in nanoseconds.
+results = {}
+for root, dirs, files in os.walk(args.criterion_dir):
+    if os.path.basename(root) != 'new' or 'benchmark.json' not in files or 'estimates.json' not in files:
+        continue
+    benchmark = load_json(os.path.join(root, 'benchmark.json'))
+    estimates = load_json(os.path.join(root, 'estimates.json'))
+    mean = estimates['mean']
+    results[benchmark['full_id']] = {
+        'mean': mean['point_estimate'],
+        'mean_lower_bound': mean['confidence_interval']['lower_bound'],
+        'mean_upper_bound': mean['confidence_interval']['upper_bound'],
+        'median': estimates['median']['point_estimate'],
+        'std_dev': estimates['std_dev']['point_estimate'],
+    }
+
+if len(results) == 0:
+    print("No benchmark results in {}".format(args.criterion_dir))
+    sys.exit(1)
+
+summary = json.dumps({'unit': 'ns', 'benchmarks': results}, indent=2, sort_keys=True)
+if args.output is not None:
+    with open(args.output, 'w') as f:
+        f.write(summary + '\n')
+else:
+    print(summary)
+
+if args.baseline is None:
+    sys.exit(0)
+
+# Compare with the baseline.  A benchmark regresses if its mean time increases by more than the threshold, and the
+# confidence intervals do not overlap.
+baseline = load_json(args.baseline)['benchmarks']
+regressions = []
+for name, result in sorted(results.items()):
+    if name not in baseline:
+        print("{}: not in the baseline".format(name), file=sys.stderr)
+        continue
+    base = baseline[name]
+    change = (result['mean'] / base['mean'] - 1) * 100
+    print("{}: {:.2f} ns -> {:.2f} ns ({:+.2f}%)".format(name, base['mean'], result['mean'], change), file=sys.stderr)
+    if change > args.threshold and result['mean_lower_bound'] > base['mean_upper_bound']:
+        regressions.append(name)
+
+if len(regressions) > 0:
+    print("Regressed by more than {}%: {}".format(args.threshold, ", ".join(regressions)), file=sys.stderr)
+    sys.exit(1)
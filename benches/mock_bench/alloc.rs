@@ -1,29 +1,35 @@
 use criterion::Criterion;
 
 use mmtk::memory_manager;
-use mmtk::util::test_util::fixtures::*;
-use mmtk::util::test_util::mock_method::*;
-use mmtk::util::test_util::mock_vm::{write_mockvm, MockVM};
+use mmtk::util::test_util::mock_gc::MockGC;
 use mmtk::AllocationSemantics;
 
+/// The heap size.  The allocated objects are not reachable, and are reclaimed by GCs.
+const HEAP_SIZE: usize = 32 * 1024 * 1024;
+
+/// The allocation semantics to benchmark.  Depending on the plan, they are served by the bump
+/// pointer, Immix, free-list, mark-compact and large object allocators.  Immortal allocations are
+/// not benchmarked, as they would fill up the heap.
+const SEMANTICS: [AllocationSemantics; 3] = [
+    AllocationSemantics::Default,
+    AllocationSemantics::NonMoving,
+    AllocationSemantics::Los,
+];
+
 pub fn bench(c: &mut Criterion) {
-    // Setting a larger heap, although the GC should be disabled in the MockVM
-    let mut fixture = MutatorFixture::create_with_heapsize(1 << 30);
-    {
-        write_mockvm(|mock| {
-            *mock = {
-                MockVM {
-                    is_collection_enabled: MockMethod::new_fixed(Box::new(|_| false)),
-                    ..MockVM::default()
-                }
-            }
+    // The plan and the other options are read from the environment variables.
+    let mut gc = MockGC::new(0, HEAP_SIZE, |_| {});
+    let plan = *gc.mmtk().get_options().plan;
+
+    let mut group = c.benchmark_group(format!("alloc/{:?}", plan));
+    for semantics in SEMANTICS {
+        let allocator = memory_manager::get_allocator_mapping(gc.mmtk(), semantics);
+        // Each iteration allocates and initializes an object with two fields, as a binding would
+        // do with `alloc` and `post_alloc`.  This includes the amortized cost of the slow path and
+        // the GCs.
+        group.bench_function(format!("{:?}/{:?}", semantics, allocator), |b| {
+            b.iter(|| gc.alloc(2, 0, semantics))
         });
     }
-
-    c.bench_function("alloc", |b| {
-        b.iter(|| {
-            let _addr =
-                memory_manager::alloc(&mut fixture.mutator, 8, 8, 0, AllocationSemantics::Default);
-        })
-    });
+    group.finish();
 }
@@ -0,0 +1,46 @@
+use criterion::Criterion;
+
+use mmtk::memory_manager;
+use mmtk::util::test_util::mock_gc::{field_slot, MockGC};
+use mmtk::util::test_util::mock_vm::MockVM;
+use mmtk::vm::ObjectModel;
+use mmtk::AllocationSemantics;
+use std::sync::atomic::Ordering;
+
+pub fn bench(c: &mut Criterion) {
+    // The plan and the other options are read from the environment variables.
+    let mut gc = MockGC::new(0, 8 * 1024 * 1024, |_| {});
+    let plan = *gc.mmtk().get_options().plan;
+    let barrier = gc.mmtk().get_plan().constraints().barrier;
+
+    let src = gc.alloc(1, 0, AllocationSemantics::Default);
+    let target = gc.alloc(0, 1, AllocationSemantics::Default);
+    let slot = field_slot(src, 0);
+
+    let mut group = c.benchmark_group(format!("barrier/{:?}/{:?}", plan, barrier));
+
+    // The pre and the post barriers, and the store, as done by `MockGC::store_field`.  The source
+    // object is logged after the first iteration, so this measures the fast path.
+    group.bench_function("store_field", |b| {
+        b.iter(|| gc.store_field(src, 0, Some(target)))
+    });
+
+    group.bench_function("object_reference_write_post/fast", |b| {
+        // Log the object, so each iteration only checks the log bit.
+        memory_manager::object_reference_write_post(gc.mutator(), src, slot, Some(target));
+        b.iter(|| {
+            memory_manager::object_reference_write_post(gc.mutator(), src, slot, Some(target))
+        })
+    });
+
+    group.bench_function("object_reference_write_post/slow", |b| {
+        // Unlog the object before each barrier, so each iteration takes the slow path and records
+        // the object in the mod buffer.  This includes the cost of setting the log bit.
+        b.iter(|| {
+            MockVM::GLOBAL_LOG_BIT_SPEC.mark_as_unlogged::<MockVM>(src, Ordering::Relaxed);
+            memory_manager::object_reference_write_post(gc.mutator(), src, slot, Some(target))
+        })
+    });
+
+    group.finish();
+}
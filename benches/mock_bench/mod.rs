@@ -1,6 +1,7 @@
 use criterion::Criterion;
 
 pub mod alloc;
+pub mod barrier;
 pub mod internal_pointer;
 pub mod sft;
 
@@ -9,8 +10,11 @@ pub mod sft;
 // we pick the right benchmark to run.
 
 // The benchmark can be executed with the following command. The feature `mock_test` is required, as the tests use MockVM.
-// MMTK_BENCH=alloc cargo bench --features mock_test
-// MMTK_BENCH=sft   cargo bench --features mock_test
+// MMTK_BENCH=alloc   cargo bench --features mock_test
+// MMTK_BENCH=barrier cargo bench --features mock_test
+// MMTK_BENCH=sft     cargo bench --features mock_test
+// The `alloc` and `barrier` benchmarks use the plan set by the env var MMTK_PLAN, and include the plan in the benchmark
+// names. See benches/README.md for running all the benchmarks and comparing the results.
 
 // [Yi] I am not sure if these benchmarks are helpful any more after the MockVM refactoring. MockVM is really slow, as it
 // is accessed with a lock, and it dispatches every call to function pointers in a struct. These tests may use MockVM,
@@ -25,6 +29,7 @@ pub fn bench(c: &mut Criterion) {
     match std::env::var("MMTK_BENCH") {
         Ok(bench) => match bench.as_str() {
             "alloc" => alloc::bench(c),
+            "barrier" => barrier::bench(c),
             "internal_pointer" => internal_pointer::bench(c),
             "sft" => sft::bench(c),
             _ => panic!("Unknown benchmark {:?}", bench),
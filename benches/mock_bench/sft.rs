@@ -14,4 +14,14 @@ pub fn bench(c: &mut Criterion) {
     c.bench_function("sft read", |b| {
         b.iter(|| memory_manager::is_in_mmtk_spaces(black_box(obj)))
     });
+
+    // Objects in other spaces.  The SFT map may find contiguous and discontiguous spaces
+    // differently.
+    for semantics in [AllocationSemantics::Los, AllocationSemantics::Immortal] {
+        let addr = memory_manager::alloc(&mut fixture.mutator, 8, 8, 0, semantics);
+        let obj = MockVM::object_start_to_ref(addr);
+        c.bench_function(&format!("sft read/{:?}", semantics), |b| {
+            b.iter(|| memory_manager::is_in_mmtk_spaces(black_box(obj)))
+        });
+    }
 }
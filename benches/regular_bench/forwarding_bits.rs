@@ -0,0 +1,76 @@
+//! Benchmarks for claiming the forwarding bits of small objects in parallel.
+//!
+//! Side forwarding bits use two bits per object, so the bits of four small objects share one
+//! byte.  When GC workers forward neighbouring objects at the same time, a compare-and-swap loop
+//! on the byte fails and retries whenever another worker claims a neighbour, while `fetch_or`
+//! (used by `object_forwarding::attempt_to_forward`) succeeds the first time.
+
+use criterion::Criterion;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Barrier;
+
+const BEING_FORWARDED: u8 = 0b10;
+const FORWARDING_MASK: u8 = 0b11;
+
+/// The number of objects.  Four objects share one byte of forwarding bits.
+const NUM_OBJECTS: usize = 4096;
+/// The number of threads that claim objects at the same time.
+const NUM_THREADS: usize = 4;
+
+type Claim = fn(&AtomicU8, usize) -> u8;
+
+/// Claim the forwarding bits at `shift` with a compare-and-swap loop, and return the old bits.
+fn claim_with_cas(byte: &AtomicU8, shift: usize) -> u8 {
+    let mut old_byte = byte.load(Ordering::SeqCst);
+    loop {
+        let old_value = (old_byte >> shift) & FORWARDING_MASK;
+        if old_value != 0 {
+            return old_value;
+        }
+        let new_byte = old_byte | (BEING_FORWARDED << shift);
+        match byte.compare_exchange(old_byte, new_byte, Ordering::SeqCst, Ordering::Relaxed) {
+            Ok(_) => return old_value,
+            Err(current) => old_byte = current,
+        }
+    }
+}
+
+/// Claim the forwarding bits at `shift` with `fetch_or`, and return the old bits.
+fn claim_with_fetch_or(byte: &AtomicU8, shift: usize) -> u8 {
+    let old_value = (byte.load(Ordering::SeqCst) >> shift) & FORWARDING_MASK;
+    if old_value != 0 {
+        return old_value;
+    }
+    (byte.fetch_or(BEING_FORWARDED << shift, Ordering::SeqCst) >> shift) & FORWARDING_MASK
+}
+
+fn claim_all_in_parallel(bits: &[AtomicU8], claim: Claim) {
+    let barrier = Barrier::new(NUM_THREADS);
+    std::thread::scope(|s| {
+        for thread in 0..NUM_THREADS {
+            let barrier = &barrier;
+            s.spawn(move || {
+                barrier.wait();
+                // Neighbouring objects are claimed by different threads.
+                for object in (thread..NUM_OBJECTS).step_by(NUM_THREADS) {
+                    claim(&bits[object / 4], (object % 4) * 2);
+                }
+            });
+        }
+    });
+}
+
+pub fn bench(c: &mut Criterion) {
+    let bits: Vec<AtomicU8> = (0..NUM_OBJECTS / 4).map(|_| AtomicU8::new(0)).collect();
+    let claims: [(&str, Claim); 2] = [("cas", claim_with_cas), ("fetch_or", claim_with_fetch_or)];
+    for (name, claim) in claims {
+        c.bench_function(&format!("forwarding_bits_claim_{}", name), |b| {
+            b.iter(|| {
+                claim_all_in_parallel(&bits, claim);
+                for byte in bits.iter() {
+                    assert_eq!(byte.swap(0, Ordering::Relaxed), 0b1010_1010);
+                }
+            })
+        });
+    }
+}
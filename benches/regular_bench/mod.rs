@@ -1,7 +1,9 @@
 pub use criterion::Criterion;
 
 mod bulk_meta;
+mod forwarding_bits;
 
 pub fn bench(c: &mut Criterion) {
     bulk_meta::bench(c);
+    forwarding_bits::bench(c);
 }
@@ -0,0 +1,196 @@
+/* The C API of MMTk (the Cargo feature "ffi"). See `src/ffi/mod.rs`. */
+
+/* Generated with cbindgen. Do not edit by hand. Run `cbindgen --config cbindgen.toml --output include/mmtk_ffi.h` to regenerate. */
+
+#ifndef MMTK_FFI_H
+#define MMTK_FFI_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum MMTk_AllocationSemantics {
+  MMTk_AllocationSemantics_Default = 0,
+  MMTk_AllocationSemantics_Immortal = 1,
+  MMTk_AllocationSemantics_Los = 2,
+  MMTk_AllocationSemantics_Code = 3,
+  MMTk_AllocationSemantics_ReadOnly = 4,
+  MMTk_AllocationSemantics_LargeCode = 5,
+  MMTk_AllocationSemantics_NonMoving = 6,
+} MMTk_AllocationSemantics;
+
+typedef enum MMTk_AllocationError {
+  MMTk_AllocationError_HeapOutOfMemory,
+  MMTk_AllocationError_MmapOutOfMemory,
+} MMTk_AllocationError;
+
+typedef struct MMTk_Builder MMTk_Builder;
+
+typedef struct MMTk_GCWorker MMTk_GCWorker;
+
+typedef struct MMTk_Mutator MMTk_Mutator;
+
+typedef struct MMTk_RootsFactory MMTk_RootsFactory;
+
+typedef void *MMTk_VMThread;
+
+typedef void *MMTk_VMMutatorThread;
+
+typedef void *MMTk_VMWorkerThread;
+
+typedef uintptr_t MMTk_Address;
+
+/* An object reference. 0 (null) is only allowed where the API says so. */
+typedef uintptr_t MMTk_ObjectReference;
+
+typedef struct MMTk_MutatorClosure {
+  void (*func)(MMTk_Mutator *mutator, void *data);
+  void *data;
+} MMTk_MutatorClosure;
+
+typedef struct MMTk_SlotClosure {
+  void (*func)(MMTk_Address slot, void *data);
+  void *data;
+} MMTk_SlotClosure;
+
+typedef struct MMTk_Upcalls {
+  void (*stop_all_mutators)(MMTk_VMWorkerThread tls, MMTk_MutatorClosure visitor);
+  void (*resume_mutators)(MMTk_VMWorkerThread tls);
+  void (*block_for_gc)(MMTk_VMMutatorThread tls);
+  void (*spawn_gc_thread)(MMTk_VMThread tls, MMTk_GCWorker *worker);
+  void (*out_of_memory)(MMTk_VMThread tls, MMTk_AllocationError err_kind);
+  void (*schedule_finalization)(MMTk_VMWorkerThread tls);
+  bool (*is_mutator)(MMTk_VMThread tls);
+  MMTk_Mutator *(*get_mutator)(MMTk_VMMutatorThread tls);
+  void (*get_mutators)(MMTk_MutatorClosure visitor);
+  size_t (*number_of_mutators)(void);
+  size_t (*get_object_size)(MMTk_ObjectReference object);
+  void (*dump_object)(MMTk_ObjectReference object);
+  void (*scan_object)(MMTk_VMWorkerThread tls, MMTk_ObjectReference object, MMTk_SlotClosure visitor);
+  void (*scan_roots_in_mutator_thread)(MMTk_VMWorkerThread tls,
+                                       MMTk_Mutator *mutator,
+                                       MMTk_RootsFactory *factory);
+  void (*scan_vm_specific_roots)(MMTk_VMWorkerThread tls, MMTk_RootsFactory *factory);
+  MMTk_ObjectReference (*get_referent)(MMTk_ObjectReference reference);
+  void (*set_referent)(MMTk_ObjectReference reference, MMTk_ObjectReference referent);
+  void (*enqueue_references)(MMTk_VMWorkerThread tls,
+                             const MMTk_ObjectReference *references,
+                             size_t len);
+} MMTk_Upcalls;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+MMTk_Builder *mmtk_create_builder(void);
+
+bool mmtk_set_option_from_string(MMTk_Builder *builder, const char *name, const char *value);
+
+bool mmtk_set_fixed_heap_size(MMTk_Builder *builder, size_t heap_size);
+
+void mmtk_init(MMTk_Builder *builder, const MMTk_Upcalls *upcalls);
+
+void mmtk_initialize_collection(MMTk_VMThread tls);
+
+void mmtk_start_worker(MMTk_VMWorkerThread tls, MMTk_GCWorker *worker);
+
+MMTk_Mutator *mmtk_bind_mutator(MMTk_VMMutatorThread tls);
+
+void mmtk_destroy_mutator(MMTk_Mutator *mutator);
+
+void mmtk_flush_mutator(MMTk_Mutator *mutator);
+
+MMTk_Address mmtk_alloc(MMTk_Mutator *mutator,
+                        size_t size,
+                        size_t align,
+                        size_t offset,
+                        MMTk_AllocationSemantics semantics);
+
+void mmtk_post_alloc(MMTk_Mutator *mutator,
+                     MMTk_ObjectReference object,
+                     size_t bytes,
+                     MMTk_AllocationSemantics semantics);
+
+void mmtk_object_reference_write_pre(MMTk_Mutator *mutator,
+                                     MMTk_ObjectReference src,
+                                     MMTk_Address slot,
+                                     MMTk_ObjectReference target);
+
+void mmtk_object_reference_write_post(MMTk_Mutator *mutator,
+                                      MMTk_ObjectReference src,
+                                      MMTk_Address slot,
+                                      MMTk_ObjectReference target);
+
+#if defined(MMTK_FEATURE_OBJECT_PINNING)
+bool mmtk_pin_object(MMTk_ObjectReference object);
+#endif
+
+#if defined(MMTK_FEATURE_OBJECT_PINNING)
+bool mmtk_unpin_object(MMTk_ObjectReference object);
+#endif
+
+#if defined(MMTK_FEATURE_OBJECT_PINNING)
+bool mmtk_is_pinned(MMTk_ObjectReference object);
+#endif
+
+#if defined(MMTK_FEATURE_IS_MMTK_OBJECT)
+bool mmtk_is_mmtk_object(MMTk_Address addr);
+#endif
+
+bool mmtk_is_in_mmtk_spaces(MMTk_ObjectReference object);
+
+#if defined(MMTK_FEATURE_ADDRESS_BASED_HASHING)
+uintptr_t mmtk_object_hash(MMTk_ObjectReference object);
+#endif
+
+bool mmtk_is_live_object(MMTk_ObjectReference object);
+
+bool mmtk_will_never_move(MMTk_ObjectReference object);
+
+bool mmtk_is_mapped_address(MMTk_Address address);
+
+void mmtk_handle_user_collection_request(MMTk_VMMutatorThread tls);
+
+void mmtk_add_finalizer(MMTk_ObjectReference object);
+
+MMTk_ObjectReference mmtk_get_finalized_object(void);
+
+void mmtk_add_weak_candidate(MMTk_ObjectReference reff);
+
+void mmtk_add_soft_candidate(MMTk_ObjectReference reff);
+
+void mmtk_add_phantom_candidate(MMTk_ObjectReference reff);
+
+void mmtk_harness_begin(MMTk_VMMutatorThread tls);
+
+void mmtk_harness_end(void);
+
+size_t mmtk_used_bytes(void);
+
+size_t mmtk_free_bytes(void);
+
+size_t mmtk_total_bytes(void);
+
+MMTk_Address mmtk_starting_heap_address(void);
+
+MMTk_Address mmtk_last_heap_address(void);
+
+void mmtk_roots_factory_report_slots(MMTk_RootsFactory *factory,
+                                     const MMTk_Address *slots,
+                                     size_t len);
+
+void mmtk_roots_factory_report_pinning_roots(MMTk_RootsFactory *factory,
+                                             const MMTk_ObjectReference *nodes,
+                                             size_t len);
+
+void mmtk_roots_factory_report_tpinning_roots(MMTk_RootsFactory *factory,
+                                              const MMTk_ObjectReference *nodes,
+                                              size_t len);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* MMTK_FFI_H */
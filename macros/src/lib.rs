@@ -5,6 +5,7 @@ use syn::DeriveInput;
 
 mod has_spaces_impl;
 mod plan_trace_object_impl;
+mod region_impl;
 mod util;
 
 const DEBUG_MACRO_OUTPUT: bool = false;
@@ -21,6 +22,11 @@ const DEBUG_MACRO_OUTPUT: bool = false;
 /// * Add `#[parent]` to the field that contain more space fields.  This attribute is usually
 ///   added to `Gen`, `CommonPlan` or `BasePlan` fields.  There can be at most one parent in
 ///   a struct.
+///
+/// The macro also works for enums, e.g. a plan component that chooses its spaces at run time.
+/// Add the attributes to the fields of each variant, which may have named or unnamed fields.
+/// The generated methods match `self` with the variants, and each variant can have its own
+/// parent.
 #[proc_macro_error]
 #[proc_macro_derive(HasSpaces, attributes(space, parent))]
 pub fn derive_has_spaces(input: TokenStream) -> TokenStream {
@@ -37,19 +43,29 @@ pub fn derive_has_spaces(input: TokenStream) -> TokenStream {
 /// `HasSpaces` trait.  When using this derive macro, all spaces must implement the
 /// `PolicyTraceObject` trait.  The generated `trace_object` method will check for spaces in the
 /// current plan and, if the object is not in any of them, check for plans in the parent struct.
-/// The parent struct must also implement the `PlanTraceObject` trait.
+/// The parent struct must also implement the `PlanTraceObject` trait.  Like `HasSpaces`, this
+/// macro works for enums, too, and `trace_object` dispatches on the variant of `self`.
 ///
 /// In addition, the user can add the following attributes to fields in order to control the
 /// behavior of the generated `trace_object` method.
 ///
 /// * Add `#[copy_semantics(CopySemantics::X)]` to a space field to specify that when tracing
 ///   objects in that space, `Some(CopySemantics::X)` will be passed to the `Space::trace_object`
-///   method as the `copy` argument.
+///   method as the `copy` argument.  Custom plans can use their own copy semantics with
+///   `#[copy_semantics(CopySemantics::Custom(CustomCopySemantics::X))]`, and register it in the
+///   `CopyConfig` of the plan.
 /// * Add `#[post_scan]` to any space field that has some policy-specific `post_scan_object()`. For
 ///   objects in those spaces, `post_scan_object()` in the policy will be called after
 ///   `VM::VMScanning::scan_object()`.
+/// * Add `#[dominant]` to the space field where most objects are traced, e.g. the Immix space of
+///   the Immix plan.  The generated `trace_object` checks the address range of that space first,
+///   if the space is contiguous, before it looks up the space of the object.  At most one field
+///   can be dominant, and it must also be a `#[space]` field.
 #[proc_macro_error]
-#[proc_macro_derive(PlanTraceObject, attributes(space, parent, copy_semantics, post_scan))]
+#[proc_macro_derive(
+    PlanTraceObject,
+    attributes(space, parent, copy_semantics, post_scan, dominant)
+)]
 pub fn derive_plan_trace_object(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     let output = plan_trace_object_impl::derive(input);
@@ -62,3 +78,27 @@ pub fn derive_plan_trace_object(input: TokenStream) -> TokenStream {
 
     output.into()
 }
+
+/// This macro will generate an implementation of `Region` for a type that refers to an aligned
+/// memory region, such as a block or a line.
+///
+/// The type must be a tuple struct whose only field holds the start address of the region, and
+/// the type of the field must implement `RegionAddress` (e.g. `Address` or `NonZeroUsize`).  The
+/// user specifies the region with the `#[region(...)]` attribute, which can be repeated.
+///
+/// * `log_bytes = expr` (required): log2 of the size of the region in bytes.  Use `cfg_attr` if
+///   the size depends on features.
+/// * `parent = Type`: the region is contained in the larger region `Type`, such as a block in a
+///   `Chunk`.  This generates an implementation of `SubRegion<Type>`.  It can be specified more
+///   than once.
+/// * `metadata = [spec, ...]`: the side metadata specs of the per-region state.  This generates
+///   an associated constant `METADATA_SPECS` that lists them, so the space can clear or
+///   register all the metadata of the region in one place.
+#[proc_macro_error]
+#[proc_macro_derive(Region, attributes(region))]
+pub fn derive_region(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let output = region_impl::derive(input);
+
+    output.into()
+}
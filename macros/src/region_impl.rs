@@ -0,0 +1,92 @@
+use proc_macro2::TokenStream as TokenStream2;
+use proc_macro_error::{abort, abort_call_site};
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{DeriveInput, Expr, Type};
+
+pub(crate) fn derive(input: DeriveInput) -> TokenStream2 {
+    let ident = input.ident;
+    let vis = input.vis;
+
+    let syn::Data::Struct(syn::DataStruct {
+        fields: syn::Fields::Unnamed(ref fields),
+        ..
+    }) = input.data
+    else {
+        abort_call_site!("`#[derive(Region)]` only supports tuple structs with one field.");
+    };
+    if fields.unnamed.len() != 1 {
+        abort_call_site!("`#[derive(Region)]` only supports tuple structs with one field.");
+    }
+    if !input.generics.params.is_empty() {
+        abort_call_site!("`#[derive(Region)]` does not support generic structs.");
+    }
+
+    let mut log_bytes: Option<Expr> = None;
+    let mut parents: Vec<Type> = vec![];
+    let mut metadata: Option<Expr> = None;
+
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("region")) {
+        let result = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("log_bytes") {
+                if log_bytes.is_some() {
+                    abort! { meta.path.span(), "Duplicated `log_bytes` in #[region]" }
+                }
+                log_bytes = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("parent") {
+                parents.push(meta.value()?.parse()?);
+            } else if meta.path.is_ident("metadata") {
+                if metadata.is_some() {
+                    abort! { meta.path.span(), "Duplicated `metadata` in #[region]" }
+                }
+                metadata = Some(meta.value()?.parse()?);
+            } else {
+                return Err(meta.error("Expected `log_bytes`, `parent` or `metadata`"));
+            }
+            Ok(())
+        });
+        if let Err(e) = result {
+            abort! { e.span(), "{}", e }
+        }
+    }
+
+    let Some(log_bytes) = log_bytes else {
+        abort_call_site!("`#[derive(Region)]` needs `#[region(log_bytes = expr)]`.");
+    };
+
+    let sub_region_impls = parents.iter().map(|parent| {
+        quote! {
+            impl crate::util::linear_scan::SubRegion<#parent> for #ident {}
+        }
+    });
+
+    let metadata_specs = metadata.map(|specs| {
+        quote! {
+            impl #ident {
+                /// The side metadata of the region.
+                #vis const METADATA_SPECS: &'static [crate::util::metadata::side_metadata::SideMetadataSpec] = &#specs;
+            }
+        }
+    });
+
+    quote! {
+        impl crate::util::linear_scan::Region for #ident {
+            const LOG_BYTES: usize = #log_bytes;
+
+            #[inline(always)]
+            fn from_aligned_address(address: crate::util::Address) -> Self {
+                debug_assert!(address.is_aligned_to(Self::BYTES));
+                Self(crate::util::linear_scan::RegionAddress::from_address(address))
+            }
+
+            #[inline(always)]
+            fn start(&self) -> crate::util::Address {
+                crate::util::linear_scan::RegionAddress::to_address(self.0)
+            }
+        }
+
+        #(#sub_region_impls)*
+
+        #metadata_specs
+    }
+}
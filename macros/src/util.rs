@@ -1,5 +1,7 @@
-use proc_macro_error::abort;
-use syn::{spanned::Spanned, Attribute, Field, FieldsNamed};
+use proc_macro2::TokenStream as TokenStream2;
+use proc_macro_error::{abort, abort_call_site};
+use quote::{format_ident, quote};
+use syn::{spanned::Spanned, Attribute, Data, Field, Fields};
 
 pub fn get_field_attribute<'f>(field: &'f Field, attr_name: &str) -> Option<&'f Attribute> {
     let attrs = field
@@ -15,31 +17,134 @@ pub fn get_field_attribute<'f>(field: &'f Field, attr_name: &str) -> Option<&'f
     attrs.first().cloned()
 }
 
-pub fn get_fields_with_attribute<'f>(fields: &'f FieldsNamed, attr_name: &str) -> Vec<&'f Field> {
-    fields
-        .named
-        .iter()
-        .filter(|f| get_field_attribute(f, attr_name).is_some())
-        .collect::<Vec<_>>()
+/// A field of a struct or an enum variant, and how the generated code accesses it.
+pub struct AccessedField<'f> {
+    pub field: &'f Field,
+    /// A place expression of the field: `self.field` for a struct, or `(*binding)` for a field
+    /// bound by the pattern of an enum variant.
+    pub access: TokenStream2,
+}
+
+/// The fields of a struct, or the fields of a variant of an enum.
+pub struct FieldGroup<'f> {
+    /// The pattern that matches `self` with the variant and binds its fields.  `None` for a
+    /// struct.
+    pub pattern: Option<TokenStream2>,
+    pub fields: Vec<AccessedField<'f>>,
 }
 
-pub fn get_unique_field_with_attribute<'f>(
-    fields: &'f FieldsNamed,
-    attr_name: &str,
-) -> Option<&'f Field> {
-    let mut result = None;
-
-    for field in fields.named.iter() {
-        if let Some(attr) = get_field_attribute(field, attr_name) {
-            if result.is_none() {
-                result = Some(field);
-                continue;
-            } else {
-                let span = attr.path().span();
-                abort! { span, "At most one field in a struct can have the #[{}] attribute.", attr_name };
+impl<'f> FieldGroup<'f> {
+    pub fn fields_with_attribute(&self, attr_name: &str) -> Vec<&AccessedField<'f>> {
+        self.fields
+            .iter()
+            .filter(|f| get_field_attribute(f.field, attr_name).is_some())
+            .collect::<Vec<_>>()
+    }
+
+    pub fn unique_field_with_attribute(&self, attr_name: &str) -> Option<&AccessedField<'f>> {
+        let mut result = None;
+
+        for field in self.fields.iter() {
+            if let Some(attr) = get_field_attribute(field.field, attr_name) {
+                if result.is_none() {
+                    result = Some(field);
+                    continue;
+                } else {
+                    let span = attr.path().span();
+                    abort! { span, "At most one field in a struct or an enum variant can have the #[{}] attribute.", attr_name };
+                }
             }
         }
+
+        result
+    }
+}
+
+/// Get the field groups of the type a derive macro is applied to.  A struct with named fields has
+/// one group.  An enum has one group for each variant.  Other types are not supported.
+pub fn get_field_groups<'f>(data: &'f Data, derive_name: &str) -> Vec<FieldGroup<'f>> {
+    match data {
+        Data::Struct(syn::DataStruct {
+            fields: Fields::Named(fields),
+            ..
+        }) => {
+            let fields = fields
+                .named
+                .iter()
+                .map(|field| {
+                    let ident = field.ident.as_ref().unwrap();
+                    AccessedField {
+                        field,
+                        access: quote! { self.#ident },
+                    }
+                })
+                .collect();
+            vec![FieldGroup {
+                pattern: None,
+                fields,
+            }]
+        }
+        Data::Enum(data) if !data.variants.is_empty() => data
+            .variants
+            .iter()
+            .map(|variant| {
+                let v_ident = &variant.ident;
+                let bindings = (0..variant.fields.len())
+                    .map(|i| format_ident!("__mmtk_field_{}", i))
+                    .collect::<Vec<_>>();
+                let pattern = match &variant.fields {
+                    Fields::Named(fields) => {
+                        let names = fields.named.iter().map(|f| f.ident.as_ref().unwrap());
+                        quote! { Self::#v_ident { #(#names: #bindings),* } }
+                    }
+                    Fields::Unnamed(_) => quote! { Self::#v_ident(#(#bindings),*) },
+                    Fields::Unit => quote! { Self::#v_ident },
+                };
+                let fields = variant
+                    .fields
+                    .iter()
+                    .zip(bindings.iter())
+                    .map(|(field, binding)| AccessedField {
+                        field,
+                        access: quote! { (*#binding) },
+                    })
+                    .collect();
+                FieldGroup {
+                    pattern: Some(pattern),
+                    fields,
+                }
+            })
+            .collect(),
+        _ => abort_call_site!(
+            "`#[derive({})]` only supports structs with named fields and enums with variants.",
+            derive_name
+        ),
+    }
+}
+
+/// Generate the body of a method that takes `self` by reference.  `body` generates the code for
+/// a field group.  For a struct, this is the code for its only group.  For an enum, this matches
+/// `self` with each variant, and runs the code for the group of the variant.
+pub fn generate_match_self(
+    groups: &[FieldGroup],
+    mut body: impl FnMut(&FieldGroup) -> TokenStream2,
+) -> TokenStream2 {
+    if groups.len() == 1 && groups[0].pattern.is_none() {
+        return body(&groups[0]);
     }
 
-    result
+    let arms = groups.iter().map(|group| {
+        let pattern = group.pattern.as_ref().unwrap();
+        let code = body(group);
+        quote! {
+            #pattern => {
+                #code
+            }
+        }
+    });
+    quote! {
+        match self {
+            #(#arms)*
+        }
+    }
 }
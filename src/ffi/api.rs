@@ -0,0 +1,358 @@
+// All functions here are extern function. There is no point for marking them as unsafe.
+#![allow(clippy::not_unsafe_ptr_arg_deref)]
+
+use super::upcalls::{set_upcalls, MMTkUpcalls, RootsFactory};
+use super::FfiVM;
+use crate::memory_manager;
+use crate::scheduler::GCWorker;
+use crate::util::opaque_pointer::*;
+use crate::util::options::GCTriggerSelector;
+use crate::util::{Address, ObjectReference};
+use crate::vm::slot::SimpleSlot;
+use crate::AllocationSemantics;
+use crate::MMTKBuilder;
+use crate::Mutator;
+use crate::MMTK;
+use std::ffi::{c_char, CStr};
+use std::sync::OnceLock;
+
+static SINGLETON: OnceLock<Box<MMTK<FfiVM>>> = OnceLock::new();
+
+fn mmtk() -> &'static MMTK<FfiVM> {
+    SINGLETON.get().expect("mmtk_init has not been called")
+}
+
+/// Create an MMTk builder with the default options.
+#[no_mangle]
+pub extern "C" fn mmtk_create_builder() -> *mut MMTKBuilder {
+    Box::into_raw(Box::new(MMTKBuilder::new()))
+}
+
+/// Set an option by its name and value, both as null-terminated strings. Return true if the option
+/// is set.
+#[no_mangle]
+pub extern "C" fn mmtk_set_option_from_string(
+    builder: *mut MMTKBuilder,
+    name: *const c_char,
+    value: *const c_char,
+) -> bool {
+    let builder = unsafe { &mut *builder };
+    let name = unsafe { CStr::from_ptr(name) };
+    let value = unsafe { CStr::from_ptr(value) };
+    match (name.to_str(), value.to_str()) {
+        (Ok(name), Ok(value)) => builder.set_option(name, value),
+        _ => false,
+    }
+}
+
+/// Use a fixed heap size of `heap_size` bytes. Return true if the option is set.
+#[no_mangle]
+pub extern "C" fn mmtk_set_fixed_heap_size(builder: *mut MMTKBuilder, heap_size: usize) -> bool {
+    let builder = unsafe { &mut *builder };
+    builder
+        .options
+        .gc_trigger
+        .set(GCTriggerSelector::FixedHeapSize(heap_size))
+}
+
+/// Create the MMTk instance with the options in `builder`, and the upcalls into the runtime. This
+/// consumes the builder. The upcalls must live as long as the program.
+#[no_mangle]
+pub extern "C" fn mmtk_init(builder: *mut MMTKBuilder, upcalls: *const MMTkUpcalls) {
+    let builder = unsafe { Box::from_raw(builder) };
+    set_upcalls(unsafe { &*upcalls });
+    let mmtk = memory_manager::mmtk_init::<FfiVM>(&builder);
+    SINGLETON
+        .set(mmtk)
+        .unwrap_or_else(|_| panic!("mmtk_init is called more than once"));
+}
+
+/// See [`memory_manager::initialize_collection`].
+#[no_mangle]
+pub extern "C" fn mmtk_initialize_collection(tls: VMThread) {
+    memory_manager::initialize_collection(mmtk(), tls)
+}
+
+/// The entry point of a GC worker thread spawned by the `spawn_gc_thread` upcall. See
+/// [`memory_manager::start_worker`].
+#[no_mangle]
+pub extern "C" fn mmtk_start_worker(tls: VMWorkerThread, worker: *mut GCWorker<FfiVM>) {
+    let worker = unsafe { Box::from_raw(worker) };
+    memory_manager::start_worker::<FfiVM>(mmtk(), tls, worker)
+}
+
+/// See [`memory_manager::bind_mutator`]. The mutator must be destroyed with `mmtk_destroy_mutator`.
+#[no_mangle]
+pub extern "C" fn mmtk_bind_mutator(tls: VMMutatorThread) -> *mut Mutator<FfiVM> {
+    Box::into_raw(memory_manager::bind_mutator(mmtk(), tls))
+}
+
+/// See [`memory_manager::destroy_mutator`].
+#[no_mangle]
+pub extern "C" fn mmtk_destroy_mutator(mutator: *mut Mutator<FfiVM>) {
+    memory_manager::destroy_mutator(unsafe { &mut *mutator });
+    let _ = unsafe { Box::from_raw(mutator) };
+}
+
+/// See [`memory_manager::flush_mutator`].
+#[no_mangle]
+pub extern "C" fn mmtk_flush_mutator(mutator: *mut Mutator<FfiVM>) {
+    memory_manager::flush_mutator(unsafe { &mut *mutator })
+}
+
+/// Allocate `size` bytes. Objects that are too large for the default allocator are allocated in the
+/// large object space. See [`memory_manager::alloc`].
+#[no_mangle]
+pub extern "C" fn mmtk_alloc(
+    mutator: *mut Mutator<FfiVM>,
+    size: usize,
+    align: usize,
+    offset: usize,
+    semantics: AllocationSemantics,
+) -> Address {
+    let semantics = large_object_semantics(size, semantics);
+    memory_manager::alloc::<FfiVM>(unsafe { &mut *mutator }, size, align, offset, semantics)
+}
+
+/// See [`memory_manager::post_alloc`]. `bytes` and `semantics` must be the same as the ones passed
+/// to `mmtk_alloc`.
+#[no_mangle]
+pub extern "C" fn mmtk_post_alloc(
+    mutator: *mut Mutator<FfiVM>,
+    object: ObjectReference,
+    bytes: usize,
+    semantics: AllocationSemantics,
+) {
+    let semantics = large_object_semantics(bytes, semantics);
+    memory_manager::post_alloc::<FfiVM>(unsafe { &mut *mutator }, object, bytes, semantics)
+}
+
+fn large_object_semantics(bytes: usize, semantics: AllocationSemantics) -> AllocationSemantics {
+    if bytes
+        >= mmtk()
+            .get_plan()
+            .constraints()
+            .max_non_los_default_alloc_bytes
+    {
+        AllocationSemantics::Los
+    } else {
+        semantics
+    }
+}
+
+/// The pre write barrier. `slot` is the address of the field in `src`, and `target` is the old
+/// value of the field, or null. See [`memory_manager::object_reference_write_pre`].
+#[no_mangle]
+pub extern "C" fn mmtk_object_reference_write_pre(
+    mutator: *mut Mutator<FfiVM>,
+    src: ObjectReference,
+    slot: Address,
+    target: Option<ObjectReference>,
+) {
+    memory_manager::object_reference_write_pre(
+        unsafe { &mut *mutator },
+        src,
+        SimpleSlot::from_address(slot),
+        target,
+    )
+}
+
+/// The post write barrier. `slot` is the address of the field in `src`, and `target` is the new
+/// value of the field, or null. See [`memory_manager::object_reference_write_post`].
+#[no_mangle]
+pub extern "C" fn mmtk_object_reference_write_post(
+    mutator: *mut Mutator<FfiVM>,
+    src: ObjectReference,
+    slot: Address,
+    target: Option<ObjectReference>,
+) {
+    memory_manager::object_reference_write_post(
+        unsafe { &mut *mutator },
+        src,
+        SimpleSlot::from_address(slot),
+        target,
+    )
+}
+
+/// See [`memory_manager::pin_object`].
+#[cfg(feature = "object_pinning")]
+#[no_mangle]
+pub extern "C" fn mmtk_pin_object(object: ObjectReference) -> bool {
+    memory_manager::pin_object(object)
+}
+
+/// See [`memory_manager::unpin_object`].
+#[cfg(feature = "object_pinning")]
+#[no_mangle]
+pub extern "C" fn mmtk_unpin_object(object: ObjectReference) -> bool {
+    memory_manager::unpin_object(object)
+}
+
+/// See [`memory_manager::is_pinned`].
+#[cfg(feature = "object_pinning")]
+#[no_mangle]
+pub extern "C" fn mmtk_is_pinned(object: ObjectReference) -> bool {
+    memory_manager::is_pinned(object)
+}
+
+/// See [`memory_manager::is_mmtk_object`].
+#[cfg(feature = "is_mmtk_object")]
+#[no_mangle]
+pub extern "C" fn mmtk_is_mmtk_object(addr: Address) -> bool {
+    memory_manager::is_mmtk_object(addr).is_some()
+}
+
+/// See [`memory_manager::is_in_mmtk_spaces`].
+#[no_mangle]
+pub extern "C" fn mmtk_is_in_mmtk_spaces(object: ObjectReference) -> bool {
+    memory_manager::is_in_mmtk_spaces(object)
+}
+
+/// See [`memory_manager::object_hash`].
+#[cfg(feature = "address_based_hashing")]
+#[no_mangle]
+pub extern "C" fn mmtk_object_hash(object: ObjectReference) -> usize {
+    memory_manager::object_hash::<FfiVM>(object)
+}
+
+/// See [`memory_manager::is_live_object`].
+#[no_mangle]
+pub extern "C" fn mmtk_is_live_object(object: ObjectReference) -> bool {
+    memory_manager::is_live_object(object)
+}
+
+/// Return true if the object will never be moved by the GC.
+#[no_mangle]
+pub extern "C" fn mmtk_will_never_move(object: ObjectReference) -> bool {
+    !object.is_movable()
+}
+
+/// See [`memory_manager::is_mapped_address`].
+#[no_mangle]
+pub extern "C" fn mmtk_is_mapped_address(address: Address) -> bool {
+    memory_manager::is_mapped_address(address)
+}
+
+/// See [`memory_manager::handle_user_collection_request`].
+#[no_mangle]
+pub extern "C" fn mmtk_handle_user_collection_request(tls: VMMutatorThread) {
+    memory_manager::handle_user_collection_request::<FfiVM>(mmtk(), tls);
+}
+
+/// See [`memory_manager::add_finalizer`].
+#[no_mangle]
+pub extern "C" fn mmtk_add_finalizer(object: ObjectReference) {
+    memory_manager::add_finalizer(mmtk(), object)
+}
+
+/// Return an object that is ready for finalization, or null if there is none. See
+/// [`memory_manager::get_finalized_object`].
+#[no_mangle]
+pub extern "C" fn mmtk_get_finalized_object() -> Option<ObjectReference> {
+    memory_manager::get_finalized_object(mmtk())
+}
+
+/// See [`memory_manager::add_weak_candidate`].
+#[no_mangle]
+pub extern "C" fn mmtk_add_weak_candidate(reff: ObjectReference) {
+    memory_manager::add_weak_candidate(mmtk(), reff)
+}
+
+/// See [`memory_manager::add_soft_candidate`].
+#[no_mangle]
+pub extern "C" fn mmtk_add_soft_candidate(reff: ObjectReference) {
+    memory_manager::add_soft_candidate(mmtk(), reff)
+}
+
+/// See [`memory_manager::add_phantom_candidate`].
+#[no_mangle]
+pub extern "C" fn mmtk_add_phantom_candidate(reff: ObjectReference) {
+    memory_manager::add_phantom_candidate(mmtk(), reff)
+}
+
+/// See [`memory_manager::harness_begin`].
+#[no_mangle]
+pub extern "C" fn mmtk_harness_begin(tls: VMMutatorThread) {
+    memory_manager::harness_begin(mmtk(), tls)
+}
+
+/// See [`memory_manager::harness_end`].
+#[no_mangle]
+pub extern "C" fn mmtk_harness_end() {
+    memory_manager::harness_end(mmtk())
+}
+
+/// See [`memory_manager::used_bytes`].
+#[no_mangle]
+pub extern "C" fn mmtk_used_bytes() -> usize {
+    memory_manager::used_bytes(mmtk())
+}
+
+/// See [`memory_manager::free_bytes`].
+#[no_mangle]
+pub extern "C" fn mmtk_free_bytes() -> usize {
+    memory_manager::free_bytes(mmtk())
+}
+
+/// See [`memory_manager::total_bytes`].
+#[no_mangle]
+pub extern "C" fn mmtk_total_bytes() -> usize {
+    memory_manager::total_bytes(mmtk())
+}
+
+/// See [`memory_manager::starting_heap_address`].
+#[no_mangle]
+pub extern "C" fn mmtk_starting_heap_address() -> Address {
+    memory_manager::starting_heap_address()
+}
+
+/// See [`memory_manager::last_heap_address`].
+#[no_mangle]
+pub extern "C" fn mmtk_last_heap_address() -> Address {
+    memory_manager::last_heap_address()
+}
+
+/// Report `len` root slots at `slots` from the `scan_*_roots` upcalls. Each slot is the address of
+/// a word that holds an object reference or null. The slots may be updated if the objects move.
+#[no_mangle]
+pub extern "C" fn mmtk_roots_factory_report_slots(
+    factory: *mut RootsFactory,
+    slots: *const Address,
+    len: usize,
+) {
+    let factory = unsafe { &mut *factory };
+    let slots = unsafe { std::slice::from_raw_parts(slots, len) };
+    factory.report_slots(
+        slots
+            .iter()
+            .copied()
+            .map(SimpleSlot::from_address)
+            .collect(),
+    )
+}
+
+/// Report `len` objects at `nodes` as roots from the `scan_*_roots` upcalls. Those objects are
+/// pinned in this GC, so the runtime does not need to update references to them.
+#[no_mangle]
+pub extern "C" fn mmtk_roots_factory_report_pinning_roots(
+    factory: *mut RootsFactory,
+    nodes: *const ObjectReference,
+    len: usize,
+) {
+    let factory = unsafe { &mut *factory };
+    let nodes = unsafe { std::slice::from_raw_parts(nodes, len) };
+    factory.report_pinning_roots(nodes.to_vec())
+}
+
+/// Report `len` objects at `nodes` as roots from the `scan_*_roots` upcalls. Those objects are
+/// pinned in this GC, and the objects they point to are also kept in place.
+#[no_mangle]
+pub extern "C" fn mmtk_roots_factory_report_tpinning_roots(
+    factory: *mut RootsFactory,
+    nodes: *const ObjectReference,
+    len: usize,
+) {
+    let factory = unsafe { &mut *factory };
+    let nodes = unsafe { std::slice::from_raw_parts(nodes, len) };
+    factory.report_tpinning_roots(nodes.to_vec())
+}
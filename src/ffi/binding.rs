@@ -0,0 +1,244 @@
+use super::upcalls::{upcalls, MutatorClosure, RootsFactory, SlotClosure};
+use crate::util::alloc::{AllocationError, AllocationErrorContext};
+use crate::util::constants::BYTES_IN_WORD;
+use crate::util::copy::{CopySemantics, GCWorkerCopyContext};
+use crate::util::opaque_pointer::*;
+use crate::util::{Address, ObjectReference};
+use crate::vm::slot::{SimpleSlot, UnimplementedMemorySlice};
+use crate::vm::*;
+use crate::Mutator;
+
+/// The [`VMBinding`] implementation for runtimes that use MMTk through the C API. Every call from
+/// MMTk into the runtime goes through the [`super::MMTkUpcalls`] passed to `mmtk_init`.
+#[derive(Default)]
+pub struct FfiVM;
+
+impl VMBinding for FfiVM {
+    type VMObjectModel = FfiObjectModel;
+    type VMScanning = FfiScanning;
+    type VMCollection = FfiCollection;
+    type VMActivePlan = FfiActivePlan;
+    type VMReferenceGlue = FfiReferenceGlue;
+    type VMSlot = SimpleSlot;
+    type VMMemorySlice = UnimplementedMemorySlice;
+
+    const MAX_ALIGNMENT: usize = 1 << 6;
+}
+
+pub struct FfiObjectModel;
+
+impl ObjectModel<FfiVM> for FfiObjectModel {
+    const GLOBAL_LOG_BIT_SPEC: VMGlobalLogBitSpec = VMGlobalLogBitSpec::side_first();
+
+    // The forwarding pointer overwrites the first word of the object. Everything else is on the side.
+    const LOCAL_FORWARDING_POINTER_SPEC: VMLocalForwardingPointerSpec =
+        VMLocalForwardingPointerSpec::in_header(0);
+    const LOCAL_FORWARDING_BITS_SPEC: VMLocalForwardingBitsSpec =
+        VMLocalForwardingBitsSpec::side_first();
+    const LOCAL_MARK_BIT_SPEC: VMLocalMarkBitSpec =
+        VMLocalMarkBitSpec::side_after(Self::LOCAL_FORWARDING_BITS_SPEC.as_spec());
+    const LOCAL_LOS_MARK_NURSERY_SPEC: VMLocalLOSMarkNurserySpec =
+        VMLocalLOSMarkNurserySpec::side_after(Self::LOCAL_MARK_BIT_SPEC.as_spec());
+    #[cfg(feature = "object_pinning")]
+    const LOCAL_PINNING_BIT_SPEC: VMLocalPinningBitSpec =
+        VMLocalPinningBitSpec::side_after(Self::LOCAL_LOS_MARK_NURSERY_SPEC.as_spec());
+    #[cfg(feature = "address_based_hashing")]
+    const GLOBAL_HASH_STATE_SPEC: VMGlobalHashStateSpec =
+        VMGlobalHashStateSpec::side_after(Self::GLOBAL_LOG_BIT_SPEC.as_spec());
+
+    const UNIFIED_OBJECT_REFERENCE_ADDRESS: bool = true;
+    const OBJECT_REF_OFFSET_LOWER_BOUND: isize = 0;
+
+    fn copy(
+        from: ObjectReference,
+        semantics: CopySemantics,
+        copy_context: &mut GCWorkerCopyContext<FfiVM>,
+    ) -> ObjectReference {
+        let bytes = Self::get_current_size(from);
+        // The copy may be larger than the object if the copy needs a hash word.
+        let bytes_when_copied = Self::get_size_when_copied(from);
+        let dst = copy_context.alloc_copy(from, bytes_when_copied, BYTES_IN_WORD, 0, semantics);
+        unsafe {
+            std::ptr::copy_nonoverlapping::<u8>(
+                from.to_raw_address().to_ptr(),
+                dst.to_mut_ptr(),
+                bytes,
+            );
+        }
+        let to = unsafe { ObjectReference::from_raw_address_unchecked(dst) };
+        copy_context.post_copy(to, bytes_when_copied, semantics);
+        to
+    }
+
+    fn copy_to(from: ObjectReference, to: ObjectReference, region: Address) -> Address {
+        let bytes = Self::get_current_size(from);
+        let bytes_when_copied = Self::get_size_when_copied(from);
+        if from != to {
+            unsafe {
+                std::ptr::copy::<u8>(
+                    from.to_raw_address().to_ptr(),
+                    to.to_raw_address().to_mut_ptr(),
+                    bytes,
+                );
+            }
+        }
+        debug_assert!(region <= to.to_raw_address());
+        to.to_raw_address() + bytes_when_copied
+    }
+
+    fn get_current_size(object: ObjectReference) -> usize {
+        let bytes = (upcalls().get_object_size)(object);
+        #[cfg(feature = "address_based_hashing")]
+        let bytes = bytes + Self::GLOBAL_HASH_STATE_SPEC.hash_bytes_in_object::<FfiVM>(object);
+        bytes
+    }
+
+    fn get_size_when_copied(object: ObjectReference) -> usize {
+        let bytes = (upcalls().get_object_size)(object);
+        #[cfg(feature = "address_based_hashing")]
+        let bytes = bytes + Self::GLOBAL_HASH_STATE_SPEC.hash_bytes_when_copied::<FfiVM>(object);
+        bytes
+    }
+
+    fn get_align_when_copied(_object: ObjectReference) -> usize {
+        BYTES_IN_WORD
+    }
+
+    fn get_align_offset_when_copied(_object: ObjectReference) -> usize {
+        0
+    }
+
+    fn get_reference_when_copied_to(_from: ObjectReference, to: Address) -> ObjectReference {
+        unsafe { ObjectReference::from_raw_address_unchecked(to) }
+    }
+
+    // MMTk does not use type descriptors.
+    fn get_type_descriptor(_reference: ObjectReference) -> &'static [i8] {
+        &[]
+    }
+
+    fn ref_to_object_start(object: ObjectReference) -> Address {
+        object.to_raw_address()
+    }
+
+    fn ref_to_header(object: ObjectReference) -> Address {
+        object.to_raw_address()
+    }
+
+    fn dump_object(object: ObjectReference) {
+        (upcalls().dump_object)(object)
+    }
+}
+
+pub struct FfiScanning;
+
+impl Scanning<FfiVM> for FfiScanning {
+    fn scan_object<SV: SlotVisitor<SimpleSlot>>(
+        tls: VMWorkerThread,
+        object: ObjectReference,
+        slot_visitor: &mut SV,
+    ) {
+        (upcalls().scan_object)(tls, object, SlotClosure::from_slot_visitor(slot_visitor))
+    }
+
+    fn notify_initial_thread_scan_complete(_partial_scan: bool, _tls: VMWorkerThread) {}
+
+    fn scan_roots_in_mutator_thread(
+        tls: VMWorkerThread,
+        mutator: &'static mut Mutator<FfiVM>,
+        factory: impl RootsWorkFactory<SimpleSlot>,
+    ) {
+        let mut factory = RootsFactory::new(factory);
+        (upcalls().scan_roots_in_mutator_thread)(tls, mutator, &mut factory)
+    }
+
+    fn scan_vm_specific_roots(tls: VMWorkerThread, factory: impl RootsWorkFactory<SimpleSlot>) {
+        let mut factory = RootsFactory::new(factory);
+        (upcalls().scan_vm_specific_roots)(tls, &mut factory)
+    }
+
+    fn supports_return_barrier() -> bool {
+        false
+    }
+
+    // The runtime reports the roots from scratch every time they are scanned.
+    fn prepare_for_roots_re_scanning() {}
+}
+
+pub struct FfiCollection;
+
+impl Collection<FfiVM> for FfiCollection {
+    fn stop_all_mutators<F>(tls: VMWorkerThread, mut mutator_visitor: F)
+    where
+        F: FnMut(&'static mut Mutator<FfiVM>),
+    {
+        (upcalls().stop_all_mutators)(tls, MutatorClosure::from_rust_closure(&mut mutator_visitor))
+    }
+
+    fn resume_mutators(tls: VMWorkerThread) {
+        (upcalls().resume_mutators)(tls)
+    }
+
+    fn block_for_gc(tls: VMMutatorThread) {
+        (upcalls().block_for_gc)(tls)
+    }
+
+    fn spawn_gc_thread(tls: VMThread, ctx: GCThreadContext<FfiVM>) {
+        let GCThreadContext::Worker(worker) = ctx;
+        (upcalls().spawn_gc_thread)(tls, Box::into_raw(worker))
+    }
+
+    // The C API only passes the kind of the error.
+    fn out_of_memory(tls: VMThread, err_kind: AllocationError, _context: &AllocationErrorContext) {
+        (upcalls().out_of_memory)(tls, err_kind)
+    }
+
+    fn schedule_finalization(tls: VMWorkerThread) {
+        (upcalls().schedule_finalization)(tls)
+    }
+}
+
+pub struct FfiActivePlan;
+
+impl ActivePlan<FfiVM> for FfiActivePlan {
+    fn is_mutator(tls: VMThread) -> bool {
+        (upcalls().is_mutator)(tls)
+    }
+
+    fn mutator(tls: VMMutatorThread) -> &'static mut Mutator<FfiVM> {
+        unsafe { &mut *(upcalls().get_mutator)(tls) }
+    }
+
+    fn mutators<'a>() -> Box<dyn Iterator<Item = &'a mut Mutator<FfiVM>> + 'a> {
+        let mut mutators = vec![];
+        let mut visitor = |mutator: &'static mut Mutator<FfiVM>| mutators.push(mutator);
+        (upcalls().get_mutators)(MutatorClosure::from_rust_closure(&mut visitor));
+        Box::new(mutators.into_iter())
+    }
+
+    fn number_of_mutators() -> usize {
+        (upcalls().number_of_mutators)()
+    }
+}
+
+pub struct FfiReferenceGlue;
+
+impl ReferenceGlue<FfiVM> for FfiReferenceGlue {
+    type FinalizableType = ObjectReference;
+
+    fn set_referent(reference: ObjectReference, referent: ObjectReference) {
+        (upcalls().set_referent)(reference, Some(referent))
+    }
+
+    fn get_referent(object: ObjectReference) -> Option<ObjectReference> {
+        (upcalls().get_referent)(object)
+    }
+
+    fn clear_referent(object: ObjectReference) {
+        (upcalls().set_referent)(object, None)
+    }
+
+    fn enqueue_references(references: &[ObjectReference], tls: VMWorkerThread) {
+        (upcalls().enqueue_references)(tls, references.as_ptr(), references.len())
+    }
+}
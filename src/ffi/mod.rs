@@ -0,0 +1,34 @@
+//! A C API for MMTk (enabled by the feature "ffi").
+//!
+//! Most bindings expose the same set of functions from [`crate::memory_manager`] to their runtime
+//! through a hand-written `extern "C"` layer, and implement [`crate::vm::VMBinding`] by calling
+//! back into the runtime. This module provides both parts for runtimes that are written in C or
+//! C++ and do not need anything beyond the common functionality:
+//! *   [`FfiVM`] is a [`crate::vm::VMBinding`] implementation that forwards each call from MMTk to
+//!     the runtime through a table of function pointers, [`MMTkUpcalls`], supplied by the runtime.
+//! *   The `mmtk_*` functions in this module are exported with C linkage. They are declared in the
+//!     C header `include/mmtk_ffi.h`, which is generated with `cbindgen` (see `cbindgen.toml`).
+//!
+//! A runtime should depend on `mmtk` with the feature "ffi" from a small Rust crate that is
+//! built as a `staticlib` or a `cdylib`, call `mmtk_init` with its upcalls, and use the rest of
+//! the functions in the same way as the functions in [`crate::memory_manager`].
+//!
+//! [`FfiVM`] makes a few assumptions about the runtime, and bindings that cannot satisfy them
+//! should implement [`crate::vm::VMBinding`] themselves:
+//! *   An object reference points to the start of the object, and the object has no header
+//!     metadata that MMTk needs to know about. All the MMTk metadata is in side metadata, except
+//!     that the first word of an object is overwritten with a forwarding pointer when it is copied.
+//! *   Objects are copied with word alignment, and their size does not change when copied.
+//! *   Reference fields are word-sized slots holding object references (or 0 for null).
+//! *   Reference objects (for Java-style weak reference processing) hold their referents in a
+//!     field that the runtime reads and writes with the `get_referent` and `set_referent` upcalls.
+//!
+//! Only one MMTk instance can be created with this API.
+
+mod api;
+mod binding;
+mod upcalls;
+
+pub use self::api::*;
+pub use self::binding::FfiVM;
+pub use self::upcalls::{MMTkUpcalls, MutatorClosure, RootsFactory, SlotClosure};
@@ -0,0 +1,162 @@
+use super::FfiVM;
+use crate::scheduler::GCWorker;
+use crate::util::alloc::AllocationError;
+use crate::util::opaque_pointer::*;
+use crate::util::{Address, ObjectReference};
+use crate::vm::slot::SimpleSlot;
+use crate::vm::{RootsWorkFactory, SlotVisitor};
+use crate::Mutator;
+use std::ffi::c_void;
+use std::sync::OnceLock;
+
+/// The functions that MMTk calls into the runtime. The runtime passes a table of these functions to
+/// `mmtk_init`, and the table must live as long as the program. See the methods of the traits in
+/// [`crate::vm`] for what each function needs to do.
+#[repr(C)]
+pub struct MMTkUpcalls {
+    /// Stop all the mutators, and call `visitor` for each mutator. See [`crate::vm::Collection::stop_all_mutators`].
+    pub stop_all_mutators: extern "C" fn(tls: VMWorkerThread, visitor: MutatorClosure),
+    /// Resume all the mutators. See [`crate::vm::Collection::resume_mutators`].
+    pub resume_mutators: extern "C" fn(tls: VMWorkerThread),
+    /// Block the current mutator until GC is finished. See [`crate::vm::Collection::block_for_gc`].
+    pub block_for_gc: extern "C" fn(tls: VMMutatorThread),
+    /// Spawn a GC worker thread. The new thread should call `mmtk_start_worker` with `worker`.
+    /// See [`crate::vm::Collection::spawn_gc_thread`].
+    pub spawn_gc_thread: extern "C" fn(tls: VMThread, worker: *mut GCWorker<FfiVM>),
+    /// Handle an out-of-memory error. See [`crate::vm::Collection::out_of_memory`].
+    pub out_of_memory: extern "C" fn(tls: VMThread, err_kind: AllocationError),
+    /// Notify the runtime that there are finalizable objects ready to be finalized. See
+    /// [`crate::vm::Collection::schedule_finalization`].
+    pub schedule_finalization: extern "C" fn(tls: VMWorkerThread),
+    /// Return whether the thread is a mutator. See [`crate::vm::ActivePlan::is_mutator`].
+    pub is_mutator: extern "C" fn(tls: VMThread) -> bool,
+    /// Return the mutator (the pointer returned by `mmtk_bind_mutator`) of a mutator thread. See [`crate::vm::ActivePlan::mutator`].
+    pub get_mutator: extern "C" fn(tls: VMMutatorThread) -> *mut Mutator<FfiVM>,
+    /// Call `visitor` for each mutator. See [`crate::vm::ActivePlan::mutators`].
+    pub get_mutators: extern "C" fn(visitor: MutatorClosure),
+    /// Return the number of mutators. See [`crate::vm::ActivePlan::number_of_mutators`].
+    pub number_of_mutators: extern "C" fn() -> usize,
+    /// Return the size of an object in bytes. See [`crate::vm::ObjectModel::get_current_size`].
+    pub get_object_size: extern "C" fn(object: ObjectReference) -> usize,
+    /// Print debugging information for an object. See [`crate::vm::ObjectModel::dump_object`].
+    pub dump_object: extern "C" fn(object: ObjectReference),
+    /// Call `visitor` with the address of each reference field of an object. See [`crate::vm::Scanning::scan_object`].
+    pub scan_object:
+        extern "C" fn(tls: VMWorkerThread, object: ObjectReference, visitor: SlotClosure),
+    /// Report the roots in a mutator thread with `mmtk_roots_factory_*`. See [`crate::vm::Scanning::scan_roots_in_mutator_thread`].
+    pub scan_roots_in_mutator_thread: extern "C" fn(
+        tls: VMWorkerThread,
+        mutator: *mut Mutator<FfiVM>,
+        factory: *mut RootsFactory,
+    ),
+    /// Report the roots that are not in mutator threads with `mmtk_roots_factory_*`. See [`crate::vm::Scanning::scan_vm_specific_roots`].
+    pub scan_vm_specific_roots: extern "C" fn(tls: VMWorkerThread, factory: *mut RootsFactory),
+    /// Return the referent of a reference object, or null if it has been cleared. Only called for the objects added with `mmtk_add_*_candidate`. See [`crate::vm::ReferenceGlue::get_referent`].
+    pub get_referent: extern "C" fn(reference: ObjectReference) -> Option<ObjectReference>,
+    /// Set the referent of a reference object, or clear it if `referent` is null. See [`crate::vm::ReferenceGlue::set_referent`] and [`crate::vm::ReferenceGlue::clear_referent`].
+    pub set_referent: extern "C" fn(reference: ObjectReference, referent: Option<ObjectReference>),
+    /// Enqueue the `len` reference objects in `references`, whose referents have been cleared. See [`crate::vm::ReferenceGlue::enqueue_references`].
+    pub enqueue_references:
+        extern "C" fn(tls: VMWorkerThread, references: *const ObjectReference, len: usize),
+}
+
+static UPCALLS: OnceLock<&'static MMTkUpcalls> = OnceLock::new();
+
+pub(super) fn set_upcalls(upcalls: &'static MMTkUpcalls) {
+    UPCALLS
+        .set(upcalls)
+        .unwrap_or_else(|_| panic!("The upcalls are already set"));
+}
+
+pub(super) fn upcalls() -> &'static MMTkUpcalls {
+    UPCALLS.get().expect("mmtk_init has not been called")
+}
+
+/// A Rust closure that takes a mutator, passed to the runtime. The runtime calls it with
+/// `closure.func(mutator, closure.data)`.
+#[repr(C)]
+pub struct MutatorClosure {
+    pub func: extern "C" fn(mutator: *mut Mutator<FfiVM>, data: *mut c_void),
+    pub data: *mut c_void,
+}
+
+impl MutatorClosure {
+    pub(super) fn from_rust_closure<F>(f: &mut F) -> Self
+    where
+        F: FnMut(&'static mut Mutator<FfiVM>),
+    {
+        extern "C" fn call<F: FnMut(&'static mut Mutator<FfiVM>)>(
+            mutator: *mut Mutator<FfiVM>,
+            data: *mut c_void,
+        ) {
+            let f = unsafe { &mut *(data as *mut F) };
+            f(unsafe { &mut *mutator });
+        }
+        Self {
+            func: call::<F>,
+            data: f as *mut F as *mut c_void,
+        }
+    }
+}
+
+/// A Rust closure that takes the address of a slot, passed to the runtime. The runtime calls it
+/// with `closure.func(slot, closure.data)`.
+#[repr(C)]
+pub struct SlotClosure {
+    pub func: extern "C" fn(slot: Address, data: *mut c_void),
+    pub data: *mut c_void,
+}
+
+impl SlotClosure {
+    pub(super) fn from_slot_visitor<SV: SlotVisitor<SimpleSlot>>(visitor: &mut SV) -> Self {
+        extern "C" fn call<SV: SlotVisitor<SimpleSlot>>(slot: Address, data: *mut c_void) {
+            let visitor = unsafe { &mut *(data as *mut SV) };
+            visitor.visit_slot(SimpleSlot::from_address(slot));
+        }
+        Self {
+            func: call::<SV>,
+            data: visitor as *mut SV as *mut c_void,
+        }
+    }
+}
+
+/// An object-safe version of [`RootsWorkFactory`].
+trait DynRootsWorkFactory {
+    fn create_process_roots_work(&mut self, slots: Vec<SimpleSlot>);
+    fn create_process_pinning_roots_work(&mut self, nodes: Vec<ObjectReference>);
+    fn create_process_tpinning_roots_work(&mut self, nodes: Vec<ObjectReference>);
+}
+
+impl<F: RootsWorkFactory<SimpleSlot>> DynRootsWorkFactory for F {
+    fn create_process_roots_work(&mut self, slots: Vec<SimpleSlot>) {
+        RootsWorkFactory::create_process_roots_work(self, slots)
+    }
+    fn create_process_pinning_roots_work(&mut self, nodes: Vec<ObjectReference>) {
+        RootsWorkFactory::create_process_pinning_roots_work(self, nodes)
+    }
+    fn create_process_tpinning_roots_work(&mut self, nodes: Vec<ObjectReference>) {
+        RootsWorkFactory::create_process_tpinning_roots_work(self, nodes)
+    }
+}
+
+/// The factory for root scanning work, passed to the runtime as an opaque pointer. The runtime
+/// reports roots with the `mmtk_roots_factory_*` functions.
+pub struct RootsFactory(Box<dyn DynRootsWorkFactory>);
+
+impl RootsFactory {
+    pub(super) fn new(factory: impl RootsWorkFactory<SimpleSlot>) -> Self {
+        Self(Box::new(factory))
+    }
+
+    pub(super) fn report_slots(&mut self, slots: Vec<SimpleSlot>) {
+        self.0.create_process_roots_work(slots)
+    }
+
+    pub(super) fn report_pinning_roots(&mut self, nodes: Vec<ObjectReference>) {
+        self.0.create_process_pinning_roots_work(nodes)
+    }
+
+    pub(super) fn report_tpinning_roots(&mut self, nodes: Vec<ObjectReference>) {
+        self.0.create_process_tpinning_roots_work(nodes)
+    }
+}
@@ -1,8 +1,13 @@
+use crate::plan::space_targeted::SpaceSet;
+#[cfg(feature = "analysis")]
+use crate::util::analysis::lifetime::ObjectLifetimes;
 use atomic_refcell::AtomicRefCell;
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+#[cfg(feature = "analysis")]
+use std::sync::Arc;
 use std::sync::Mutex;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 /// This stores some global states for an MMTK instance.
 /// Some MMTK components like plans and allocators may keep an reference to the struct, and can access it.
@@ -42,11 +47,35 @@ pub struct GlobalState {
     pub(crate) stacks_prepared: AtomicBool,
     /// A counter that keeps tracks of the number of bytes allocated since last stress test
     pub(crate) allocation_bytes: AtomicUsize,
+    /// The recent allocation rate of mutators.
+    pub(crate) allocation_rate: AllocationRate,
+    /// The lifetime data of objects, recorded by the lifetime analysis.
+    #[cfg(feature = "analysis")]
+    pub(crate) object_lifetimes: Arc<ObjectLifetimes>,
     /// A counteer that keeps tracks of the number of bytes allocated by malloc
     #[cfg(feature = "malloc_counted_size")]
     pub(crate) malloc_bytes: AtomicUsize,
     /// This stores the live bytes and the used bytes (by pages) for each space in last GC. This counter is only updated in the GC release phase.
     pub(crate) live_bytes_in_last_gc: AtomicRefCell<HashMap<&'static str, LiveBytesStats>>,
+    /// Has the binding requested to verify the heap at the end of the next GC?
+    #[cfg(feature = "heap_verifier")]
+    pub(crate) heap_verification_requested: AtomicBool,
+    /// The number of finished nursery GCs.
+    pub(crate) nursery_gcs: AtomicUsize,
+    /// The number of finished full-heap GCs.
+    pub(crate) full_gcs: AtomicUsize,
+    /// The spaces that the binding requested the next GC to collect, for a space-targeted GC.
+    pub(crate) requested_space_targets: Mutex<Option<SpaceSet>>,
+    /// The spaces collected by the current GC, if it is a space-targeted GC.
+    pub(crate) space_targets: Mutex<Option<SpaceSet>>,
+    /// The number of live [`GCDisabledScope`]s.  MMTk does not trigger GC while this is not zero.
+    /// This is only changed while holding the lock of `gc_disabled_time`.
+    pub(crate) gc_disabled_depth: AtomicUsize,
+    /// The time spent with GC disabled by [`GCDisabledScope`]s.
+    pub(crate) gc_disabled_time: Mutex<GCDisabledTime>,
+    /// The number of polls that would have triggered a GC if GC was not disabled by a
+    /// [`GCDisabledScope`].
+    pub(crate) gc_suppressed_polls: AtomicUsize,
 }
 
 impl GlobalState {
@@ -78,6 +107,11 @@ impl GlobalState {
         self.emergency_collection
             .store(emergency_collection, Ordering::Relaxed);
 
+        // An emergency collection collects all the spaces.
+        let requested_space_targets = self.requested_space_targets.lock().unwrap().take();
+        *self.space_targets.lock().unwrap() =
+            requested_space_targets.filter(|_| !emergency_collection);
+
         emergency_collection
     }
 
@@ -123,6 +157,13 @@ impl GlobalState {
             .store(false, Ordering::SeqCst);
         self.user_triggered_collection
             .store(false, Ordering::Relaxed);
+        *self.space_targets.lock().unwrap() = None;
+    }
+
+    /// The spaces collected by the current GC, or `None` if it is not a space-targeted GC.  See
+    /// [`crate::plan::space_targeted`].
+    pub(crate) fn space_targets(&self) -> Option<SpaceSet> {
+        *self.space_targets.lock().unwrap()
     }
 
     /// Are the stacks scanned?
@@ -168,6 +209,72 @@ impl GlobalState {
         old_allocation_bytes + size
     }
 
+    /// Get the recent allocation rate of mutators in bytes per millisecond.  See
+    /// [`crate::MMTK::get_allocation_rate`].
+    pub fn get_allocation_rate(&self) -> f64 {
+        self.allocation_rate.bytes_per_ms()
+    }
+
+    /// Get the current GC epoch.  See [`crate::memory_manager::gc_epoch`].
+    pub fn gc_epoch(&self) -> GcEpoch {
+        GcEpoch {
+            nursery: self.nursery_gcs.load(Ordering::SeqCst),
+            full: self.full_gcs.load(Ordering::SeqCst),
+        }
+    }
+
+    /// Advance the GC epoch when a GC finishes, and return the new epoch.  This is called before
+    /// mutators are resumed.
+    pub(crate) fn advance_gc_epoch(&self, nursery: bool) -> GcEpoch {
+        if nursery {
+            self.nursery_gcs.fetch_add(1, Ordering::SeqCst);
+        } else {
+            self.full_gcs.fetch_add(1, Ordering::SeqCst);
+        }
+        self.gc_epoch()
+    }
+
+    /// Is GC disabled by a [`GCDisabledScope`]?
+    pub fn is_gc_disabled(&self) -> bool {
+        self.gc_disabled_depth.load(Ordering::SeqCst) != 0
+    }
+
+    /// Enter a GC-disabled scope.  The clock starts when the outermost scope is entered.
+    pub(crate) fn disable_gc(&self) {
+        let mut time = self.gc_disabled_time.lock().unwrap();
+        if self.gc_disabled_depth.fetch_add(1, Ordering::SeqCst) == 0 {
+            time.since = Some(Instant::now());
+        }
+    }
+
+    /// Leave a GC-disabled scope.  The clock stops when the outermost scope is left.
+    pub(crate) fn enable_gc(&self) {
+        let mut time = self.gc_disabled_time.lock().unwrap();
+        let depth = self.gc_disabled_depth.fetch_sub(1, Ordering::SeqCst);
+        debug_assert!(depth > 0, "GC is enabled more times than it is disabled");
+        if depth == 1 {
+            let since = time.since.take().unwrap();
+            time.total += since.elapsed();
+        }
+    }
+
+    /// Get the total time spent with GC disabled, including the current GC-disabled scope if any.
+    pub fn gc_disabled_time(&self) -> Duration {
+        let time = self.gc_disabled_time.lock().unwrap();
+        time.total + time.since.map_or(Duration::ZERO, |since| since.elapsed())
+    }
+
+    /// Record a poll that would have triggered a GC if GC was not disabled, and return the number
+    /// of such polls so far.
+    pub(crate) fn record_gc_suppressed(&self) -> usize {
+        self.gc_suppressed_polls.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    /// Get the number of polls that would have triggered a GC if GC was not disabled.
+    pub fn gc_suppressed_polls(&self) -> usize {
+        self.gc_suppressed_polls.load(Ordering::SeqCst)
+    }
+
     #[cfg(feature = "malloc_counted_size")]
     pub fn get_malloc_bytes_in_pages(&self) -> usize {
         crate::util::conversions::bytes_to_pages_up(self.malloc_bytes.load(Ordering::Relaxed))
@@ -200,9 +307,90 @@ impl Default for GlobalState {
             cur_collection_attempts: AtomicUsize::new(0),
             scanned_stacks: AtomicUsize::new(0),
             allocation_bytes: AtomicUsize::new(0),
+            allocation_rate: AllocationRate::new(),
+            #[cfg(feature = "analysis")]
+            object_lifetimes: Arc::default(),
             #[cfg(feature = "malloc_counted_size")]
             malloc_bytes: AtomicUsize::new(0),
             live_bytes_in_last_gc: AtomicRefCell::new(HashMap::new()),
+            #[cfg(feature = "heap_verifier")]
+            heap_verification_requested: AtomicBool::new(false),
+            nursery_gcs: AtomicUsize::new(0),
+            full_gcs: AtomicUsize::new(0),
+            requested_space_targets: Mutex::new(None),
+            space_targets: Mutex::new(None),
+            gc_disabled_depth: AtomicUsize::new(0),
+            gc_disabled_time: Mutex::new(GCDisabledTime::default()),
+            gc_suppressed_polls: AtomicUsize::new(0),
+        }
+    }
+}
+
+/// The number of buckets in the sliding window of [`AllocationRate`].
+const ALLOCATION_RATE_BUCKETS: usize = 10;
+/// The time span of each bucket in milliseconds.  The window covers one second.
+const ALLOCATION_RATE_BUCKET_MS: usize = 100;
+
+/// Estimates the allocation rate of mutators over a sliding window of wall-clock time (including
+/// GC pauses).  Allocators record the bytes they allocate in the allocation slow path, so the bytes
+/// allocated in the fast path are recorded when an allocator gets a new thread-local buffer.
+///
+/// The buckets are updated without locking.  A few bytes recorded while a bucket is being reused
+/// for a new time slot may be lost, which is fine for an estimate.
+pub(crate) struct AllocationRate {
+    epoch: Instant,
+    /// Each bucket holds the index of a time slot, and the bytes allocated in that time slot.  A
+    /// bucket is reused for a later time slot once the window has moved past it.
+    buckets: [(AtomicUsize, AtomicUsize); ALLOCATION_RATE_BUCKETS],
+}
+
+impl AllocationRate {
+    fn new() -> Self {
+        Self {
+            epoch: Instant::now(),
+            buckets: std::array::from_fn(|_| (AtomicUsize::new(usize::MAX), AtomicUsize::new(0))),
+        }
+    }
+
+    fn now_ms(&self) -> usize {
+        self.epoch.elapsed().as_millis() as usize
+    }
+
+    /// Record the bytes allocated by a mutator.
+    pub fn record(&self, bytes: usize) {
+        let slot = self.now_ms() / ALLOCATION_RATE_BUCKET_MS;
+        let (bucket_slot, bucket_bytes) = &self.buckets[slot % ALLOCATION_RATE_BUCKETS];
+        let old_slot = bucket_slot.load(Ordering::Relaxed);
+        if old_slot != slot
+            && bucket_slot
+                .compare_exchange(old_slot, slot, Ordering::Relaxed, Ordering::Relaxed)
+                .is_ok()
+        {
+            bucket_bytes.store(0, Ordering::Relaxed);
+        }
+        bucket_bytes.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// Get the allocation rate in bytes per millisecond over the window.  The window covers the
+    /// last second, or the time since MMTk started if that is shorter.
+    pub fn bytes_per_ms(&self) -> f64 {
+        let now = self.now_ms();
+        let slot = now / ALLOCATION_RATE_BUCKET_MS;
+        let first_slot = slot.saturating_sub(ALLOCATION_RATE_BUCKETS - 1);
+        let bytes: usize = self
+            .buckets
+            .iter()
+            .filter(|(bucket_slot, _)| {
+                let s = bucket_slot.load(Ordering::Relaxed);
+                s != usize::MAX && s >= first_slot && s <= slot
+            })
+            .map(|(_, bucket_bytes)| bucket_bytes.load(Ordering::Relaxed))
+            .sum();
+        let window_ms = now - first_slot * ALLOCATION_RATE_BUCKET_MS;
+        if window_ms == 0 {
+            0.0
+        } else {
+            bytes as f64 / window_ms as f64
         }
     }
 }
@@ -214,6 +402,55 @@ pub enum GcStatus {
     GcProper,
 }
 
+/// The GC epoch, i.e. the numbers of nursery GCs and full-heap GCs that have finished.  Both
+/// numbers only increase, and are updated at the end of each GC before mutators are resumed.  A
+/// binding can record the epoch when it computes something from object addresses, and only
+/// recompute it if the epoch has changed.  Plans that are not generational only do full-heap GCs.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct GcEpoch {
+    /// The number of finished nursery GCs.
+    pub nursery: usize,
+    /// The number of finished full-heap GCs.
+    pub full: usize,
+}
+
+impl GcEpoch {
+    /// The total number of finished GCs.
+    pub fn total(&self) -> usize {
+        self.nursery + self.full
+    }
+}
+
+/// The time spent with GC disabled by [`GCDisabledScope`]s.
+#[derive(Default)]
+pub(crate) struct GCDisabledTime {
+    /// When the outermost live scope was entered, or `None` if GC is not disabled.
+    since: Option<Instant>,
+    /// The total time of the GC-disabled periods that have ended.
+    total: Duration,
+}
+
+/// A guard that keeps GC disabled while it is alive.  It is created by
+/// [`crate::memory_manager::gc_disabled_scope`], and enables GC again when it is dropped, unless
+/// other scopes are still alive.  Scopes can be nested, and can be created by different threads.
+#[must_use = "GC is enabled again as soon as the scope is dropped"]
+pub struct GCDisabledScope<'a> {
+    state: &'a GlobalState,
+}
+
+impl<'a> GCDisabledScope<'a> {
+    pub(crate) fn new(state: &'a GlobalState) -> Self {
+        state.disable_gc();
+        Self { state }
+    }
+}
+
+impl Drop for GCDisabledScope<'_> {
+    fn drop(&mut self) {
+        self.state.enable_gc();
+    }
+}
+
 /// Statistics for the live bytes in the last GC. The statistics is per space.
 #[derive(Copy, Clone, Debug)]
 pub struct LiveBytesStats {
@@ -225,3 +462,27 @@ pub struct LiveBytesStats {
     /// The ratio of live_bytes and used_bytes reflects the utilization of the memory in the space.
     pub used_bytes: usize,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allocation_rate() {
+        let rate = AllocationRate::new();
+        assert_eq!(rate.bytes_per_ms(), 0.0);
+
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        rate.record(1024 * 1024);
+        rate.record(1024 * 1024);
+        let bytes_per_ms = rate.bytes_per_ms();
+        // At least 10 ms have passed, and the window is at most one second.
+        assert!(bytes_per_ms > 0.0);
+        assert!(bytes_per_ms <= (2 * 1024 * 1024) as f64 / 10.0);
+        assert!(
+            bytes_per_ms
+                >= (2 * 1024 * 1024) as f64
+                    / (ALLOCATION_RATE_BUCKETS * ALLOCATION_RATE_BUCKET_MS) as f64
+        );
+    }
+}
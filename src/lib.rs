@@ -46,11 +46,23 @@ pub(crate) use mmtk::MMAPPER;
 pub use mmtk::MMTK;
 
 mod global_state;
+pub use crate::global_state::GCDisabledScope;
+pub use crate::global_state::GcEpoch;
 pub use crate::global_state::LiveBytesStats;
+#[cfg(feature = "analysis")]
+pub use crate::util::analysis::demographics::LiveTypeStats;
+#[cfg(feature = "analysis")]
+pub use crate::util::analysis::leak::LeakSuspect;
+#[cfg(feature = "analysis")]
+pub use crate::util::analysis::lifetime::LifetimeStats;
+#[cfg(feature = "analysis")]
+pub use crate::util::analysis::RtAnalysis;
 
 mod policy;
 
 pub mod build_info;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 pub mod memory_manager;
 pub mod plan;
 pub mod scheduler;
@@ -58,5 +70,6 @@ pub mod util;
 pub mod vm;
 
 pub use crate::plan::{
-    AllocationSemantics, BarrierSelector, Mutator, MutatorContext, ObjectQueue, Plan,
+    AllocationSemantics, BarrierSelector, BarrierSourceHint, Mutator, MutatorContext, ObjectQueue,
+    Plan,
 };
@@ -14,6 +14,7 @@
 use crate::mmtk::MMTKBuilder;
 use crate::mmtk::MMTK;
 use crate::plan::AllocationSemantics;
+use crate::plan::{BarrierSelector, BarrierSourceHint};
 use crate::plan::{Mutator, MutatorContext};
 use crate::scheduler::WorkBucketStage;
 use crate::scheduler::{GCWork, GCWorker};
@@ -22,7 +23,7 @@ use crate::util::constants::{LOG_BYTES_IN_PAGE, MIN_OBJECT_SIZE};
 use crate::util::heap::layout::vm_layout::vm_layout;
 use crate::util::opaque_pointer::*;
 use crate::util::{Address, ObjectReference};
-use crate::vm::slot::MemorySlice;
+use crate::vm::slot::{MemorySlice, Slot};
 use crate::vm::ReferenceGlue;
 use crate::vm::VMBinding;
 
@@ -36,8 +37,8 @@ use std::collections::HashMap;
 /// 1. Create an [`crate::MMTKBuilder`] instance.
 /// 2. Set command line options for MMTKBuilder by [`crate::memory_manager::process`] or [`crate::memory_manager::process_bulk`].
 /// 3. Initialize MMTk by calling this function, `mmtk_init()`, and pass the builder earlier. This call will return an MMTK instance.
-///    Usually a binding store the MMTK instance statically as a singleton. We plan to allow multiple instances, but this is not yet fully
-///    supported. Currently we assume a binding will only need one MMTk instance. Note that GC is enabled by default and the binding should
+///    Usually a binding store the MMTK instance statically as a singleton. A binding may create multiple independent instances in a
+///    process, each with its own heap (see [`crate::MMTK`] for the limitations). Note that GC is enabled by default and the binding should
 ///    implement `VMCollection::is_collection_enabled()` if it requires that the GC should be disabled at a particular time.
 ///
 /// This method will attempt to initialize the built-in `env_logger` if the Cargo feature "builtin_env_logger" is enabled (by default).
@@ -96,6 +97,101 @@ pub fn set_vm_space<VM: VMBinding>(mmtk: &'static mut MMTK<VM>, start: Address,
         .set_vm_region(start, size);
 }
 
+/// Add an externally mmapped region to the VM space, like [`set_vm_space`].  The region can be at an
+/// arbitrary address outside the heap range of MMTk, such as a segment of a boot image, or a data
+/// section of a shared library that contains objects.  Regions must not overlap with each other.
+///
+/// Unlike [`set_vm_space`], this function can be called at run time, for example, when the VM maps
+/// an AOT image.  It must not be called during a GC.  Usually the VM calls it from a mutator
+/// thread, which will not be stopped for a GC in the middle of this function.
+///
+/// If `map_vo_bit` is false, MMTk does not map the VO bits for the region (if the feature `vo_bit`
+/// is enabled), and [`crate::MMTK::initialize_vm_space_object`] will not set VO bits for objects
+/// in the region.  Those objects cannot be found with `is_mmtk_object` or internal pointers, so
+/// the VM should only do this for regions that are never scanned conservatively.
+#[cfg(feature = "vm_space")]
+pub fn add_vm_space_region<VM: VMBinding>(
+    mmtk: &MMTK<VM>,
+    start: Address,
+    size: usize,
+    map_vo_bit: bool,
+) {
+    mmtk.get_plan()
+        .base()
+        .vm_space
+        .add_vm_region(start, size, map_vo_bit);
+}
+
+/// Remove a region from the VM space, for example, before the VM unmaps an AOT image.  `start` and
+/// `size` must be the same as those used for adding the region with [`set_vm_space`] or
+/// [`add_vm_space_region`].  After this call, addresses in the region are no longer in MMTk spaces,
+/// and the VM may unmap the memory, or add another region at the same address.
+///
+/// Like [`add_vm_space_region`], this function must not be called during a GC.  The VM must also
+/// make sure that objects in the region are no longer referenced, either by other objects or by
+/// roots, and that no object in the region has been written with a write barrier since the last
+/// GC, because the barrier may have remembered the object or its fields for the next GC.
+#[cfg(feature = "vm_space")]
+pub fn remove_vm_space_region<VM: VMBinding>(mmtk: &MMTK<VM>, start: Address, size: usize) {
+    mmtk.get_plan()
+        .base()
+        .vm_space
+        .remove_vm_region(start, size);
+}
+
+/// Seal the read-only space, that is, the space for objects allocated with
+/// [`AllocationSemantics::ReadOnly`].  After this call, the objects in the space are immutable:
+/// MMTk `mprotect`s the memory of the space so that any write to it faults, and no longer marks or
+/// scans the objects in GC.  The objects they reference are found once in the next GC, and are
+/// treated as pinned roots in every GC from then on, so they never move.  This is useful for
+/// frozen objects shared by processes created with `fork()`.
+///
+/// This requires the feature `vo_bit` to find objects in the space.  The plan must support pinning
+/// roots (see [`crate::plan::PlanConstraints::supports_pinning_roots`]), as the references in the
+/// sealed objects cannot be updated if their targets move.  Otherwise this function panics.  The
+/// VM must not allocate objects with `AllocationSemantics::ReadOnly` after calling this function
+/// (including bump allocation from the thread-local buffers of mutators), and must not call this
+/// function during a GC.
+#[cfg(all(feature = "ro_space", feature = "vo_bit"))]
+pub fn seal_ro_space<VM: VMBinding>(mmtk: &MMTK<VM>) {
+    assert!(
+        mmtk.get_plan().constraints().supports_pinning_roots,
+        "Cannot seal the read-only space: the plan {:?} does not support pinning roots",
+        *mmtk.get_options().plan
+    );
+    mmtk.get_plan().base().ro_space.seal();
+}
+
+/// Make the pages in `start..start+size` of the code spaces executable and not writable if
+/// `executable` is true, or writable and not executable otherwise.  The code spaces are the spaces
+/// for objects allocated with [`AllocationSemantics::Code`] and [`AllocationSemantics::LargeCode`].
+///
+/// This requires the option `code_space_wx`, which maps the code spaces without execution
+/// permission.  A JIT compiler writes machine code into objects in the code spaces, and makes the
+/// pages executable before running the code.  The range must be page-aligned, and must be in one
+/// of the code spaces.  The VM must make the pages writable again before allocating objects in
+/// them or writing to objects in them.  MMTk still traces the code objects in GC, so
+/// [`crate::vm::Scanning::scan_object`] can report the references embedded in the code, and MMTk
+/// makes the pages writable during GC so that it can mark the objects.
+///
+/// This must not be called during a GC.
+#[cfg(feature = "code_space")]
+pub fn set_code_executable<VM: VMBinding>(
+    mmtk: &MMTK<VM>,
+    start: Address,
+    size: usize,
+    executable: bool,
+) {
+    use crate::policy::space::Space;
+    let base = mmtk.get_plan().base();
+    let space = if base.code_space.address_in_space(start) {
+        &base.code_space
+    } else {
+        &base.code_lo_space
+    };
+    space.set_executable(start..start + size, executable);
+}
+
 /// Request MMTk to create a mutator for the given thread. The ownership
 /// of returned boxed mutator is transferred to the binding, and the binding needs to take care of its
 /// lifetime. For performance reasons, A VM should store the returned mutator in a thread local storage
@@ -119,6 +215,28 @@ pub fn bind_mutator<VM: VMBinding>(
     mutator
 }
 
+/// Bind a mutator to another thread.  VMs that multiplex language threads over OS threads can
+/// call this when they migrate the language thread of the mutator to another OS thread.  The
+/// mutator must not be allocating at the time (e.g. it must not be blocked for GC in the
+/// allocation slow path).
+///
+/// Arguments:
+/// * `mutator`: A reference to the mutator.
+/// * `tls`: The new thread of the mutator.
+pub fn rebind_mutator<VM: VMBinding>(mutator: &mut Mutator<VM>, tls: VMMutatorThread) {
+    mutator.rebind(tls)
+}
+
+/// Park a mutator for a long time.  This flushes the mutator and releases the memory reserved by
+/// its allocators (e.g. the blocks of its thread-local allocation buffers), so that the memory is
+/// not held by a mutator that does not run.  The mutator can still allocate after this call.
+///
+/// Arguments:
+/// * `mutator`: A reference to the mutator.
+pub fn park_mutator<VM: VMBinding>(mutator: &mut Mutator<VM>) {
+    mutator.park()
+}
+
 /// Report to MMTk that a mutator is no longer needed. All mutator state is flushed before it is
 /// destroyed. A binding should not attempt to use the mutator after this call. MMTk will not
 /// attempt to reclaim the memory for the mutator, so a binding should properly reclaim the memory
@@ -139,6 +257,73 @@ pub fn flush_mutator<VM: VMBinding>(mutator: &mut Mutator<VM>) {
     mutator.flush()
 }
 
+/// Set the stack watermark of a mutator.  The VM calls this after scanning the stack of the
+/// mutator and installing a return barrier on the frame at `frontier`, or when the return barrier
+/// is triggered and the frames below `frontier` have not been returned to since the last scan.
+/// Pass `Address::ZERO` to require the whole stack to be scanned in the next GC.
+/// See [`crate::plan::StackWatermark`].
+///
+/// Arguments:
+/// * `mutator`: A reference to the mutator.
+/// * `frontier`: The watermark. Its meaning is up to the VM.
+pub fn set_stack_watermark<VM: VMBinding>(mutator: &mut Mutator<VM>, frontier: Address) {
+    mutator.stack_watermark.set(frontier)
+}
+
+/// Flush every mutator, and retire their thread-local allocation buffers, so that the memory in the
+/// buffers can be reused by other mutators. This uses a handshake (see [`request_handshake`]), and
+/// waits until all mutators are flushed. This must not be called by a mutator thread.
+///
+/// Arguments:
+/// * `mmtk`: A reference to an MMTk instance.
+/// * `tls`: The current thread.
+pub fn flush_all_mutators<VM: VMBinding>(mmtk: &MMTK<VM>, tls: VMThread) {
+    mmtk.handshake.perform(
+        tls,
+        std::sync::Arc::new(|mutator: &mut Mutator<VM>| {
+            mutator.flush();
+            mutator.retire_allocation_buffers();
+        }),
+    )
+}
+
+/// Ask every mutator to execute `action` at its next yieldpoint, and wait until all of them have
+/// done so. This must not be called by a mutator thread. See [`crate::scheduler::Handshake`].
+///
+/// Arguments:
+/// * `mmtk`: A reference to an MMTk instance.
+/// * `tls`: The current thread.
+/// * `action`: The action to be executed by each mutator.
+pub fn request_handshake<VM: VMBinding>(
+    mmtk: &MMTK<VM>,
+    tls: VMThread,
+    action: crate::scheduler::HandshakeAction<VM>,
+) {
+    mmtk.handshake.perform(tls, action)
+}
+
+/// The VM calls this at a yieldpoint of a mutator (or on behalf of a blocked mutator) after MMTk
+/// calls [`crate::vm::Collection::request_handshake`]. It executes the action of the handshake in
+/// progress for the mutator, if the mutator has not done so. Return true if the action is executed.
+/// This is cheap if no handshake is in progress.
+///
+/// Arguments:
+/// * `mmtk`: A reference to an MMTk instance.
+/// * `mutator`: A reference to the mutator.
+pub fn handshake_yieldpoint<VM: VMBinding>(mmtk: &MMTK<VM>, mutator: &mut Mutator<VM>) -> bool {
+    mmtk.handshake.yieldpoint(mutator)
+}
+
+/// Get the stack watermark of a mutator if the VM only needs to scan the frames above the
+/// watermark in the current GC, or `None` if the VM needs to scan the whole stack.  The VM should
+/// call this in [`crate::vm::Scanning::scan_roots_in_mutator_thread`].
+///
+/// Arguments:
+/// * `mutator`: A reference to the mutator.
+pub fn get_stack_watermark<VM: VMBinding>(mutator: &Mutator<VM>) -> Option<Address> {
+    mutator.stack_watermark.get()
+}
+
 /// Allocate memory for an object. For performance reasons, a VM should
 /// implement the allocation fast-path on their side rather than just calling this function.
 ///
@@ -196,6 +381,60 @@ pub fn alloc_slow<VM: VMBinding>(
     mutator.alloc_slow(size, align, offset, semantics)
 }
 
+/// Allocate memory for an object with the given options.  This is the same as [`alloc`], except
+/// for what happens if the allocation cannot be satisfied without a GC.  With
+/// [`crate::util::alloc::OnAllocationFail::ReturnFailure`], the allocation never triggers a GC or
+/// blocks for one, and returns a null address if the fast path and one attempt of the slow path
+/// cannot satisfy it.  It does not report out of memory to the binding, either.  This is useful in
+/// contexts where a GC is not permitted, such as VM-internal code that holds raw pointers to
+/// objects.  The allocation may still take locks and map memory, so it is not async-signal-safe,
+/// and must not be used in signal handlers.
+///
+/// Arguments:
+/// * `mutator`: The mutator to perform this allocation request.
+/// * `size`: The number of bytes required for the object.
+/// * `align`: Required alignment for the object.
+/// * `offset`: Offset associated with the alignment.
+/// * `semantics`: The allocation semantic required for the allocation.
+/// * `options`: The options for the allocation.
+pub fn alloc_with_options<VM: VMBinding>(
+    mutator: &mut Mutator<VM>,
+    size: usize,
+    align: usize,
+    offset: usize,
+    semantics: AllocationSemantics,
+    options: crate::util::alloc::AllocationOptions,
+) -> Address {
+    debug_assert!(size >= MIN_OBJECT_SIZE);
+    debug_assert!(align >= VM::MIN_ALIGNMENT);
+    debug_assert!(align <= VM::MAX_ALIGNMENT);
+    debug_assert!(VM::USE_ALLOCATION_OFFSET || offset == 0);
+
+    mutator.alloc_with_options(size, align, offset, semantics, options)
+}
+
+/// Invoke the allocation slow path with the given options.  This is the slow path of
+/// [`alloc_with_options`] for a binding that implements the fast path on its side.  See
+/// [`alloc_slow`] and [`alloc_with_options`].
+///
+/// Arguments:
+/// * `mutator`: The mutator to perform this allocation request.
+/// * `size`: The number of bytes required for the object.
+/// * `align`: Required alignment for the object.
+/// * `offset`: Offset associated with the alignment.
+/// * `semantics`: The allocation semantic required for the allocation.
+/// * `options`: The options for the allocation.
+pub fn alloc_slow_with_options<VM: VMBinding>(
+    mutator: &mut Mutator<VM>,
+    size: usize,
+    align: usize,
+    offset: usize,
+    semantics: AllocationSemantics,
+    options: crate::util::alloc::AllocationOptions,
+) -> Address {
+    mutator.alloc_slow_with_options(size, align, offset, semantics, options)
+}
+
 /// Perform post-allocation actions, usually initializing object metadata. For many allocators none are
 /// required. For performance reasons, a VM should implement the post alloc fast-path on their side
 /// rather than just calling this function.
@@ -311,6 +550,59 @@ pub fn object_reference_write_post<VM: VMBinding>(
         .object_reference_write_post(src, slot, target);
 }
 
+/// The write barrier slow path by MMTk.  A binding that implements the write barrier fast path on
+/// its side should call this when the fast path decides that the slow path is needed.  For example,
+/// for [`crate::plan::BarrierSelector::ObjectBarrier`], the fast path checks whether the unlog bit
+/// of `src` is set.  This is a no-op for plans without a barrier.
+///
+/// Arguments:
+/// * `mutator`: The mutator for the current thread.
+/// * `src`: The modified source object.
+/// * `slot`: The location of the field to be modified.
+/// * `target`: The target for the write operation.  `None` if the slot does not hold an object
+///   reference.
+pub fn object_reference_write_slow<VM: VMBinding>(
+    mutator: &mut Mutator<VM>,
+    src: ObjectReference,
+    slot: VM::VMSlot,
+    target: Option<ObjectReference>,
+) {
+    mutator
+        .barrier()
+        .object_reference_write_slow(src, slot, target);
+}
+
+/// A pre-barrier indicating that some fields of `object` will probably be modified soon, without
+/// write barriers.  The binding must call this before any field is modified, and there must be no
+/// GC safepoints between this call and the field writes.  This is a no-op for plans without a
+/// barrier.
+///
+/// Arguments:
+/// * `mutator`: The mutator for the current thread.
+/// * `object`: The object that will probably be modified.
+pub fn object_probable_write<VM: VMBinding>(mutator: &mut Mutator<VM>, object: ObjectReference) {
+    mutator.barrier().object_probable_write(object);
+}
+
+/// Remember an object whose reference fields have been modified without write barriers, for
+/// example, by native code that mutates the object in place through FFI.  The next GC will trace
+/// the references stored in the object as if each of the writes had gone through the write
+/// barrier of the current plan.
+///
+/// This works for any plan, so the binding does not need to know which barrier is in use.  With
+/// [`crate::plan::BarrierSelector::ObjectBarrier`], the object is logged and remembered unless it
+/// has been logged since the last GC.  This is a no-op for plans without a barrier.
+///
+/// The binding must call this after the fields are modified, and before the current thread
+/// reaches the next GC safepoint.
+///
+/// Arguments:
+/// * `mutator`: The mutator for the current thread.
+/// * `object`: The modified object.
+pub fn remember_object<VM: VMBinding>(mutator: &mut Mutator<VM>, object: ObjectReference) {
+    mutator.barrier().remember_object(object);
+}
+
 /// The *subsuming* memory region copy barrier by MMTk.
 /// This is called when the VM tries to copy a piece of heap memory to another.
 /// The data within the slice does not necessarily to be all valid pointers,
@@ -385,6 +677,44 @@ pub fn memory_region_copy_post<VM: VMBinding>(
     mutator.barrier().memory_region_copy_post(src, dst);
 }
 
+/// Return true if a store of an object reference into a field of a source object needs a write
+/// barrier.  This lets compilers remove redundant barriers statically.  It only depends on the
+/// plan and the arguments, so a compiler can query it once and cache the result.
+///
+/// The barrier can be elided if the plan has no barrier, or if the source object is newly
+/// allocated with a semantics for which new objects are not remembered (see
+/// [`crate::plan::PlanConstraints::barrier_elidable_for_new_objects`]).  New objects allocated
+/// with other semantics, such as `Immortal` and `NonMoving`, are treated as mature objects by
+/// generational plans, so stores into them still need barriers.
+///
+/// Arguments:
+/// * `mmtk`: The reference to an MMTk instance.
+/// * `src_space_hint`: What the compiler knows about the source object.
+/// * `semantics`: The allocation semantics of the source object.  This is ignored if
+///   `src_space_hint` is [`crate::plan::BarrierSourceHint::Unknown`].
+pub fn barrier_required_for<VM: VMBinding>(
+    mmtk: &MMTK<VM>,
+    src_space_hint: BarrierSourceHint,
+    semantics: AllocationSemantics,
+) -> bool {
+    let constraints = mmtk.get_plan().constraints();
+    if constraints.barrier == BarrierSelector::NoBarrier {
+        return false;
+    }
+    match src_space_hint {
+        BarrierSourceHint::Unknown => true,
+        BarrierSourceHint::NewlyAllocated => {
+            !(constraints.barrier_elidable_for_new_objects
+                && matches!(
+                    semantics,
+                    AllocationSemantics::Default
+                        | AllocationSemantics::Los
+                        | AllocationSemantics::LargeCode
+                ))
+        }
+    }
+}
+
 /// Return an AllocatorSelector for the given allocation semantic. This method is provided
 /// so that VM compilers may call it to help generate allocation fast-path.
 ///
@@ -395,7 +725,7 @@ pub fn get_allocator_mapping<VM: VMBinding>(
     mmtk: &MMTK<VM>,
     semantics: AllocationSemantics,
 ) -> AllocatorSelector {
-    mmtk.get_plan().get_allocator_mapping()[semantics]
+    mmtk.allocator_mapping[semantics]
 }
 
 /// The standard malloc. MMTk either uses its own allocator, or forward the call to a
@@ -475,13 +805,44 @@ pub fn gc_poll<VM: VMBinding>(mmtk: &MMTK<VM>, tls: VMMutatorThread) {
         "gc_poll() can only be called by a mutator thread."
     );
 
-    if VM::VMCollection::is_collection_enabled() && mmtk.gc_trigger.poll(false, None) {
+    if !VM::VMCollection::is_collection_enabled() {
+        return;
+    }
+    if mmtk.state.is_gc_disabled() {
+        mmtk.gc_trigger.poll_while_gc_disabled(tls, None);
+    } else if mmtk.gc_trigger.poll(false, None) {
         debug!("Collection required");
         assert!(mmtk.state.is_initialized(), "GC is not allowed here: collection is not initialized (did you call initialize_collection()?).");
-        VM::VMCollection::block_for_gc(tls);
+        mmtk.handshake.block_for_gc(tls);
     }
 }
 
+/// Disable GC until the returned scope is dropped.  Scopes are counted, so they can be nested, and
+/// can be held by different threads at the same time.  GC is enabled again when the last scope is
+/// dropped.  This allows a library to disable GC around its critical section without knowing
+/// whether its caller has already disabled GC.
+///
+/// While GC is disabled, allocation may exceed the heap size, as if
+/// [`crate::vm::Collection::is_collection_enabled`] returned false.  If an allocation or
+/// [`gc_poll`] would have triggered a GC, MMTk records it, and warns and calls
+/// [`crate::vm::Collection::gc_suppressed`] when the number of suppressed GCs reaches a power of
+/// two.  User collection requests are ignored unless they are forced.  See
+/// [`crate::MMTK::gc_disabled_time`] and [`crate::MMTK::gc_suppressed_polls`] for the statistics.
+///
+/// Arguments:
+/// * `mmtk`: A reference to an MMTk instance.
+pub fn gc_disabled_scope<VM: VMBinding>(mmtk: &MMTK<VM>) -> crate::GCDisabledScope<'_> {
+    crate::GCDisabledScope::new(&mmtk.state)
+}
+
+/// Return true if GC is disabled by a scope returned by [`gc_disabled_scope`].
+///
+/// Arguments:
+/// * `mmtk`: A reference to an MMTk instance.
+pub fn is_gc_disabled<VM: VMBinding>(mmtk: &MMTK<VM>) -> bool {
+    mmtk.state.is_gc_disabled()
+}
+
 /// Wrapper for [`crate::scheduler::GCWorker::run`].
 pub fn start_worker<VM: VMBinding>(
     mmtk: &'static MMTK<VM>,
@@ -547,6 +908,257 @@ pub fn live_bytes_in_last_gc<VM: VMBinding>(
     mmtk.state.live_bytes_in_last_gc.borrow().clone()
 }
 
+/// Take the ranges of memory moved by the last GC, so that the binding can fix its external data structures
+/// (e.g. object ID tables of debuggers, or sampling profiles) in bulk. This requires the option `record_relocations`,
+/// and returns an empty vector otherwise.
+///
+/// The ranges are sorted by their old addresses, and adjacent ranges moved together are coalesced. Use
+/// [`crate::util::copy::relocate`] or [`crate::util::copy::relocate_object`] to find the new location of an
+/// address. The relocations are replaced at the end of each GC, and each GC only records its own moves, so the
+/// binding should take them after every GC and before the next GC, for example, in
+/// [`crate::vm::Collection::resume_mutators`]. Only the objects copied by GC workers are recorded, which excludes
+/// the objects moved by `MarkCompact`.
+///
+/// Arguments:
+/// * `mmtk`: A reference to an MMTk instance.
+pub fn take_relocations<VM: VMBinding>(mmtk: &MMTK<VM>) -> Vec<crate::util::copy::Relocation> {
+    mmtk.relocation_log.take()
+}
+
+/// Return the current GC epoch, i.e. the numbers of nursery GCs and full-heap GCs that have finished. A binding can
+/// compare epochs to cheaply find out whether any GC has happened since it computed something from object
+/// addresses. See [`crate::GcEpoch`].
+///
+/// Arguments:
+/// * `mmtk`: A reference to an MMTk instance.
+pub fn gc_epoch<VM: VMBinding>(mmtk: &MMTK<VM>) -> crate::GcEpoch {
+    mmtk.state.gc_epoch()
+}
+
+/// Return the GC epoch after the last GC if `object` was moved to its current address by the last GC, or `None`
+/// otherwise. MMTk only remembers the objects moved by the last GC, so a binding that recorded an epoch older than the
+/// last GC cannot use this to find out whether the object was moved. This requires the option `record_relocations`,
+/// and always returns `None` without it. Objects moved by `MarkCompact` are not recorded.
+///
+/// Arguments:
+/// * `mmtk`: A reference to an MMTk instance.
+/// * `object`: The object to query. It must be live.
+pub fn object_last_moved_epoch<VM: VMBinding>(
+    mmtk: &MMTK<VM>,
+    object: ObjectReference,
+) -> Option<crate::GcEpoch> {
+    mmtk.relocation_log.last_moved_epoch(object)
+}
+
+/// Return the live object histogram of the last GC, classified by [`crate::vm::ObjectModel::get_type_name`].
+///
+/// The histogram includes at most `live_demographics_top_n` (an MMTk option) types with the most live bytes,
+/// sorted by the live bytes in descending order. Objects are counted when they are scanned during tracing,
+/// so for a nursery GC in generational plans, only the objects scanned in that GC are counted.
+/// The value returned by this method is only updated at the end of a GC.
+#[cfg(feature = "analysis")]
+pub fn live_object_demographics_in_last_gc<VM: VMBinding>(
+    mmtk: &MMTK<VM>,
+) -> Vec<crate::LiveTypeStats> {
+    mmtk.analysis_manager.live_object_demographics()
+}
+
+/// Return the leak suspects found in the last full-heap GC. A leak suspect is a group of objects
+/// (with the same type name and allocation site) that have survived at least `leak_report_min_full_gcs`
+/// (an MMTk option) full-heap GCs, and whose total size grew since the previous full-heap GC.
+///
+/// The report includes at most `live_demographics_top_n` (an MMTk option) groups, sorted by bytes in
+/// descending order. The value returned by this method is only updated at the end of a full-heap GC.
+#[cfg(feature = "analysis")]
+pub fn leak_suspects_in_last_full_gc<VM: VMBinding>(mmtk: &MMTK<VM>) -> Vec<crate::LeakSuspect> {
+    mmtk.analysis_manager.leak_suspects()
+}
+
+/// Add an analysis routine to an MMTk instance. The hooks of the routine will be called along with
+/// the analysis routines provided by MMTk core. See [`crate::RtAnalysis`] for the hooks.
+///
+/// Arguments:
+/// * `mmtk`: A reference to an MMTk instance.
+/// * `routine`: The analysis routine to add.
+#[cfg(feature = "analysis")]
+pub fn add_analysis_routine<VM: VMBinding>(
+    mmtk: &MMTK<VM>,
+    routine: std::sync::Arc<std::sync::Mutex<dyn crate::RtAnalysis<VM> + Send>>,
+) {
+    mmtk.analysis_manager.add_analysis_routine(routine);
+}
+
+/// Return the lifetime statistics (the ages of dead objects, in GCs) of an MMTk instance for each
+/// size class.
+///
+/// Only objects that are swept individually by their spaces are recorded. Currently these are
+/// objects in the large object space, the malloc mark sweep space, and the native mark sweep space
+/// (only with the feature `vo_bit`, and only if the binding does not use an allocation offset or
+/// an offset between the object reference and the object start). Objects in other spaces, such as
+/// copying spaces and Immix spaces, are never recorded, so the statistics do not cover the whole
+/// heap for most plans. Use [`crate::LifetimeStats::survival_curve`] to compute the survival curve
+/// of a size class.
+///
+/// Arguments:
+/// * `mmtk`: A reference to an MMTk instance.
+#[cfg(feature = "analysis")]
+pub fn object_lifetimes_by_size_class<VM: VMBinding>(mmtk: &MMTK<VM>) -> Vec<crate::LifetimeStats> {
+    mmtk.analysis_manager.object_lifetimes()
+}
+
+/// Record the allocation site of an object. The allocation site is an opaque tag supplied by the
+/// binding, such as an index into a table of allocation sites or the ID of a captured backtrace.
+/// MMTk includes the tag when it reports a bad object, e.g. in a sanity GC failure. The tag `0` means
+/// the allocation site is unknown. This should be called after [`post_alloc`], which resets the tag.
+///
+/// Arguments:
+/// * `object`: The object that is allocated.
+/// * `site`: The allocation site tag.
+#[cfg(feature = "alloc_site")]
+pub fn set_allocation_site(object: ObjectReference, site: u16) {
+    crate::util::alloc_site::set_alloc_site(object, site);
+}
+
+/// Return the allocation site tag of an object recorded by [`set_allocation_site`], or `0` if it is
+/// unknown. Bindings may use this to include the allocation site in [`crate::vm::ObjectModel::dump_object`].
+///
+/// Arguments:
+/// * `object`: The object to query.
+#[cfg(feature = "alloc_site")]
+pub fn get_allocation_site(object: ObjectReference) -> u16 {
+    crate::util::alloc_site::get_alloc_site(object)
+}
+
+/// Return a hash code of an object that does not change when the object is moved by the GC. The
+/// hash code is the address of the object when it is first hashed. If the object moves later, the
+/// GC appends a word to the object to remember the hash code. See [`crate::util::object_hash`] for
+/// what the binding needs to do to support it.
+///
+/// Arguments:
+/// * `object`: The object to hash.
+#[cfg(feature = "address_based_hashing")]
+pub fn object_hash<VM: VMBinding>(object: ObjectReference) -> usize {
+    crate::util::object_hash::object_hash::<VM>(object)
+}
+
+/// Return a stable 64-bit ID of an object. The ID is assigned when this is first called for the object, and does
+/// not change when the object is moved by the GC. IDs are never reused. See [`crate::util::object_id`] for the cost.
+///
+/// Arguments:
+/// * `mmtk`: A reference to an MMTk instance.
+/// * `object`: The object to identify. It must be in an MMTk space.
+#[cfg(feature = "object_id")]
+pub fn object_id<VM: VMBinding>(mmtk: &MMTK<VM>, object: ObjectReference) -> u64 {
+    mmtk.object_ids.object_id(object)
+}
+
+/// Return the object with an ID returned by [`object_id`], or `None` if the object has died or the ID has not been
+/// assigned. The result is the current address of the object.
+///
+/// Arguments:
+/// * `mmtk`: A reference to an MMTk instance.
+/// * `id`: The object ID.
+#[cfg(feature = "object_id")]
+pub fn object_from_id<VM: VMBinding>(mmtk: &MMTK<VM>, id: u64) -> Option<ObjectReference> {
+    mmtk.object_ids.object_from_id(id)
+}
+
+/// Create a global handle to an object. MMTk keeps the handle consistent across GCs: the object of a strong handle is
+/// kept alive, and a weak handle is cleared when its object dies. Both are updated when the object is moved. See
+/// [`crate::util::handle_table`].
+///
+/// Arguments:
+/// * `mmtk`: A reference to an MMTk instance.
+/// * `object`: The object of the handle.
+/// * `kind`: Whether the handle is strong or weak.
+pub fn create_handle<VM: VMBinding>(
+    mmtk: &MMTK<VM>,
+    object: ObjectReference,
+    kind: crate::util::handle_table::HandleKind,
+) -> crate::util::handle_table::Handle {
+    mmtk.handle_table.create(object, kind)
+}
+
+/// Load the current object of a handle created by [`create_handle`], or `None` if it is a weak handle whose object has
+/// died. The handle must not have been deleted.
+///
+/// Arguments:
+/// * `mmtk`: A reference to an MMTk instance.
+/// * `handle`: The handle.
+pub fn load_handle<VM: VMBinding>(
+    mmtk: &MMTK<VM>,
+    handle: crate::util::handle_table::Handle,
+) -> Option<ObjectReference> {
+    mmtk.handle_table.load(handle)
+}
+
+/// Delete a handle created by [`create_handle`]. The handle must not be used afterwards, and it may be reused by a
+/// later call to [`create_handle`].
+///
+/// Arguments:
+/// * `mmtk`: A reference to an MMTk instance.
+/// * `handle`: The handle.
+pub fn delete_handle<VM: VMBinding>(mmtk: &MMTK<VM>, handle: crate::util::handle_table::Handle) {
+    mmtk.handle_table.delete(handle)
+}
+
+/// Attach a word of user data to an object, replacing the previous data, if any. MMTk moves the data with the
+/// object when the object is moved by the GC, and removes the data when the object dies. See
+/// [`crate::util::object_user_data`]. Returns the previous data of the object.
+///
+/// Arguments:
+/// * `mmtk`: A reference to an MMTk instance.
+/// * `object`: The object to annotate. It must be in an MMTk space.
+/// * `data`: The user data.
+#[cfg(feature = "object_user_data")]
+pub fn set_object_user_data<VM: VMBinding>(
+    mmtk: &MMTK<VM>,
+    object: ObjectReference,
+    data: usize,
+) -> Option<usize> {
+    mmtk.object_user_data.set(object, data)
+}
+
+/// Return the user data attached to an object with [`set_object_user_data`], or `None` if there is none.
+///
+/// Arguments:
+/// * `mmtk`: A reference to an MMTk instance.
+/// * `object`: The object to query.
+#[cfg(feature = "object_user_data")]
+pub fn get_object_user_data<VM: VMBinding>(
+    mmtk: &MMTK<VM>,
+    object: ObjectReference,
+) -> Option<usize> {
+    mmtk.object_user_data.get(object)
+}
+
+/// Remove the user data attached to an object, and return it, if any. The death callback is not called for it.
+///
+/// Arguments:
+/// * `mmtk`: A reference to an MMTk instance.
+/// * `object`: The object to query.
+#[cfg(feature = "object_user_data")]
+pub fn clear_object_user_data<VM: VMBinding>(
+    mmtk: &MMTK<VM>,
+    object: ObjectReference,
+) -> Option<usize> {
+    mmtk.object_user_data.clear(object)
+}
+
+/// Set a callback that is called with each object that dies with user data attached, and the data. The callback is
+/// called by a GC worker during a GC, and must not access the dead object. It replaces the previous callback.
+///
+/// Arguments:
+/// * `mmtk`: A reference to an MMTk instance.
+/// * `callback`: The callback.
+#[cfg(feature = "object_user_data")]
+pub fn set_object_user_data_death_callback<VM: VMBinding>(
+    mmtk: &MMTK<VM>,
+    callback: crate::util::object_user_data::ObjectUserDataDeathCallback,
+) {
+    mmtk.object_user_data.set_death_callback(callback);
+}
+
 /// Return the starting address of the heap. *Note that currently MMTk uses
 /// a fixed address range as heap.*
 pub fn starting_heap_address() -> Address {
@@ -567,6 +1179,21 @@ pub fn total_bytes<VM: VMBinding>(mmtk: &MMTK<VM>) -> usize {
     mmtk.get_plan().get_total_pages() << LOG_BYTES_IN_PAGE
 }
 
+/// Tell MMTk that the mutators are idle (e.g. the application is waiting for input or for the next
+/// request), so that GC workers can use the idle period to do useful work, such as sweeping blocks
+/// that would otherwise be swept by mutators when they allocate.  This returns immediately.
+///
+/// Mutators may resume running at any time.  A GC requested while workers are doing idle work
+/// starts after the current idle work packets finish, and they stop early if a GC is requested.
+///
+/// Arguments:
+/// * `mmtk`: A reference to an MMTk instance.
+pub fn notify_idle<VM: VMBinding>(mmtk: &MMTK<VM>) {
+    if mmtk.state.is_initialized() {
+        mmtk.scheduler.request_idle_work();
+    }
+}
+
 /// The application code has requested a collection. This is just a GC hint, and
 /// we may ignore it.
 ///
@@ -584,6 +1211,34 @@ pub fn handle_user_collection_request<VM: VMBinding>(
     mmtk.handle_user_collection_request(tls, false, false)
 }
 
+/// The application code has requested a collection that only collects the spaces of the given
+/// names, such as `["los"]`, for example, to release the memory of large objects without tracing
+/// the rest of the heap.  This is just a GC hint like [`handle_user_collection_request`].
+///
+/// The objects in the other spaces are considered live in the GC, and their fields are scanned as
+/// roots, which costs time proportional to the size of the other spaces.  Only some plans can
+/// collect some of their spaces alone (see
+/// [`crate::plan::Plan::supports_space_targeted_collection`]).  Currently, SemiSpace can collect
+/// its large object space alone.  If the plan cannot collect the
+/// spaces alone, this returns false immediately without triggering a GC.  An emergency
+/// collection collects all the spaces instead.
+///
+/// Returns whether a GC was ran or not.  If MMTk triggers a GC, this method will block the
+/// calling thread and return true when the GC finishes.
+///
+/// Arguments:
+/// * `mmtk`: A reference to an MMTk instance.
+/// * `tls`: The thread that triggers this collection request.
+/// * `spaces`: The names of the spaces to collect.
+#[cfg(feature = "vo_bit")]
+pub fn handle_user_space_targeted_collection_request<VM: VMBinding>(
+    mmtk: &MMTK<VM>,
+    tls: VMMutatorThread,
+    spaces: &[&str],
+) -> bool {
+    mmtk.handle_user_space_targeted_collection_request(tls, spaces)
+}
+
 /// Is the object alive?
 ///
 /// Arguments:
@@ -592,6 +1247,82 @@ pub fn is_live_object(object: ObjectReference) -> bool {
     object.is_live()
 }
 
+/// Has MMTk decided that the object is dead?  This is useful for detecting resurrection bugs, i.e.
+/// storing a reference to a dead object into a slot without tracing it, for example, in a
+/// finalizer or a weak reference callback.
+///
+/// The answer is only precise in the following cases, and this function returns false otherwise.
+/// -   During a GC, after the transitive closure following strong references, and before the
+///     spaces start releasing memory.  This includes the stages of processing weak references
+///     (e.g. in `Scanning::process_weak_refs`).  An object is dead if it is not marked or
+///     forwarded.  The binding may still resurrect it by tracing it with an `ObjectTracer`.
+/// -   Between GCs if the feature "vo_bit" is enabled.  An object is dead if its VO bit is not set.
+///
+/// Arguments:
+/// * `mmtk`: A reference to an MMTk instance.
+/// * `object`: The object reference to query.
+pub fn is_dead_object<VM: VMBinding>(mmtk: &MMTK<VM>, object: ObjectReference) -> bool {
+    crate::util::resurrection::is_dead(mmtk, object)
+}
+
+/// Assert that `slot` does not refer to an object that MMTk has decided to be dead, or to the old
+/// copy of an object that has been moved in the current GC.  The binding may call this after
+/// storing a reference into a slot where MMTk may not see it, such as when handling finalizers and
+/// weak references, to catch resurrection bugs where they happen instead of as memory corruption
+/// after the GC.  See [`is_dead_object`] for when MMTk knows an object is dead.
+///
+/// This function panics and reports the offending slot and the referent if the check fails.
+///
+/// Arguments:
+/// * `mmtk`: A reference to an MMTk instance.
+/// * `slot`: The slot to check.
+pub fn assert_not_resurrected<VM: VMBinding>(mmtk: &MMTK<VM>, slot: VM::VMSlot) {
+    if let Some(reason) = crate::util::resurrection::check_slot(mmtk, slot) {
+        let referent = slot.load().unwrap();
+        #[cfg(feature = "alloc_site")]
+        let referent_site = crate::util::alloc_site::describe(referent);
+        #[cfg(not(feature = "alloc_site"))]
+        let referent_site = "";
+        panic!(
+            "Slot {:?} refers to {}{}: {}.  Did the binding store a reference without tracing it?",
+            slot, referent, referent_site, reason
+        );
+    }
+}
+
+/// Report a memory access fault (such as `SIGSEGV`) at `addr` to the PageProtect plan.  Return
+/// true if `addr` is in the PageProtect space, in which case the fault is counted.  Return false
+/// if the fault is not caused by MMTk, or if the current plan is not PageProtect.
+///
+/// This function does not check whether `addr` is in a live object.  PageProtect never protects
+/// the pages of live objects, so a real fault in its space is an access to the protected page of a
+/// dead object, i.e. a use-after-free bug in the binding.  The binding should only report faults
+/// that actually happened.
+///
+/// The binding may call this in its signal handler, as this function does not allocate memory or
+/// take any lock.  The number of the pages kept protected after objects die can be set with the
+/// option `quarantine_gcs`.
+///
+/// Arguments:
+/// * `mmtk`: A reference to an MMTk instance.
+/// * `addr`: The faulting address.
+pub fn report_page_protect_fault<VM: VMBinding>(mmtk: &MMTK<VM>, addr: Address) -> bool {
+    mmtk.get_plan()
+        .downcast_ref::<crate::plan::PageProtect<VM>>()
+        .is_some_and(|plan| plan.report_fault(addr))
+}
+
+/// Get the number of faults reported with [`report_page_protect_fault`] that were accesses to
+/// dead objects.  Return zero if the current plan is not PageProtect.
+///
+/// Arguments:
+/// * `mmtk`: A reference to an MMTk instance.
+pub fn page_protect_fault_count<VM: VMBinding>(mmtk: &MMTK<VM>) -> usize {
+    mmtk.get_plan()
+        .downcast_ref::<crate::plan::PageProtect<VM>>()
+        .map_or(0, |plan| plan.fault_count())
+}
+
 /// Check if `addr` is the raw address of an object reference to an MMTk object.
 ///
 /// Concretely:
@@ -689,6 +1420,59 @@ pub fn is_in_mmtk_spaces(object: ObjectReference) -> bool {
         .is_in_space(object)
 }
 
+/// Return information about the space and the policy of an object, such as the name of the space,
+/// whether the object may move, and the generation of the object in a generational plan. Return
+/// `None` if the object is not in any MMTk space, i.e. [`is_in_mmtk_spaces`] returns false.
+/// Bindings may use this for heap introspection and debugging.
+///
+/// Arguments:
+/// * `mmtk`: A reference to an MMTk instance.
+/// * `object`: The object reference to query.
+pub fn object_space_info<VM: VMBinding>(
+    mmtk: &MMTK<VM>,
+    object: ObjectReference,
+) -> Option<crate::policy::sft::ObjectSpaceInfo> {
+    use crate::mmtk::SFT_MAP;
+    use crate::policy::sft::{Generation, ObjectSpaceInfo};
+    let sft = SFT_MAP.get_checked(object.to_raw_address());
+    if !sft.is_in_space(object) {
+        return None;
+    }
+    #[cfg(feature = "object_pinning")]
+    let pinned = sft.is_object_pinned(object);
+    #[cfg(not(feature = "object_pinning"))]
+    let pinned = !sft.is_movable();
+    let generation = mmtk.get_plan().generational().map(|gen| {
+        if gen.is_object_in_nursery(object) {
+            Generation::Nursery
+        } else {
+            Generation::Mature
+        }
+    });
+    Some(ObjectSpaceInfo {
+        space_name: sft.name(),
+        policy: sft.policy_kind(),
+        movable: sft.is_movable() && !pinned,
+        generation,
+        pinned,
+    })
+}
+
+/// Describe the GC metadata of an object for debugging, such as its mark bit, forwarding state,
+/// log bit, VO bit and pinning bit, and the states of the chunk, block and line that contain the
+/// object, depending on the policy of its space. The result has one item per line. This is
+/// intended for crash triage, e.g. from a debugger or from [`crate::vm::ObjectModel::dump_object`].
+///
+/// Note that the GC may change the metadata concurrently, so the description may be inconsistent
+/// if this is called during GC.
+///
+/// Arguments:
+/// * `mmtk`: A reference to an MMTk instance.
+/// * `object`: The object reference to describe.
+pub fn describe_object<VM: VMBinding>(mmtk: &MMTK<VM>, object: ObjectReference) -> String {
+    crate::util::metadata::describe::describe_object(mmtk, object)
+}
+
 /// Is the address in the mapped memory? The runtime can use this function to check
 /// if an address is mapped by MMTk. Note that this is different than is_in_mmtk_spaces().
 /// For malloc spaces, MMTk does not map those addresses (malloc does the mmap), so
@@ -747,12 +1531,112 @@ pub fn harness_begin<VM: VMBinding>(mmtk: &MMTK<VM>, tls: VMMutatorThread) {
 /// Generic hook to allow benchmarks to be harnessed. We stop collecting
 /// statistics, and print stats values.
 ///
+/// A harness that runs multiple workloads, or needs the statistics of nested measurement
+/// windows as data, should use [`MMTK::harness`] instead.
+///
 /// Arguments:
 /// * `mmtk`: A reference to an MMTk instance.
 pub fn harness_end<VM: VMBinding>(mmtk: &'static MMTK<VM>) {
     mmtk.harness_end();
 }
 
+/// Enable or disable collecting statistics at run time. This allows a runtime to collect
+/// detailed statistics for a window of time. Statistics collected in different windows
+/// are accumulated, and printed at [`harness_end`]. Unlike [`harness_begin`], this does
+/// not trigger a GC.
+///
+/// This should be called by a mutator thread when GC is not in progress, so that the GC
+/// phases are correctly accounted for.
+///
+/// Arguments:
+/// * `mmtk`: A reference to an MMTk instance.
+/// * `enabled`: Whether statistics should be collected.
+pub fn set_stats_enabled<VM: VMBinding>(mmtk: &MMTK<VM>, enabled: bool) {
+    mmtk.set_stats_enabled(enabled);
+}
+
+/// Enable or disable all the analysis routines at run time, including the routines added by
+/// [`add_analysis_routine`].
+///
+/// Arguments:
+/// * `mmtk`: A reference to an MMTk instance.
+/// * `enabled`: Whether the analysis routines should run.
+#[cfg(feature = "analysis")]
+pub fn set_analysis_enabled<VM: VMBinding>(mmtk: &MMTK<VM>, enabled: bool) {
+    mmtk.analysis_manager.set_running(enabled);
+}
+
+/// Request MMTk to verify the heap at the end of the next GC. MMTk will walk the heap, and check that
+/// every reference field of a reachable object refers to a live object with its VO bit set in an MMTk space.
+/// MMTk panics if the verification fails, after reporting each bad slot and the object that holds it.
+/// Set the option `verify_heap` to verify the heap at the end of every GC.
+///
+/// Arguments:
+/// * `mmtk`: A reference to an MMTk instance.
+#[cfg(feature = "heap_verifier")]
+pub fn request_heap_verification<VM: VMBinding>(mmtk: &MMTK<VM>) {
+    mmtk.state
+        .heap_verification_requested
+        .store(true, std::sync::atomic::Ordering::SeqCst);
+}
+
+/// Register a kind of objects that the VM allocates outside MMTk spaces, but that participate in
+/// tracing. See [`crate::vm::OffHeapObjects`] for how MMTk traces them. A binding should register
+/// all the kinds before the first GC.
+///
+/// Arguments:
+/// * `mmtk`: A reference to an MMTk instance.
+/// * `kind`: The kind of off-heap objects.
+pub fn register_off_heap_objects<VM: VMBinding>(
+    mmtk: &MMTK<VM>,
+    kind: Box<dyn crate::vm::OffHeapObjects<VM>>,
+) {
+    mmtk.off_heap_objects.register(kind, &mmtk.stats);
+}
+
+/// Register the deduplication of object values in GC. See [`crate::vm::Deduplication`]. A binding can only register
+/// it once, and should register it before the first GC.
+///
+/// Arguments:
+/// * `mmtk`: A reference to an MMTk instance.
+/// * `deduplication`: The binding's implementation of deduplication.
+pub fn register_deduplication<VM: VMBinding>(
+    mmtk: &MMTK<VM>,
+    deduplication: Box<dyn crate::vm::Deduplication<VM>>,
+) {
+    mmtk.deduplication.register(deduplication);
+}
+
+/// Add an object whose value may be deduplicated in the next GC, for example, a newly created string. The object must
+/// have its value when it is added. The candidate is buffered in the mutator until the mutator is stopped for the next
+/// GC, so the candidates added by a mutator that is destroyed before the GC are not processed. See
+/// [`crate::vm::Deduplication`].
+///
+/// Arguments:
+/// * `mutator`: A reference to the mutator that adds the candidate.
+/// * `object`: The candidate object.
+pub fn add_deduplication_candidate<VM: VMBinding>(
+    mutator: &mut Mutator<VM>,
+    object: ObjectReference,
+) {
+    mutator.deduplication_candidates.push(object);
+}
+
+/// Invalidate the cached slots of a category of stable roots, so that MMTk calls
+/// [`crate::vm::Scanning::scan_stable_roots`] for the category again in the next GC. A binding should call this
+/// when slots are added to or removed from the category. It must not be called during a GC.
+///
+/// Arguments:
+/// * `mmtk`: A reference to an MMTk instance.
+/// * `category`: The category of stable roots.
+pub fn invalidate_stable_roots<VM: VMBinding>(mmtk: &MMTK<VM>, category: usize) {
+    debug_assert!(
+        !mmtk.gc_in_progress(),
+        "Stable roots are invalidated during GC"
+    );
+    mmtk.stable_roots.invalidate(category);
+}
+
 /// Register a finalizable object. MMTk will retain the liveness of
 /// the object even if it is not reachable from the program.
 /// Note that finalization upon exit is not supported.
@@ -1,31 +1,50 @@
 //! MMTk instance.
 use crate::global_state::{GcStatus, GlobalState};
 use crate::plan::gc_requester::GCRequester;
+#[cfg(feature = "vo_bit")]
+use crate::plan::space_targeted::SpaceSet;
+use crate::plan::AllocationSemantics;
 use crate::plan::CreateGeneralPlanArgs;
 use crate::plan::Plan;
+use crate::plan::SlotFilterCounters;
+use crate::policy::immix::ImmixSpace;
 use crate::policy::sft_map::{create_sft_map, SFTMap};
+use crate::scheduler::GCPhaseTimes;
 use crate::scheduler::GCWorkScheduler;
+use crate::scheduler::Handshake;
 
 #[cfg(feature = "vo_bit")]
 use crate::util::address::ObjectReference;
+use crate::util::alloc::AllocatorSelector;
 #[cfg(feature = "analysis")]
 use crate::util::analysis::AnalysisManager;
+use crate::util::copy::{CopyAccounting, CopyStats, RelocationLog};
+use crate::util::deduplication::{DeduplicationStats, DeduplicationTable};
 use crate::util::finalizable_processor::FinalizableProcessor;
+use crate::util::handle_table::HandleTable;
+use crate::util::harness::{Harness, HarnessWindows, DEFAULT_WINDOW};
 use crate::util::heap::gc_trigger::GCTrigger;
 use crate::util::heap::layout::heap_parameters::MAX_SPACES;
-use crate::util::heap::layout::vm_layout::VMLayout;
+use crate::util::heap::layout::vm_layout::{vm_layout, VMLayout};
 use crate::util::heap::layout::{self, Mmapper, VMMap};
 use crate::util::heap::HeapMeta;
+use crate::util::memory::{MmapAnnotation, MmapProtection, MmapStrategy};
+use crate::util::off_heap_objects::OffHeapObjectRegistry;
 use crate::util::opaque_pointer::*;
-use crate::util::options::Options;
+use crate::util::options::{GCTriggerSelector, Options};
+use crate::util::pinned_root_batches::{PinnedRootBatches, PinnedRootsStats};
 use crate::util::reference_processor::ReferenceProcessors;
 #[cfg(feature = "sanity")]
 use crate::util::sanity::sanity_checker::SanityChecker;
 #[cfg(feature = "extreme_assertions")]
 use crate::util::slot_logger::SlotLogger;
+use crate::util::stable_roots::StableRoots;
 use crate::util::statistics::stats::Stats;
+use crate::util::weak_processing_stats::{WeakProcessingAccounting, WeakProcessingStats};
+use crate::util::weak_slot_processor::WeakSlotProcessor;
 use crate::vm::ReferenceGlue;
 use crate::vm::VMBinding;
+use enum_map::EnumMap;
 use std::cell::UnsafeCell;
 use std::collections::HashMap;
 use std::default::Default;
@@ -34,19 +53,20 @@ use std::sync::Arc;
 use std::sync::Mutex;
 
 lazy_static! {
-    // I am not sure if we should include these mmappers as part of MMTk struct.
-    // The considerations are:
-    // 1. We need VMMap and Mmapper to create spaces. It is natural that the mappers are not
-    //    part of MMTK, as creating MMTK requires these mappers. We could use Rc/Arc for these mappers though.
-    // 2. These mmappers are possibly global across multiple MMTk instances, as they manage the
-    //    entire address space.
-    // TODO: We should refactor this when we know more about how multiple MMTK instances work.
+    // The VMMap, the Mmapper, the SFT map and the side metadata are indexed by address, and manage
+    // the entire address space of the process. They are shared by all the MMTk instances in the
+    // process. Each MMTk instance reserves disjoint address ranges for its spaces from `HEAP_META`,
+    // so different instances never use the same entries in those tables.
 
     /// A global VMMap that manages the mapping of spaces to virtual memory ranges.
     pub static ref VM_MAP: Box<dyn VMMap + Send + Sync> = layout::create_vm_map();
 
     /// A global Mmapper for mmaping and protection of virtual memory.
     pub static ref MMAPPER: Box<dyn Mmapper + Send + Sync> = layout::create_mmapper();
+
+    /// The heap range that has not been reserved by any space of any MMTk instance. An MMTk
+    /// instance holds the lock while creating its spaces, so MMTk instances are created one at a time.
+    pub(crate) static ref HEAP_META: Mutex<HeapMeta> = Mutex::new(HeapMeta::new());
 }
 
 use crate::util::rust_util::InitializeOnce;
@@ -54,10 +74,16 @@ use crate::util::rust_util::InitializeOnce;
 // A global space function table that allows efficient dispatch space specific code for addresses in our heap.
 pub static SFT_MAP: InitializeOnce<Box<dyn SFTMap>> = InitializeOnce::new();
 
+// Whether the quarantined address ranges in `MMAPPER` are released for a checkpoint. `MMAPPER` is
+// shared by all MMTk instances, so we only release and re-quarantine the ranges once.
+static QUARANTINE_RELEASED: AtomicBool = AtomicBool::new(false);
+
 /// MMTk builder. This is used to set options and other settings before actually creating an MMTk instance.
 pub struct MMTKBuilder {
     /// The options for this instance.
     pub options: Options,
+    /// The allocation semantics that use the allocator of another semantics.
+    allocation_routes: EnumMap<AllocationSemantics, Option<AllocationSemantics>>,
 }
 
 impl MMTKBuilder {
@@ -74,6 +100,7 @@ impl MMTKBuilder {
     pub fn new_no_env_vars() -> Self {
         MMTKBuilder {
             options: Options::default(),
+            allocation_routes: EnumMap::default(),
         }
     }
 
@@ -89,14 +116,34 @@ impl MMTKBuilder {
     }
 
     /// Custom VM layout constants. VM bindings may use this function for compressed or 39-bit heap support.
+    /// For compressed pointers, use [`VMLayout::new_compressed`] to let MMTk place the heap.
     /// This function must be called before MMTk::new()
     pub fn set_vm_layout(&mut self, constants: VMLayout) {
         VMLayout::set_custom_vm_layout(constants)
     }
 
+    /// Route the allocation semantics `semantics` to the allocator (and the space) that the plan
+    /// uses for `target`, instead of the allocator that the plan uses for `semantics` by default.
+    /// For example, routing `Code` to `NonMoving` allocates code objects in the non-moving space,
+    /// and routing `Immortal` to `Los` allocates immortal objects in the large object space.
+    ///
+    /// The routes are validated against the selected plan when the MMTk instance is built, and
+    /// [`MMTKBuilder::build`] panics if a route is not valid for the plan.  `Default` cannot be
+    /// routed, a semantics that the plan allocates with a large object allocator can only be routed
+    /// to another large object allocator, and a semantics cannot be routed to the allocator of
+    /// `Default` if the plan moves objects.  Routes are not transitive: `target` always resolves to
+    /// the allocator the plan uses for it.
+    pub fn set_allocation_route(
+        &mut self,
+        semantics: AllocationSemantics,
+        target: AllocationSemantics,
+    ) {
+        self.allocation_routes[semantics] = Some(target);
+    }
+
     /// Build an MMTk instance from the builder.
     pub fn build<VM: VMBinding>(&self) -> MMTK<VM> {
-        MMTK::new(Arc::new(self.options.clone()))
+        MMTK::new(Arc::new(self.options.clone()), &self.allocation_routes)
     }
 }
 
@@ -107,7 +154,28 @@ impl Default for MMTKBuilder {
 }
 
 /// An MMTk instance. MMTk allows multiple instances to run independently, and each instance gives users a separate heap.
-/// *Note that multi-instances is not fully supported yet*
+///
+/// The spaces of all the instances in a process are reserved from the same heap range with disjoint
+/// address ranges, so the total number of spaces of all the instances cannot exceed
+/// [`MAX_SPACES`](crate::util::heap::layout::heap_parameters::MAX_SPACES). Functions in
+/// [`crate::memory_manager`] that do not take an MMTk instance as an argument (such as
+/// `is_in_mmtk_spaces` and `is_mmtk_object`) answer for objects of any instance. The address ranges of
+/// an instance are not reused after the instance is dropped.
+///
+/// Multiple instances are not supported if the spaces are not contiguous (32-bit targets, or a
+/// custom [`VMLayout`] such as the one for compressed pointers), or if the feature "nogc_lock_free"
+/// is enabled.
+///
+/// Not all the state is per instance. The instances share the tables indexed by address (the VM
+/// map, the mmapper, the SFT map and the side metadata), which is why their address ranges have
+/// to be disjoint. The following state is also process-global, and is shared (or only usable) by
+/// all the instances in a process:
+/// * The C API of the feature "ffi", which manages a single instance.
+/// * The object lifetime statistics of the feature "analysis".
+/// * The quarantined memory released by [`MMTK::prepare_to_checkpoint`], which is tracked by the
+///   mmapper.
+/// * The heap file directory (the option `heap_file_dir`), which is set by the first instance.
+/// * The huge page statistics returned by [`crate::util::memory::get_huge_page_stats`].
 pub struct MMTK<VM: VMBinding> {
     pub(crate) options: Arc<Options>,
     pub(crate) state: Arc<GlobalState>,
@@ -115,15 +183,36 @@ pub struct MMTK<VM: VMBinding> {
     pub(crate) reference_processors: ReferenceProcessors,
     pub(crate) finalizable_processor:
         Mutex<FinalizableProcessor<<VM::VMReferenceGlue as ReferenceGlue<VM>>::FinalizableType>>,
+    pub(crate) weak_slot_processor: WeakSlotProcessor<VM::VMSlot>,
+    pub(crate) off_heap_objects: OffHeapObjectRegistry<VM>,
+    pub(crate) stable_roots: StableRoots<VM>,
+    pub(crate) pinned_root_batches: PinnedRootBatches<VM>,
+    pub(crate) copy_accounting: CopyAccounting,
+    pub(crate) relocation_log: RelocationLog,
+    pub(crate) weak_processing_accounting: WeakProcessingAccounting,
+    pub(crate) handle_table: HandleTable,
+    pub(crate) deduplication: DeduplicationTable<VM>,
+    #[cfg(feature = "object_id")]
+    pub(crate) object_ids: crate::util::object_id::ObjectIdTable,
+    #[cfg(feature = "object_user_data")]
+    pub(crate) object_user_data: crate::util::object_user_data::ObjectUserDataTable,
+    /// The counters of the slot filters of GC workers.  `None` if the slot filters are not used.
+    pub(crate) slot_filter_counters: Option<SlotFilterCounters>,
+    pub(crate) handshake: Arc<Handshake<VM>>,
     pub(crate) scheduler: Arc<GCWorkScheduler<VM>>,
     #[cfg(feature = "sanity")]
     pub(crate) sanity_checker: Mutex<SanityChecker<VM::VMSlot>>,
+    /// A copy of the heap at the start of the current GC, for verifying copying.
+    #[cfg(feature = "shadow_heap")]
+    pub(crate) shadow_heap: Mutex<crate::util::shadow_heap::ShadowHeap>,
     #[cfg(feature = "extreme_assertions")]
     pub(crate) slot_logger: SlotLogger<VM::VMSlot>,
     pub(crate) gc_trigger: Arc<GCTrigger<VM>>,
     pub(crate) gc_requester: Arc<GCRequester<VM>>,
     pub(crate) stats: Arc<Stats>,
-    inside_harness: AtomicBool,
+    pub(crate) harness_windows: HarnessWindows,
+    /// The allocator mapping of the plan, with the allocation routes set by the binding.
+    pub(crate) allocator_mapping: &'static EnumMap<AllocationSemantics, AllocatorSelector>,
     #[cfg(feature = "sanity")]
     inside_sanity: AtomicBool,
     /// Analysis counters. The feature analysis allows us to periodically stop the world and collect some statistics.
@@ -136,19 +225,51 @@ unsafe impl<VM: VMBinding> Send for MMTK<VM> {}
 
 impl<VM: VMBinding> MMTK<VM> {
     /// Create an MMTK instance. This is not public. Bindings should use [`MMTKBuilder::build`].
-    pub(crate) fn new(options: Arc<Options>) -> Self {
+    pub(crate) fn new(
+        options: Arc<Options>,
+        allocation_routes: &EnumMap<AllocationSemantics, Option<AllocationSemantics>>,
+    ) -> Self {
         // Initialize SFT first in case we need to use this in the constructor.
         // The first call will initialize SFT map. Other calls will be blocked until SFT map is initialized.
         crate::policy::sft_map::SFTRefStorage::pre_use_check();
         SFT_MAP.initialize_once(&create_sft_map);
 
-        let num_workers = if cfg!(feature = "single_worker") {
+        let mut options = options;
+        if *options.deterministic_gc {
+            // Work done concurrently with mutators, or depending on how long mutators have been
+            // idle, makes GC depend on the timing of the mutators.
+            let options = Arc::make_mut(&mut options);
+            if *options.concurrent_sweeping
+                || *options.concurrent_defrag_preparation
+                || *options.reclaim_idle_mutator_blocks
+                || *options.idle_mutator_flush_timeout != 0
+            {
+                warn!("deterministic_gc is set. Concurrent sweeping, concurrent defrag preparation and idle mutator flushing are disabled.");
+            }
+            options.concurrent_sweeping.set(false);
+            options.concurrent_defrag_preparation.set(false);
+            options.reclaim_idle_mutator_blocks.set(false);
+            options.idle_mutator_flush_timeout.set(0);
+        }
+
+        let num_workers = if cfg!(feature = "single_worker") || *options.deterministic_gc {
             1
         } else {
             *options.threads
         };
 
-        let scheduler = GCWorkScheduler::new(num_workers, (*options.thread_affinity).clone());
+        if *options.deterministic_gc
+            && !matches!(*options.gc_trigger, GCTriggerSelector::FixedHeapSize(_))
+        {
+            warn!("deterministic_gc is set, but the heap size is not fixed. GC may not be deterministic.");
+        }
+
+        let scheduler = GCWorkScheduler::new(
+            num_workers,
+            (*options.thread_affinity).clone(),
+            *options.tracing_queue_budget,
+            *options.tracing_order,
+        );
 
         let state = Arc::new(GlobalState::default());
 
@@ -160,11 +281,26 @@ impl<VM: VMBinding> MMTK<VM> {
             state.clone(),
         ));
 
+        let handshake = Arc::new(Handshake::new());
+
         let stats = Arc::new(Stats::new(&options));
 
+        crate::util::heap::space_index::initialize();
+        if !options.heap_file_dir.is_empty() {
+            crate::util::heap_file::init(&options.heap_file_dir)
+                .unwrap_or_else(|e| panic!("Failed to create the heap file directory: {e}"));
+        }
+
         // We need this during creating spaces, but we do not use this once the MMTk instance is created.
-        // So we do not save it in MMTK. This may change in the future.
-        let mut heap = HeapMeta::new();
+        // It is shared by all the MMTk instances, and we hold the lock until the spaces are created
+        // and the space map is finalized.
+        let mut heap = HEAP_META.lock().unwrap();
+        // `Map32` hands out the rest of the heap range to discontiguous spaces when the first
+        // instance finalizes the space map, so there is no heap range left for other instances.
+        assert!(
+            !VM_MAP.is_finalized() || vm_layout().force_use_contiguous_spaces,
+            "Multiple MMTk instances are only supported with contiguous spaces (the default VM layout on 64-bit targets)"
+        );
 
         let mut plan = crate::plan::create_plan(
             *options.plan,
@@ -175,8 +311,9 @@ impl<VM: VMBinding> MMTK<VM> {
                 state: state.clone(),
                 gc_trigger: gc_trigger.clone(),
                 scheduler: scheduler.clone(),
+                handshake: handshake.clone(),
                 stats: &stats,
-                heap: &mut heap,
+                heap: &mut *heap,
             },
         );
 
@@ -191,7 +328,6 @@ impl<VM: VMBinding> MMTK<VM> {
             gc_trigger.set_plan(static_plan);
         }
 
-        // TODO: This probably does not work if we have multiple MMTk instances.
         // This needs to be called after we create Plan. It needs to use HeapMeta, which is gradually built when we create spaces.
         VM_MAP.finalize_static_space_map(
             heap.get_discontig_start(),
@@ -207,6 +343,35 @@ impl<VM: VMBinding> MMTK<VM> {
             },
         );
 
+        crate::util::heap_file::write_index()
+            .unwrap_or_else(|e| panic!("Failed to write the heap file index: {e}"));
+
+        // The spaces are created.  Release the lock before validating the allocation routes, so
+        // an invalid route does not poison the lock for other instances.
+        drop(heap);
+        let allocator_mapping = if allocation_routes.values().all(Option::is_none) {
+            plan.get_allocator_mapping()
+        } else {
+            let map = crate::plan::route_allocator_mapping(&*plan, allocation_routes)
+                .unwrap_or_else(|e| panic!("Invalid allocation route: {e}"));
+            // `MutatorConfig` refers to the mapping with a static reference.
+            Box::leak(Box::new(map))
+        };
+
+        if cfg!(debug_assertions) {
+            let layout = crate::util::heap_layout::describe(&*plan);
+            if let Err(e) = layout.validate() {
+                panic!("Invalid heap layout:\n{e}\nThe heap layout:\n{layout}");
+            }
+        }
+
+        let slot_filter_counters = (*options.deduplicate_slots
+            && plan.constraints().may_trace_duplicate_edges)
+            .then(|| SlotFilterCounters::new(&stats));
+
+        #[cfg(feature = "analysis")]
+        let analysis_manager = Arc::new(AnalysisManager::new(stats.clone(), &options, &state));
+
         MMTK {
             options,
             state,
@@ -215,16 +380,34 @@ impl<VM: VMBinding> MMTK<VM> {
             finalizable_processor: Mutex::new(FinalizableProcessor::<
                 <VM::VMReferenceGlue as ReferenceGlue<VM>>::FinalizableType,
             >::new()),
+            weak_slot_processor: WeakSlotProcessor::new(),
+            off_heap_objects: OffHeapObjectRegistry::new(),
+            stable_roots: StableRoots::new(),
+            pinned_root_batches: PinnedRootBatches::new(&stats),
+            copy_accounting: CopyAccounting::new(&stats),
+            relocation_log: RelocationLog::default(),
+            weak_processing_accounting: WeakProcessingAccounting::new(&stats),
+            handle_table: HandleTable::default(),
+            deduplication: DeduplicationTable::new(&stats),
+            #[cfg(feature = "object_id")]
+            object_ids: Default::default(),
+            #[cfg(feature = "object_user_data")]
+            object_user_data: Default::default(),
+            slot_filter_counters,
+            handshake,
             scheduler,
             #[cfg(feature = "sanity")]
             sanity_checker: Mutex::new(SanityChecker::new()),
+            #[cfg(feature = "shadow_heap")]
+            shadow_heap: Mutex::new(Default::default()),
             #[cfg(feature = "sanity")]
             inside_sanity: AtomicBool::new(false),
-            inside_harness: AtomicBool::new(false),
+            harness_windows: HarnessWindows::default(),
+            allocator_mapping,
             #[cfg(feature = "extreme_assertions")]
             slot_logger: SlotLogger::new(),
             #[cfg(feature = "analysis")]
-            analysis_manager: Arc::new(AnalysisManager::new(stats.clone())),
+            analysis_manager,
             gc_trigger,
             gc_requester,
             stats,
@@ -276,6 +459,17 @@ impl<VM: VMBinding> MMTK<VM> {
     /// re-spawn the threads using their saved contexts.  The VM must not allocate objects in the
     /// MMTk heap before calling `MMTK::after_fork()`.
     ///
+    /// This function may be called while a GC is in progress, for example, from a thread that is
+    /// not blocked for GC, or while mutators are blocked for an emergency GC.  In that case, GC
+    /// workers finish the work packets they are executing, and exit without taking more packets.
+    /// The remaining packets and the state of the scheduler are kept, and the GC is resumed after
+    /// `MMTK::after_fork()` respawns the GC workers, in both the parent and the child process.
+    /// Mutators that are blocked in [`crate::vm::Collection::block_for_gc`] stay blocked until the
+    /// resumed GC finishes.  In the child process, only the thread that called `fork()` exists,
+    /// and the VM is responsible for handling the mutators (and their threads) that were stopped
+    /// for the GC, as MMTk will call [`crate::vm::Collection::resume_mutators`] when the GC
+    /// finishes.
+    ///
     /// TODO: Currently, the MMTk core does not keep any files open for a long time.  In the
     /// future, this function and the `after_fork` function may be used for handling open file
     /// descriptors across invocations of `fork()`.  One possible use case is logging GC activities
@@ -302,7 +496,8 @@ impl<VM: VMBinding> MMTK<VM> {
 
     /// Call this function after the VM called the `fork()` system call.
     ///
-    /// This function will re-spawn MMTk threads from saved contexts.
+    /// This function will re-spawn MMTk threads from saved contexts.  If the threads stopped in
+    /// the middle of a GC, they will resume the GC.
     ///
     /// # Arguments
     ///
@@ -318,26 +513,180 @@ impl<VM: VMBinding> MMTK<VM> {
         self.scheduler.respawn_gc_threads_after_forking(tls);
     }
 
+    /// Pause the GC threads.  This asks all GC worker threads to exit in the same way as
+    /// `MMTK::prepare_to_fork()`, and blocks until all GC workers have exited.  The underlying
+    /// native threads may still be running for a short while after this function returns.  A
+    /// subsequent call to `MMTK::resume_gc_threads()` will re-spawn the GC threads.
+    ///
+    /// If a GC is in progress, it is suspended, and the mutators stay blocked for the GC until
+    /// the GC threads are resumed.  This function must not be called by a GC thread.
+    pub fn pause_gc_threads(&'static self) {
+        assert!(
+            self.state.is_initialized(),
+            "MMTk collection has not been initialized, yet (was initialize_collection() called before?)"
+        );
+        probe!(mmtk, pause_gc_threads);
+        self.scheduler.stop_gc_threads_for_forking();
+        self.scheduler.wait_for_gc_threads_to_exit();
+    }
+
+    /// Re-spawn the GC threads paused by `MMTK::pause_gc_threads()`.  If the threads were paused
+    /// in the middle of a GC, they will resume the GC.
+    ///
+    /// # Arguments
+    ///
+    /// *   `tls`: The thread that wants to respawn MMTk threads. This value will be passed back
+    ///     to the VM in `Collection::spawn_gc_thread()` so that the VM knows the context.
+    pub fn resume_gc_threads(&'static self, tls: VMThread) {
+        assert!(
+            self.state.is_initialized(),
+            "MMTk collection has not been initialized, yet (was initialize_collection() called before?)"
+        );
+        probe!(mmtk, resume_gc_threads);
+        self.scheduler.respawn_gc_threads_after_forking(tls);
+    }
+
+    /// Shut down the GC threads of this MMTk instance, so that the VM can unload the runtime from
+    /// the host process without leaking threads.  This stops the GC threads (if they are not
+    /// paused by `MMTK::pause_gc_threads()`), waits until all GC workers have exited, and
+    /// releases the resources of the GC workers.  The underlying native threads may still be
+    /// running for a short while after this function returns, and the VM should wait for them to
+    /// exit in a VM-specific manner if needed.
+    ///
+    /// After this call, MMTk no longer triggers GC, and the GC threads cannot be started again.
+    /// The VM should not allocate objects in the MMTk heap afterwards.  This function must not
+    /// be called during a GC or by a GC thread.
+    pub fn shutdown(&'static self) {
+        assert!(
+            self.state.is_initialized(),
+            "MMTk collection has not been initialized, yet (was initialize_collection() called before?)"
+        );
+        assert!(
+            !self.gc_in_progress(),
+            "MMTk cannot be shut down during a GC"
+        );
+        probe!(mmtk, shutdown);
+        if self.scheduler.are_gc_threads_running() {
+            self.scheduler.stop_gc_threads_for_forking();
+        }
+        self.scheduler.wait_for_gc_threads_to_exit();
+        self.scheduler.discard_gc_workers();
+        self.state.initialized.store(false, Ordering::SeqCst);
+    }
+
+    /// Prepare MMTk for checkpointing the process, for example, with CRIU (Checkpoint/Restore In
+    /// Userspace).  A subsequent call to `MMTK::after_restore()` will re-establish the state of
+    /// MMTk in the restored process, or in the original process if it continues running after the
+    /// checkpoint.
+    ///
+    /// This function does the following:
+    ///
+    /// -   It stops GC threads in the same way as `MMTK::prepare_to_fork()`.  The same caution
+    ///     applies: the VM should wait for the underlying native threads of the GC threads to
+    ///     exit before taking the checkpoint.
+    /// -   It unmaps the address ranges that MMTk reserved with `PROT_NONE` but has not used
+    ///     (quarantined memory), for both spaces and side metadata, so that the checkpoint does
+    ///     not contain those (potentially very large) mappings.  MMTk still records those
+    ///     ranges, and will reserve them again at the same addresses on restore.
+    ///
+    /// The memory that MMTk has mapped, including lazily mapped side metadata, is recorded in
+    /// the same global tables as the quarantined memory, and the checkpoint contains it at its
+    /// original addresses.  It is restored as is, and those tables remain valid after restore.
+    ///
+    /// The quarantined ranges are shared by all MMTk instances in the process, and are released
+    /// by the first instance that calls this function.  The VM must not allocate objects in the
+    /// MMTk heap before calling `MMTK::after_restore()`.
+    pub fn prepare_to_checkpoint(&'static self) -> std::io::Result<()> {
+        assert!(
+            self.state.is_initialized(),
+            "MMTk collection has not been initialized, yet (was initialize_collection() called before?)"
+        );
+        probe!(mmtk, prepare_to_checkpoint);
+        self.scheduler.stop_gc_threads_for_forking();
+        if !QUARANTINE_RELEASED.swap(true, Ordering::SeqCst) {
+            MMAPPER.release_quarantined_ranges()?;
+        }
+        Ok(())
+    }
+
+    /// Call this function after the process is restored from a checkpoint taken after
+    /// `MMTK::prepare_to_checkpoint()`, or if the process continues running after the checkpoint.
+    ///
+    /// This function reserves the released quarantined memory again at its original addresses,
+    /// and re-spawns MMTk threads from saved contexts.  It returns an error if the original
+    /// address ranges cannot be reserved, for example, if something else in the restored process
+    /// has mapped memory there.
+    ///
+    /// # Arguments
+    ///
+    /// *   `tls`: The thread that wants to respawn MMTk threads after restoring. This value will be
+    ///     passed back to the VM in `Collection::spawn_gc_thread()` so that the VM knows the
+    ///     context.
+    pub fn after_restore(&'static self, tls: VMThread) -> std::io::Result<()> {
+        assert!(
+            self.state.is_initialized(),
+            "MMTk collection has not been initialized, yet (was initialize_collection() called before?)"
+        );
+        probe!(mmtk, after_restore);
+        if QUARANTINE_RELEASED.swap(false, Ordering::SeqCst) {
+            let strategy = MmapStrategy::new(
+                *self.options.transparent_hugepages,
+                MmapProtection::NoAccess,
+            );
+            let anno = MmapAnnotation::Misc {
+                name: "restored-quarantine",
+            };
+            MMAPPER.requarantine_released_ranges(strategy, &anno)?;
+        }
+        self.scheduler.respawn_gc_threads_after_forking(tls);
+        Ok(())
+    }
+
     /// Generic hook to allow benchmarks to be harnessed. MMTk will trigger a GC
     /// to clear any residual garbage and start collecting statistics for the benchmark.
     /// This is usually called by the benchmark harness as its last step before the actual benchmark.
+    ///
+    /// This opens the measurement window [`crate::util::harness::DEFAULT_WINDOW`].  Use
+    /// [`MMTK::harness`] for multiple or nested windows.
     pub fn harness_begin(&self, tls: VMMutatorThread) {
         probe!(mmtk, harness_begin);
-        self.handle_user_collection_request(tls, true, true);
-        self.inside_harness.store(true, Ordering::SeqCst);
-        self.stats.start_all();
-        self.scheduler.enable_stat();
+        let harness = self.harness();
+        harness.collect(tls);
+        harness.begin(DEFAULT_WINDOW);
     }
 
     /// Generic hook to allow benchmarks to be harnessed. MMTk will stop collecting
     /// statistics, and print out the collected statistics in a defined format.
     /// This is usually called by the benchmark harness right after the actual benchmark.
+    ///
+    /// This closes the measurement window [`crate::util::harness::DEFAULT_WINDOW`].  MMTk keeps
+    /// gathering statistics if other windows are still open.  Use [`MMTK::harness`] to get the
+    /// statistics of a window as a [`crate::util::harness::HarnessStats`].
     pub fn harness_end(&'static self) {
-        self.stats.stop_all(self);
-        self.inside_harness.store(false, Ordering::SeqCst);
+        self.harness().end(DEFAULT_WINDOW);
+        self.stats.print_stats(self);
         probe!(mmtk, harness_end);
     }
 
+    /// Get a handle to the measurement windows of this MMTk instance.  A benchmark harness can
+    /// open and close multiple named windows, possibly nested, and get the statistics of each
+    /// window.  See [`crate::util::harness`].
+    pub fn harness(&self) -> Harness<VM> {
+        Harness::new(self)
+    }
+
+    /// Enable or disable collecting statistics at run time. Unlike `harness_begin` and
+    /// `harness_end`, this does not trigger a GC or print the statistics, and statistics
+    /// collected in different enabled windows are accumulated.
+    ///
+    /// This can be called in any order with `harness_begin` and `harness_end`.  Opening a window
+    /// while statistics are enabled keeps gathering them, and statistics enabled by this method
+    /// stay enabled when the last window is closed.  Disabling statistics while a window is open
+    /// also stops gathering statistics for that window.
+    pub fn set_stats_enabled(&self, enabled: bool) {
+        self.harness().set_stats_enabled(enabled);
+    }
+
     #[cfg(feature = "sanity")]
     pub(crate) fn sanity_begin(&self) {
         self.inside_sanity.store(true, Ordering::Relaxed)
@@ -374,6 +723,112 @@ impl<VM: VMBinding> MMTK<VM> {
         *self.state.gc_status.lock().unwrap() != GcStatus::NotInGC
     }
 
+    /// Return true if a GC has been requested but has not stopped the mutators, yet.  Work done
+    /// while mutators are idle should stop early if this returns true.
+    pub fn is_gc_requested(&self) -> bool {
+        self.gc_requester.is_requested()
+    }
+
+    /// Get a breakdown of the wall-clock time of the most recently finished GC, such as the time
+    /// of stopping the world, scanning roots, computing the transitive closure and processing weak
+    /// references.  Return `None` if no GC has finished yet.
+    ///
+    /// The phase times are always recorded, so bindings can export them without enabling the
+    /// statistics of the harness.  The returned value is updated right before mutators are resumed
+    /// at the end of each GC.
+    pub fn last_gc_phase_times(&self) -> Option<GCPhaseTimes> {
+        self.scheduler.last_gc_phase_times()
+    }
+
+    /// Get the bytes copied in the most recently finished GC, such as the bytes promoted from the
+    /// nursery to the mature space, and the survival rate of the nursery.  All fields are zero or
+    /// `None` if no GC has finished yet.
+    ///
+    /// This is updated before [`crate::util::heap::GCTriggerPolicy::on_gc_end`] is called, so
+    /// GC triggers (including those created by the binding) can use it for adaptive heap sizing.
+    pub fn last_gc_copy_stats(&self) -> CopyStats {
+        self.copy_accounting.last_gc()
+    }
+
+    /// Get what weak reference processing and finalization did in the most recently finished GC,
+    /// such as the number of references enqueued and objects made ready for finalization, and the
+    /// time of each weak reference processing stage.  All fields are zero if no GC has finished yet.
+    ///
+    /// The returned value is updated right before mutators are resumed at the end of each GC, so
+    /// the binding can read it in [`crate::vm::Collection::resume_mutators`].  The counts are also
+    /// added to the harness statistics.
+    pub fn last_gc_weak_processing_stats(&self) -> WeakProcessingStats {
+        self.weak_processing_accounting.last_gc()
+    }
+
+    /// Get how many roots were pinned by pinned root batches in the most recently finished GC,
+    /// and how many bytes of them could have been moved otherwise.  See
+    /// [`crate::vm::RootsWorkFactory::create_process_tpinning_roots_batch_work`].  All fields are
+    /// zero if no GC has finished yet.  The counts are also added to the harness statistics.
+    pub fn last_gc_pinned_roots_stats(&self) -> PinnedRootsStats {
+        self.pinned_root_batches.last_gc()
+    }
+
+    /// Get what deduplication did in the most recently finished GC, such as the number of
+    /// candidates redirected to canonical values.  See [`crate::vm::Deduplication`].  All fields
+    /// are zero if no GC has finished yet or the binding has not registered deduplication.  The
+    /// counts are also added to the harness statistics.
+    pub fn last_gc_deduplication_stats(&self) -> DeduplicationStats {
+        self.deduplication.last_gc()
+    }
+
+    /// Get the approximate live bytes of the Immix blocks swept in the most recently finished GC,
+    /// grouped by the number of holes (runs of free lines) in the block: the element at index
+    /// `i` is the live bytes of the blocks with `i` holes.  This is the histogram Immix uses to
+    /// select the blocks to defragment.  Return an empty vector if the plan has no Immix space.
+    pub fn last_gc_immix_live_bytes_histogram(&self) -> Vec<usize> {
+        let mut histogram = vec![];
+        self.get_plan().for_each_space(&mut |space| {
+            if let Some(immix) = space.downcast_ref::<ImmixSpace<VM>>() {
+                histogram = immix.live_bytes_histogram();
+            }
+        });
+        histogram
+    }
+
+    /// Get the number of Immix blocks (clean or reusable) that mutators acquired before the most
+    /// recent GC, since the GC before it.  With `StickyImmix`, this is the consumed part of the
+    /// budget set by the option `sticky_immix_nursery_blocks`.  Return 0 if the plan has no Immix
+    /// space.
+    pub fn last_gc_immix_mutator_blocks(&self) -> usize {
+        let mut blocks = 0;
+        self.get_plan().for_each_space(&mut |space| {
+            if let Some(immix) = space.downcast_ref::<ImmixSpace<VM>>() {
+                blocks = immix.last_gc_mutator_blocks();
+            }
+        });
+        blocks
+    }
+
+    /// Get the recent allocation rate of mutators in bytes per millisecond.  The rate is estimated
+    /// from the bytes allocated in the allocation slow path over a sliding window of the last
+    /// second (wall-clock time, including GC pauses).  Allocations in the fast path are counted
+    /// when the allocator gets a new thread-local buffer, so the rate is an approximation.
+    ///
+    /// GC triggers (including those created by the binding) can use it for heap sizing.  It is also
+    /// used to size the nursery with [`crate::util::options::NurserySize::AllocationRateBounded`].
+    pub fn get_allocation_rate(&self) -> f64 {
+        self.state.get_allocation_rate()
+    }
+
+    /// Get the total time spent with GC disabled by [`crate::memory_manager::gc_disabled_scope`],
+    /// including the current GC-disabled period if GC is disabled now.  Nested and overlapping
+    /// scopes are counted once.
+    pub fn gc_disabled_time(&self) -> std::time::Duration {
+        self.state.gc_disabled_time()
+    }
+
+    /// Get the number of times that MMTk would have triggered a GC, but did not because GC was
+    /// disabled by [`crate::memory_manager::gc_disabled_scope`].
+    pub fn gc_suppressed_polls(&self) -> usize {
+        self.state.gc_suppressed_polls()
+    }
+
     /// Return true if a collection is in progress and past the preparatory stage.
     pub fn gc_in_progress_proper(&self) -> bool {
         *self.state.gc_status.lock().unwrap() == GcStatus::GcProper
@@ -425,7 +880,11 @@ impl<VM: VMBinding> MMTK<VM> {
             return false;
         }
 
-        if force || !*self.options.ignore_system_gc && VM::VMCollection::is_collection_enabled() {
+        if force
+            || !*self.options.ignore_system_gc
+                && VM::VMCollection::is_collection_enabled()
+                && !self.state.is_gc_disabled()
+        {
             info!("User triggering collection");
             if exhaustive {
                 if let Some(gen) = self.get_plan().generational() {
@@ -437,13 +896,48 @@ impl<VM: VMBinding> MMTK<VM> {
                 .user_triggered_collection
                 .store(true, Ordering::Relaxed);
             self.gc_requester.request();
-            VM::VMCollection::block_for_gc(tls);
+            self.handshake.block_for_gc(tls);
             return true;
         }
 
         false
     }
 
+    /// The application code has requested a space-targeted collection of the spaces named
+    /// `spaces`.  See [`crate::memory_manager::handle_user_space_targeted_collection_request`].
+    #[cfg(feature = "vo_bit")]
+    pub fn handle_user_space_targeted_collection_request(
+        &self,
+        tls: VMMutatorThread,
+        spaces: &[&str],
+    ) -> bool {
+        let plan = self.get_plan();
+        let mut targets = SpaceSet::default();
+        for name in spaces {
+            let mut found = false;
+            plan.for_each_space(&mut |space| {
+                if space.get_name() == *name {
+                    targets.insert(space);
+                    found = true;
+                }
+            });
+            if !found || !plan.supports_space_targeted_collection(name) {
+                warn!(
+                    "The plan cannot collect the space {} alone. The request is ignored.",
+                    name
+                );
+                return false;
+            }
+        }
+
+        *self.state.requested_space_targets.lock().unwrap() = Some(targets);
+        let triggered = self.handle_user_collection_request(tls, false, false);
+        if !triggered {
+            *self.state.requested_space_targets.lock().unwrap() = None;
+        }
+        triggered
+    }
+
     /// MMTK has requested stop-the-world activity (e.g., stw within a concurrent gc).
     // This is not used, as we do not have a concurrent plan.
     #[allow(unused)]
@@ -529,6 +1023,74 @@ impl<VM: VMBinding> MMTK<VM> {
         })
     }
 
+    /// Enumerate the objects in a space or an address range.  It is like
+    /// [`MMTK::enumerate_objects`], but only visits the objects in the given scope, so tools can
+    /// inspect the nursery or a single block without visiting the entire heap.  The same
+    /// requirements about allocation and GC apply.
+    ///
+    /// Return false if the scope is a space, and the plan does not have a space of that name.
+    #[cfg(feature = "vo_bit")]
+    pub fn enumerate_objects_in<F>(&self, scope: crate::util::EnumerationScope, f: F) -> bool
+    where
+        F: FnMut(ObjectReference),
+    {
+        use crate::util::object_enum;
+
+        let mut enumerator = object_enum::ClosureObjectEnumerator::<_, VM>::new(f);
+        self.enumerate_objects_in_scope(scope, &mut enumerator)
+    }
+
+    /// Get work packets that enumerate the objects in a space or an address range in parallel.
+    /// Each work packet calls `f` for the objects in a part of the scope, such as a block.  The
+    /// binding may add the packets to a work bucket with [`crate::memory_manager::add_work_packets`],
+    /// for example, when it inspects the heap in a GC.  The spaces are only visited when this
+    /// function is called, so the packets must be executed before any object is allocated, moved
+    /// or reclaimed.  See [`MMTK::enumerate_objects`] for other requirements.
+    ///
+    /// Return `None` if the scope is a space, and the plan does not have a space of that name.
+    #[cfg(feature = "vo_bit")]
+    pub fn enumerate_objects_in_parallel<F>(
+        &self,
+        scope: crate::util::EnumerationScope,
+        f: F,
+    ) -> Option<Vec<Box<dyn crate::scheduler::GCWork<VM>>>>
+    where
+        F: Fn(ObjectReference) + Send + Sync + 'static,
+    {
+        use crate::util::object_enum;
+
+        let mut enumerator = object_enum::WorkPacketObjectEnumerator::<VM>::new(Arc::new(f));
+        self.enumerate_objects_in_scope(scope, &mut enumerator)
+            .then(|| enumerator.into_packets())
+    }
+
+    #[cfg(feature = "vo_bit")]
+    fn enumerate_objects_in_scope(
+        &self,
+        scope: crate::util::EnumerationScope,
+        enumerator: &mut dyn crate::util::object_enum::ObjectEnumerator,
+    ) -> bool {
+        use crate::util::object_enum::RegionObjectEnumerator;
+        use crate::util::EnumerationScope;
+
+        let mut found = false;
+        match scope {
+            EnumerationScope::Space(name) => self.get_plan().for_each_space(&mut |space| {
+                if space.get_name() == name {
+                    space.enumerate_objects(enumerator);
+                    found = true;
+                }
+            }),
+            EnumerationScope::Region(region) => {
+                let mut enumerator = RegionObjectEnumerator::new(enumerator, region);
+                self.get_plan()
+                    .for_each_space(&mut |space| space.enumerate_objects(&mut enumerator));
+                found = true;
+            }
+        }
+        found
+    }
+
     /// Aggregate a hash map of live bytes per space with the space stats to produce
     /// a map of live bytes stats for the spaces.
     pub(crate) fn aggregate_live_bytes_in_last_gc(
@@ -585,6 +1147,15 @@ impl<VM: VMBinding> MMTK<VM> {
         result_so_far
     }
 
+    /// Describe the heap layout, including the address ranges of all spaces, the side metadata for
+    /// the spaces, and the chunks reserved or mapped by the mmapper.  The result can be printed in a
+    /// machine-readable format, and can be checked for overlapping ranges with
+    /// [`crate::util::heap_layout::HeapLayout::validate`].  MMTk validates the layout in debug
+    /// builds when the instance is created.
+    pub fn dump_heap_layout(&self) -> crate::util::heap_layout::HeapLayout {
+        crate::util::heap_layout::describe(self.get_plan())
+    }
+
     /// Initialize object metadata for a VM space object.
     /// Objects in the VM space are allocated/managed by the binding. This function provides a way for
     /// the binding to set object metadata in MMTk for an object in the space.
@@ -31,6 +31,18 @@ impl BarrierSelector {
     }
 }
 
+/// What a compiler knows about the source object of a store when it decides whether to emit a
+/// write barrier.  See [`crate::memory_manager::barrier_required_for`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BarrierSourceHint {
+    /// Nothing is known about the source object.
+    Unknown,
+    /// The source object was allocated by the current mutator, and there is no GC safepoint
+    /// between the allocation and the store.  For example, the stores that initialize the fields
+    /// of a new object.
+    NewlyAllocated,
+}
+
 /// A barrier is a combination of fast-path behaviour + slow-path semantics.
 /// This trait exposes generic barrier interfaces. The implementations will define their
 /// own fast-path code and slow-path semantics.
@@ -114,6 +126,12 @@ pub trait Barrier<VM: VMBinding>: 'static + Send + Downcast {
     ///
     // TODO: Review any potential use cases for other VM bindings.
     fn object_probable_write(&mut self, _obj: ObjectReference) {}
+
+    /// Remember an object whose reference fields have been modified without a write barrier, so
+    /// that the next GC will trace the references stored in it.  Unlike `object_probable_write`,
+    /// this is called *after* the fields are modified.  Barriers that do not remember objects
+    /// ignore this.
+    fn remember_object(&mut self, _obj: ObjectReference) {}
 }
 
 impl_downcast!(Barrier<VM> where VM: VMBinding);
@@ -249,4 +267,11 @@ impl<S: BarrierSemantics> Barrier<S::VM> for ObjectBarrier<S> {
             self.semantics.object_probable_write_slow(obj);
         }
     }
+
+    fn remember_object(&mut self, obj: ObjectReference) {
+        // Log the object so that it is only remembered once until the next GC.
+        if self.object_is_unlogged(obj) && self.log_object(obj) {
+            self.semantics.object_probable_write_slow(obj);
+        }
+    }
 }
@@ -0,0 +1,114 @@
+//! The copy reserve of the semispace plans.
+//!
+//! A semispace plan must keep enough free pages to copy the surviving objects of the from-space
+//! into the to-space.  By default, it reserves as many pages as the objects allocated in the
+//! space, i.e. it assumes all objects may survive.  If the option `elastic_copy_reserve` is set,
+//! the reserve is sized by the highest survival rate of the recent GCs (with a safety margin), so
+//! that the pages not reserved can be used by other spaces when few objects survive.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// The number of recent GCs whose survival rates are considered.
+const HISTORY_LENGTH: usize = 4;
+/// The reserve is the highest recent survival rate multiplied by this factor.
+const SAFETY_MARGIN: f64 = 1.5;
+/// The lowest ratio of the reserve to the used pages.
+const MIN_RESERVE_RATIO: f64 = 0.1;
+
+pub(crate) struct CopyReserve {
+    /// Is the reserve sized by survival rates?  If not, the ratio is always 1.
+    elastic: bool,
+    /// The survival rates of the recent GCs, from the oldest to the newest.
+    recent_survival_rates: Mutex<VecDeque<f64>>,
+    /// The ratio of the reserve to the used pages, as returned by `f64::to_bits`.
+    ratio: AtomicU64,
+}
+
+impl CopyReserve {
+    pub fn new(elastic: bool) -> Self {
+        Self {
+            elastic,
+            recent_survival_rates: Mutex::new(VecDeque::with_capacity(HISTORY_LENGTH)),
+            ratio: AtomicU64::new(1.0f64.to_bits()),
+        }
+    }
+
+    /// Get the ratio of the reserve to the used pages.
+    pub fn ratio(&self) -> f64 {
+        f64::from_bits(self.ratio.load(Ordering::Relaxed))
+    }
+
+    /// Get the number of pages to reserve for copying the objects in `used_pages` pages.
+    pub fn reserved_pages(&self, used_pages: usize) -> usize {
+        (used_pages as f64 * self.ratio()).ceil() as usize
+    }
+
+    /// Get the number of pages that can be allocated from `free_pages` pages, so that enough
+    /// pages remain for copying the newly allocated objects.
+    pub fn available_pages(&self, free_pages: usize) -> usize {
+        (free_pages as f64 / (1.0 + self.ratio())) as usize
+    }
+
+    /// Record that `survived_pages` of the `collected_pages` pages survived a GC, and update the
+    /// ratio of the reserve.  This is called when the from-space is released.
+    pub fn record_survival(&self, survived_pages: usize, collected_pages: usize) {
+        if !self.elastic || collected_pages == 0 {
+            return;
+        }
+        let rate = survived_pages as f64 / collected_pages as f64;
+        let mut recent = self.recent_survival_rates.lock().unwrap();
+        if recent.len() == HISTORY_LENGTH {
+            recent.pop_front();
+        }
+        recent.push_back(rate);
+        let max_rate = recent.iter().copied().fold(0.0, f64::max);
+        let ratio = (max_rate * SAFETY_MARGIN).clamp(MIN_RESERVE_RATIO, 1.0);
+        trace!(
+            "Survival rate = {:.3} ({} / {} pages), copy reserve ratio = {:.3}",
+            rate,
+            survived_pages,
+            collected_pages,
+            ratio
+        );
+        self.ratio.store(ratio.to_bits(), Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_reserve() {
+        let reserve = CopyReserve::new(false);
+        reserve.record_survival(10, 100);
+        assert_eq!(reserve.reserved_pages(100), 100);
+        assert_eq!(reserve.available_pages(100), 50);
+    }
+
+    #[test]
+    fn elastic_reserve() {
+        let reserve = CopyReserve::new(true);
+        assert_eq!(reserve.reserved_pages(100), 100);
+
+        reserve.record_survival(25, 100);
+        assert_eq!(reserve.reserved_pages(100), 38);
+        assert_eq!(reserve.available_pages(275), 200);
+
+        // The highest recent survival rate is used.
+        reserve.record_survival(1, 100);
+        assert_eq!(reserve.reserved_pages(100), 38);
+
+        // Old survival rates are forgotten.
+        for _ in 0..HISTORY_LENGTH {
+            reserve.record_survival(1, 100);
+        }
+        assert_eq!(reserve.reserved_pages(100), 10);
+
+        // The reserve never exceeds the used pages.
+        reserve.record_survival(90, 100);
+        assert_eq!(reserve.reserved_pages(100), 100);
+    }
+}
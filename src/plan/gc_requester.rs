@@ -34,6 +34,11 @@ impl<VM: VMBinding> GCRequester<VM> {
         }
     }
 
+    /// Return true if a GC has been requested and mutators have not been stopped for it, yet.
+    pub fn is_requested(&self) -> bool {
+        self.request_flag.load(Ordering::Relaxed)
+    }
+
     /// Clear the "GC requested" flag so that mutators can trigger the next GC.
     /// Called by a GC worker when all mutators have come to a stop.
     pub fn clear_request(&self) {
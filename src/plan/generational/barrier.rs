@@ -2,61 +2,80 @@
 
 use crate::plan::barriers::BarrierSemantics;
 use crate::plan::PlanTraceObject;
-use crate::plan::VectorQueue;
 use crate::policy::gc_work::DEFAULT_TRACE;
-use crate::scheduler::WorkBucketStage;
+use crate::scheduler::{GCWorkScheduler, WorkBucketStage};
 use crate::util::constants::BYTES_IN_INT;
+use crate::util::remset::{RemSet, RemSetBuffer, RemSetCounters};
+use crate::util::statistics::stats::Stats;
 use crate::util::*;
 use crate::vm::slot::MemorySlice;
 use crate::vm::VMBinding;
 use crate::MMTK;
 
 use super::gc_work::GenNurseryProcessEdges;
-use super::gc_work::ProcessModBuf;
-use super::gc_work::ProcessRegionModBuf;
+use super::gc_work::ScanModBufs;
 use super::global::GenerationalPlanExt;
 
+/// The remembered sets of the generational barrier.  Mutators flush their modbufs into them, and
+/// they are scanned in the `Closure` stage of every GC.
+pub struct ModBufs<VM: VMBinding> {
+    /// Objects in mature space(s) that may contain pointers to the nursery space.
+    pub(super) objects: RemSet<ObjectReference>,
+    /// Sub-arrays or array slices in mature space(s) that may contain pointers to the nursery
+    /// space.
+    pub(super) regions: RemSet<VM::VMMemorySlice>,
+    objects_counters: RemSetCounters,
+    regions_counters: RemSetCounters,
+}
+
+impl<VM: VMBinding> ModBufs<VM> {
+    pub(crate) fn new(num_workers: usize, stats: &Stats) -> Self {
+        Self {
+            objects: RemSet::new(num_workers, RemSet::<ObjectReference>::DEFAULT_CHUNK_SIZE),
+            regions: RemSet::new(num_workers, RemSet::<VM::VMMemorySlice>::DEFAULT_CHUNK_SIZE),
+            objects_counters: RemSetCounters::new(stats, "modbuf.objects"),
+            regions_counters: RemSetCounters::new(stats, "modbuf.regions"),
+        }
+    }
+
+    /// Report the statistics of the modbufs since the last GC to the event counters.
+    pub(super) fn report_stats(&self) {
+        self.objects_counters.report(&self.objects);
+        self.regions_counters.report(&self.regions);
+    }
+
+    /// Schedule the work packet that scans the modbufs in the current GC.  All the mutators are
+    /// flushed when their roots are scanned in the `Prepare` stage, so the modbufs are complete
+    /// when the `Closure` stage starts.
+    pub(crate) fn schedule_scanning<P: GenerationalPlanExt<VM> + PlanTraceObject<VM>>(
+        &'static self,
+        scheduler: &GCWorkScheduler<VM>,
+    ) {
+        scheduler.work_buckets[WorkBucketStage::Closure]
+            .add(ScanModBufs::<GenNurseryProcessEdges<VM, P, DEFAULT_TRACE>>::new(self));
+    }
+}
+
 pub struct GenObjectBarrierSemantics<
     VM: VMBinding,
     P: GenerationalPlanExt<VM> + PlanTraceObject<VM>,
 > {
-    /// MMTk instance
-    mmtk: &'static MMTK<VM>,
     /// Generational plan
     plan: &'static P,
     /// Object modbuf. Contains a list of objects that may contain pointers to the nursery space.
-    modbuf: VectorQueue<ObjectReference>,
+    modbuf: RemSetBuffer<ObjectReference>,
     /// Array-copy modbuf. Contains a list of sub-arrays or array slices that may contain pointers to the nursery space.
-    region_modbuf: VectorQueue<VM::VMMemorySlice>,
+    region_modbuf: RemSetBuffer<VM::VMMemorySlice>,
 }
 
 impl<VM: VMBinding, P: GenerationalPlanExt<VM> + PlanTraceObject<VM>>
     GenObjectBarrierSemantics<VM, P>
 {
-    pub fn new(mmtk: &'static MMTK<VM>, plan: &'static P) -> Self {
+    pub fn new(_mmtk: &'static MMTK<VM>, plan: &'static P) -> Self {
         Self {
-            mmtk,
             plan,
-            modbuf: VectorQueue::new(),
-            region_modbuf: VectorQueue::new(),
-        }
-    }
-
-    fn flush_modbuf(&mut self) {
-        let buf = self.modbuf.take();
-        if !buf.is_empty() {
-            self.mmtk.scheduler.work_buckets[WorkBucketStage::Closure]
-                .add(ProcessModBuf::<GenNurseryProcessEdges<VM, P, DEFAULT_TRACE>>::new(buf));
-        }
-    }
-
-    fn flush_region_modbuf(&mut self) {
-        let buf = self.region_modbuf.take();
-        if !buf.is_empty() {
-            debug_assert!(!buf.is_empty());
-            self.mmtk.scheduler.work_buckets[WorkBucketStage::Closure].add(ProcessRegionModBuf::<
-                GenNurseryProcessEdges<VM, P, DEFAULT_TRACE>,
-            >::new(buf));
+            modbuf: RemSetBuffer::default(),
+            region_modbuf: RemSetBuffer::default(),
         }
     }
 }
@@ -67,8 +86,9 @@ impl<VM: VMBinding, P: GenerationalPlanExt<VM> + PlanTraceObject<VM>> BarrierSem
     type VM = VM;
 
     fn flush(&mut self) {
-        self.flush_modbuf();
-        self.flush_region_modbuf();
+        let modbufs = self.plan.modbufs();
+        self.modbuf.flush(&modbufs.objects);
+        self.region_modbuf.flush(&modbufs.regions);
     }
 
     fn object_reference_write_slow(
@@ -78,8 +98,7 @@ impl<VM: VMBinding, P: GenerationalPlanExt<VM> + PlanTraceObject<VM>> BarrierSem
         _target: Option<ObjectReference>,
     ) {
         // enqueue the object
-        self.modbuf.push(src);
-        self.modbuf.is_full().then(|| self.flush_modbuf());
+        self.modbuf.insert(&self.plan.modbufs().objects, src);
     }
 
     fn memory_region_copy_slow(&mut self, _src: VM::VMMemorySlice, dst: VM::VMMemorySlice) {
@@ -96,16 +115,12 @@ impl<VM: VMBinding, P: GenerationalPlanExt<VM> + PlanTraceObject<VM>> BarrierSem
                 0,
                 "bytes should be a multiple of 32-bit words"
             );
-            self.region_modbuf.push(dst);
-            self.region_modbuf
-                .is_full()
-                .then(|| self.flush_region_modbuf());
+            self.region_modbuf.insert(&self.plan.modbufs().regions, dst);
         }
     }
 
     fn object_probable_write_slow(&mut self, obj: ObjectReference) {
         // enqueue the object
-        self.modbuf.push(obj);
-        self.modbuf.is_full().then(|| self.flush_modbuf());
+        self.modbuf.insert(&self.plan.modbufs().objects, obj);
     }
 }
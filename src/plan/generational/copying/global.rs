@@ -1,6 +1,8 @@
 use super::gc_work::GenCopyGCWorkContext;
 use super::gc_work::GenCopyNurseryGCWorkContext;
 use super::mutator::ALLOCATOR_MAPPING;
+use crate::plan::copy_reserve::CopyReserve;
+use crate::plan::generational::barrier::ModBufs;
 use crate::plan::generational::global::CommonGenPlan;
 use crate::plan::generational::global::GenerationalPlan;
 use crate::plan::generational::global::GenerationalPlanExt;
@@ -40,6 +42,8 @@ pub struct GenCopy<VM: VMBinding> {
     #[space]
     #[copy_semantics(CopySemantics::Mature)]
     pub copyspace1: CopySpace<VM>,
+    /// How many pages are reserved for copying the objects in the mature from-space.
+    copy_reserve: CopyReserve,
 }
 
 /// The plan constraints for the generational copying plan.
@@ -63,6 +67,7 @@ impl<VM: VMBinding> Plan for GenCopy<VM> {
                 (CopySelector::CopySpace(0), self.tospace()),
             ],
             constraints: &GENCOPY_CONSTRAINTS,
+            ..Default::default()
         }
     }
 
@@ -80,6 +85,7 @@ impl<VM: VMBinding> Plan for GenCopy<VM> {
         } else {
             scheduler.schedule_common_work::<GenCopyNurseryGCWorkContext<VM>>(self);
         }
+        self.gen.modbufs.schedule_scanning::<Self>(scheduler);
     }
 
     fn get_allocator_mapping(&self) -> &'static EnumMap<AllocationSemantics, AllocatorSelector> {
@@ -108,6 +114,13 @@ impl<VM: VMBinding> Plan for GenCopy<VM> {
 
     fn release(&mut self, tls: VMWorkerThread) {
         let full_heap = !self.gen.is_current_gc_nursery();
+        if full_heap {
+            // Both the nursery and the from-space are collected, and the survivors are in the to-space.
+            self.copy_reserve.record_survival(
+                self.tospace().reserved_pages(),
+                self.fromspace().reserved_pages() + self.gen.nursery.reserved_pages(),
+            );
+        }
         self.gen.release(tls);
         if full_heap {
             self.fromspace().release();
@@ -120,7 +133,11 @@ impl<VM: VMBinding> Plan for GenCopy<VM> {
     }
 
     fn get_collection_reserved_pages(&self) -> usize {
-        self.gen.get_collection_reserved_pages() + self.tospace().reserved_pages()
+        // The nursery always reserves as many pages as it uses, as nursery survival rates vary a lot.
+        self.gen.get_collection_reserved_pages()
+            + self
+                .copy_reserve
+                .reserved_pages(self.tospace().reserved_pages())
     }
 
     fn get_used_pages(&self) -> usize {
@@ -174,8 +191,14 @@ impl<VM: VMBinding> GenerationalPlan for GenCopy<VM> {
         self.tospace().available_physical_pages()
     }
 
+    fn get_copying_nursery_bytes(&self) -> Option<usize> {
+        // Exclude the side metadata of the nursery.
+        let pages = self.gen.nursery.get_page_resource().reserved_pages();
+        Some(crate::util::conversions::pages_to_bytes(pages))
+    }
+
     fn get_mature_reserved_pages(&self) -> usize {
-        self.tospace().reserved_pages()
+        self.tospace().reserved_pages() + self.common().get_los().mature_pages()
     }
 
     fn force_full_heap_collection(&self) {
@@ -197,6 +220,10 @@ impl<VM: VMBinding> GenerationalPlanExt<VM> for GenCopy<VM> {
         self.gen
             .trace_object_nursery::<Q, KIND>(queue, object, worker)
     }
+
+    fn modbufs(&self) -> &ModBufs<VM> {
+        &self.gen.modbufs
+    }
 }
 
 impl<VM: VMBinding> GenCopy<VM> {
@@ -217,11 +244,13 @@ impl<VM: VMBinding> GenCopy<VM> {
             true,
         );
 
+        let copy_reserve = CopyReserve::new(*plan_args.global_args.options.elastic_copy_reserve);
         let res = GenCopy {
             gen: CommonGenPlan::new(plan_args),
             hi: AtomicBool::new(false),
             copyspace0,
             copyspace1,
+            copy_reserve,
         };
 
         res.verify_side_metadata_sanity();
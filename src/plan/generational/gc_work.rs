@@ -11,6 +11,7 @@ use crate::MMTK;
 use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
 
+use super::barrier::ModBufs;
 use super::global::GenerationalPlanExt;
 
 /// Process edges for a nursery GC. This type is provided if a generational plan does not use
@@ -88,6 +89,39 @@ impl<VM: VMBinding, P: GenerationalPlanExt<VM> + PlanTraceObject<VM>, const KIND
     }
 }
 
+/// Take the entries flushed into the modbufs, and create a [`ProcessModBuf`] or
+/// [`ProcessRegionModBuf`] work packet for each chunk of entries.  This is executed in the
+/// `Closure` stage of every GC.
+pub struct ScanModBufs<E: ProcessEdgesWork> {
+    modbufs: &'static ModBufs<E::VM>,
+    phantom: PhantomData<E>,
+}
+
+impl<E: ProcessEdgesWork> ScanModBufs<E> {
+    pub fn new(modbufs: &'static ModBufs<E::VM>) -> Self {
+        Self {
+            modbufs,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<E: ProcessEdgesWork> GCWork<E::VM> for ScanModBufs<E> {
+    fn do_work(&mut self, _worker: &mut GCWorker<E::VM>, mmtk: &'static MMTK<E::VM>) {
+        let mut packets = self
+            .modbufs
+            .objects
+            .generate_scan_packets(|modbuf| Box::new(ProcessModBuf::<E>::new(modbuf)) as _);
+        packets.extend(
+            self.modbufs.regions.generate_scan_packets(|modbuf| {
+                Box::new(ProcessRegionModBuf::<E>::new(modbuf)) as _
+            }),
+        );
+        self.modbufs.report_stats();
+        mmtk.scheduler.work_buckets[WorkBucketStage::Closure].bulk_add(packets);
+    }
+}
+
 /// The modbuf contains a list of objects in mature space(s) that
 /// may contain pointers to the nursery space.
 /// This work packet scans the recorded objects and forwards pointers if necessary.
@@ -1,3 +1,4 @@
+use crate::plan::generational::barrier::ModBufs;
 use crate::plan::global::CommonPlan;
 use crate::plan::global::CreateSpecificPlanArgs;
 use crate::plan::ObjectQueue;
@@ -36,6 +37,8 @@ pub struct CommonGenPlan<VM: VMBinding> {
     /// Is next GC full heap?
     pub next_gc_full_heap: AtomicBool,
     pub full_heap_gc_count: Arc<Mutex<EventCounter>>,
+    /// The remembered sets of the generational barrier.
+    pub modbufs: ModBufs<VM>,
 }
 
 impl<VM: VMBinding> CommonGenPlan<VM> {
@@ -48,6 +51,10 @@ impl<VM: VMBinding> CommonGenPlan<VM> {
             .global_args
             .stats
             .new_event_counter("majorGC", true, true);
+        let modbufs = ModBufs::new(
+            args.global_args.scheduler.num_workers(),
+            args.global_args.stats,
+        );
         let common = CommonPlan::new(args);
 
         CommonGenPlan {
@@ -56,6 +63,7 @@ impl<VM: VMBinding> CommonGenPlan<VM> {
             gc_full_heap: AtomicBool::default(),
             next_gc_full_heap: AtomicBool::new(false),
             full_heap_gc_count,
+            modbufs,
         }
     }
 
@@ -98,7 +106,8 @@ impl<VM: VMBinding> CommonGenPlan<VM> {
         space_full: bool,
         space: Option<SpaceStats<VM>>,
     ) -> bool {
-        let cur_nursery = self.nursery.reserved_pages();
+        // Young large objects are part of the nursery, too.
+        let cur_nursery = self.nursery.reserved_pages() + self.common.get_los().nursery_pages();
         let max_nursery = self.common.base.gc_trigger.get_max_nursery_pages();
         let nursery_full = cur_nursery >= max_nursery;
         trace!(
@@ -312,7 +321,16 @@ pub trait GenerationalPlan: Plan {
     /// Return the number of pages available for allocation into the mature space.
     fn get_mature_physical_pages_available(&self) -> usize;
 
-    /// Return the number of used pages in the mature space.
+    /// Return the number of bytes allocated in the nursery if the plan promotes surviving nursery
+    /// objects by copying them to the mature space, so that MMTk can compute the survival rate of
+    /// the nursery from the promoted bytes.  Return `None` if objects may be promoted in place.
+    /// This is called before the plan prepares for a GC.
+    fn get_copying_nursery_bytes(&self) -> Option<usize> {
+        None
+    }
+
+    /// Return the number of used pages in the mature space, including the pages of large objects
+    /// that have survived a GC.
     fn get_mature_reserved_pages(&self) -> usize;
 
     /// Return whether last GC is a full GC.
@@ -334,6 +352,9 @@ pub trait GenerationalPlanExt<VM: VMBinding>: GenerationalPlan<VM = VM> {
         object: ObjectReference,
         worker: &mut GCWorker<VM>,
     ) -> ObjectReference;
+
+    /// Get the remembered sets that the generational barrier flushes its modbufs into.
+    fn modbufs(&self) -> &ModBufs<VM>;
 }
 
 /// Is current GC only collecting objects allocated since last GC? This method can be called
@@ -1,5 +1,6 @@
 use super::gc_work::GenImmixMatureGCWorkContext;
 use super::gc_work::GenImmixNurseryGCWorkContext;
+use crate::plan::generational::barrier::ModBufs;
 use crate::plan::generational::global::CommonGenPlan;
 use crate::plan::generational::global::GenerationalPlan;
 use crate::plan::global::BasePlan;
@@ -82,6 +83,7 @@ impl<VM: VMBinding> Plan for GenImmix<VM> {
             },
             space_mapping: vec![(CopySelector::ImmixHybrid(0), &self.immix_space)],
             constraints: &GENIMMIX_CONSTRAINTS,
+            ..Default::default()
         }
     }
 
@@ -119,6 +121,7 @@ impl<VM: VMBinding> Plan for GenImmix<VM> {
                 GenImmixMatureGCWorkContext<VM, TRACE_KIND_DEFRAG>,
             >(self, &self.immix_space, scheduler);
         }
+        self.gen.modbufs.schedule_scanning::<Self>(scheduler);
     }
 
     fn get_allocator_mapping(&self) -> &'static EnumMap<AllocationSemantics, AllocatorSelector> {
@@ -213,8 +216,14 @@ impl<VM: VMBinding> GenerationalPlan for GenImmix<VM> {
         self.immix_space.available_physical_pages()
     }
 
+    fn get_copying_nursery_bytes(&self) -> Option<usize> {
+        // Exclude the side metadata of the nursery.
+        let pages = self.gen.nursery.get_page_resource().reserved_pages();
+        Some(crate::util::conversions::pages_to_bytes(pages))
+    }
+
     fn get_mature_reserved_pages(&self) -> usize {
-        self.immix_space.reserved_pages()
+        self.immix_space.reserved_pages() + self.common().get_los().mature_pages()
     }
 
     fn force_full_heap_collection(&self) {
@@ -236,6 +245,10 @@ impl<VM: VMBinding> crate::plan::generational::global::GenerationalPlanExt<VM> f
         self.gen
             .trace_object_nursery::<Q, KIND>(queue, object, worker)
     }
+
+    fn modbufs(&self) -> &ModBufs<VM> {
+        &self.gen.modbufs
+    }
 }
 
 impl<VM: VMBinding> GenImmix<VM> {
@@ -256,6 +269,9 @@ impl<VM: VMBinding> GenImmix<VM> {
                 // In GenImmix, young objects are not allocated in ImmixSpace directly.
                 #[cfg(feature = "vo_bit")]
                 mixed_age: false,
+                concurrent_sweeping: false,
+                concurrent_defrag_preparation: false,
+                mutator_block_budget: 0,
             },
         );
 
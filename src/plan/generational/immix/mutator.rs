@@ -6,6 +6,7 @@ use crate::plan::generational::immix::GenImmix;
 use crate::plan::mutator_context::unreachable_prepare_func;
 use crate::plan::mutator_context::Mutator;
 use crate::plan::mutator_context::MutatorConfig;
+use crate::plan::mutator_context::StackWatermark;
 use crate::plan::AllocationSemantics;
 use crate::util::alloc::allocators::Allocators;
 use crate::util::alloc::BumpAllocator;
@@ -46,6 +47,10 @@ pub fn create_genimmix_mutator<VM: VMBinding>(
             mmtk, genimmix,
         ))),
         mutator_tls,
+        stack_watermark: StackWatermark::default(),
+        active_since_idle_check: true,
+        deduplication_candidates: Vec::new(),
+        allocation_in_progress: false,
         config,
         plan: genimmix,
     }
@@ -30,6 +30,9 @@ pub mod immix;
 
 pub(super) mod gc_work;
 pub(super) mod global;
+/// Check that the remembered set is complete at the start of nursery GCs.
+#[cfg(all(feature = "extreme_assertions", feature = "vo_bit"))]
+pub(crate) mod remset_checker;
 
 /// # Barrier overhead measurement:
 ///  - Set `FULL_NURSERY_GC` to `true`.
@@ -51,6 +54,7 @@ pub const GEN_CONSTRAINTS: PlanConstraints = PlanConstraints {
     max_non_los_default_alloc_bytes:
         crate::plan::plan_constraints::MAX_NON_LOS_ALLOC_BYTES_COPYING_PLAN,
     needs_prepare_mutator: false,
+    supports_pinning_roots: false,
     ..PlanConstraints::default()
 };
 
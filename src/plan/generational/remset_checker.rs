@@ -0,0 +1,84 @@
+//! Remembered set completeness checker for generational plans.
+//!
+//! Generational plans rely on the object barrier to log every mature object that is modified
+//! between GCs. A mature object that refers to a nursery object but is still unlogged at the start
+//! of a nursery GC means that the binding missed a write barrier. The nursery object will not be
+//! traced from the mature object, and the reference will become dangling after the GC.
+//!
+//! At the start of each nursery GC, this checker walks the mature objects via the VO bits, and
+//! reports every unlogged mature object that has a reference to a nursery object.
+
+use crate::plan::is_nursery_gc;
+use crate::scheduler::{GCWork, GCWorker};
+use crate::util::ObjectReference;
+use crate::vm::slot::Slot;
+use crate::vm::{ObjectModel, Scanning, VMBinding};
+use crate::MMTK;
+use std::marker::PhantomData;
+use std::sync::atomic::Ordering;
+
+/// A work packet that checks the remembered set at the start of a nursery GC. It must be executed
+/// before any object is traced or any modified object is processed, i.e. in the `Prepare` stage.
+/// It panics if any reference from an unlogged mature object to a nursery object is found.
+pub struct CheckRemsetCompleteness<VM: VMBinding>(PhantomData<VM>);
+
+impl<VM: VMBinding> Default for CheckRemsetCompleteness<VM> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<VM: VMBinding> GCWork<VM> for CheckRemsetCompleteness<VM> {
+    fn do_work(&mut self, worker: &mut GCWorker<VM>, mmtk: &'static MMTK<VM>) {
+        let plan = mmtk.get_plan();
+        if !is_nursery_gc(plan) {
+            return;
+        }
+        let gen = plan.generational().unwrap();
+
+        let mut objects = vec![];
+        mmtk.enumerate_objects(|object| objects.push(object));
+
+        let tls = worker.tls;
+        let mut failures = 0;
+        for src in objects {
+            if gen.is_object_in_nursery(src)
+                || !VM::VMObjectModel::GLOBAL_LOG_BIT_SPEC.is_unlogged::<VM>(src, Ordering::SeqCst)
+            {
+                continue;
+            }
+            let mut check = |slot: Option<VM::VMSlot>, target: ObjectReference| {
+                if gen.is_object_in_nursery(target) {
+                    error!(
+                        "Missing write barrier: unlogged mature object {} refers to nursery object {} (slot: {:?})",
+                        src, target, slot
+                    );
+                    failures += 1;
+                }
+            };
+            if VM::VMScanning::support_slot_enqueuing(tls, src) {
+                VM::VMScanning::scan_object(tls, src, &mut |slot: VM::VMSlot| {
+                    if let Some(target) = slot.load() {
+                        check(Some(slot), target);
+                    }
+                });
+            } else {
+                VM::VMScanning::scan_object_and_trace_edges(
+                    tls,
+                    src,
+                    &mut |target: ObjectReference| {
+                        check(None, target);
+                        // Do not update the field.
+                        target
+                    },
+                );
+            }
+        }
+
+        assert_eq!(
+            failures, 0,
+            "Found {} references from unlogged mature objects to nursery objects",
+            failures
+        );
+    }
+}
@@ -3,10 +3,12 @@
 use super::PlanConstraints;
 use crate::global_state::GlobalState;
 use crate::mmtk::MMTK;
+use crate::plan::space_targeted::SpaceSet;
 use crate::plan::tracing::ObjectQueue;
 use crate::plan::Mutator;
 use crate::policy::immortalspace::ImmortalSpace;
 use crate::policy::largeobjectspace::LargeObjectSpace;
+use crate::policy::marksweepspace::native_ms::MarkSweepSpace;
 use crate::policy::space::{PlanCreateSpaceArgs, Space};
 #[cfg(feature = "vm_space")]
 use crate::policy::vmspace::VMSpace;
@@ -38,7 +40,7 @@ pub fn create_mutator<VM: VMBinding>(
     tls: VMMutatorThread,
     mmtk: &'static MMTK<VM>,
 ) -> Box<Mutator<VM>> {
-    Box::new(match *mmtk.options.plan {
+    let mut mutator = Box::new(match *mmtk.options.plan {
         PlanSelector::NoGC => crate::plan::nogc::mutator::create_nogc_mutator(tls, mmtk),
         PlanSelector::SemiSpace => crate::plan::semispace::mutator::create_ss_mutator(tls, mmtk),
         PlanSelector::GenCopy => {
@@ -58,7 +60,10 @@ pub fn create_mutator<VM: VMBinding>(
         PlanSelector::StickyImmix => {
             crate::plan::sticky::immix::mutator::create_stickyimmix_mutator(tls, mmtk)
         }
-    })
+    });
+    // Use the mapping with the allocation routes set by the binding, if any.
+    mutator.config.allocator_mapping = mmtk.allocator_mapping;
+    mutator
 }
 
 pub fn create_plan<VM: VMBinding>(
@@ -160,6 +165,42 @@ pub trait Plan: 'static + HasSpaces + Sync + Downcast {
     /// Schedule work for the upcoming GC.
     fn schedule_collection(&'static self, _scheduler: &GCWorkScheduler<Self::VM>);
 
+    /// Schedule work to do while mutators are idle (see [`crate::memory_manager::notify_idle`]),
+    /// such as sweeping blocks in the background.  The work packets should be added to the
+    /// `Unconstrained` bucket with `add_no_notify`, as this is called while holding the lock for
+    /// synchronizing GC workers.  Mutators may resume running at any time, but no GC starts until
+    /// the idle work packets are finished.  Idle work packets should check
+    /// [`crate::MMTK::is_gc_requested`] and return early if a GC is requested.
+    ///
+    /// Return `true` if any work packet is scheduled.  The default implementation schedules
+    /// nothing.
+    fn schedule_idle_work(&'static self, _scheduler: &GCWorkScheduler<Self::VM>) -> bool {
+        false
+    }
+
+    /// Schedule work packets to sweep the heap (and prepare for the next GC) while mutators run
+    /// after a GC, if the option `concurrent_sweeping` or `concurrent_defrag_preparation` is set.  This is called after mutators are resumed, and the work
+    /// packets should be added in the same way as [`Plan::schedule_idle_work`].  Unlike idle work,
+    /// the next GC does not start until the packets are finished, so the spaces can expect that
+    /// they are fully swept when the next GC starts.
+    ///
+    /// Return `true` if any work packet is scheduled.  The default implementation schedules
+    /// nothing.
+    fn schedule_concurrent_sweeping(&'static self, _scheduler: &GCWorkScheduler<Self::VM>) -> bool {
+        false
+    }
+
+    /// Can the plan do a space-targeted GC that only collects the space named `space_name`?  See
+    /// [`crate::memory_manager::handle_user_space_targeted_collection_request`].
+    ///
+    /// A plan that returns `true` for any space shall check `GlobalState::space_targets` in
+    /// `schedule_collection`, `prepare` and `release`.  In a space-targeted GC, it shall schedule
+    /// the GC with `crate::plan::space_targeted::schedule_collection`, and only prepare and release
+    /// the targeted spaces.  The default implementation returns `false`.
+    fn supports_space_targeted_collection(&self, _space_name: &str) -> bool {
+        false
+    }
+
     /// Get the common plan. CommonPlan is included by most of MMTk GC plans.
     fn common(&self) -> &CommonPlan<Self::VM> {
         panic!("Common Plan not handled!")
@@ -382,6 +423,7 @@ pub struct CreateGeneralPlanArgs<'a, VM: VMBinding> {
     pub state: Arc<GlobalState>,
     pub gc_trigger: Arc<crate::util::heap::gc_trigger::GCTrigger<VM>>,
     pub scheduler: Arc<GCWorkScheduler<VM>>,
+    pub handshake: Arc<Handshake<VM>>,
     pub stats: &'a Stats,
     pub heap: &'a mut HeapMeta,
 }
@@ -405,18 +447,25 @@ impl<VM: VMBinding> CreateSpecificPlanArgs<'_, VM> {
         permission_exec: bool,
         vmrequest: VMRequest,
     ) -> PlanCreateSpaceArgs<VM> {
+        #[allow(unused_mut)]
+        let mut global_side_metadata_specs = self.global_side_metadata_specs.clone();
+        #[cfg(feature = "address_based_hashing")]
+        crate::util::object_hash::extend_global_side_metadata_specs::<VM>(
+            &mut global_side_metadata_specs,
+        );
         PlanCreateSpaceArgs {
             name,
             zeroed,
             permission_exec,
             vmrequest,
-            global_side_metadata_specs: self.global_side_metadata_specs.clone(),
+            global_side_metadata_specs,
             vm_map: self.global_args.vm_map,
             mmapper: self.global_args.mmapper,
             heap: self.global_args.heap,
             constraints: self.constraints,
             gc_trigger: self.global_args.gc_trigger.clone(),
             scheduler: self.global_args.scheduler.clone(),
+            handshake: self.global_args.handshake.clone(),
             options: self.global_args.options.clone(),
             global_state: self.global_args.state.clone(),
         }
@@ -519,7 +568,7 @@ impl<VM: VMBinding> BasePlan<VM> {
             return self.vm_space.trace_object(queue, object);
         }
 
-        VM::VMActivePlan::vm_trace_object::<Q>(queue, object, worker)
+        crate::util::off_heap_objects::trace_object_outside_spaces::<VM, Q>(queue, object, worker)
     }
 
     pub fn prepare(&mut self, _tls: VMWorkerThread, _full_heap: bool) {
@@ -584,9 +633,10 @@ pub struct CommonPlan<VM: VMBinding> {
     pub immortal: ImmortalSpace<VM>,
     #[space]
     pub los: LargeObjectSpace<VM>,
-    // TODO: We should use a marksweep space for nonmoving.
+    /// The space for [`AllocationSemantics::NonMoving`].  Its objects are collected, but never
+    /// moved, regardless of the plan.
     #[space]
-    pub nonmoving: ImmortalSpace<VM>,
+    pub nonmoving: MarkSweepSpace<VM>,
     #[parent]
     pub base: BasePlan<VM>,
 }
@@ -604,7 +654,7 @@ impl<VM: VMBinding> CommonPlan<VM> {
                 args.get_space_args("los", true, false, VMRequest::discontiguous()),
                 false,
             ),
-            nonmoving: ImmortalSpace::new(args.get_space_args(
+            nonmoving: MarkSweepSpace::new_non_moving(args.get_space_args(
                 "nonmoving",
                 true,
                 false,
@@ -645,17 +695,56 @@ impl<VM: VMBinding> CommonPlan<VM> {
     pub fn prepare(&mut self, tls: VMWorkerThread, full_heap: bool) {
         self.immortal.prepare();
         self.los.prepare(full_heap);
-        self.nonmoving.prepare();
+        // The non-moving space is only collected in full-heap GCs.
+        if full_heap {
+            self.nonmoving.prepare();
+        }
         self.base.prepare(tls, full_heap)
     }
 
     pub fn release(&mut self, tls: VMWorkerThread, full_heap: bool) {
         self.immortal.release();
         self.los.release(full_heap);
-        self.nonmoving.release();
+        if full_heap {
+            self.nonmoving.release();
+        }
         self.base.release(tls, full_heap)
     }
 
+    /// Prepare the spaces for tracing the heap again in the same full-heap GC (mark-compact only).
+    /// The non-moving space is not released between the two traces, as its mutator allocators are
+    /// only released once in a GC.  Its marks are cleared instead.
+    pub fn prepare_for_retracing(&mut self, tls: VMWorkerThread) {
+        self.immortal.release();
+        self.los.release(true);
+        self.base.release(tls, true);
+        self.immortal.prepare();
+        self.los.prepare(true);
+        self.base.prepare(tls, true);
+        self.nonmoving.clear_marks();
+    }
+
+    /// Can a space-targeted GC collect the space named `space_name` of the common plan alone?  Only
+    /// the large object space can be collected alone.  The mutators' allocators of the non-moving
+    /// space are only released in full-heap GCs, and the other spaces are immortal.
+    pub fn supports_space_targeted_collection(&self, space_name: &str) -> bool {
+        space_name == self.los.get_name()
+    }
+
+    /// Prepare the targeted spaces of the common plan for a space-targeted GC.
+    pub fn prepare_space_targeted(&mut self, targets: SpaceSet) {
+        if targets.contains_space(self.los.as_space()) {
+            self.los.prepare(true);
+        }
+    }
+
+    /// Release the targeted spaces of the common plan after a space-targeted GC.
+    pub fn release_space_targeted(&mut self, targets: SpaceSet) {
+        if targets.contains_space(self.los.as_space()) {
+            self.los.release(true);
+        }
+    }
+
     pub fn get_immortal(&self) -> &ImmortalSpace<VM> {
         &self.immortal
     }
@@ -664,7 +753,7 @@ impl<VM: VMBinding> CommonPlan<VM> {
         &self.los
     }
 
-    pub fn get_nonmoving(&self) -> &ImmortalSpace<VM> {
+    pub fn get_nonmoving(&self) -> &MarkSweepSpace<VM> {
         &self.nonmoving
     }
 }
@@ -725,6 +814,20 @@ pub trait PlanTraceObject<VM: VMBinding> {
         worker: &mut GCWorker<VM>,
     ) -> ObjectReference;
 
+    /// Trace objects in the plan, given the space index recorded for the chunk of the object
+    /// (see [`crate::util::heap::space_index`]).  The derived `trace_object` loads the index once,
+    /// and passes it down to the parent plans with this method, so each space can be checked
+    /// with a comparison.  The default implementation ignores the index.
+    fn trace_object_with_space_index<Q: ObjectQueue, const KIND: TraceKind>(
+        &self,
+        _space_index: u8,
+        queue: &mut Q,
+        object: ObjectReference,
+        worker: &mut GCWorker<VM>,
+    ) -> ObjectReference {
+        self.trace_object::<Q, KIND>(queue, object, worker)
+    }
+
     /// Post-scan objects in the plan. Each object is scanned by `VM::VMScanning::scan_object()`, and this function
     /// will be called after the `VM::VMScanning::scan_object()` as a hook to invoke possible policy post scan method.
     /// If a plan does not have any policy that needs post scan, this method can be implemented as empty.
@@ -764,6 +867,39 @@ pub enum AllocationSemantics {
     ReadOnly = 4,
     /// Los + Code.
     LargeCode = 5,
-    /// Non moving objects will not be moved by GC.
+    /// Non moving objects will not be moved by GC.  They are allocated in the non-moving space,
+    /// which every plan that collects garbage has, whether or not it moves objects.  The space is a
+    /// mark-sweep space collected in full-heap GCs, so it cannot hold objects larger than the
+    /// largest mark-sweep size class.  Such objects should be allocated with `Los`, which does not
+    /// move objects either.
     NonMoving = 6,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::copyspace::CopySpace;
+    use crate::policy::gc_work::DEFAULT_TRACE;
+    use crate::util::copy::CopySemantics;
+    use crate::util::test_util::mock_vm::MockVM;
+
+    /// The derive macros also support enums, so a plan can choose its spaces at run time.
+    #[allow(dead_code)]
+    #[derive(HasSpaces, PlanTraceObject)]
+    enum NurseryChoice<VM: VMBinding> {
+        Copying {
+            #[space]
+            #[copy_semantics(CopySemantics::PromoteToMature)]
+            nursery: CopySpace<VM>,
+            #[parent]
+            common: CommonPlan<VM>,
+        },
+        NonMoving(#[space] ImmortalSpace<VM>, #[parent] CommonPlan<VM>),
+    }
+
+    #[test]
+    fn derive_for_enum() {
+        // Any variant with a moving space makes the plan moving.
+        assert!(NurseryChoice::<MockVM>::may_move_objects::<DEFAULT_TRACE>());
+    }
+}
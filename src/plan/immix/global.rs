@@ -29,6 +29,7 @@ use mmtk_macros::{HasSpaces, PlanTraceObject};
 pub struct Immix<VM: VMBinding> {
     #[post_scan]
     #[space]
+    #[dominant]
     #[copy_semantics(CopySemantics::DefaultCopy)]
     pub immix_space: ImmixSpace<VM>,
     #[parent]
@@ -62,11 +63,14 @@ impl<VM: VMBinding> Plan for Immix<VM> {
         use enum_map::enum_map;
         CopyConfig {
             copy_mapping: enum_map! {
-                CopySemantics::DefaultCopy => CopySelector::Immix(0),
+                CopySemantics::DefaultCopy | CopySemantics::DefaultCopyPartition(_) => {
+                    CopySelector::Immix(0)
+                }
                 _ => CopySelector::Unused,
             },
             space_mapping: vec![(CopySelector::Immix(0), &self.immix_space)],
             constraints: &IMMIX_CONSTRAINTS,
+            ..Default::default()
         }
     }
 
@@ -78,6 +82,12 @@ impl<VM: VMBinding> Plan for Immix<VM> {
         >(self, &self.immix_space, scheduler)
     }
 
+    fn schedule_concurrent_sweeping(&'static self, scheduler: &GCWorkScheduler<VM>) -> bool {
+        let immix_scheduled = self.immix_space.schedule_concurrent_sweeping(scheduler);
+        let los_scheduled = self.common.los.schedule_concurrent_sweeping(scheduler);
+        immix_scheduled || los_scheduled
+    }
+
     fn get_allocator_mapping(&self) -> &'static EnumMap<AllocationSemantics, AllocatorSelector> {
         &ALLOCATOR_MAPPING
     }
@@ -128,6 +138,8 @@ impl<VM: VMBinding> Plan for Immix<VM> {
 
 impl<VM: VMBinding> Immix<VM> {
     pub fn new(args: CreateGeneralPlanArgs<VM>) -> Self {
+        let concurrent_sweeping = *args.options.concurrent_sweeping;
+        let concurrent_defrag_preparation = *args.options.concurrent_defrag_preparation;
         let plan_args = CreateSpecificPlanArgs {
             global_args: args,
             constraints: &IMMIX_CONSTRAINTS,
@@ -140,6 +152,9 @@ impl<VM: VMBinding> Immix<VM> {
                 unlog_object_when_traced: false,
                 #[cfg(feature = "vo_bit")]
                 mixed_age: false,
+                concurrent_sweeping,
+                concurrent_defrag_preparation,
+                mutator_block_budget: 0,
             },
         )
     }
@@ -148,7 +163,8 @@ impl<VM: VMBinding> Immix<VM> {
         mut plan_args: CreateSpecificPlanArgs<VM>,
         space_args: ImmixSpaceArgs,
     ) -> Self {
-        let immix = Immix {
+        let concurrent_sweeping = space_args.concurrent_sweeping;
+        let mut immix = Immix {
             immix_space: ImmixSpace::new(
                 plan_args.get_space_args("immix", true, false, VMRequest::discontiguous()),
                 space_args,
@@ -156,6 +172,10 @@ impl<VM: VMBinding> Immix<VM> {
             common: CommonPlan::new(plan_args),
             last_gc_was_defrag: AtomicBool::new(false),
         };
+        immix
+            .common
+            .los
+            .set_concurrent_sweeping(concurrent_sweeping);
 
         immix.verify_side_metadata_sanity();
 
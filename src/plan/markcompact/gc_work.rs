@@ -6,6 +6,7 @@ use crate::scheduler::gc_work::*;
 use crate::scheduler::GCWork;
 use crate::scheduler::GCWorker;
 use crate::scheduler::WorkBucketStage;
+use crate::util::handle_table::ScanStrongHandles;
 use crate::vm::ActivePlan;
 use crate::vm::Scanning;
 use crate::vm::VMBinding;
@@ -45,8 +46,7 @@ impl<VM: VMBinding> GCWork<VM> for UpdateReferences<VM> {
         mmtk.state.prepare_for_stack_scanning();
         // Prepare common and base spaces for the 2nd round of transitive closure
         let plan_mut = unsafe { &mut *(self.plan as *mut MarkCompact<VM>) };
-        plan_mut.common.release(worker.tls, true);
-        plan_mut.common.prepare(worker.tls, true);
+        plan_mut.common.prepare_for_retracing(worker.tls);
         #[cfg(feature = "extreme_assertions")]
         mmtk.slot_logger.reset();
 
@@ -63,6 +63,10 @@ impl<VM: VMBinding> GCWork<VM> for UpdateReferences<VM> {
 
         mmtk.scheduler.work_buckets[WorkBucketStage::SecondRoots]
             .add(ScanVMSpecificRoots::<MarkCompactForwardingGCWorkContext<VM>>::new());
+        mmtk.scheduler.work_buckets[WorkBucketStage::SecondRoots]
+            .add(ScanStableRoots::<MarkCompactForwardingGCWorkContext<VM>>::new());
+        mmtk.scheduler.work_buckets[WorkBucketStage::SecondRoots]
+            .add(ScanStrongHandles::<ForwardingProcessEdges<VM>>::new());
     }
 }
 
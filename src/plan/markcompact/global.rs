@@ -30,6 +30,7 @@ use mmtk_macros::{HasSpaces, PlanTraceObject};
 #[derive(HasSpaces, PlanTraceObject)]
 pub struct MarkCompact<VM: VMBinding> {
     #[space]
+    #[dominant]
     #[copy_semantics(CopySemantics::DefaultCopy)]
     pub mc_space: MarkCompactSpace<VM>,
     #[parent]
@@ -43,6 +44,7 @@ pub const MARKCOMPACT_CONSTRAINTS: PlanConstraints = PlanConstraints {
     max_non_los_default_alloc_bytes:
         crate::plan::plan_constraints::MAX_NON_LOS_ALLOC_BYTES_COPYING_PLAN,
     needs_prepare_mutator: false,
+    supports_pinning_roots: false,
     ..PlanConstraints::default()
 };
 
@@ -138,10 +140,35 @@ impl<VM: VMBinding> Plan for MarkCompact<VM> {
                 .add(ForwardFinalization::<ForwardingProcessEdges<VM>>::new());
         }
 
+        // Off-heap objects registered by the binding
+        {
+            use crate::util::off_heap_objects::{PrepareOffHeapObjects, ReleaseOffHeapObjects};
+            scheduler.work_buckets[WorkBucketStage::Prepare]
+                .add(PrepareOffHeapObjects::<VM>::default());
+            // Clear the marks again so that the second transitive closure scans the off-heap
+            // objects again to forward their reference fields.
+            scheduler.work_buckets[WorkBucketStage::CalculateForwarding]
+                .add(PrepareOffHeapObjects::<VM>::default());
+            scheduler.work_buckets[WorkBucketStage::Release]
+                .add(ReleaseOffHeapObjects::<VM>::default());
+        }
+
+        // Weak slots reported by `Scanning::scan_object`
+        {
+            use crate::util::weak_slot_processor::ProcessWeakSlots;
+            scheduler.work_buckets[WorkBucketStage::WeakRefClosure]
+                .add(ProcessWeakSlots::<VM>::new());
+        }
+
         // VM-specific weak ref processing
         scheduler.work_buckets[WorkBucketStage::VMRefClosure]
             .set_sentinel(Box::new(VMProcessWeakRefs::<MarkingProcessEdges<VM>>::new()));
 
+        // VM-specific metadata unloading
+        scheduler.work_buckets[WorkBucketStage::VMUnloading].set_sentinel(Box::new(
+            VMProcessUnloading::<MarkingProcessEdges<VM>>::new(),
+        ));
+
         // VM-specific weak ref forwarding
         scheduler.work_buckets[WorkBucketStage::VMRefForwarding]
             .add(VMForwardWeakRefs::<ForwardingProcessEdges<VM>>::new());
@@ -6,6 +6,7 @@ use crate::plan::mutator_context::unreachable_prepare_func;
 use crate::plan::mutator_context::Mutator;
 use crate::plan::mutator_context::MutatorConfig;
 use crate::plan::mutator_context::ReservedAllocators;
+use crate::plan::mutator_context::StackWatermark;
 use crate::plan::AllocationSemantics;
 use crate::util::alloc::allocators::{AllocatorSelector, Allocators};
 use crate::util::alloc::MarkCompactAllocator;
@@ -47,6 +48,10 @@ pub fn create_markcompact_mutator<VM: VMBinding>(
         allocators: Allocators::<VM>::new(mutator_tls, mmtk, &config.space_mapping),
         barrier: Box::new(NoBarrier),
         mutator_tls,
+        stack_watermark: StackWatermark::default(),
+        active_since_idle_check: true,
+        deduplication_candidates: Vec::new(),
+        allocation_in_progress: false,
         config,
         plan: markcompact,
     }
@@ -1,12 +1,15 @@
 use super::MarkSweep;
-use crate::policy::gc_work::DEFAULT_TRACE;
+use crate::policy::gc_work::TraceKind;
+use crate::policy::gc_work::TRACE_KIND_TRANSITIVE_PIN;
 use crate::scheduler::gc_work::*;
 use crate::vm::VMBinding;
 
-pub struct MSGCWorkContext<VM: VMBinding>(std::marker::PhantomData<VM>);
-impl<VM: VMBinding> crate::scheduler::GCWorkContext for MSGCWorkContext<VM> {
+pub struct MSGCWorkContext<VM: VMBinding, const KIND: TraceKind>(std::marker::PhantomData<VM>);
+impl<VM: VMBinding, const KIND: TraceKind> crate::scheduler::GCWorkContext
+    for MSGCWorkContext<VM, KIND>
+{
     type VM = VM;
     type PlanType = MarkSweep<VM>;
-    type DefaultProcessEdges = PlanProcessEdges<Self::VM, MarkSweep<VM>, DEFAULT_TRACE>;
-    type PinningProcessEdges = PlanProcessEdges<Self::VM, MarkSweep<VM>, DEFAULT_TRACE>;
+    type DefaultProcessEdges = PlanProcessEdges<Self::VM, MarkSweep<VM>, KIND>;
+    type PinningProcessEdges = PlanProcessEdges<Self::VM, MarkSweep<VM>, TRACE_KIND_TRANSITIVE_PIN>;
 }
@@ -10,6 +10,7 @@ use crate::plan::PlanConstraints;
 use crate::policy::space::Space;
 use crate::scheduler::GCWorkScheduler;
 use crate::util::alloc::allocators::AllocatorSelector;
+use crate::util::copy::*;
 use crate::util::heap::gc_trigger::SpaceStats;
 use crate::util::heap::VMRequest;
 use crate::util::metadata::side_metadata::SideMetadataContext;
@@ -33,7 +34,11 @@ pub struct MarkSweep<VM: VMBinding> {
     #[parent]
     common: CommonPlan<VM>,
     #[space]
+    #[dominant]
+    #[copy_semantics(CopySemantics::DefaultCopy)]
     ms: MarkSweepSpace<VM>,
+    /// `MS_CONSTRAINTS`, or `MS_REPACK_CONSTRAINTS` if the space is repacked.
+    constraints: &'static PlanConstraints,
 }
 
 /// The plan constraints for the mark sweep plan.
@@ -46,9 +51,60 @@ pub const MS_CONSTRAINTS: PlanConstraints = PlanConstraints {
     ..PlanConstraints::default()
 };
 
+/// The plan constraints for the mark sweep plan if the option `ms_repack_interval` is set, in which
+/// case the native mark sweep space moves objects when it is repacked.
+pub const MS_REPACK_CONSTRAINTS: PlanConstraints = PlanConstraints {
+    moves_objects: true,
+    ..MS_CONSTRAINTS
+};
+
 impl<VM: VMBinding> Plan for MarkSweep<VM> {
+    #[cfg(feature = "malloc_mark_sweep")]
     fn schedule_collection(&'static self, scheduler: &GCWorkScheduler<VM>) {
-        scheduler.schedule_common_work::<MSGCWorkContext<VM>>(self);
+        use crate::policy::gc_work::DEFAULT_TRACE;
+        scheduler.schedule_common_work::<MSGCWorkContext<VM, DEFAULT_TRACE>>(self);
+    }
+
+    #[cfg(not(feature = "malloc_mark_sweep"))]
+    fn schedule_collection(&'static self, scheduler: &GCWorkScheduler<VM>) {
+        use crate::policy::marksweepspace::native_ms::{TRACE_KIND_FAST, TRACE_KIND_REPACK};
+        if self.ms.decide_whether_to_repack() {
+            scheduler.schedule_common_work::<MSGCWorkContext<VM, TRACE_KIND_REPACK>>(self);
+        } else {
+            scheduler.schedule_common_work::<MSGCWorkContext<VM, TRACE_KIND_FAST>>(self);
+        }
+    }
+
+    #[cfg(not(feature = "malloc_mark_sweep"))]
+    fn create_copy_config(&'static self) -> CopyConfig<Self::VM> {
+        use enum_map::enum_map;
+        CopyConfig {
+            copy_mapping: enum_map! {
+                CopySemantics::DefaultCopy | CopySemantics::DefaultCopyPartition(_) => {
+                    CopySelector::MarkSweep(0)
+                }
+                _ => CopySelector::Unused,
+            },
+            space_mapping: vec![(CopySelector::MarkSweep(0), &self.ms)],
+            constraints: self.constraints,
+            ..Default::default()
+        }
+    }
+
+    #[cfg(not(feature = "malloc_mark_sweep"))]
+    fn schedule_idle_work(&'static self, scheduler: &GCWorkScheduler<VM>) -> bool {
+        self.ms.schedule_idle_sweeping(scheduler)
+    }
+
+    fn schedule_concurrent_sweeping(&'static self, scheduler: &GCWorkScheduler<VM>) -> bool {
+        // The blocks are left unswept in the release phase (unless with eager sweeping), and we
+        // sweep them in the same way as sweeping them while mutators are idle.
+        #[cfg(not(feature = "malloc_mark_sweep"))]
+        let ms_scheduled = self.ms.schedule_idle_sweeping(scheduler);
+        #[cfg(feature = "malloc_mark_sweep")]
+        let ms_scheduled = false;
+        let los_scheduled = self.common.los.schedule_concurrent_sweeping(scheduler);
+        ms_scheduled || los_scheduled
     }
 
     fn get_allocator_mapping(&self) -> &'static EnumMap<AllocationSemantics, AllocatorSelector> {
@@ -57,6 +113,8 @@ impl<VM: VMBinding> Plan for MarkSweep<VM> {
 
     fn prepare(&mut self, tls: VMWorkerThread) {
         self.common.prepare(tls, true);
+        #[cfg(not(feature = "malloc_mark_sweep"))]
+        self.ms.select_repack_sources(self.get_available_pages());
         self.ms.prepare();
     }
 
@@ -73,10 +131,16 @@ impl<VM: VMBinding> Plan for MarkSweep<VM> {
         self.base().collection_required(self, space_full)
     }
 
+    #[cfg(feature = "malloc_mark_sweep")]
     fn current_gc_may_move_object(&self) -> bool {
         false
     }
 
+    #[cfg(not(feature = "malloc_mark_sweep"))]
+    fn current_gc_may_move_object(&self) -> bool {
+        self.ms.in_repack()
+    }
+
     fn get_used_pages(&self) -> usize {
         self.common.get_used_pages() + self.ms.reserved_pages()
     }
@@ -94,7 +158,7 @@ impl<VM: VMBinding> Plan for MarkSweep<VM> {
     }
 
     fn constraints(&self) -> &'static PlanConstraints {
-        &MS_CONSTRAINTS
+        self.constraints
     }
 }
 
@@ -103,13 +167,21 @@ impl<VM: VMBinding> MarkSweep<VM> {
         let mut global_side_metadata_specs = SideMetadataContext::new_global_specs(&[]);
         MarkSweepSpace::<VM>::extend_global_side_metadata_specs(&mut global_side_metadata_specs);
 
+        // Only the native mark sweep space can be repacked.
+        let constraints =
+            if cfg!(not(feature = "malloc_mark_sweep")) && *args.options.ms_repack_interval != 0 {
+                &MS_REPACK_CONSTRAINTS
+            } else {
+                &MS_CONSTRAINTS
+            };
         let mut plan_args = CreateSpecificPlanArgs {
             global_args: args,
-            constraints: &MS_CONSTRAINTS,
+            constraints,
             global_side_metadata_specs,
         };
 
-        let res = MarkSweep {
+        let concurrent_sweeping = *plan_args.global_args.options.concurrent_sweeping;
+        let mut res = MarkSweep {
             ms: MarkSweepSpace::new(plan_args.get_space_args(
                 "ms",
                 true,
@@ -117,7 +189,9 @@ impl<VM: VMBinding> MarkSweep<VM> {
                 VMRequest::discontiguous(),
             )),
             common: CommonPlan::new(plan_args),
+            constraints,
         };
+        res.common.los.set_concurrent_sweeping(concurrent_sweeping);
 
         res.verify_side_metadata_sanity();
 
@@ -6,3 +6,4 @@ pub mod mutator;
 
 pub use self::global::MarkSweep;
 pub use self::global::MS_CONSTRAINTS;
+pub use self::global::MS_REPACK_CONSTRAINTS;
@@ -5,6 +5,7 @@ use crate::plan::mutator_context::Mutator;
 use crate::plan::mutator_context::MutatorConfig;
 use crate::plan::mutator_context::ReservedAllocators;
 use crate::plan::mutator_context::SpaceMapping;
+use crate::plan::mutator_context::StackWatermark;
 use crate::plan::AllocationSemantics;
 use crate::plan::Plan;
 use crate::util::alloc::allocators::{AllocatorSelector, Allocators};
@@ -125,6 +126,10 @@ pub fn create_ms_mutator<VM: VMBinding>(
         allocators: Allocators::<VM>::new(mutator_tls, mmtk, &config.space_mapping),
         barrier: Box::new(NoBarrier),
         mutator_tls,
+        stack_watermark: StackWatermark::default(),
+        active_since_idle_check: true,
+        deduplication_candidates: Vec::new(),
+        allocation_in_progress: false,
         config,
         plan: mmtk.get_plan(),
     }
@@ -15,6 +15,9 @@
 
 mod barriers;
 pub use barriers::BarrierSelector;
+pub use barriers::BarrierSourceHint;
+
+pub(crate) mod copy_reserve;
 
 pub(crate) mod gc_requester;
 
@@ -29,15 +32,20 @@ pub use global::Plan;
 pub(crate) use global::PlanTraceObject;
 
 mod mutator_context;
+pub(crate) use mutator_context::route_allocator_mapping;
 pub use mutator_context::Mutator;
 pub use mutator_context::MutatorContext;
+pub use mutator_context::StackWatermark;
 
 mod plan_constraints;
 pub use plan_constraints::PlanConstraints;
 pub(crate) use plan_constraints::DEFAULT_PLAN_CONSTRAINTS;
 
+pub(crate) mod space_targeted;
+
 mod tracing;
 pub use tracing::{ObjectQueue, ObjectsClosure, VectorObjectQueue, VectorQueue};
+pub(crate) use tracing::{SlotCollector, SlotFilter, SlotFilterCounters};
 
 /// Generational plans (with a copying nursery)
 mod generational;
@@ -53,6 +61,7 @@ mod semispace;
 
 pub(crate) use generational::global::is_nursery_gc;
 pub(crate) use generational::global::GenerationalPlan;
+pub(crate) use pageprotect::PageProtect;
 
 // Expose plan constraints as public. Though a binding can get them from plan.constraints(),
 // it is possible for performance reasons that they want the constraints as constants.
@@ -62,6 +71,7 @@ pub use generational::immix::GENIMMIX_CONSTRAINTS;
 pub use immix::IMMIX_CONSTRAINTS;
 pub use markcompact::MARKCOMPACT_CONSTRAINTS;
 pub use marksweep::MS_CONSTRAINTS;
+pub use marksweep::MS_REPACK_CONSTRAINTS;
 pub use nogc::NOGC_CONSTRAINTS;
 pub use pageprotect::PP_CONSTRAINTS;
 pub use semispace::SS_CONSTRAINTS;
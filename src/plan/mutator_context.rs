@@ -5,7 +5,10 @@ use crate::plan::global::Plan;
 use crate::plan::AllocationSemantics;
 use crate::policy::space::Space;
 use crate::util::alloc::allocators::{AllocatorSelector, Allocators};
+use crate::util::alloc::AllocationOptions;
 use crate::util::alloc::Allocator;
+use crate::util::alloc::FreeListAllocator;
+use crate::util::alloc::ImmixAllocator;
 use crate::util::{Address, ObjectReference};
 use crate::util::{VMMutatorThread, VMWorkerThread};
 use crate::vm::VMBinding;
@@ -92,16 +95,77 @@ pub struct Mutator<VM: VMBinding> {
     pub barrier: Box<dyn Barrier<VM>>,
     /// The mutator thread that is bound with this Mutator struct.
     pub mutator_tls: VMMutatorThread,
+    /// The state of partial stack scanning with return barriers.
+    pub(crate) stack_watermark: StackWatermark,
+    /// True if the mutator has allocated in the slow path since the last check for idle mutators.
+    pub(crate) active_since_idle_check: bool,
+    /// The deduplication candidates added by the mutator since the last GC.  They are moved to
+    /// the deduplication table when the mutator is stopped for a GC.
+    pub(crate) deduplication_candidates: Vec<ObjectReference>,
+    /// True if the mutator is allocating, including when it is blocked for a GC in the allocation
+    /// slow path.  The mutator cannot be rebound to another thread at this time.
+    pub(crate) allocation_in_progress: bool,
     pub(crate) plan: &'static dyn Plan<VM = VM>,
     pub(crate) config: MutatorConfig<VM>,
 }
 
+/// The state of partial stack scanning of a mutator.
+///
+/// A VM that supports return barriers ([`crate::vm::Scanning::supports_return_barrier`]) can
+/// install a return barrier on a stack frame after scanning the stack, and report the frame as the
+/// *watermark* of the stack with [`crate::memory_manager::set_stack_watermark`].  The frames below
+/// the watermark (i.e. the frames that were scanned and have not been returned to since) cannot
+/// have new references.  In a nursery GC, the references in those frames can only point to mature
+/// objects which are not moved, so the VM only needs to re-scan the frames above the watermark.
+/// The VM gets the watermark with [`crate::memory_manager::get_stack_watermark`] when scanning
+/// the stack, which returns `None` if the whole stack must be scanned in the current GC.
+///
+/// The meaning of the watermark address (such as the stack pointer or the frame pointer of the
+/// frame) is up to the VM.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct StackWatermark {
+    /// The watermark reported by the VM, or zero if there is none.
+    frontier: Address,
+    /// True if the current GC allows scanning only the frames above the watermark.
+    partial_scan_allowed: bool,
+}
+
+impl Default for StackWatermark {
+    fn default() -> Self {
+        Self {
+            frontier: Address::ZERO,
+            partial_scan_allowed: false,
+        }
+    }
+}
+
+impl StackWatermark {
+    /// Set the watermark.  Zero means the whole stack needs to be scanned in the next GC.
+    pub(crate) fn set(&mut self, frontier: Address) {
+        self.frontier = frontier;
+    }
+
+    /// Get the watermark if the current GC only needs to scan the frames above it.
+    pub(crate) fn get(&self) -> Option<Address> {
+        (self.partial_scan_allowed && !self.frontier.is_zero()).then_some(self.frontier)
+    }
+
+    /// Decide whether the stack can be scanned partially in the current GC before scanning the
+    /// stack.  Return true if there is a watermark and the GC allows partial scanning.
+    pub(crate) fn prepare_for_scanning(&mut self, partial_scan_allowed: bool) -> bool {
+        self.partial_scan_allowed = partial_scan_allowed;
+        self.get().is_some()
+    }
+}
+
 impl<VM: VMBinding> MutatorContext<VM> for Mutator<VM> {
     fn prepare(&mut self, tls: VMWorkerThread) {
         (*self.config.prepare_func)(self, tls)
     }
     fn release(&mut self, tls: VMWorkerThread) {
-        (*self.config.release_func)(self, tls)
+        (*self.config.release_func)(self, tls);
+        self.release_nonmoving_allocator();
     }
 
     // Note that this method is slow, and we expect VM bindings that care about performance to implement allocation fastpath sequence in their bindings.
@@ -112,11 +176,15 @@ impl<VM: VMBinding> MutatorContext<VM> for Mutator<VM> {
         offset: usize,
         allocator: AllocationSemantics,
     ) -> Address {
-        unsafe {
+        self.active_since_idle_check = true;
+        self.allocation_in_progress = true;
+        let result = unsafe {
             self.allocators
                 .get_allocator_mut(self.config.allocator_mapping[allocator])
         }
-        .alloc(size, align, offset)
+        .alloc(size, align, offset);
+        self.allocation_in_progress = false;
+        result
     }
 
     fn alloc_slow(
@@ -126,11 +194,53 @@ impl<VM: VMBinding> MutatorContext<VM> for Mutator<VM> {
         offset: usize,
         allocator: AllocationSemantics,
     ) -> Address {
-        unsafe {
+        self.active_since_idle_check = true;
+        self.allocation_in_progress = true;
+        let result = unsafe {
+            self.allocators
+                .get_allocator_mut(self.config.allocator_mapping[allocator])
+        }
+        .alloc_slow(size, align, offset);
+        self.allocation_in_progress = false;
+        result
+    }
+
+    fn alloc_with_options(
+        &mut self,
+        size: usize,
+        align: usize,
+        offset: usize,
+        allocator: AllocationSemantics,
+        options: AllocationOptions,
+    ) -> Address {
+        self.active_since_idle_check = true;
+        self.allocation_in_progress = true;
+        let result = unsafe {
+            self.allocators
+                .get_allocator_mut(self.config.allocator_mapping[allocator])
+        }
+        .alloc_with_options(size, align, offset, options);
+        self.allocation_in_progress = false;
+        result
+    }
+
+    fn alloc_slow_with_options(
+        &mut self,
+        size: usize,
+        align: usize,
+        offset: usize,
+        allocator: AllocationSemantics,
+        options: AllocationOptions,
+    ) -> Address {
+        self.active_since_idle_check = true;
+        self.allocation_in_progress = true;
+        let result = unsafe {
             self.allocators
                 .get_allocator_mut(self.config.allocator_mapping[allocator])
         }
-        .alloc_slow(size, align, offset)
+        .alloc_slow_with_options(size, align, offset, options);
+        self.allocation_in_progress = false;
+        result
     }
 
     // Note that this method is slow, and we expect VM bindings that care about performance to implement allocation fastpath sequence in their bindings.
@@ -140,12 +250,27 @@ impl<VM: VMBinding> MutatorContext<VM> for Mutator<VM> {
         _bytes: usize,
         allocator: AllocationSemantics,
     ) {
-        unsafe {
+        let allocator = unsafe {
             self.allocators
                 .get_allocator_mut(self.config.allocator_mapping[allocator])
-        }
-        .get_space()
-        .initialize_object_metadata(refer, true)
+        };
+        allocator
+            .get_space()
+            .initialize_object_metadata(refer, true);
+
+        #[cfg(feature = "analysis")]
+        allocator
+            .get_context()
+            .state
+            .object_lifetimes
+            .record_birth(refer);
+
+        // The memory may have been used by another object. The binding sets the allocation site after this.
+        #[cfg(feature = "alloc_site")]
+        crate::util::alloc_site::set_alloc_site(refer, crate::util::alloc_site::UNKNOWN_ALLOC_SITE);
+
+        #[cfg(feature = "address_based_hashing")]
+        crate::util::object_hash::on_object_allocated::<VM>(refer);
     }
 
     fn get_tls(&self) -> VMMutatorThread {
@@ -171,11 +296,116 @@ impl<VM: VMBinding> Mutator<VM> {
             .collect()
     }
 
+    /// Retire the thread-local allocation buffers of all the allocators of this mutator, so that
+    /// the unused memory in the buffers can be reused by other mutators or reclaimed by GC.
+    pub fn retire_allocation_buffers(&mut self) {
+        for selector in self.get_all_allocator_selectors() {
+            unsafe { self.allocators.get_allocator_mut(selector) }.retire_thread_local_buffer();
+        }
+    }
+
+    /// Flush the mutator and retire its allocation buffers if it has not allocated in the slow
+    /// path since the last check.  This is the action of the handshakes for flushing idle mutators.
+    pub(crate) fn flush_if_idle(&mut self) {
+        if !self.active_since_idle_check {
+            trace!("Flush idle mutator {:?}", self.mutator_tls);
+            self.flush();
+            self.retire_allocation_buffers();
+        }
+        self.active_since_idle_check = false;
+    }
+
+    /// Release the allocator of the non-moving space of the common plan if the space is collected
+    /// in the current GC.  The space waits for the allocators of all mutators to be released.  All
+    /// the plans that do GC have the common plan.
+    fn release_nonmoving_allocator(&mut self) {
+        let nonmoving = self.plan.common().get_nonmoving();
+        if !nonmoving.in_collection() {
+            return;
+        }
+        let descriptor = nonmoving.common().descriptor;
+        let selector = self
+            .config
+            .space_mapping
+            .iter()
+            .find(|(_, space)| space.common().descriptor == descriptor)
+            .map(|(selector, _)| *selector)
+            .expect("The mutator has no allocator for the non-moving space");
+        unsafe {
+            self.allocators
+                .get_typed_allocator_mut::<FreeListAllocator<VM>>(selector)
+        }
+        .release();
+    }
+
+    /// Return the unused lines of the blocks of the Immix allocators to their spaces if the
+    /// mutator has not allocated in the slow path since the last check.  This is the action of the
+    /// handshakes for reclaiming blocks held by idle mutators.
+    pub(crate) fn return_unused_lines_if_idle(&mut self) {
+        if !self.active_since_idle_check {
+            trace!("Return unused lines of idle mutator {:?}", self.mutator_tls);
+            for selector in self.get_all_allocator_selectors() {
+                if let AllocatorSelector::Immix(_) = selector {
+                    unsafe {
+                        self.allocators
+                            .get_typed_allocator_mut::<ImmixAllocator<VM>>(selector)
+                    }
+                    .return_unused_lines();
+                }
+            }
+        }
+        self.active_since_idle_check = false;
+    }
+
+    /// Bind the mutator to another thread.  This is used by VMs that multiplex language threads
+    /// (such as fibers or goroutines) over OS threads, and migrate the language threads between
+    /// OS threads.  This updates the thread of the mutator and all its allocators.
+    ///
+    /// # Panics
+    /// This panics if the mutator is allocating, for example, when it is blocked for a GC in the
+    /// allocation slow path.
+    pub fn rebind(&mut self, tls: VMMutatorThread) {
+        assert!(
+            !self.allocation_in_progress,
+            "Cannot rebind mutator {:?} to {:?} while it is allocating",
+            self.mutator_tls, tls
+        );
+        self.mutator_tls = tls;
+        for selector in self.get_all_allocator_selectors() {
+            unsafe { self.allocators.get_allocator_mut(selector) }.set_tls(tls.0);
+        }
+    }
+
+    /// Park the mutator for a long time.  This flushes the mutator and retires its allocation
+    /// buffers, so that the memory reserved by the mutator can be used by other mutators or
+    /// reclaimed by GC while the mutator is parked.  The mutator can allocate again after it is
+    /// parked, and it will acquire new memory as needed.
+    ///
+    /// # Panics
+    /// This panics if the mutator is allocating.
+    pub fn park(&mut self) {
+        assert!(
+            !self.allocation_in_progress,
+            "Cannot park mutator {:?} while it is allocating",
+            self.mutator_tls
+        );
+        self.flush();
+        self.retire_allocation_buffers();
+    }
+
     /// Inform each allocator about destroying. Call allocator-specific on destroy methods.
     pub fn on_destroy(&mut self) {
         for selector in self.get_all_allocator_selectors() {
             unsafe { self.allocators.get_allocator_mut(selector) }.on_mutator_destroy();
         }
+        // The mutator will not reach yieldpoints any more.  All the allocators share the same
+        // handshake coordinator.
+        let selector = self.config.allocator_mapping[AllocationSemantics::Default];
+        let handshake = unsafe { self.allocators.get_allocator(selector) }
+            .get_context()
+            .handshake
+            .clone();
+        handshake.remove_mutator(self);
     }
 
     /// Get the allocator for the selector.
@@ -286,6 +516,32 @@ pub trait MutatorContext<VM: VMBinding>: Send + 'static {
         offset: usize,
         allocator: AllocationSemantics,
     ) -> Address;
+    /// Allocate memory for an object with the given options.
+    ///
+    /// Arguments:
+    /// * `size`: the number of bytes required for the object.
+    /// * `align`: required alignment for the object.
+    /// * `offset`: offset associated with the alignment. The result plus the offset will be aligned to the given alignment.
+    /// * `allocator`: the allocation semantic used for this object.
+    /// * `options`: the options for the allocation.
+    fn alloc_with_options(
+        &mut self,
+        size: usize,
+        align: usize,
+        offset: usize,
+        allocator: AllocationSemantics,
+        options: AllocationOptions,
+    ) -> Address;
+    /// The slow path allocation with the given options.  See
+    /// [`alloc_slow`](MutatorContext::alloc_slow).
+    fn alloc_slow_with_options(
+        &mut self,
+        size: usize,
+        align: usize,
+        offset: usize,
+        allocator: AllocationSemantics,
+        options: AllocationOptions,
+    ) -> Address;
     /// Perform post-allocation actions.  For many allocators none are
     /// required.
     ///
@@ -409,16 +665,60 @@ pub(crate) fn create_allocator_mapping(
         map[AllocationSemantics::Los] = AllocatorSelector::LargeObject(reserved.n_large_object);
         reserved.n_large_object += 1;
 
-        // TODO: This should be freelist allocator once we use marksweep for nonmoving space.
-        map[AllocationSemantics::NonMoving] =
-            AllocatorSelector::BumpPointer(reserved.n_bump_pointer);
-        reserved.n_bump_pointer += 1;
+        map[AllocationSemantics::NonMoving] = AllocatorSelector::FreeList(reserved.n_free_list);
+        reserved.n_free_list += 1;
     }
 
     reserved.validate();
     map
 }
 
+/// Apply the allocation routes set by the binding (see [`crate::MMTKBuilder::set_allocation_route`])
+/// to the allocator mapping of a plan.  A semantics that has a route uses the allocator that the
+/// plan uses for the target semantics.  Return an error if a route is not valid for the plan.
+pub(crate) fn route_allocator_mapping<VM: VMBinding>(
+    plan: &dyn Plan<VM = VM>,
+    routes: &EnumMap<AllocationSemantics, Option<AllocationSemantics>>,
+) -> Result<EnumMap<AllocationSemantics, AllocatorSelector>, String> {
+    let plan_mapping = plan.get_allocator_mapping();
+    let default_selector = plan_mapping[AllocationSemantics::Default];
+    let mut map = *plan_mapping;
+    for (semantics, target) in routes.iter() {
+        let Some(target) = *target else {
+            continue;
+        };
+        let selector = plan_mapping[target];
+        if selector == plan_mapping[semantics] {
+            continue;
+        }
+        if semantics == AllocationSemantics::Default {
+            // Plans use the default allocator in mutator prepare and release.
+            return Err(format!(
+                "Default cannot be routed to {target:?}. The default allocator is fixed for each plan."
+            ));
+        }
+        if selector == AllocatorSelector::None {
+            return Err(format!(
+                "{semantics:?} cannot be routed to {target:?}, as the plan has no allocator for {target:?}."
+            ));
+        }
+        if matches!(plan_mapping[semantics], AllocatorSelector::LargeObject(_))
+            && !matches!(selector, AllocatorSelector::LargeObject(_))
+        {
+            return Err(format!(
+                "{semantics:?} cannot be routed to {target:?}, as {target:?} does not allocate large objects."
+            ));
+        }
+        if selector == default_selector && plan.constraints().moves_objects {
+            return Err(format!(
+                "{semantics:?} cannot be routed to {target:?}, as the plan may move objects allocated with {target:?}."
+            ));
+        }
+        map[semantics] = selector;
+    }
+    Ok(map)
+}
+
 /// Create a space mapping for spaces in Common/BasePlan for a plan. A plan should reserve its own allocators.
 ///
 /// # Arguments
@@ -474,12 +774,11 @@ pub(crate) fn create_space_mapping<VM: VMBinding>(
             plan.common().get_los(),
         ));
         reserved.n_large_object += 1;
-        // TODO: This should be freelist allocator once we use marksweep for nonmoving space.
         vec.push((
-            AllocatorSelector::BumpPointer(reserved.n_bump_pointer),
+            AllocatorSelector::FreeList(reserved.n_free_list),
             plan.common().get_nonmoving(),
         ));
-        reserved.n_bump_pointer += 1;
+        reserved.n_free_list += 1;
     }
 
     reserved.validate();
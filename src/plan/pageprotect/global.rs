@@ -11,12 +11,14 @@ use crate::util::alloc::allocators::AllocatorSelector;
 use crate::util::heap::gc_trigger::SpaceStats;
 use crate::util::heap::VMRequest;
 use crate::util::metadata::side_metadata::SideMetadataContext;
+use crate::util::Address;
 use crate::{plan::global::BasePlan, vm::VMBinding};
 use crate::{
     plan::global::CommonPlan, policy::largeobjectspace::LargeObjectSpace,
     util::opaque_pointer::VMWorkerThread,
 };
 use enum_map::EnumMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use mmtk_macros::{HasSpaces, PlanTraceObject};
 
@@ -26,6 +28,8 @@ pub struct PageProtect<VM: VMBinding> {
     pub space: LargeObjectSpace<VM>,
     #[parent]
     pub common: CommonPlan<VM>,
+    /// The number of faults on the pages of dead objects reported by the binding.
+    faults: AtomicUsize,
 }
 
 /// The plan constraints for the page protect plan.
@@ -109,10 +113,34 @@ impl<VM: VMBinding> PageProtect<VM> {
                 true,
             ),
             common: CommonPlan::new(plan_args),
+            faults: AtomicUsize::new(0),
         };
 
         ret.verify_side_metadata_sanity();
 
         ret
     }
+
+    /// Check if a memory access fault at `addr` is in this plan's space, and count it if so.  As
+    /// the pages of live objects are never protected, such a fault is an access to a dead object.
+    /// This does not take any lock, so that it can be called in a signal handler.
+    pub fn report_fault(&self, addr: Address) -> bool {
+        let layout = crate::util::heap::layout::vm_layout::vm_layout();
+        if addr < layout.heap_start || addr >= layout.heap_end || !self.space.address_in_space(addr)
+        {
+            return false;
+        }
+        self.faults.fetch_add(1, Ordering::Relaxed);
+        true
+    }
+
+    /// Get the number of faults on the pages of dead objects reported so far.
+    pub fn fault_count(&self) -> usize {
+        self.faults.load(Ordering::Relaxed)
+    }
+
+    /// Get the number of pages of dead objects that are kept protected and not reused, yet.
+    pub fn quarantined_pages(&self) -> usize {
+        self.space.quarantined_pages()
+    }
 }
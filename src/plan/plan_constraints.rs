@@ -33,6 +33,13 @@ pub struct PlanConstraints {
     /// The barrier this plan uses. A binding may check this and know what kind of write barrier is in use
     /// if they would like to implement the barrier fast path in the binding side.
     pub barrier: BarrierSelector,
+    /// True if a store into an object that the current mutator has just allocated with
+    /// [`crate::AllocationSemantics::Default`], [`crate::AllocationSemantics::Los`] or
+    /// [`crate::AllocationSemantics::LargeCode`] does not need a write barrier, as long as there is
+    /// no GC safepoint between the allocation and the store.  This holds for the object barrier,
+    /// because such objects are not unlogged until they survive a GC.  A compiler can use
+    /// [`crate::memory_manager::barrier_required_for`] instead of checking this directly.
+    pub barrier_elidable_for_new_objects: bool,
     // the following seems unused for now
     /// True if this plan requires linear scanning. This is unused and may be incorrect.
     pub needs_linear_scan: bool,
@@ -45,6 +52,12 @@ pub struct PlanConstraints {
     /// `MutatorConfig::prepare_func`).  Those plans can set this to `false` so that the
     /// `PrepareMutator` work packets will not be created at all.
     pub needs_prepare_mutator: bool,
+    /// True if the plan supports pinning roots, that is, roots reported with
+    /// [`crate::vm::RootsWorkFactory::create_process_pinning_roots_work`] or
+    /// [`crate::vm::RootsWorkFactory::create_process_tpinning_roots_work`].  Plans that do not
+    /// support them use `UnsupportedProcessEdges` as the `PinningProcessEdges` of their work
+    /// contexts.
+    pub supports_pinning_roots: bool,
 }
 
 impl PlanConstraints {
@@ -64,7 +77,9 @@ impl PlanConstraints {
             needs_forward_after_liveness: false,
             needs_log_bit: false,
             barrier: BarrierSelector::NoBarrier,
+            barrier_elidable_for_new_objects: true,
             needs_prepare_mutator: true,
+            supports_pinning_roots: true,
         }
     }
 }
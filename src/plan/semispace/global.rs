@@ -1,4 +1,5 @@
 use super::gc_work::SSGCWorkContext;
+use crate::plan::copy_reserve::CopyReserve;
 use crate::plan::global::CommonPlan;
 use crate::plan::global::CreateGeneralPlanArgs;
 use crate::plan::global::CreateSpecificPlanArgs;
@@ -33,6 +34,8 @@ pub struct SemiSpace<VM: VMBinding> {
     pub copyspace1: CopySpace<VM>,
     #[parent]
     pub common: CommonPlan<VM>,
+    /// How many pages are reserved for copying the objects in the from-space.
+    copy_reserve: CopyReserve,
 }
 
 /// The plan constraints for the semispace plan.
@@ -41,6 +44,7 @@ pub const SS_CONSTRAINTS: PlanConstraints = PlanConstraints {
     max_non_los_default_alloc_bytes:
         crate::plan::plan_constraints::MAX_NON_LOS_ALLOC_BYTES_COPYING_PLAN,
     needs_prepare_mutator: false,
+    supports_pinning_roots: false,
     ..PlanConstraints::default()
 };
 
@@ -53,7 +57,9 @@ impl<VM: VMBinding> Plan for SemiSpace<VM> {
         use enum_map::enum_map;
         CopyConfig {
             copy_mapping: enum_map! {
-                CopySemantics::DefaultCopy => CopySelector::CopySpace(0),
+                CopySemantics::DefaultCopy | CopySemantics::DefaultCopyPartition(_) => {
+                    CopySelector::CopySpace(0)
+                }
                 _ => CopySelector::Unused,
             },
             space_mapping: vec![
@@ -61,10 +67,15 @@ impl<VM: VMBinding> Plan for SemiSpace<VM> {
                 (CopySelector::CopySpace(0), &self.copyspace0),
             ],
             constraints: &SS_CONSTRAINTS,
+            ..Default::default()
         }
     }
 
     fn schedule_collection(&'static self, scheduler: &GCWorkScheduler<VM>) {
+        if self.base().global_state.space_targets().is_some() {
+            crate::plan::space_targeted::schedule_collection(self, scheduler);
+            return;
+        }
         scheduler.schedule_common_work::<SSGCWorkContext<VM>>(self);
     }
 
@@ -73,6 +84,10 @@ impl<VM: VMBinding> Plan for SemiSpace<VM> {
     }
 
     fn prepare(&mut self, tls: VMWorkerThread) {
+        if let Some(targets) = self.base().global_state.space_targets() {
+            self.common.prepare_space_targeted(targets);
+            return;
+        }
         self.common.prepare(tls, true);
 
         self.hi
@@ -91,7 +106,15 @@ impl<VM: VMBinding> Plan for SemiSpace<VM> {
     }
 
     fn release(&mut self, tls: VMWorkerThread) {
+        if let Some(targets) = self.base().global_state.space_targets() {
+            self.common.release_space_targeted(targets);
+            return;
+        }
         self.common.release(tls, true);
+        self.copy_reserve.record_survival(
+            self.tospace().reserved_pages(),
+            self.fromspace().reserved_pages(),
+        );
         // release the collected region
         self.fromspace().release();
     }
@@ -100,12 +123,19 @@ impl<VM: VMBinding> Plan for SemiSpace<VM> {
         self.base().collection_required(self, space_full)
     }
 
+    fn supports_space_targeted_collection(&self, space_name: &str) -> bool {
+        // Unless they are prepared, the copy spaces consider all their objects live, and no
+        // object is moved.
+        self.common.supports_space_targeted_collection(space_name)
+    }
+
     fn current_gc_may_move_object(&self) -> bool {
         true
     }
 
     fn get_collection_reserved_pages(&self) -> usize {
-        self.tospace().reserved_pages()
+        self.copy_reserve
+            .reserved_pages(self.tospace().reserved_pages())
     }
 
     fn get_used_pages(&self) -> usize {
@@ -113,10 +143,11 @@ impl<VM: VMBinding> Plan for SemiSpace<VM> {
     }
 
     fn get_available_pages(&self) -> usize {
-        (self
-            .get_total_pages()
-            .saturating_sub(self.get_reserved_pages()))
-            >> 1
+        // New objects also need to be copied, so only part of the free pages can be allocated.
+        self.copy_reserve.available_pages(
+            self.get_total_pages()
+                .saturating_sub(self.get_reserved_pages()),
+        )
     }
 
     fn base(&self) -> &BasePlan<VM> {
@@ -134,6 +165,7 @@ impl<VM: VMBinding> Plan for SemiSpace<VM> {
 
 impl<VM: VMBinding> SemiSpace<VM> {
     pub fn new(args: CreateGeneralPlanArgs<VM>) -> Self {
+        let copy_reserve = CopyReserve::new(*args.options.elastic_copy_reserve);
         let mut plan_args = CreateSpecificPlanArgs {
             global_args: args,
             constraints: &SS_CONSTRAINTS,
@@ -151,6 +183,7 @@ impl<VM: VMBinding> SemiSpace<VM> {
                 true,
             ),
             common: CommonPlan::new(plan_args),
+            copy_reserve,
         };
 
         res.verify_side_metadata_sanity();
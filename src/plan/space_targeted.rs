@@ -0,0 +1,173 @@
+//! Space-targeted GCs, which only collect some of the spaces of a plan.
+//!
+//! A binding may request a GC that only collects some spaces, such as the large object space, with
+//! [`crate::memory_manager::handle_user_space_targeted_collection_request`].  A space-targeted GC
+//! only prepares and releases the targeted spaces, and the objects in the other spaces are
+//! considered live.  The fields of all the objects in the other spaces are scanned as roots, so
+//! the objects in the targeted spaces they refer to are kept alive.  Only the objects in the
+//! targeted spaces are traced, and no object is moved.
+//!
+//! Finding the objects in the other spaces needs the VO bits, and scanning them costs time
+//! proportional to the size of those spaces.  It is still cheaper than a full-heap GC if only the
+//! targeted spaces need to release memory, as the other spaces are not traced, swept or compacted.
+//!
+//! A plan can only collect a space alone if its other spaces do not need to be prepared for
+//! [`crate::util::ObjectReference::is_live`] to return `true` for all their objects, and they are
+//! not affected by a GC that does not prepare or release them (for example, the remembered sets of
+//! generational plans would be lost).  See
+//! [`crate::plan::Plan::supports_space_targeted_collection`].
+
+use crate::mmtk::SFT_MAP;
+use crate::plan::Plan;
+use crate::policy::sft::GCWorkerMutRef;
+use crate::policy::space::Space;
+use crate::scheduler::gc_work::{ProcessEdgesBase, ScanObjects, SlotOf};
+use crate::scheduler::{GCWork, GCWorkContext, GCWorkScheduler, GCWorker};
+use crate::scheduler::{ProcessEdgesWork, WorkBucketStage};
+use crate::util::heap::space_index;
+use crate::util::object_enum::ClosureObjectEnumerator;
+use crate::util::ObjectReference;
+use crate::vm::VMBinding;
+use crate::MMTK;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+/// A set of spaces, represented by their space indices (see [`crate::util::heap::space_index`]).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct SpaceSet(u128);
+
+impl SpaceSet {
+    #[cfg(feature = "vo_bit")]
+    pub fn insert<VM: VMBinding>(&mut self, space: &dyn Space<VM>) {
+        self.0 |= 1 << space.space_index();
+    }
+
+    pub fn contains_space<VM: VMBinding>(&self, space: &dyn Space<VM>) -> bool {
+        self.contains_index(space.space_index())
+    }
+
+    /// Is `object` in one of the spaces?  This uses the space index of the chunk of `object`, so
+    /// the spaces must acquire their pages from page resources.
+    pub fn contains_object(&self, object: ObjectReference) -> bool {
+        let index = space_index::get(object.to_raw_address());
+        index != space_index::UNKNOWN_SPACE_INDEX && self.contains_index(index)
+    }
+
+    fn contains_index(&self, index: u8) -> bool {
+        self.0 & (1 << index) != 0
+    }
+}
+
+/// Schedule a space-targeted GC for `plan`.  The plan shall call this in
+/// [`Plan::schedule_collection`] if the current GC is a space-targeted GC, and only prepare and
+/// release the targeted spaces in [`Plan::prepare`] and [`Plan::release`].
+pub(crate) fn schedule_collection<P: Plan>(plan: &'static P, scheduler: &GCWorkScheduler<P::VM>) {
+    scheduler.schedule_common_work::<SpaceTargetedGCWorkContext<P>>(plan);
+    scheduler.work_buckets[WorkBucketStage::Closure].add(ScanUntargetedSpaces::<P::VM>::default());
+}
+
+pub(crate) struct SpaceTargetedGCWorkContext<P: Plan>(PhantomData<P>);
+
+unsafe impl<P: Plan> Send for SpaceTargetedGCWorkContext<P> {}
+
+impl<P: Plan> GCWorkContext for SpaceTargetedGCWorkContext<P> {
+    type VM = P::VM;
+    type PlanType = P;
+    type DefaultProcessEdges = SpaceTargetedProcessEdges<P::VM>;
+    // No object is moved in a space-targeted GC.
+    type PinningProcessEdges = SpaceTargetedProcessEdges<P::VM>;
+}
+
+/// Trace the objects in the targeted spaces with the SFT, and ignore the objects in other spaces,
+/// which are considered live.
+pub(crate) struct SpaceTargetedProcessEdges<VM: VMBinding> {
+    base: ProcessEdgesBase<VM>,
+    targets: SpaceSet,
+}
+
+impl<VM: VMBinding> ProcessEdgesWork for SpaceTargetedProcessEdges<VM> {
+    type VM = VM;
+    type ScanObjectsWorkType = ScanObjects<Self>;
+
+    fn new(
+        slots: Vec<SlotOf<Self>>,
+        roots: bool,
+        mmtk: &'static MMTK<VM>,
+        bucket: WorkBucketStage,
+    ) -> Self {
+        let base = ProcessEdgesBase::new(slots, roots, mmtk, bucket);
+        let targets = mmtk
+            .state
+            .space_targets()
+            .expect("The current GC is not space-targeted");
+        Self { base, targets }
+    }
+
+    fn trace_object(&mut self, object: ObjectReference) -> ObjectReference {
+        if !self.targets.contains_object(object) {
+            return object;
+        }
+        let worker = GCWorkerMutRef::new(self.worker());
+        let sft = unsafe { SFT_MAP.get_unchecked(object.to_raw_address()) };
+        sft.sft_trace_object(&mut self.base.nodes, object, worker)
+    }
+
+    fn create_scan_work(&self, nodes: Vec<ObjectReference>) -> ScanObjects<Self> {
+        ScanObjects::<Self>::new(nodes, false, self.bucket)
+    }
+}
+
+impl<VM: VMBinding> Deref for SpaceTargetedProcessEdges<VM> {
+    type Target = ProcessEdgesBase<VM>;
+    fn deref(&self) -> &Self::Target {
+        &self.base
+    }
+}
+
+impl<VM: VMBinding> DerefMut for SpaceTargetedProcessEdges<VM> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.base
+    }
+}
+
+/// Scan the objects in the spaces that are not targeted, so that the objects in the targeted spaces
+/// they refer to are kept alive.
+#[derive(Default)]
+pub(crate) struct ScanUntargetedSpaces<VM: VMBinding>(PhantomData<VM>);
+
+impl<VM: VMBinding> GCWork<VM> for ScanUntargetedSpaces<VM> {
+    fn do_work(&mut self, _worker: &mut GCWorker<VM>, mmtk: &'static MMTK<VM>) {
+        let targets = mmtk.state.space_targets().unwrap();
+        let mut packets: Vec<Box<dyn GCWork<VM>>> = vec![];
+        let mut buffer = vec![];
+        let mut scanned = 0;
+        {
+            let mut enumerator = ClosureObjectEnumerator::<_, VM>::new(|object| {
+                buffer.push(object);
+                scanned += 1;
+                if buffer.len() == <SpaceTargetedProcessEdges<VM> as ProcessEdgesWork>::CAPACITY {
+                    let objects = std::mem::take(&mut buffer);
+                    packets.push(Box::new(ScanObjects::<SpaceTargetedProcessEdges<VM>>::new(
+                        objects,
+                        false,
+                        WorkBucketStage::Closure,
+                    )));
+                }
+            });
+            mmtk.get_plan().for_each_space(&mut |space| {
+                if !targets.contains_space(space) {
+                    space.enumerate_objects(&mut enumerator);
+                }
+            });
+        }
+        if !buffer.is_empty() {
+            packets.push(Box::new(ScanObjects::<SpaceTargetedProcessEdges<VM>>::new(
+                buffer,
+                false,
+                WorkBucketStage::Closure,
+            )));
+        }
+        debug!("Scanning {} objects in spaces not targeted", scanned);
+        mmtk.scheduler.work_buckets[WorkBucketStage::Closure].bulk_add(packets);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::plan::generational::barrier::ModBufs;
 use crate::plan::generational::global::GenerationalPlan;
 use crate::plan::global::CommonPlan;
 use crate::plan::global::CreateGeneralPlanArgs;
@@ -37,6 +38,10 @@ pub struct StickyImmix<VM: VMBinding> {
     gc_full_heap: AtomicBool,
     next_gc_full_heap: AtomicBool,
     full_heap_gc_count: Arc<Mutex<EventCounter>>,
+    /// The blocks mutators acquired between GCs, i.e. the consumed nursery block budget.
+    nursery_blocks_count: Arc<Mutex<EventCounter>>,
+    /// The remembered sets of the generational barrier.
+    modbufs: ModBufs<VM>,
 }
 
 /// The plan constraints for the sticky immix plan.
@@ -58,11 +63,14 @@ impl<VM: VMBinding> Plan for StickyImmix<VM> {
         use enum_map::enum_map;
         CopyConfig {
             copy_mapping: enum_map! {
-                CopySemantics::DefaultCopy => CopySelector::Immix(0),
+                CopySemantics::DefaultCopy | CopySemantics::DefaultCopyPartition(_) => {
+                    CopySelector::Immix(0)
+                }
                 _ => CopySelector::Unused,
             },
             space_mapping: vec![(CopySelector::Immix(0), &self.immix.immix_space)],
             constraints: &STICKY_IMMIX_CONSTRAINTS,
+            ..Default::default()
         }
     }
 
@@ -103,6 +111,7 @@ impl<VM: VMBinding> Plan for StickyImmix<VM> {
                 StickyImmixMatureGCWorkContext<VM, TRACE_KIND_DEFRAG>,
             >(self, &self.immix.immix_space, scheduler);
         }
+        self.modbufs.schedule_scanning::<Self>(scheduler);
     }
 
     fn get_allocator_mapping(
@@ -113,6 +122,10 @@ impl<VM: VMBinding> Plan for StickyImmix<VM> {
     }
 
     fn prepare(&mut self, tls: crate::util::VMWorkerThread) {
+        self.nursery_blocks_count
+            .lock()
+            .unwrap()
+            .inc_by(self.immix.immix_space.mutator_blocks_acquired() as u64);
         if self.is_current_gc_nursery() {
             // Prepare both large object space and immix space
             self.immix.immix_space.prepare(
@@ -147,8 +160,11 @@ impl<VM: VMBinding> Plan for StickyImmix<VM> {
     }
 
     fn collection_required(&self, space_full: bool, space: Option<SpaceStats<Self::VM>>) -> bool {
+        // Young large objects are part of the nursery, too.
         let nursery_full = self.immix.immix_space.get_pages_allocated()
-            > self.base().gc_trigger.get_max_nursery_pages();
+            + self.immix.common().get_los().nursery_pages()
+            > self.base().gc_trigger.get_max_nursery_pages()
+            || self.immix.immix_space.is_mutator_block_budget_exhausted();
         if space_full
             && space.is_some()
             && space.as_ref().unwrap().0.name() != self.immix.immix_space.name()
@@ -229,7 +245,7 @@ impl<VM: VMBinding> GenerationalPlan for StickyImmix<VM> {
     }
 
     fn get_mature_reserved_pages(&self) -> usize {
-        self.immix.immix_space.reserved_pages()
+        self.immix.immix_space.reserved_pages() + self.immix.common().get_los().mature_pages()
     }
 
     fn force_full_heap_collection(&self) {
@@ -306,11 +322,18 @@ impl<VM: VMBinding> crate::plan::generational::global::GenerationalPlanExt<VM> f
 
         object
     }
+
+    fn modbufs(&self) -> &ModBufs<VM> {
+        &self.modbufs
+    }
 }
 
 impl<VM: VMBinding> StickyImmix<VM> {
     pub fn new(args: CreateGeneralPlanArgs<VM>) -> Self {
         let full_heap_gc_count = args.stats.new_event_counter("majorGC", true, true);
+        let nursery_blocks_count = args.stats.new_event_counter("nurseryBlocks", true, true);
+        let nursery_blocks = *args.options.sticky_immix_nursery_blocks;
+        let modbufs = ModBufs::new(args.scheduler.num_workers(), args.stats);
         let plan_args = CreateSpecificPlanArgs {
             global_args: args,
             constraints: &STICKY_IMMIX_CONSTRAINTS,
@@ -332,6 +355,12 @@ impl<VM: VMBinding> StickyImmix<VM> {
                 // In StickyImmix, both young and old objects are allocated in the ImmixSpace.
                 #[cfg(feature = "vo_bit")]
                 mixed_age: true,
+                // Nursery GCs may move young objects.
+                concurrent_sweeping: false,
+                concurrent_defrag_preparation: false,
+                // Mutators allocate young objects into any blocks, so the block budget bounds the
+                // nursery.
+                mutator_block_budget: nursery_blocks,
             },
         );
         Self {
@@ -339,6 +368,8 @@ impl<VM: VMBinding> StickyImmix<VM> {
             gc_full_heap: AtomicBool::new(false),
             next_gc_full_heap: AtomicBool::new(false),
             full_heap_gc_count,
+            nursery_blocks_count,
+            modbufs,
         }
     }
 
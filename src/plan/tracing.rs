@@ -3,8 +3,14 @@
 
 use crate::scheduler::gc_work::{ProcessEdgesWork, SlotOf};
 use crate::scheduler::{GCWorker, WorkBucketStage};
+use crate::util::statistics::counter::EventCounter;
+use crate::util::statistics::stats::Stats;
 use crate::util::ObjectReference;
-use crate::vm::SlotVisitor;
+use crate::vm::slot::Slot;
+use crate::vm::{SlotVisitor, VMBinding};
+use crate::MMTK;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
 
 /// This trait represents an object queue to enqueue objects during tracing.
 pub trait ObjectQueue {
@@ -25,7 +31,7 @@ pub struct VectorQueue<T> {
 
 impl<T> VectorQueue<T> {
     /// Reserve a capacity of this on first enqueue to avoid frequent resizing.
-    const CAPACITY: usize = 4096;
+    pub(crate) const CAPACITY: usize = 4096;
 
     /// Create an empty `VectorObjectQueue`.
     pub fn new() -> Self {
@@ -77,11 +83,127 @@ impl ObjectQueue for VectorQueue<ObjectReference> {
     }
 }
 
+/// A hasher for [`SlotFilter`].  Slots are usually addresses, so a multiplicative hash is good
+/// enough, and much cheaper than the default hasher.
+#[derive(Default)]
+struct SlotHasher(u64);
+
+impl SlotHasher {
+    const MULTIPLIER: u64 = 0x9e37_79b9_7f4a_7c15;
+
+    fn add(&mut self, value: u64) {
+        self.0 = (self.0.rotate_left(5) ^ value).wrapping_mul(Self::MULTIPLIER);
+    }
+}
+
+impl Hasher for SlotHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.add(*byte as u64);
+        }
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.add(value);
+    }
+
+    fn write_usize(&mut self, value: usize) {
+        self.add(value as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// A per-worker filter that suppresses slots which the worker has already visited in the current
+/// trace, for plans that may trace duplicate slots (see
+/// [`crate::plan::PlanConstraints::may_trace_duplicate_edges`]).  It is enabled by the option
+/// `deduplicate_slots`.
+///
+/// The filter is a direct-mapped cache of recently visited slots, so it only catches a duplicate if
+/// no other slot with the same hash is visited in between.  Tracing a slot twice is harmless, so
+/// missing duplicates is fine, but a slot must never be suppressed across traces.  The filter is
+/// cleared when each GC prepares and releases the workers.
+pub(crate) struct SlotFilter<S: Slot> {
+    entries: Box<[Option<S>]>,
+    /// The number of slots checked since the counts were last taken.
+    lookups: usize,
+    /// The number of slots suppressed since the counts were last taken.
+    hits: usize,
+}
+
+impl<S: Slot> SlotFilter<S> {
+    const LOG_ENTRIES: usize = 10;
+
+    pub fn new() -> Self {
+        Self {
+            entries: vec![None; 1 << Self::LOG_ENTRIES].into_boxed_slice(),
+            lookups: 0,
+            hits: 0,
+        }
+    }
+
+    /// Return `true` if `slot` has been visited since the filter was cleared.  Otherwise, remember
+    /// `slot` and return `false`.
+    pub fn is_duplicate(&mut self, slot: S) -> bool {
+        let mut hasher = SlotHasher::default();
+        slot.hash(&mut hasher);
+        let index = (hasher.finish() >> (64 - Self::LOG_ENTRIES)) as usize;
+        self.lookups += 1;
+        if self.entries[index] == Some(slot) {
+            self.hits += 1;
+            true
+        } else {
+            self.entries[index] = Some(slot);
+            false
+        }
+    }
+
+    /// Forget all the visited slots.
+    pub fn clear(&mut self) {
+        self.entries.fill(None);
+    }
+
+    /// Return the numbers of lookups and hits, and reset them.
+    pub fn take_counts(&mut self) -> (usize, usize) {
+        let counts = (self.lookups, self.hits);
+        self.lookups = 0;
+        self.hits = 0;
+        counts
+    }
+}
+
+/// The statistics counters of the [`SlotFilter`]s of all workers.  The hit rate of the filters is
+/// `slotFilter.hits / slotFilter.lookups`.
+pub(crate) struct SlotFilterCounters {
+    lookups: Arc<Mutex<EventCounter>>,
+    hits: Arc<Mutex<EventCounter>>,
+}
+
+impl SlotFilterCounters {
+    pub fn new(stats: &Stats) -> Self {
+        Self {
+            lookups: stats.new_event_counter("slotFilter.lookups", true, true),
+            hits: stats.new_event_counter("slotFilter.hits", true, true),
+        }
+    }
+
+    /// Add the counts of a worker's filter.  This is called when each worker is released.
+    pub fn add<S: Slot>(&self, filter: &mut SlotFilter<S>) {
+        let (lookups, hits) = filter.take_counts();
+        self.lookups.lock().unwrap().inc_by(lookups as u64);
+        self.hits.lock().unwrap().inc_by(hits as u64);
+    }
+}
+
 /// A transitive closure visitor to collect the slots from objects.
 /// It maintains a buffer for the slots, and flushes slots to a new work packet
 /// if the buffer is full or if the type gets dropped.
 pub struct ObjectsClosure<'a, E: ProcessEdgesWork> {
     buffer: VectorQueue<SlotOf<E>>,
+    /// Weak slots to be recorded in the `WeakSlotProcessor` when this closure is dropped.
+    weak_slots: Vec<SlotOf<E>>,
     pub(crate) worker: &'a mut GCWorker<E::VM>,
     bucket: WorkBucketStage,
 }
@@ -95,6 +217,7 @@ impl<'a, E: ProcessEdgesWork> ObjectsClosure<'a, E> {
     pub fn new(worker: &'a mut GCWorker<E::VM>, bucket: WorkBucketStage) -> Self {
         Self {
             buffer: VectorQueue::new(),
+            weak_slots: vec![],
             worker,
             bucket,
         }
@@ -102,7 +225,17 @@ impl<'a, E: ProcessEdgesWork> ObjectsClosure<'a, E> {
 
     fn flush(&mut self) {
         let buf = self.buffer.take();
-        if !buf.is_empty() {
+        if buf.is_empty() {
+            return;
+        }
+        let mmtk = self.worker.mmtk;
+        let overflow = &mmtk.scheduler.tracing_overflow;
+        if overflow.should_spill(&mmtk.scheduler, self.bucket) {
+            let bucket = self.bucket;
+            overflow.spill(bucket, buf, move |buf| {
+                Box::new(E::new(buf, false, mmtk, bucket))
+            });
+        } else {
             self.worker.add_work(
                 self.bucket,
                 E::new(buf, false, self.worker.mmtk, self.bucket),
@@ -114,23 +247,108 @@ impl<'a, E: ProcessEdgesWork> ObjectsClosure<'a, E> {
 impl<E: ProcessEdgesWork> SlotVisitor<SlotOf<E>> for ObjectsClosure<'_, E> {
     fn visit_slot(&mut self, slot: SlotOf<E>) {
         #[cfg(debug_assertions)]
-        {
-            use crate::vm::slot::Slot;
-            trace!(
-                "(ObjectsClosure) Visit slot {:?} (pointing to {:?})",
-                slot,
-                slot.load()
-            );
+        trace!(
+            "(ObjectsClosure) Visit slot {:?} (pointing to {:?})",
+            slot,
+            slot.load()
+        );
+        if let Some(filter) = self.worker.slot_filter.as_mut() {
+            if filter.is_duplicate(slot) {
+                return;
+            }
         }
         self.buffer.push(slot);
         if self.buffer.is_full() {
             self.flush();
         }
     }
+
+    fn visit_weak_slot(&mut self, slot: SlotOf<E>) {
+        if self.worker.mmtk.weak_slot_processor.is_accepting() {
+            self.weak_slots.push(slot);
+        } else {
+            // The weak slots have been processed in this GC. Treat it as a strong slot.
+            self.visit_slot(slot);
+        }
+    }
 }
 
 impl<E: ProcessEdgesWork> Drop for ObjectsClosure<'_, E> {
     fn drop(&mut self) {
         self.flush();
+        if !self.weak_slots.is_empty() {
+            self.worker
+                .mmtk
+                .weak_slot_processor
+                .add_slots(&mut self.weak_slots);
+        }
+    }
+}
+
+/// A slot visitor that collects the slots of objects into a vector, so that the caller can process
+/// them immediately instead of creating work packets for them.  Weak slots are recorded in the
+/// `WeakSlotProcessor` the same way as [`ObjectsClosure`] does.
+pub(crate) struct SlotCollector<'a, VM: VMBinding> {
+    pub slots: Vec<VM::VMSlot>,
+    weak_slots: Vec<VM::VMSlot>,
+    mmtk: &'a MMTK<VM>,
+}
+
+impl<'a, VM: VMBinding> SlotCollector<'a, VM> {
+    pub fn new(mmtk: &'a MMTK<VM>) -> Self {
+        Self {
+            slots: vec![],
+            weak_slots: vec![],
+            mmtk,
+        }
+    }
+}
+
+impl<VM: VMBinding> SlotVisitor<VM::VMSlot> for SlotCollector<'_, VM> {
+    fn visit_slot(&mut self, slot: VM::VMSlot) {
+        self.slots.push(slot);
+    }
+
+    fn visit_weak_slot(&mut self, slot: VM::VMSlot) {
+        if self.mmtk.weak_slot_processor.is_accepting() {
+            self.weak_slots.push(slot);
+        } else {
+            self.visit_slot(slot);
+        }
+    }
+}
+
+impl<VM: VMBinding> Drop for SlotCollector<'_, VM> {
+    fn drop(&mut self) {
+        if !self.weak_slots.is_empty() {
+            self.mmtk
+                .weak_slot_processor
+                .add_slots(&mut self.weak_slots);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::Address;
+
+    #[test]
+    fn slot_filter_suppresses_duplicates() {
+        let mut filter = SlotFilter::<Address>::new();
+        let slot1 = unsafe { Address::from_usize(0x1000_0000) };
+        let slot2 = slot1 + 8usize;
+
+        assert!(!filter.is_duplicate(slot1));
+        assert!(!filter.is_duplicate(slot2));
+        assert!(filter.is_duplicate(slot1));
+        assert!(filter.is_duplicate(slot2));
+        assert_eq!(filter.take_counts(), (4, 2));
+        assert_eq!(filter.take_counts(), (0, 0));
+
+        // Slots visited before the filter is cleared are not duplicates.
+        filter.clear();
+        assert!(!filter.is_duplicate(slot1));
+        assert_eq!(filter.take_counts(), (1, 0));
     }
 }
@@ -2,18 +2,19 @@ use crate::plan::{ObjectQueue, VectorObjectQueue};
 use crate::policy::copy_context::PolicyCopyContext;
 use crate::policy::gc_work::TRACE_KIND_TRANSITIVE_PIN;
 use crate::policy::sft::GCWorkerMutRef;
+use crate::policy::sft::PolicyKind;
 use crate::policy::sft::SFT;
 use crate::policy::space::{CommonSpace, Space};
 use crate::scheduler::GCWorker;
 use crate::util::alloc::allocator::AllocatorContext;
 use crate::util::heap::{MonotonePageResource, PageResource};
+use crate::util::memory::{self, MmapProtection};
 use crate::util::metadata::{extract_side_metadata, MetadataSpec};
 use crate::util::object_enum::ObjectEnumerator;
 use crate::util::object_forwarding;
 use crate::util::{copy::*, object_enum};
 use crate::util::{Address, ObjectReference};
 use crate::vm::*;
-use libc::{mprotect, PROT_EXEC, PROT_NONE, PROT_READ, PROT_WRITE};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
@@ -28,6 +29,12 @@ impl<VM: VMBinding> SFT for CopySpace<VM> {
     fn name(&self) -> &'static str {
         self.get_name()
     }
+    fn policy_kind(&self) -> PolicyKind {
+        PolicyKind::CopySpace
+    }
+    fn describe_object_metadata(&self, object: ObjectReference) -> Vec<String> {
+        vec![crate::util::metadata::describe::forwarding::<VM>(object)]
+    }
 
     fn is_live(&self, object: ObjectReference) -> bool {
         !self.is_from_space() || object_forwarding::is_forwarded::<VM>(object)
@@ -188,6 +195,12 @@ impl<VM: VMBinding> CopySpace<VM> {
     }
 
     pub fn release(&self) {
+        probe!(
+            mmtk,
+            sweep_space_begin,
+            self.get_name().as_ptr(),
+            self.get_name().len()
+        );
         for (start, size) in self.pr.iterate_allocated_regions() {
             // Clear the forwarding bits if it is on the side.
             if let MetadataSpec::OnSide(side_forwarding_status_table) =
@@ -199,12 +212,23 @@ impl<VM: VMBinding> CopySpace<VM> {
             // Clear VO bits because all objects in the space are dead.
             #[cfg(feature = "vo_bit")]
             crate::util::metadata::vo_bit::bzero_vo_bit(start, size);
+
+            if *self.common.options.poison_on_free {
+                crate::util::memory::poison(start, size);
+            }
+            crate::util::memory::mark_noaccess(start, size);
         }
 
         unsafe {
             self.pr.reset();
         }
         self.from_space.store(false, Ordering::SeqCst);
+        probe!(
+            mmtk,
+            sweep_space_end,
+            self.get_name().as_ptr(),
+            self.get_name().len()
+        );
     }
 
     fn is_from_space(&self) -> bool {
@@ -274,9 +298,7 @@ impl<VM: VMBinding> CopySpace<VM> {
         }
         let start = self.common().start;
         let extent = self.common().extent;
-        unsafe {
-            mprotect(start.to_mut_ptr(), extent, PROT_NONE);
-        }
+        memory::mprotect(start, extent).unwrap();
         trace!("Protect {:x} {:x}", start, start + extent);
     }
 
@@ -289,13 +311,7 @@ impl<VM: VMBinding> CopySpace<VM> {
         }
         let start = self.common().start;
         let extent = self.common().extent;
-        unsafe {
-            mprotect(
-                start.to_mut_ptr(),
-                extent,
-                PROT_READ | PROT_WRITE | PROT_EXEC,
-            );
-        }
+        memory::munprotect(start, extent, MmapProtection::ReadWriteExec).unwrap();
         trace!("Unprotect {:x} {:x}", start, start + extent);
     }
 }
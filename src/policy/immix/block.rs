@@ -1,6 +1,7 @@
 use super::defrag::Histogram;
 use super::line::Line;
 use super::ImmixSpace;
+use crate::policy::space::Space;
 use crate::util::constants::*;
 use crate::util::heap::blockpageresource::BlockPool;
 use crate::util::heap::chunk_map::Chunk;
@@ -13,6 +14,7 @@ use crate::util::metadata::MetadataSpec;
 use crate::util::object_enum::BlockMayHaveObjects;
 use crate::util::Address;
 use crate::vm::*;
+use mmtk_macros::Region;
 use std::sync::atomic::Ordering;
 
 /// The block allocation state.
@@ -68,25 +70,13 @@ impl BlockState {
 
 /// Data structure to reference an immix block.
 #[repr(transparent)]
-#[derive(Debug, Clone, Copy, PartialOrd, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialOrd, PartialEq, Region)]
+#[cfg_attr(not(feature = "immix_smaller_block"), region(log_bytes = 15))]
+#[cfg_attr(feature = "immix_smaller_block", region(log_bytes = 13))]
+#[region(parent = Chunk)]
+#[region(metadata = [Block::DEFRAG_STATE_TABLE, Block::MARK_TABLE, Block::LIVE_BYTES_TABLE])]
 pub struct Block(Address);
 
-impl Region for Block {
-    #[cfg(not(feature = "immix_smaller_block"))]
-    const LOG_BYTES: usize = 15;
-    #[cfg(feature = "immix_smaller_block")]
-    const LOG_BYTES: usize = 13;
-
-    fn from_aligned_address(address: Address) -> Self {
-        debug_assert!(address.is_aligned_to(Self::BYTES));
-        Self(address)
-    }
-
-    fn start(&self) -> Address {
-        self.0
-    }
-}
-
 impl BlockMayHaveObjects for Block {
     fn may_have_objects(&self) -> bool {
         self.get_state() != BlockState::Unallocated
@@ -111,10 +101,9 @@ impl Block {
     pub const MARK_TABLE: SideMetadataSpec =
         crate::util::metadata::side_metadata::spec_defs::IX_BLOCK_MARK;
 
-    /// Get the chunk containing the block.
-    pub fn chunk(&self) -> Chunk {
-        Chunk::from_unaligned_address(self.0)
-    }
+    /// Block live bytes table (side)
+    pub const LIVE_BYTES_TABLE: SideMetadataSpec =
+        crate::util::metadata::side_metadata::spec_defs::IX_BLOCK_LIVE_BYTES;
 
     /// Get the address range of the block's line mark table.
     #[allow(clippy::assertions_on_constants)]
@@ -165,6 +154,26 @@ impl Block {
         byte as usize
     }
 
+    /// Get the approximate number of bytes of the objects in the block that are live in the
+    /// current (or the last) GC.
+    pub fn live_bytes(&self) -> usize {
+        Self::LIVE_BYTES_TABLE.load_atomic::<u32>(self.start(), Ordering::Relaxed) as usize
+    }
+
+    /// Count a live object of `bytes` bytes in the block.
+    pub fn inc_live_bytes(&self, bytes: usize) {
+        Self::LIVE_BYTES_TABLE.fetch_add_atomic::<u32>(
+            self.start(),
+            bytes as u32,
+            Ordering::Relaxed,
+        );
+    }
+
+    /// Reset the number of live bytes before a major GC.
+    pub fn reset_live_bytes(&self) {
+        Self::LIVE_BYTES_TABLE.store_atomic::<u32>(self.start(), 0, Ordering::Relaxed);
+    }
+
     /// Initialize a clean block after acquired from page-resource.
     pub fn init(&self, copy: bool) {
         self.set_state(if copy {
@@ -173,6 +182,7 @@ impl Block {
             BlockState::Unmarked
         });
         Self::DEFRAG_STATE_TABLE.store_atomic::<u8>(self.start(), 0, Ordering::SeqCst);
+        self.reset_live_bytes();
     }
 
     /// Deinitalize a block before releasing.
@@ -192,7 +202,7 @@ impl Block {
     #[allow(clippy::assertions_on_constants)]
     pub fn lines(&self) -> RegionIterator<Line> {
         debug_assert!(!super::BLOCK_ONLY);
-        RegionIterator::<Line>::new(self.start_line(), self.end_line())
+        self.iter_region::<Line>()
     }
 
     /// Sweep this block.
@@ -238,6 +248,7 @@ impl Block {
             let mut holes = 0;
             let mut prev_line_is_marked = true;
             let line_mark_state = line_mark_state.unwrap();
+            let poison = *space.common().options.poison_on_free;
 
             for line in self.lines() {
                 if line.is_marked(line_mark_state) {
@@ -250,6 +261,10 @@ impl Block {
 
                     #[cfg(feature = "immix_zero_on_release")]
                     crate::util::memory::zero(line.start(), Line::BYTES);
+                    if poison {
+                        crate::util::memory::poison(line.start(), Line::BYTES);
+                    }
+                    crate::util::memory::mark_noaccess(line.start(), Line::BYTES);
 
                     // We need to clear the pin bit if it is on the side, as this line can be reused
                     #[cfg(feature = "object_pinning")]
@@ -280,8 +295,12 @@ impl Block {
                     // Clear mark state.
                     self.set_state(BlockState::Unmarked);
                 }
-                // Update mark_histogram
-                mark_histogram[holes] += marked_lines;
+                // Update mark_histogram with the bytes to evacuate from the block.  A marked line
+                // may only hold a few live bytes, so the marked lines overestimate the live data
+                // of blocks with many small objects.  The live bytes are approximate, so we never
+                // count more than the marked lines.
+                mark_histogram[holes] +=
+                    usize::min(self.live_bytes(), marked_lines << Line::LOG_BYTES);
                 // Record number of holes in block side metadata.
                 self.set_holes(holes);
 
@@ -369,4 +388,9 @@ impl ReusableBlockPool {
     pub fn flush_all(&self) {
         self.queue.flush_all();
     }
+
+    /// Flush the block queue, and sort the blocks in the order of their addresses.
+    pub fn flush_all_sorted(&self) {
+        self.queue.flush_all_sorted();
+    }
 }
@@ -17,12 +17,16 @@ pub struct Defrag {
     in_defrag_collection: AtomicBool,
     /// Is defrag space exhausted?
     defrag_space_exhausted: AtomicBool,
-    /// A list of completed mark histograms reported by workers
+    /// A list of completed mark histograms reported by workers.  Each histogram holds the live
+    /// bytes of the blocks swept in the last GC, indexed by the number of holes in the block.
     pub mark_histograms: Mutex<Vec<Histogram>>,
     /// A block with number of holes greater than this threshold will be defragmented.
     pub defrag_spill_threshold: AtomicUsize,
     /// The number of remaining clean pages in defrag space.
     available_clean_pages_for_defrag: AtomicUsize,
+    /// Whether the defrag sources for the next GC have been selected before the GC (see
+    /// [`Defrag::select_threshold_before_gc`]).
+    sources_selected: AtomicBool,
 }
 
 pub struct StatsForDefrag {
@@ -57,6 +61,18 @@ impl Defrag {
         self.mark_histograms.lock().push(histogram)
     }
 
+    /// Get the live bytes of the blocks swept in the last GC, indexed by the number of holes in the
+    /// block.  This sums the histograms of all the workers.
+    pub fn live_bytes_histogram(&self) -> Vec<usize> {
+        let mut histogram = self.new_histogram();
+        for h in self.mark_histograms.lock().iter() {
+            for (sum, bytes) in histogram.iter_mut().zip(h.iter()) {
+                *sum += bytes;
+            }
+        }
+        histogram.to_vec()
+    }
+
     /// Check if the current GC is a defrag GC.
     pub fn in_defrag(&self) -> bool {
         self.in_defrag_collection.load(Ordering::Acquire)
@@ -115,14 +131,38 @@ impl Defrag {
         }
     }
 
-    /// Prepare work. Should be called in ImmixSpace::prepare.
+    /// Prepare work. Should be called in ImmixSpace::prepare.  `sources_selected` is true if the
+    /// defrag sources have been selected before the GC, in which case we reuse the threshold.
     #[allow(clippy::assertions_on_constants)]
-    pub fn prepare<VM: VMBinding>(&self, space: &ImmixSpace<VM>, plan_stats: StatsForDefrag) {
+    pub fn prepare<VM: VMBinding>(
+        &self,
+        space: &ImmixSpace<VM>,
+        plan_stats: StatsForDefrag,
+        sources_selected: bool,
+    ) {
         debug_assert!(super::DEFRAG);
         self.defrag_space_exhausted.store(false, Ordering::Release);
 
         // Calculate available free space for defragmentation.
+        let available_clean_pages_for_defrag =
+            self.update_available_clean_pages(space, &plan_stats);
+
+        if self.in_defrag() && !sources_selected {
+            self.establish_defrag_spill_threshold(space)
+        }
+
+        self.available_clean_pages_for_defrag.store(
+            available_clean_pages_for_defrag + plan_stats.collection_reserved_pages,
+            Ordering::Release,
+        );
+    }
 
+    /// Calculate the free space available for defragmentation, and return the number of pages.
+    fn update_available_clean_pages<VM: VMBinding>(
+        &self,
+        space: &ImmixSpace<VM>,
+        plan_stats: &StatsForDefrag,
+    ) -> usize {
         let mut available_clean_pages_for_defrag = plan_stats.total_pages as isize
             - plan_stats.reserved_pages as isize
             + self.defrag_headroom_pages(space) as isize;
@@ -132,15 +172,29 @@ impl Defrag {
 
         self.available_clean_pages_for_defrag
             .store(available_clean_pages_for_defrag as usize, Ordering::Release);
+        available_clean_pages_for_defrag as usize
+    }
 
-        if self.in_defrag() {
-            self.establish_defrag_spill_threshold(space)
-        }
+    /// Calculate the defrag threshold for the next GC between GCs, with the mark histograms of the
+    /// last GC and the current free space, and return the threshold.  The caller marks the blocks
+    /// above the threshold as defrag sources, so that the prepare stage of the next GC does not
+    /// need to select them if the next GC is a defrag GC.
+    pub fn select_threshold_before_gc<VM: VMBinding>(
+        &self,
+        space: &ImmixSpace<VM>,
+        plan_stats: StatsForDefrag,
+    ) -> usize {
+        debug_assert!(super::DEFRAG);
+        self.update_available_clean_pages(space, &plan_stats);
+        self.establish_defrag_spill_threshold(space);
+        self.sources_selected.store(true, Ordering::SeqCst);
+        self.defrag_spill_threshold.load(Ordering::Acquire)
+    }
 
-        self.available_clean_pages_for_defrag.store(
-            available_clean_pages_for_defrag as usize + plan_stats.collection_reserved_pages,
-            Ordering::Release,
-        );
+    /// Return `true` if the defrag sources have been selected before the current GC, and reset
+    /// the state for the next GC.
+    pub fn take_sources_selected(&self) -> bool {
+        self.sources_selected.swap(false, Ordering::SeqCst)
     }
 
     /// Get the numebr of all the recyclable lines in all the reusable blocks.
@@ -151,11 +205,12 @@ impl Defrag {
     ) -> usize {
         let mut total_available_lines = 0;
         space.reusable_blocks.iterate_blocks(|block| {
-            let bucket = block.get_holes();
             let unavailable_lines = match block.get_state() {
                 BlockState::Reusable { unavailable_lines } => unavailable_lines as usize,
-                s => unreachable!("{:?} {:?}", block, s),
+                // When selecting defrag sources between GCs, a mutator may have taken the block.
+                _ => return,
             };
+            let bucket = block.get_holes();
             let available_lines = Block::LINES - unavailable_lines;
             spill_avail_histograms[bucket] += available_lines;
             total_available_lines += available_lines;
@@ -180,16 +235,15 @@ impl Defrag {
         let mut threshold = Block::LINES >> 1;
         let mark_histograms = self.mark_histograms.lock();
         // Blocks are grouped by buckets, indexed by the number of holes in the block.
-        // `mark_histograms` remembers the number of live lines for each bucket.
+        // `mark_histograms` remembers the number of live bytes for each bucket.
         // Here, reversely iterate all the bucket to find a threshold that all buckets above this
         // threshold can be evacuated, without causing to-space overflow.
         for index in (Self::MIN_SPILL_THRESHOLD..Self::NUM_BINS).rev() {
             threshold = index;
-            // Calculate total number of live lines in this bucket.
-            let this_bucket_mark = mark_histograms
-                .iter()
-                .map(|v| v[threshold] as isize)
-                .sum::<isize>();
+            // Calculate total number of lines needed for the live bytes in this bucket.
+            let this_bucket_bytes = mark_histograms.iter().map(|v| v[threshold]).sum::<usize>();
+            let this_bucket_mark =
+                ((this_bucket_bytes + Line::BYTES - 1) >> Line::LOG_BYTES) as isize;
             // Calculate the number of free lines in this bucket.
             let this_bucket_avail = spill_avail_histograms[threshold] as isize;
             // Update counters
@@ -1,13 +1,16 @@
 use super::defrag::StatsForDefrag;
 use super::line::*;
 use super::{block::*, defrag::Defrag};
+use crate::mmtk::SFT_MAP;
 use crate::plan::VectorObjectQueue;
 use crate::policy::gc_work::{TraceKind, TRACE_KIND_TRANSITIVE_PIN};
 use crate::policy::sft::GCWorkerMutRef;
+use crate::policy::sft::PolicyKind;
 use crate::policy::sft::SFT;
-use crate::policy::sft_map::SFTMap;
+use crate::policy::sft_map::{SFTMap, SFTRawPointer};
 use crate::policy::space::{CommonSpace, Space};
 use crate::util::alloc::allocator::AllocatorContext;
+use crate::util::alloc::AllocationOptions;
 use crate::util::constants::LOG_BYTES_IN_PAGE;
 use crate::util::heap::chunk_map::*;
 use crate::util::heap::BlockPageResource;
@@ -29,7 +32,7 @@ use crate::{
     MMTK,
 };
 use atomic::Ordering;
-use std::sync::{atomic::AtomicU8, atomic::AtomicUsize, Arc};
+use std::sync::{atomic::AtomicBool, atomic::AtomicU8, atomic::AtomicUsize, Arc};
 
 pub(crate) const TRACE_KIND_FAST: TraceKind = 0;
 pub(crate) const TRACE_KIND_DEFRAG: TraceKind = 1;
@@ -49,12 +52,18 @@ pub struct ImmixSpace<VM: VMBinding> {
     pub(super) defrag: Defrag,
     /// How many lines have been consumed since last GC?
     lines_consumed: AtomicUsize,
+    /// How many blocks have mutators acquired since last GC?
+    mutator_blocks_acquired: AtomicUsize,
+    /// How many blocks had mutators acquired before the current (or the last) GC?
+    last_gc_mutator_blocks: AtomicUsize,
     /// Object mark state
     mark_state: u8,
     /// Work packet scheduler
     scheduler: Arc<GCWorkScheduler<VM>>,
     /// Some settings for this space
     space_args: ImmixSpaceArgs,
+    /// Whether the chunks are being swept (or waiting to be swept) concurrently with mutators.
+    sweeping_concurrently: AtomicBool,
 }
 
 /// Some arguments for Immix Space.
@@ -81,6 +90,18 @@ pub struct ImmixSpaceArgs {
     // Currently only used when "vo_bit" is enabled.  Using #[cfg(...)] to eliminate dead code warning.
     #[cfg(feature = "vo_bit")]
     pub mixed_age: bool,
+    /// Sweep the chunks concurrently with mutators after a GC, unless objects are moved in the GC.
+    /// The plan needs to call [`ImmixSpace::schedule_concurrent_sweeping`] after the GC.
+    pub concurrent_sweeping: bool,
+    /// Select the defrag sources for the next GC concurrently with mutators after the chunks are
+    /// swept.  The plan needs to call [`ImmixSpace::schedule_concurrent_sweeping`] after the GC.
+    pub concurrent_defrag_preparation: bool,
+    /// The number of blocks (clean or reusable) mutators may acquire between two GCs.  When the
+    /// budget is used up, mutators no longer get reusable blocks, and acquiring a clean block
+    /// polls the GC trigger, so that the plan can trigger a GC in
+    /// [`crate::plan::Plan::collection_required`] with
+    /// [`ImmixSpace::is_mutator_block_budget_exhausted`].  0 means no budget.
+    pub mutator_block_budget: usize,
 }
 
 unsafe impl<VM: VMBinding> Sync for ImmixSpace<VM> {}
@@ -89,6 +110,41 @@ impl<VM: VMBinding> SFT for ImmixSpace<VM> {
     fn name(&self) -> &'static str {
         self.get_name()
     }
+    fn policy_kind(&self) -> PolicyKind {
+        PolicyKind::ImmixSpace
+    }
+    fn describe_object_metadata(&self, object: ObjectReference) -> Vec<String> {
+        use crate::util::metadata::describe;
+        let block = Block::containing(object);
+        #[allow(unused_mut)]
+        let mut lines = vec![
+            describe::mark_bit::<VM>(object),
+            describe::forwarding::<VM>(object),
+        ];
+        #[cfg(feature = "object_pinning")]
+        lines.push(describe::pin_bit::<VM>(object));
+        lines.push(format!(
+            "chunk {:?}: {:?}",
+            block.chunk(),
+            self.chunk_map.get(block.chunk())
+        ));
+        lines.push(format!(
+            "block {:?}: {:?}, defrag source: {}",
+            block,
+            block.get_state(),
+            block.is_defrag_source()
+        ));
+        if !super::BLOCK_ONLY {
+            let line = Line::containing(object);
+            lines.push(format!(
+                "line {} in block: mark {} (current line mark state: {})",
+                line.get_index_within_block(),
+                unsafe { Line::MARK_TABLE.load::<u8>(line.start()) },
+                self.line_mark_state.load(Ordering::Acquire)
+            ));
+        }
+        lines
+    }
 
     fn get_forwarded_object(&self, object: ObjectReference) -> Option<ObjectReference> {
         // If we never move objects, look no further.
@@ -165,6 +221,80 @@ impl<VM: VMBinding> SFT for ImmixSpace<VM> {
     }
 }
 
+/// The SFT for the chunks of an [`ImmixSpace`] in which no object moves in the current GC, i.e.
+/// chunks without defrag source blocks in a major GC.  Objects in those chunks are never
+/// forwarded, so `is_live` only needs to check the mark bit.  The space uses this SFT for those
+/// chunks from the preparation of each major GC until the chunks are swept, if the SFT map
+/// supports different SFTs for the same space.
+#[repr(transparent)]
+struct StationaryChunkSFT<VM: VMBinding>(ImmixSpace<VM>);
+
+impl<VM: VMBinding> SFT for StationaryChunkSFT<VM> {
+    fn name(&self) -> &'static str {
+        self.0.name()
+    }
+    fn policy_kind(&self) -> PolicyKind {
+        self.0.policy_kind()
+    }
+    fn sft_variant(&self) -> u8 {
+        1
+    }
+    fn describe_object_metadata(&self, object: ObjectReference) -> Vec<String> {
+        let mut lines = self.0.describe_object_metadata(object);
+        lines.push("in a stationary chunk".to_string());
+        lines
+    }
+    fn get_forwarded_object(&self, _object: ObjectReference) -> Option<ObjectReference> {
+        None
+    }
+    fn is_live(&self, object: ObjectReference) -> bool {
+        self.0.is_marked(object)
+    }
+    #[cfg(feature = "object_pinning")]
+    fn pin_object(&self, object: ObjectReference) -> bool {
+        self.0.pin_object(object)
+    }
+    #[cfg(feature = "object_pinning")]
+    fn unpin_object(&self, object: ObjectReference) -> bool {
+        self.0.unpin_object(object)
+    }
+    #[cfg(feature = "object_pinning")]
+    fn is_object_pinned(&self, object: ObjectReference) -> bool {
+        self.0.is_object_pinned(object)
+    }
+    fn is_movable(&self) -> bool {
+        self.0.is_movable()
+    }
+    #[cfg(feature = "sanity")]
+    fn is_sane(&self) -> bool {
+        self.0.is_sane()
+    }
+    fn initialize_object_metadata(&self, object: ObjectReference, alloc: bool) {
+        self.0.initialize_object_metadata(object, alloc)
+    }
+    #[cfg(feature = "is_mmtk_object")]
+    fn is_mmtk_object(&self, addr: Address) -> Option<ObjectReference> {
+        self.0.is_mmtk_object(addr)
+    }
+    #[cfg(feature = "is_mmtk_object")]
+    fn find_object_from_internal_pointer(
+        &self,
+        ptr: Address,
+        max_search_bytes: usize,
+    ) -> Option<ObjectReference> {
+        self.0
+            .find_object_from_internal_pointer(ptr, max_search_bytes)
+    }
+    fn sft_trace_object(
+        &self,
+        queue: &mut VectorObjectQueue,
+        object: ObjectReference,
+        worker: GCWorkerMutRef,
+    ) -> ObjectReference {
+        self.0.sft_trace_object(queue, object, worker)
+    }
+}
+
 impl<VM: VMBinding> Space<VM> for ImmixSpace<VM> {
     fn as_space(&self) -> &dyn Space<VM> {
         self
@@ -182,6 +312,9 @@ impl<VM: VMBinding> Space<VM> for ImmixSpace<VM> {
         &self.common
     }
     fn initialize_sft(&self, sft_map: &mut dyn SFTMap) {
+        if !super::NEVER_MOVE_OBJECTS {
+            sft_map.notify_space_creation(self.stationary_chunk_sft());
+        }
         self.common().initialize_sft(self.as_sft(), sft_map)
     }
     fn release_multiple_pages(&mut self, _start: Address) {
@@ -256,30 +389,27 @@ impl<VM: VMBinding> ImmixSpace<VM> {
 
     /// Get side metadata specs
     fn side_metadata_specs() -> Vec<SideMetadataSpec> {
-        metadata::extract_side_metadata(&if super::BLOCK_ONLY {
-            vec![
-                MetadataSpec::OnSide(Block::DEFRAG_STATE_TABLE),
-                MetadataSpec::OnSide(Block::MARK_TABLE),
-                MetadataSpec::OnSide(ChunkMap::ALLOC_TABLE),
-                *VM::VMObjectModel::LOCAL_MARK_BIT_SPEC,
-                *VM::VMObjectModel::LOCAL_FORWARDING_BITS_SPEC,
-                *VM::VMObjectModel::LOCAL_FORWARDING_POINTER_SPEC,
-                #[cfg(feature = "object_pinning")]
-                *VM::VMObjectModel::LOCAL_PINNING_BIT_SPEC,
-            ]
-        } else {
-            vec![
-                MetadataSpec::OnSide(Line::MARK_TABLE),
-                MetadataSpec::OnSide(Block::DEFRAG_STATE_TABLE),
-                MetadataSpec::OnSide(Block::MARK_TABLE),
-                MetadataSpec::OnSide(ChunkMap::ALLOC_TABLE),
-                *VM::VMObjectModel::LOCAL_MARK_BIT_SPEC,
-                *VM::VMObjectModel::LOCAL_FORWARDING_BITS_SPEC,
-                *VM::VMObjectModel::LOCAL_FORWARDING_POINTER_SPEC,
-                #[cfg(feature = "object_pinning")]
-                *VM::VMObjectModel::LOCAL_PINNING_BIT_SPEC,
-            ]
-        })
+        let mut specs: Vec<MetadataSpec> = vec![];
+        if !super::BLOCK_ONLY {
+            specs.extend(
+                Line::METADATA_SPECS
+                    .iter()
+                    .map(|s| MetadataSpec::OnSide(*s)),
+            );
+        }
+        specs.extend(
+            Block::METADATA_SPECS
+                .iter()
+                .map(|s| MetadataSpec::OnSide(*s)),
+        );
+        specs.extend([
+            *VM::VMObjectModel::LOCAL_MARK_BIT_SPEC,
+            *VM::VMObjectModel::LOCAL_FORWARDING_BITS_SPEC,
+            *VM::VMObjectModel::LOCAL_FORWARDING_POINTER_SPEC,
+            #[cfg(feature = "object_pinning")]
+            *VM::VMObjectModel::LOCAL_PINNING_BIT_SPEC,
+        ]);
+        metadata::extract_side_metadata(&specs)
     }
 
     pub fn new(
@@ -307,6 +437,7 @@ impl<VM: VMBinding> ImmixSpace<VM> {
         let scheduler = args.scheduler.clone();
         let common =
             CommonSpace::new(args.into_policy_args(true, false, Self::side_metadata_specs()));
+        let space_index = common.space_index;
         ImmixSpace {
             pr: if common.vmrequest.is_discontiguous() {
                 BlockPageResource::new_discontiguous(
@@ -324,21 +455,32 @@ impl<VM: VMBinding> ImmixSpace<VM> {
                 )
             },
             common,
-            chunk_map: ChunkMap::new(),
+            chunk_map: ChunkMap::new(space_index),
             line_mark_state: AtomicU8::new(Line::RESET_MARK_STATE),
             line_unavail_state: AtomicU8::new(Line::RESET_MARK_STATE),
             lines_consumed: AtomicUsize::new(0),
+            mutator_blocks_acquired: AtomicUsize::new(0),
+            last_gc_mutator_blocks: AtomicUsize::new(0),
             reusable_blocks: ReusableBlockPool::new(scheduler.num_workers()),
             defrag: Defrag::default(),
             // Set to the correct mark state when inititialized. We cannot rely on prepare to set it (prepare may get skipped in nursery GCs).
             mark_state: Self::MARKED_STATE,
             scheduler: scheduler.clone(),
             space_args,
+            sweeping_concurrently: AtomicBool::new(false),
         }
     }
 
     /// Flush the thread-local queues in BlockPageResource
     pub fn flush_page_resource(&self) {
+        // With `deterministic_gc`, the blocks are reused in the order of their addresses instead of
+        // the order they are released in.
+        if *self.common.options.deterministic_gc {
+            self.reusable_blocks.flush_all_sorted();
+            #[cfg(target_pointer_width = "64")]
+            self.pr.flush_all_sorted();
+            return;
+        }
         self.reusable_blocks.flush_all();
         #[cfg(target_pointer_width = "64")]
         self.pr.flush_all()
@@ -349,6 +491,28 @@ impl<VM: VMBinding> ImmixSpace<VM> {
         self.defrag.defrag_headroom_pages(self)
     }
 
+    /// Get the SFT for the chunks in which no object moves in the current GC.
+    fn stationary_chunk_sft(&self) -> SFTRawPointer {
+        // Safety: `StationaryChunkSFT` is a transparent wrapper of `ImmixSpace`.
+        let sft = unsafe { &*(self as *const Self as *const StationaryChunkSFT<VM>) };
+        sft as &(dyn SFT + Sync + 'static)
+    }
+
+    /// Use the SFT for stationary chunks for `chunk` if `stationary` is true, or the default SFT
+    /// otherwise.  See [`StationaryChunkSFT`].
+    fn set_chunk_sft(&self, chunk: Chunk, stationary: bool) {
+        if super::NEVER_MOVE_OBJECTS {
+            return;
+        }
+        let sft = if stationary {
+            self.stationary_chunk_sft()
+        } else {
+            self.as_sft() as SFTRawPointer
+        };
+        // Safety: the chunk is allocated by this space, so it has a valid SFT entry.
+        unsafe { SFT_MAP.update_sub_range(sft, chunk.start(), Chunk::BYTES) };
+    }
+
     /// Check if current GC is a defrag GC.
     pub fn in_defrag(&self) -> bool {
         self.defrag.in_defrag()
@@ -380,6 +544,13 @@ impl<VM: VMBinding> ImmixSpace<VM> {
     }
 
     pub fn prepare(&mut self, major_gc: bool, plan_stats: StatsForDefrag) {
+        debug_assert!(
+            !self.sweeping_concurrently.load(Ordering::SeqCst),
+            "The last GC is not swept, yet."
+        );
+        let mutator_blocks = self.mutator_blocks_acquired.swap(0, Ordering::Relaxed);
+        self.last_gc_mutator_blocks
+            .store(mutator_blocks, Ordering::Relaxed);
         if major_gc {
             // Update mark_state
             if VM::VMObjectModel::LOCAL_MARK_BIT_SPEC.is_on_side() {
@@ -390,8 +561,9 @@ impl<VM: VMBinding> ImmixSpace<VM> {
             }
 
             // Prepare defrag info
+            let sources_selected = self.defrag.take_sources_selected();
             if super::DEFRAG {
-                self.defrag.prepare(self, plan_stats);
+                self.defrag.prepare(self, plan_stats, sources_selected);
             }
 
             // Prepare each block for GC
@@ -407,6 +579,7 @@ impl<VM: VMBinding> ImmixSpace<VM> {
                     } else {
                         None
                     },
+                    sources_selected,
                 })
             });
             self.scheduler().work_buckets[WorkBucketStage::Prepare].bulk_add(work_packets);
@@ -472,11 +645,60 @@ impl<VM: VMBinding> ImmixSpace<VM> {
         if !super::BLOCK_ONLY {
             self.reusable_blocks.reset();
         }
+        self.lines_consumed.store(0, Ordering::Relaxed);
+
+        if self.can_sweep_concurrently() {
+            // Sweep chunks and blocks after mutators resume.
+            self.sweeping_concurrently.store(true, Ordering::SeqCst);
+            return;
+        }
         // Sweep chunks and blocks
-        let work_packets = self.generate_sweep_tasks();
+        let work_packets = self.generate_sweep_tasks(false);
         self.scheduler().work_buckets[WorkBucketStage::Release].bulk_add(work_packets);
+    }
 
-        self.lines_consumed.store(0, Ordering::Relaxed);
+    /// Return `true` if the chunks can be swept concurrently with mutators after the current GC.
+    fn can_sweep_concurrently(&self) -> bool {
+        if !self.space_args.concurrent_sweeping || self.defrag.in_defrag() {
+            return false;
+        }
+        // Sweeping would overwrite the VO bits of objects that mutators allocate in the meantime.
+        #[cfg(feature = "vo_bit")]
+        if vo_bit::helper::vo_bits_updated_when_sweeping::<VM>() {
+            return false;
+        }
+        true
+    }
+
+    /// Schedule work packets to sweep the chunks if the last GC left them to be swept concurrently
+    /// with mutators.  Return `true` if any work packet is scheduled.
+    ///
+    /// While the chunks are being swept, mutators only get reusable blocks that have been swept.
+    /// The lines of clean blocks that mutators get in the meantime are marked, so that the sweeper
+    /// does not release them if their chunks are not swept, yet.
+    ///
+    /// If `concurrent_defrag_preparation` is set, this also selects the defrag sources for the next
+    /// GC after the chunks are swept.
+    pub fn schedule_concurrent_sweeping(&'static self, scheduler: &GCWorkScheduler<VM>) -> bool {
+        if !self.sweeping_concurrently.load(Ordering::SeqCst) {
+            if self.space_args.concurrent_defrag_preparation && super::DEFRAG {
+                // We are holding the lock for synchronizing GC workers.  Do not notify now.
+                scheduler.work_buckets[WorkBucketStage::Unconstrained]
+                    .add_no_notify(SelectDefragSources { space: self });
+                return true;
+            }
+            return false;
+        }
+        let work_packets = self.generate_sweep_tasks(true);
+        if work_packets.is_empty() {
+            self.sweeping_concurrently.store(false, Ordering::SeqCst);
+            return false;
+        }
+        for work_packet in work_packets {
+            // We are holding the lock for synchronizing GC workers.  Do not notify now.
+            scheduler.work_buckets[WorkBucketStage::Unconstrained].add_boxed_no_notify(work_packet);
+        }
+        true
     }
 
     /// This is called when a GC finished.
@@ -489,8 +711,20 @@ impl<VM: VMBinding> ImmixSpace<VM> {
         did_defrag
     }
 
-    /// Generate chunk sweep tasks
-    fn generate_sweep_tasks(&self) -> Vec<Box<dyn GCWork<VM>>> {
+    /// Get the approximate live bytes of the blocks swept in the last GC, indexed by the number of
+    /// holes in the block.
+    pub fn live_bytes_histogram(&self) -> Vec<usize> {
+        self.defrag.live_bytes_histogram()
+    }
+
+    /// Generate chunk sweep tasks.  `concurrent` is true if they run concurrently with mutators.
+    fn generate_sweep_tasks(&self, concurrent: bool) -> Vec<Box<dyn GCWork<VM>>> {
+        probe!(
+            mmtk,
+            sweep_space_begin,
+            self.get_name().as_ptr(),
+            self.get_name().len()
+        );
         self.defrag.mark_histograms.lock().clear();
         // # Safety: ImmixSpace reference is always valid within this collection cycle.
         let space = unsafe { &*(self as *const Self) };
@@ -502,28 +736,61 @@ impl<VM: VMBinding> ImmixSpace<VM> {
             Box::new(SweepChunk {
                 space,
                 chunk,
+                concurrent,
                 epilogue: epilogue.clone(),
             })
         });
         epilogue.counter.store(tasks.len(), Ordering::SeqCst);
+        if tasks.is_empty() {
+            probe!(
+                mmtk,
+                sweep_space_end,
+                self.get_name().as_ptr(),
+                self.get_name().len()
+            );
+        }
         tasks
     }
 
     /// Release a block.
     pub fn release_block(&self, block: Block) {
+        if *self.common.options.poison_on_free {
+            crate::util::memory::poison(block.start(), Block::BYTES);
+        }
+        crate::util::memory::mark_noaccess(block.start(), Block::BYTES);
+        if *self.common.options.protect_free_blocks {
+            crate::util::memory::mprotect(block.start(), Block::BYTES).unwrap();
+        }
         block.deinit();
         self.pr.release_block(block);
     }
 
     /// Allocate a clean block.
-    pub fn get_clean_block(&self, tls: VMThread, copy: bool) -> Option<Block> {
-        let block_address = self.acquire(tls, Block::PAGES);
+    pub fn get_clean_block(
+        &self,
+        tls: VMThread,
+        copy: bool,
+        alloc_options: AllocationOptions,
+    ) -> Option<Block> {
+        let block_address = self.acquire(tls, Block::PAGES, alloc_options);
         if block_address.is_zero() {
             return None;
         }
         self.defrag.notify_new_clean_block(copy);
+        if !copy {
+            self.mutator_blocks_acquired.fetch_add(1, Ordering::Relaxed);
+        }
         let block = Block::from_aligned_address(block_address);
-        block.init(copy);
+        // The block may be in a chunk that is not swept, yet.  Mark it (or all of its lines) as
+        // live before it is seen as allocated, so that the sweeper keeps it.
+        let sweeping = self.sweeping_concurrently.load(Ordering::SeqCst);
+        if sweeping && !super::BLOCK_ONLY {
+            let state = self.line_mark_state.load(Ordering::Acquire);
+            for line in block.lines() {
+                line.mark(state);
+            }
+        }
+        block.init(copy || (sweeping && super::BLOCK_ONLY));
         self.chunk_map.set(block.chunk(), ChunkState::Allocated);
         self.lines_consumed
             .fetch_add(Block::LINES, Ordering::SeqCst);
@@ -535,6 +802,10 @@ impl<VM: VMBinding> ImmixSpace<VM> {
         if super::BLOCK_ONLY {
             return None;
         }
+        // Let the mutator acquire a clean block instead, which polls the GC trigger.
+        if !copy && self.is_mutator_block_budget_exhausted() {
+            return None;
+        }
         loop {
             if let Some(block) = self.reusable_blocks.pop() {
                 // Skip blocks that should be evacuated.
@@ -551,6 +822,9 @@ impl<VM: VMBinding> ImmixSpace<VM> {
                     _ => unreachable!("{:?} {:?}", block, block.get_state()),
                 };
                 self.lines_consumed.fetch_add(lines_delta, Ordering::SeqCst);
+                if !copy {
+                    self.mutator_blocks_acquired.fetch_add(1, Ordering::Relaxed);
+                }
 
                 block.init(copy);
                 return Some(block);
@@ -560,6 +834,44 @@ impl<VM: VMBinding> ImmixSpace<VM> {
         }
     }
 
+    /// Return the lines of a block that a mutator has not allocated into to the reusable block
+    /// list, so that other mutators can allocate into them.  `first_unused` is the first line the
+    /// mutator has not allocated into, and the mutator must not allocate into the block again.
+    pub(crate) fn return_unused_lines(&self, first_unused: Line) {
+        debug_assert!(!super::BLOCK_ONLY);
+        // The sweeper may add the block to the reusable block list again if its chunk is not
+        // swept, yet.  In StickyImmix, the lines we mark below would be considered to only
+        // contain old objects in nursery GCs.
+        if self.sweeping_concurrently.load(Ordering::SeqCst) || self.space_args.mixed_age {
+            return;
+        }
+        let block = first_unused.block();
+        let unavail_state = self.line_unavail_state.load(Ordering::Acquire);
+        let current_state = self.line_mark_state.load(Ordering::Acquire);
+        // Hole searching skips marked lines.  The marks become stale in the next GC.
+        for line in RegionIterator::<Line>::new(block.start_line(), first_unused) {
+            line.mark(current_state);
+        }
+        let unavailable_lines = block
+            .lines()
+            .filter(|line| line.is_marked(unavail_state) || line.is_marked(current_state))
+            .count();
+        if unavailable_lines == Block::LINES {
+            return;
+        }
+        trace!(
+            "Return {} unused lines of {:?}",
+            Block::LINES - unavailable_lines,
+            block
+        );
+        block.set_state(BlockState::Reusable {
+            unavailable_lines: unavailable_lines as _,
+        });
+        self.lines_consumed
+            .fetch_sub(Block::LINES - unavailable_lines, Ordering::SeqCst);
+        self.reusable_blocks.push(block);
+    }
+
     /// Trace and mark objects without evacuation.
     pub fn trace_object_without_moving(
         &self,
@@ -705,11 +1017,13 @@ impl<VM: VMBinding> ImmixSpace<VM> {
         }
     }
 
-    /// Mark all the lines that the given object spans.
+    /// Mark all the lines that the given object spans, and count the object in the live bytes of
+    /// its block.  This is called once for each live object in a GC.
     #[allow(clippy::assertions_on_constants)]
     pub fn mark_lines(&self, object: ObjectReference) {
         debug_assert!(!super::BLOCK_ONLY);
         Line::mark_lines_for_object::<VM>(object, self.line_mark_state.load(Ordering::Acquire));
+        Block::containing(object).inc_live_bytes(VM::VMObjectModel::get_current_size(object));
     }
 
     /// Atomically mark an object.
@@ -819,6 +1133,23 @@ impl<VM: VMBinding> ImmixSpace<VM> {
         self.lines_consumed.load(Ordering::SeqCst) >> (LOG_BYTES_IN_PAGE - Line::LOG_BYTES as u8)
     }
 
+    /// Return the number of blocks mutators have acquired since the last GC.
+    pub(crate) fn mutator_blocks_acquired(&self) -> usize {
+        self.mutator_blocks_acquired.load(Ordering::Relaxed)
+    }
+
+    /// Return the number of blocks mutators acquired before the most recent GC.
+    pub fn last_gc_mutator_blocks(&self) -> usize {
+        self.last_gc_mutator_blocks.load(Ordering::Relaxed)
+    }
+
+    /// Return true if mutators have used up the block budget set by
+    /// [`ImmixSpaceArgs::mutator_block_budget`].
+    pub(crate) fn is_mutator_block_budget_exhausted(&self) -> bool {
+        let budget = self.space_args.mutator_block_budget;
+        budget != 0 && self.mutator_blocks_acquired() >= budget
+    }
+
     /// Post copy routine for Immix copy contexts
     fn post_copy(&self, object: ObjectReference, _bytes: usize) {
         // Mark the object
@@ -841,6 +1172,8 @@ pub struct PrepareBlockState<VM: VMBinding> {
     pub space: &'static ImmixSpace<VM>,
     pub chunk: Chunk,
     pub defrag_threshold: Option<usize>,
+    /// Whether the defrag sources were selected before the GC.
+    pub sources_selected: bool,
 }
 
 impl<VM: VMBinding> PrepareBlockState<VM> {
@@ -869,6 +1202,7 @@ impl<VM: VMBinding> GCWork<VM> for PrepareBlockState<VM> {
     fn do_work(&mut self, _worker: &mut GCWorker<VM>, _mmtk: &'static MMTK<VM>) {
         // Clear object mark table for this chunk
         self.reset_object_mark();
+        let mut has_defrag_source = false;
         // Iterate over all blocks in this chunk
         for block in self.chunk.iter_region::<Block>() {
             let state = block.get_state();
@@ -885,17 +1219,27 @@ impl<VM: VMBinding> GCWork<VM> for PrepareBlockState<VM> {
                 true
             } else if let Some(defrag_threshold) = self.defrag_threshold {
                 // This GC is a defrag GC.
-                block.get_holes() > defrag_threshold
+                if self.sources_selected {
+                    // Keep the selection.  Blocks that mutators took after the selection are no
+                    // longer defrag sources, as `Block::init` clears the defrag state.
+                    block.is_defrag_source()
+                } else {
+                    block.get_holes() > defrag_threshold
+                }
             } else {
                 // Not a defrag GC.
                 false
             };
             block.set_as_defrag_source(is_defrag_source);
+            has_defrag_source |= is_defrag_source;
             // Clear block mark data.
             block.set_state(BlockState::Unmarked);
+            block.reset_live_bytes();
             debug_assert!(!block.get_state().is_reusable());
             debug_assert_ne!(block.get_state(), BlockState::Marked);
         }
+        // No object in this chunk moves in this GC unless the chunk has defrag sources.
+        self.space.set_chunk_sft(self.chunk, !has_defrag_source);
     }
 }
 
@@ -903,6 +1247,8 @@ impl<VM: VMBinding> GCWork<VM> for PrepareBlockState<VM> {
 struct SweepChunk<VM: VMBinding> {
     space: &'static ImmixSpace<VM>,
     chunk: Chunk,
+    /// Whether mutators are running while the chunk is swept.
+    concurrent: bool,
     /// A destructor invoked when all `SweepChunk` packets are finished.
     epilogue: Arc<FlushPageResource<VM>>,
 }
@@ -910,6 +1256,8 @@ struct SweepChunk<VM: VMBinding> {
 impl<VM: VMBinding> GCWork<VM> for SweepChunk<VM> {
     fn do_work(&mut self, _worker: &mut GCWorker<VM>, mmtk: &'static MMTK<VM>) {
         assert_eq!(self.space.chunk_map.get(self.chunk), ChunkState::Allocated);
+        // Objects allocated in the chunk after the GC may move in the next GC.
+        self.space.set_chunk_sft(self.chunk, false);
 
         let mut histogram = self.space.defrag.new_histogram();
         let line_mark_state = if super::BLOCK_ONLY {
@@ -958,8 +1306,10 @@ impl<VM: VMBinding> GCWork<VM> for SweepChunk<VM> {
             }
         }
         probe!(mmtk, sweep_chunk, allocated_blocks);
-        // Set this chunk as free if there is not live blocks.
-        if allocated_blocks == 0 {
+        // Set this chunk as free if there is not live blocks.  When sweeping concurrently, a
+        // mutator may get a block in this chunk at any time, so we keep the chunk, and the next GC
+        // will free it if it is still empty.
+        if allocated_blocks == 0 && !self.concurrent {
             self.space.chunk_map.set(self.chunk, ChunkState::Free)
         }
         self.space.defrag.add_completed_mark_histogram(histogram);
@@ -967,6 +1317,51 @@ impl<VM: VMBinding> GCWork<VM> for SweepChunk<VM> {
     }
 }
 
+/// Select the defrag sources for the next GC between GCs.  See
+/// [`Defrag::select_threshold_before_gc`].
+struct SelectDefragSources<VM: VMBinding> {
+    space: &'static ImmixSpace<VM>,
+}
+
+impl<VM: VMBinding> GCWork<VM> for SelectDefragSources<VM> {
+    fn do_work(&mut self, _worker: &mut GCWorker<VM>, mmtk: &'static MMTK<VM>) {
+        let threshold = self
+            .space
+            .defrag
+            .select_threshold_before_gc(self.space, StatsForDefrag::new(mmtk.get_plan()));
+        let space = self.space;
+        let work_packets = space.chunk_map.generate_tasks(|chunk| {
+            Box::new(MarkDefragSources {
+                space,
+                chunk,
+                threshold,
+            })
+        });
+        space.scheduler().work_buckets[WorkBucketStage::Unconstrained].bulk_add(work_packets);
+    }
+}
+
+/// Mark the blocks in a chunk that have more holes than the threshold as defrag sources for the
+/// next GC.  The next GC uses them if it is a defrag GC, and clears them otherwise.
+struct MarkDefragSources<VM: VMBinding> {
+    space: &'static ImmixSpace<VM>,
+    chunk: Chunk,
+    threshold: usize,
+}
+
+impl<VM: VMBinding> GCWork<VM> for MarkDefragSources<VM> {
+    fn do_work(&mut self, _worker: &mut GCWorker<VM>, _mmtk: &'static MMTK<VM>) {
+        debug_assert!(self.space.chunk_map.get(self.chunk) == ChunkState::Allocated);
+        for block in self.chunk.iter_region::<Block>() {
+            // Only reusable blocks have holes.  Mutators may take a block at any time, and a block
+            // that is taken in the middle of this is still a valid defrag source.
+            if block.get_state().is_reusable() && block.get_holes() > self.threshold {
+                block.set_as_defrag_source(true);
+            }
+        }
+    }
+}
+
 /// Count number of remaining work pacets, and flush page resource if all packets are finished.
 struct FlushPageResource<VM: VMBinding> {
     space: &'static ImmixSpace<VM>,
@@ -979,7 +1374,23 @@ impl<VM: VMBinding> FlushPageResource<VM> {
         if 1 == self.counter.fetch_sub(1, Ordering::SeqCst) {
             // We've finished releasing all the dead blocks to the BlockPageResource's thread-local queues.
             // Now flush the BlockPageResource.
-            self.space.flush_page_resource()
+            self.space.flush_page_resource();
+            let concurrent = self
+                .space
+                .sweeping_concurrently
+                .swap(false, Ordering::SeqCst);
+            // The mark histograms and the reusable blocks are ready.  Select the defrag sources
+            // for the next GC if we are not in a GC.
+            if concurrent && self.space.space_args.concurrent_defrag_preparation && super::DEFRAG {
+                self.space.scheduler().work_buckets[WorkBucketStage::Unconstrained]
+                    .add(SelectDefragSources { space: self.space });
+            }
+            probe!(
+                mmtk,
+                sweep_space_end,
+                self.space.get_name().as_ptr(),
+                self.space.get_name().len()
+            );
         }
     }
 }
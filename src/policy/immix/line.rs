@@ -1,31 +1,18 @@
 use super::block::Block;
-use crate::util::linear_scan::{Region, RegionIterator};
+use crate::util::linear_scan::{Region, RegionIterator, SubRegion};
 use crate::util::metadata::side_metadata::SideMetadataSpec;
 use crate::{
     util::{Address, ObjectReference},
     vm::*,
 };
+use mmtk_macros::Region;
 
 /// Data structure to reference a line within an immix block.
 #[repr(transparent)]
-#[derive(Debug, Clone, Copy, PartialOrd, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialOrd, PartialEq, Eq, Region)]
+#[region(log_bytes = 8, parent = Block, metadata = [Line::MARK_TABLE])]
 pub struct Line(Address);
 
-impl Region for Line {
-    const LOG_BYTES: usize = 8;
-
-    #[allow(clippy::assertions_on_constants)] // make sure line is not used when BLOCK_ONLY is turned on.
-    fn from_aligned_address(address: Address) -> Self {
-        debug_assert!(!super::BLOCK_ONLY);
-        debug_assert!(address.is_aligned_to(Self::BYTES));
-        Self(address)
-    }
-
-    fn start(&self) -> Address {
-        self.0
-    }
-}
-
 #[allow(clippy::assertions_on_constants)]
 impl Line {
     pub const RESET_MARK_STATE: u8 = 1;
@@ -38,13 +25,12 @@ impl Line {
     /// Get the block containing the line.
     pub fn block(&self) -> Block {
         debug_assert!(!super::BLOCK_ONLY);
-        Block::from_unaligned_address(self.0)
+        self.parent()
     }
 
     /// Get line index within its containing block.
     pub fn get_index_within_block(&self) -> usize {
-        let addr = self.start();
-        addr.get_extent(Block::align(addr)) >> Line::LOG_BYTES
+        self.index_in_parent()
     }
 
     /// Mark the line. This will update the side line mark table.
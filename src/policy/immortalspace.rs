@@ -1,9 +1,14 @@
 use atomic::Ordering;
+use std::ops::Range;
+use std::sync::atomic::AtomicBool;
+use std::sync::Mutex;
 
+use crate::policy::sft::PolicyKind;
 use crate::policy::sft::SFT;
 use crate::policy::space::{CommonSpace, Space};
 use crate::util::address::Address;
 use crate::util::heap::{MonotonePageResource, PageResource};
+use crate::util::memory::MmapProtection;
 use crate::util::metadata::mark_bit::MarkState;
 
 use crate::util::object_enum::{self, ObjectEnumerator};
@@ -12,7 +17,9 @@ use crate::util::{metadata, ObjectReference};
 use crate::plan::{ObjectQueue, VectorObjectQueue};
 
 use crate::policy::sft::GCWorkerMutRef;
-use crate::vm::{ObjectModel, VMBinding};
+use crate::util::opaque_pointer::VMWorkerThread;
+use crate::vm::slot::Slot;
+use crate::vm::{ObjectModel, RootsWorkFactory, Scanning, VMBinding};
 
 /// This type implements a simple immortal collection
 /// policy. Under this policy all that is required is for the
@@ -24,17 +31,33 @@ pub struct ImmortalSpace<VM: VMBinding> {
     pr: MonotonePageResource<VM>,
     /// Is this used as VM space? If this is used as VM space, we never allocate into this space, but we trace objects normally.
     vm_space: bool,
+    /// Is the space sealed? A sealed space is read-only. We do not allocate into it, and we do not
+    /// mark or scan its objects in GC. See [`ImmortalSpace::seal`].
+    sealed: AtomicBool,
+    /// The objects outside this space that are referenced by objects in this space. They are
+    /// computed once in the first GC after the space is sealed, and are reported as pinning roots
+    /// in every GC, as the references to them cannot be updated.
+    sealed_roots: Mutex<Option<Vec<ObjectReference>>>,
+    /// The address ranges that the VM made executable with [`ImmortalSpace::set_executable`].
+    /// They are not writable, except during GC.
+    executable: Mutex<Vec<Range<Address>>>,
 }
 
 impl<VM: VMBinding> SFT for ImmortalSpace<VM> {
     fn name(&self) -> &'static str {
         self.get_name()
     }
+    fn policy_kind(&self) -> PolicyKind {
+        PolicyKind::ImmortalSpace
+    }
+    fn describe_object_metadata(&self, object: ObjectReference) -> Vec<String> {
+        vec![crate::util::metadata::describe::mark_bit::<VM>(object)]
+    }
     fn is_live(&self, _object: ObjectReference) -> bool {
         true
     }
     fn is_reachable(&self, object: ObjectReference) -> bool {
-        self.mark_state.is_marked::<VM>(object)
+        self.is_sealed() || self.mark_state.is_marked::<VM>(object)
     }
     #[cfg(feature = "object_pinning")]
     fn pin_object(&self, _object: ObjectReference) -> bool {
@@ -155,6 +178,9 @@ impl<VM: VMBinding> ImmortalSpace<VM> {
             },
             common,
             vm_space: false,
+            sealed: AtomicBool::new(false),
+            sealed_roots: Mutex::new(None),
+            executable: Mutex::new(vec![]),
         }
     }
 
@@ -174,12 +200,27 @@ impl<VM: VMBinding> ImmortalSpace<VM> {
                 metadata::extract_side_metadata(&[*VM::VMObjectModel::LOCAL_MARK_BIT_SPEC]),
             )),
             vm_space: true,
+            sealed: AtomicBool::new(false),
+            sealed_roots: Mutex::new(None),
+            executable: Mutex::new(vec![]),
         }
     }
 
     pub fn prepare(&mut self) {
+        // GC may write mark bits in the object headers.
+        for range in self.executable.get_mut().unwrap().iter() {
+            crate::util::memory::munprotect(
+                range.start,
+                range.end - range.start,
+                MmapProtection::ReadWrite,
+            )
+            .unwrap();
+        }
         self.mark_state.on_global_prepare::<VM>();
-        if self.vm_space {
+        if self.is_sealed() {
+            // We never mark objects in a sealed space, and the mark bits may be in the read-only
+            // object headers.
+        } else if self.vm_space {
             // If this is VM space, we never allocate into it, and we should reset the mark bit for the entire space.
             self.mark_state
                 .on_block_reset::<VM>(self.common.start, self.common.extent)
@@ -199,6 +240,14 @@ impl<VM: VMBinding> ImmortalSpace<VM> {
 
     pub fn release(&mut self) {
         self.mark_state.on_global_release::<VM>();
+        for range in self.executable.get_mut().unwrap().iter() {
+            crate::util::memory::munprotect(
+                range.start,
+                range.end - range.start,
+                MmapProtection::ReadExec,
+            )
+            .unwrap();
+        }
     }
 
     pub fn trace_object<Q: ObjectQueue>(
@@ -212,9 +261,139 @@ impl<VM: VMBinding> ImmortalSpace<VM> {
             "{:x}: VO bit not set",
             object
         );
+        // Objects in a sealed space are always live, and the objects they point to are roots.
+        if self.is_sealed() {
+            return object;
+        }
         if self.mark_state.test_and_mark::<VM>(object) {
             queue.enqueue(object);
         }
         object
     }
+
+    /// Seal the space.  The objects in the space become read-only, and the space will not be used
+    /// for allocation any more.
+    ///
+    /// We `mprotect` the allocated memory of the space so that any write to it faults.  In GC, we
+    /// no longer mark or scan objects in the space.  Instead, the objects outside the space that
+    /// are referenced from the space are found once in the first GC after the space is sealed
+    /// (see [`ImmortalSpace::scan_sealed_roots`]), and are pinned as roots in every GC.
+    ///
+    /// This needs the VO bits to find objects in the space, and the plan needs to support pinning
+    /// roots, as the references in the sealed objects cannot be updated.
+    #[cfg(feature = "vo_bit")]
+    pub fn seal(&self) {
+        assert!(
+            !self.vm_space,
+            "Cannot seal {} used as VM space",
+            self.name()
+        );
+        if self.sealed.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        for (start, size) in self.pr.iterate_allocated_regions() {
+            debug!("{}: protect {} to {}", self.name(), start, start + size);
+            crate::util::memory::mprotect_read_only(start, size).unwrap();
+        }
+    }
+
+    /// Make the pages in `range` executable and not writable if `executable` is true, or writable
+    /// and not executable otherwise.  This is only allowed for spaces with execution permission when
+    /// the option `code_space_wx` is enabled, which maps those spaces without execution permission.
+    ///
+    /// The pages stay executable in GC, but are writable (and not executable) while GC is in
+    /// progress, as GC may write mark bits in the object headers.
+    pub fn set_executable(&self, range: Range<Address>, executable: bool) {
+        assert!(
+            self.common.permission_exec && *self.common.options.code_space_wx,
+            "{} cannot be made executable",
+            self.name()
+        );
+        assert!(
+            range
+                .start
+                .is_aligned_to(crate::util::constants::BYTES_IN_PAGE)
+                && range
+                    .end
+                    .is_aligned_to(crate::util::constants::BYTES_IN_PAGE),
+            "{}: {}-{} is not page-aligned",
+            self.name(),
+            range.start,
+            range.end
+        );
+        if range.is_empty() {
+            return;
+        }
+        assert!(
+            self.address_in_space(range.start) && self.address_in_space(range.end - 1usize),
+            "{}: {}-{} is not in the space",
+            self.name(),
+            range.start,
+            range.end
+        );
+
+        let mut ranges = self.executable.lock().unwrap();
+        let prot = if executable {
+            MmapProtection::ReadExec
+        } else {
+            MmapProtection::ReadWrite
+        };
+        crate::util::memory::munprotect(range.start, range.end - range.start, prot).unwrap();
+        // Remove the range from the executable ranges, and add it back if it is executable now.
+        let mut remaining = vec![];
+        for r in ranges.drain(..) {
+            if r.start < range.start {
+                remaining.push(r.start..r.end.min(range.start));
+            }
+            if r.end > range.end {
+                remaining.push(r.start.max(range.end)..r.end);
+            }
+        }
+        if executable {
+            remaining.push(range);
+        }
+        *ranges = remaining;
+    }
+
+    /// Is the space sealed by [`ImmortalSpace::seal`]?
+    pub fn is_sealed(&self) -> bool {
+        self.sealed.load(Ordering::Relaxed)
+    }
+
+    /// Report the objects referenced from a sealed space as pinning roots.  They are computed by
+    /// scanning all the objects in the space when this is called for the first time after sealing.
+    pub(crate) fn scan_sealed_roots(
+        &self,
+        tls: VMWorkerThread,
+        mut factory: impl RootsWorkFactory<VM::VMSlot>,
+    ) {
+        if !self.is_sealed() {
+            return;
+        }
+        let mut sealed_roots = self.sealed_roots.lock().unwrap();
+        let roots = sealed_roots.get_or_insert_with(|| {
+            let mut roots = vec![];
+            let mut enumerator = object_enum::ClosureObjectEnumerator::<_, VM>::new(|object| {
+                VM::VMScanning::scan_object(tls, object, &mut |slot: VM::VMSlot| {
+                    if let Some(target) = slot.load() {
+                        if !self.in_space(target) {
+                            roots.push(target);
+                        }
+                    }
+                });
+            });
+            self.enumerate_objects(&mut enumerator);
+            roots.sort_unstable();
+            roots.dedup();
+            debug!(
+                "{}: {} objects are referenced from the sealed space",
+                self.name(),
+                roots.len()
+            );
+            roots
+        });
+        if !roots.is_empty() {
+            factory.create_process_pinning_roots_work(roots.clone());
+        }
+    }
 }
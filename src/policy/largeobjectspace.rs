@@ -1,10 +1,14 @@
 use atomic::Ordering;
+use std::sync::atomic::AtomicUsize;
+use std::sync::Mutex;
 
 use crate::plan::ObjectQueue;
 use crate::plan::VectorObjectQueue;
 use crate::policy::sft::GCWorkerMutRef;
+use crate::policy::sft::PolicyKind;
 use crate::policy::sft::SFT;
 use crate::policy::space::{CommonSpace, Space};
+use crate::util::alloc::AllocationOptions;
 use crate::util::constants::BYTES_IN_PAGE;
 use crate::util::heap::{FreeListPageResource, PageResource};
 use crate::util::metadata;
@@ -23,18 +27,38 @@ const LOS_BIT_MASK: u8 = 0b11;
 
 /// This type implements a policy for large objects. Each instance corresponds
 /// to one Treadmill space.
+///
+/// Newly allocated objects are young (in the nursery) until they survive a GC.  A nursery GC only
+/// traces and sweeps young objects, and a full heap GC handles all objects.  The space keeps
+/// track of the pages of young objects and the pages promoted in each GC, so that generational
+/// plans can trigger nursery GCs on large object allocation and account promoted objects as mature.
 pub struct LargeObjectSpace<VM: VMBinding> {
     common: CommonSpace<VM>,
     pr: FreeListPageResource<VM>,
     mark_state: u8,
     in_nursery_gc: bool,
     treadmill: TreadMill,
+    /// The number of pages of young objects, i.e. objects allocated since the last GC that have
+    /// not been promoted, yet.
+    nursery_pages: AtomicUsize,
+    /// The number of pages of young objects promoted in the current or the last GC.
+    promoted_pages: AtomicUsize,
+    /// Whether to sweep dead mature objects concurrently with mutators after a full heap GC.
+    concurrent_sweeping: bool,
+    /// Dead objects that are not swept, yet.  They are swept concurrently with mutators.
+    unswept_objects: Mutex<Vec<ObjectReference>>,
 }
 
 impl<VM: VMBinding> SFT for LargeObjectSpace<VM> {
     fn name(&self) -> &'static str {
         self.get_name()
     }
+    fn policy_kind(&self) -> PolicyKind {
+        PolicyKind::LargeObjectSpace
+    }
+    fn describe_object_metadata(&self, object: ObjectReference) -> Vec<String> {
+        vec![crate::util::metadata::describe::los_mark_nursery_bits::<VM>(object)]
+    }
     fn is_live(&self, object: ObjectReference) -> bool {
         self.test_mark_bit(object, self.mark_state)
     }
@@ -92,7 +116,12 @@ impl<VM: VMBinding> SFT for LargeObjectSpace<VM> {
             );
         }
 
-        self.treadmill.add_to_treadmill(object, alloc);
+        // Look up the pages of the object once, so that tracing does not need to lock the page
+        // resource.
+        let pages = self
+            .pr
+            .get_allocated_pages(get_super_page(object.to_object_start::<VM>()));
+        self.treadmill.add_to_treadmill(object, alloc, pages);
     }
     #[cfg(feature = "is_mmtk_object")]
     fn is_mmtk_object(&self, addr: Address) -> Option<ObjectReference> {
@@ -177,8 +206,9 @@ impl<VM: VMBinding> Space<VM> for LargeObjectSpace<VM> {
     }
 }
 
-use crate::scheduler::GCWorker;
+use crate::scheduler::{GCWork, GCWorkScheduler, GCWorker, WorkBucketStage};
 use crate::util::copy::CopySemantics;
+use crate::MMTK;
 
 impl<VM: VMBinding> crate::policy::gc_work::PolicyTraceObject<VM> for LargeObjectSpace<VM> {
     fn trace_object<Q: ObjectQueue, const KIND: crate::policy::gc_work::TraceKind>(
@@ -212,35 +242,74 @@ impl<VM: VMBinding> LargeObjectSpace<VM> {
         } else {
             FreeListPageResource::new_contiguous(common.start, common.extent, vm_map)
         };
-        pr.protect_memory_on_release = if protect_memory_on_release {
-            Some(common.mmap_strategy().prot)
-        } else {
-            None
-        };
+        pr.protect_memory_on_release =
+            if protect_memory_on_release || *common.options.protect_free_blocks {
+                Some(common.mmap_strategy().prot)
+            } else {
+                None
+            };
+        if pr.protect_memory_on_release.is_some() {
+            pr.quarantine_gcs = *common.options.quarantine_gcs;
+        }
         LargeObjectSpace {
             pr,
             common,
             mark_state: 0,
             in_nursery_gc: false,
             treadmill: TreadMill::new(),
+            nursery_pages: AtomicUsize::new(0),
+            promoted_pages: AtomicUsize::new(0),
+            concurrent_sweeping: false,
+            unswept_objects: Mutex::new(vec![]),
         }
     }
 
+    /// Sweep dead mature objects concurrently with mutators after full heap GCs.  The plan needs
+    /// to call [`LargeObjectSpace::schedule_concurrent_sweeping`] after each GC.
+    pub fn set_concurrent_sweeping(&mut self, concurrent_sweeping: bool) {
+        self.concurrent_sweeping = concurrent_sweeping;
+    }
+
     pub fn prepare(&mut self, full_heap: bool) {
+        debug_assert!(
+            self.unswept_objects.get_mut().unwrap().is_empty(),
+            "The last GC is not swept, yet."
+        );
         if full_heap {
             debug_assert!(self.treadmill.is_from_space_empty());
             self.mark_state = MARK_BIT - self.mark_state;
         }
         self.treadmill.flip(full_heap);
         self.in_nursery_gc = !full_heap;
+        self.promoted_pages.store(0, Ordering::Relaxed);
     }
 
     pub fn release(&mut self, full_heap: bool) {
+        probe!(
+            mmtk,
+            sweep_space_begin,
+            self.get_name().as_ptr(),
+            self.get_name().len()
+        );
         self.sweep_large_pages(true);
         debug_assert!(self.treadmill.is_nursery_empty());
+        // Young objects have either been promoted or released.
+        self.nursery_pages.store(0, Ordering::Relaxed);
         if full_heap {
-            self.sweep_large_pages(false);
+            if self.concurrent_sweeping {
+                let dead_objects = self.collect_dead_objects(false);
+                *self.unswept_objects.get_mut().unwrap() = dead_objects;
+            } else {
+                self.sweep_large_pages(false);
+            }
         }
+        self.pr.release_expired_quarantine();
+        probe!(
+            mmtk,
+            sweep_space_end,
+            self.get_name().as_ptr(),
+            self.get_name().len()
+        );
     }
     // Allow nested-if for this function to make it clear that test_and_mark() is only executed
     // for the outer condition is met.
@@ -267,7 +336,11 @@ impl<VM: VMBinding> LargeObjectSpace<VM> {
             // clearing nursery bit/moving objects out of logical nursery
             if self.test_and_mark(object, self.mark_state) {
                 trace!("LOS object {} is being marked now", object);
-                self.treadmill.copy(object, nursery_object);
+                let pages = self.treadmill.copy(object, nursery_object);
+                if nursery_object {
+                    self.nursery_pages.fetch_sub(pages, Ordering::Relaxed);
+                    self.promoted_pages.fetch_add(pages, Ordering::Relaxed);
+                }
                 // We just moved the object out of the logical nursery, mark it as unlogged.
                 if nursery_object && self.common.needs_log_bit {
                     VM::VMObjectModel::GLOBAL_LOG_BIT_SPEC
@@ -285,26 +358,97 @@ impl<VM: VMBinding> LargeObjectSpace<VM> {
     }
 
     fn sweep_large_pages(&mut self, sweep_nursery: bool) {
-        let sweep = |object: ObjectReference| {
-            #[cfg(feature = "vo_bit")]
-            crate::util::metadata::vo_bit::unset_vo_bit(object);
-            self.pr
-                .release_pages(get_super_page(object.to_object_start::<VM>()));
-        };
-        if sweep_nursery {
-            for object in self.treadmill.collect_nursery() {
-                sweep(object);
-            }
+        for object in self.collect_dead_objects(sweep_nursery) {
+            self.sweep_dead_object(object);
+        }
+    }
+
+    /// Remove the dead young objects (if `sweep_nursery`) or the dead mature objects from the
+    /// treadmill.
+    fn collect_dead_objects(&mut self, sweep_nursery: bool) -> Vec<ObjectReference> {
+        let mut dead_objects = if sweep_nursery {
+            self.treadmill.collect_nursery()
         } else {
-            for object in self.treadmill.collect() {
-                sweep(object)
-            }
+            self.treadmill.collect()
+        };
+        // The treadmill does not keep the objects in order. Sort them so that pages are released in
+        // the same order in every run.
+        if *self.common.options.deterministic_gc {
+            dead_objects.sort_unstable();
         }
+        dead_objects
+    }
+
+    /// Release the pages of a dead object.
+    fn sweep_dead_object(&self, object: ObjectReference) {
+        #[cfg(feature = "analysis")]
+        self.common
+            .global_state
+            .object_lifetimes
+            .record_death(object, VM::VMObjectModel::get_current_size(object));
+        #[cfg(feature = "vo_bit")]
+        crate::util::metadata::vo_bit::unset_vo_bit(object);
+        if *self.common.options.poison_on_free {
+            crate::util::memory::poison(
+                object.to_object_start::<VM>(),
+                VM::VMObjectModel::get_current_size(object),
+            );
+        }
+        crate::util::memory::mark_noaccess(
+            object.to_object_start::<VM>(),
+            VM::VMObjectModel::get_current_size(object),
+        );
+        self.pr
+            .release_pages(get_super_page(object.to_object_start::<VM>()));
+    }
+
+    /// Schedule a work packet to sweep the dead objects that the last GC left to be swept
+    /// concurrently with mutators.  Return `true` if the work packet is scheduled.
+    pub fn schedule_concurrent_sweeping(&'static self, scheduler: &GCWorkScheduler<VM>) -> bool {
+        if self.unswept_objects.lock().unwrap().is_empty() {
+            return false;
+        }
+        // We are holding the lock for synchronizing GC workers.  Do not notify now.
+        scheduler.work_buckets[WorkBucketStage::Unconstrained]
+            .add_no_notify(SweepLargeObjects { space: self });
+        true
     }
 
     /// Allocate an object
-    pub fn allocate_pages(&self, tls: VMThread, pages: usize) -> Address {
-        self.acquire(tls, pages)
+    pub fn allocate_pages(
+        &self,
+        tls: VMThread,
+        pages: usize,
+        alloc_options: AllocationOptions,
+    ) -> Address {
+        let start = self.acquire(tls, pages, alloc_options);
+        if !start.is_zero() {
+            self.nursery_pages.fetch_add(pages, Ordering::Relaxed);
+        }
+        start
+    }
+
+    /// Get the number of pages of young objects, i.e. objects allocated since the last GC that
+    /// have not been promoted, yet.
+    pub fn nursery_pages(&self) -> usize {
+        self.nursery_pages.load(Ordering::Relaxed)
+    }
+
+    /// Get the number of pages of objects that have survived at least one GC.
+    pub fn mature_pages(&self) -> usize {
+        self.reserved_pages().saturating_sub(self.nursery_pages())
+    }
+
+    /// Get the number of pages of young objects promoted in the current GC (if called during a
+    /// GC), or in the last GC (if called between GCs).
+    pub fn promoted_pages(&self) -> usize {
+        self.promoted_pages.load(Ordering::Relaxed)
+    }
+
+    /// Get the number of pages of dead objects that are kept protected and not reused, yet.  See
+    /// the option `quarantine_gcs`.
+    pub fn quarantined_pages(&self) -> usize {
+        self.pr.get_quarantined_pages()
     }
 
     /// Test if the object's mark bit is the same as the given value. If it is not the same,
@@ -368,3 +512,29 @@ impl<VM: VMBinding> LargeObjectSpace<VM> {
 fn get_super_page(cell: Address) -> Address {
     cell.align_down(BYTES_IN_PAGE)
 }
+
+/// Sweep the dead objects that the last GC left unswept, while mutators run.
+struct SweepLargeObjects<VM: VMBinding> {
+    space: &'static LargeObjectSpace<VM>,
+}
+
+impl<VM: VMBinding> GCWork<VM> for SweepLargeObjects<VM> {
+    fn do_work(&mut self, _worker: &mut GCWorker<VM>, _mmtk: &'static MMTK<VM>) {
+        probe!(
+            mmtk,
+            sweep_space_begin,
+            self.space.get_name().as_ptr(),
+            self.space.get_name().len()
+        );
+        let dead_objects = std::mem::take(&mut *self.space.unswept_objects.lock().unwrap());
+        for object in dead_objects {
+            self.space.sweep_dead_object(object);
+        }
+        probe!(
+            mmtk,
+            sweep_space_end,
+            self.space.get_name().as_ptr(),
+            self.space.get_name().len()
+        );
+    }
+}
@@ -4,21 +4,25 @@ use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
 use crate::policy::sft::GCWorkerMutRef;
+use crate::policy::sft::PolicyKind;
 use crate::policy::sft::SFT;
 use crate::policy::space::{CommonSpace, Space};
 use crate::util::address::Address;
+use crate::util::alloc::AllocationOptions;
 
 use crate::util::conversions;
 use crate::util::heap::gc_trigger::GCTrigger;
 use crate::util::heap::layout::vm_layout::vm_layout;
 use crate::util::heap::PageResource;
 use crate::util::heap::VMRequest;
+use crate::util::heap_layout::SpaceLayout;
 use crate::util::memory::MmapAnnotation;
 use crate::util::memory::MmapStrategy;
 use crate::util::metadata::side_metadata::SideMetadataContext;
 use crate::util::metadata::side_metadata::SideMetadataSanity;
 use crate::util::object_enum::ObjectEnumerator;
 use crate::util::opaque_pointer::*;
+use crate::util::options::PretouchMode;
 use crate::util::ObjectReference;
 use crate::vm::VMBinding;
 
@@ -43,12 +47,17 @@ pub struct LockFreeImmortalSpace<VM: VMBinding> {
     slow_path_zeroing: bool,
     metadata: SideMetadataContext,
     gc_trigger: Arc<GCTrigger<VM>>,
+    /// The index of the space.  See [`crate::util::heap::space_index`].
+    space_index: u8,
 }
 
 impl<VM: VMBinding> SFT for LockFreeImmortalSpace<VM> {
     fn name(&self) -> &'static str {
         self.get_name()
     }
+    fn policy_kind(&self) -> PolicyKind {
+        PolicyKind::LockFreeImmortalSpace
+    }
     fn is_live(&self, _object: ObjectReference) -> bool {
         unimplemented!()
     }
@@ -116,6 +125,18 @@ impl<VM: VMBinding> Space<VM> for LockFreeImmortalSpace<VM> {
     fn common(&self) -> &CommonSpace<VM> {
         unimplemented!()
     }
+    fn space_index(&self) -> u8 {
+        self.space_index
+    }
+    fn describe_layout(&self) -> SpaceLayout {
+        SpaceLayout::new(
+            self.name,
+            self.space_index,
+            true,
+            vec![self.start..self.limit],
+            &self.metadata,
+        )
+    }
 
     fn get_gc_trigger(&self) -> &GCTrigger<VM> {
         &self.gc_trigger
@@ -136,7 +157,7 @@ impl<VM: VMBinding> Space<VM> for LockFreeImmortalSpace<VM> {
         data_pages + meta_pages
     }
 
-    fn acquire(&self, _tls: VMThread, pages: usize) -> Address {
+    fn acquire(&self, _tls: VMThread, pages: usize, _alloc_options: AllocationOptions) -> Address {
         trace!("LockFreeImmortalSpace::acquire");
         let bytes = conversions::pages_to_bytes(pages);
         let start = self
@@ -222,6 +243,7 @@ impl<VM: VMBinding> LockFreeImmortalSpace<VM> {
             unreachable!()
         };
         let start = args.heap.reserve(extent, top);
+        let space_index = args.heap.new_space_index();
 
         let space = Self {
             name: args.name,
@@ -233,15 +255,30 @@ impl<VM: VMBinding> LockFreeImmortalSpace<VM> {
             metadata: SideMetadataContext {
                 global: args.global_side_metadata_specs,
                 local: vec![],
+                mmap_strategy: MmapStrategy::side_metadata(&args.options),
             },
             gc_trigger: args.gc_trigger,
+            space_index,
         };
 
-        // Eagerly memory map the entire heap (also zero all the memory)
-        let strategy = MmapStrategy::new(
-            *args.options.transparent_hugepages,
-            crate::util::memory::MmapProtection::ReadWrite,
+        crate::util::heap_file::record_space(
+            space.name,
+            space.start,
+            space.total_bytes,
+            &space.metadata,
         );
+
+        // Eagerly memory map the entire heap (also zero all the memory)
+        let strategy = MmapStrategy {
+            huge_page: args.options.huge_page_support(space.get_name()),
+            prot: crate::util::memory::MmapProtection::ReadWrite,
+            numa: args
+                .options
+                .numa_space_policies
+                .get(space.get_name())
+                .unwrap_or(*args.options.numa_policy),
+            populate: *args.options.pretouch != PretouchMode::No,
+        };
         crate::util::memory::dzmmap_noreplace(
             start,
             aligned_total_bytes,
@@ -258,6 +295,7 @@ impl<VM: VMBinding> LockFreeImmortalSpace<VM> {
                 // TODO(Javad): handle meta space allocation failure
                 panic!("failed to mmap meta memory: {e}")
             });
+        crate::util::heap::space_index::set(start, aligned_total_bytes, space_index);
 
         space
     }
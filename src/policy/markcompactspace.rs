@@ -1,5 +1,6 @@
 use std::ops::Range;
 
+use super::sft::PolicyKind;
 use super::sft::SFT;
 use super::space::{CommonSpace, Space};
 use crate::plan::VectorObjectQueue;
@@ -36,6 +37,19 @@ impl<VM: VMBinding> SFT for MarkCompactSpace<VM> {
     fn name(&self) -> &'static str {
         self.get_name()
     }
+    fn policy_kind(&self) -> PolicyKind {
+        PolicyKind::MarkCompactSpace
+    }
+    fn describe_object_metadata(&self, object: ObjectReference) -> Vec<String> {
+        let forwarding = match Self::get_header_forwarding_pointer(object) {
+            Some(new_object) => format!("forwarding pointer: {}", new_object),
+            None => "forwarding pointer: none".to_string(),
+        };
+        vec![
+            crate::util::metadata::describe::mark_bit::<VM>(object),
+            forwarding,
+        ]
+    }
 
     fn get_forwarded_object(&self, object: ObjectReference) -> Option<ObjectReference> {
         Self::get_header_forwarding_pointer(object)
@@ -413,8 +427,12 @@ impl<VM: VMBinding> MarkCompactSpace<VM> {
 
                     // copy object
                     trace!(" copy from {} to {}", obj, new_object);
+                    #[cfg(feature = "address_based_hashing")]
+                    let hash_state = crate::util::object_hash::get_hash_state::<VM>(obj);
                     let end_of_new_object =
                         VM::VMObjectModel::copy_to(obj, new_object, Address::ZERO);
+                    #[cfg(feature = "address_based_hashing")]
+                    crate::util::object_hash::on_object_moved::<VM>(obj, hash_state, new_object);
                     // update VO bit,
                     vo_bit::set_vo_bit(new_object);
                     to = new_object.to_object_start::<VM>() + copied_size;
@@ -4,24 +4,28 @@ use super::metadata::*;
 use crate::plan::ObjectQueue;
 use crate::plan::VectorObjectQueue;
 use crate::policy::sft::GCWorkerMutRef;
+use crate::policy::sft::PolicyKind;
 use crate::policy::sft::SFT;
 use crate::policy::space::CommonSpace;
-use crate::scheduler::GCWorkScheduler;
+use crate::scheduler::{GCWorkScheduler, Handshake};
+use crate::util::alloc::AllocationOptions;
 use crate::util::heap::gc_trigger::GCTrigger;
 use crate::util::heap::PageResource;
-use crate::util::malloc::library::{BYTES_IN_MALLOC_PAGE, LOG_BYTES_IN_MALLOC_PAGE};
+use crate::util::malloc::library::{MallocLibrary, BYTES_IN_MALLOC_PAGE, LOG_BYTES_IN_MALLOC_PAGE};
 use crate::util::malloc::malloc_ms_util::*;
+use crate::util::memory::MmapStrategy;
 use crate::util::metadata::side_metadata::{
     SideMetadataContext, SideMetadataSanity, SideMetadataSpec,
 };
 use crate::util::metadata::MetadataSpec;
 use crate::util::object_enum::ObjectEnumerator;
 use crate::util::opaque_pointer::*;
+use crate::util::options::MallocBackend;
 use crate::util::Address;
 use crate::util::ObjectReference;
 use crate::util::{conversions, metadata};
 use crate::vm::VMBinding;
-use crate::vm::{ActivePlan, Collection, ObjectModel};
+use crate::vm::{ActivePlan, ObjectModel};
 use crate::{policy::space::Space, util::heap::layout::vm_layout::BYTES_IN_CHUNK};
 #[cfg(debug_assertions)]
 use std::collections::HashMap;
@@ -32,11 +36,46 @@ use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 #[cfg(debug_assertions)]
 use std::sync::Mutex;
+use std::sync::OnceLock;
 // If true, we will use a hashmap to store all the allocated memory from malloc, and use it
 // to make sure our allocation is correct.
 #[cfg(debug_assertions)]
 const ASSERT_ALLOCATION: bool = false;
 
+/// The malloc library used by malloc spaces, selected by the option `malloc_backend`. The side
+/// metadata of malloc spaces is global, as malloc may return any address. So all the MMTk
+/// instances in a process must use the same library.
+static MALLOC_LIBRARY: OnceLock<(MallocBackend, &'static MallocLibrary)> = OnceLock::new();
+
+fn malloc_library() -> &'static MallocLibrary {
+    MALLOC_LIBRARY
+        .get()
+        .expect("The malloc library is not selected")
+        .1
+}
+
+/// The maximum number of dead objects we collect before freeing them with one
+/// `MallocLibrary::free_bulk` call when sweeping.
+const FREE_BATCH_SIZE: usize = 1024;
+
+/// Dead objects found when sweeping a chunk. We free them in batches to amortize the cost of
+/// freeing in the malloc library and of updating `active_bytes`.
+struct FreeBatch {
+    /// The pointers returned by the malloc library, and their usable sizes.
+    regions: Vec<(*mut libc::c_void, usize)>,
+    /// The sum of the sizes in `regions`.
+    bytes: usize,
+}
+
+impl FreeBatch {
+    fn new() -> Self {
+        FreeBatch {
+            regions: Vec::with_capacity(FREE_BATCH_SIZE),
+            bytes: 0,
+        }
+    }
+}
+
 /// This space uses malloc to get new memory, and performs mark-sweep for the memory.
 pub struct MallocSpace<VM: VMBinding> {
     phantom: PhantomData<VM>,
@@ -48,6 +87,7 @@ pub struct MallocSpace<VM: VMBinding> {
     /// Work packet scheduler
     scheduler: Arc<GCWorkScheduler<VM>>,
     gc_trigger: Arc<GCTrigger<VM>>,
+    handshake: Arc<Handshake<VM>>,
     // Mapping between allocated address and its size - this is used to check correctness.
     // Size will be set to zero when the memory is freed.
     #[cfg(debug_assertions)]
@@ -61,12 +101,20 @@ pub struct MallocSpace<VM: VMBinding> {
     pub completed_work_packets: AtomicU32,
     #[cfg(debug_assertions)]
     pub work_live_bytes: AtomicUsize,
+    /// The number of sweep work packets that have not finished in the current GC.
+    pending_sweep_packets: AtomicUsize,
 }
 
 impl<VM: VMBinding> SFT for MallocSpace<VM> {
     fn name(&self) -> &'static str {
         self.get_name()
     }
+    fn policy_kind(&self) -> PolicyKind {
+        PolicyKind::MallocSpace
+    }
+    fn describe_object_metadata(&self, object: ObjectReference) -> Vec<String> {
+        vec![crate::util::metadata::describe::mark_bit::<VM>(object)]
+    }
 
     fn is_live(&self, object: ObjectReference) -> bool {
         is_marked::<VM>(object, Ordering::SeqCst)
@@ -158,6 +206,22 @@ impl<VM: VMBinding> Space<VM> for MallocSpace<VM> {
         unreachable!()
     }
 
+    fn space_index(&self) -> u8 {
+        // The memory is allocated by malloc, not from page resources.
+        crate::util::heap::space_index::UNKNOWN_SPACE_INDEX
+    }
+
+    fn describe_layout(&self) -> crate::util::heap_layout::SpaceLayout {
+        // We do not know the address ranges of the memory allocated by malloc.
+        crate::util::heap_layout::SpaceLayout::new(
+            self.get_name(),
+            self.space_index(),
+            false,
+            vec![],
+            &self.metadata,
+        )
+    }
+
     fn get_gc_trigger(&self) -> &GCTrigger<VM> {
         self.gc_trigger.as_ref()
     }
@@ -279,6 +343,12 @@ impl<VM: VMBinding> MallocSpace<VM> {
             // Besides we cannot meaningfully measure the live bytes vs total pages for MallocSpace.
             panic!("count_live_bytes_in_gc is not supported by MallocSpace");
         }
+        let backend = *args.options.malloc_backend;
+        let (selected, _) = MALLOC_LIBRARY.get_or_init(|| (backend, MallocLibrary::get(backend)));
+        assert_eq!(
+            *selected, backend,
+            "All the MMTk instances must use the same malloc_backend"
+        );
         MallocSpace {
             phantom: PhantomData,
             active_bytes: AtomicUsize::new(0),
@@ -292,9 +362,11 @@ impl<VM: VMBinding> MallocSpace<VM> {
                     MetadataSpec::OnSide(OFFSET_MALLOC_METADATA_SPEC),
                     *VM::VMObjectModel::LOCAL_MARK_BIT_SPEC,
                 ]),
+                mmap_strategy: MmapStrategy::side_metadata(&args.options),
             },
             scheduler: args.scheduler.clone(),
             gc_trigger: args.gc_trigger,
+            handshake: args.handshake,
             #[cfg(debug_assertions)]
             active_mem: Mutex::new(HashMap::new()),
             #[cfg(debug_assertions)]
@@ -303,6 +375,7 @@ impl<VM: VMBinding> MallocSpace<VM> {
             completed_work_packets: AtomicU32::new(0),
             #[cfg(debug_assertions)]
             work_live_bytes: AtomicUsize::new(0),
+            pending_sweep_packets: AtomicUsize::new(0),
         }
     }
 
@@ -353,17 +426,30 @@ impl<VM: VMBinding> MallocSpace<VM> {
         }
     }
 
-    pub fn alloc(&self, tls: VMThread, size: usize, align: usize, offset: usize) -> Address {
+    pub fn alloc(
+        &self,
+        tls: VMThread,
+        size: usize,
+        align: usize,
+        offset: usize,
+        alloc_options: AllocationOptions,
+    ) -> Address {
         // TODO: Should refactor this and Space.acquire()
-        if self.get_gc_trigger().poll(false, Some(self)) {
+        if !alloc_options.on_fail.allow_gc() {
+            // The allocation must not trigger a GC.  Fail it if we would have triggered a GC.
+            if self.get_gc_trigger().is_gc_required(Some(self)) {
+                return unsafe { Address::zero() };
+            }
+        } else if self.get_gc_trigger().poll(false, Some(self)) {
             assert!(VM::VMActivePlan::is_mutator(tls), "Polling in GC worker");
-            VM::VMCollection::block_for_gc(VMMutatorThread(tls));
+            self.handshake.block_for_gc(VMMutatorThread(tls));
             return unsafe { Address::zero() };
         }
 
-        let (address, is_offset_malloc) = alloc::<VM>(size, align, offset);
+        let lib = malloc_library();
+        let (address, is_offset_malloc) = alloc::<VM>(lib, size, align, offset);
         if !address.is_zero() {
-            let actual_size = get_malloc_usable_size(address, is_offset_malloc);
+            let actual_size = get_malloc_usable_size(lib, address, is_offset_malloc);
 
             // If the side metadata for the address has not yet been mapped, we will map all the side metadata for the range [address, address + actual_size).
             if !is_meta_space_mapped(address, actual_size) {
@@ -394,7 +480,7 @@ impl<VM: VMBinding> MallocSpace<VM> {
 
     pub fn free(&self, addr: Address) {
         let offset_malloc_bit = is_offset_malloc(addr);
-        let bytes = get_malloc_usable_size(addr, offset_malloc_bit);
+        let bytes = get_malloc_usable_size(malloc_library(), addr, offset_malloc_bit);
         self.free_internal(addr, bytes, offset_malloc_bit);
     }
 
@@ -403,13 +489,13 @@ impl<VM: VMBinding> MallocSpace<VM> {
     fn free_internal(&self, addr: Address, bytes: usize, offset_malloc_bit: bool) {
         if offset_malloc_bit {
             trace!("Free memory {:x}", addr);
-            offset_free(addr);
+            offset_free(malloc_library(), addr);
             unsafe { unset_offset_malloc_bit_unsafe(addr) };
         } else {
             let ptr = addr.to_mut_ptr();
             trace!("Free memory {:?}", ptr);
             unsafe {
-                free(ptr);
+                malloc_library().free(ptr);
             }
         }
 
@@ -496,6 +582,12 @@ impl<VM: VMBinding> MallocSpace<VM> {
         // we can assume that the chunk mark metadata is not being accessed by anything else and hence we use
         // non-atomic accesses
         let space = unsafe { &*(self as *const Self) };
+        probe!(
+            mmtk,
+            sweep_space_begin,
+            self.get_name().as_ptr(),
+            self.get_name().len()
+        );
         while chunk < end {
             if is_chunk_mapped(chunk) && unsafe { is_chunk_marked_unsafe(chunk) } {
                 work_packets.push(Box::new(MSSweepChunk { ms: space, chunk }));
@@ -513,6 +605,16 @@ impl<VM: VMBinding> MallocSpace<VM> {
             self.work_live_bytes.store(0, Ordering::SeqCst);
         }
 
+        if work_packets.is_empty() {
+            probe!(
+                mmtk,
+                sweep_space_end,
+                self.get_name().as_ptr(),
+                self.get_name().len()
+            );
+        }
+        self.pending_sweep_packets
+            .store(work_packets.len(), Ordering::SeqCst);
         self.scheduler.work_buckets[WorkBucketStage::Release].bulk_add(work_packets);
     }
 
@@ -534,10 +636,48 @@ impl<VM: VMBinding> MallocSpace<VM> {
     fn get_malloc_addr_size(object: ObjectReference) -> (Address, bool, usize) {
         let obj_start = object.to_object_start::<VM>();
         let offset_malloc_bit = is_offset_malloc(obj_start);
-        let bytes = get_malloc_usable_size(obj_start, offset_malloc_bit);
+        let bytes = get_malloc_usable_size(malloc_library(), obj_start, offset_malloc_bit);
         (obj_start, offset_malloc_bit, bytes)
     }
 
+    /// Add a dead object to the batch, and free the batch if it is full.
+    fn free_later(
+        &self,
+        batch: &mut FreeBatch,
+        addr: Address,
+        bytes: usize,
+        offset_malloc_bit: bool,
+    ) {
+        trace!("Free memory {:x} later", addr);
+        batch
+            .regions
+            .push((get_malloc_result(addr, offset_malloc_bit), bytes));
+        batch.bytes += bytes;
+        if offset_malloc_bit {
+            unsafe { unset_offset_malloc_bit_unsafe(addr) };
+        }
+
+        #[cfg(debug_assertions)]
+        if ASSERT_ALLOCATION {
+            self.active_mem.lock().unwrap().insert(addr, 0).unwrap();
+        }
+
+        if batch.regions.len() >= FREE_BATCH_SIZE {
+            self.free_batch(batch);
+        }
+    }
+
+    /// Free all the objects in the batch.
+    fn free_batch(&self, batch: &mut FreeBatch) {
+        if batch.regions.is_empty() {
+            return;
+        }
+        unsafe { malloc_library().free_bulk(&batch.regions) };
+        self.active_bytes.fetch_sub(batch.bytes, Ordering::SeqCst);
+        batch.regions.clear();
+        batch.bytes = 0;
+    }
+
     /// Clean up for an empty chunk
     fn clean_up_empty_chunk(&self, chunk_start: Address) {
         // Since the chunk mark metadata is a byte, we don't need synchronization
@@ -550,7 +690,12 @@ impl<VM: VMBinding> MallocSpace<VM> {
 
     /// Sweep an object if it is dead, and unset page marks for empty pages before this object.
     /// Return true if the object is swept.
-    fn sweep_object(&self, object: ObjectReference, empty_page_start: &mut Address) -> bool {
+    fn sweep_object(
+        &self,
+        object: ObjectReference,
+        empty_page_start: &mut Address,
+        batch: &mut FreeBatch,
+    ) -> bool {
         let (obj_start, offset_malloc, bytes) = Self::get_malloc_addr_size(object);
 
         // We are the only thread that is dealing with the object. We can use non-atomic methods for the metadata.
@@ -558,8 +703,14 @@ impl<VM: VMBinding> MallocSpace<VM> {
             // Dead object
             trace!("Object {} has been allocated but not marked", object);
 
+            #[cfg(feature = "analysis")]
+            self.common()
+                .global_state
+                .object_lifetimes
+                .record_death(object, bytes);
+
             // Free object
-            self.free_internal(obj_start, bytes, offset_malloc);
+            self.free_later(batch, obj_start, bytes, offset_malloc);
             trace!("free object {}", object);
             unsafe { unset_vo_bit_unsafe(object) };
 
@@ -651,6 +802,7 @@ impl<VM: VMBinding> MallocSpace<VM> {
 
             // The start of a possibly empty page. This will be updated during the sweeping, and always points to the next page of last live objects.
             let mut empty_page_start = Address::ZERO;
+            let mut batch = FreeBatch::new();
 
             // Scan the chunk by every 'bulk_load_size' region.
             while address < chunk_end {
@@ -674,7 +826,7 @@ impl<VM: VMBinding> MallocSpace<VM> {
                         false,
                     >::new(address, end);
                     for object in bulk_load_scan {
-                        self.sweep_object(object, &mut empty_page_start);
+                        self.sweep_object(object, &mut empty_page_start, &mut batch);
                     }
                 } else {
                     // TODO we aren't actually accounting for the case where an object is alive and spans
@@ -688,6 +840,7 @@ impl<VM: VMBinding> MallocSpace<VM> {
                 address += bulk_load_size;
                 debug_assert!(address.is_aligned_to(bulk_load_size));
             }
+            self.free_batch(&mut batch);
 
             // Linear scan through the chunk, and add up all the live object sizes.
             // We have to do this as a separate pass, as in the above pass, we did not go through all the live objects
@@ -757,6 +910,7 @@ impl<VM: VMBinding> MallocSpace<VM> {
 
         // The start of a possibly empty page. This will be updated during the sweeping, and always points to the next page of last live objects.
         let mut empty_page_start = Address::ZERO;
+        let mut batch = FreeBatch::new();
 
         let chunk_linear_scan = crate::util::linear_scan::ObjectIterator::<
             VM,
@@ -781,7 +935,7 @@ impl<VM: VMBinding> MallocSpace<VM> {
                 );
             }
 
-            let live = !self.sweep_object(object, &mut empty_page_start);
+            let live = !self.sweep_object(object, &mut empty_page_start, &mut batch);
             if live {
                 // Live object. Unset mark bit.
                 // We should be the only thread that access this chunk, it is okay to use non-atomic store.
@@ -795,6 +949,7 @@ impl<VM: VMBinding> MallocSpace<VM> {
                 }
             }
         }
+        self.free_batch(&mut batch);
 
         // If we never updated empty_page_start, the entire chunk is empty.
         if empty_page_start.is_zero() {
@@ -839,5 +994,13 @@ pub struct MSSweepChunk<VM: VMBinding> {
 impl<VM: VMBinding> GCWork<VM> for MSSweepChunk<VM> {
     fn do_work(&mut self, _worker: &mut GCWorker<VM>, _mmtk: &'static MMTK<VM>) {
         self.ms.sweep_chunk(self.chunk);
+        if 1 == self.ms.pending_sweep_packets.fetch_sub(1, Ordering::SeqCst) {
+            probe!(
+                mmtk,
+                sweep_space_end,
+                self.ms.get_name().as_ptr(),
+                self.ms.get_name().len()
+            );
+        }
     }
 }
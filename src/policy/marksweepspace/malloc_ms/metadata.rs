@@ -1,5 +1,6 @@
 use crate::util::conversions;
 use crate::util::heap::layout::vm_layout::BYTES_IN_CHUNK;
+use crate::util::memory::MmapStrategy;
 use crate::util::metadata::side_metadata;
 use crate::util::metadata::side_metadata::SideMetadataContext;
 use crate::util::metadata::side_metadata::SideMetadataSpec;
@@ -14,6 +15,7 @@ lazy_static! {
     pub(super) static ref CHUNK_METADATA: SideMetadataContext = SideMetadataContext {
         global: vec![ACTIVE_CHUNK_METADATA_SPEC],
         local: vec![],
+        mmap_strategy: MmapStrategy::SIDE_METADATA,
     };
 
     /// Lock to synchronize the mapping of side metadata for a newly allocated chunk by malloc
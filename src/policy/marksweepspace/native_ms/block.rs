@@ -4,6 +4,7 @@ use atomic::Ordering;
 
 use super::BlockList;
 use super::MarkSweepSpace;
+use crate::policy::space::Space;
 use crate::util::constants::LOG_BYTES_IN_PAGE;
 use crate::util::heap::chunk_map::*;
 use crate::util::linear_scan::Region;
@@ -16,6 +17,7 @@ use crate::{
     },
     vm::VMBinding,
 };
+use mmtk_macros::Region;
 
 use std::num::NonZeroUsize;
 
@@ -25,7 +27,19 @@ use std::num::NonZeroUsize;
 /// size of `Option<Block>` is the same as `Block` itself.
 // TODO: If we actually use the first block, we would need to turn the type into `Block(Address)`, and use `None` and
 // `Block(Address::ZERO)` to differentiate those.
-#[derive(Clone, Copy, PartialOrd, PartialEq)]
+#[derive(Clone, Copy, PartialOrd, PartialEq, Region)]
+#[region(log_bytes = 16, parent = Chunk)]
+#[region(metadata = [
+    Block::MARK_TABLE,
+    Block::NEXT_BLOCK_TABLE,
+    Block::PREV_BLOCK_TABLE,
+    Block::FREE_LIST_TABLE,
+    Block::SIZE_TABLE,
+    Block::BLOCK_LIST_TABLE,
+    Block::TLS_TABLE,
+    Block::LIVE_CELLS_TABLE,
+    Block::REPACK_TABLE,
+])]
 #[repr(transparent)]
 pub struct Block(NonZeroUsize);
 
@@ -35,20 +49,6 @@ impl std::fmt::Debug for Block {
     }
 }
 
-impl Region for Block {
-    const LOG_BYTES: usize = 16;
-
-    fn from_aligned_address(address: Address) -> Self {
-        debug_assert!(address.is_aligned_to(Self::BYTES));
-        debug_assert!(!address.is_zero());
-        Self(unsafe { NonZeroUsize::new_unchecked(address.as_usize()) })
-    }
-
-    fn start(&self) -> Address {
-        unsafe { Address::from_usize(self.0.get()) }
-    }
-}
-
 impl BlockMayHaveObjects for Block {
     fn may_have_objects(&self) -> bool {
         self.get_state() != BlockState::Unallocated
@@ -59,16 +59,6 @@ impl Block {
     /// Log pages in block
     pub const LOG_PAGES: usize = Self::LOG_BYTES - LOG_BYTES_IN_PAGE as usize;
 
-    pub const METADATA_SPECS: [SideMetadataSpec; 7] = [
-        Self::MARK_TABLE,
-        Self::NEXT_BLOCK_TABLE,
-        Self::PREV_BLOCK_TABLE,
-        Self::FREE_LIST_TABLE,
-        Self::SIZE_TABLE,
-        Self::BLOCK_LIST_TABLE,
-        Self::TLS_TABLE,
-    ];
-
     /// Block mark table (side)
     pub const MARK_TABLE: SideMetadataSpec =
         crate::util::metadata::side_metadata::spec_defs::MS_BLOCK_MARK;
@@ -100,6 +90,14 @@ impl Block {
     pub const TLS_TABLE: SideMetadataSpec =
         crate::util::metadata::side_metadata::spec_defs::MS_BLOCK_TLS;
 
+    /// The number of cells marked in the last GC.  Only counted if repacking is enabled.
+    pub const LIVE_CELLS_TABLE: SideMetadataSpec =
+        crate::util::metadata::side_metadata::spec_defs::MS_BLOCK_LIVE;
+
+    /// Whether the live objects in the block are moved out in the current GC.
+    pub const REPACK_TABLE: SideMetadataSpec =
+        crate::util::metadata::side_metadata::spec_defs::MS_BLOCK_REPACK;
+
     pub fn load_free_list(&self) -> Address {
         unsafe { Address::from_usize(Block::FREE_LIST_TABLE.load::<usize>(self.start())) }
     }
@@ -219,6 +217,38 @@ impl Block {
         !self.load_free_list().is_zero()
     }
 
+    /// Get the number of cells in the block.
+    pub fn cells(&self) -> usize {
+        Block::BYTES / self.load_block_cell_size()
+    }
+
+    /// Get the number of cells marked in the last GC.  This is an upper bound of the number of
+    /// live objects in the block if no object has been allocated in the block since then.
+    pub fn live_cells(&self) -> usize {
+        Self::LIVE_CELLS_TABLE.load_atomic::<u16>(self.start(), Ordering::Relaxed) as usize
+    }
+
+    /// Count a cell that is marked in the current GC.
+    pub fn inc_live_cells(&self) {
+        Self::LIVE_CELLS_TABLE.fetch_add_atomic::<u16>(self.start(), 1, Ordering::Relaxed);
+    }
+
+    /// Reset the number of marked cells before a GC.
+    pub fn reset_live_cells(&self) {
+        Self::LIVE_CELLS_TABLE.store_atomic::<u16>(self.start(), 0, Ordering::Relaxed);
+    }
+
+    /// Is the block a repacking source, i.e. the live objects in it are moved to other blocks in
+    /// the current GC?
+    pub fn is_repack_source(&self) -> bool {
+        Self::REPACK_TABLE.load_atomic::<u8>(self.start(), Ordering::Relaxed) != 0
+    }
+
+    /// Set or clear the repacking source flag.
+    pub fn set_repack_source(&self, source: bool) {
+        Self::REPACK_TABLE.store_atomic::<u8>(self.start(), source as u8, Ordering::Relaxed);
+    }
+
     /// Get block mark state.
     pub fn get_state(&self) -> BlockState {
         let byte = Self::MARK_TABLE.load_atomic::<u8>(self.start(), Ordering::SeqCst);
@@ -250,7 +280,7 @@ impl Block {
     }
 
     /// Sweep the block. This is done either lazily in the allocation phase, or eagerly at the end of a GC.
-    pub fn sweep<VM: VMBinding>(&self) {
+    pub fn sweep<VM: VMBinding>(&self, space: &MarkSweepSpace<VM>) {
         // The important point here is that we need to distinguish cell address, allocation address, and object reference.
         // We only know cell addresses here. We do not know the allocation address, and we also do not know the object reference.
         // The mark bit is set for object references, and we need to use the mark bit to decide whether a cell is live or not.
@@ -260,6 +290,13 @@ impl Block {
             unimplemented!()
         }
 
+        // We can only poison free cells (or make them inaccessible) if the mark bit is on the side. Otherwise,
+        // we will read the mark bits from free cells in the next GC, and the poison pattern may look like a mark bit.
+        let mark_bit_on_side = VM::VMObjectModel::LOCAL_MARK_BIT_SPEC
+            .as_spec()
+            .is_on_side();
+        let poison = *space.common().options.poison_on_free;
+
         // Check if we can treat it as the simple case: cell address === object reference.
         // If the binding does not use allocation offset, and they use the same allocation alignment which the cell size is aligned to,
         // then we have cell address === allocation address.
@@ -273,17 +310,22 @@ impl Block {
             && VM::VMObjectModel::UNIFIED_OBJECT_REFERENCE_ADDRESS
         {
             // In this case, we can use the simplest and the most efficicent sweep.
-            self.simple_sweep::<VM>()
+            self.simple_sweep(space, mark_bit_on_side, poison)
         } else {
             // Otherwise we fallback to a generic but slow sweep. This roughly has ~10% mutator overhead for lazy sweeping.
-            self.naive_brute_force_sweep::<VM>()
+            self.naive_brute_force_sweep::<VM>(mark_bit_on_side, poison)
         }
     }
 
     /// This implementation uses object reference and cell address interchangably. This is not correct for most cases.
     /// However, in certain cases, such as OpenJDK, this is correct, and efficient. See the sweep method for the invariants
     /// that we need to use this method correctly.
-    fn simple_sweep<VM: VMBinding>(&self) {
+    fn simple_sweep<VM: VMBinding>(
+        &self,
+        #[allow(unused_variables)] space: &MarkSweepSpace<VM>,
+        release_cell_body: bool,
+        poison: bool,
+    ) {
         let cell_size = self.load_block_cell_size();
         debug_assert_ne!(cell_size, 0);
         let mut cell = self.start();
@@ -297,10 +339,24 @@ impl Block {
             if !VM::VMObjectModel::LOCAL_MARK_BIT_SPEC
                 .is_marked::<VM>(potential_object, Ordering::SeqCst)
             {
+                // If the VO bit is set, the cell holds a dead object. Record it before releasing the cell.
+                #[cfg(all(feature = "analysis", feature = "vo_bit"))]
+                if crate::util::metadata::vo_bit::is_vo_bit_set(potential_object) {
+                    let lifetimes = &space.common().global_state.object_lifetimes;
+                    if cfg!(feature = "eager_sweeping") {
+                        lifetimes.record_death(potential_object, cell_size);
+                    } else {
+                        // Lazy sweeping happens after the GC, which has advanced the epoch.
+                        lifetimes.record_death_after_gc(potential_object, cell_size);
+                    }
+                }
                 // clear VO bit if it is ever set. It is possible that the VO bit is never set for this cell (i.e. there was no object in this cell before this GC),
                 // we unset the bit anyway.
                 #[cfg(feature = "vo_bit")]
                 crate::util::metadata::vo_bit::unset_vo_bit_nocheck(potential_object);
+                if release_cell_body {
+                    Self::release_cell_body(cell, cell_size, poison);
+                }
                 unsafe {
                     cell.store::<Address>(last);
                 }
@@ -316,7 +372,7 @@ impl Block {
     /// In this implementation, we simply go through each possible object
     /// reference and see if it has the mark bit set. If we find mark bit, that means the cell is alive. If we didn't find
     /// the mark bit in the entire cell, it means the cell is dead.
-    fn naive_brute_force_sweep<VM: VMBinding>(&self) {
+    fn naive_brute_force_sweep<VM: VMBinding>(&self, release_cell_body: bool, poison: bool) {
         use crate::util::constants::MIN_OBJECT_SIZE;
 
         // Cell size for this block.
@@ -372,6 +428,9 @@ impl Block {
 
                     // store the previous cell to make the free list
                     debug_assert!(last.is_zero() || (last >= self.start() && last < self.end()));
+                    if release_cell_body {
+                        Self::release_cell_body(cell, cell_size, poison);
+                    }
                     unsafe {
                         cell.store::<Address>(last);
                     }
@@ -385,9 +444,53 @@ impl Block {
         self.store_free_list(last);
     }
 
-    /// Get the chunk containing the block.
-    pub fn chunk(&self) -> Chunk {
-        Chunk::from_unaligned_address(self.start())
+    /// Clear the mark bits of the objects in the block if the mark bit is in the header.  Side mark
+    /// bits are cleared in bulk for each chunk instead.  Like the naive sweep, we look for the
+    /// first marked potential object reference in each cell, and treat it as the object in the
+    /// cell.
+    pub fn clear_marks_in_header<VM: VMBinding>(&self) {
+        use crate::util::constants::MIN_OBJECT_SIZE;
+
+        let cell_size = self.load_block_cell_size();
+        if cell_size == 0 {
+            return;
+        }
+        let mut cell = self.start();
+        while cell + cell_size <= self.end() {
+            let mut cursor = cell;
+            while cursor < cell + cell_size {
+                let potential_object_ref = unsafe {
+                    // We know cursor plus an offset cannot be 0.
+                    ObjectReference::from_raw_address_unchecked(
+                        cursor + VM::VMObjectModel::OBJECT_REF_OFFSET_LOWER_BOUND,
+                    )
+                };
+                if VM::VMObjectModel::LOCAL_MARK_BIT_SPEC
+                    .is_marked::<VM>(potential_object_ref, Ordering::SeqCst)
+                {
+                    VM::VMObjectModel::LOCAL_MARK_BIT_SPEC.store_atomic::<VM, u8>(
+                        potential_object_ref,
+                        0,
+                        None,
+                        Ordering::SeqCst,
+                    );
+                    break;
+                }
+                cursor += MIN_OBJECT_SIZE;
+            }
+            cell += cell_size;
+        }
+    }
+
+    /// Poison a free cell if `poison` is true, and mark it as inaccessible for memory checkers. The first word
+    /// of the cell is not touched, as it will hold the free list link.
+    fn release_cell_body(cell: Address, cell_size: usize, poison: bool) {
+        let body = cell + crate::util::constants::BYTES_IN_ADDRESS;
+        let len = cell_size - crate::util::constants::BYTES_IN_ADDRESS;
+        if poison {
+            crate::util::memory::poison(body, len);
+        }
+        crate::util::memory::mark_noaccess(body, len);
     }
 
     /// Initialize a clean block after acquired from page-resource.
@@ -395,6 +498,33 @@ impl Block {
         self.set_state(BlockState::Unmarked);
     }
 
+    /// Divide a new block into cells of the given size, and put all the cells in the free list.
+    pub fn init_cells(&self, cell_size: usize) {
+        debug_assert_ne!(cell_size, 0);
+        let block_end = self.start() + Block::BYTES;
+        let mut old_cell = unsafe { Address::zero() };
+        let mut new_cell = self.start();
+
+        let final_cell = loop {
+            unsafe {
+                new_cell.store::<Address>(old_cell);
+            }
+            old_cell = new_cell;
+            new_cell += cell_size;
+            if new_cell + cell_size > block_end {
+                break old_cell;
+            };
+        };
+
+        self.store_free_list(final_cell);
+        self.store_block_cell_size(cell_size);
+        #[cfg(feature = "malloc_native_mimalloc")]
+        {
+            self.store_local_free_list(Address::ZERO);
+            self.store_thread_free_list(Address::ZERO);
+        }
+    }
+
     /// Deinitalize a block before releasing.
     pub fn deinit(&self) {
         self.set_state(BlockState::Unallocated);
@@ -170,7 +170,7 @@ impl BlockList {
             // We should not have unallocated blocks in a block list
             debug_assert_ne!(block.get_state(), BlockState::Unallocated);
             if !block.attempt_release(space) {
-                block.sweep::<VM>();
+                block.sweep(space);
             }
         }
     }
@@ -1,5 +1,5 @@
 use std::sync::{
-    atomic::{AtomicUsize, Ordering},
+    atomic::{AtomicBool, AtomicUsize, Ordering},
     Arc,
 };
 
@@ -7,31 +7,38 @@ use crate::{
     policy::{marksweepspace::native_ms::*, sft::GCWorkerMutRef},
     scheduler::{GCWorkScheduler, GCWorker, WorkBucketStage},
     util::{
+        alloc::allocator,
         copy::CopySemantics,
         epilogue,
         heap::{BlockPageResource, PageResource},
         metadata::{self, side_metadata::SideMetadataSpec, MetadataSpec},
         object_enum::{self, ObjectEnumerator},
-        ObjectReference,
+        object_forwarding, Address, ObjectReference,
     },
     vm::{ActivePlan, VMBinding},
 };
 
-#[cfg(feature = "is_mmtk_object")]
-use crate::util::Address;
-
 use crate::plan::ObjectQueue;
 use crate::plan::VectorObjectQueue;
+use crate::policy::copy_context::PolicyCopyContext;
+use crate::policy::gc_work::TraceKind;
+use crate::policy::sft::PolicyKind;
 use crate::policy::sft::SFT;
 use crate::policy::space::{CommonSpace, Space};
+use crate::util::alloc::AllocationOptions;
 use crate::util::constants::LOG_BYTES_IN_PAGE;
 use crate::util::heap::chunk_map::*;
 use crate::util::linear_scan::Region;
-use crate::util::VMThread;
+use crate::util::{VMThread, VMWorkerThread};
 use crate::vm::ObjectModel;
 use crate::vm::Scanning;
 use std::sync::Mutex;
 
+/// Trace objects without moving them.
+pub(crate) const TRACE_KIND_FAST: TraceKind = 0;
+/// Trace objects, and move the objects in the repacking source blocks.
+pub(crate) const TRACE_KIND_REPACK: TraceKind = 1;
+
 /// The result for `MarkSweepSpace.acquire_block()`. `MarkSweepSpace` will attempt
 /// to allocate from abandoned blocks first. If none found, it will get a new block
 /// from the page resource.
@@ -66,6 +73,13 @@ pub enum BlockAcquireResult {
 /// |                | Eager: Sweep local blocks                       | Eager: Sweep global blocks                   |           |
 /// |                | Both: Return local blocks to a temp global list |                                              |           |
 /// | GC - End of GC | -                                               | Merge the temp global lists                  | -         |
+///
+/// If the option `ms_repack_interval` is set, the space periodically repacks sparse blocks.  In
+/// the `Prepare` stage of a repacking GC, we select sparse blocks from the abandoned block lists
+/// as repacking sources.  The live objects in the sources are copied into fresh blocks of the same
+/// size class during tracing, so that the sources are released as a whole in the `Release` stage.
+/// Each GC worker copies objects into its own target blocks (see [`MarkSweepCopyContext`]), and
+/// returns them to the space in the `Release` stage.
 pub struct MarkSweepSpace<VM: VMBinding> {
     pub common: CommonSpace<VM>,
     pr: BlockPageResource<VM, Block>,
@@ -83,8 +97,25 @@ pub struct MarkSweepSpace<VM: VMBinding> {
     /// and will be moved to the abandoned lists above at the end of a GC.
     abandoned_in_gc: Mutex<AbandonedBlockLists>,
     /// Count the number of pending `ReleaseMarkSweepSpace` and `ReleaseMutator` work packets during
-    /// the `Release` stage.
+    /// the `Release` stage.  In a repacking GC, this also counts the `ReleaseCollector` packets,
+    /// which return the repacking targets of the GC workers.
     pending_release_packets: AtomicUsize,
+    /// Repack the space every this number of GCs.  Zero means never.
+    repack_interval: usize,
+    /// The number of GCs since the space was last repacked.
+    gcs_since_repack: AtomicUsize,
+    /// Is the current GC a repacking GC?
+    in_repack: AtomicBool,
+    /// Is the space collected in the current GC?  This is set when the space is prepared, and
+    /// cleared when all the release packets are done.  All the objects in the space are considered
+    /// live in GCs that do not collect the space, such as nursery GCs of generational plans.
+    in_collection: AtomicBool,
+    /// The blocks whose live objects are moved out in the current GC.
+    repack_sources: Mutex<Vec<Block>>,
+    /// The number of fresh blocks we expect to need for the repacking targets in the current GC.
+    repack_budget: AtomicUsize,
+    /// Set if we have run out of `repack_budget`.  We stop moving objects once this is set.
+    repack_exhausted: AtomicBool,
 }
 
 unsafe impl<VM: VMBinding> Sync for MarkSweepSpace<VM> {}
@@ -161,9 +192,34 @@ impl<VM: VMBinding> SFT for MarkSweepSpace<VM> {
     fn name(&self) -> &'static str {
         self.common.name
     }
+    fn policy_kind(&self) -> PolicyKind {
+        PolicyKind::MarkSweepSpace
+    }
+    fn describe_object_metadata(&self, object: ObjectReference) -> Vec<String> {
+        let block = Block::containing(object);
+        vec![
+            crate::util::metadata::describe::mark_bit::<VM>(object),
+            format!(
+                "chunk {:?}: {:?}",
+                block.chunk(),
+                self.chunk_map.get(block.chunk())
+            ),
+            format!("block {:?}: {:?}", block, block.get_state()),
+        ]
+    }
 
     fn is_live(&self, object: crate::util::ObjectReference) -> bool {
-        VM::VMObjectModel::LOCAL_MARK_BIT_SPEC.is_marked::<VM>(object, Ordering::SeqCst)
+        !self.in_collection()
+            || VM::VMObjectModel::LOCAL_MARK_BIT_SPEC.is_marked::<VM>(object, Ordering::SeqCst)
+            || (self.is_in_repack_source(object) && object_forwarding::is_forwarded::<VM>(object))
+    }
+
+    fn get_forwarded_object(&self, object: ObjectReference) -> Option<ObjectReference> {
+        if self.is_in_repack_source(object) && object_forwarding::is_forwarded::<VM>(object) {
+            Some(object_forwarding::read_forwarding_pointer::<VM>(object))
+        } else {
+            None
+        }
     }
 
     #[cfg(feature = "object_pinning")]
@@ -182,7 +238,7 @@ impl<VM: VMBinding> SFT for MarkSweepSpace<VM> {
     }
 
     fn is_movable(&self) -> bool {
-        false
+        self.repack_interval != 0
     }
 
     #[cfg(feature = "sanity")]
@@ -190,9 +246,12 @@ impl<VM: VMBinding> SFT for MarkSweepSpace<VM> {
         true
     }
 
-    fn initialize_object_metadata(&self, _object: crate::util::ObjectReference, _alloc: bool) {
+    fn initialize_object_metadata(&self, object: crate::util::ObjectReference, _alloc: bool) {
+        if self.common.needs_log_bit {
+            VM::VMObjectModel::GLOBAL_LOG_BIT_SPEC.mark_as_unlogged::<VM>(object, Ordering::SeqCst);
+        }
         #[cfg(feature = "vo_bit")]
-        crate::util::metadata::vo_bit::set_vo_bit(_object);
+        crate::util::metadata::vo_bit::set_vo_bit(object);
     }
 
     #[cfg(feature = "is_mmtk_object")]
@@ -256,18 +315,25 @@ impl<VM: VMBinding> Space<VM> for MarkSweepSpace<VM> {
 }
 
 impl<VM: VMBinding> crate::policy::gc_work::PolicyTraceObject<VM> for MarkSweepSpace<VM> {
-    fn trace_object<Q: ObjectQueue, const KIND: crate::policy::gc_work::TraceKind>(
+    fn trace_object<Q: ObjectQueue, const KIND: TraceKind>(
         &self,
         queue: &mut Q,
         object: ObjectReference,
-        _copy: Option<CopySemantics>,
-        _worker: &mut GCWorker<VM>,
+        copy: Option<CopySemantics>,
+        worker: &mut GCWorker<VM>,
     ) -> ObjectReference {
-        self.trace_object(queue, object)
+        // Other plans may use the same trace kind for their own policies (e.g. the defragmenting
+        // trace of Immix), in which case the space is not repacked.
+        if KIND == TRACE_KIND_REPACK && self.in_repack() {
+            self.trace_object_with_repacking(queue, object, copy.unwrap(), worker)
+        } else {
+            // Objects reached from pinning roots are never moved.
+            self.trace_object(queue, object)
+        }
     }
 
-    fn may_move_objects<const KIND: crate::policy::gc_work::TraceKind>() -> bool {
-        false
+    fn may_move_objects<const KIND: TraceKind>() -> bool {
+        KIND == TRACE_KIND_REPACK
     }
 }
 
@@ -284,27 +350,47 @@ impl<VM: VMBinding> MarkSweepSpace<VM> {
     }
 
     pub fn new(args: crate::policy::space::PlanCreateSpaceArgs<VM>) -> MarkSweepSpace<VM> {
+        let repack_interval = *args.options.ms_repack_interval;
+        Self::new_with_repack_interval(args, repack_interval)
+    }
+
+    /// Create a space that never moves objects.  It is used as the non-moving space of all plans.
+    pub fn new_non_moving(
+        args: crate::policy::space::PlanCreateSpaceArgs<VM>,
+    ) -> MarkSweepSpace<VM> {
+        Self::new_with_repack_interval(args, 0)
+    }
+
+    fn new_with_repack_interval(
+        args: crate::policy::space::PlanCreateSpaceArgs<VM>,
+        repack_interval: usize,
+    ) -> MarkSweepSpace<VM> {
         let scheduler = args.scheduler.clone();
         let vm_map = args.vm_map;
         let is_discontiguous = args.vmrequest.is_discontiguous();
         let local_specs = {
-            metadata::extract_side_metadata(&vec![
-                MetadataSpec::OnSide(Block::NEXT_BLOCK_TABLE),
-                MetadataSpec::OnSide(Block::PREV_BLOCK_TABLE),
-                MetadataSpec::OnSide(Block::FREE_LIST_TABLE),
-                MetadataSpec::OnSide(Block::SIZE_TABLE),
+            let mut specs: Vec<MetadataSpec> = Block::METADATA_SPECS
+                .iter()
+                .map(|spec| MetadataSpec::OnSide(*spec))
+                .collect();
+            specs.extend([
                 #[cfg(feature = "malloc_native_mimalloc")]
                 MetadataSpec::OnSide(Block::LOCAL_FREE_LIST_TABLE),
                 #[cfg(feature = "malloc_native_mimalloc")]
                 MetadataSpec::OnSide(Block::THREAD_FREE_LIST_TABLE),
-                MetadataSpec::OnSide(Block::BLOCK_LIST_TABLE),
-                MetadataSpec::OnSide(Block::TLS_TABLE),
-                MetadataSpec::OnSide(Block::MARK_TABLE),
-                MetadataSpec::OnSide(ChunkMap::ALLOC_TABLE),
                 *VM::VMObjectModel::LOCAL_MARK_BIT_SPEC,
-            ])
+            ]);
+            // Objects are only forwarded if the space is repacked.
+            if repack_interval != 0 {
+                specs.extend([
+                    *VM::VMObjectModel::LOCAL_FORWARDING_BITS_SPEC,
+                    *VM::VMObjectModel::LOCAL_FORWARDING_POINTER_SPEC,
+                ]);
+            }
+            metadata::extract_side_metadata(&specs)
         };
         let common = CommonSpace::new(args.into_policy_args(false, false, local_specs));
+        let space_index = common.space_index;
         MarkSweepSpace {
             pr: if is_discontiguous {
                 BlockPageResource::new_discontiguous(
@@ -322,11 +408,18 @@ impl<VM: VMBinding> MarkSweepSpace<VM> {
                 )
             },
             common,
-            chunk_map: ChunkMap::new(),
+            chunk_map: ChunkMap::new(space_index),
             scheduler,
             abandoned: Mutex::new(AbandonedBlockLists::new()),
             abandoned_in_gc: Mutex::new(AbandonedBlockLists::new()),
             pending_release_packets: AtomicUsize::new(0),
+            repack_interval,
+            gcs_since_repack: AtomicUsize::new(0),
+            in_repack: AtomicBool::new(false),
+            in_collection: AtomicBool::new(false),
+            repack_sources: Mutex::new(vec![]),
+            repack_budget: AtomicUsize::new(0),
+            repack_exhausted: AtomicBool::new(false),
         }
     }
 
@@ -382,7 +475,7 @@ impl<VM: VMBinding> MarkSweepSpace<VM> {
         }
     }
 
-    fn trace_object<Q: ObjectQueue>(
+    pub fn trace_object<Q: ObjectQueue>(
         &self,
         queue: &mut Q,
         object: ObjectReference,
@@ -395,11 +488,217 @@ impl<VM: VMBinding> MarkSweepSpace<VM> {
         if self.attempt_mark(object) {
             let block = Block::containing(object);
             block.set_state(BlockState::Marked);
+            if self.repack_interval != 0 {
+                block.inc_live_cells();
+            }
             queue.enqueue(object);
         }
         object
     }
 
+    /// Trace an object in a repacking GC.  Objects in the repacking sources are copied to the
+    /// repacking targets unless we have run out of the budget.
+    fn trace_object_with_repacking<Q: ObjectQueue>(
+        &self,
+        queue: &mut Q,
+        object: ObjectReference,
+        semantics: CopySemantics,
+        worker: &mut GCWorker<VM>,
+    ) -> ObjectReference {
+        if !Block::containing(object).is_repack_source() {
+            return self.trace_object(queue, object);
+        }
+
+        let forwarding_status = object_forwarding::attempt_to_forward::<VM>(object);
+        if object_forwarding::state_is_forwarded_or_being_forwarded(forwarding_status) {
+            // Another thread is forwarding the object, or has forwarded it.  The object may not be
+            // moved if that thread has run out of the budget.
+            object_forwarding::spin_and_get_forwarded_object::<VM>(object, forwarding_status)
+        } else if self.is_marked(object) {
+            // The object has been marked in place, e.g. by the trace from pinning roots.
+            object_forwarding::clear_forwarding_bits::<VM>(object);
+            object
+        } else if self.repack_exhausted.load(Ordering::Relaxed) {
+            // Mark the object in place.  We have won the forwarding race, so no other thread can
+            // mark it at the same time.
+            VM::VMObjectModel::LOCAL_MARK_BIT_SPEC.mark::<VM>(object, Ordering::SeqCst);
+            object_forwarding::clear_forwarding_bits::<VM>(object);
+            let block = Block::containing(object);
+            block.set_state(BlockState::Marked);
+            block.inc_live_cells();
+            queue.enqueue(object);
+            object
+        } else {
+            let new_object = object_forwarding::forward_object::<VM>(
+                object,
+                semantics,
+                worker.get_copy_context_mut(),
+                |_| {},
+            );
+            queue.enqueue(new_object);
+            new_object
+        }
+    }
+
+    fn is_marked(&self, object: ObjectReference) -> bool {
+        VM::VMObjectModel::LOCAL_MARK_BIT_SPEC.is_marked::<VM>(object, Ordering::SeqCst)
+    }
+
+    /// Is the object in a repacking source in the current GC?  Objects in other blocks are never
+    /// forwarded, and we should not look at their forwarding bits.
+    fn is_in_repack_source(&self, object: ObjectReference) -> bool {
+        self.in_repack() && Block::containing(object).is_repack_source()
+    }
+
+    /// Is the space collected in the current GC?  See `in_collection`.
+    pub fn in_collection(&self) -> bool {
+        self.in_collection.load(Ordering::SeqCst)
+    }
+
+    /// Is the current GC a repacking GC?
+    pub fn in_repack(&self) -> bool {
+        self.in_repack.load(Ordering::Relaxed)
+    }
+
+    /// Decide whether the current GC repacks the space.  This is called once when each GC is
+    /// scheduled.
+    pub fn decide_whether_to_repack(&self) -> bool {
+        let repack = self.repack_interval != 0
+            && self.gcs_since_repack.fetch_add(1, Ordering::Relaxed) + 1 >= self.repack_interval;
+        if repack {
+            self.gcs_since_repack.store(0, Ordering::Relaxed);
+        }
+        self.in_repack.store(repack, Ordering::Relaxed);
+        repack
+    }
+
+    /// Select the repacking sources for the current GC.  This must be called before preparing the
+    /// space, as the space resets the live cell counts of the last GC in the `Prepare` stage.
+    ///
+    /// We only consider the blocks in the abandoned lists.  Mutators have not allocated into them
+    /// since the last GC, so the number of cells marked in the last GC is an upper bound of the
+    /// number of objects we may copy out of each block.  A block is a candidate if at most half of
+    /// its cells were live.  We select the sparsest candidates first, as long as the fresh blocks
+    /// needed to hold their live objects fit in `available_pages`.
+    pub fn select_repack_sources(&self, available_pages: usize) {
+        if !self.in_repack() {
+            return;
+        }
+        let abandoned = self.abandoned.lock().unwrap();
+
+        // (block, bin, live cells, cells)
+        let mut candidates: Vec<(Block, usize, usize, usize)> = vec![];
+        for bin in 0..MI_BIN_FULL {
+            for list in [
+                &abandoned.available[bin],
+                &abandoned.unswept[bin],
+                &abandoned.consumed[bin],
+            ] {
+                for block in list.iter() {
+                    let live = block.live_cells();
+                    let cells = block.cells();
+                    if live > 0 && live * 2 <= cells {
+                        candidates.push((block, bin, live, cells));
+                    }
+                }
+            }
+        }
+        // Sort by the fraction of live cells, sparsest first.
+        candidates.sort_by(|a, b| (a.2 * b.3).cmp(&(b.2 * a.3)));
+
+        let max_blocks = available_pages >> Block::LOG_PAGES;
+        let mut live_per_bin = [0usize; MI_BIN_FULL];
+        let mut blocks_needed = 0;
+        let mut sources = self.repack_sources.lock().unwrap();
+        debug_assert!(sources.is_empty());
+        for (block, bin, live, cells) in candidates {
+            let blocks_for_bin = (live_per_bin[bin] + cells - 1) / cells;
+            let new_blocks_for_bin = (live_per_bin[bin] + live + cells - 1) / cells;
+            if blocks_needed + new_blocks_for_bin - blocks_for_bin > max_blocks {
+                continue;
+            }
+            live_per_bin[bin] += live;
+            blocks_needed += new_blocks_for_bin - blocks_for_bin;
+            block.set_repack_source(true);
+            sources.push(block);
+        }
+        debug!(
+            "Repacking {} blocks into at most {} blocks",
+            sources.len(),
+            blocks_needed
+        );
+        self.repack_budget.store(blocks_needed, Ordering::Relaxed);
+        self.repack_exhausted.store(false, Ordering::Relaxed);
+    }
+
+    /// Acquire a fresh block for the repacking targets of a GC worker, and divide it into cells of
+    /// the given size.
+    fn acquire_repack_block(&self, tls: VMWorkerThread, cell_size: usize) -> Block {
+        // The budget is an estimate, so we acquire a block anyway, but stop moving more objects if
+        // we have exceeded the budget.
+        if self
+            .repack_budget
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |budget| {
+                budget.checked_sub(1)
+            })
+            .is_err()
+        {
+            self.repack_exhausted.store(true, Ordering::Relaxed);
+        }
+        let acquired = self.acquire(
+            tls.0,
+            Block::BYTES >> LOG_BYTES_IN_PAGE,
+            AllocationOptions::default(),
+        );
+        assert!(
+            !acquired.is_zero(),
+            "Out of memory when repacking the mark sweep space"
+        );
+        let block = Block::from_unaligned_address(acquired);
+        self.record_new_block(block);
+        block.init_cells(cell_size);
+        block.store_tls(tls.0);
+        block.set_state(BlockState::Marked);
+        // The chunk may have been free when we cleared the mark bits in the `Prepare` stage.
+        if let MetadataSpec::OnSide(side) = *VM::VMObjectModel::LOCAL_MARK_BIT_SPEC {
+            side.bzero_metadata(block.start(), Block::BYTES);
+        }
+        block
+    }
+
+    /// Mark an object copied into a repacking target.
+    fn post_copy(&self, object: ObjectReference) {
+        VM::VMObjectModel::LOCAL_MARK_BIT_SPEC.mark::<VM>(object, Ordering::SeqCst);
+        #[cfg(feature = "vo_bit")]
+        crate::util::metadata::vo_bit::set_vo_bit(object);
+        let block = Block::containing(object);
+        debug_assert_eq!(block.get_state(), BlockState::Marked);
+        block.inc_live_cells();
+    }
+
+    /// Return the repacking targets of a GC worker to the space.  They are abandoned blocks after
+    /// the GC, like the blocks returned by mutators in the `Release` stage.
+    fn return_repack_targets(&self, targets: &mut BlockLists) {
+        {
+            let mut abandoned_in_gc = self.abandoned_in_gc.lock().unwrap();
+            for bin in 0..MI_BIN_FULL {
+                abandoned_in_gc.consumed[bin].append(&mut targets[bin]);
+            }
+        }
+        self.release_packet_done();
+    }
+
+    /// Clear the repacking sources.  The sources that still have marked objects are swept like
+    /// other blocks, and the others are released.
+    fn release_repack_sources(&self) {
+        for block in self.repack_sources.lock().unwrap().drain(..) {
+            block.set_repack_source(false);
+            if let MetadataSpec::OnSide(side) = *VM::VMObjectModel::LOCAL_FORWARDING_BITS_SPEC {
+                side.bzero_metadata(block.start(), Block::BYTES);
+            }
+        }
+    }
+
     pub fn record_new_block(&self, block: Block) {
         block.init();
         self.chunk_map.set(block.chunk(), ChunkState::Allocated);
@@ -408,21 +707,43 @@ impl<VM: VMBinding> MarkSweepSpace<VM> {
     pub fn prepare(&mut self) {
         #[cfg(debug_assertions)]
         self.abandoned_in_gc.lock().unwrap().assert_empty();
+        self.in_collection.store(true, Ordering::SeqCst);
 
         // # Safety: MarkSweepSpace reference is always valid within this collection cycle.
         let space = unsafe { &*(self as *const Self) };
         let work_packets = self
             .chunk_map
-            .generate_tasks(|chunk| Box::new(PrepareChunkMap { space, chunk }));
+            .for_each_chunk(move |chunk, _worker| space.prepare_chunk(chunk));
         self.scheduler.work_buckets[crate::scheduler::WorkBucketStage::Prepare]
             .bulk_add(work_packets);
     }
 
+    /// Clear the marks of the space in the middle of a GC, so that the space can be traced again.
+    /// This is used by plans that trace the heap twice in a GC (i.e. MarkCompact) for the spaces
+    /// they do not release between the two traces.  The chunks are cleared in the current thread.
+    pub fn clear_marks(&self) {
+        for chunk in self.chunk_map.allocated_chunks() {
+            self.prepare_chunk(chunk);
+        }
+    }
+
     pub fn release(&mut self) {
+        probe!(
+            mmtk,
+            sweep_space_begin,
+            self.get_name().as_ptr(),
+            self.get_name().len()
+        );
         let num_mutators = VM::VMActivePlan::number_of_mutators();
-        // all ReleaseMutator work packets plus the ReleaseMarkSweepSpace packet
+        // All ReleaseMutator work packets plus the ReleaseMarkSweepSpace packet, and all
+        // ReleaseCollector packets if we are repacking
+        let num_collectors = if self.in_repack() {
+            self.scheduler.num_workers()
+        } else {
+            0
+        };
         self.pending_release_packets
-            .store(num_mutators + 1, Ordering::SeqCst);
+            .store(num_mutators + 1 + num_collectors, Ordering::SeqCst);
 
         // Do work in separate work packet in order not to slow down the `Release` work packet which
         // blocks all `ReleaseMutator` packets.
@@ -432,6 +753,7 @@ impl<VM: VMBinding> MarkSweepSpace<VM> {
     }
 
     pub fn end_of_gc(&mut self) {
+        self.in_repack.store(false, Ordering::Relaxed);
         epilogue::debug_assert_counter_zero(
             &self.pending_release_packets,
             "pending_release_packets",
@@ -441,6 +763,13 @@ impl<VM: VMBinding> MarkSweepSpace<VM> {
     /// Release a block.
     pub fn release_block(&self, block: Block) {
         self.block_clear_metadata(block);
+        if *self.common.options.poison_on_free {
+            crate::util::memory::poison(block.start(), Block::BYTES);
+        }
+        crate::util::memory::mark_noaccess(block.start(), Block::BYTES);
+        if *self.common.options.protect_free_blocks {
+            crate::util::memory::mprotect(block.start(), Block::BYTES).unwrap();
+        }
 
         block.deinit();
         self.pr.release_block(block);
@@ -454,7 +783,13 @@ impl<VM: VMBinding> MarkSweepSpace<VM> {
         crate::util::metadata::vo_bit::bzero_vo_bit(block.start(), Block::BYTES);
     }
 
-    pub fn acquire_block(&self, tls: VMThread, size: usize, align: usize) -> BlockAcquireResult {
+    pub fn acquire_block(
+        &self,
+        tls: VMThread,
+        size: usize,
+        align: usize,
+        alloc_options: AllocationOptions,
+    ) -> BlockAcquireResult {
         {
             let mut abandoned = self.abandoned.lock().unwrap();
             let bin = mi_bin::<VM>(size, align);
@@ -476,7 +811,7 @@ impl<VM: VMBinding> MarkSweepSpace<VM> {
             }
         }
 
-        let acquired = self.acquire(tls, Block::BYTES >> LOG_BYTES_IN_PAGE);
+        let acquired = self.acquire(tls, Block::BYTES >> LOG_BYTES_IN_PAGE, alloc_options);
         if acquired.is_zero() {
             BlockAcquireResult::Exhausted
         } else {
@@ -492,9 +827,35 @@ impl<VM: VMBinding> MarkSweepSpace<VM> {
         &self.abandoned_in_gc
     }
 
+    /// Schedule work packets to sweep the unswept abandoned blocks while mutators are idle, so
+    /// that mutators do not need to sweep them when they allocate.  Return `true` if any work
+    /// packet is scheduled.  Only lazy sweeping leaves unswept blocks.
+    pub fn schedule_idle_sweeping(&'static self, scheduler: &GCWorkScheduler<VM>) -> bool {
+        if cfg!(feature = "eager_sweeping") {
+            return false;
+        }
+        let bins: Vec<usize> = {
+            let abandoned = self.abandoned.lock().unwrap();
+            (0..MI_BIN_FULL)
+                .filter(|bin| !abandoned.unswept[*bin].is_empty())
+                .collect()
+        };
+        for bin in bins.iter() {
+            // We are holding the lock for synchronizing GC workers.  Do not notify now.
+            scheduler.work_buckets[WorkBucketStage::Unconstrained].add_no_notify(
+                SweepAbandonedBlocks {
+                    space: self,
+                    bin: *bin,
+                },
+            );
+        }
+        !bins.is_empty()
+    }
+
     pub fn release_packet_done(&self) {
         let old = self.pending_release_packets.fetch_sub(1, Ordering::SeqCst);
         if old == 1 {
+            self.in_collection.store(false, Ordering::SeqCst);
             if cfg!(feature = "eager_sweeping") {
                 // When doing eager sweeping, we start sweeing now.
                 // After sweeping, we will recycle blocks.
@@ -544,40 +905,57 @@ impl<VM: VMBinding> MarkSweepSpace<VM> {
 
         // BlockPageResource uses worker-local block queues to eliminate contention when releasing
         // blocks, similar to how the MarkSweepSpace caches blocks in `abandoned_in_gc` before
-        // returning to the global pool.  We flush the BlockPageResource, too.
-        self.pr.flush_all();
+        // returning to the global pool.  We flush the BlockPageResource, too.  With
+        // `deterministic_gc`, the blocks are reused in the order of their addresses.
+        if *self.common.options.deterministic_gc {
+            self.pr.flush_all_sorted();
+        } else {
+            self.pr.flush_all();
+        }
+
+        probe!(
+            mmtk,
+            sweep_space_end,
+            self.get_name().as_ptr(),
+            self.get_name().len()
+        );
     }
 }
 
 use crate::scheduler::GCWork;
 use crate::MMTK;
 
-struct PrepareChunkMap<VM: VMBinding> {
-    space: &'static MarkSweepSpace<VM>,
-    chunk: Chunk,
-}
-
-impl<VM: VMBinding> GCWork<VM> for PrepareChunkMap<VM> {
-    fn do_work(&mut self, _worker: &mut GCWorker<VM>, _mmtk: &'static MMTK<VM>) {
-        debug_assert!(self.space.chunk_map.get(self.chunk) == ChunkState::Allocated);
+impl<VM: VMBinding> MarkSweepSpace<VM> {
+    /// Prepare a chunk for the GC.  This is done in parallel for each chunk.
+    fn prepare_chunk(&self, chunk: Chunk) {
+        debug_assert!(self.chunk_map.get(chunk) == ChunkState::Allocated);
+        let mark_bit_on_side = VM::VMObjectModel::LOCAL_MARK_BIT_SPEC
+            .as_spec()
+            .is_on_side();
         // number of allocated blocks.
         let mut n_occupied_blocks = 0;
-        self.chunk
+        chunk
             .iter_region::<Block>()
             .filter(|block| block.get_state() != BlockState::Unallocated)
             .for_each(|block| {
                 // Clear block mark
                 block.set_state(BlockState::Unmarked);
+                if !mark_bit_on_side {
+                    block.clear_marks_in_header::<VM>();
+                }
+                if self.repack_interval != 0 {
+                    block.reset_live_cells();
+                }
                 // Count occupied blocks
                 n_occupied_blocks += 1
             });
         if n_occupied_blocks == 0 {
             // Set this chunk as free if there is no live blocks.
-            self.space.chunk_map.set(self.chunk, ChunkState::Free)
+            self.chunk_map.set(chunk, ChunkState::Free)
         } else {
             // Otherwise this chunk is occupied, and we reset the mark bit if it is on the side.
             if let MetadataSpec::OnSide(side) = *VM::VMObjectModel::LOCAL_MARK_BIT_SPEC {
-                side.bzero_metadata(self.chunk.start(), Chunk::BYTES);
+                side.bzero_metadata(chunk.start(), Chunk::BYTES);
             }
         }
     }
@@ -591,6 +969,9 @@ impl<VM: VMBinding> GCWork<VM> for ReleaseMarkSweepSpace<VM> {
     fn do_work(&mut self, _worker: &mut GCWorker<VM>, _mmtk: &'static MMTK<VM>) {
         {
             let mut abandoned = self.space.abandoned.lock().unwrap();
+            if self.space.in_repack() {
+                self.space.release_repack_sources();
+            }
             abandoned.sweep_later(self.space);
         }
 
@@ -622,7 +1003,7 @@ impl<VM: VMBinding> GCWork<VM> for SweepChunk<VM> {
             // We have released unmarked blocks in `ReleaseMarkSweepSpace` and `ReleaseMutator`.
             // We shouldn't see any unmarked blocks now.
             debug_assert_eq!(block.get_state(), BlockState::Marked);
-            block.sweep::<VM>();
+            block.sweep(self.space);
             allocated_blocks += 1;
         }
         probe!(mmtk, sweep_chunk, allocated_blocks);
@@ -634,6 +1015,41 @@ impl<VM: VMBinding> GCWork<VM> for SweepChunk<VM> {
     }
 }
 
+/// Sweep the unswept abandoned blocks of one size class while mutators are idle.  Mutators may
+/// take blocks from the same lists concurrently, so we only hold the lock while moving blocks
+/// between lists.
+struct SweepAbandonedBlocks<VM: VMBinding> {
+    space: &'static MarkSweepSpace<VM>,
+    bin: usize,
+}
+
+impl<VM: VMBinding> GCWork<VM> for SweepAbandonedBlocks<VM> {
+    fn do_work(&mut self, _worker: &mut GCWorker<VM>, mmtk: &'static MMTK<VM>) {
+        let mut swept_blocks = 0;
+        // Stop as soon as a GC is requested.  The remaining blocks will be swept by mutators, or
+        // released by the next GC.
+        while !mmtk.is_gc_requested() {
+            let Some(block) = self.space.abandoned.lock().unwrap().unswept[self.bin].pop() else {
+                break;
+            };
+            block.sweep(self.space);
+            swept_blocks += 1;
+
+            let mut abandoned = self.space.abandoned.lock().unwrap();
+            if block.has_free_cells() {
+                abandoned.available[self.bin].push(block);
+            } else {
+                abandoned.consumed[self.bin].push(block);
+            }
+        }
+        trace!(
+            "Swept {} abandoned blocks in bin {} while mutators are idle",
+            swept_blocks,
+            self.bin
+        );
+    }
+}
+
 struct RecycleBlocks<VM: VMBinding> {
     space: &'static MarkSweepSpace<VM>,
     counter: AtomicUsize,
@@ -652,3 +1068,71 @@ impl<VM: VMBinding> Drop for RecycleBlocks<VM> {
         epilogue::debug_assert_counter_zero(&self.counter, "RecycleBlocks::counter");
     }
 }
+
+/// The copy context for repacking the native mark sweep space.  Each GC worker copies objects into
+/// its own repacking targets, and returns them to the space when it is released.
+pub struct MarkSweepCopyContext<VM: VMBinding> {
+    tls: VMWorkerThread,
+    space: &'static MarkSweepSpace<VM>,
+    /// The blocks that this worker copies objects into in the current GC.  We copy objects into
+    /// the first block of the list of each size class.
+    targets: BlockLists,
+}
+
+impl<VM: VMBinding> PolicyCopyContext for MarkSweepCopyContext<VM> {
+    type VM = VM;
+
+    fn prepare(&mut self) {}
+    fn release(&mut self) {
+        if self.space.in_repack() {
+            self.space.return_repack_targets(&mut self.targets);
+        }
+    }
+    fn alloc_copy(
+        &mut self,
+        _original: ObjectReference,
+        bytes: usize,
+        align: usize,
+        offset: usize,
+    ) -> Address {
+        let bin = mi_bin::<VM>(bytes, align);
+        let cell = self.alloc_cell(bin);
+        allocator::align_allocation::<VM>(cell, align, offset)
+    }
+    fn post_copy(&mut self, obj: ObjectReference, _bytes: usize) {
+        self.space.post_copy(obj)
+    }
+}
+
+impl<VM: VMBinding> MarkSweepCopyContext<VM> {
+    pub(crate) fn new(tls: VMWorkerThread, space: &'static MarkSweepSpace<VM>) -> Self {
+        MarkSweepCopyContext {
+            tls,
+            space,
+            targets: new_empty_block_lists(),
+        }
+    }
+
+    /// Allocate a cell in the repacking targets for an object of the given size class.
+    fn alloc_cell(&mut self, bin: usize) -> Address {
+        let list = &mut self.targets[bin];
+        let cell_size = list.size;
+        let alloc_cell = |block: Block| {
+            let cell = block.load_free_list();
+            if !cell.is_zero() {
+                block.store_free_list(unsafe { cell.load::<Address>() });
+                crate::util::memory::zero(cell, cell_size);
+            }
+            cell
+        };
+        if let Some(block) = list.first {
+            let cell = alloc_cell(block);
+            if !cell.is_zero() {
+                return cell;
+            }
+        }
+        let block = self.space.acquire_repack_block(self.tls, cell_size);
+        list.push(block);
+        alloc_cell(block)
+    }
+}
@@ -26,6 +26,24 @@ pub trait SFT {
     /// The space name
     fn name(&self) -> &'static str;
 
+    /// The policy of the space
+    fn policy_kind(&self) -> PolicyKind;
+
+    /// Tell apart the SFTs of the same space.  A space may use different SFTs for the parts of the
+    /// space that behave differently, so the SFTs can skip per-object checks that are unnecessary
+    /// for those parts (see [`crate::policy::sft_map::SFTMap::update_sub_range`]).  The SFT of the
+    /// space itself is variant 0.
+    fn sft_variant(&self) -> u8 {
+        0
+    }
+
+    /// Describe the policy-specific metadata of an object for debugging, one item per string, such
+    /// as the mark bit, and the state of the block that contains the object. A policy should only
+    /// read the metadata that it uses. See [`crate::memory_manager::describe_object`].
+    fn describe_object_metadata(&self, _object: ObjectReference) -> Vec<String> {
+        vec![]
+    }
+
     /// Get forwarding pointer if the object is forwarded.
     fn get_forwarded_object(&self, _object: ObjectReference) -> Option<ObjectReference> {
         None
@@ -103,6 +121,57 @@ pub trait SFT {
     ) -> ObjectReference;
 }
 
+/// The policy of a space, returned by [`SFT::policy_kind`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PolicyKind {
+    /// A semi-space copying policy. See [`crate::policy::copyspace::CopySpace`].
+    CopySpace,
+    /// The Immix policy. See [`crate::policy::immix::ImmixSpace`].
+    ImmixSpace,
+    /// A non-moving space whose objects are never reclaimed. See
+    /// [`crate::policy::immortalspace::ImmortalSpace`].
+    ImmortalSpace,
+    /// A space for large objects. See [`crate::policy::largeobjectspace::LargeObjectSpace`].
+    LargeObjectSpace,
+    /// A lock-free immortal space. See
+    /// [`crate::policy::lockfreeimmortalspace::LockFreeImmortalSpace`].
+    LockFreeImmortalSpace,
+    /// A mark-compact policy. See [`crate::policy::markcompactspace::MarkCompactSpace`].
+    MarkCompactSpace,
+    /// The native mark-sweep policy.
+    MarkSweepSpace,
+    /// The mark-sweep policy on top of malloc.
+    MallocSpace,
+    /// A space for memory managed by the VM. See `crate::policy::vmspace::VMSpace`.
+    VMSpace,
+}
+
+/// The generation of an object in a generational plan.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Generation {
+    /// The object is young, and is collected in nursery GCs.
+    Nursery,
+    /// The object has survived nursery GCs, and is only collected in full-heap GCs.
+    Mature,
+}
+
+/// Information about the space and the policy of an object, returned by
+/// [`crate::memory_manager::object_space_info`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ObjectSpaceInfo {
+    /// The name of the space.
+    pub space_name: &'static str,
+    /// The policy of the space.
+    pub policy: PolicyKind,
+    /// Whether the GC may move the object. This is false if the space is non-moving, or the object
+    /// is pinned.
+    pub movable: bool,
+    /// The generation of the object, or `None` if the plan is not generational.
+    pub generation: Option<Generation>,
+    /// Whether the object is pinned. Objects in non-moving spaces are always considered pinned.
+    pub pinned: bool,
+}
+
 // Create erased VM refs for these types that will be used in `sft_trace_object()`.
 // In this way, we can store the refs with <VM> in SFT (which cannot have parameters with generic type parameters)
 
@@ -123,6 +192,9 @@ impl SFT for EmptySpaceSFT {
     fn name(&self) -> &'static str {
         EMPTY_SFT_NAME
     }
+    fn policy_kind(&self) -> PolicyKind {
+        panic!("Called policy_kind() on an empty space")
+    }
     fn is_live(&self, object: ObjectReference) -> bool {
         panic!(
             "Called is_live() on {:x}, which maps to an empty space",
@@ -38,6 +38,23 @@ pub trait SFTMap {
     /// Otherwise, the caller should check with `has_sft_entry()` before calling this method.
     unsafe fn update(&self, space: SFTRawPointer, start: Address, bytes: usize);
 
+    /// Set a different SFT for a part of a space, rounded to chunks.  A space can use this to
+    /// specialize the behavior of the SFT for the part, such as skipping the per-object forwarding
+    /// check in `is_live` for the chunks where no object will move.  `sft` must be an SFT of the
+    /// space that owns the range, with a unique [`SFT::sft_variant`], and the space must have
+    /// registered it with `notify_space_creation()`.  Setting the SFT of the space itself restores
+    /// the default behavior.
+    ///
+    /// Return false and do nothing if the map cannot have different SFTs for the same space (i.e.
+    /// `SFTSpaceMap`).  The space then has to rely on its own SFT.
+    ///
+    /// # Safety
+    /// The address must have a valid SFT entry in the map, and the range must be owned by the space.
+    unsafe fn update_sub_range(&self, sft: SFTRawPointer, start: Address, bytes: usize) -> bool {
+        self.update(sft, start, bytes);
+        true
+    }
+
     /// Notify the SFT map for space creation. `DenseChunkMap` needs to create an entry for the space.
     fn notify_space_creation(&mut self, _space: SFTRawPointer) {}
 
@@ -220,6 +237,16 @@ mod space_map {
             self.sft.get_unchecked(index).store(space);
         }
 
+        unsafe fn update_sub_range(
+            &self,
+            _sft: SFTRawPointer,
+            _start: Address,
+            _bytes: usize,
+        ) -> bool {
+            // We only have one entry for each space.
+            false
+        }
+
         unsafe fn clear(&self, addr: Address) {
             let index = Self::addr_to_index(addr);
             self.sft.get_unchecked(index).store(&EMPTY_SPACE_SFT as _);
@@ -306,10 +333,12 @@ mod dense_chunk_map {
     use super::*;
     use crate::util::conversions;
     use crate::util::heap::layout::vm_layout::BYTES_IN_CHUNK;
+    use crate::util::memory::MmapStrategy;
     use crate::util::metadata::side_metadata::spec_defs::SFT_DENSE_CHUNK_MAP_INDEX;
     use crate::util::metadata::side_metadata::*;
     use std::collections::HashMap;
-    use std::sync::atomic::Ordering;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
 
     /// SFTDenseChunkMap is a small table. It has one entry for each space in the table, and use
     /// side metadata to record the index for each chunk. This works for both 32 bits and 64 bits.
@@ -321,11 +350,15 @@ mod dense_chunk_map {
     /// will be costly in terms of memory. In this case, the dense chunk map is a good solution.
     pub struct SFTDenseChunkMap {
         /// The dense table, one entry per space. We use side metadata to store the space index for each chunk.
-        /// 0 is EMPTY_SPACE_SFT.
-        sft: Vec<SFTRefStorage>,
-        /// A map from space name (assuming they are unique) to their index. We use this to know whether we have
-        /// pushed &dyn SFT for a space, and to know its index.
-        index_map: HashMap<String, usize>,
+        /// 0 is EMPTY_SPACE_SFT. The table has a fixed size, and unused entries are EMPTY_SPACE_SFT, so adding
+        /// the spaces of a new MMTk instance does not move the entries that other instances are reading.
+        sft: Box<[SFTRefStorage]>,
+        /// The number of entries in `sft` that are used, including EMPTY_SPACE_SFT.
+        num_entries: AtomicUsize,
+        /// A map from the address of a space (and the SFT variant) to its index. We use this to know
+        /// whether we have pushed &dyn SFT for a space, and to know its index. Spaces of different MMTk
+        /// instances may have the same name, so we cannot use the names as keys.
+        index_map: Mutex<HashMap<(usize, u8), u8>>,
     }
 
     unsafe impl Sync for SFTDenseChunkMap {}
@@ -334,7 +367,7 @@ mod dense_chunk_map {
         fn has_sft_entry(&self, addr: Address) -> bool {
             if SFT_DENSE_CHUNK_MAP_INDEX.is_mapped(addr) {
                 let index = Self::addr_to_index(addr);
-                index < self.sft.len() as u8
+                (index as usize) < self.num_entries.load(Ordering::Acquire)
             } else {
                 // We haven't mapped side metadata for the chunk, so we do not have an SFT entry for the address.
                 false
@@ -363,20 +396,28 @@ mod dense_chunk_map {
         fn notify_space_creation(&mut self, space: SFTRawPointer) {
             // Insert the space into the SFT table, and the SFT map.
 
-            let space_name = unsafe { &*space }.name().to_string();
+            let mut index_map = self.index_map.lock().unwrap();
             // We shouldn't have this space in our map yet. Otherwise, this method is called multiple times for the same space.
-            assert!(!self.index_map.contains_key(&space_name));
+            assert!(!index_map.contains_key(&Self::space_key(space)));
             // Index for the space
-            let index = self.sft.len();
-            // Insert to hashmap and vec
-            self.sft.push(SFTRefStorage::new(space));
-            self.index_map.insert(space_name, index);
+            let index = self.num_entries.load(Ordering::Relaxed);
+            assert!(
+                index < self.sft.len(),
+                "Too many spaces for SFTDenseChunkMap: {}",
+                unsafe { &*space }.name()
+            );
+            // Insert to hashmap and the table. Other threads may be reading the table, so we
+            // only publish the new entry after it is stored.
+            self.sft[index].store(space);
+            self.num_entries.store(index + 1, Ordering::Release);
+            index_map.insert(Self::space_key(space), index as u8);
         }
 
         unsafe fn eager_initialize(&mut self, space: SFTRawPointer, start: Address, bytes: usize) {
             let context = SideMetadataContext {
                 global: vec![SFT_DENSE_CHUNK_MAP_INDEX],
                 local: vec![],
+                mmap_strategy: MmapStrategy::SIDE_METADATA,
             };
             context
                 .try_map_metadata_space(start, bytes, "SFTDenseChunkMap")
@@ -393,7 +434,12 @@ mod dense_chunk_map {
             start: Address,
             bytes: usize,
         ) {
-            let index: u8 = *self.index_map.get((*space).name()).unwrap() as u8;
+            let index: u8 = *self
+                .index_map
+                .lock()
+                .unwrap()
+                .get(&Self::space_key(space))
+                .unwrap();
 
             // Iterate through the chunks and record the space index in the side metadata.
             let first_chunk = conversions::chunk_align_down(start);
@@ -428,13 +474,25 @@ mod dense_chunk_map {
         const EMPTY_SFT_INDEX: u8 = 0;
 
         pub fn new() -> Self {
+            // The index is stored as a u8 in the side metadata.
+            let sft = (0..1 << u8::BITS)
+                .map(|_| SFTRefStorage::default())
+                .collect();
             Self {
+                sft,
                 // Empty space is at index 0
-                sft: vec![SFTRefStorage::default()],
-                index_map: HashMap::new(),
+                num_entries: AtomicUsize::new(1),
+                index_map: Mutex::new(HashMap::new()),
             }
         }
 
+        fn space_key(space: SFTRawPointer) -> (usize, u8) {
+            (
+                space as *const () as usize,
+                unsafe { &*space }.sft_variant(),
+            )
+        }
+
         pub fn addr_to_index(addr: Address) -> u8 {
             SFT_DENSE_CHUNK_MAP_INDEX.load_atomic::<u8>(addr, Ordering::Relaxed)
         }
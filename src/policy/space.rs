@@ -1,6 +1,8 @@
 use crate::global_state::GlobalState;
 use crate::plan::PlanConstraints;
-use crate::scheduler::GCWorkScheduler;
+use crate::scheduler::gc_work::PretouchMemory;
+use crate::scheduler::{GCWorkScheduler, Handshake, WorkBucketStage};
+use crate::util::alloc::AllocationOptions;
 use crate::util::conversions::*;
 use crate::util::metadata::side_metadata::{
     SideMetadataContext, SideMetadataSanity, SideMetadataSpec,
@@ -11,7 +13,7 @@ use crate::util::ObjectReference;
 
 use crate::util::heap::layout::vm_layout::{vm_layout, LOG_BYTES_IN_CHUNK};
 use crate::util::heap::{PageResource, VMRequest};
-use crate::util::options::Options;
+use crate::util::options::{Options, PretouchMode};
 use crate::vm::{ActivePlan, Collection};
 
 use crate::util::constants::{LOG_BYTES_IN_MBYTE, LOG_BYTES_IN_PAGE};
@@ -28,8 +30,10 @@ use crate::util::heap::layout::vm_layout::BYTES_IN_CHUNK;
 use crate::util::heap::layout::Mmapper;
 use crate::util::heap::layout::VMMap;
 use crate::util::heap::space_descriptor::SpaceDescriptor;
+use crate::util::heap::space_index;
 use crate::util::heap::HeapMeta;
-use crate::util::memory::{self, HugePageSupport, MmapProtection, MmapStrategy};
+use crate::util::heap_layout::SpaceLayout;
+use crate::util::memory::{self, MmapProtection, MmapStrategy};
 use crate::vm::VMBinding;
 
 use std::marker::PhantomData;
@@ -66,25 +70,48 @@ pub trait Space<VM: VMBinding>: 'static + SFT + Sync + Downcast {
     /// An allocator should call this method before doing any computation on the size to
     /// avoid arithmatic overflow. If we have to do computation in the allocation fastpath and
     /// overflow happens there, there is nothing we can do about it.
-    /// Return a boolean to indicate if we will be out of memory, determined by the check.
-    fn will_oom_on_acquire(&self, tls: VMThread, size: usize) -> bool {
+    /// Return a boolean to indicate if we will be out of memory, determined by the check.  If the
+    /// allocation does not allow GC, we do not report out of memory to the binding.
+    fn will_oom_on_acquire(
+        &self,
+        tls: VMThread,
+        size: usize,
+        alloc_options: AllocationOptions,
+    ) -> bool {
         let max_pages = self.get_gc_trigger().policy.get_max_heap_size_in_pages();
         let requested_pages = size >> LOG_BYTES_IN_PAGE;
         if requested_pages > max_pages {
+            if !alloc_options.on_fail.allow_gc() {
+                return true;
+            }
+            info!(
+                "Memory mapped by MMTk:\n{}",
+                crate::util::memory::format_mmap_records()
+            );
+            probe!(
+                mmtk,
+                out_of_memory,
+                crate::util::alloc::AllocationError::HeapOutOfMemory,
+                size
+            );
+            let context =
+                self.get_gc_trigger()
+                    .allocation_error_context(Some(self.get_name()), size, false);
             VM::VMCollection::out_of_memory(
                 tls,
                 crate::util::alloc::AllocationError::HeapOutOfMemory,
+                &context,
             );
             return true;
         }
         false
     }
 
-    fn acquire(&self, tls: VMThread, pages: usize) -> Address {
+    fn acquire(&self, tls: VMThread, pages: usize, alloc_options: AllocationOptions) -> Address {
         trace!("Space.acquire, tls={:?}", tls);
 
         debug_assert!(
-            !self.will_oom_on_acquire(tls, pages << LOG_BYTES_IN_PAGE),
+            !self.will_oom_on_acquire(tls, pages << LOG_BYTES_IN_PAGE, alloc_options),
             "The requested pages is larger than the max heap size. Is will_go_oom_on_acquire used before acquring memory?"
         );
 
@@ -93,6 +120,13 @@ pub trait Space<VM: VMBinding>: 'static + SFT + Sync + Downcast {
         // - If gc is disabled, we cannot attempt a GC.
         let should_poll =
             VM::VMActivePlan::is_mutator(tls) && VM::VMCollection::is_collection_enabled();
+        // - If gc is disabled by a GC-disabled scope, we only record whether we would attempt a GC.
+        let gc_disabled = should_poll && self.common().global_state.is_gc_disabled();
+        if gc_disabled {
+            self.get_gc_trigger()
+                .poll_while_gc_disabled(VMMutatorThread(tls), Some(self.as_space()));
+        }
+        let should_poll = should_poll && !gc_disabled;
         // Is a GC allowed here? If we should poll but are not allowed to poll, we will panic.
         // initialize_collection() has to be called so we know GC is initialized.
         let allow_gc = should_poll && self.common().global_state.is_initialized();
@@ -103,7 +137,20 @@ pub trait Space<VM: VMBinding>: 'static + SFT + Sync + Downcast {
         trace!("Pages reserved");
         trace!("Polling ..");
 
-        if should_poll && self.get_gc_trigger().poll(false, Some(self.as_space())) {
+        // The allocation must not trigger a GC.  Fail it if we would have triggered a GC.
+        if should_poll
+            && !alloc_options.on_fail.allow_gc()
+            && self.get_gc_trigger().is_gc_required(Some(self.as_space()))
+        {
+            debug!("Collection required, but the allocation does not allow GC");
+            pr.clear_request(pages_reserved);
+            return unsafe { Address::zero() };
+        }
+
+        if should_poll
+            && alloc_options.on_fail.allow_gc()
+            && self.get_gc_trigger().poll(false, Some(self.as_space()))
+        {
             debug!("Collection required");
             assert!(allow_gc, "GC is not allowed here: collection is not initialized (did you call initialize_collection()?).");
 
@@ -113,7 +160,8 @@ pub trait Space<VM: VMBinding>: 'static + SFT + Sync + Downcast {
                 .policy
                 .on_pending_allocation(pages_reserved);
 
-            VM::VMCollection::block_for_gc(VMMutatorThread(tls)); // We have checked that this is mutator
+            // We have checked that this is mutator
+            self.common().handshake.block_for_gc(VMMutatorThread(tls));
             unsafe { Address::zero() }
         } else {
             debug!("Collection not required");
@@ -181,11 +229,41 @@ pub trait Space<VM: VMBinding>: 'static + SFT + Sync + Downcast {
                         mmap();
                     }
 
+                    // Record the space for the chunks, after the side metadata is mapped.
+                    space_index::set(res.start, bytes, self.common().space_index);
+
+                    // The pages may have been protected when they were released.
+                    if *self.common().options.protect_free_blocks {
+                        if let Err(e) =
+                            memory::munprotect(res.start, bytes, self.common().mmap_strategy().prot)
+                        {
+                            panic!("Failed to unprotect pages at {}: {}", res.start, e);
+                        }
+                    }
+
+                    memory::mark_undefined(res.start, bytes);
+
                     // TODO: Concurrent zeroing
                     if self.common().zeroed {
                         memory::zero(res.start, bytes);
                     }
 
+                    // Let GC workers pre-touch the rest of the new chunk before mutators use it.
+                    if res.new_chunk && *self.common().options.pretouch == PretouchMode::Background
+                    {
+                        let chunk_start = conversions::chunk_align_down(res.start);
+                        let chunk_end = conversions::chunk_align_up(res.start + bytes);
+                        let may_be_protected =
+                            self.common().options.may_protect_pages(self.get_name());
+                        self.common().scheduler.work_buckets[WorkBucketStage::Unconstrained].add(
+                            PretouchMemory::new(
+                                chunk_start,
+                                chunk_end - chunk_start,
+                                may_be_protected,
+                            ),
+                        );
+                    }
+
                     // Some assertions
                     {
                         // --- Assert the start of the allocated region ---
@@ -218,6 +296,12 @@ pub trait Space<VM: VMBinding>: 'static + SFT + Sync + Downcast {
                 Err(_) => {
                     drop(lock); // drop the lock immediately
 
+                    // The allocation must not trigger a GC.  Fail it instead of forcing a GC.
+                    if !alloc_options.on_fail.allow_gc() {
+                        pr.clear_request(pages_reserved);
+                        return unsafe { Address::zero() };
+                    }
+
                     // We thought we had memory to allocate, but somehow failed the allocation. Will force a GC.
                     assert!(
                         allow_gc,
@@ -233,7 +317,8 @@ pub trait Space<VM: VMBinding>: 'static + SFT + Sync + Downcast {
                         .policy
                         .on_pending_allocation(pages_reserved);
 
-                    VM::VMCollection::block_for_gc(VMMutatorThread(tls)); // We asserted that this is mutator.
+                    // We asserted that this is mutator.
+                    self.common().handshake.block_for_gc(VMMutatorThread(tls));
                     unsafe { Address::zero() }
                 }
             }
@@ -249,9 +334,19 @@ pub trait Space<VM: VMBinding>: 'static + SFT + Sync + Downcast {
     }
 
     fn in_space(&self, object: ObjectReference) -> bool {
+        // Fast path: the chunk records the space that owns it.
+        let index = space_index::get(object.to_raw_address());
+        if index != space_index::UNKNOWN_SPACE_INDEX {
+            return index == self.space_index();
+        }
         self.address_in_space(object.to_raw_address())
     }
 
+    /// The index of the space.  See [`crate::util::heap::space_index`].
+    fn space_index(&self) -> u8 {
+        self.common().space_index
+    }
+
     /**
      * This is called after we get result from page resources.  The space may
      * tap into the hook to monitor heap growth.  The call is made from within the
@@ -359,6 +454,33 @@ pub trait Space<VM: VMBinding>: 'static + SFT + Sync + Downcast {
             .verify_metadata_context(std::any::type_name::<Self>(), &self.common().metadata)
     }
 
+    /// Describe the address ranges and the side metadata of the space.  See
+    /// [`crate::MMTK::dump_heap_layout`].
+    fn describe_layout(&self) -> SpaceLayout {
+        let common = self.common();
+        let ranges = if common.contiguous {
+            vec![common.start..common.start + common.extent]
+        } else {
+            let mut ranges = vec![];
+            let mut a = self
+                .get_page_resource()
+                .common()
+                .get_head_discontiguous_region();
+            while !a.is_zero() {
+                ranges.push(a..a + common.vm_map().get_contiguous_region_size(a));
+                a = common.vm_map().get_next_contiguous_region(a);
+            }
+            ranges
+        };
+        SpaceLayout::new(
+            common.name,
+            self.space_index(),
+            common.contiguous,
+            ranges,
+            &common.metadata,
+        )
+    }
+
     /// Enumerate objects in the current space.
     ///
     /// Implementers can use the `enumerator` to report
@@ -447,6 +569,9 @@ impl_downcast!(Space<VM> where VM: VMBinding);
 pub struct CommonSpace<VM: VMBinding> {
     pub name: &'static str,
     pub descriptor: SpaceDescriptor,
+    /// The index of the space, recorded for the chunks of the space.  See
+    /// [`crate::util::heap::space_index`].
+    pub(crate) space_index: u8,
     pub vmrequest: VMRequest,
 
     /// For a copying space that allows sft_trace_object(), this should be set before each GC so we know
@@ -465,6 +590,11 @@ pub struct CommonSpace<VM: VMBinding> {
 
     pub vm_map: &'static dyn VMMap,
     pub mmapper: &'static dyn Mmapper,
+    /// The scheduler, used for background work of the space such as pre-touching memory.
+    pub(crate) scheduler: Arc<GCWorkScheduler<VM>>,
+    /// The handshake coordinator.  A mutator acknowledges the handshake in progress before it
+    /// blocks for a GC in the allocation slow path.
+    pub(crate) handshake: Arc<Handshake<VM>>,
 
     pub(crate) metadata: SideMetadataContext,
 
@@ -503,6 +633,7 @@ pub struct PlanCreateSpaceArgs<'a, VM: VMBinding> {
     pub constraints: &'a PlanConstraints,
     pub gc_trigger: Arc<GCTrigger<VM>>,
     pub scheduler: Arc<GCWorkScheduler<VM>>,
+    pub handshake: Arc<Handshake<VM>>,
     pub options: Arc<Options>,
     pub global_state: Arc<GlobalState>,
 }
@@ -529,6 +660,7 @@ impl<VM: VMBinding> CommonSpace<VM> {
         let mut rtn = CommonSpace {
             name: args.plan_args.name,
             descriptor: SpaceDescriptor::UNINITIALIZED,
+            space_index: args.plan_args.heap.new_space_index(),
             vmrequest: args.plan_args.vmrequest,
             copy: None,
             immortal: args.immortal,
@@ -540,11 +672,14 @@ impl<VM: VMBinding> CommonSpace<VM> {
             extent: 0,
             vm_map: args.plan_args.vm_map,
             mmapper: args.plan_args.mmapper,
+            scheduler: args.plan_args.scheduler.clone(),
+            handshake: args.plan_args.handshake,
             needs_log_bit: args.plan_args.constraints.needs_log_bit,
             gc_trigger: args.plan_args.gc_trigger,
             metadata: SideMetadataContext {
                 global: args.plan_args.global_side_metadata_specs,
                 local: args.local_side_metadata_specs,
+                mmap_strategy: MmapStrategy::side_metadata(&args.plan_args.options),
             },
             acquire_lock: Mutex::new(()),
             global_state: args.plan_args.global_state,
@@ -558,6 +693,7 @@ impl<VM: VMBinding> CommonSpace<VM> {
             // FIXME
             rtn.descriptor = SpaceDescriptor::create_descriptor();
             // VM.memory.setHeapRange(index, HEAP_START, HEAP_END);
+            crate::util::heap_file::record_space(rtn.name, rtn.start, 0, &rtn.metadata);
             return rtn;
         }
 
@@ -617,6 +753,8 @@ impl<VM: VMBinding> CommonSpace<VM> {
             }
         }
 
+        crate::util::heap_file::record_space(rtn.name, rtn.start, rtn.extent, &rtn.metadata);
+
         // For contiguous space, we know its address range so we reserve metadata memory for its range.
         rtn.metadata
             .try_map_metadata_address_range(rtn.start, rtn.extent, rtn.name)
@@ -658,16 +796,21 @@ impl<VM: VMBinding> CommonSpace<VM> {
 
     pub fn mmap_strategy(&self) -> MmapStrategy {
         MmapStrategy {
-            huge_page: if *self.options.transparent_hugepages {
-                HugePageSupport::TransparentHugePages
-            } else {
-                HugePageSupport::No
-            },
-            prot: if self.permission_exec || cfg!(feature = "exec_permission_on_all_spaces") {
+            huge_page: self.options.huge_page_support(self.name),
+            prot: if self.permission_exec && *self.options.code_space_wx {
+                // The VM makes code executable with `ImmortalSpace::set_executable`.
+                MmapProtection::ReadWrite
+            } else if self.permission_exec || cfg!(feature = "exec_permission_on_all_spaces") {
                 MmapProtection::ReadWriteExec
             } else {
                 MmapProtection::ReadWrite
             },
+            numa: self
+                .options
+                .numa_space_policies
+                .get(self.name)
+                .unwrap_or(*self.options.numa_policy),
+            populate: *self.options.pretouch == PretouchMode::OnMap,
         }
     }
 }
@@ -1,14 +1,18 @@
 use crate::mmtk::SFT_MAP;
 use crate::plan::{ObjectQueue, VectorObjectQueue};
 use crate::policy::sft::GCWorkerMutRef;
+use crate::policy::sft::PolicyKind;
 use crate::policy::sft::SFT;
 use crate::policy::space::{CommonSpace, Space};
 use crate::util::address::Address;
+use crate::util::alloc::AllocationOptions;
 use crate::util::constants::BYTES_IN_PAGE;
 use crate::util::heap::externalpageresource::{ExternalPageResource, ExternalPages};
 use crate::util::heap::layout::vm_layout::BYTES_IN_CHUNK;
 use crate::util::heap::PageResource;
+use crate::util::heap_layout::SpaceLayout;
 use crate::util::metadata::mark_bit::MarkState;
+use crate::util::metadata::side_metadata::SideMetadataContext;
 #[cfg(feature = "set_unlog_bits_vm_space")]
 use crate::util::metadata::MetadataSpec;
 use crate::util::object_enum::ObjectEnumerator;
@@ -17,21 +21,34 @@ use crate::util::ObjectReference;
 use crate::vm::{ObjectModel, VMBinding};
 
 use std::sync::atomic::Ordering;
+use std::sync::Mutex;
 
 /// A special space for VM/Runtime managed memory. The implementation is similar to [`crate::policy::immortalspace::ImmortalSpace`],
 /// except that VM space does not allocate. Instead, the runtime can add regions that are externally managed
 /// and mmapped to the space, and allow objects in those regions to be traced in the same way
 /// as other MMTk objects allocated by MMTk.
+///
+/// The space can have multiple discontiguous regions at arbitrary addresses outside the heap range
+/// of MMTk, such as the segments of a boot image, or the data sections of shared libraries that
+/// contain objects. Regions must not overlap with each other.
 pub struct VMSpace<VM: VMBinding> {
     mark_state: MarkState,
     common: CommonSpace<VM>,
     pr: ExternalPageResource<VM>,
+    /// Serialize adding and removing regions, as regions may share chunks.
+    regions_lock: Mutex<()>,
 }
 
 impl<VM: VMBinding> SFT for VMSpace<VM> {
     fn name(&self) -> &'static str {
         self.common.name
     }
+    fn policy_kind(&self) -> PolicyKind {
+        PolicyKind::VMSpace
+    }
+    fn describe_object_metadata(&self, object: ObjectReference) -> Vec<String> {
+        vec![crate::util::metadata::describe::mark_bit::<VM>(object)]
+    }
     fn is_live(&self, _object: ObjectReference) -> bool {
         true
     }
@@ -63,8 +80,13 @@ impl<VM: VMBinding> SFT for VMSpace<VM> {
         if self.common.needs_log_bit {
             VM::VMObjectModel::GLOBAL_LOG_BIT_SPEC.mark_as_unlogged::<VM>(object, Ordering::SeqCst);
         }
+        // Regions added without VO bits may not have VO bits mapped.
         #[cfg(feature = "vo_bit")]
-        crate::util::metadata::vo_bit::set_vo_bit(object);
+        if crate::util::metadata::vo_bit::VO_BIT_SIDE_METADATA_SPEC
+            .is_mapped(object.to_raw_address())
+        {
+            crate::util::metadata::vo_bit::set_vo_bit(object);
+        }
     }
     #[cfg(feature = "is_mmtk_object")]
     fn is_mmtk_object(&self, addr: Address) -> Option<ObjectReference> {
@@ -136,7 +158,7 @@ impl<VM: VMBinding> Space<VM> for VMSpace<VM> {
         unreachable!()
     }
 
-    fn acquire(&self, _tls: VMThread, _pages: usize) -> Address {
+    fn acquire(&self, _tls: VMThread, _pages: usize, _alloc_options: AllocationOptions) -> Address {
         unreachable!()
     }
 
@@ -147,6 +169,22 @@ impl<VM: VMBinding> Space<VM> for VMSpace<VM> {
         SFT_MAP.get_checked(start).name() == self.name()
     }
 
+    fn describe_layout(&self) -> SpaceLayout {
+        let ranges = self
+            .pr
+            .get_external_pages()
+            .iter()
+            .map(|ep| ep.start..ep.end)
+            .collect();
+        SpaceLayout::new(
+            self.common.name,
+            self.space_index(),
+            false,
+            ranges,
+            &self.common.metadata,
+        )
+    }
+
     fn enumerate_objects(&self, enumerator: &mut dyn ObjectEnumerator) {
         let external_pages = self.pr.get_external_pages();
         for ep in external_pages.iter() {
@@ -180,6 +218,7 @@ impl<VM: VMBinding> VMSpace<VM> {
         let space = Self {
             mark_state: MarkState::new(),
             pr: ExternalPageResource::new(args.vm_map),
+            regions_lock: Mutex::new(()),
             common: CommonSpace::new(args.into_policy_args(
                 false,
                 true,
@@ -191,22 +230,95 @@ impl<VM: VMBinding> VMSpace<VM> {
 
         if !vm_space_start.is_zero() {
             // Do not set sft here, as the space may be moved. We do so for those regions in `initialize_sft`.
-            space.set_vm_region_inner(vm_space_start, vm_space_size, false);
+            space.set_vm_region_inner(vm_space_start, vm_space_size, false, true);
         }
 
         space
     }
 
     pub fn set_vm_region(&mut self, start: Address, size: usize) {
-        self.set_vm_region_inner(start, size, true);
+        self.set_vm_region_inner(start, size, true, true);
     }
 
-    fn set_vm_region_inner(&self, start: Address, size: usize, set_sft: bool) {
+    /// Add a region to the VM space.  If `map_vo_bit` is false, we do not map the VO bit side
+    /// metadata for the region (unless it is shared with other regions or spaces), and objects
+    /// in the region will not have VO bits.  Such a region can contain objects that are only
+    /// reached from precise references, and save the memory for VO bits.
+    ///
+    /// This can be called while mutators are running, but not during a GC.
+    pub fn add_vm_region(&self, start: Address, size: usize, map_vo_bit: bool) {
+        self.set_vm_region_inner(start, size, true, map_vo_bit);
+    }
+
+    /// Remove a region that was added to the VM space.  `start` and `size` must be the same as
+    /// those used when adding the region.
+    ///
+    /// We clear the side metadata for the region, and clear the SFT entries and mark the memory as
+    /// unmapped in the mmapper for the chunks that are not shared with other regions.  The side
+    /// metadata memory stays mapped, as it may be shared with other regions.
+    ///
+    /// This can be called while mutators are running, but not during a GC.
+    pub fn remove_vm_region(&self, start: Address, size: usize) {
+        let _guard = self.regions_lock.lock().unwrap();
+        let end = (start + size).align_up(BYTES_IN_PAGE);
+        let start = start.align_down(BYTES_IN_PAGE);
+        self.pr.remove_external_pages(ExternalPages { start, end });
+
+        // Clear the side metadata, as the VM may add another region at the same address later.
+        for spec in self
+            .common
+            .metadata
+            .global
+            .iter()
+            .chain(self.common.metadata.local.iter())
+        {
+            // VO bits may not be mapped for this region.
+            if spec.is_mapped(start) && spec.is_mapped(end - 1usize) {
+                spec.bzero_metadata(start, end - start);
+            }
+        }
+
+        let regions = self.pr.get_external_pages();
+        let mut chunk = start.align_down(BYTES_IN_CHUNK);
+        while chunk < end {
+            let chunk_end = chunk + BYTES_IN_CHUNK;
+            let shared = regions
+                .iter()
+                .any(|ep| ep.start < chunk_end && chunk < ep.end);
+            if !shared {
+                debug!("Remove chunk {} from VM space", chunk);
+                unsafe {
+                    SFT_MAP.clear(chunk);
+                }
+                self.common.mmapper.mark_as_unmapped(chunk, BYTES_IN_CHUNK);
+            }
+            chunk = chunk_end;
+        }
+    }
+
+    fn set_vm_region_inner(&self, start: Address, size: usize, set_sft: bool, map_vo_bit: bool) {
         assert!(size > 0);
         assert!(!start.is_zero());
+        let _guard = self.regions_lock.lock().unwrap();
 
         let end = start + size;
 
+        // Regions may share chunks, but must not overlap.
+        for ep in self.pr.get_external_pages().iter() {
+            assert!(
+                Address::range_intersection(
+                    &(start.align_down(BYTES_IN_PAGE)..end.align_up(BYTES_IN_PAGE)),
+                    &(ep.start..ep.end)
+                )
+                .is_empty(),
+                "The VM region ({}, {}) overlaps with an existing VM region ({}, {})",
+                start,
+                end,
+                ep.start,
+                ep.end
+            );
+        }
+
         let chunk_start = start.align_down(BYTES_IN_CHUNK);
         let chunk_end = end.align_up(BYTES_IN_CHUNK);
         let chunk_size = chunk_end - chunk_start;
@@ -227,10 +339,31 @@ impl<VM: VMBinding> VMSpace<VM> {
         // Mark as mapped in mmapper
         self.common.mmapper.mark_as_mapped(chunk_start, chunk_size);
         // Map side metadata
-        self.common
-            .metadata
+        if map_vo_bit {
+            self.common
+                .metadata
+                .try_map_metadata_space(chunk_start, chunk_size, self.get_name())
+                .unwrap();
+        } else {
+            #[cfg(feature = "vo_bit")]
+            let global = self
+                .common
+                .metadata
+                .global
+                .iter()
+                .copied()
+                .filter(|spec| *spec != crate::util::metadata::vo_bit::VO_BIT_SIDE_METADATA_SPEC)
+                .collect();
+            #[cfg(not(feature = "vo_bit"))]
+            let global = self.common.metadata.global.clone();
+            SideMetadataContext {
+                global,
+                local: self.common.metadata.local.clone(),
+                mmap_strategy: self.common.metadata.mmap_strategy,
+            }
             .try_map_metadata_space(chunk_start, chunk_size, self.get_name())
             .unwrap();
+        }
         // Insert to vm map: it would be good if we can make VM map aware of the region. However, the region may be outside what we can map in our VM map implementation.
         // self.common.vm_map.insert(chunk_start, chunk_size, self.common.descriptor);
         // Set SFT if we should
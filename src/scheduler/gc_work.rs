@@ -2,13 +2,15 @@ use super::work_bucket::WorkBucketStage;
 use super::*;
 use crate::global_state::GcStatus;
 use crate::plan::ObjectsClosure;
+use crate::plan::SlotCollector;
 use crate::plan::VectorObjectQueue;
 use crate::util::*;
 use crate::vm::slot::Slot;
 use crate::vm::*;
 use crate::*;
 use std::marker::PhantomData;
-use std::ops::{Deref, DerefMut};
+use std::ops::{Deref, DerefMut, Range};
+use std::sync::Arc;
 
 pub struct ScheduleCollection;
 
@@ -57,7 +59,13 @@ impl<C: GCWorkContext> GCWork<C::VM> for Prepare<C> {
         trace!("Prepare Global");
         // We assume this is the only running work packet that accesses plan at the point of execution
         let plan_mut: &mut C::PlanType = unsafe { &mut *(self.plan as *const _ as *mut _) };
+        mmtk.copy_accounting.on_gc_prepare(
+            plan_mut
+                .generational()
+                .and_then(|gen_plan| gen_plan.get_copying_nursery_bytes()),
+        );
         plan_mut.prepare(worker.tls);
+        mmtk.weak_slot_processor.prepare();
 
         if plan_mut.constraints().needs_prepare_mutator {
             let prepare_mutator_packets = <C::VM as VMBinding>::VMActivePlan::mutators()
@@ -106,6 +114,9 @@ impl<VM: VMBinding> GCWork<VM> for PrepareCollector {
     fn do_work(&mut self, worker: &mut GCWorker<VM>, mmtk: &'static MMTK<VM>) {
         trace!("Prepare Collector");
         worker.get_copy_context_mut().prepare();
+        if let Some(filter) = worker.slot_filter.as_mut() {
+            filter.clear();
+        }
         mmtk.get_plan().prepare_worker(worker);
     }
 }
@@ -190,9 +201,20 @@ impl<VM: VMBinding> GCWork<VM> for ReleaseMutator<VM> {
 pub struct ReleaseCollector;
 
 impl<VM: VMBinding> GCWork<VM> for ReleaseCollector {
-    fn do_work(&mut self, worker: &mut GCWorker<VM>, _mmtk: &'static MMTK<VM>) {
+    fn do_work(&mut self, worker: &mut GCWorker<VM>, mmtk: &'static MMTK<VM>) {
         trace!("Release Collector");
         worker.get_copy_context_mut().release();
+        let copied_bytes = worker.get_copy_context_mut().take_copied_bytes();
+        mmtk.copy_accounting.add_copied_bytes(&copied_bytes);
+        let relocations = worker.get_copy_context_mut().take_relocations();
+        mmtk.relocation_log.add(relocations);
+        if let (Some(filter), Some(counters)) = (
+            worker.slot_filter.as_mut(),
+            mmtk.slot_filter_counters.as_ref(),
+        ) {
+            counters.add(filter);
+            filter.clear();
+        }
     }
 }
 
@@ -212,15 +234,37 @@ impl<C: GCWorkContext> GCWork<C::VM> for StopMutators<C> {
     fn do_work(&mut self, worker: &mut GCWorker<C::VM>, mmtk: &'static MMTK<C::VM>) {
         trace!("stop_all_mutators start");
         mmtk.state.prepare_for_stack_scanning();
+        probe!(mmtk, safepoint_requested);
         <C::VM as VMBinding>::VMCollection::stop_all_mutators(worker.tls, |mutator| {
+            probe!(
+                mmtk,
+                safepoint_reached,
+                mutator.mutator_tls.0 .0.to_address().as_usize()
+            );
+            mmtk.handshake.execute_deferred_actions(mutator);
+            mmtk.deduplication.flush_candidates(mutator);
             // TODO: The stack scanning work won't start immediately, as the `Prepare` bucket is not opened yet (the bucket is opened in notify_mutators_paused).
             // Should we push to Unconstrained instead?
             mmtk.scheduler.work_buckets[WorkBucketStage::Prepare]
                 .add(ScanMutatorRoots::<C>(mutator));
         });
         trace!("stop_all_mutators end");
+        probe!(mmtk, mutators_stopped);
+        // Take the shadow heap snapshot now. No packet in the `Prepare` bucket (such as `Prepare`
+        // and root scanning) can run until the bucket is opened below.
+        #[cfg(feature = "shadow_heap")]
+        if !mmtk.get_plan().constraints().needs_forward_after_liveness {
+            crate::util::shadow_heap::take_snapshot(mmtk, worker.tls);
+        }
         mmtk.scheduler.notify_mutators_paused(mmtk);
         mmtk.scheduler.work_buckets[WorkBucketStage::Prepare].add(ScanVMSpecificRoots::<C>::new());
+        mmtk.scheduler.work_buckets[WorkBucketStage::Prepare].add(ScanStableRoots::<C>::new());
+        mmtk.scheduler.work_buckets[WorkBucketStage::Prepare]
+            .add(ScanPinnedRootBatches::<C>::new());
+        #[cfg(feature = "ro_space")]
+        if mmtk.get_plan().base().ro_space.is_sealed() {
+            mmtk.scheduler.work_buckets[WorkBucketStage::Prepare].add(ScanSealedRoots::<C>::new());
+        }
     }
 }
 
@@ -352,6 +396,46 @@ impl<E: ProcessEdgesWork> GCWork<E::VM> for VMProcessWeakRefs<E> {
     }
 }
 
+/// Delegate to the VM binding for unloading VM metadata whose liveness depends on objects.
+///
+/// This work packet is the sentinel of the `VMUnloading` bucket, and it reschedules itself if
+/// the VM binding requests to be called again after expanding the transitive closure.
+pub struct VMProcessUnloading<E: ProcessEdgesWork> {
+    phantom_data: PhantomData<E>,
+}
+
+impl<E: ProcessEdgesWork> VMProcessUnloading<E> {
+    pub fn new() -> Self {
+        Self {
+            phantom_data: PhantomData,
+        }
+    }
+}
+
+impl<E: ProcessEdgesWork> GCWork<E::VM> for VMProcessUnloading<E> {
+    fn do_work(&mut self, worker: &mut GCWorker<E::VM>, _mmtk: &'static MMTK<E::VM>) {
+        trace!("VMProcessUnloading");
+
+        let stage = WorkBucketStage::VMUnloading;
+
+        let need_to_repeat = {
+            let tracer_factory = ProcessEdgesWorkTracerContext::<E> {
+                stage,
+                phantom_data: PhantomData,
+            };
+            <E::VM as VMBinding>::VMScanning::process_unloading(worker, tracer_factory)
+        };
+
+        if need_to_repeat {
+            // Schedule Self as the new sentinel so we'll call `process_unloading` again after the
+            // current transitive closure.
+            let new_self = Box::new(Self::new());
+
+            worker.scheduler().work_buckets[stage].set_sentinel(new_self);
+        }
+    }
+}
+
 /// Delegate to the VM binding for forwarding weak references.
 ///
 /// Some VMs (e.g. v8) do not have a Java-like global weak reference storage, and the
@@ -410,6 +494,21 @@ impl<C: GCWorkContext> GCWork<C::VM> for ScanMutatorRoots<C> {
     fn do_work(&mut self, worker: &mut GCWorker<C::VM>, mmtk: &'static MMTK<C::VM>) {
         trace!("ScanMutatorRoots for mutator {:?}", self.0.get_tls());
         let mutators = <C::VM as VMBinding>::VMActivePlan::number_of_mutators();
+        // The frames below the stack watermark can only refer to mature objects, which are not
+        // moved in nursery GCs.  Allow the VM to skip them in nursery GCs.
+        let partial_scan_allowed = <C::VM as VMBinding>::VMScanning::supports_return_barrier()
+            && crate::plan::is_nursery_gc(mmtk.get_plan());
+        if self
+            .0
+            .stack_watermark
+            .prepare_for_scanning(partial_scan_allowed)
+        {
+            trace!(
+                "Partially scan mutator {:?} above {:?}",
+                self.0.get_tls(),
+                self.0.stack_watermark.get()
+            );
+        }
         let factory = ProcessEdgesWorkRootsWorkFactory::<
             C::VM,
             C::DefaultProcessEdges,
@@ -424,7 +523,8 @@ impl<C: GCWorkContext> GCWork<C::VM> for ScanMutatorRoots<C> {
 
         if mmtk.state.inform_stack_scanned(mutators) {
             <C::VM as VMBinding>::VMScanning::notify_initial_thread_scan_complete(
-                false, worker.tls,
+                partial_scan_allowed,
+                worker.tls,
             );
             mmtk.set_gc_status(GcStatus::GcProper);
         }
@@ -452,6 +552,87 @@ impl<C: GCWorkContext> GCWork<C::VM> for ScanVMSpecificRoots<C> {
     }
 }
 
+/// Report the stable root slots as roots.  The slots are cached.  See
+/// [`crate::vm::Scanning::scan_stable_roots`].
+#[derive(Default)]
+pub struct ScanStableRoots<C: GCWorkContext>(PhantomData<C>);
+
+impl<C: GCWorkContext> ScanStableRoots<C> {
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<C: GCWorkContext> GCWork<C::VM> for ScanStableRoots<C> {
+    fn do_work(&mut self, worker: &mut GCWorker<C::VM>, mmtk: &'static MMTK<C::VM>) {
+        trace!("ScanStableRoots");
+        let mut factory = ProcessEdgesWorkRootsWorkFactory::<
+            C::VM,
+            C::DefaultProcessEdges,
+            C::PinningProcessEdges,
+        >::new(mmtk);
+        mmtk.stable_roots.for_each_category(worker.tls, |slots| {
+            for chunk in slots.chunks(<C::DefaultProcessEdges as ProcessEdgesWork>::CAPACITY) {
+                factory.create_process_roots_work(chunk.to_vec());
+            }
+        });
+    }
+}
+
+/// Report the objects of the pinned root batches kept from previous GCs as transitively pinning
+/// roots.  See [`crate::vm::RootsWorkFactory::create_process_tpinning_roots_batch_work`].
+#[derive(Default)]
+pub struct ScanPinnedRootBatches<C: GCWorkContext>(PhantomData<C>);
+
+impl<C: GCWorkContext> ScanPinnedRootBatches<C> {
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<C: GCWorkContext> GCWork<C::VM> for ScanPinnedRootBatches<C> {
+    fn do_work(&mut self, _worker: &mut GCWorker<C::VM>, mmtk: &'static MMTK<C::VM>) {
+        trace!("ScanPinnedRootBatches");
+        let mut factory = ProcessEdgesWorkRootsWorkFactory::<
+            C::VM,
+            C::DefaultProcessEdges,
+            C::PinningProcessEdges,
+        >::new(mmtk);
+        mmtk.pinned_root_batches.for_each_kept_batch(|nodes| {
+            factory.create_process_tpinning_roots_work(nodes.to_vec());
+        });
+    }
+}
+
+/// Report the objects referenced from the sealed read-only space as roots.  See
+/// [`crate::policy::immortalspace::ImmortalSpace::seal`].
+#[cfg(feature = "ro_space")]
+#[derive(Default)]
+pub struct ScanSealedRoots<C: GCWorkContext>(PhantomData<C>);
+
+#[cfg(feature = "ro_space")]
+impl<C: GCWorkContext> ScanSealedRoots<C> {
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+#[cfg(feature = "ro_space")]
+impl<C: GCWorkContext> GCWork<C::VM> for ScanSealedRoots<C> {
+    fn do_work(&mut self, worker: &mut GCWorker<C::VM>, mmtk: &'static MMTK<C::VM>) {
+        trace!("ScanSealedRoots");
+        let factory = ProcessEdgesWorkRootsWorkFactory::<
+            C::VM,
+            C::DefaultProcessEdges,
+            C::PinningProcessEdges,
+        >::new(mmtk);
+        mmtk.get_plan()
+            .base()
+            .ro_space
+            .scan_sealed_roots(worker.tls, factory);
+    }
+}
+
 pub struct ProcessEdgesBase<VM: VMBinding> {
     pub slots: Vec<VM::VMSlot>,
     pub nodes: VectorObjectQueue,
@@ -621,7 +802,19 @@ pub trait ProcessEdgesWork:
     /// this method will simply return with no work packet created.
     fn flush(&mut self) {
         let nodes = self.pop_nodes();
-        if !nodes.is_empty() {
+        if nodes.is_empty() {
+            return;
+        }
+        let mmtk = self.mmtk();
+        let overflow = &mmtk.scheduler.tracing_overflow;
+        if !Self::SCAN_OBJECTS_IMMEDIATELY && overflow.should_spill(&mmtk.scheduler, self.bucket) {
+            // The scan work packet would be queued.  Spill the nodes instead, and create the
+            // packet with a new instance of this type when the nodes are refilled.
+            let bucket = self.bucket;
+            overflow.spill(bucket, nodes, move |nodes| {
+                Box::new(Self::new(vec![], false, mmtk, bucket).create_scan_work(nodes))
+            });
+        } else {
             self.start_or_dispatch_scan_work(self.create_scan_work(nodes));
         }
     }
@@ -706,6 +899,37 @@ impl<VM: VMBinding> ProcessEdgesWork for SFTProcessEdges<VM> {
     }
 }
 
+/// Process a range of the roots described by a [`RootsDescriptor`].  The slots are got from the
+/// descriptor when the packet is executed, so that the GC workers get the slots in parallel.
+pub(crate) struct ProcessRootsDescriptorChunk<E: ProcessEdgesWork> {
+    roots: Arc<dyn RootsDescriptor<<E::VM as VMBinding>::VMSlot>>,
+    range: Range<usize>,
+    phantom: PhantomData<E>,
+}
+
+impl<E: ProcessEdgesWork> ProcessRootsDescriptorChunk<E> {
+    fn new(
+        roots: Arc<dyn RootsDescriptor<<E::VM as VMBinding>::VMSlot>>,
+        range: Range<usize>,
+    ) -> Self {
+        Self {
+            roots,
+            range,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<E: ProcessEdgesWork> GCWork<E::VM> for ProcessRootsDescriptorChunk<E> {
+    fn do_work(&mut self, worker: &mut GCWorker<E::VM>, mmtk: &'static MMTK<E::VM>) {
+        let mut slots = Vec::with_capacity(self.range.len());
+        self.roots.slots(self.range.clone(), &mut slots);
+        probe!(mmtk, roots, RootsKind::NORMAL, slots.len());
+        let mut process_edges = E::new(slots, true, mmtk, WorkBucketStage::Closure);
+        process_edges.do_work(worker, mmtk);
+    }
+}
+
 /// An implementation of `RootsWorkFactory` that creates work packets based on `ProcessEdgesWork`
 /// for handling roots.  The `DPE` and the `PPE` type parameters correspond to the
 /// `DefaultProcessEdge` and the `PinningProcessEdges` type members of the [`GCWorkContext`] trait.
@@ -758,6 +982,25 @@ impl<VM: VMBinding, DPE: ProcessEdgesWork<VM = VM>, PPE: ProcessEdgesWork<VM = V
         );
     }
 
+    fn create_process_roots_descriptor_work(
+        &mut self,
+        roots: Box<dyn RootsDescriptor<VM::VMSlot>>,
+    ) {
+        let roots: Arc<dyn RootsDescriptor<VM::VMSlot>> = Arc::from(roots);
+        let num_slots = roots.num_slots();
+        let packets = (0..num_slots)
+            .step_by(DPE::CAPACITY)
+            .map(|start| {
+                let end = usize::min(start + DPE::CAPACITY, num_slots);
+                Box::new(ProcessRootsDescriptorChunk::<DPE>::new(
+                    roots.clone(),
+                    start..end,
+                )) as Box<dyn GCWork<VM>>
+            })
+            .collect();
+        self.mmtk.scheduler.work_buckets[WorkBucketStage::Closure].bulk_add(packets);
+    }
+
     fn create_process_pinning_roots_work(&mut self, nodes: Vec<ObjectReference>) {
         probe!(mmtk, roots, RootsKind::PINNING, nodes.len());
         // Will process roots within the PinningRootsTrace bucket
@@ -777,6 +1020,15 @@ impl<VM: VMBinding, DPE: ProcessEdgesWork<VM = VM>, PPE: ProcessEdgesWork<VM = V
             ProcessRootNode::<VM, PPE, PPE>::new(nodes, WorkBucketStage::TPinningClosure),
         );
     }
+
+    fn create_process_tpinning_roots_batch_work(
+        &mut self,
+        nodes: Vec<ObjectReference>,
+        gcs: usize,
+    ) {
+        self.mmtk.pinned_root_batches.add(&nodes, gcs);
+        self.create_process_tpinning_roots_work(nodes);
+    }
 }
 
 impl<VM: VMBinding, DPE: ProcessEdgesWork<VM = VM>, PPE: ProcessEdgesWork<VM = VM>>
@@ -815,6 +1067,11 @@ pub trait ScanObjectsWork<VM: VMBinding>: GCWork<VM> + Sized {
     /// Return the work bucket for this work packet and its derived work packets.
     fn get_bucket(&self) -> WorkBucketStage;
 
+    /// The maximum number of objects a packet scans in the hierarchical order (see
+    /// `Options::hierarchical_copying`), including the descendants of the objects in the packet.
+    /// The remaining objects are scanned in a new packet.
+    const HIERARCHICAL_SCAN_LIMIT: usize = 4096;
+
     /// The common code for ScanObjects and PlanScanObjects.
     fn do_work_common(
         &self,
@@ -822,6 +1079,11 @@ pub trait ScanObjectsWork<VM: VMBinding>: GCWork<VM> + Sized {
         worker: &mut GCWorker<<Self::E as ProcessEdgesWork>::VM>,
         mmtk: &'static MMTK<<Self::E as ProcessEdgesWork>::VM>,
     ) {
+        if *mmtk.get_options().hierarchical_copying {
+            self.do_work_hierarchical(buffer, worker, mmtk);
+            return;
+        }
+
         let tls = worker.tls;
 
         let objects_to_scan = buffer;
@@ -844,8 +1106,25 @@ pub trait ScanObjectsWork<VM: VMBinding>: GCWork<VM> + Sized {
                 }
             }
 
+            #[cfg(feature = "analysis")]
+            mmtk.analysis_manager.trace_hook(
+                &mut closure.worker.shared.analysis_buffer.borrow_mut(),
+                objects_to_scan,
+            );
+
             for object in objects_to_scan.iter().copied() {
-                if <VM as VMBinding>::VMScanning::support_slot_enqueuing(tls, object) {
+                if let Some(offsets) = <VM as VMBinding>::VMObjectModel::get_pointer_offsets(object)
+                {
+                    trace!("Scan object (offsets) {}", object);
+                    // The binding describes the slots of the object. Enqueue them without calling
+                    // back to the VM.
+                    let object_start =
+                        <VM as VMBinding>::VMObjectModel::ref_to_object_start(object);
+                    offsets.visit_slot_addresses(object_start, |address| {
+                        closure.visit_slot(<VM as VMBinding>::VMScanning::create_slot(address));
+                    });
+                    self.post_scan_object(object);
+                } else if <VM as VMBinding>::VMScanning::support_slot_enqueuing(tls, object) {
                     trace!("Scan object (slot) {}", object);
                     // If an object supports slot-enqueuing, we enqueue its slots.
                     <VM as VMBinding>::VMScanning::scan_object(tls, object, &mut closure);
@@ -866,6 +1145,107 @@ pub trait ScanObjectsWork<VM: VMBinding>: GCWork<VM> + Sized {
         let scan_and_trace = scan_later.len();
         probe!(mmtk, scan_objects, total_objects, scan_and_trace);
 
+        self.scan_and_trace_objects(&scan_later, worker);
+    }
+
+    /// Scan the objects in `buffer` and their descendants in a depth-first order, and trace the
+    /// slots of each object as soon as the object is scanned, instead of creating work packets for
+    /// the slots.  When an object is scanned, the objects its slots point to are traced (and copied
+    /// by a copying policy) together, and they are scanned next, so that their children are copied
+    /// right after them.  This places the objects close to their parents and siblings in the
+    /// to-space, at the cost of scanning the objects of a packet sequentially.
+    fn do_work_hierarchical(
+        &self,
+        buffer: &[ObjectReference],
+        worker: &mut GCWorker<<Self::E as ProcessEdgesWork>::VM>,
+        mmtk: &'static MMTK<<Self::E as ProcessEdgesWork>::VM>,
+    ) {
+        let tls = worker.tls;
+        let count_live_bytes = *mmtk.get_options().count_live_bytes_in_gc;
+        // Record the objects scanned in this packet, including those scanned later, so that we
+        // count their live bytes and report them to the analysis routines in one batch.
+        let record_objects = count_live_bytes || cfg!(feature = "analysis");
+        let mut recorded = vec![];
+
+        // Scan the objects in the packet in their original order.
+        let mut stack: Vec<ObjectReference> = buffer.iter().rev().copied().collect();
+        let mut scanned = 0;
+        let mut scan_later = vec![];
+        let mut collector = SlotCollector::new(mmtk);
+        let mut process_edges = None;
+        while scanned < Self::HIERARCHICAL_SCAN_LIMIT {
+            let Some(object) = stack.pop() else {
+                break;
+            };
+            if record_objects {
+                recorded.push(object);
+            }
+            if let Some(offsets) = <VM as VMBinding>::VMObjectModel::get_pointer_offsets(object) {
+                trace!("Scan object (offsets, hierarchical) {}", object);
+                let object_start = <VM as VMBinding>::VMObjectModel::ref_to_object_start(object);
+                offsets.visit_slot_addresses(object_start, |address| {
+                    collector.visit_slot(<VM as VMBinding>::VMScanning::create_slot(address));
+                });
+            } else if <VM as VMBinding>::VMScanning::support_slot_enqueuing(tls, object) {
+                trace!("Scan object (slot, hierarchical) {}", object);
+                <VM as VMBinding>::VMScanning::scan_object(tls, object, &mut collector);
+            } else {
+                scan_later.push(object);
+                continue;
+            }
+            self.post_scan_object(object);
+            scanned += 1;
+
+            // Trace the slots of the object now, and scan the objects they enqueue next.
+            let slots = std::mem::take(&mut collector.slots);
+            let edges = process_edges
+                .get_or_insert_with(|| Self::E::new(vec![], false, mmtk, self.get_bucket()));
+            edges.slots = slots;
+            edges.set_worker(worker);
+            edges.process_slots();
+            let children = edges.pop_nodes();
+            stack.extend(children.into_iter().rev());
+        }
+        drop(collector);
+
+        if crate::util::rust_util::unlikely(count_live_bytes) {
+            let mut live_bytes_stats = worker.shared.live_bytes_per_space.borrow_mut();
+            for object in recorded.iter().copied() {
+                crate::scheduler::worker::GCWorkerShared::<VM>::increase_live_bytes(
+                    &mut live_bytes_stats,
+                    object,
+                );
+            }
+        }
+
+        #[cfg(feature = "analysis")]
+        mmtk.analysis_manager
+            .trace_hook(&mut worker.shared.analysis_buffer.borrow_mut(), &recorded);
+
+        let total_objects = scanned + scan_later.len();
+        let scan_and_trace = scan_later.len();
+        probe!(mmtk, scan_objects, total_objects, scan_and_trace);
+
+        if !stack.is_empty() {
+            // Scan the rest in another packet, which may be stolen by other workers.
+            let edges = process_edges
+                .get_or_insert_with(|| Self::E::new(vec![], false, mmtk, self.get_bucket()));
+            let work_packet = edges.create_scan_work(stack);
+            worker.add_work(self.get_bucket(), work_packet);
+        }
+
+        self.scan_and_trace_objects(&scan_later, worker);
+    }
+
+    /// Scan the objects that do not support slot-enqueuing with
+    /// `Scanning::scan_object_and_trace_edges`, and trace their outgoing edges at the same time.
+    fn scan_and_trace_objects(
+        &self,
+        scan_later: &[ObjectReference],
+        worker: &mut GCWorker<<Self::E as ProcessEdgesWork>::VM>,
+    ) {
+        let tls = worker.tls;
+
         // If any object does not support slot-enqueuing, we process them now.
         if !scan_later.is_empty() {
             let object_tracer_context = ProcessEdgesWorkTracerContext::<Self::E> {
@@ -1196,3 +1576,29 @@ impl<VM: VMBinding> ProcessEdgesWork for UnsupportedProcessEdges<VM> {
         panic!("unsupported!")
     }
 }
+
+/// Pre-touch the memory newly mapped for a space in the background, so that mutators do not take
+/// page faults when they start using it.  See the option `pretouch`.
+pub(crate) struct PretouchMemory {
+    start: Address,
+    bytes: usize,
+    /// Some pages may be protected by the space.  See [`crate::util::memory::pretouch_memory`].
+    may_be_protected: bool,
+}
+
+impl PretouchMemory {
+    pub fn new(start: Address, bytes: usize, may_be_protected: bool) -> Self {
+        Self {
+            start,
+            bytes,
+            may_be_protected,
+        }
+    }
+}
+
+impl<VM: VMBinding> GCWork<VM> for PretouchMemory {
+    fn do_work(&mut self, _worker: &mut GCWorker<VM>, _mmtk: &'static MMTK<VM>) {
+        trace!("Pre-touch {} bytes at {}", self.bytes, self.start);
+        crate::util::memory::pretouch_memory(self.start, self.bytes, self.may_be_protected);
+    }
+}
@@ -0,0 +1,296 @@
+//! Handshakes with mutators.
+//!
+//! A handshake asks every mutator to execute an action at its next yieldpoint, without stopping
+//! all mutators at the same time.  Concurrent GC algorithms use handshakes to, for example, ask
+//! each mutator to flush its barrier buffers, or to report its stack bounds or stack watermark.
+//!
+//! A handshake works as follows:
+//!
+//! 1.  MMTk (or the binding) calls [`Handshake::perform`] with an action.  MMTk takes a snapshot
+//!     of the mutators with [`crate::vm::ActivePlan::mutators`].  Only those mutators take part in
+//!     the handshake.  Mutators bound after this point do not execute the action.
+//! 2.  MMTk calls [`crate::vm::Collection::request_handshake`].  The VM should arrange for every
+//!     mutator to call [`crate::memory_manager::handshake_yieldpoint`] at its next yieldpoint.
+//! 3.  At the yieldpoint, the mutator executes the action on its own `Mutator` and acknowledges
+//!     the handshake.  The VM may also call `handshake_yieldpoint` on behalf of a mutator that is
+//!     blocked (e.g. in native code) and cannot reach a yieldpoint, as long as the mutator does
+//!     not access its `Mutator` at the same time.
+//! 4.  `Handshake::perform` returns when all mutators have acknowledged the handshake.  A mutator
+//!     destroyed with [`crate::memory_manager::destroy_mutator`] before executing the action
+//!     counts as acknowledged.  A mutator that blocks for a GC counts as acknowledged, too.  It
+//!     does not execute the action in the allocation slow path, where it may be holding references
+//!     into its `Mutator`.  Instead, MMTk executes the action on its behalf while the mutators are
+//!     stopped for the GC.
+//!
+//! Only one handshake can be in progress at a time.  Concurrent calls of `Handshake::perform`
+//! are serialized.  MMTk may also start a handshake without waiting for it (e.g. to flush idle
+//! mutators from an allocation slow path, where the current thread is a mutator).  A mutator never
+//! waits for other mutators in a handshake.
+
+use crate::plan::Mutator;
+use crate::util::{VMMutatorThread, VMThread};
+use crate::vm::{ActivePlan, Collection, VMBinding};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+/// An action executed by each mutator in a handshake.
+pub type HandshakeAction<VM> = Arc<dyn Fn(&mut Mutator<VM>) + Send + Sync>;
+
+struct HandshakeState<VM: VMBinding> {
+    /// The action of the handshake in progress, or `None` if there is no handshake in progress.
+    action: Option<HandshakeAction<VM>>,
+    /// The mutators that have not acknowledged the handshake in progress, identified by their
+    /// addresses, and their threads.
+    pending: HashMap<usize, VMMutatorThread>,
+    /// The actions that mutators blocked for a GC have not executed, keyed by the addresses of the
+    /// mutators.  MMTk executes them when it stops the mutators.
+    deferred: HashMap<usize, Vec<HandshakeAction<VM>>>,
+    /// The number of handshakes completed.
+    completed: usize,
+}
+
+/// The coordinator of handshakes.  See the module-level documentation.
+pub struct Handshake<VM: VMBinding> {
+    /// True if a handshake is in progress.  Mutators check this at yieldpoints without locking.
+    requested: AtomicBool,
+    state: Mutex<HandshakeState<VM>>,
+    /// Notified when a handshake is completed.
+    completed: Condvar,
+    /// When MMTk last checked for idle mutators.  See [`Handshake::maybe_flush_idle_mutators`].
+    last_idle_check: Mutex<Option<Instant>>,
+    /// When MMTk last started a handshake to reclaim the blocks held by idle mutators.  See
+    /// [`Handshake::maybe_reclaim_idle_mutator_blocks`].
+    last_reclaim: Mutex<Option<Instant>>,
+}
+
+impl<VM: VMBinding> Handshake<VM> {
+    pub(crate) fn new() -> Self {
+        Self {
+            requested: AtomicBool::new(false),
+            state: Mutex::new(HandshakeState {
+                action: None,
+                pending: HashMap::new(),
+                deferred: HashMap::new(),
+                completed: 0,
+            }),
+            completed: Condvar::new(),
+            last_idle_check: Mutex::new(None),
+            last_reclaim: Mutex::new(None),
+        }
+    }
+
+    /// Return true if a handshake is in progress.
+    pub fn is_in_progress(&self) -> bool {
+        self.requested.load(Ordering::Acquire)
+    }
+
+    /// Ask every mutator to execute `action` at its next yieldpoint, and wait until all of them
+    /// have done so.  This must not be called by a mutator thread, or it may wait for itself.
+    ///
+    /// Arguments:
+    /// * `tls`: The current thread.
+    /// * `action`: The action to be executed by each mutator.
+    pub fn perform(&self, tls: VMThread, action: HandshakeAction<VM>) {
+        let mut state = self.state.lock().unwrap();
+        // Wait for the handshake in progress, if any.
+        while state.action.is_some() {
+            state = self.completed.wait(state).unwrap();
+        }
+        let Some(ticket) = self.start_locked(&mut state, action) else {
+            return;
+        };
+        drop(state);
+
+        VM::VMCollection::request_handshake(tls);
+
+        let mut state = self.state.lock().unwrap();
+        while state.completed < ticket {
+            state = self.completed.wait(state).unwrap();
+        }
+    }
+
+    /// Start a handshake without waiting for it.  Return false if another handshake is in
+    /// progress, in which case the handshake is not started.
+    ///
+    /// Arguments:
+    /// * `tls`: The current thread.
+    /// * `action`: The action to be executed by each mutator.
+    pub fn start(&self, tls: VMThread, action: HandshakeAction<VM>) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if state.action.is_some() {
+            return false;
+        }
+        if self.start_locked(&mut state, action).is_some() {
+            drop(state);
+            VM::VMCollection::request_handshake(tls);
+        }
+        true
+    }
+
+    /// Set up a handshake.  Return the number of completed handshakes when this handshake
+    /// completes, or `None` if there are no mutators to handshake with.
+    fn start_locked(
+        &self,
+        state: &mut HandshakeState<VM>,
+        action: HandshakeAction<VM>,
+    ) -> Option<usize> {
+        debug_assert!(state.action.is_none());
+        debug_assert!(state.pending.is_empty());
+        for mutator in VM::VMActivePlan::mutators() {
+            let id = Self::mutator_id(mutator);
+            if let Some(actions) = state.deferred.get_mut(&id) {
+                // The mutator is blocked for a GC.  Execute the action when the GC stops it.
+                actions.push(action.clone());
+            } else {
+                state.pending.insert(id, mutator.mutator_tls);
+            }
+        }
+        if state.pending.is_empty() {
+            return None;
+        }
+        debug!("Start a handshake with {} mutators", state.pending.len());
+        state.action = Some(action);
+        self.requested.store(true, Ordering::Release);
+        Some(state.completed + 1)
+    }
+
+    /// Start a handshake to flush the mutators that have not entered the allocation slow path
+    /// since the last check, if `timeout` has elapsed since the last check.  The flushed mutators
+    /// retire their thread-local allocation buffers so that the memory can be reused by other
+    /// mutators.  This does not wait for the handshake.
+    pub(crate) fn maybe_flush_idle_mutators(&self, tls: VMThread, timeout: Duration) {
+        let Ok(mut last_idle_check) = self.last_idle_check.try_lock() else {
+            // Another thread is checking.
+            return;
+        };
+        let now = Instant::now();
+        let Some(last) = *last_idle_check else {
+            // Start timing from the first allocation slow path.
+            *last_idle_check = Some(now);
+            return;
+        };
+        if now.duration_since(last) < timeout {
+            return;
+        }
+        if self.start(tls, Arc::new(Mutator::flush_if_idle)) {
+            *last_idle_check = Some(now);
+        }
+    }
+
+    /// Start a handshake to make the mutators that have not entered the allocation slow path since
+    /// the last check return the unused lines of their Immix blocks, unless such a handshake was
+    /// started less than `interval` ago.  This does not wait for the handshake, and the lines can
+    /// be used in later allocation slow paths.  The interval keeps mutators from starting a
+    /// handshake in every allocation slow path when the heap is nearly full.
+    pub(crate) fn maybe_reclaim_idle_mutator_blocks(&self, tls: VMThread, interval: Duration) {
+        let Ok(mut last_reclaim) = self.last_reclaim.try_lock() else {
+            // Another thread is starting the handshake.
+            return;
+        };
+        let now = Instant::now();
+        if last_reclaim.is_some_and(|last| now.duration_since(last) < interval) {
+            return;
+        }
+        if self.start(tls, Arc::new(Mutator::return_unused_lines_if_idle)) {
+            debug!("{:?}: Reclaim blocks held by idle mutators", tls);
+            *last_reclaim = Some(now);
+        }
+    }
+
+    /// Block the current mutator for a GC with [`crate::vm::Collection::block_for_gc`].  The
+    /// mutator acknowledges the handshake in progress first, if it has not done so, so that the
+    /// handshake does not wait for the mutator until the GC finishes.  This may be called in the
+    /// allocation slow path of the mutator, so the mutator does not execute the action here.  The
+    /// action is deferred until [`Handshake::execute_deferred_actions`] is called for the mutator
+    /// while it is stopped for the GC.
+    pub(crate) fn block_for_gc(&self, tls: VMMutatorThread) {
+        if self.is_in_progress() {
+            let mut state = self.state.lock().unwrap();
+            if let Some(action) = state.action.clone() {
+                let ids: Vec<usize> = state
+                    .pending
+                    .iter()
+                    .filter(|(_, mutator_tls)| **mutator_tls == tls)
+                    .map(|(id, _)| *id)
+                    .collect();
+                for id in ids {
+                    state.deferred.entry(id).or_default().push(action.clone());
+                    self.acknowledge_locked(&mut state, id);
+                }
+            }
+        }
+        VM::VMCollection::block_for_gc(tls);
+    }
+
+    /// Execute the actions deferred for `mutator` when it blocked for a GC.  This is called when
+    /// the mutator is stopped for the GC.
+    pub(crate) fn execute_deferred_actions(&self, mutator: &mut Mutator<VM>) {
+        let id = Self::mutator_id(mutator);
+        let Some(actions) = self.state.lock().unwrap().deferred.remove(&id) else {
+            return;
+        };
+        for action in actions {
+            action(mutator);
+        }
+    }
+
+    /// Execute the action of the handshake in progress for `mutator` if it has not done so.
+    /// Return true if the action is executed.
+    pub fn yieldpoint(&self, mutator: &mut Mutator<VM>) -> bool {
+        if !self.is_in_progress() {
+            return false;
+        }
+
+        let id = Self::mutator_id(mutator);
+        let action = {
+            let state = self.state.lock().unwrap();
+            match state.action {
+                Some(ref action) if state.pending.contains_key(&id) => action.clone(),
+                _ => return false,
+            }
+        };
+
+        // Execute the action without holding the lock, so that other mutators can execute the
+        // action at the same time.
+        action(mutator);
+
+        self.acknowledge(id);
+        true
+    }
+
+    /// Acknowledge the handshake in progress for a mutator that is being destroyed, if it has not
+    /// done so.  The mutator does not execute the action, or any deferred actions.
+    pub(crate) fn remove_mutator(&self, mutator: &mut Mutator<VM>) {
+        let id = Self::mutator_id(mutator);
+        let mut state = self.state.lock().unwrap();
+        state.deferred.remove(&id);
+        self.acknowledge_locked(&mut state, id);
+    }
+
+    /// Remove the mutator from the pending mutators of the handshake in progress, and complete the
+    /// handshake if it is the last one.
+    fn acknowledge(&self, id: usize) {
+        let mut state = self.state.lock().unwrap();
+        self.acknowledge_locked(&mut state, id);
+    }
+
+    fn acknowledge_locked(&self, state: &mut HandshakeState<VM>, id: usize) {
+        if state.pending.remove(&id).is_none() {
+            return;
+        }
+        if state.pending.is_empty() {
+            debug!("The handshake is completed");
+            state.action = None;
+            state.completed += 1;
+            self.requested.store(false, Ordering::Release);
+            // Wake up the threads waiting for this handshake, or waiting to perform handshakes.
+            self.completed.notify_all();
+        }
+    }
+
+    fn mutator_id(mutator: &mut Mutator<VM>) -> usize {
+        mutator as *mut Mutator<VM> as usize
+    }
+}
@@ -6,7 +6,14 @@ pub(crate) mod affinity;
 mod scheduler;
 pub(crate) use scheduler::GCWorkScheduler;
 
+mod handshake;
+pub use handshake::{Handshake, HandshakeAction};
+
+mod phase_times;
+pub use phase_times::GCPhaseTimes;
+
 mod stat;
+pub(crate) mod tracing_overflow;
 mod work_counter;
 
 mod work;
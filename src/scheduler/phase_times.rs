@@ -0,0 +1,168 @@
+//! The wall-clock time of the phases of the most recent GC.
+//!
+//! Unlike the work packet statistics (see the `stat` module), the phase times are always recorded,
+//! and they are cheap to record because the GC workers only read the clock when a work bucket is
+//! opened.
+
+use super::WorkBucketStage;
+use enum_map::EnumMap;
+use std::time::{Duration, Instant};
+
+/// A breakdown of the wall-clock time of a GC.  See [`crate::MMTK::last_gc_phase_times`].
+///
+/// Buckets are opened in the order of their stages, and a bucket is only opened after the
+/// previous buckets are drained.  So the time of a stage is the time from when its bucket is
+/// opened until the next bucket is opened.  Stages that are not used by the plan take no time.
+#[derive(Clone, Debug, Default)]
+pub struct GCPhaseTimes {
+    /// The time from when the GC workers started the GC until the mutators are resumed.
+    pub total: Duration,
+    /// The time from when all mutators are stopped until the mutators are resumed.  This excludes
+    /// the time for stopping mutators, which is recorded as the time of the `Unconstrained` stage.
+    pub stop_the_world: Duration,
+    /// The time of the `Prepare` stage, in which the plan, the spaces and the mutators are
+    /// prepared for the GC, and roots are scanned.
+    pub root_scanning: Duration,
+    /// The time of computing the transitive closure following strong references, including the
+    /// closures of pinning roots.
+    pub closure: Duration,
+    /// The time of processing weak references, finalizers and other VM-specific weak data
+    /// structures, including the time to expand the transitive closure from them.
+    pub weak_reference_processing: Duration,
+    /// The time of the `Release` and `Final` stages, and the time the plan takes to finish the GC
+    /// before the mutators are resumed.
+    pub release: Duration,
+    /// The time of each stage.  The time of the `Unconstrained` stage is the time from when the
+    /// GC workers started the GC until all mutators are stopped.
+    pub buckets: EnumMap<WorkBucketStage, Duration>,
+}
+
+/// Records the time of the phases of the current GC.  The scheduler calls it when a GC starts,
+/// when a bucket is opened, and when the GC ends.
+#[derive(Default)]
+pub(crate) struct PhaseTimer {
+    /// When did the current GC start?
+    gc_start: Option<Instant>,
+    /// When were all mutators stopped in the current GC?
+    stw_start: Option<Instant>,
+    /// The stage of the most recently opened bucket in the current GC, and when it was opened.
+    current_stage: Option<(WorkBucketStage, Instant)>,
+    /// The time of each stage of the current GC.
+    buckets: EnumMap<WorkBucketStage, Duration>,
+    /// The phase times of the last finished GC.
+    last_gc: Option<GCPhaseTimes>,
+}
+
+impl PhaseTimer {
+    /// Called when the GC workers start a GC.
+    pub(crate) fn on_gc_start(&mut self, now: Instant) {
+        debug_assert!(self.gc_start.is_none(), "GC already started?");
+        self.gc_start = Some(now);
+        self.stw_start = None;
+        self.buckets = EnumMap::default();
+        self.current_stage = Some((WorkBucketStage::Unconstrained, now));
+    }
+
+    /// Called when the bucket of `stage` is opened.  This ends the previous stage.
+    pub(crate) fn on_stage_opened(&mut self, stage: WorkBucketStage, now: Instant) {
+        if self.gc_start.is_none() {
+            // Not in a GC.
+            return;
+        }
+        self.end_current_stage(now);
+        if stage == WorkBucketStage::first_stw_stage() {
+            self.stw_start = Some(now);
+        }
+        self.current_stage = Some((stage, now));
+    }
+
+    /// Called right before the mutators are resumed.  This ends the last stage.
+    pub(crate) fn on_gc_end(&mut self, now: Instant) {
+        let Some(gc_start) = self.gc_start.take() else {
+            debug_assert!(false, "GC not started yet?");
+            return;
+        };
+        self.end_current_stage(now);
+
+        let buckets = std::mem::take(&mut self.buckets);
+        let sum = |stages: &[WorkBucketStage]| -> Duration {
+            stages.iter().map(|stage| buckets[*stage]).sum()
+        };
+        let stw_start = self.stw_start.take().unwrap_or(now);
+        self.last_gc = Some(GCPhaseTimes {
+            total: now - gc_start,
+            stop_the_world: now - stw_start,
+            root_scanning: buckets[WorkBucketStage::Prepare],
+            closure: sum(&[
+                WorkBucketStage::TPinningClosure,
+                WorkBucketStage::PinningRootsTrace,
+                WorkBucketStage::Closure,
+            ]),
+            weak_reference_processing: sum(&[
+                WorkBucketStage::SoftRefClosure,
+                WorkBucketStage::WeakRefClosure,
+                WorkBucketStage::FinalRefClosure,
+                WorkBucketStage::PhantomRefClosure,
+                WorkBucketStage::VMRefClosure,
+                WorkBucketStage::VMUnloading,
+            ]),
+            // The plan finishes the GC after the `Final` stage, so we count it as part of the
+            // `Final` stage.
+            release: sum(&[WorkBucketStage::Release, WorkBucketStage::Final]),
+            buckets,
+        });
+    }
+
+    /// Get the phase times of the last finished GC.
+    pub(crate) fn last_gc(&self) -> Option<GCPhaseTimes> {
+        self.last_gc.clone()
+    }
+
+    fn end_current_stage(&mut self, now: Instant) {
+        if let Some((stage, start)) = self.current_stage.take() {
+            self.buckets[stage] += now.saturating_duration_since(start);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_phase_times() {
+        let mut timer = PhaseTimer::default();
+        assert!(timer.last_gc().is_none());
+
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+        timer.on_gc_start(at(0));
+        timer.on_stage_opened(WorkBucketStage::Prepare, at(2));
+        timer.on_stage_opened(WorkBucketStage::Closure, at(5));
+        timer.on_stage_opened(WorkBucketStage::WeakRefClosure, at(15));
+        timer.on_stage_opened(WorkBucketStage::VMRefClosure, at(16));
+        timer.on_stage_opened(WorkBucketStage::Release, at(18));
+        timer.on_stage_opened(WorkBucketStage::Final, at(21));
+        timer.on_gc_end(at(22));
+
+        let times = timer.last_gc().unwrap();
+        assert_eq!(times.total, Duration::from_millis(22));
+        assert_eq!(times.stop_the_world, Duration::from_millis(20));
+        assert_eq!(
+            times.buckets[WorkBucketStage::Unconstrained],
+            Duration::from_millis(2)
+        );
+        assert_eq!(times.root_scanning, Duration::from_millis(3));
+        assert_eq!(times.closure, Duration::from_millis(10));
+        assert_eq!(times.weak_reference_processing, Duration::from_millis(3));
+        assert_eq!(times.release, Duration::from_millis(4));
+        assert_eq!(
+            times.buckets[WorkBucketStage::SoftRefClosure],
+            Duration::ZERO
+        );
+
+        // Opening buckets between GCs does not affect the last GC.
+        timer.on_stage_opened(WorkBucketStage::Prepare, at(30));
+        assert_eq!(timer.last_gc().unwrap().total, Duration::from_millis(22));
+    }
+}
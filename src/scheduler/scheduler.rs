@@ -1,16 +1,18 @@
 use self::worker::PollResult;
 
 use super::gc_work::ScheduleCollection;
+use super::phase_times::{GCPhaseTimes, PhaseTimer};
 use super::stat::SchedulerStat;
+use super::tracing_overflow::TracingOverflow;
 use super::work_bucket::*;
 use super::worker::{GCWorker, ThreadId, WorkerGroup};
 use super::worker_goals::{WorkerGoal, WorkerGoals};
 use super::worker_monitor::{LastParkedResult, WorkerMonitor};
 use super::*;
-use crate::global_state::GcStatus;
+use crate::global_state::{GcStatus, GlobalState};
 use crate::mmtk::MMTK;
 use crate::util::opaque_pointer::*;
-use crate::util::options::AffinityKind;
+use crate::util::options::{AffinityKind, TracingOrder};
 use crate::util::rust_util::array_from_fn;
 use crate::vm::Collection;
 use crate::vm::VMBinding;
@@ -18,7 +20,8 @@ use crate::Plan;
 use crossbeam::deque::Steal;
 use enum_map::{Enum, EnumMap};
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::Instant;
 
 pub struct GCWorkScheduler<VM: VMBinding> {
@@ -30,6 +33,12 @@ pub struct GCWorkScheduler<VM: VMBinding> {
     pub(crate) worker_monitor: Arc<WorkerMonitor>,
     /// How to assign the affinity of each GC thread. Specified by the user.
     affinity: AffinityKind,
+    /// The stage of the most recently opened work bucket, as returned by `WorkBucketStage::into_usize`.
+    current_stage: AtomicUsize,
+    /// Records the time of the phases of the current GC.
+    phase_timer: Mutex<PhaseTimer>,
+    /// Tracing work packets spilled because the queued packets exceed the budget.
+    pub(crate) tracing_overflow: TracingOverflow<VM>,
 }
 
 // FIXME: GCWorkScheduler should be naturally Sync, but we cannot remove this `impl` yet.
@@ -38,16 +47,21 @@ pub struct GCWorkScheduler<VM: VMBinding> {
 unsafe impl<VM: VMBinding> Sync for GCWorkScheduler<VM> {}
 
 impl<VM: VMBinding> GCWorkScheduler<VM> {
-    pub fn new(num_workers: usize, affinity: AffinityKind) -> Arc<Self> {
+    pub fn new(
+        num_workers: usize,
+        affinity: AffinityKind,
+        tracing_queue_budget: usize,
+        tracing_order: TracingOrder,
+    ) -> Arc<Self> {
         let worker_monitor: Arc<WorkerMonitor> = Arc::new(WorkerMonitor::new(num_workers));
-        let worker_group = WorkerGroup::new(num_workers);
+        let worker_group = WorkerGroup::new(num_workers, tracing_order);
 
         // Create work buckets for workers.
         // TODO: Replace `array_from_fn` with `std::array::from_fn` after bumping MSRV.
         let mut work_buckets = EnumMap::from_array(array_from_fn(|stage_num| {
             let stage = WorkBucketStage::from_usize(stage_num);
             let active = stage == WorkBucketStage::Unconstrained;
-            WorkBucket::new(active, worker_monitor.clone())
+            WorkBucket::new(stage, active, worker_monitor.clone())
         }));
 
         // Set the open condition of each bucket.
@@ -78,6 +92,9 @@ impl<VM: VMBinding> GCWorkScheduler<VM> {
             worker_group,
             worker_monitor,
             affinity,
+            current_stage: AtomicUsize::new(WorkBucketStage::Unconstrained.into_usize()),
+            phase_timer: Mutex::new(PhaseTimer::default()),
+            tracing_overflow: TracingOverflow::new(tracing_queue_budget),
         })
     }
 
@@ -101,6 +118,30 @@ impl<VM: VMBinding> GCWorkScheduler<VM> {
         self.worker_monitor.make_request(WorkerGoal::StopForFork);
     }
 
+    /// Return true if GC workers are asked to exit for forking.  Workers stop taking work packets
+    /// once this is set, even in the middle of a GC.
+    pub(crate) fn is_stopping_for_fork(&self) -> bool {
+        self.worker_monitor.is_stopping_for_fork()
+    }
+
+    /// Block the current thread until all GC workers have exited after
+    /// [`GCWorkScheduler::stop_gc_threads_for_forking`] is called.  The underlying native threads
+    /// may still be running for a short while after the workers have exited.
+    pub fn wait_for_gc_threads_to_exit(&self) {
+        self.worker_monitor.wait_for_all_workers_to_exit();
+    }
+
+    /// Return true if GC threads are spawn and have not been asked to stop.
+    pub(crate) fn are_gc_threads_running(&self) -> bool {
+        self.worker_group.is_spawned()
+    }
+
+    /// Release the `GCWorker` instances of the exited GC workers, including their local work
+    /// queues and copy contexts.  GC threads cannot be respawn after this.
+    pub(crate) fn discard_gc_workers(&self) {
+        self.worker_group.discard_surrendered_workers();
+    }
+
     /// Surrender the `GCWorker` struct of a GC worker when it exits.
     pub fn surrender_gc_worker(&self, worker: Box<GCWorker<VM>>) {
         let all_surrendered = self.worker_group.surrender_gc_worker(worker);
@@ -121,6 +162,33 @@ impl<VM: VMBinding> GCWorkScheduler<VM> {
         self.worker_group.respawn(tls)
     }
 
+    /// Get the stage of the most recently opened work bucket.  It is `Unconstrained` between GCs.
+    ///
+    /// Buckets are opened in the order of their stages, so this is the stage the current GC is
+    /// in.  Note that packets in earlier buckets (e.g. `Unconstrained`) may still be executed in
+    /// this stage.  Work packet statistics use the stage of the bucket a packet is polled from.
+    pub(crate) fn current_stage(&self) -> WorkBucketStage {
+        WorkBucketStage::from_usize(self.current_stage.load(Ordering::Relaxed))
+    }
+
+    /// Get the phase times of the most recently finished GC.
+    pub(crate) fn last_gc_phase_times(&self) -> Option<GCPhaseTimes> {
+        self.phase_timer.lock().unwrap().last_gc()
+    }
+
+    fn set_current_stage(&self, stage: WorkBucketStage) {
+        self.current_stage
+            .store(stage.into_usize(), Ordering::Relaxed);
+        // The `Unconstrained` stage is set at the end of a GC.  The last stage is ended by
+        // `PhaseTimer::on_gc_end` right before resuming mutators.
+        if stage != WorkBucketStage::Unconstrained {
+            self.phase_timer
+                .lock()
+                .unwrap()
+                .on_stage_opened(stage, Instant::now());
+        }
+    }
+
     /// Resolve the affinity of a thread.
     pub fn resolve_affinity(&self, thread: ThreadId) {
         self.affinity.resolve_affinity(thread);
@@ -132,6 +200,12 @@ impl<VM: VMBinding> GCWorkScheduler<VM> {
         self.worker_monitor.make_request(WorkerGoal::Gc);
     }
 
+    /// Request workers to do idle work.  Called by mutators via `memory_manager::notify_idle`.
+    pub(crate) fn request_idle_work(&self) {
+        debug!("A mutator is requesting workers to do idle work...");
+        self.worker_monitor.make_request(WorkerGoal::IdleWork);
+    }
+
     /// Add the `ScheduleCollection` packet.  Called by the last parked worker.
     fn add_schedule_collection_packet(&self) {
         // We are still holding the mutex `WorkerMonitor::sync`.  Do not notify now.
@@ -165,6 +239,36 @@ impl<VM: VMBinding> GCWorkScheduler<VM> {
                 .add(ScheduleSanityGC::<C::PlanType>::new(plan));
         }
 
+        // Shadow heap verification. `StopMutators` takes the snapshot before any object is moved
+        // (or any VO bit is cleared). Verify before the spaces release the forwarding states.
+        #[cfg(feature = "shadow_heap")]
+        if !plan.constraints().needs_forward_after_liveness {
+            use crate::util::shadow_heap::ShadowHeapVerify;
+            self.work_buckets[WorkBucketStage::Compact].add(ShadowHeapVerify::<VM>::default());
+        }
+
+        // Check the remembered set for generational plans. The work packet checks if the current
+        // GC is a nursery GC when executed.
+        #[cfg(all(feature = "extreme_assertions", feature = "vo_bit"))]
+        if plan.generational().is_some() {
+            use crate::plan::generational::remset_checker::CheckRemsetCompleteness;
+            self.work_buckets[WorkBucketStage::Prepare]
+                .add(CheckRemsetCompleteness::<VM>::default());
+        }
+
+        // Heap verification
+        #[cfg(feature = "heap_verifier")]
+        if *plan.options().verify_heap
+            || plan
+                .base()
+                .global_state
+                .heap_verification_requested
+                .swap(false, Ordering::SeqCst)
+        {
+            use crate::util::heap_verifier::VerifyHeap;
+            self.work_buckets[WorkBucketStage::Final].add(VerifyHeap::<VM>::default());
+        }
+
         // Reference processing
         if !*plan.base().options.no_reference_types {
             use crate::util::reference_processor::{
@@ -199,6 +303,67 @@ impl<VM: VMBinding> GCWorkScheduler<VM> {
             }
         }
 
+        // Off-heap objects registered by the binding
+        {
+            use crate::util::off_heap_objects::{PrepareOffHeapObjects, ReleaseOffHeapObjects};
+            self.work_buckets[WorkBucketStage::Prepare].add(PrepareOffHeapObjects::<VM>::default());
+            self.work_buckets[WorkBucketStage::Release].add(ReleaseOffHeapObjects::<VM>::default());
+        }
+
+        // Global handles.  Strong handles are roots, but they are traced at the start of the
+        // closure, as their objects may be moved.  Weak handles are updated after the binding
+        // processes its weak references.
+        {
+            use crate::util::handle_table::{ForwardHandles, ScanStrongHandles, UpdateHandles};
+            self.work_buckets[WorkBucketStage::Closure]
+                .add(ScanStrongHandles::<C::DefaultProcessEdges>::new());
+            self.work_buckets[WorkBucketStage::VMUnloading].add(UpdateHandles::<VM>::default());
+            if plan.constraints().needs_forward_after_liveness {
+                self.work_buckets[WorkBucketStage::VMRefForwarding]
+                    .add(ForwardHandles::<VM>::default());
+            }
+        }
+
+        // Deduplication, after the binding processes its weak references.
+        {
+            use crate::util::deduplication::{Deduplicate, ForwardCanonicalValues};
+            self.work_buckets[WorkBucketStage::VMUnloading].add(Deduplicate::<VM>::default());
+            if plan.constraints().needs_forward_after_liveness {
+                self.work_buckets[WorkBucketStage::VMRefForwarding]
+                    .add(ForwardCanonicalValues::<VM>::default());
+            }
+        }
+
+        // Object IDs, after the binding processes its weak references.
+        #[cfg(feature = "object_id")]
+        {
+            use crate::util::object_id::{ForwardObjectIds, UpdateObjectIds};
+            self.work_buckets[WorkBucketStage::VMUnloading].add(UpdateObjectIds::<VM>::default());
+            if plan.constraints().needs_forward_after_liveness {
+                self.work_buckets[WorkBucketStage::VMRefForwarding]
+                    .add(ForwardObjectIds::<VM>::default());
+            }
+        }
+
+        // User data of objects, at the same time as object IDs.
+        #[cfg(feature = "object_user_data")]
+        {
+            use crate::util::object_user_data::{ForwardObjectUserData, UpdateObjectUserData};
+            self.work_buckets[WorkBucketStage::VMUnloading]
+                .add(UpdateObjectUserData::<VM>::default());
+            if plan.constraints().needs_forward_after_liveness {
+                self.work_buckets[WorkBucketStage::VMRefForwarding]
+                    .add(ForwardObjectUserData::<VM>::default());
+            }
+        }
+
+        // Weak slots reported by `Scanning::scan_object`. They are processed together with
+        // Java-style weak references, but regardless of `Options::no_reference_types`.
+        {
+            use crate::util::weak_slot_processor::ProcessWeakSlots;
+            self.work_buckets[WorkBucketStage::WeakRefClosure].add(ProcessWeakSlots::<VM>::new());
+        }
+
         // We add the VM-specific weak ref processing work regardless of MMTK-side options,
         // including Options::no_finalizer and Options::no_reference_types.
         //
@@ -223,6 +388,11 @@ impl<VM: VMBinding> GCWorkScheduler<VM> {
         self.work_buckets[WorkBucketStage::VMRefClosure]
             .set_sentinel(Box::new(VMProcessWeakRefs::<C::DefaultProcessEdges>::new()));
 
+        // VM-specific metadata unloading, after all weak references are processed. It is also a
+        // sentinel so that the VM binding can expand the transitive closure and be called again.
+        self.work_buckets[WorkBucketStage::VMUnloading]
+            .set_sentinel(Box::new(VMProcessUnloading::<C::DefaultProcessEdges>::new()));
+
         if plan.constraints().needs_forward_after_liveness {
             // VM-specific weak ref forwarding
             self.work_buckets[WorkBucketStage::VMRefForwarding]
@@ -270,7 +440,11 @@ impl<VM: VMBinding> GCWorkScheduler<VM> {
             let bucket_opened = bucket.update(self);
             buckets_updated = buckets_updated || bucket_opened;
             if bucket_opened {
+                // Buckets are opened in the order of their stages, and a bucket can only be opened
+                // after all previous buckets are drained.  So opening a bucket closes the previous one.
+                probe!(mmtk, bucket_closed, WorkBucketStage::from_usize(i - 1));
                 probe!(mmtk, bucket_opened, id);
+                self.set_current_stage(id);
                 new_packets = new_packets || !bucket.is_drained();
                 if new_packets {
                     // Quit the loop. There are already new packets in the newly opened buckets.
@@ -334,11 +508,12 @@ impl<VM: VMBinding> GCWorkScheduler<VM> {
     }
 
     /// Get a schedulable work packet without retry.
-    fn poll_schedulable_work_once(&self, worker: &GCWorker<VM>) -> Steal<Box<dyn GCWork<VM>>> {
+    fn poll_schedulable_work_once(&self, worker: &GCWorker<VM>) -> Steal<StagedWork<VM>> {
         let mut should_retry = false;
-        // Try find a packet that can be processed only by this worker.
+        // Try find a packet that can be processed only by this worker.  Designated packets are
+        // not added to any bucket.  They belong to the stage that added them.
         if let Some(w) = worker.shared.designated_work.pop() {
-            return Steal::Success(w);
+            return Steal::Success((self.current_stage(), w));
         }
         // Try get a packet from a work bucket.
         for work_bucket in self.work_buckets.values() {
@@ -367,7 +542,7 @@ impl<VM: VMBinding> GCWorkScheduler<VM> {
     }
 
     /// Get a schedulable work packet.
-    fn poll_schedulable_work(&self, worker: &GCWorker<VM>) -> Option<Box<dyn GCWork<VM>>> {
+    fn poll_schedulable_work(&self, worker: &GCWorker<VM>) -> Option<StagedWork<VM>> {
         // Loop until we successfully get a packet.
         loop {
             match self.poll_schedulable_work_once(worker) {
@@ -388,17 +563,23 @@ impl<VM: VMBinding> GCWorkScheduler<VM> {
     /// Called by workers to get a schedulable work packet.
     /// Park the worker if there're no available packets.
     pub(crate) fn poll(&self, worker: &GCWorker<VM>) -> PollResult<VM> {
-        if let Some(work) = self.poll_schedulable_work(worker) {
-            return Ok(work);
+        if !self.is_stopping_for_fork() {
+            if let Some(work) = self.poll_schedulable_work(worker) {
+                return Ok(work);
+            }
         }
         self.poll_slow(worker)
     }
 
     fn poll_slow(&self, worker: &GCWorker<VM>) -> PollResult<VM> {
         loop {
-            // Retry polling
-            if let Some(work) = self.poll_schedulable_work(worker) {
-                return Ok(work);
+            // Retry polling, unless workers are stopping for forking.  In that case, we leave the
+            // remaining packets in the buckets, and the workers will execute them after they are
+            // respawned.
+            if !self.is_stopping_for_fork() {
+                if let Some(work) = self.poll_schedulable_work(worker) {
+                    return Ok(work);
+                }
             }
 
             let ordinal = worker.ordinal;
@@ -419,6 +600,13 @@ impl<VM: VMBinding> GCWorkScheduler<VM> {
             WorkerGoal::Gc => {
                 // We are in the progress of GC.
 
+                // A mutator wants to fork in the middle of GC.  All workers have stopped at packet
+                // boundaries.  Suspend the GC, and let all workers exit.
+                if goals.suspend_current_for_fork() {
+                    trace!("A mutator wanted to fork during GC.  Suspend the GC.");
+                    return LastParkedResult::WakeAll;
+                }
+
                 // In stop-the-world GC, mutators cannot request for GC while GC is in progress.
                 // When we support concurrent GC, we should remove this assertion.
                 assert!(
@@ -432,6 +620,14 @@ impl<VM: VMBinding> GCWorkScheduler<VM> {
                 // During GC, if all workers parked, all open buckets must have been drained.
                 self.assert_all_activated_buckets_are_empty();
 
+                // Pass the objects scanned by the workers to the analysis routines before the next
+                // buckets are opened, in which the objects may be moved.
+                #[cfg(feature = "analysis")]
+                for w in &self.worker_group.workers_shared {
+                    let mut buffer = w.analysis_buffer.borrow_mut();
+                    worker.mmtk.analysis_manager.flush_trace_buffer(&mut buffer);
+                }
+
                 // Find more work for workers to do.
                 let found_more_work = self.find_more_work_for_workers();
 
@@ -443,6 +639,11 @@ impl<VM: VMBinding> GCWorkScheduler<VM> {
 
                     // Clear the current goal
                     goals.on_current_goal_completed();
+                    if *worker.mmtk.options.concurrent_sweeping
+                        || *worker.mmtk.options.concurrent_defrag_preparation
+                    {
+                        goals.set_request(WorkerGoal::ConcurrentSweep);
+                    }
                     self.respond_to_requests(worker, goals)
                 }
             }
@@ -452,6 +653,24 @@ impl<VM: VMBinding> GCWorkScheduler<VM> {
                     worker.ordinal
                 )
             }
+            WorkerGoal::ConcurrentSweep | WorkerGoal::IdleWork => {
+                // Stop at packet boundaries for forking.  The remaining work is done after the
+                // workers are respawned.
+                if goals.suspend_current_for_fork() {
+                    trace!(
+                        "A mutator wanted to fork during {:?}.  Suspend it.",
+                        current_goal
+                    );
+                    return LastParkedResult::WakeAll;
+                }
+
+                // Concurrent sweeping and idle work packets do not generate packets in other
+                // buckets.
+                self.assert_all_activated_buckets_are_empty();
+                trace!("{:?} finished.", current_goal);
+                goals.on_current_goal_completed();
+                self.respond_to_requests(worker, goals)
+            }
         }
     }
 
@@ -469,17 +688,31 @@ impl<VM: VMBinding> GCWorkScheduler<VM> {
         };
 
         match goal {
+            WorkerGoal::ConcurrentSweep => {
+                trace!("A GC finished.  Sweep the heap while mutators run.");
+                // We are still holding the mutex `WorkerMonitor::sync`.  Do not notify now.
+                let scheduled = worker.mmtk.get_plan().schedule_concurrent_sweeping(self);
+                if scheduled {
+                    LastParkedResult::WakeAll
+                } else {
+                    trace!("Nothing to sweep.");
+                    goals.on_current_goal_completed();
+                    self.respond_to_requests(worker, goals)
+                }
+            }
             WorkerGoal::Gc => {
                 trace!("A mutator requested a GC to be scheduled.");
 
                 // We set the eBPF trace point here so that bpftrace scripts can start recording
                 // work packet events before the `ScheduleCollection` work packet starts.
-                probe!(mmtk, gc_start);
+                probe!(mmtk, gc_start, GcCause::of(&worker.mmtk.state));
 
                 {
+                    let now = Instant::now();
                     let mut gc_start_time = worker.mmtk.state.gc_start_time.borrow_mut();
                     assert!(gc_start_time.is_none(), "GC already started?");
-                    *gc_start_time = Some(Instant::now());
+                    *gc_start_time = Some(now);
+                    self.phase_timer.lock().unwrap().on_gc_start(now);
                 }
 
                 self.add_schedule_collection_packet();
@@ -489,6 +722,18 @@ impl<VM: VMBinding> GCWorkScheduler<VM> {
                 trace!("A mutator wanted to fork.");
                 LastParkedResult::WakeAll
             }
+            WorkerGoal::IdleWork => {
+                trace!("A mutator reported that mutators are idle.");
+                // We are still holding the mutex `WorkerMonitor::sync`.  Do not notify now.
+                let scheduled = worker.mmtk.get_plan().schedule_idle_work(self);
+                if scheduled {
+                    LastParkedResult::WakeAll
+                } else {
+                    trace!("No idle work to do.");
+                    goals.on_current_goal_completed();
+                    self.respond_to_requests(worker, goals)
+                }
+            }
         }
     }
 
@@ -499,6 +744,13 @@ impl<VM: VMBinding> GCWorkScheduler<VM> {
             return true;
         }
 
+        // Refill spilled tracing packets before scheduling sentinels or opening new buckets, so
+        // that the transitive closure is completed in the current stage.
+        if self.tracing_overflow.refill(self) {
+            trace!("Some spilled tracing packets are refilled.");
+            return true;
+        }
+
         // See if any bucket has a sentinel.
         if self.schedule_sentinels() {
             trace!("Some sentinels are scheduled.");
@@ -520,13 +772,31 @@ impl<VM: VMBinding> GCWorkScheduler<VM> {
         // All GC workers must have parked by now.
         debug_assert!(!self.worker_group.has_designated_work());
         debug_assert!(self.all_buckets_empty());
+        debug_assert!(self.tracing_overflow.is_empty());
 
         // Deactivate all work buckets to prepare for the next GC.
         self.deactivate_all();
         self.debug_assert_all_buckets_deactivated();
+        self.set_current_stage(WorkBucketStage::Unconstrained);
 
         let mmtk = worker.mmtk;
 
+        // All copy contexts have been released.  Compute the copy statistics before telling the
+        // GC trigger, so that the GC trigger can use them.
+        let copy_stats = mmtk.copy_accounting.on_gc_end();
+        debug!("Copy statistics: {:?}", copy_stats);
+
+        // Advance the GC epoch before the plan resets its state for the next GC.
+        let nursery = mmtk
+            .get_plan()
+            .generational()
+            .is_some_and(|plan| plan.is_current_gc_nursery());
+        let epoch = mmtk.state.advance_gc_epoch(nursery);
+        mmtk.relocation_log.on_gc_end(epoch);
+
+        let pinned_roots_stats = mmtk.pinned_root_batches.on_gc_end();
+        debug!("Pinned roots statistics: {:?}", pinned_roots_stats);
+
         // Tell GC trigger that GC ended - this happens before we resume mutators.
         mmtk.gc_trigger.policy.on_gc_end(mmtk);
 
@@ -536,6 +806,10 @@ impl<VM: VMBinding> GCWorkScheduler<VM> {
         plan_mut.end_of_gc(worker.tls);
         probe!(mmtk, plan_end_of_gc_end);
 
+        // Let analysis routines know that GC ended.
+        #[cfg(feature = "analysis")]
+        mmtk.analysis_manager.gc_end_hook(mmtk);
+
         // Compute the elapsed time of the GC.
         let start_time = {
             let mut gc_start_time = worker.mmtk.state.gc_start_time.borrow_mut();
@@ -550,8 +824,14 @@ impl<VM: VMBinding> GCWorkScheduler<VM> {
             elapsed.as_millis()
         );
 
-        // USDT tracepoint for the end of GC.
-        probe!(mmtk, gc_end);
+        // USDT tracepoints for the end of the last bucket and the end of GC.
+        probe!(mmtk, bucket_closed, WorkBucketStage::Final);
+        probe!(
+            mmtk,
+            gc_end,
+            GcCause::of(&mmtk.state),
+            mmtk.is_emergency_collection()
+        );
 
         if *mmtk.get_options().count_live_bytes_in_gc {
             for (space_name, &stats) in mmtk.state.live_bytes_in_last_gc.borrow().iter() {
@@ -573,6 +853,15 @@ impl<VM: VMBinding> GCWorkScheduler<VM> {
         // Reset the triggering information.
         mmtk.state.reset_collection_trigger();
 
+        {
+            let mut phase_timer = self.phase_timer.lock().unwrap();
+            phase_timer.on_gc_end(Instant::now());
+            if let Some(phase_times) = phase_timer.last_gc() {
+                let weak_stats = mmtk.weak_processing_accounting.on_gc_end(&phase_times);
+                debug!("Weak processing statistics: {:?}", weak_stats);
+            }
+        }
+
         // Set to NotInGC after everything, and right before resuming mutators.
         mmtk.set_gc_status(GcStatus::NotInGC);
         <VM as VMBinding>::VMCollection::resume_mutators(worker.tls);
@@ -585,6 +874,13 @@ impl<VM: VMBinding> GCWorkScheduler<VM> {
         }
     }
 
+    pub fn disable_stat(&self) {
+        for worker in &self.worker_group.workers_shared {
+            let worker_stat = worker.borrow_stat();
+            worker_stat.disable();
+        }
+    }
+
     pub fn statistics(&self) -> HashMap<String, String> {
         let mut summary = SchedulerStat::default();
         for worker in &self.worker_group.workers_shared {
@@ -606,6 +902,31 @@ impl<VM: VMBinding> GCWorkScheduler<VM> {
         // opening the first STW bucket.  In the future, we should redesign the opening condition
         // of work buckets to make the synchronization more robust,
         first_stw_bucket.activate();
+        probe!(mmtk, bucket_opened, WorkBucketStage::first_stw_stage());
+        self.set_current_stage(WorkBucketStage::first_stw_stage());
         self.worker_monitor.notify_work_available(true);
     }
 }
+
+/// The cause of a GC, reported by the `gc_start` and `gc_end` USDT tracepoints.
+#[repr(usize)]
+enum GcCause {
+    /// The GC trigger decided to collect, usually because an allocation found the heap full.
+    Heap = 0,
+    /// The binding requested a GC via `handle_user_collection_request`.
+    User = 1,
+    /// MMTk requested a GC internally.
+    Internal = 2,
+}
+
+impl GcCause {
+    fn of(state: &GlobalState) -> Self {
+        if state.is_user_triggered_collection() {
+            GcCause::User
+        } else if state.internal_triggered_collection.load(Ordering::Relaxed) {
+            GcCause::Internal
+        } else {
+            GcCause::Heap
+        }
+    }
+}
@@ -2,8 +2,10 @@
 use super::work_counter::{WorkCounter, WorkCounterBase, WorkDuration};
 #[cfg(feature = "perf_counter")]
 use crate::scheduler::work_counter::WorkPerfEvent;
+use crate::scheduler::WorkBucketStage;
 use crate::vm::VMBinding;
 use crate::MMTK;
+use enum_map::EnumMap;
 use std::any::TypeId;
 use std::collections::HashMap;
 use std::marker::PhantomData;
@@ -28,6 +30,11 @@ pub struct SchedulerStat {
     /// We assume different threads have the same set of work counters
     /// (in the same order).
     work_counters: HashMap<TypeId, Vec<Vec<Box<dyn WorkCounter>>>>,
+    /// Count the number of work packets executed in different stages
+    stage_counts: EnumMap<WorkBucketStage, usize>,
+    /// Collect work counters from work threads for each stage.
+    /// The layout of the two dimensional vectors is the same as `work_counters`.
+    stage_counters: EnumMap<WorkBucketStage, Vec<Vec<Box<dyn WorkCounter>>>>,
 }
 
 impl SchedulerStat {
@@ -116,6 +123,36 @@ impl SchedulerStat {
             stat.insert(pkt, format!("{:.3}", time / 1e6));
         }
 
+        // Work counts and counter readings of each stage
+        for (stage, count) in self.stage_counts.iter() {
+            if *count == 0 {
+                continue;
+            }
+            stat.insert(format!("stage.{:?}.count", stage), format!("{}", count));
+            for v in self.stage_counters[stage].iter() {
+                let fold = v
+                    .iter()
+                    .fold(Default::default(), |acc: WorkCounterBase, x| {
+                        acc.merge(x.get_base())
+                    });
+                let name = v.first().unwrap().name();
+                // Like work packets, times are printed in ms, and other counters are printed as is.
+                let scale = if name == "time" { 1e6 } else { 1.0 };
+                stat.insert(
+                    format!("stage.{:?}.{}.total", stage, name),
+                    format!("{:.3}", fold.total / scale),
+                );
+                stat.insert(
+                    format!("stage.{:?}.{}.min", stage, name),
+                    format!("{:.3}", fold.min / scale),
+                );
+                stat.insert(
+                    format!("stage.{:?}.{}.max", stage, name),
+                    format!("{:.3}", fold.max / scale),
+                );
+            }
+        }
+
         stat
     }
     /// Merge work counters from different worker threads
@@ -151,6 +188,19 @@ impl SchedulerStat {
                 v.push(c.clone());
             }
         }
+        // Merge work count and work counters for different stages
+        for (stage, count) in stat.stage_counts.iter() {
+            self.stage_counts[stage] += *count;
+        }
+        for (stage, counters) in stat.stage_counters.iter() {
+            let vs = &mut self.stage_counters[stage];
+            if vs.is_empty() {
+                *vs = vec![vec![]; counters.len()];
+            }
+            for (v, c) in vs.iter_mut().zip(counters.iter()) {
+                v.push(c.clone());
+            }
+        }
     }
 }
 
@@ -158,13 +208,17 @@ impl SchedulerStat {
 pub struct WorkStat {
     type_id: TypeId,
     type_name: &'static str,
+    stage: WorkBucketStage,
+    /// Whether the counters were started for the work packet. Statistics may be enabled or
+    /// disabled while a work packet is being executed.
+    measured: bool,
 }
 
 impl WorkStat {
     /// Stop all work counters for the work packet type of the just executed
     /// work packet
     pub fn end_of_work<VM: VMBinding>(&self, worker_stat: &mut WorkerLocalStat<VM>) {
-        if !worker_stat.is_enabled() {
+        if !self.measured {
             return;
         };
         // Insert type ID, name pair
@@ -180,6 +234,11 @@ impl WorkStat {
             .and_modify(|v| {
                 v.iter_mut().for_each(|c| c.stop());
             });
+        // Increment work count and stop counters for the stage
+        worker_stat.stage_counts[self.stage] += 1;
+        worker_stat.stage_counters[self.stage]
+            .iter_mut()
+            .for_each(|c| c.stop());
     }
 }
 
@@ -188,6 +247,9 @@ pub struct WorkerLocalStat<C> {
     work_id_name_map: HashMap<TypeId, &'static str>,
     work_counts: HashMap<TypeId, usize>,
     work_counters: HashMap<TypeId, Vec<Box<dyn WorkCounter>>>,
+    stage_counts: EnumMap<WorkBucketStage, usize>,
+    /// Work counters for each stage.  Empty if no work packet has been measured in the stage.
+    stage_counters: EnumMap<WorkBucketStage, Vec<Box<dyn WorkCounter>>>,
     enabled: AtomicBool,
     _phantom: PhantomData<C>,
 }
@@ -200,6 +262,8 @@ impl<C> Default for WorkerLocalStat<C> {
             work_id_name_map: Default::default(),
             work_counts: Default::default(),
             work_counters: Default::default(),
+            stage_counts: Default::default(),
+            stage_counters: Default::default(),
             enabled: AtomicBool::new(false),
             _phantom: Default::default(),
         }
@@ -213,24 +277,35 @@ impl<VM: VMBinding> WorkerLocalStat<VM> {
     pub fn enable(&self) {
         self.enabled.store(true, Ordering::SeqCst);
     }
+    pub fn disable(&self) {
+        self.enabled.store(false, Ordering::SeqCst);
+    }
     /// Measure the execution of a work packet by starting all counters for that
-    /// type
+    /// type and for the stage of the bucket the work packet is polled from
     pub fn measure_work(
         &mut self,
         work_id: TypeId,
         work_name: &'static str,
+        stage: WorkBucketStage,
         mmtk: &'static MMTK<VM>,
     ) -> WorkStat {
         let stat = WorkStat {
             type_id: work_id,
             type_name: work_name,
+            stage,
+            measured: self.is_enabled(),
         };
-        if self.is_enabled() {
+        if stat.measured {
             self.work_counters
                 .entry(work_id)
                 .or_insert_with(|| Self::counter_set(mmtk))
                 .iter_mut()
                 .for_each(|c| c.start());
+            let stage_counters = &mut self.stage_counters[stage];
+            if stage_counters.is_empty() {
+                *stage_counters = Self::counter_set(mmtk);
+            }
+            stage_counters.iter_mut().for_each(|c| c.start());
         }
         stat
     }
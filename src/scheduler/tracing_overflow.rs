@@ -0,0 +1,166 @@
+//! Reducing the memory of queued tracing work packets.
+//!
+//! During tracing, each work packet may create new work packets for the objects or slots it
+//! discovers.  For some object graphs (e.g. very wide graphs, or graphs that the workers traverse in
+//! a breadth-first order), the packets in the work buckets can grow unboundedly, and the process may
+//! run out of memory in the middle of a GC.  Each of those packets holds a buffer which reserves
+//! space for [`VectorQueue::CAPACITY`] entries, even if it only holds a few.
+//!
+//! When the packets queued in a bucket exceed the budget set by the option
+//! `tracing_queue_budget`, new tracing packets are spilled to [`TracingOverflow`] instead.  A
+//! spilled packet keeps its entries in a vector shrunk to fit, and the work packet is only created
+//! when it is refilled.  Spilled packets are refilled to their buckets, within the budget, when
+//! all the workers have parked and before any sentinel is scheduled or any new bucket is opened,
+//! so the transitive closure is still completed in the same stage.
+//!
+//! Spilling does not bound the memory used by tracing.  It only saves the unused capacity of the
+//! packet buffers and the work packets themselves.  The spilled entries are kept in memory, and
+//! their number is only bounded by the number of objects or slots discovered in the GC.
+
+use super::work_bucket::WorkBucketStage;
+use super::{GCWork, GCWorkScheduler};
+use crate::plan::VectorQueue;
+use crate::util::Address;
+use crate::vm::VMBinding;
+use std::sync::Mutex;
+
+/// The estimated memory of a queued tracing work packet, in bytes.
+const ESTIMATED_PACKET_BYTES: usize =
+    VectorQueue::<Address>::CAPACITY * std::mem::size_of::<Address>();
+
+/// A work packet spilled to [`TracingOverflow`].  The closure owns the entries of the packet, and
+/// creates the work packet when the packet is refilled.
+struct SpilledPacket<VM: VMBinding> {
+    bucket: WorkBucketStage,
+    create: Box<dyn FnOnce() -> Box<dyn GCWork<VM>> + Send>,
+}
+
+/// Tracing work packets spilled because the queued packets exceed the budget.  See the module
+/// documentation.
+pub(crate) struct TracingOverflow<VM: VMBinding> {
+    /// The budget for the queued packets of a bucket, in bytes.  0 means no limit.
+    budget: usize,
+    spilled: Mutex<Vec<SpilledPacket<VM>>>,
+}
+
+impl<VM: VMBinding> TracingOverflow<VM> {
+    pub fn new(budget: usize) -> Self {
+        Self {
+            budget,
+            spilled: Mutex::new(vec![]),
+        }
+    }
+
+    /// Return `true` if the packets queued in `bucket` exceed the budget, and new tracing packets
+    /// for `bucket` should be spilled.
+    pub fn should_spill(&self, scheduler: &GCWorkScheduler<VM>, bucket: WorkBucketStage) -> bool {
+        // Packets in the `Unconstrained` bucket are not refilled when the workers park.
+        self.budget != 0
+            && bucket != WorkBucketStage::Unconstrained
+            && scheduler.work_buckets[bucket].len() * ESTIMATED_PACKET_BYTES > self.budget
+    }
+
+    /// Spill a packet for `bucket` with `entries`.  `create` creates the work packet from the
+    /// entries when the packet is refilled.
+    pub fn spill<T: Send + 'static>(
+        &self,
+        bucket: WorkBucketStage,
+        mut entries: Vec<T>,
+        create: impl FnOnce(Vec<T>) -> Box<dyn GCWork<VM>> + Send + 'static,
+    ) {
+        entries.shrink_to_fit();
+        let packet = SpilledPacket {
+            bucket,
+            create: Box::new(move || create(entries)),
+        };
+        self.spilled.lock().unwrap().push(packet);
+    }
+
+    /// Return `true` if there is no spilled packet.
+    pub fn is_empty(&self) -> bool {
+        self.spilled.lock().unwrap().is_empty()
+    }
+
+    /// Move spilled packets back to their buckets, until the packets queued in a bucket reach half
+    /// of the budget, so that the workers have room to create new packets before spilling again.
+    /// This does not notify the workers.  Return `true` if any packet is refilled.
+    ///
+    /// This should only be called after all the workers are parked.
+    pub fn refill(&self, scheduler: &GCWorkScheduler<VM>) -> bool {
+        let mut spilled = self.spilled.lock().unwrap();
+        if spilled.is_empty() {
+            return false;
+        }
+        let limit = std::cmp::max(self.budget / 2 / ESTIMATED_PACKET_BYTES, 1);
+        let mut refilled = 0;
+        while let Some(packet) = spilled.pop() {
+            let bucket = &scheduler.work_buckets[packet.bucket];
+            debug_assert!(bucket.is_activated());
+            if bucket.len() >= limit {
+                spilled.push(packet);
+                break;
+            }
+            bucket.add_boxed_no_notify((packet.create)());
+            refilled += 1;
+        }
+        trace!(
+            "Refilled {} tracing packets, {} remain spilled",
+            refilled,
+            spilled.len()
+        );
+        refilled != 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheduler::GCWorker;
+    use crate::util::options::{AffinityKind, TracingOrder};
+    use crate::util::test_util::mock_vm::MockVM;
+    use crate::MMTK;
+
+    struct DummyWork;
+
+    impl GCWork<MockVM> for DummyWork {
+        fn do_work(&mut self, _worker: &mut GCWorker<MockVM>, _mmtk: &'static MMTK<MockVM>) {}
+    }
+
+    #[test]
+    fn spill_and_refill() {
+        let scheduler = GCWorkScheduler::<MockVM>::new(
+            1,
+            AffinityKind::OsDefault,
+            0,
+            TracingOrder::BreadthFirst,
+        );
+        let overflow = TracingOverflow::<MockVM>::new(4 * ESTIMATED_PACKET_BYTES);
+        let bucket = WorkBucketStage::Closure;
+        scheduler.work_buckets[bucket].activate();
+
+        for _ in 0..4 {
+            assert!(!overflow.should_spill(&scheduler, bucket));
+            scheduler.work_buckets[bucket].add_no_notify(DummyWork);
+        }
+        scheduler.work_buckets[bucket].add_no_notify(DummyWork);
+        assert!(overflow.should_spill(&scheduler, bucket));
+        // The `Unconstrained` bucket is never spilled.
+        assert!(!overflow.should_spill(&scheduler, WorkBucketStage::Unconstrained));
+
+        for i in 0..3 {
+            overflow.spill(bucket, vec![i; 100], |_| Box::new(DummyWork));
+        }
+        // The bucket is above half of the budget.
+        assert!(!overflow.refill(&scheduler));
+
+        // Drain the bucket.  Refill up to half of the budget.
+        while !scheduler.work_buckets[bucket].is_empty() {
+            let local = crossbeam::deque::Worker::new_lifo();
+            let _ = scheduler.work_buckets[bucket].poll(&local);
+            while local.pop().is_some() {}
+        }
+        assert!(overflow.refill(&scheduler));
+        assert_eq!(scheduler.work_buckets[bucket].len(), 2);
+        assert!(!overflow.is_empty());
+    }
+}
@@ -38,7 +38,12 @@ pub trait GCWork<VM: VMBinding>: 'static + Send {
         // Start collecting statistics
         let stat = {
             let mut worker_stat = worker.shared.borrow_stat_mut();
-            worker_stat.measure_work(TypeId::of::<Self>(), type_name::<Self>(), mmtk)
+            worker_stat.measure_work(
+                TypeId::of::<Self>(),
+                type_name::<Self>(),
+                worker.work_stage,
+                mmtk,
+            )
         };
 
         // Do the actual work
@@ -6,13 +6,20 @@ use enum_map::Enum;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 
+/// A work packet together with the stage of the bucket it is added to.  Work packet statistics
+/// are attributed to this stage when the packet is executed, even if the packet is executed from
+/// a local queue or stolen by another worker.
+pub type StagedWork<VM> = (WorkBucketStage, Box<dyn GCWork<VM>>);
+
 struct BucketQueue<VM: VMBinding> {
-    queue: Injector<Box<dyn GCWork<VM>>>,
+    stage: WorkBucketStage,
+    queue: Injector<StagedWork<VM>>,
 }
 
 impl<VM: VMBinding> BucketQueue<VM> {
-    fn new() -> Self {
+    fn new(stage: WorkBucketStage) -> Self {
         Self {
+            stage,
             queue: Injector::new(),
         }
     }
@@ -21,20 +28,21 @@ impl<VM: VMBinding> BucketQueue<VM> {
         self.queue.is_empty()
     }
 
-    fn steal_batch_and_pop(
-        &self,
-        dest: &Worker<Box<dyn GCWork<VM>>>,
-    ) -> Steal<Box<dyn GCWork<VM>>> {
+    fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    fn steal_batch_and_pop(&self, dest: &Worker<StagedWork<VM>>) -> Steal<StagedWork<VM>> {
         self.queue.steal_batch_and_pop(dest)
     }
 
     fn push(&self, w: Box<dyn GCWork<VM>>) {
-        self.queue.push(w);
+        self.queue.push((self.stage, w));
     }
 
     fn push_all(&self, ws: Vec<Box<dyn GCWork<VM>>>) {
         for w in ws {
-            self.queue.push(w);
+            self.push(w);
         }
     }
 }
@@ -62,10 +70,10 @@ pub struct WorkBucket<VM: VMBinding> {
 }
 
 impl<VM: VMBinding> WorkBucket<VM> {
-    pub(crate) fn new(active: bool, monitor: Arc<WorkerMonitor>) -> Self {
+    pub(crate) fn new(stage: WorkBucketStage, active: bool, monitor: Arc<WorkerMonitor>) -> Self {
         Self {
             active: AtomicBool::new(active),
-            queue: BucketQueue::new(),
+            queue: BucketQueue::new(stage),
             prioritized_queue: None,
             monitor,
             can_open: None,
@@ -110,6 +118,16 @@ impl<VM: VMBinding> WorkBucket<VM> {
                 .unwrap_or(true)
     }
 
+    /// Get the number of work packets in this bucket.
+    pub fn len(&self) -> usize {
+        self.queue.len()
+            + self
+                .prioritized_queue
+                .as_ref()
+                .map(|q| q.len())
+                .unwrap_or(0)
+    }
+
     pub fn is_drained(&self) -> bool {
         self.is_activated() && self.is_empty()
     }
@@ -173,7 +191,7 @@ impl<VM: VMBinding> WorkBucket<VM> {
     }
 
     /// Get a work packet from this bucket
-    pub fn poll(&self, worker: &Worker<Box<dyn GCWork<VM>>>) -> Steal<Box<dyn GCWork<VM>>> {
+    pub fn poll(&self, worker: &Worker<StagedWork<VM>>) -> Steal<StagedWork<VM>> {
         if !self.is_activated() || self.is_empty() {
             return Steal::Empty;
         }
@@ -268,6 +286,10 @@ pub enum WorkBucketStage {
     /// NOTE: This stage is intended to replace the Java-specific weak reference handling stages
     /// above.
     VMRefClosure,
+    /// Let the VM sweep VM metadata whose liveness depends on the liveness of objects, such as
+    /// classes, shapes and inline caches, after all weak references are processed.  Potentially
+    /// expand the transitive closure.
+    VMUnloading,
     /// Compute the forwarding addresses of objects (mark-compact-only).
     CalculateForwarding,
     /// Scan roots again to initiate another transitive closure to update roots and reference
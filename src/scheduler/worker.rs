@@ -2,9 +2,11 @@ use super::stat::WorkerLocalStat;
 use super::work_bucket::*;
 use super::*;
 use crate::mmtk::MMTK;
+use crate::plan::SlotFilter;
 use crate::util::copy::GCWorkerCopyContext;
 use crate::util::heap::layout::heap_parameters::MAX_SPACES;
 use crate::util::opaque_pointer::*;
+use crate::util::options::TracingOrder;
 use crate::util::ObjectReference;
 use crate::vm::{Collection, GCThreadContext, VMBinding};
 use atomic::Atomic;
@@ -44,17 +46,23 @@ pub struct GCWorkerShared<VM: VMBinding> {
     /// at the end of a GC, and reset this counter.
     /// The live bytes are stored in an array. The index is the index from the space descriptor.
     pub live_bytes_per_space: AtomicRefCell<[usize; MAX_SPACES]>,
+    /// Objects scanned by this worker that have not been passed to the analysis routines yet.
+    /// See [`crate::util::analysis::AnalysisManager::trace_hook`].
+    #[cfg(feature = "analysis")]
+    pub analysis_buffer: AtomicRefCell<Vec<ObjectReference>>,
     /// A queue of GCWork that can only be processed by the owned thread.
     pub designated_work: ArrayQueue<Box<dyn GCWork<VM>>>,
     /// Handle for stealing packets from the current worker
-    pub stealer: Option<Stealer<Box<dyn GCWork<VM>>>>,
+    pub stealer: Option<Stealer<StagedWork<VM>>>,
 }
 
 impl<VM: VMBinding> GCWorkerShared<VM> {
-    pub fn new(stealer: Option<Stealer<Box<dyn GCWork<VM>>>>) -> Self {
+    pub fn new(stealer: Option<Stealer<StagedWork<VM>>>) -> Self {
         Self {
             stat: Default::default(),
             live_bytes_per_space: AtomicRefCell::new([0; MAX_SPACES]),
+            #[cfg(feature = "analysis")]
+            analysis_buffer: AtomicRefCell::new(vec![]),
             designated_work: ArrayQueue::new(16),
             stealer,
         }
@@ -98,7 +106,12 @@ pub struct GCWorker<VM: VMBinding> {
     /// Reference to the shared part of the GC worker.  It is used for synchronization.
     pub shared: Arc<GCWorkerShared<VM>>,
     /// Local work packet queue.
-    pub local_work_buffer: deque::Worker<Box<dyn GCWork<VM>>>,
+    pub local_work_buffer: deque::Worker<StagedWork<VM>>,
+    /// The filter of duplicate slots, if the option `deduplicate_slots` is in effect.
+    pub(crate) slot_filter: Option<SlotFilter<VM::VMSlot>>,
+    /// The stage of the bucket the work packet being executed was polled from.
+    #[cfg(feature = "work_packet_stats")]
+    pub(crate) work_stage: WorkBucketStage,
 }
 
 unsafe impl<VM: VMBinding> Sync for GCWorkerShared<VM> {}
@@ -124,10 +137,10 @@ impl<VM: VMBinding> GCWorkerShared<VM> {
 pub(crate) struct WorkerShouldExit;
 
 /// The result type of `GCWorker::pool`.
-/// Too many functions return `Option<Box<dyn GCWork<VM>>>`.  In most cases, when `None` is
+/// Too many functions return `Option<StagedWork<VM>>`.  In most cases, when `None` is
 /// returned, the caller should try getting work packets from another place.  To avoid confusion,
 /// we use `Err(WorkerShouldExit)` to clearly indicate that the worker should exit immediately.
-pub(crate) type PollResult<VM> = Result<Box<dyn GCWork<VM>>, WorkerShouldExit>;
+pub(crate) type PollResult<VM> = Result<StagedWork<VM>, WorkerShouldExit>;
 
 impl<VM: VMBinding> GCWorker<VM> {
     pub(crate) fn new(
@@ -135,7 +148,7 @@ impl<VM: VMBinding> GCWorker<VM> {
         ordinal: ThreadId,
         scheduler: Arc<GCWorkScheduler<VM>>,
         shared: Arc<GCWorkerShared<VM>>,
-        local_work_buffer: deque::Worker<Box<dyn GCWork<VM>>>,
+        local_work_buffer: deque::Worker<StagedWork<VM>>,
     ) -> Self {
         Self {
             tls: VMWorkerThread(VMThread::UNINITIALIZED),
@@ -146,6 +159,12 @@ impl<VM: VMBinding> GCWorker<VM> {
             mmtk,
             shared,
             local_work_buffer,
+            slot_filter: mmtk
+                .slot_filter_counters
+                .as_ref()
+                .map(|_| SlotFilter::new()),
+            #[cfg(feature = "work_packet_stats")]
+            work_stage: WorkBucketStage::Unconstrained,
         }
     }
 
@@ -161,7 +180,7 @@ impl<VM: VMBinding> GCWorker<VM> {
             self.scheduler.work_buckets[bucket].add_prioritized(Box::new(work));
             return;
         }
-        self.local_work_buffer.push(Box::new(work));
+        self.local_work_buffer.push((bucket, Box::new(work)));
     }
 
     /// Add a work packet to the work queue.
@@ -174,7 +193,7 @@ impl<VM: VMBinding> GCWorker<VM> {
             self.scheduler.work_buckets[bucket].add(work);
             return;
         }
-        self.local_work_buffer.push(Box::new(work));
+        self.local_work_buffer.push((bucket, Box::new(work)));
     }
 
     /// Get the scheduler. There is only one scheduler per MMTk instance.
@@ -194,12 +213,16 @@ impl<VM: VMBinding> GCWorker<VM> {
     /// 3. Poll from activated global work-buckets
     /// 4. Steal from other workers
     fn poll(&mut self) -> PollResult<VM> {
-        if let Some(work) = self.shared.designated_work.pop() {
-            return Ok(work);
-        }
+        // If the workers are stopping for forking, leave the packets in the queues.  They will be
+        // executed after the workers are respawned.
+        if !self.scheduler().is_stopping_for_fork() {
+            if let Some(work) = self.shared.designated_work.pop() {
+                return Ok((self.scheduler().current_stage(), work));
+            }
 
-        if let Some(work) = self.local_work_buffer.pop() {
-            return Ok(work);
+            if let Some(work) = self.local_work_buffer.pop() {
+                return Ok(work);
+            }
         }
 
         self.scheduler().poll(self)
@@ -211,7 +234,8 @@ impl<VM: VMBinding> GCWorker<VM> {
     ///
     /// Each worker will keep polling and executing work packets in a loop.  It runs until the
     /// worker is requested to exit.  Currently a worker may exit after
-    /// [`crate::mmtk::MMTK::prepare_to_fork`] is called.
+    /// [`crate::mmtk::MMTK::prepare_to_fork`] is called, even in the middle of a GC.  In that case,
+    /// the worker resumes the GC when it is respawned.
     ///
     /// Arguments:
     /// * `tls`: The VM-specific thread-local storage for this GC worker thread.
@@ -226,7 +250,12 @@ impl<VM: VMBinding> GCWorker<VM> {
         WORKER_ORDINAL.with(|x| x.store(self.ordinal, Ordering::SeqCst));
         self.scheduler.resolve_affinity(self.ordinal);
         self.tls = tls;
-        self.copy = crate::plan::create_gc_worker_context(tls, mmtk);
+        // If the worker stopped for forking in the middle of a GC, keep the copy context, which has
+        // been prepared for the current GC and may hold allocation buffers that contain copied
+        // objects.
+        if !mmtk.gc_in_progress() {
+            self.copy = crate::plan::create_gc_worker_context(tls, mmtk);
+        }
         loop {
             // Instead of having work_start and work_end tracepoints, we have
             // one tracepoint before polling for more work and one tracepoint
@@ -237,10 +266,15 @@ impl<VM: VMBinding> GCWorker<VM> {
             // If we have work_start and work_end, we cannot measure the first
             // poll.
             probe!(mmtk, work_poll);
-            let Ok(mut work) = self.poll() else {
+            #[allow(unused_variables)]
+            let Ok((stage, mut work)) = self.poll() else {
                 // The worker is asked to exit.  Break from the loop.
                 break;
             };
+            #[cfg(feature = "work_packet_stats")]
+            {
+                self.work_stage = stage;
+            }
             // probe! expands to an empty block on unsupported platforms
             #[allow(unused_variables)]
             let typename = work.get_type_name();
@@ -271,7 +305,7 @@ enum WorkerCreationState<VM: VMBinding> {
     /// been spawn.
     Initial {
         /// The local work queues for to-be-created workers.
-        local_work_queues: Vec<deque::Worker<Box<dyn GCWork<VM>>>>,
+        local_work_queues: Vec<deque::Worker<StagedWork<VM>>>,
     },
     /// All worker threads are spawn and running.  `GCWorker` structs have been transferred to
     /// worker threads.
@@ -287,6 +321,9 @@ enum WorkerCreationState<VM: VMBinding> {
         #[allow(clippy::vec_box)]
         workers: Vec<Box<GCWorker<VM>>>,
     },
+    /// Worker threads have exited, and their `GCWorker` structs have been dropped because MMTk is
+    /// shut down.  GC workers cannot be respawn.
+    ShutDown,
 }
 
 /// A worker group to manage all the GC workers.
@@ -303,10 +340,13 @@ pub(crate) struct WorkerGroup<VM: VMBinding> {
 unsafe impl<VM: VMBinding> Sync for WorkerGroup<VM> {}
 
 impl<VM: VMBinding> WorkerGroup<VM> {
-    /// Create a WorkerGroup
-    pub fn new(num_workers: usize) -> Arc<Self> {
+    /// Create a WorkerGroup.  `tracing_order` decides the order of the local work queues.
+    pub fn new(num_workers: usize, tracing_order: TracingOrder) -> Arc<Self> {
         let local_work_queues = (0..num_workers)
-            .map(|_| deque::Worker::new_fifo())
+            .map(|_| match tracing_order {
+                TracingOrder::BreadthFirst => deque::Worker::new_fifo(),
+                TracingOrder::DepthFirst => deque::Worker::new_lifo(),
+            })
             .collect::<Vec<_>>();
 
         let workers_shared = (0..num_workers)
@@ -350,11 +390,37 @@ impl<VM: VMBinding> WorkerGroup<VM> {
         *state = Some(WorkerCreationState::Spawned)
     }
 
+    /// Return true if worker threads are spawn and have not been asked to stop.
+    pub fn is_spawned(&self) -> bool {
+        matches!(
+            *self.state.lock().unwrap(),
+            Some(WorkerCreationState::Spawned)
+        )
+    }
+
+    /// Drop the `GCWorker` structs surrendered by the exited worker threads.  Worker threads
+    /// cannot be respawn after this.
+    pub fn discard_surrendered_workers(&self) {
+        let mut state = self.state.lock().unwrap();
+
+        let WorkerCreationState::Surrendered { workers } = state.take().unwrap() else {
+            panic!("GC workers have not been stopped.");
+        };
+        assert_eq!(
+            workers.len(),
+            self.worker_count(),
+            "Some GC workers have not exited, yet."
+        );
+        drop(workers);
+
+        *state = Some(WorkerCreationState::ShutDown)
+    }
+
     /// Create `GCWorker` instances.
     #[allow(clippy::vec_box)] // See `WorkerCreationState::Surrendered`.
     fn create_workers(
         &self,
-        local_work_queues: Vec<deque::Worker<Box<dyn GCWork<VM>>>>,
+        local_work_queues: Vec<deque::Worker<StagedWork<VM>>>,
         mmtk: &'static MMTK<VM>,
     ) -> Vec<Box<GCWorker<VM>>> {
         debug!("Creating GCWorker instances...");
@@ -8,7 +8,12 @@
 //! -   When in the progress of GC, the last parker will try to open buckets or announce the GC
 //!     has finished.
 //! -   When stopping for fork, every waken worker should save its thread state (giving in the
-//!     `GCWorker` struct) and exit.
+//!     `GCWorker` struct) and exit.  If a GC is in progress, the GC is suspended, and resumed
+//!     after the workers are respawned.
+//! -   When doing idle work, the last parker will announce the idle work has finished.  A GC
+//!     requested in the meantime starts after that, so idle work never overlaps with GC.
+//! -   When sweeping concurrently after a GC, the same applies.  The sweeping always finishes
+//!     before the next GC starts.
 //!
 //! The struct `WorkerGoals` keeps the set of goals requested by mutators, but GC workers will only
 //! respond to one request at a time, and will favor higher-priority goals.
@@ -22,6 +27,9 @@ pub(crate) struct WorkerGoals {
     current: Option<WorkerGoal>,
     /// Requests received from mutators.  `requests[goal]` is true if the `goal` is requested.
     requests: EnumMap<WorkerGoal, bool>,
+    /// The goal that workers were working towards when they stopped for forking.  It becomes the
+    /// current goal again when all workers have exited.
+    suspended: Option<WorkerGoal>,
 }
 
 /// A goal, i.e. something that workers should work together to achieve.
@@ -29,10 +37,16 @@ pub(crate) struct WorkerGoals {
 /// Members of this `enum` should be listed from the highest priority to the lowest priority.
 #[derive(Debug, Enum, Clone, Copy)]
 pub(crate) enum WorkerGoal {
+    /// Sweep the heap and prepare for the next GC while mutators run after a GC (see the options
+    /// `concurrent_sweeping` and `concurrent_defrag_preparation`).  It has a higher priority than
+    /// `Gc` so that a GC never starts before the last GC is swept.
+    ConcurrentSweep,
     /// Do a garbage collection.
     Gc,
     /// Stop all GC threads so that the VM can call `fork()`.
     StopForFork,
+    /// Do useful work while mutators are idle, such as sweeping blocks in the background.
+    IdleWork,
 }
 
 impl WorkerGoals {
@@ -66,10 +80,34 @@ impl WorkerGoals {
         self.current
     }
 
-    /// Called when the current goal is completed.  This will clear the current goal.
+    /// Called when the current goal is completed.  This will clear the current goal, or resume the
+    /// goal suspended by `suspend_current_for_fork`.
     pub fn on_current_goal_completed(&mut self) {
         probe!(mmtk, goal_complete);
-        self.current = None
+        self.current = self.suspended.take();
+    }
+
+    /// If `StopForFork` is requested while workers are working towards another goal (i.e. in the
+    /// middle of a GC), suspend the current goal, and make `StopForFork` the current goal.  Return
+    /// `true` if the current goal is suspended.
+    pub fn suspend_current_for_fork(&mut self) -> bool {
+        if !self.requests[WorkerGoal::StopForFork] {
+            return false;
+        }
+        let Some(current) = self.current else {
+            return false;
+        };
+        debug_assert!(self.suspended.is_none());
+        self.requests[WorkerGoal::StopForFork] = false;
+        self.suspended = Some(current);
+        self.current = Some(WorkerGoal::StopForFork);
+        probe!(mmtk, goal_set, WorkerGoal::StopForFork);
+        true
+    }
+
+    /// Get the goal suspended for forking if exists.
+    pub fn suspended(&self) -> Option<WorkerGoal> {
+        self.suspended
     }
 
     /// Test if the given `goal` is requested.  Used for debug purpose, only.  The workers always
@@ -113,4 +151,57 @@ mod tests {
         assert!(matches!(next_goal, Some(WorkerGoal::Gc)));
         assert!(matches!(goals.current(), Some(WorkerGoal::Gc)));
     }
+
+    #[test]
+    fn test_concurrent_sweep_before_gc() {
+        let mut goals = WorkerGoals::default();
+        goals.set_request(WorkerGoal::Gc);
+        goals.set_request(WorkerGoal::ConcurrentSweep);
+
+        // The last GC is swept before the next GC starts.
+        assert!(matches!(
+            goals.poll_next_goal(),
+            Some(WorkerGoal::ConcurrentSweep)
+        ));
+        goals.on_current_goal_completed();
+        assert!(matches!(goals.poll_next_goal(), Some(WorkerGoal::Gc)));
+    }
+
+    #[test]
+    fn test_suspend_for_fork() {
+        let mut goals = WorkerGoals::default();
+        goals.set_request(WorkerGoal::Gc);
+        goals.poll_next_goal();
+
+        // Not requested.
+        assert!(!goals.suspend_current_for_fork());
+
+        goals.set_request(WorkerGoal::StopForFork);
+        assert!(goals.suspend_current_for_fork());
+        assert!(matches!(goals.current(), Some(WorkerGoal::StopForFork)));
+        assert!(matches!(goals.suspended(), Some(WorkerGoal::Gc)));
+        assert!(!goals.debug_is_requested(WorkerGoal::StopForFork));
+
+        // All workers exited.  The GC is resumed.
+        goals.on_current_goal_completed();
+        assert!(matches!(goals.current(), Some(WorkerGoal::Gc)));
+        assert!(goals.suspended().is_none());
+
+        // The GC finished.
+        goals.on_current_goal_completed();
+        assert!(goals.current().is_none());
+    }
+
+    #[test]
+    fn test_no_suspend_between_gcs() {
+        let mut goals = WorkerGoals::default();
+        goals.set_request(WorkerGoal::StopForFork);
+
+        // There is nothing to suspend.  The request is handled by `poll_next_goal`.
+        assert!(!goals.suspend_current_for_fork());
+        assert!(matches!(
+            goals.poll_next_goal(),
+            Some(WorkerGoal::StopForFork)
+        ));
+    }
 }
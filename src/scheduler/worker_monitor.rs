@@ -2,8 +2,10 @@
 //!
 //! -   allowing workers to park,
 //! -   letting the last parked worker take action, and
-//! -   letting workers and mutators notify workers when workers are given things to do.
+//! -   letting workers and mutators notify workers when workers are given things to do, and
+//! -   letting mutators wait for workers to exit.
 
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Condvar, Mutex};
 
 use super::{
@@ -35,6 +37,13 @@ pub(crate) struct WorkerMonitor {
     /// -   any work packets available, and
     /// -   any field in `sync.goals.requests` set to true.
     workers_have_anything_to_do: Condvar,
+    /// True from when `StopForFork` is requested until all workers have exited.  Workers check this
+    /// between work packets, and stop taking packets when it is set, so that they can stop in the
+    /// middle of a GC.
+    stopping_for_fork: AtomicBool,
+    /// Mutators wait on this for all workers to exit after requesting `StopForFork`.  Notified
+    /// when the last worker exits.
+    all_workers_exited: Condvar,
 }
 
 /// The synchronized part of `WorkerMonitor`.
@@ -92,6 +101,8 @@ impl WorkerMonitor {
                 goals: Default::default(),
             }),
             workers_have_anything_to_do: Default::default(),
+            stopping_for_fork: AtomicBool::new(false),
+            all_workers_exited: Default::default(),
         }
     }
 
@@ -100,6 +111,10 @@ impl WorkerMonitor {
     pub fn make_request(&self, goal: WorkerGoal) {
         let mut guard = self.sync.lock().unwrap();
         let newly_requested = guard.goals.set_request(goal);
+        if matches!(goal, WorkerGoal::StopForFork) {
+            // Set this after the request so that the last parked worker will see the request.
+            self.stopping_for_fork.store(true, Ordering::SeqCst);
+        }
         if newly_requested {
             self.notify_work_available(false);
         }
@@ -201,9 +216,8 @@ impl WorkerMonitor {
             // worker; and the last parked worker always checks it before waiting.  So this
             // condition will not be set without any worker noticing.
             //
-            // Note that generational barriers may add `ProcessModBuf` work packets when not in GC.
-            // This is benign because those work packets are not executed immediately, and are
-            // guaranteed to be executed in the next GC.
+            // Note that generational barriers do not add work packets when not in GC.  They flush
+            // their modbufs into remembered sets, which are scanned in the next GC.
 
             // Notes on spurious wake-up:
             //
@@ -238,10 +252,34 @@ impl WorkerMonitor {
         Ok(())
     }
 
+    /// Return true if workers should stop taking work packets because they are asked to exit for
+    /// forking.
+    pub fn is_stopping_for_fork(&self) -> bool {
+        self.stopping_for_fork.load(Ordering::SeqCst)
+    }
+
+    /// Block the current thread until all workers have exited after `StopForFork` is requested.
+    /// Return immediately if workers are not stopping.
+    pub fn wait_for_all_workers_to_exit(&self) {
+        let mut sync = self.sync.lock().unwrap();
+        while self.stopping_for_fork.load(Ordering::SeqCst) {
+            sync = self.all_workers_exited.wait(sync).unwrap();
+        }
+    }
+
     /// Called when all workers have exited.
     pub fn on_all_workers_exited(&self) {
-        let mut sync = self.sync.try_lock().unwrap();
+        // Mutators may be holding the lock while checking `stopping_for_fork`.
+        let mut sync = self.sync.lock().unwrap();
+        if let Some(goal) = sync.goals.suspended() {
+            debug!(
+                "Goal {:?} will be resumed after GC workers are respawned.",
+                goal
+            );
+        }
         sync.goals.on_current_goal_completed();
+        self.stopping_for_fork.store(false, Ordering::SeqCst);
+        self.all_workers_exited.notify_all();
     }
 }
 
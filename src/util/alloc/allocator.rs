@@ -1,4 +1,5 @@
 use crate::global_state::GlobalState;
+use crate::scheduler::Handshake;
 use crate::util::address::Address;
 #[cfg(feature = "analysis")]
 use crate::util::analysis::AnalysisManager;
@@ -6,6 +7,7 @@ use crate::util::heap::gc_trigger::GCTrigger;
 use crate::util::options::Options;
 use crate::MMTK;
 
+use atomic_refcell::AtomicRefCell;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
@@ -27,6 +29,67 @@ pub enum AllocationError {
     MmapOutOfMemory,
 }
 
+/// The context of an [`AllocationError`].  MMTk passes it to [`Collection::out_of_memory`] so that
+/// the binding can tell which space failed and why, and raise a precise exception (e.g. "large
+/// object space exhausted" instead of "heap exhausted").
+#[derive(Clone, Debug)]
+pub struct AllocationErrorContext {
+    /// The name of the space that failed to allocate, or `None` if the error is not specific to a
+    /// space (e.g. when the OS fails to mmap memory).
+    pub space: Option<&'static str>,
+    /// The size of the failed allocation or memory request in bytes.
+    pub requested_bytes: usize,
+    /// The memory reserved by the heap in bytes when the error happened, if known.
+    pub used_bytes: Option<usize>,
+    /// The maximum heap size in bytes allowed by the GC trigger, if known.
+    pub max_bytes: Option<usize>,
+    /// True if MMTk attempted an emergency GC before reporting the error.
+    pub emergency_collection_attempted: bool,
+}
+
+impl AllocationErrorContext {
+    /// Create a context for an error that is not specific to a space, and happens before MMTk can
+    /// do a GC, such as an mmap failure.
+    pub(crate) fn without_space(requested_bytes: usize) -> Self {
+        Self {
+            space: None,
+            requested_bytes,
+            used_bytes: None,
+            max_bytes: None,
+            emergency_collection_attempted: false,
+        }
+    }
+}
+
+/// What an allocation does if it cannot be satisfied without a GC.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum OnAllocationFail {
+    /// Trigger a GC and block the mutator until the GC finishes, then retry the allocation.  If
+    /// the heap is still full after an emergency GC, report [`AllocationError::HeapOutOfMemory`].
+    /// This is what [`crate::memory_manager::alloc`] does.
+    #[default]
+    RequestGC,
+    /// Return a null address without triggering a GC.  The allocation only tries the fast path and
+    /// one attempt of the slow path, and does not block or report out of memory.  This is for
+    /// contexts where a GC is not permitted.  The allocation may still take locks, so it is not
+    /// async-signal-safe.
+    ReturnFailure,
+}
+
+impl OnAllocationFail {
+    /// Can the allocation trigger a GC?
+    pub fn allow_gc(&self) -> bool {
+        *self == OnAllocationFail::RequestGC
+    }
+}
+
+/// Options for an allocation request.  See [`crate::memory_manager::alloc_with_options`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct AllocationOptions {
+    /// What the allocation does if it cannot be satisfied without a GC.
+    pub on_fail: OnAllocationFail,
+}
+
 pub fn align_allocation_no_fill<VM: VMBinding>(
     region: Address,
     alignment: usize,
@@ -135,8 +198,12 @@ pub struct AllocatorContext<VM: VMBinding> {
     pub state: Arc<GlobalState>,
     pub options: Arc<Options>,
     pub gc_trigger: Arc<GCTrigger<VM>>,
+    pub handshake: Arc<Handshake<VM>>,
     #[cfg(feature = "analysis")]
     pub analysis_manager: Arc<AnalysisManager<VM>>,
+    /// The options of the allocation in progress.  The context is shared by the allocators of one
+    /// mutator, and this is only accessed by the mutator thread.
+    alloc_options: AtomicRefCell<AllocationOptions>,
 }
 
 impl<VM: VMBinding> AllocatorContext<VM> {
@@ -145,10 +212,27 @@ impl<VM: VMBinding> AllocatorContext<VM> {
             state: mmtk.state.clone(),
             options: mmtk.options.clone(),
             gc_trigger: mmtk.gc_trigger.clone(),
+            handshake: mmtk.handshake.clone(),
             #[cfg(feature = "analysis")]
             analysis_manager: mmtk.analysis_manager.clone(),
+            alloc_options: AtomicRefCell::new(AllocationOptions::default()),
         }
     }
+
+    /// Set the options for the allocation that is about to start.
+    pub fn set_alloc_options(&self, options: AllocationOptions) {
+        *self.alloc_options.borrow_mut() = options;
+    }
+
+    /// Reset the options to the default after an allocation finishes.
+    pub fn clear_alloc_options(&self) {
+        *self.alloc_options.borrow_mut() = AllocationOptions::default();
+    }
+
+    /// Get the options of the allocation in progress.
+    pub fn get_alloc_options(&self) -> AllocationOptions {
+        *self.alloc_options.borrow()
+    }
 }
 
 /// A trait which implements allocation routines. Every allocator needs to implements this trait.
@@ -156,6 +240,10 @@ pub trait Allocator<VM: VMBinding>: Downcast {
     /// Return the [`VMThread`] associated with this allocator instance.
     fn get_tls(&self) -> VMThread;
 
+    /// Set the [`VMThread`] associated with this allocator instance.  This is called when the
+    /// mutator that owns this allocator is rebound to another thread.
+    fn set_tls(&mut self, tls: VMThread);
+
     /// Return the [`Space`](src/policy/space/Space) instance associated with this allocator instance.
     fn get_space(&self) -> &'static dyn Space<VM>;
 
@@ -194,6 +282,28 @@ pub trait Allocator<VM: VMBinding>: Downcast {
     /// * `offset` the required offset in bytes.
     fn alloc(&mut self, size: usize, align: usize, offset: usize) -> Address;
 
+    /// An allocation attempt with the given options.  This is the same as
+    /// [`alloc`](Allocator::alloc), except that the options apply to the slow path and the spaces
+    /// if the allocation goes to the slow path.
+    ///
+    /// Arguments:
+    /// * `size`: the allocation size in bytes.
+    /// * `align`: the required alignment in bytes.
+    /// * `offset` the required offset in bytes.
+    /// * `options`: the options for the allocation.
+    fn alloc_with_options(
+        &mut self,
+        size: usize,
+        align: usize,
+        offset: usize,
+        options: AllocationOptions,
+    ) -> Address {
+        self.get_context().set_alloc_options(options);
+        let result = self.alloc(size, align, offset);
+        self.get_context().clear_alloc_options();
+        result
+    }
+
     /// Slowpath allocation attempt. This function is explicitly not inlined for performance
     /// considerations.
     ///
@@ -206,6 +316,28 @@ pub trait Allocator<VM: VMBinding>: Downcast {
         self.alloc_slow_inline(size, align, offset)
     }
 
+    /// Slowpath allocation attempt with the given options.  See
+    /// [`alloc_with_options`](Allocator::alloc_with_options).
+    ///
+    /// Arguments:
+    /// * `size`: the allocation size in bytes.
+    /// * `align`: the required alignment in bytes.
+    /// * `offset` the required offset in bytes.
+    /// * `options`: the options for the allocation.
+    #[inline(never)]
+    fn alloc_slow_with_options(
+        &mut self,
+        size: usize,
+        align: usize,
+        offset: usize,
+        options: AllocationOptions,
+    ) -> Address {
+        self.get_context().set_alloc_options(options);
+        let result = self.alloc_slow_inline(size, align, offset);
+        self.get_context().clear_alloc_options();
+        result
+    }
+
     /// Slowpath allocation attempt. This function executes the actual slowpath allocation.  A
     /// slowpath allocation in MMTk attempts to allocate the object using the per-allocator
     /// definition of [`alloc_slow_once`](Allocator::alloc_slow_once). This function also accounts for increasing the
@@ -213,7 +345,8 @@ pub trait Allocator<VM: VMBinding>: Downcast {
     /// being used, the [`alloc_slow_once_precise_stress`](Allocator::alloc_slow_once_precise_stress) function is used instead.
     ///
     /// Note that in the case where the VM is out of memory, we invoke
-    /// [`Collection::out_of_memory`] with a [`AllocationError::HeapOutOfMemory`] error to inform
+    /// [`Collection::out_of_memory`] with a [`AllocationError::HeapOutOfMemory`] error (and the
+    /// [`AllocationErrorContext`] of the error) to inform
     /// the binding and then return a null pointer back to it. We have no assumptions on whether
     /// the VM will continue executing or abort immediately on a
     /// [`AllocationError::HeapOutOfMemory`] error.
@@ -223,9 +356,19 @@ pub trait Allocator<VM: VMBinding>: Downcast {
     /// * `align`: the required alignment in bytes.
     /// * `offset` the required offset in bytes.
     fn alloc_slow_inline(&mut self, size: usize, align: usize, offset: usize) -> Address {
+        probe!(mmtk, alloc_slow_start, size, align);
         let tls = self.get_tls();
         let is_mutator = VM::VMActivePlan::is_mutator(tls);
         let stress_test = self.get_context().options.is_stress_test_gc_enabled();
+        let allow_gc = self.get_context().get_alloc_options().on_fail.allow_gc();
+
+        let idle_mutator_flush_timeout = *self.get_context().options.idle_mutator_flush_timeout;
+        if is_mutator && allow_gc && idle_mutator_flush_timeout > 0 {
+            self.get_context().handshake.maybe_flush_idle_mutators(
+                tls,
+                std::time::Duration::from_millis(idle_mutator_flush_timeout as u64),
+            );
+        }
 
         // Information about the previous collection.
         let mut emergency_collection = false;
@@ -243,7 +386,8 @@ pub trait Allocator<VM: VMBinding>: Downcast {
                 // If we should do a stress GC now, we tell the alloc_slow_once_precise_stress()
                 // so they would avoid try any thread local allocation, and directly call
                 // global acquire and do a poll.
-                let need_poll = is_mutator && self.get_context().gc_trigger.should_do_stress_gc();
+                let need_poll =
+                    is_mutator && allow_gc && self.get_context().gc_trigger.should_do_stress_gc();
                 self.alloc_slow_once_precise_stress(size, align, offset, need_poll)
             } else {
                 // If we are not doing precise stress GC, just call the normal alloc_slow_once().
@@ -270,21 +414,28 @@ pub trait Allocator<VM: VMBinding>: Downcast {
                         .store(true, Ordering::SeqCst);
                 }
 
+                // For allocators that have thread local buffers, we count the entire thread local
+                // buffer size as allocated.  For allocators that do not have thread local buffer,
+                // we know exactly how many bytes we allocate.
+                let buffer_size = if self.does_thread_local_allocation() {
+                    crate::util::conversions::raw_align_up(
+                        size,
+                        self.get_thread_local_buffer_granularity(),
+                    )
+                } else {
+                    size
+                };
+                self.get_context().state.allocation_rate.record(buffer_size);
+                self.get_context().gc_trigger.record_allocation(buffer_size);
+
                 // Only update the allocation bytes if we haven't failed a previous allocation in this loop
                 if stress_test && self.get_context().state.is_initialized() && !previous_result_zero
                 {
-                    let allocated_size = if *self.get_context().options.precise_stress
-                        || !self.does_thread_local_allocation()
-                    {
-                        // For precise stress test, or for allocators that do not have thread local buffer,
-                        // we know exactly how many bytes we allocate.
+                    let allocated_size = if *self.get_context().options.precise_stress {
+                        // For precise stress test, we know exactly how many bytes we allocate.
                         size
                     } else {
-                        // For normal stress test, we count the entire thread local buffer size as allocated.
-                        crate::util::conversions::raw_align_up(
-                            size,
-                            self.get_thread_local_buffer_granularity(),
-                        )
+                        buffer_size
                     };
                     let _allocation_bytes = self
                         .get_context()
@@ -310,6 +461,12 @@ pub trait Allocator<VM: VMBinding>: Downcast {
                 return result;
             }
 
+            // The allocation does not allow GC, so we do not retry after a GC, or report OOM.
+            if !allow_gc {
+                trace!("Allocation failed without GC");
+                return result;
+            }
+
             // It is possible to have cases where a thread is blocked for another GC (non emergency)
             // immediately after being blocked for a GC (emergency) (e.g. in stress test), that is saying
             // the thread does not leave this loop between the two GCs. The local var 'emergency_collection'
@@ -330,7 +487,21 @@ pub trait Allocator<VM: VMBinding>: Downcast {
                 if fail_with_oom {
                     // Note that we throw a `HeapOutOfMemory` error here and return a null ptr back to the VM
                     trace!("Throw HeapOutOfMemory!");
-                    VM::VMCollection::out_of_memory(tls, AllocationError::HeapOutOfMemory);
+                    info!(
+                        "Memory mapped by MMTk:\n{}",
+                        crate::util::memory::format_mmap_records()
+                    );
+                    probe!(mmtk, out_of_memory, AllocationError::HeapOutOfMemory, size);
+                    let context = self.get_context().gc_trigger.allocation_error_context(
+                        Some(self.get_space().get_name()),
+                        size,
+                        true,
+                    );
+                    VM::VMCollection::out_of_memory(
+                        tls,
+                        AllocationError::HeapOutOfMemory,
+                        &context,
+                    );
                     self.get_context()
                         .state
                         .allocation_success
@@ -427,6 +598,13 @@ pub trait Allocator<VM: VMBinding>: Downcast {
         self.alloc_slow_once_traced(size, align, offset)
     }
 
+    /// Stop allocating into the current thread-local buffer, so that the unused memory in the
+    /// buffer can be reused by other mutators, or reclaimed by the next GC.  The allocator will
+    /// acquire a new buffer in the slow path when it allocates again.
+    fn retire_thread_local_buffer(&mut self) {
+        // By default, do nothing
+    }
+
     /// The [`crate::plan::Mutator`] that includes this allocator is going to be destroyed. Some allocators
     /// may need to save/transfer its thread local data to the space.
     fn on_mutator_destroy(&mut self) {
@@ -94,6 +94,10 @@ impl<VM: VMBinding> Allocator<VM> for BumpAllocator<VM> {
         true
     }
 
+    fn retire_thread_local_buffer(&mut self) {
+        self.reset();
+    }
+
     fn get_thread_local_buffer_granularity(&self) -> usize {
         BLOCK_SIZE
     }
@@ -171,6 +175,10 @@ impl<VM: VMBinding> Allocator<VM> for BumpAllocator<VM> {
     fn get_tls(&self) -> VMThread {
         self.tls
     }
+
+    fn set_tls(&mut self, tls: VMThread) {
+        self.tls = tls;
+    }
 }
 
 impl<VM: VMBinding> BumpAllocator<VM> {
@@ -194,12 +202,18 @@ impl<VM: VMBinding> BumpAllocator<VM> {
         offset: usize,
         stress_test: bool,
     ) -> Address {
-        if self.space.will_oom_on_acquire(self.tls, size) {
+        let alloc_options = self.context.get_alloc_options();
+        if self
+            .space
+            .will_oom_on_acquire(self.tls, size, alloc_options)
+        {
             return Address::ZERO;
         }
 
         let block_size = (size + BLOCK_MASK) & (!BLOCK_MASK);
-        let acquired_start = self.space.acquire(self.tls, bytes_to_pages_up(block_size));
+        let acquired_start =
+            self.space
+                .acquire(self.tls, bytes_to_pages_up(block_size), alloc_options);
         if acquired_start.is_zero() {
             trace!("Failed to acquire a new block");
             acquired_start
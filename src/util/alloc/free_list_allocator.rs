@@ -38,6 +38,10 @@ impl<VM: VMBinding> Allocator<VM> for FreeListAllocator<VM> {
         self.tls
     }
 
+    fn set_tls(&mut self, tls: VMThread) {
+        self.tls = tls;
+    }
+
     fn get_space(&self) -> &'static dyn crate::policy::space::Space<VM> {
         self.space
     }
@@ -97,6 +101,12 @@ impl<VM: VMBinding> Allocator<VM> for FreeListAllocator<VM> {
         true
     }
 
+    fn retire_thread_local_buffer(&mut self) {
+        // Give the blocks to other mutators.
+        let mut global = self.space.get_abandoned_block_lists().lock().unwrap();
+        self.abandon_blocks(&mut global);
+    }
+
     fn get_thread_local_buffer_granularity(&self) -> usize {
         Block::BYTES
     }
@@ -152,6 +162,9 @@ impl<VM: VMBinding> FreeListAllocator<VM> {
         if cell.is_zero() {
             return cell; // return failed allocation
         }
+        let cell_size = block.load_block_cell_size();
+        // The free cell may have been marked as no-access, except its link.
+        crate::util::memory::mark_undefined(cell, cell_size);
         let next_cell = unsafe { cell.load::<Address>() };
         // Clear the link
         unsafe { cell.store::<Address>(Address::ZERO) };
@@ -165,7 +178,6 @@ impl<VM: VMBinding> FreeListAllocator<VM> {
 
         // Zeroing memory right before we return it.
         // If we move the zeroing to somewhere else, we need to clear the list link here: cell.store::<Address>(Address::ZERO)
-        let cell_size = block.load_block_cell_size();
         crate::util::memory::zero(cell, cell_size);
 
         // Make sure the memory is zeroed. This looks silly as we zero the cell right before this check.
@@ -267,7 +279,7 @@ impl<VM: VMBinding> FreeListAllocator<VM> {
                 debug_assert!(self.available_blocks[bin].is_empty()); // only use this function if there are no blocks available
 
                 if let Some(block) = self.unswept_blocks.get_mut(bin).unwrap().pop() {
-                    block.sweep::<VM>();
+                    block.sweep(self.space);
                     if block.has_free_cells() {
                         // recyclable block
                         self.add_to_available_blocks(
@@ -296,7 +308,12 @@ impl<VM: VMBinding> FreeListAllocator<VM> {
     ) -> Option<Block> {
         let bin = mi_bin::<VM>(size, align);
         loop {
-            match self.space.acquire_block(self.tls, size, align) {
+            match self.space.acquire_block(
+                self.tls,
+                size,
+                align,
+                self.context.get_alloc_options(),
+            ) {
                 crate::policy::marksweepspace::native_ms::BlockAcquireResult::Exhausted => {
                     debug!("Acquire global block: None");
                     // GC
@@ -325,7 +342,7 @@ impl<VM: VMBinding> FreeListAllocator<VM> {
                 crate::policy::marksweepspace::native_ms::BlockAcquireResult::AbandonedUnswept(block) => {
                     debug!("Acquire global block: AbandonedUnswep {:?}", block);
                     block.store_tls(self.tls);
-                    block.sweep::<VM>();
+                    block.sweep(self.space);
                     if block.has_free_cells() {
                         self.add_to_available_blocks(bin, block, stress_test);
                         return Some(block);
@@ -338,33 +355,9 @@ impl<VM: VMBinding> FreeListAllocator<VM> {
     }
 
     fn init_block(&self, block: Block, cell_size: usize) {
-        debug_assert_ne!(cell_size, 0);
         self.space.record_new_block(block);
-
         // construct free list
-        let block_end = block.start() + Block::BYTES;
-        let mut old_cell = unsafe { Address::zero() };
-        let mut new_cell = block.start();
-
-        let final_cell = loop {
-            unsafe {
-                new_cell.store::<Address>(old_cell);
-            }
-            old_cell = new_cell;
-            new_cell += cell_size;
-            if new_cell + cell_size > block_end {
-                break old_cell;
-            };
-        };
-
-        block.store_free_list(final_cell);
-        block.store_block_cell_size(cell_size);
-        #[cfg(feature = "malloc_native_mimalloc")]
-        {
-            block.store_local_free_list(Address::ZERO);
-            block.store_thread_free_list(Address::ZERO);
-        }
-
+        block.init_cells(cell_size);
         self.store_block_tls(block);
     }
 
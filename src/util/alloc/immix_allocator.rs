@@ -1,7 +1,9 @@
 use std::sync::Arc;
+use std::time::Duration;
 
 use super::allocator::{align_allocation_no_fill, fill_alignment_gap, AllocatorContext};
 use super::BumpPointer;
+use crate::policy::immix::block::Block;
 use crate::policy::immix::line::*;
 use crate::policy::immix::ImmixSpace;
 use crate::policy::space::Space;
@@ -13,6 +15,10 @@ use crate::util::rust_util::unlikely;
 use crate::util::Address;
 use crate::vm::*;
 
+/// The minimum interval between two handshakes that reclaim the blocks held by idle mutators.  See
+/// the option `reclaim_idle_mutator_blocks`.
+pub(crate) const RECLAIM_IDLE_MUTATOR_BLOCKS_INTERVAL: Duration = Duration::from_millis(10);
+
 /// Immix allocator
 #[repr(C)]
 pub struct ImmixAllocator<VM: VMBinding> {
@@ -57,6 +63,11 @@ impl<VM: VMBinding> Allocator<VM> for ImmixAllocator<VM> {
         true
     }
 
+    fn retire_thread_local_buffer(&mut self) {
+        // Let other mutators allocate into the rest of the blocks.
+        self.return_unused_lines();
+    }
+
     fn get_thread_local_buffer_granularity(&self) -> usize {
         crate::policy::immix::block::Block::BYTES
     }
@@ -103,6 +114,21 @@ impl<VM: VMBinding> Allocator<VM> for ImmixAllocator<VM> {
     /// Acquire a clean block from ImmixSpace for allocation.
     fn alloc_slow_once(&mut self, size: usize, align: usize, offset: usize) -> Address {
         trace!("{:?}: alloc_slow_once", self.tls);
+        // Large objects are only allocated into clean blocks.
+        if !self.request_for_large && self.should_reclaim_idle_mutator_blocks() {
+            // Allocate into the lines returned by idle mutators in earlier handshakes, if any.
+            if self.acquire_recyclable_lines(size, align, offset) {
+                return self.alloc(size, align, offset);
+            }
+            // An allocation that must not trigger a GC does not start a handshake, either, as that
+            // calls into the VM.
+            if self.context.get_alloc_options().on_fail.allow_gc() {
+                self.context.handshake.maybe_reclaim_idle_mutator_blocks(
+                    self.tls,
+                    RECLAIM_IDLE_MUTATOR_BLOCKS_INTERVAL,
+                );
+            }
+        }
         self.acquire_clean_block(size, align, offset)
     }
 
@@ -163,6 +189,10 @@ impl<VM: VMBinding> Allocator<VM> for ImmixAllocator<VM> {
     fn get_tls(&self) -> VMThread {
         self.tls
     }
+
+    fn set_tls(&mut self, tls: VMThread) {
+        self.tls = tls;
+    }
 }
 
 impl<VM: VMBinding> ImmixAllocator<VM> {
@@ -189,6 +219,38 @@ impl<VM: VMBinding> ImmixAllocator<VM> {
         self.space
     }
 
+    /// Return true if the option `reclaim_idle_mutator_blocks` is set and acquiring a clean block
+    /// would trigger a GC.  In that case, the mutator allocates into the lines returned by idle
+    /// mutators if there are any, and asks the idle mutators to return the unused lines of their
+    /// blocks with a handshake.  The mutator does not wait for the handshake.
+    fn should_reclaim_idle_mutator_blocks(&self) -> bool {
+        !crate::policy::immix::BLOCK_ONLY
+            && !self.copy
+            && *self.context.options.reclaim_idle_mutator_blocks
+            && VM::VMActivePlan::is_mutator(self.tls)
+            && self.context.gc_trigger.is_heap_full_after(Block::PAGES)
+    }
+
+    /// Return the unused lines of the blocks this allocator is allocating into to the space, so
+    /// that other mutators can allocate into them, and reset the allocator.  This is called when the
+    /// thread-local buffer is retired, and for idle mutators before a GC would be triggered.  See
+    /// the option `reclaim_idle_mutator_blocks`.
+    pub(crate) fn return_unused_lines(&mut self) {
+        if !crate::policy::immix::BLOCK_ONLY && !self.copy {
+            for bump_pointer in [self.bump_pointer, self.large_bump_pointer] {
+                // The cursor is right after the last allocated object.  Nothing is left if the
+                // first unused line is at the end of the block.
+                let first_unused = bump_pointer.cursor.align_up(Line::BYTES);
+                if bump_pointer.cursor.is_zero() || first_unused.is_aligned_to(Block::BYTES) {
+                    continue;
+                }
+                self.immix_space()
+                    .return_unused_lines(Line::from_aligned_address(first_unused));
+            }
+        }
+        self.reset();
+    }
+
     /// Large-object (larger than a line) bump allocation.
     fn overflow_alloc(&mut self, size: usize, align: usize, offset: usize) -> Address {
         trace!("{:?}: overflow_alloc", self.tls);
@@ -249,6 +311,10 @@ impl<VM: VMBinding> ImmixAllocator<VM> {
                     end_line,
                     self.tls
                 );
+                crate::util::memory::mark_undefined(
+                    self.bump_pointer.cursor,
+                    self.bump_pointer.limit - self.bump_pointer.cursor,
+                );
                 crate::util::memory::zero(
                     self.bump_pointer.cursor,
                     self.bump_pointer.limit - self.bump_pointer.cursor,
@@ -289,7 +355,11 @@ impl<VM: VMBinding> ImmixAllocator<VM> {
 
     // Get a clean block from ImmixSpace.
     fn acquire_clean_block(&mut self, size: usize, align: usize, offset: usize) -> Address {
-        match self.immix_space().get_clean_block(self.tls, self.copy) {
+        match self.immix_space().get_clean_block(
+            self.tls,
+            self.copy,
+            self.context.get_alloc_options(),
+        ) {
             None => Address::ZERO,
             Some(block) => {
                 trace!(
@@ -25,6 +25,10 @@ impl<VM: VMBinding> Allocator<VM> for LargeObjectAllocator<VM> {
         self.tls
     }
 
+    fn set_tls(&mut self, tls: VMThread) {
+        self.tls = tls;
+    }
+
     fn get_context(&self) -> &AllocatorContext<VM> {
         &self.context
     }
@@ -49,13 +53,17 @@ impl<VM: VMBinding> Allocator<VM> for LargeObjectAllocator<VM> {
     }
 
     fn alloc_slow_once(&mut self, size: usize, align: usize, _offset: usize) -> Address {
-        if self.space.will_oom_on_acquire(self.tls, size) {
+        let alloc_options = self.context.get_alloc_options();
+        if self
+            .space
+            .will_oom_on_acquire(self.tls, size, alloc_options)
+        {
             return Address::ZERO;
         }
 
         let maxbytes = allocator::get_maximum_aligned_size::<VM>(size, align);
         let pages = crate::util::conversions::bytes_to_pages_up(maxbytes);
-        self.space.allocate_pages(self.tls, pages)
+        self.space.allocate_pages(self.tls, pages, alloc_options)
     }
 }
 
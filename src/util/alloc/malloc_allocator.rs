@@ -37,12 +37,22 @@ impl<VM: VMBinding> Allocator<VM> for MallocAllocator<VM> {
         self.tls
     }
 
+    fn set_tls(&mut self, tls: VMThread) {
+        self.tls = tls;
+    }
+
     fn does_thread_local_allocation(&self) -> bool {
         false
     }
 
     fn alloc_slow_once(&mut self, size: usize, align: usize, offset: usize) -> Address {
-        self.space.alloc(self.tls, size, align, offset)
+        self.space.alloc(
+            self.tls,
+            size,
+            align,
+            offset,
+            self.context.get_alloc_options(),
+        )
     }
 }
 
@@ -42,10 +42,18 @@ impl<VM: VMBinding> Allocator<VM> for MarkCompactAllocator<VM> {
         self.bump_allocator.get_tls()
     }
 
+    fn set_tls(&mut self, tls: VMThread) {
+        self.bump_allocator.set_tls(tls)
+    }
+
     fn does_thread_local_allocation(&self) -> bool {
         true
     }
 
+    fn retire_thread_local_buffer(&mut self) {
+        self.reset();
+    }
+
     fn get_thread_local_buffer_granularity(&self) -> usize {
         self.bump_allocator.get_thread_local_buffer_granularity()
     }
@@ -0,0 +1,60 @@
+//! Allocation site tracking for debugging.
+//!
+//! A binding may record an allocation site for each object with
+//! [`crate::memory_manager::set_allocation_site`]. The allocation site is an opaque 16-bit tag
+//! supplied by the binding, such as an index into a table of allocation sites in the compiled
+//! code, or the ID of a captured backtrace. MMTk stores the tag in a global side metadata table,
+//! and includes it in the reports about bad objects (sanity GC failures, heap verification
+//! failures, etc.) so that we can find out who allocated a corrupted object.
+//!
+//! The tag `0` means the allocation site is unknown. `Mutator::post_alloc` resets the tag of a new
+//! object to `0`, so the binding should set the tag after calling `post_alloc`.
+//!
+//! Limitations:
+//! *   The tag is moved with the object when the object is forwarded by a copying space. Plans
+//!     that compute forwarding addresses after liveness (i.e. MarkCompact) do not move the tag.
+//! *   On 32-bit targets, the side metadata uses one tag per 32 bytes, so objects that are closer
+//!     than 32 bytes share the same tag.
+
+use crate::mmtk::SFT_MAP;
+use crate::util::metadata::side_metadata::SideMetadataSpec;
+use crate::util::ObjectReference;
+use std::sync::atomic::Ordering;
+
+/// The side metadata spec for the allocation site of an object.
+pub(crate) const ALLOC_SITE_SPEC: SideMetadataSpec =
+    crate::util::metadata::side_metadata::spec_defs::ALLOC_SITE;
+
+/// The tag for objects whose allocation site is unknown.
+pub(crate) const UNKNOWN_ALLOC_SITE: u16 = 0;
+
+/// Set the allocation site of an object.
+pub(crate) fn set_alloc_site(object: ObjectReference, site: u16) {
+    ALLOC_SITE_SPEC.store_atomic::<u16>(object.to_raw_address(), site, Ordering::Relaxed);
+}
+
+/// Get the allocation site of an object.
+pub(crate) fn get_alloc_site(object: ObjectReference) -> u16 {
+    ALLOC_SITE_SPEC.load_atomic::<u16>(object.to_raw_address(), Ordering::Relaxed)
+}
+
+/// Move the allocation site of an object to its new copy.
+pub(crate) fn on_object_forwarded(from: ObjectReference, to: ObjectReference) {
+    set_alloc_site(to, get_alloc_site(from));
+}
+
+/// Return a description of the allocation site of an object that can be appended to a report,
+/// or an empty string if the object is not in an MMTk space (in which case its side metadata may
+/// not be mapped).
+pub(crate) fn describe(object: ObjectReference) -> String {
+    if !SFT_MAP
+        .get_checked(object.to_raw_address())
+        .is_in_space(object)
+    {
+        return String::new();
+    }
+    match get_alloc_site(object) {
+        UNKNOWN_ALLOC_SITE => " (allocation site: unknown)".to_string(),
+        site => format!(" (allocation site: {})", site),
+    }
+}
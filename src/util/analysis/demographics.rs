@@ -0,0 +1,100 @@
+use crate::util::analysis::RtAnalysis;
+use crate::util::ObjectReference;
+use crate::vm::{ObjectModel, VMBinding};
+use crate::MMTK;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Statistics for the live objects of one type in the last GC.
+#[derive(Copy, Clone, Debug)]
+pub struct LiveTypeStats {
+    /// The type name returned by [`crate::vm::ObjectModel::get_type_name`].
+    pub type_name: &'static str,
+    /// The number of live objects of the type.
+    pub count: usize,
+    /// Total accumulated bytes of live objects of the type.
+    pub bytes: usize,
+}
+
+/**
+ * This file implements an analysis routine that classifies the live objects in each GC by the
+ * type names provided by the binding (see `ObjectModel::get_type_name`). Every object scanned
+ * during tracing is counted once. At the end of each GC, the `top_n` types with the most live
+ * bytes are kept as the histogram of that GC, and can be queried with
+ * `memory_manager::live_object_demographics_in_last_gc`.
+ *
+ * Note that for generational plans, a nursery GC only scans the objects in the nursery and the
+ * objects in the mature space that are remembered, and the histogram is only for those objects.
+ */
+pub struct LiveObjectDemographics {
+    running: bool,
+    top_n: usize,
+    /// Live objects found so far in the current GC, indexed by type name.
+    current: HashMap<&'static str, (usize, usize)>,
+    /// The histogram of the last GC, sorted by the live bytes in descending order.
+    last_gc: Arc<Mutex<Vec<LiveTypeStats>>>,
+}
+
+impl LiveObjectDemographics {
+    pub fn new(running: bool, top_n: usize, last_gc: Arc<Mutex<Vec<LiveTypeStats>>>) -> Self {
+        Self {
+            running,
+            top_n,
+            current: HashMap::new(),
+            last_gc,
+        }
+    }
+}
+
+impl<VM: VMBinding> RtAnalysis<VM> for LiveObjectDemographics {
+    fn gc_hook(&mut self, _mmtk: &'static MMTK<VM>) {
+        // A new GC starts.
+        self.current.clear();
+    }
+
+    fn trace_hook(&mut self, objects: &[ObjectReference]) {
+        if !self.running {
+            return;
+        }
+
+        for object in objects.iter().copied() {
+            let type_name = VM::VMObjectModel::get_type_name(object);
+            let bytes = VM::VMObjectModel::get_current_size(object);
+            let entry = self.current.entry(type_name).or_default();
+            entry.0 += 1;
+            entry.1 += bytes;
+        }
+    }
+
+    fn gc_end_hook(&mut self, _mmtk: &'static MMTK<VM>) {
+        if !self.running {
+            return;
+        }
+
+        let mut histogram: Vec<LiveTypeStats> = self
+            .current
+            .drain()
+            .map(|(type_name, (count, bytes))| LiveTypeStats {
+                type_name,
+                count,
+                bytes,
+            })
+            .collect();
+        histogram.sort_unstable_by(|a, b| b.bytes.cmp(&a.bytes).then(b.count.cmp(&a.count)));
+        histogram.truncate(self.top_n);
+
+        for stats in histogram.iter() {
+            info!(
+                "Live {}: {} objects, {} bytes",
+                stats.type_name, stats.count, stats.bytes
+            );
+        }
+
+        *self.last_gc.lock().unwrap() = histogram;
+    }
+
+    fn set_running(&mut self, running: bool) {
+        self.running = running;
+    }
+}
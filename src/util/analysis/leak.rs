@@ -0,0 +1,188 @@
+use crate::util::analysis::lifetime;
+use crate::util::analysis::RtAnalysis;
+use crate::util::ObjectReference;
+use crate::vm::{ObjectModel, VMBinding};
+use crate::MMTK;
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+/// A group of long-lived objects that keeps growing, reported by the leak report.
+#[derive(Copy, Clone, Debug)]
+pub struct LeakSuspect {
+    /// The type name returned by [`crate::vm::ObjectModel::get_type_name`].
+    pub type_name: &'static str,
+    /// The allocation site tag recorded by `memory_manager::set_allocation_site`. This is always 0
+    /// (unknown) if the feature "alloc_site" is not enabled.
+    pub alloc_site: u16,
+    /// The number of objects of the group that have survived the given number of full-heap GCs.
+    pub count: usize,
+    /// Total bytes of those objects.
+    pub bytes: usize,
+    /// The increase of `bytes` since the previous full-heap GC.
+    pub bytes_growth: usize,
+    /// The number of consecutive full-heap GCs (including the last one) in which `bytes` grew.
+    pub growing_full_gcs: usize,
+}
+
+/// The key to group long-lived objects.
+type GroupKey = (&'static str, u16);
+
+/**
+ * This file implements an analysis routine that reports the long-lived objects that keep growing,
+ * to help diagnose memory leaks in the managed heap.
+ *
+ * In each full-heap GC, we look at every object scanned during tracing. We find the number of
+ * full-heap GCs that it has survived from the GC epoch when the object was allocated (see the
+ * object lifetime analysis), and count the objects that have survived at least
+ * `leak_report_min_full_gcs` (an MMTk option) full-heap GCs, grouped by the type name and the
+ * allocation site. At the end of the GC, groups whose total bytes grew since the previous
+ * full-heap GC are reported, and can be queried with
+ * `memory_manager::leak_suspects_in_last_full_gc`.
+ *
+ * Nursery GCs do not scan all live objects, and are ignored. Like the lifetime analysis, GC epochs
+ * wrap around after 256 GCs, so the ages of very old objects are not accurate. The GC epoch of an
+ * object is moved with the object when it is forwarded by a copying space, but not when it is
+ * compacted by MarkCompact.
+ */
+pub struct LeakReport {
+    running: bool,
+    min_full_gcs: usize,
+    top_n: usize,
+    /// The number of full-heap GCs that have finished.
+    full_gcs: usize,
+    /// The number of full-heap GCs that had finished when the objects of each GC epoch were allocated.
+    full_gcs_at_epoch: Vec<usize>,
+    /// The objects seen in the current GC. A plan may scan an object more than once in a GC (e.g.
+    /// MarkCompact traces the heap twice).
+    seen: HashSet<ObjectReference>,
+    /// Long-lived objects found so far in the current GC.
+    current: HashMap<GroupKey, (usize, usize)>,
+    /// The long-lived objects in the previous full-heap GC, and the number of consecutive
+    /// full-heap GCs in which they grew.
+    previous: HashMap<GroupKey, (usize, usize)>,
+    /// The report of the last full-heap GC, sorted by bytes in descending order.
+    last_report: Arc<Mutex<Vec<LeakSuspect>>>,
+}
+
+impl LeakReport {
+    pub fn new(
+        running: bool,
+        min_full_gcs: usize,
+        top_n: usize,
+        last_report: Arc<Mutex<Vec<LeakSuspect>>>,
+    ) -> Self {
+        Self {
+            running,
+            min_full_gcs,
+            top_n,
+            full_gcs: 0,
+            full_gcs_at_epoch: vec![0; 1 << u8::BITS],
+            seen: HashSet::new(),
+            current: HashMap::new(),
+            previous: HashMap::new(),
+            last_report,
+        }
+    }
+}
+
+#[cfg(feature = "alloc_site")]
+fn alloc_site_of(object: ObjectReference) -> u16 {
+    crate::util::alloc_site::get_alloc_site(object)
+}
+
+#[cfg(not(feature = "alloc_site"))]
+fn alloc_site_of(_object: ObjectReference) -> u16 {
+    0
+}
+
+impl<VM: VMBinding> RtAnalysis<VM> for LeakReport {
+    fn gc_hook(&mut self, _mmtk: &'static MMTK<VM>) {
+        // A new GC starts.
+        self.seen.clear();
+        self.current.clear();
+    }
+
+    fn trace_hook(&mut self, objects: &[ObjectReference]) {
+        if !self.running {
+            return;
+        }
+
+        for object in objects.iter().copied() {
+            if !self.seen.insert(object) {
+                continue;
+            }
+            let birth = lifetime::birth_epoch(object);
+            // The current GC is counted if the object survives it.
+            let survived = self.full_gcs + 1 - self.full_gcs_at_epoch[birth as usize];
+            if survived < self.min_full_gcs {
+                continue;
+            }
+            let key = (
+                VM::VMObjectModel::get_type_name(object),
+                alloc_site_of(object),
+            );
+            let entry = self.current.entry(key).or_default();
+            entry.0 += 1;
+            entry.1 += VM::VMObjectModel::get_current_size(object);
+        }
+    }
+
+    fn gc_end_hook(&mut self, mmtk: &'static MMTK<VM>) {
+        // The lifetime analysis has advanced the epoch for the objects allocated after this GC.
+        let full_heap = !crate::plan::is_nursery_gc(mmtk.get_plan());
+        if full_heap {
+            self.full_gcs += 1;
+        }
+        self.full_gcs_at_epoch[mmtk.state.object_lifetimes.current_epoch() as usize] =
+            self.full_gcs;
+
+        if !self.running || !full_heap {
+            return;
+        }
+
+        let mut growing = HashMap::new();
+        let mut report = vec![];
+        for ((type_name, alloc_site), (count, bytes)) in self.current.drain() {
+            let (previous_bytes, previous_growing_gcs) = self
+                .previous
+                .get(&(type_name, alloc_site))
+                .copied()
+                .unwrap_or_default();
+            if bytes <= previous_bytes {
+                growing.insert((type_name, alloc_site), (bytes, 0));
+                continue;
+            }
+            growing.insert((type_name, alloc_site), (bytes, previous_growing_gcs + 1));
+            report.push(LeakSuspect {
+                type_name,
+                alloc_site,
+                count,
+                bytes,
+                bytes_growth: bytes - previous_bytes,
+                growing_full_gcs: previous_growing_gcs + 1,
+            });
+        }
+        self.previous = growing;
+
+        report.sort_unstable_by(|a, b| b.bytes.cmp(&a.bytes));
+        report.truncate(self.top_n);
+        for suspect in report.iter() {
+            info!(
+                "Leak suspect {} (allocation site: {}): {} objects, {} bytes, grew {} bytes, growing for {} full GCs",
+                suspect.type_name,
+                suspect.alloc_site,
+                suspect.count,
+                suspect.bytes,
+                suspect.bytes_growth,
+                suspect.growing_full_gcs,
+            );
+        }
+
+        *self.last_report.lock().unwrap() = report;
+    }
+
+    fn set_running(&mut self, running: bool) {
+        self.running = running;
+    }
+}
@@ -0,0 +1,189 @@
+use crate::util::analysis::RtAnalysis;
+use crate::util::metadata::side_metadata::SideMetadataSpec;
+use crate::util::ObjectReference;
+use crate::vm::VMBinding;
+use crate::MMTK;
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
+
+/**
+ * This file implements an analysis routine that records the lifetime of objects, measured in
+ * the number of GCs between the allocation and the death of an object.
+ *
+ * When an object is allocated, we store the current GC epoch (the number of GCs so far, modulo
+ * 256) in the side metadata `OBJ_BIRTH_EPOCH`. When a space sweeps a dead object, it reports the
+ * object with `record_death`, and we compute its age from the epoch stored in the side metadata.
+ * Ages are recorded per size class, using the same size classes as `PerSizeClassObjectCounter`.
+ *
+ * Currently only `LargeObjectSpace`, `MallocSpace` and the native `MarkSweepSpace` report dead
+ * objects, as they visit each dead object during sweeping. `MarkSweepSpace` can only tell dead
+ * objects from free cells with the feature `vo_bit`, and only if cells and objects start at the
+ * same address. With lazy sweeping, a block that is not swept before the next GC has its dead
+ * objects recorded as one GC older. Other spaces (e.g. copying spaces and Immix) reclaim memory
+ * without visiting dead objects, and their objects are not recorded. The epoch wraps around after
+ * 256 GCs, so objects older than 255 GCs are recorded with their age modulo 256.
+ *
+ * The state of the analysis is kept per MMTk instance in `ObjectLifetimes`, which spaces reach
+ * through the global state of the instance.
+ */
+pub struct ObjectLifetimeAnalysis {
+    lifetimes: Arc<ObjectLifetimes>,
+}
+
+/// The side metadata spec for the GC epoch when an object is allocated.
+pub(crate) const OBJ_BIRTH_EPOCH_SPEC: SideMetadataSpec =
+    crate::util::metadata::side_metadata::spec_defs::OBJ_BIRTH_EPOCH;
+
+/// The number of distinct ages that can be recorded.
+const NUM_AGES: usize = 1 << u8::BITS;
+
+/// The lifetime data of an MMTk instance.
+#[derive(Default)]
+pub(crate) struct ObjectLifetimes {
+    /// The current GC epoch. Incremented at the end of each GC.
+    current_epoch: AtomicU8,
+    /// Whether we record dead objects. Spaces report dead objects without going through the
+    /// `AnalysisManager`, so the running state of the routine is kept here.
+    running: AtomicBool,
+    /// The number of dead objects for each age, indexed by size class.
+    deaths: Mutex<HashMap<usize, Vec<usize>>>,
+}
+
+/// The lifetime statistics for the objects in one size class.  Only the objects in the spaces that
+/// sweep dead objects individually are counted.  See
+/// [`crate::memory_manager::object_lifetimes_by_size_class`].
+#[derive(Clone, Debug)]
+pub struct LifetimeStats {
+    /// The size class. It includes objects that are `size_class` bytes or smaller, but larger
+    /// than the previous size class (`size_class / 2`).
+    pub size_class: usize,
+    /// The number of dead objects for each age (in GCs). `deaths_by_age[i]` is the number of
+    /// objects that were allocated `i` GCs before the GC in which they were found dead.
+    pub deaths_by_age: Vec<usize>,
+}
+
+impl LifetimeStats {
+    /// Return the survival curve of the dead objects in this size class. The `i`-th element is the
+    /// fraction of the objects that survived at least `i` GCs.
+    pub fn survival_curve(&self) -> Vec<f64> {
+        let total: usize = self.deaths_by_age.iter().sum();
+        let mut remaining = total;
+        self.deaths_by_age
+            .iter()
+            .map(|deaths| {
+                let survived = remaining as f64 / total as f64;
+                remaining -= deaths;
+                survived
+            })
+            .collect()
+    }
+}
+
+fn size_class(size: usize) -> usize {
+    size.next_power_of_two()
+}
+
+/// Return the GC epoch when an object was allocated.
+pub(crate) fn birth_epoch(object: ObjectReference) -> u8 {
+    OBJ_BIRTH_EPOCH_SPEC.load_atomic::<u8>(object.to_raw_address(), Ordering::Relaxed)
+}
+
+/// Move the GC epoch of an object to its new copy.
+pub(crate) fn on_object_forwarded(from: ObjectReference, to: ObjectReference) {
+    OBJ_BIRTH_EPOCH_SPEC.store_atomic::<u8>(
+        to.to_raw_address(),
+        birth_epoch(from),
+        Ordering::Relaxed,
+    );
+}
+
+impl ObjectLifetimes {
+    /// Record the current GC epoch for a newly allocated object.
+    pub fn record_birth(&self, object: ObjectReference) {
+        OBJ_BIRTH_EPOCH_SPEC.store_atomic::<u8>(
+            object.to_raw_address(),
+            self.current_epoch(),
+            Ordering::Relaxed,
+        );
+    }
+
+    /// Return the current GC epoch. Objects allocated now belong to this epoch.
+    pub fn current_epoch(&self) -> u8 {
+        self.current_epoch.load(Ordering::Relaxed)
+    }
+
+    /// Record the age of a dead object. This should be called by the space when it sweeps a dead
+    /// object, before the memory of the object is released.
+    pub fn record_death(&self, object: ObjectReference, bytes: usize) {
+        self.record_death_in_epoch(object, bytes, self.current_epoch());
+    }
+
+    /// Record the age of a dead object that is swept after the GC that found it dead, e.g. by lazy
+    /// sweeping. The GC has already advanced the epoch, so the object died in the previous epoch.
+    pub fn record_death_after_gc(&self, object: ObjectReference, bytes: usize) {
+        self.record_death_in_epoch(object, bytes, self.current_epoch().wrapping_sub(1));
+    }
+
+    fn record_death_in_epoch(&self, object: ObjectReference, bytes: usize, epoch: u8) {
+        if !self.running.load(Ordering::Relaxed) {
+            return;
+        }
+        let age = epoch.wrapping_sub(birth_epoch(object));
+        let mut deaths = self.deaths.lock().unwrap();
+        deaths
+            .entry(size_class(bytes))
+            .or_insert_with(|| vec![0; NUM_AGES])[age as usize] += 1;
+    }
+
+    /// Return the lifetime statistics for all the size classes that have dead objects, sorted by
+    /// the size class.
+    pub fn lifetime_stats(&self) -> Vec<LifetimeStats> {
+        let deaths = self.deaths.lock().unwrap();
+        let mut stats: Vec<LifetimeStats> = deaths
+            .iter()
+            .map(|(size_class, deaths_by_age)| LifetimeStats {
+                size_class: *size_class,
+                deaths_by_age: deaths_by_age.clone(),
+            })
+            .collect();
+        stats.sort_unstable_by_key(|s| s.size_class);
+        stats
+    }
+}
+
+impl ObjectLifetimeAnalysis {
+    pub fn new(running: bool, lifetimes: Arc<ObjectLifetimes>) -> Self {
+        lifetimes.running.store(running, Ordering::Relaxed);
+        Self { lifetimes }
+    }
+}
+
+impl<VM: VMBinding> RtAnalysis<VM> for ObjectLifetimeAnalysis {
+    fn gc_end_hook(&mut self, _mmtk: &'static MMTK<VM>) {
+        // Objects allocated after this GC belong to the next epoch. We always advance the epoch,
+        // even if the routine is not running, so the ages are still correct when it runs again.
+        self.lifetimes.current_epoch.fetch_add(1, Ordering::Relaxed);
+
+        if !self.lifetimes.running.load(Ordering::Relaxed) {
+            return;
+        }
+
+        for stats in self.lifetimes.lifetime_stats() {
+            let curve = stats.survival_curve();
+            info!(
+                "Lifetime of size{}: {} dead objects, survived 1 GC: {:.3}, 2 GCs: {:.3}, 4 GCs: {:.3}",
+                stats.size_class,
+                stats.deaths_by_age.iter().sum::<usize>(),
+                curve[1],
+                curve[2],
+                curve[4],
+            );
+        }
+    }
+
+    fn set_running(&mut self, running: bool) {
+        self.lifetimes.running.store(running, Ordering::Relaxed);
+    }
+}
@@ -1,14 +1,23 @@
+use crate::global_state::GlobalState;
 use crate::scheduler::*;
+use crate::util::options::Options;
 use crate::util::statistics::stats::Stats;
+use crate::util::ObjectReference;
 use crate::vm::VMBinding;
 use crate::MMTK;
 use std::sync::{Arc, Mutex};
 
+pub mod demographics;
 pub mod gc_count;
+pub mod leak;
+pub mod lifetime;
 pub mod obj_num;
 pub mod obj_size;
 
+use self::demographics::{LiveObjectDemographics, LiveTypeStats};
 use self::gc_count::GcCounter;
+use self::leak::{LeakReport, LeakSuspect};
+use self::lifetime::{LifetimeStats, ObjectLifetimeAnalysis, ObjectLifetimes};
 use self::obj_num::ObjectCounter;
 use self::obj_size::PerSizeClassObjectCounter;
 
@@ -22,9 +31,21 @@ use self::obj_size::PerSizeClassObjectCounter;
 /// other arguments, then they can create an analysis routine specific function and
 /// invoke it in its respective place.
 ///
+/// Bindings can install their own analysis routines with
+/// [`crate::memory_manager::add_analysis_routine`].
+///
 pub trait RtAnalysis<VM: VMBinding> {
+    /// Called in the allocation slow path, every `analysis_factor` (an MMTk option) bytes of allocation.
     fn alloc_hook(&mut self, _size: usize, _align: usize, _offset: usize) {}
+    /// Called at the start of each GC.
     fn gc_hook(&mut self, _mmtk: &'static MMTK<VM>) {}
+    /// Called for every object scanned during tracing, in batches.  The objects may be passed to
+    /// the routine some time after they are scanned, but always before the GC moves them again
+    /// and before `gc_end_hook`.
+    fn trace_hook(&mut self, _objects: &[ObjectReference]) {}
+    /// Called at the end of each GC, before mutators are resumed.
+    fn gc_end_hook(&mut self, _mmtk: &'static MMTK<VM>) {}
+    /// Enable or disable the routine.
     fn set_running(&mut self, running: bool);
 }
 
@@ -42,20 +63,30 @@ impl<VM: VMBinding> GCWork<VM> for GcHookWork {
 #[derive(Default)]
 pub struct AnalysisManager<VM: VMBinding> {
     routines: Mutex<Vec<Arc<Mutex<dyn RtAnalysis<VM> + Send>>>>,
+    /// The live object histogram of the last GC, updated by `LiveObjectDemographics`.
+    live_object_demographics: Arc<Mutex<Vec<LiveTypeStats>>>,
+    /// The leak suspects found in the last full-heap GC, updated by `LeakReport`.
+    leak_suspects: Arc<Mutex<Vec<LeakSuspect>>>,
+    /// The lifetime data of objects, updated by `ObjectLifetimeAnalysis` and the spaces.  This is
+    /// shared with the global state, through which the spaces and the allocators reach it.
+    object_lifetimes: Arc<ObjectLifetimes>,
 }
 
 impl<VM: VMBinding> AnalysisManager<VM> {
-    pub fn new(stats: Arc<Stats>) -> Self {
-        let mut manager = AnalysisManager {
+    pub fn new(stats: Arc<Stats>, options: &Options, state: &GlobalState) -> Self {
+        let manager = AnalysisManager {
             routines: Mutex::new(vec![]),
+            live_object_demographics: Arc::new(Mutex::new(vec![])),
+            leak_suspects: Arc::new(Mutex::new(vec![])),
+            object_lifetimes: state.object_lifetimes.clone(),
         };
-        manager.initialize_routines(stats);
+        manager.initialize_routines(stats, options);
         manager
     }
 
     // Initializing all routines. If you want to add a new routine, here is the place
     // to do so
-    fn initialize_routines(&mut self, stats: Arc<Stats>) {
+    fn initialize_routines(&self, stats: Arc<Stats>, options: &Options) {
         let ctr = stats.new_event_counter("obj.num", true, true);
         let gc_ctr = stats.new_event_counter("gc.num", true, true);
         let obj_num = Arc::new(Mutex::new(ObjectCounter::new(true, ctr)));
@@ -64,13 +95,39 @@ impl<VM: VMBinding> AnalysisManager<VM> {
         self.add_analysis_routine(obj_num);
         self.add_analysis_routine(gc_count);
         self.add_analysis_routine(obj_size);
+        let demographics = Arc::new(Mutex::new(LiveObjectDemographics::new(
+            true,
+            *options.live_demographics_top_n,
+            self.live_object_demographics.clone(),
+        )));
+        self.add_analysis_routine(demographics);
+        let lifetime = Arc::new(Mutex::new(ObjectLifetimeAnalysis::new(
+            true,
+            self.object_lifetimes.clone(),
+        )));
+        self.add_analysis_routine(lifetime);
+        // This must be added after the lifetime analysis, which advances the GC epoch at the end of a GC.
+        let leak = Arc::new(Mutex::new(LeakReport::new(
+            true,
+            *options.leak_report_min_full_gcs,
+            *options.live_demographics_top_n,
+            self.leak_suspects.clone(),
+        )));
+        self.add_analysis_routine(leak);
     }
 
-    pub fn add_analysis_routine(&mut self, routine: Arc<Mutex<dyn RtAnalysis<VM> + Send>>) {
+    pub fn add_analysis_routine(&self, routine: Arc<Mutex<dyn RtAnalysis<VM> + Send>>) {
         let mut routines = self.routines.lock().unwrap();
         routines.push(routine.clone());
     }
 
+    pub fn set_running(&self, running: bool) {
+        let routines = self.routines.lock().unwrap();
+        for r in &*routines {
+            r.lock().unwrap().set_running(running);
+        }
+    }
+
     pub fn alloc_hook(&self, size: usize, align: usize, offset: usize) {
         let routines = self.routines.lock().unwrap();
         for r in &*routines {
@@ -84,4 +141,50 @@ impl<VM: VMBinding> AnalysisManager<VM> {
             r.lock().unwrap().gc_hook(mmtk);
         }
     }
+
+    /// The number of scanned objects a GC worker buffers before passing them to the routines.
+    const TRACE_BUFFER_SIZE: usize = 4096;
+
+    /// Called by a GC worker for the objects it has scanned.  The objects are buffered in
+    /// `buffer`, the analysis buffer of the worker, and passed to the routines in large batches so
+    /// that the workers do not lock the routines for every work packet.
+    pub fn trace_hook(&self, buffer: &mut Vec<ObjectReference>, objects: &[ObjectReference]) {
+        buffer.extend_from_slice(objects);
+        if buffer.len() >= Self::TRACE_BUFFER_SIZE {
+            self.flush_trace_buffer(buffer);
+        }
+    }
+
+    /// Pass the objects in the analysis buffer of a worker to the routines.  The scheduler calls
+    /// this for every worker whenever all the workers are parked, before opening more work
+    /// buckets, so the objects are not moved while they are in the buffers.
+    pub fn flush_trace_buffer(&self, buffer: &mut Vec<ObjectReference>) {
+        if buffer.is_empty() {
+            return;
+        }
+        let routines = self.routines.lock().unwrap();
+        for r in &*routines {
+            r.lock().unwrap().trace_hook(buffer);
+        }
+        buffer.clear();
+    }
+
+    pub fn gc_end_hook(&self, mmtk: &'static MMTK<VM>) {
+        let routines = self.routines.lock().unwrap();
+        for r in &*routines {
+            r.lock().unwrap().gc_end_hook(mmtk);
+        }
+    }
+
+    pub fn live_object_demographics(&self) -> Vec<LiveTypeStats> {
+        self.live_object_demographics.lock().unwrap().clone()
+    }
+
+    pub fn leak_suspects(&self) -> Vec<LeakSuspect> {
+        self.leak_suspects.lock().unwrap().clone()
+    }
+
+    pub fn object_lifetimes(&self) -> Vec<LifetimeStats> {
+        self.object_lifetimes.lifetime_stats()
+    }
 }
@@ -0,0 +1,153 @@
+//! Accounting of the bytes copied by GC workers in each GC.
+//!
+//! Each `GCWorkerCopyContext` counts the bytes it copies for each `CopySemantics` without
+//! synchronization, and flushes the counts to the global `CopyAccounting` when the copy context
+//! is released at the end of each GC.
+
+use super::{CopyPartition, CopySemantics, CustomCopySemantics};
+use crate::util::statistics::counter::EventCounter;
+use crate::util::statistics::stats::Stats;
+use enum_map::EnumMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// The bytes copied in a GC, and the survival rate of the nursery.  See
+/// [`crate::MMTK::last_gc_copy_stats`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CopyStats {
+    /// The bytes of objects copied from the nursery to the mature space
+    /// (`CopySemantics::PromoteToMature`).
+    pub promoted_bytes: usize,
+    /// The bytes of objects copied within the mature space (`CopySemantics::Mature`).
+    pub mature_copied_bytes: usize,
+    /// The bytes of objects copied within the nursery (`CopySemantics::Nursery`).
+    pub nursery_copied_bytes: usize,
+    /// The bytes of objects copied by non-generational plans (`CopySemantics::DefaultCopy` and
+    /// `CopySemantics::DefaultCopyPartition`).
+    pub default_copied_bytes: usize,
+    /// The bytes of objects copied with custom copy semantics (`CopySemantics::Custom`).
+    pub custom_copied_bytes: usize,
+    /// The bytes allocated in the nursery when the GC started.  This is `None` if the plan does not
+    /// have a copying nursery.  Large objects are not included, as they are never copied.
+    pub nursery_bytes: Option<usize>,
+    /// The fraction of the nursery (in bytes) that survived the GC and were promoted.  This is
+    /// `None` if `nursery_bytes` is `None` or zero.
+    pub nursery_survival_rate: Option<f64>,
+}
+
+/// Collects the bytes copied by all GC workers in the current GC.
+pub(crate) struct CopyAccounting {
+    /// The bytes copied with each copy semantics in the current GC.
+    copied_bytes: EnumMap<CopySemantics, AtomicUsize>,
+    /// The bytes in the nursery when the current GC started.
+    nursery_bytes: Mutex<Option<usize>>,
+    /// The statistics of the last finished GC.
+    last_gc: Mutex<CopyStats>,
+    /// Counters for the harness statistics, in bytes.
+    promoted_counter: Arc<Mutex<EventCounter>>,
+    mature_copied_counter: Arc<Mutex<EventCounter>>,
+}
+
+impl CopyAccounting {
+    pub fn new(stats: &Stats) -> Self {
+        Self {
+            copied_bytes: EnumMap::default(),
+            nursery_bytes: Mutex::new(None),
+            last_gc: Mutex::new(CopyStats::default()),
+            promoted_counter: stats.new_event_counter("copy.promoted", true, true),
+            mature_copied_counter: stats.new_event_counter("copy.mature", true, true),
+        }
+    }
+
+    /// Called before the plan prepares for the GC, with the bytes in the copying nursery, if any.
+    pub fn on_gc_prepare(&self, nursery_bytes: Option<usize>) {
+        *self.nursery_bytes.lock().unwrap() = nursery_bytes;
+    }
+
+    /// Add the bytes copied by a GC worker.
+    pub fn add_copied_bytes(&self, copied_bytes: &EnumMap<CopySemantics, usize>) {
+        for (semantics, bytes) in copied_bytes.iter() {
+            if *bytes != 0 {
+                self.copied_bytes[semantics].fetch_add(*bytes, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Called when all GC work is finished.  Compute the statistics of the GC.
+    pub fn on_gc_end(&self) -> CopyStats {
+        let take =
+            |semantics: CopySemantics| self.copied_bytes[semantics].swap(0, Ordering::Relaxed);
+        let promoted_bytes = take(CopySemantics::PromoteToMature);
+        let nursery_bytes = self.nursery_bytes.lock().unwrap().take();
+        let stats = CopyStats {
+            promoted_bytes,
+            mature_copied_bytes: take(CopySemantics::Mature),
+            nursery_copied_bytes: take(CopySemantics::Nursery),
+            default_copied_bytes: take(CopySemantics::DefaultCopy)
+                + CopyPartition::iter()
+                    .map(|partition| take(CopySemantics::DefaultCopyPartition(partition)))
+                    .sum::<usize>(),
+            custom_copied_bytes: CustomCopySemantics::iter()
+                .map(|custom| take(CopySemantics::Custom(custom)))
+                .sum(),
+            nursery_bytes,
+            nursery_survival_rate: nursery_bytes
+                .filter(|bytes| *bytes != 0)
+                .map(|bytes| promoted_bytes as f64 / bytes as f64),
+        };
+
+        self.promoted_counter
+            .lock()
+            .unwrap()
+            .inc_by(stats.promoted_bytes as u64);
+        self.mature_copied_counter
+            .lock()
+            .unwrap()
+            .inc_by(stats.mature_copied_bytes as u64);
+
+        *self.last_gc.lock().unwrap() = stats;
+        stats
+    }
+
+    /// Get the statistics of the last finished GC.
+    pub fn last_gc(&self) -> CopyStats {
+        *self.last_gc.lock().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::options::Options;
+
+    #[test]
+    fn compute_survival_rate() {
+        let accounting = CopyAccounting::new(&Stats::new(&Options::default()));
+        assert_eq!(accounting.last_gc(), CopyStats::default());
+
+        accounting.on_gc_prepare(Some(4096));
+        let mut worker1 = EnumMap::default();
+        worker1[CopySemantics::PromoteToMature] = 512;
+        worker1[CopySemantics::Mature] = 100;
+        let mut worker2 = EnumMap::default();
+        worker2[CopySemantics::PromoteToMature] = 512;
+        worker2[CopySemantics::Custom(CustomCopySemantics::C1)] = 64;
+        accounting.add_copied_bytes(&worker1);
+        accounting.add_copied_bytes(&worker2);
+
+        let stats = accounting.on_gc_end();
+        assert_eq!(stats.promoted_bytes, 1024);
+        assert_eq!(stats.mature_copied_bytes, 100);
+        assert_eq!(stats.default_copied_bytes, 0);
+        assert_eq!(stats.custom_copied_bytes, 64);
+        assert_eq!(stats.nursery_bytes, Some(4096));
+        assert_eq!(stats.nursery_survival_rate, Some(0.25));
+        assert_eq!(accounting.last_gc(), stats);
+
+        // Counts are reset for the next GC.
+        accounting.on_gc_prepare(None);
+        let stats = accounting.on_gc_end();
+        assert_eq!(stats.promoted_bytes, 0);
+        assert_eq!(stats.nursery_survival_rate, None);
+    }
+}
@@ -7,6 +7,7 @@ use crate::policy::copyspace::CopySpace;
 use crate::policy::copyspace::CopySpaceCopyContext;
 use crate::policy::immix::ImmixSpace;
 use crate::policy::immix::{ImmixCopyContext, ImmixHybridCopyContext};
+use crate::policy::marksweepspace::native_ms::{MarkSweepCopyContext, MarkSweepSpace};
 use crate::policy::space::Space;
 use crate::util::object_forwarding;
 use crate::util::opaque_pointer::VMWorkerThread;
@@ -21,9 +22,20 @@ use enum_map::EnumMap;
 
 use super::alloc::allocator::AllocatorContext;
 
-const MAX_COPYSPACE_COPY_ALLOCATORS: usize = 1;
-const MAX_IMMIX_COPY_ALLOCATORS: usize = 1;
-const MAX_IMMIX_HYBRID_COPY_ALLOCATORS: usize = 1;
+mod accounting;
+pub(crate) use accounting::CopyAccounting;
+pub use accounting::CopyStats;
+mod relocation;
+pub use relocation::{relocate, relocate_object, Relocation};
+pub(crate) use relocation::{RelocationLog, WorkerRelocations};
+
+// The maximum number of copy allocators of each policy in a copy context.  A plan may use more
+// than one copy allocator for the same policy, such as separate allocators for nursery and mature
+// copying, or one allocator for each `CopyPartition`.
+pub(crate) const MAX_COPYSPACE_COPY_ALLOCATORS: usize = CopyPartition::LENGTH;
+pub(crate) const MAX_IMMIX_COPY_ALLOCATORS: usize = CopyPartition::LENGTH;
+pub(crate) const MAX_IMMIX_HYBRID_COPY_ALLOCATORS: usize = CopyPartition::LENGTH;
+pub(crate) const MAX_MARK_SWEEP_COPY_ALLOCATORS: usize = CopyPartition::LENGTH;
 
 type CopySpaceMapping<VM> = Vec<(CopySelector, &'static dyn Space<VM>)>;
 
@@ -31,13 +43,18 @@ type CopySpaceMapping<VM> = Vec<(CopySelector, &'static dyn Space<VM>)>;
 /// Similar to a `MutatorConfig`,
 /// We expect each copying plan to provide a CopyConfig.
 pub struct CopyConfig<VM: VMBinding> {
-    /// Mapping CopySemantics to the actual copying allocators (CopySelector)
+    /// Mapping CopySemantics to the actual copying allocators (CopySelector).  Different copy
+    /// semantics may share one copying allocator, or use different allocators of the same policy.
     pub(crate) copy_mapping: EnumMap<CopySemantics, CopySelector>,
-    /// Mapping copying allocators with space
+    /// Mapping copying allocators with space.  Each copying allocator used in `copy_mapping`
+    /// should appear exactly once.
     pub(crate) space_mapping: CopySpaceMapping<VM>,
     /// A reference to the plan constraints.
     /// GCWorkerCopyContext may have plan-specific behaviors dependson the plan constraints.
     pub(crate) constraints: &'static PlanConstraints,
+    /// The custom copy semantics registered by the plan.  A plan that uses
+    /// `CopySemantics::Custom` should register it here and map it in `copy_mapping`.
+    pub(crate) custom_semantics: EnumMap<CustomCopySemantics, Option<CustomCopySemanticsSpec>>,
 }
 
 impl<VM: VMBinding> Default for CopyConfig<VM> {
@@ -46,6 +63,19 @@ impl<VM: VMBinding> Default for CopyConfig<VM> {
             copy_mapping: EnumMap::default(),
             space_mapping: vec![],
             constraints: &crate::plan::DEFAULT_PLAN_CONSTRAINTS,
+            custom_semantics: EnumMap::default(),
+        }
+    }
+}
+
+impl<VM: VMBinding> CopyConfig<VM> {
+    /// Are objects copied with `semantics` copied to a mature space?
+    fn is_mature(&self, semantics: CopySemantics) -> bool {
+        match semantics {
+            CopySemantics::Custom(custom) => {
+                self.custom_semantics[custom].is_some_and(|spec| spec.is_mature)
+            }
+            _ => semantics.is_mature(),
         }
     }
 }
@@ -59,8 +89,15 @@ pub struct GCWorkerCopyContext<VM: VMBinding> {
     pub immix: [MaybeUninit<ImmixCopyContext<VM>>; MAX_IMMIX_COPY_ALLOCATORS],
     /// Copy allocators for ImmixSpace
     pub immix_hybrid: [MaybeUninit<ImmixHybridCopyContext<VM>>; MAX_IMMIX_HYBRID_COPY_ALLOCATORS],
+    /// Copy allocators for the native MarkSweepSpace
+    pub ms: [MaybeUninit<MarkSweepCopyContext<VM>>; MAX_MARK_SWEEP_COPY_ALLOCATORS],
     /// The config for the plan
     config: CopyConfig<VM>,
+    /// The bytes copied with each copy semantics since the copy context was last released.
+    copied_bytes: EnumMap<CopySemantics, usize>,
+    /// The objects copied since the copy context was last released, if the option
+    /// `record_relocations` is set.
+    relocations: Option<WorkerRelocations>,
 }
 
 impl<VM: VMBinding> GCWorkerCopyContext<VM> {
@@ -99,7 +136,9 @@ impl<VM: VMBinding> GCWorkerCopyContext<VM> {
                 unsafe { self.immix_hybrid[index as usize].assume_init_mut() }
                     .alloc_copy(original, bytes, align, offset)
             }
-            CopySelector::Unused => unreachable!(),
+            CopySelector::MarkSweep(index) => unsafe { self.ms[index as usize].assume_init_mut() }
+                .alloc_copy(original, bytes, align, offset),
+            CopySelector::Unused => panic!("{:?} is not mapped to a copy allocator", semantics),
         }
     }
 
@@ -110,8 +149,11 @@ impl<VM: VMBinding> GCWorkerCopyContext<VM> {
     /// * `bytes`: The size of the object in bytes.
     /// * `semantics`: The copy semantic used for the copying.
     pub fn post_copy(&mut self, object: ObjectReference, bytes: usize, semantics: CopySemantics) {
-        if VM::VMObjectModel::LOCAL_FORWARDING_BITS_SPEC.is_in_header() {
-            // Clear forwarding bits if the forwarding bits are in the header.
+        if cfg!(feature = "vm_forwarding")
+            || VM::VMObjectModel::LOCAL_FORWARDING_BITS_SPEC.is_in_header()
+        {
+            // Clear forwarding bits if the forwarding bits are in the header (including the
+            // forwarding state kept by the binding).
             object_forwarding::clear_forwarding_bits::<VM>(object);
         } else {
             // We ensure no stale side forwarding bits exist before tracing.
@@ -120,11 +162,12 @@ impl<VM: VMBinding> GCWorkerCopyContext<VM> {
             ));
         }
         // If we are copying objects in mature space, we would need to mark the object as mature.
-        if semantics.is_mature() && self.config.constraints.needs_log_bit {
+        if self.config.is_mature(semantics) && self.config.constraints.needs_log_bit {
             // If the plan uses unlogged bit, we set the unlogged bit (the object is unlogged/mature)
             VM::VMObjectModel::GLOBAL_LOG_BIT_SPEC
                 .mark_byte_as_unlogged::<VM>(object, Ordering::Relaxed);
         }
+        self.copied_bytes[semantics] += bytes;
         // Policy specific post copy.
         match self.config.copy_mapping[semantics] {
             CopySelector::CopySpace(index) => {
@@ -137,6 +180,9 @@ impl<VM: VMBinding> GCWorkerCopyContext<VM> {
                 unsafe { self.immix_hybrid[index as usize].assume_init_mut() }
                     .post_copy(object, bytes)
             }
+            CopySelector::MarkSweep(index) => {
+                unsafe { self.ms[index as usize].assume_init_mut() }.post_copy(object, bytes)
+            }
             CopySelector::Unused => unreachable!(),
         }
     }
@@ -144,7 +190,7 @@ impl<VM: VMBinding> GCWorkerCopyContext<VM> {
     /// Prepare the copying allocators.
     pub fn prepare(&mut self) {
         // Delegate to prepare() for each policy copy context
-        for (_, selector) in self.config.copy_mapping.iter() {
+        for (selector, _) in self.config.space_mapping.iter() {
             match selector {
                 CopySelector::CopySpace(index) => {
                     unsafe { self.copy[*index as usize].assume_init_mut() }.prepare()
@@ -155,6 +201,9 @@ impl<VM: VMBinding> GCWorkerCopyContext<VM> {
                 CopySelector::ImmixHybrid(index) => {
                     unsafe { self.immix_hybrid[*index as usize].assume_init_mut() }.prepare()
                 }
+                CopySelector::MarkSweep(index) => {
+                    unsafe { self.ms[*index as usize].assume_init_mut() }.prepare()
+                }
                 CopySelector::Unused => {}
             }
         }
@@ -163,7 +212,7 @@ impl<VM: VMBinding> GCWorkerCopyContext<VM> {
     /// Release the copying allocators.
     pub fn release(&mut self) {
         // Delegate to release() for each policy copy context
-        for (_, selector) in self.config.copy_mapping.iter() {
+        for (selector, _) in self.config.space_mapping.iter() {
             match selector {
                 CopySelector::CopySpace(index) => {
                     unsafe { self.copy[*index as usize].assume_init_mut() }.release()
@@ -174,11 +223,44 @@ impl<VM: VMBinding> GCWorkerCopyContext<VM> {
                 CopySelector::ImmixHybrid(index) => {
                     unsafe { self.immix_hybrid[*index as usize].assume_init_mut() }.release()
                 }
+                CopySelector::MarkSweep(index) => {
+                    unsafe { self.ms[*index as usize].assume_init_mut() }.release()
+                }
                 CopySelector::Unused => {}
             }
         }
     }
 
+    /// Get the specification of a custom copy semantics registered by the plan, or `None` if the
+    /// plan does not use it.  A binding may use it to tell custom copy semantics apart in
+    /// [`crate::vm::ObjectModel::copy`].
+    pub fn custom_semantics(&self, custom: CustomCopySemantics) -> Option<CustomCopySemanticsSpec> {
+        self.config.custom_semantics[custom]
+    }
+
+    /// Get the bytes copied with each copy semantics since the last call, and reset the counts.
+    pub(crate) fn take_copied_bytes(&mut self) -> EnumMap<CopySemantics, usize> {
+        std::mem::take(&mut self.copied_bytes)
+    }
+
+    /// Record that `object` is copied to `new_object`, if the option `record_relocations` is set.
+    pub(crate) fn record_relocation(
+        &mut self,
+        object: ObjectReference,
+        new_object: ObjectReference,
+    ) {
+        if let Some(relocations) = self.relocations.as_mut() {
+            relocations.record::<VM>(object, new_object);
+        }
+    }
+
+    /// Get the relocations recorded since the last call.
+    pub(crate) fn take_relocations(&mut self) -> Vec<Relocation> {
+        self.relocations
+            .as_mut()
+            .map_or(vec![], |relocations| relocations.take())
+    }
+
     /// Create a GCWorkerCopyContext based on the configuration for a copying plan.
     ///
     /// Arguments:
@@ -190,12 +272,28 @@ impl<VM: VMBinding> GCWorkerCopyContext<VM> {
             copy: unsafe { MaybeUninit::uninit().assume_init() },
             immix: unsafe { MaybeUninit::uninit().assume_init() },
             immix_hybrid: unsafe { MaybeUninit::uninit().assume_init() },
+            ms: unsafe { MaybeUninit::uninit().assume_init() },
             config,
+            copied_bytes: EnumMap::default(),
+            relocations: (*mmtk.get_options().record_relocations).then(WorkerRelocations::default),
         };
         let context = Arc::new(AllocatorContext::new(mmtk));
 
         // Initiate the copy context for each policy based on the space mapping.
         for &(selector, space) in ret.config.space_mapping.iter() {
+            let (index, max) = match selector {
+                CopySelector::CopySpace(index) => (index, MAX_COPYSPACE_COPY_ALLOCATORS),
+                CopySelector::Immix(index) => (index, MAX_IMMIX_COPY_ALLOCATORS),
+                CopySelector::ImmixHybrid(index) => (index, MAX_IMMIX_HYBRID_COPY_ALLOCATORS),
+                CopySelector::MarkSweep(index) => (index, MAX_MARK_SWEEP_COPY_ALLOCATORS),
+                CopySelector::Unused => unreachable!(),
+            };
+            assert!(
+                (index as usize) < max,
+                "{:?} exceeds the maximum number of copy allocators of the policy ({})",
+                selector,
+                max
+            );
             match selector {
                 CopySelector::CopySpace(index) => {
                     ret.copy[index as usize].write(CopySpaceCopyContext::new(
@@ -218,6 +316,12 @@ impl<VM: VMBinding> GCWorkerCopyContext<VM> {
                         space.downcast_ref::<ImmixSpace<VM>>().unwrap(),
                     ));
                 }
+                CopySelector::MarkSweep(index) => {
+                    ret.ms[index as usize].write(MarkSweepCopyContext::new(
+                        worker_tls,
+                        space.downcast_ref::<MarkSweepSpace<VM>>().unwrap(),
+                    ));
+                }
                 CopySelector::Unused => unreachable!(),
             }
         }
@@ -231,7 +335,10 @@ impl<VM: VMBinding> GCWorkerCopyContext<VM> {
             copy: unsafe { MaybeUninit::uninit().assume_init() },
             immix: unsafe { MaybeUninit::uninit().assume_init() },
             immix_hybrid: unsafe { MaybeUninit::uninit().assume_init() },
+            ms: unsafe { MaybeUninit::uninit().assume_init() },
             config: CopyConfig::default(),
+            copied_bytes: EnumMap::default(),
+            relocations: None,
         }
     }
 }
@@ -251,6 +358,15 @@ pub enum CopySemantics {
     PromoteToMature,
     /// Copy in mature generation.
     Mature,
+    /// The default copy behavior, but with the copy allocator of the given partition.  A plan may
+    /// map the partitions to different copy allocators of the same space, for example, to copy
+    /// objects to memory close to the NUMA node of the object.  A plan that does not partition
+    /// its copy allocators maps them to the same allocator as `DefaultCopy`.
+    DefaultCopyPartition(CopyPartition),
+    /// A copy semantics defined by a custom plan, such as promoting an object to a code space,
+    /// or compacting a region.  The plan registers it in its `CopyConfig` with a
+    /// [`CustomCopySemanticsSpec`], so that it does not need to overload `DefaultCopy`.
+    Custom(CustomCopySemantics),
 }
 
 impl CopySemantics {
@@ -260,12 +376,79 @@ impl CopySemantics {
     }
 }
 
+/// A partition of the copy allocators for the same copy semantics.  See
+/// [`CopySemantics::DefaultCopyPartition`].
+#[derive(Clone, Copy, Enum, Debug, PartialEq, Eq)]
+pub enum CopyPartition {
+    /// The first partition.
+    P0,
+    /// The second partition.
+    P1,
+    /// The third partition.
+    P2,
+    /// The fourth partition.
+    P3,
+}
+
+impl CopyPartition {
+    /// The number of partitions.
+    pub const LENGTH: usize = <Self as Enum>::LENGTH;
+
+    /// Get the partition with the given index, such as the index of a NUMA node.  The index wraps
+    /// around if there are more than [`CopyPartition::LENGTH`] of them.
+    pub fn from_index(index: usize) -> Self {
+        <Self as Enum>::from_usize(index % Self::LENGTH)
+    }
+
+    /// Get the index of the partition.
+    pub fn index(&self) -> usize {
+        <Self as Enum>::into_usize(*self)
+    }
+
+    /// Iterate over all the partitions.
+    pub fn iter() -> impl Iterator<Item = Self> {
+        (0..Self::LENGTH).map(Self::from_index)
+    }
+}
+
+/// The identifier of a custom copy semantics.  See [`CopySemantics::Custom`].
+#[derive(Clone, Copy, Enum, Debug, PartialEq, Eq)]
+pub enum CustomCopySemantics {
+    /// The first custom copy semantics.
+    C0,
+    /// The second custom copy semantics.
+    C1,
+    /// The third custom copy semantics.
+    C2,
+    /// The fourth custom copy semantics.
+    C3,
+}
+
+impl CustomCopySemantics {
+    /// Iterate over all the custom copy semantics.
+    pub fn iter() -> impl Iterator<Item = Self> {
+        (0..<Self as Enum>::LENGTH).map(<Self as Enum>::from_usize)
+    }
+}
+
+/// The specification of a custom copy semantics registered by a plan.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CustomCopySemanticsSpec {
+    /// The name of the copy semantics, such as `"PromoteToCodeSpace"`.
+    pub name: &'static str,
+    /// Whether objects are copied to a mature space with this copy semantics.  If so, the copied
+    /// objects are marked as unlogged for plans that use the log bit, as with
+    /// `CopySemantics::PromoteToMature`.
+    pub is_mature: bool,
+}
+
 #[repr(C, u8)]
 #[derive(Copy, Clone, Debug, Default)]
 pub(crate) enum CopySelector {
     CopySpace(u8),
     Immix(u8),
     ImmixHybrid(u8),
+    MarkSweep(u8),
     #[default]
     Unused,
 }
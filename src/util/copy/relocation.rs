@@ -0,0 +1,196 @@
+//! The relocation log of moving GCs.
+//!
+//! If the option `record_relocations` is set, each `GCWorkerCopyContext` records the objects it
+//! copies, and flushes the records to the global `RelocationLog` when the copy context is released
+//! at the end of each GC.  When the GC finishes, the records are sorted and coalesced into ranges,
+//! which the binding can take with [`crate::memory_manager::take_relocations`] to fix external
+//! data structures in bulk.  The log also remembers where the objects moved by the last GC are,
+//! for [`crate::memory_manager::object_last_moved_epoch`].
+
+use crate::global_state::GcEpoch;
+use crate::util::{Address, ObjectReference};
+use crate::vm::{ObjectModel, VMBinding};
+use std::sync::Mutex;
+
+/// A range of memory moved by a GC.  An address `a` in `from..from + bytes` before the GC is at
+/// `to + (a - from)` after the GC.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Relocation {
+    /// The start of the range before the GC.
+    pub from: Address,
+    /// The start of the range after the GC.
+    pub to: Address,
+    /// The size of the range in bytes.
+    pub bytes: usize,
+}
+
+impl Relocation {
+    /// Try to extend this range with `next`.  Return `false` if they are not adjacent on both
+    /// sides.
+    fn try_extend(&mut self, next: &Relocation) -> bool {
+        if self.from + self.bytes == next.from && self.to + self.bytes == next.to {
+            self.bytes += next.bytes;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Find the new location of `address` in relocations sorted by `from`, as returned by
+/// [`crate::memory_manager::take_relocations`].  Return `None` if `address` is not moved.
+pub fn relocate(relocations: &[Relocation], address: Address) -> Option<Address> {
+    // The index of the first range that starts after `address`.
+    let index = relocations.partition_point(|r| r.from <= address);
+    let relocation = relocations[..index].last()?;
+    (address < relocation.from + relocation.bytes)
+        .then(|| relocation.to + (address - relocation.from))
+}
+
+/// Find the new location of `object` in relocations sorted by `from`.  Return `None` if `object`
+/// is not moved.
+pub fn relocate_object(
+    relocations: &[Relocation],
+    object: ObjectReference,
+) -> Option<ObjectReference> {
+    relocate(relocations, object.to_raw_address())
+        .map(|address| unsafe { ObjectReference::from_raw_address_unchecked(address) })
+}
+
+/// The relocations recorded by a GC worker.
+#[derive(Default)]
+pub(crate) struct WorkerRelocations {
+    relocations: Vec<Relocation>,
+}
+
+impl WorkerRelocations {
+    /// Record that `object` is copied to `new_object`.  This must be called before the forwarding
+    /// pointer is written to `object`, so that the size of `object` can be read.
+    pub fn record<VM: VMBinding>(&mut self, object: ObjectReference, new_object: ObjectReference) {
+        let next = Relocation {
+            from: VM::VMObjectModel::ref_to_object_start(object),
+            to: VM::VMObjectModel::ref_to_object_start(new_object),
+            bytes: VM::VMObjectModel::get_current_size(object),
+        };
+        // A worker often copies adjacent objects one after another.
+        if let Some(last) = self.relocations.last_mut() {
+            if last.try_extend(&next) {
+                return;
+            }
+        }
+        self.relocations.push(next);
+    }
+
+    pub fn take(&mut self) -> Vec<Relocation> {
+        std::mem::take(&mut self.relocations)
+    }
+}
+
+/// Collects the relocations recorded by all GC workers in the current GC.
+#[derive(Default)]
+pub(crate) struct RelocationLog {
+    /// The relocations flushed by the workers in the current GC.
+    current_gc: Mutex<Vec<Relocation>>,
+    /// The relocations of the last finished GC that have not been taken by the binding.
+    last_gc: Mutex<Vec<Relocation>>,
+    /// The relocations of the last finished GC sorted by `to`, and the epoch at the end of that GC.
+    /// They are kept until the next GC finishes.
+    last_gc_destinations: Mutex<(Vec<Relocation>, GcEpoch)>,
+}
+
+impl RelocationLog {
+    /// Add the relocations recorded by a GC worker.
+    pub fn add(&self, relocations: Vec<Relocation>) {
+        if !relocations.is_empty() {
+            self.current_gc.lock().unwrap().extend(relocations);
+        }
+    }
+
+    /// Called when all GC work is finished.  Sort and coalesce the relocations of the GC.  They
+    /// replace the relocations of the previous GC.  `epoch` is the GC epoch after this GC.
+    pub fn on_gc_end(&self, epoch: GcEpoch) {
+        let mut relocations = std::mem::take(&mut *self.current_gc.lock().unwrap());
+        relocations.sort_unstable_by_key(|r| r.from);
+        let mut coalesced: Vec<Relocation> = Vec::with_capacity(relocations.len());
+        for next in relocations {
+            match coalesced.last_mut() {
+                Some(last) if last.try_extend(&next) => {}
+                _ => coalesced.push(next),
+            }
+        }
+        let mut destinations = coalesced.clone();
+        destinations.sort_unstable_by_key(|r| r.to);
+        *self.last_gc_destinations.lock().unwrap() = (destinations, epoch);
+        *self.last_gc.lock().unwrap() = coalesced;
+    }
+
+    /// Return the epoch of the last GC if `object` was moved to its current address by the last
+    /// GC.
+    pub fn last_moved_epoch(&self, object: ObjectReference) -> Option<GcEpoch> {
+        let address = object.to_raw_address();
+        let guard = self.last_gc_destinations.lock().unwrap();
+        let (destinations, epoch) = &*guard;
+        let index = destinations.partition_point(|r| r.to <= address);
+        let relocation = destinations[..index].last()?;
+        (address < relocation.to + relocation.bytes).then_some(*epoch)
+    }
+
+    /// Take the relocations of the last GC.
+    pub fn take(&self) -> Vec<Relocation> {
+        std::mem::take(&mut *self.last_gc.lock().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn relocation(from: usize, to: usize, bytes: usize) -> Relocation {
+        unsafe {
+            Relocation {
+                from: Address::from_usize(from),
+                to: Address::from_usize(to),
+                bytes,
+            }
+        }
+    }
+
+    #[test]
+    fn coalesce_and_relocate() {
+        let log = RelocationLog::default();
+        // Two workers copied adjacent objects, and an object elsewhere.
+        log.add(vec![
+            relocation(0x1020, 0x9020, 0x10),
+            relocation(0x5000, 0xa000, 0x8),
+        ]);
+        log.add(vec![relocation(0x1000, 0x9000, 0x20)]);
+        let epoch = GcEpoch {
+            nursery: 0,
+            full: 1,
+        };
+        log.on_gc_end(epoch);
+
+        let relocations = log.take();
+        assert_eq!(
+            relocations,
+            vec![
+                relocation(0x1000, 0x9000, 0x30),
+                relocation(0x5000, 0xa000, 0x8)
+            ]
+        );
+        assert!(log.take().is_empty());
+
+        let at = |address: usize| unsafe { Address::from_usize(address) };
+        assert_eq!(relocate(&relocations, at(0x1000)), Some(at(0x9000)));
+        assert_eq!(relocate(&relocations, at(0x1028)), Some(at(0x9028)));
+        assert_eq!(relocate(&relocations, at(0x1030)), None);
+        assert_eq!(relocate(&relocations, at(0x0ff8)), None);
+        assert_eq!(relocate(&relocations, at(0x5004)), Some(at(0xa004)));
+
+        let object =
+            |address: usize| unsafe { ObjectReference::from_raw_address_unchecked(at(address)) };
+        assert_eq!(log.last_moved_epoch(object(0x9028)), Some(epoch));
+        assert_eq!(log.last_moved_epoch(object(0xa000)), Some(epoch));
+        assert_eq!(log.last_moved_epoch(object(0x1000)), None);
+    }
+}
@@ -0,0 +1,195 @@
+//! Deduplicating the values of objects in GC.  See [`crate::vm::Deduplication`].
+//!
+//! Each mutator buffers the candidates it adds, and the buffers are moved to the table when the
+//! mutators are stopped for a GC.  The candidates added since the last GC are processed in the
+//! `VMUnloading` stage, after the liveness of all objects is known.  The canonical values are kept
+//! in a table indexed by the hashes of their contents.  The table does not keep its values alive.
+//! Dead values are removed from the table, and moved values are updated, before the candidates are
+//! processed.  For plans that compute forwarding addresses after liveness (i.e. MarkCompact), the
+//! table is updated again in the `VMRefForwarding` stage.
+
+use crate::plan::Mutator;
+use crate::scheduler::{GCWork, GCWorker};
+use crate::util::statistics::counter::EventCounter;
+use crate::util::statistics::stats::Stats;
+use crate::util::ObjectReference;
+use crate::vm::{Deduplication, VMBinding};
+use crate::MMTK;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::Hasher;
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex, RwLock};
+
+/// What deduplication did in a GC.  See [`crate::MMTK::last_gc_deduplication_stats`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DeduplicationStats {
+    /// The number of candidates processed in the GC, including dead candidates.
+    pub candidates: usize,
+    /// The number of candidates redirected to a canonical value.
+    pub deduplicated: usize,
+    /// The total size of the contents of the values that the candidates no longer refer to.
+    pub bytes: usize,
+}
+
+/// The contents of a value.
+///
+/// # Safety
+/// The contents must not be accessed after the value is moved or reclaimed.
+unsafe fn contents<VM: VMBinding>(
+    deduplication: &dyn Deduplication<VM>,
+    value: ObjectReference,
+) -> &'static [u8] {
+    let (start, bytes) = deduplication.contents(value);
+    std::slice::from_raw_parts(start.to_ptr::<u8>(), bytes)
+}
+
+#[derive(Default)]
+struct DeduplicationInner {
+    /// The candidates added since the last GC.
+    candidates: Vec<ObjectReference>,
+    /// The canonical values, indexed by the hashes of their contents.
+    canonical: HashMap<u64, Vec<ObjectReference>>,
+    /// The statistics of the last finished GC.
+    last_gc: DeduplicationStats,
+}
+
+impl DeduplicationInner {
+    /// Update the moved values in the table.  If `clear_dead` is true, remove the dead values
+    /// first.
+    fn update_canonical(&mut self, clear_dead: bool) {
+        for values in self.canonical.values_mut() {
+            if clear_dead {
+                values.retain(|value| value.is_live());
+            }
+            for value in values.iter_mut() {
+                if let Some(new_value) = value.get_forwarded_object() {
+                    *value = new_value;
+                }
+            }
+        }
+        self.canonical.retain(|_, values| !values.is_empty());
+    }
+}
+
+/// The deduplication state of an MMTk instance.
+pub(crate) struct DeduplicationTable<VM: VMBinding> {
+    deduplication: RwLock<Option<Box<dyn Deduplication<VM>>>>,
+    inner: Mutex<DeduplicationInner>,
+    deduplicated_counter: Arc<Mutex<EventCounter>>,
+    bytes_counter: Arc<Mutex<EventCounter>>,
+}
+
+impl<VM: VMBinding> DeduplicationTable<VM> {
+    pub fn new(stats: &Stats) -> Self {
+        Self {
+            deduplication: RwLock::new(None),
+            inner: Mutex::default(),
+            deduplicated_counter: stats.new_event_counter("dedup.deduplicated", true, true),
+            bytes_counter: stats.new_event_counter("dedup.bytes", true, true),
+        }
+    }
+
+    pub fn register(&self, deduplication: Box<dyn Deduplication<VM>>) {
+        let mut registered = self.deduplication.write().unwrap();
+        assert!(registered.is_none(), "Deduplication is already registered");
+        *registered = Some(deduplication);
+    }
+
+    /// Move the candidates buffered by a mutator to the table.  This is called for each mutator
+    /// when it is stopped for a GC.
+    pub fn flush_candidates(&self, mutator: &mut Mutator<VM>) {
+        if mutator.deduplication_candidates.is_empty() {
+            return;
+        }
+        self.inner
+            .lock()
+            .unwrap()
+            .candidates
+            .append(&mut mutator.deduplication_candidates);
+    }
+
+    /// Process the candidates added since the last GC.  This is done in one work packet, and the
+    /// binding is called for each candidate in turn while the table is locked.
+    fn deduplicate(&self) {
+        let registered = self.deduplication.read().unwrap();
+        let Some(deduplication) = registered.as_deref() else {
+            return;
+        };
+        let mut inner = self.inner.lock().unwrap();
+        inner.update_canonical(true);
+
+        let candidates = std::mem::take(&mut inner.candidates);
+        let mut stats = DeduplicationStats {
+            candidates: candidates.len(),
+            ..Default::default()
+        };
+        for candidate in candidates {
+            if !candidate.is_live() {
+                continue;
+            }
+            let candidate = candidate.get_forwarded_object().unwrap_or(candidate);
+            let Some(value) = deduplication.value(candidate) else {
+                continue;
+            };
+            let value_contents = unsafe { contents(deduplication, value) };
+            let mut hasher = DefaultHasher::new();
+            hasher.write(value_contents);
+            let values = inner.canonical.entry(hasher.finish()).or_default();
+            let canonical = values.iter().copied().find(|canonical| {
+                *canonical == value
+                    || unsafe { contents(deduplication, *canonical) } == value_contents
+            });
+            match canonical {
+                Some(canonical) if canonical != value => {
+                    deduplication.set_value(candidate, canonical);
+                    stats.deduplicated += 1;
+                    stats.bytes += value_contents.len();
+                }
+                Some(_) => {}
+                None => values.push(value),
+            }
+        }
+
+        debug!("Deduplication: {:?}", stats);
+        self.deduplicated_counter
+            .lock()
+            .unwrap()
+            .inc_by(stats.deduplicated as u64);
+        self.bytes_counter
+            .lock()
+            .unwrap()
+            .inc_by(stats.bytes as u64);
+        inner.last_gc = stats;
+    }
+
+    /// Get the statistics of the last GC.
+    pub fn last_gc(&self) -> DeduplicationStats {
+        self.inner.lock().unwrap().last_gc
+    }
+}
+
+/// Deduplicate the candidates added since the last GC.
+#[derive(Default)]
+pub(crate) struct Deduplicate<VM: VMBinding>(PhantomData<VM>);
+
+impl<VM: VMBinding> GCWork<VM> for Deduplicate<VM> {
+    fn do_work(&mut self, _worker: &mut GCWorker<VM>, mmtk: &'static MMTK<VM>) {
+        mmtk.deduplication.deduplicate();
+    }
+}
+
+/// Update the canonical values moved after their forwarding addresses are computed
+/// (mark-compact-only).
+#[derive(Default)]
+pub(crate) struct ForwardCanonicalValues<VM: VMBinding>(PhantomData<VM>);
+
+impl<VM: VMBinding> GCWork<VM> for ForwardCanonicalValues<VM> {
+    fn do_work(&mut self, _worker: &mut GCWorker<VM>, mmtk: &'static MMTK<VM>) {
+        mmtk.deduplication
+            .inner
+            .lock()
+            .unwrap()
+            .update_canonical(false);
+    }
+}
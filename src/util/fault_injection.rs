@@ -0,0 +1,194 @@
+//! Fault injection for testing how MMTk and the binding handle allocation failures.
+//!
+//! Real out-of-memory conditions are hard to reproduce deterministically in tests.  With the
+//! `fault_injection` feature, a test can arm a [`FaultPoint`] with [`inject`], and MMTk will fail
+//! the operation at that point as if the failure really happened:
+//!
+//! *   [`FaultPoint::Mmap`]: `mmap` fails with `ENOMEM` when MMTk maps memory for a space (or
+//!     anything other than side metadata).  MMTk calls [`crate::vm::Collection::out_of_memory`]
+//!     with [`crate::util::alloc::AllocationError::MmapOutOfMemory`].
+//! *   [`FaultPoint::MetadataMmap`]: `mmap` fails with `ENOMEM` when MMTk maps side metadata.  This
+//!     is handled in the same way as [`FaultPoint::Mmap`].
+//! *   [`FaultPoint::PageResource`]: A page resource has no more pages to give, as if the space
+//!     (e.g. a copy space) is exhausted.  A mutator that hits this triggers a GC and retries the
+//!     allocation.  If the failure persists after an emergency GC, MMTk calls
+//!     [`crate::vm::Collection::out_of_memory`] with
+//!     [`crate::util::alloc::AllocationError::HeapOutOfMemory`].  A GC worker that hits this
+//!     panics, as it would if a copy space is really exhausted during a GC.
+//!
+//! Only the operations that are actually performed count.  For example, a chunk that has been
+//! mapped is not mapped again, so it will not hit [`FaultPoint::Mmap`].  Reserving address ranges
+//! with `PROT_NONE` never fails.  The `mmap` fault points are only implemented on Unix-like
+//! systems.
+//!
+//! The faults are global to the process, and are not cleared when an MMTk instance is dropped.
+//! Tests should call [`clear_all`] when they finish.  This feature is only intended for testing.
+
+use enum_map::{Enum, EnumMap};
+use std::sync::Mutex;
+
+/// The points where a fault can be injected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Enum)]
+pub enum FaultPoint {
+    /// Mapping memory other than side metadata, such as the memory of a space.
+    Mmap,
+    /// Mapping side metadata.
+    MetadataMmap,
+    /// Getting new pages from a page resource.
+    PageResource,
+}
+
+/// The state of the fault at a fault point.
+#[derive(Clone, Copy, Debug, Default)]
+struct Fault {
+    /// The number of operations to let through before failing.
+    skip: usize,
+    /// The number of operations to fail after skipping.
+    times: usize,
+    /// The number of operations failed so far.
+    injected: usize,
+}
+
+lazy_static! {
+    static ref FAULTS: Mutex<EnumMap<FaultPoint, Fault>> = Mutex::new(EnumMap::default());
+}
+
+/// Arm the fault point so that the next `skip` operations at the point succeed, and then `times`
+/// operations fail.  Use `usize::MAX` for `times` to fail all the operations until the fault is
+/// cleared.  This replaces the previous fault at the point, and resets its count of injected
+/// failures.
+pub fn inject(point: FaultPoint, skip: usize, times: usize) {
+    FAULTS.lock().unwrap()[point] = Fault {
+        skip,
+        times,
+        injected: 0,
+    };
+}
+
+/// Disarm the fault point.
+pub fn clear(point: FaultPoint) {
+    FAULTS.lock().unwrap()[point] = Fault::default();
+}
+
+/// Disarm all the fault points.
+pub fn clear_all() {
+    *FAULTS.lock().unwrap() = EnumMap::default();
+}
+
+/// Get the number of operations failed at the fault point since it was armed.
+pub fn injected(point: FaultPoint) -> usize {
+    FAULTS.lock().unwrap()[point].injected
+}
+
+/// Check if the current operation at the fault point should fail.  This is called by MMTk at each
+/// fault point.
+pub(crate) fn should_fail(point: FaultPoint) -> bool {
+    let mut faults = FAULTS.lock().unwrap();
+    let fault = &mut faults[point];
+    if fault.times == 0 {
+        false
+    } else if fault.skip > 0 {
+        fault.skip -= 1;
+        false
+    } else {
+        if fault.times != usize::MAX {
+            fault.times -= 1;
+        }
+        fault.injected += 1;
+        warn!("Injected a fault at {:?}", point);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mmap_anno_test;
+    use crate::util::constants::BYTES_IN_PAGE;
+    use crate::util::memory::{self, MmapAnnotation, MmapStrategy};
+    use crate::util::test_util::MEMORY_TEST_REGION;
+    use crate::util::test_util::{serial_test, with_cleanup};
+    use crate::util::Address;
+
+    const START: Address = MEMORY_TEST_REGION.start;
+
+    const SPACE: &MmapAnnotation = &MmapAnnotation::Space { name: "test" };
+    const SIDE_META: &MmapAnnotation = &MmapAnnotation::SideMeta {
+        space: "test",
+        meta: "test",
+    };
+
+    #[test]
+    fn skip_and_times() {
+        serial_test(|| {
+            with_cleanup(
+                || {
+                    assert!(!should_fail(FaultPoint::PageResource));
+                    inject(FaultPoint::PageResource, 1, 2);
+                    let results: Vec<bool> = (0..4)
+                        .map(|_| should_fail(FaultPoint::PageResource))
+                        .collect();
+                    assert_eq!(results, vec![false, true, true, false]);
+                    assert_eq!(injected(FaultPoint::PageResource), 2);
+                    // Other points are not affected.
+                    assert!(!should_fail(FaultPoint::Mmap));
+
+                    inject(FaultPoint::PageResource, 0, usize::MAX);
+                    assert!((0..4).all(|_| should_fail(FaultPoint::PageResource)));
+                    clear(FaultPoint::PageResource);
+                    assert!(!should_fail(FaultPoint::PageResource));
+                    assert_eq!(injected(FaultPoint::PageResource), 0);
+                },
+                clear_all,
+            )
+        })
+    }
+
+    #[test]
+    fn mmap() {
+        serial_test(|| {
+            with_cleanup(
+                || {
+                    inject(FaultPoint::Mmap, 0, usize::MAX);
+                    // Reserving the address range does not fail.
+                    let res = memory::mmap_noreserve(
+                        START,
+                        BYTES_IN_PAGE,
+                        MmapStrategy::TEST,
+                        mmap_anno_test!(),
+                    );
+                    assert!(res.is_ok());
+                    let res =
+                        memory::dzmmap_noreplace(START, BYTES_IN_PAGE, MmapStrategy::TEST, SPACE);
+                    assert_eq!(
+                        res.unwrap_err().raw_os_error(),
+                        Some(libc::ENOMEM),
+                        "mmap for a space should fail"
+                    );
+                    assert_eq!(injected(FaultPoint::Mmap), 1);
+                    // Side metadata is mapped with another fault point.
+                    let res = unsafe {
+                        memory::dzmmap(START, BYTES_IN_PAGE, MmapStrategy::TEST, SIDE_META)
+                    };
+                    assert!(res.is_ok());
+
+                    clear(FaultPoint::Mmap);
+                    inject(FaultPoint::MetadataMmap, 0, 1);
+                    let res = unsafe {
+                        memory::dzmmap(START, BYTES_IN_PAGE, MmapStrategy::TEST, SIDE_META)
+                    };
+                    assert!(res.is_err());
+                    let res = unsafe {
+                        memory::dzmmap(START, BYTES_IN_PAGE, MmapStrategy::TEST, SIDE_META)
+                    };
+                    assert!(res.is_ok());
+                    assert_eq!(injected(FaultPoint::MetadataMmap), 1);
+                },
+                || {
+                    clear_all();
+                    assert!(memory::munmap(START, BYTES_IN_PAGE).is_ok());
+                },
+            )
+        })
+    }
+}
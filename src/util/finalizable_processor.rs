@@ -2,6 +2,7 @@ use crate::plan::is_nursery_gc;
 use crate::scheduler::gc_work::ProcessEdgesWork;
 use crate::scheduler::{GCWork, GCWorker, WorkBucketStage};
 use crate::util::reference_processor::RescanReferences;
+use crate::util::weak_processing_stats::FinalizationStats;
 use crate::util::ObjectReference;
 use crate::util::VMWorkerThread;
 use crate::vm::Finalizable;
@@ -22,6 +23,8 @@ pub struct FinalizableProcessor<F: Finalizable> {
     /// Objects that can be finalized. They are actually dead, but we keep them alive
     /// until the binding pops them from the queue.
     ready_for_finalize: Vec<F>,
+    /// The number of objects added since the last scan.
+    registered: usize,
 }
 
 impl<F: Finalizable> FinalizableProcessor<F> {
@@ -30,18 +33,27 @@ impl<F: Finalizable> FinalizableProcessor<F> {
             candidates: vec![],
             nursery_index: 0,
             ready_for_finalize: vec![],
+            registered: 0,
         }
     }
 
     pub fn add(&mut self, object: F) {
         self.candidates.push(object);
+        self.registered += 1;
     }
 
     fn forward_finalizable_reference<E: ProcessEdgesWork>(e: &mut E, finalizable: &mut F) {
         finalizable.keep_alive::<E>(e);
     }
 
-    pub fn scan<E: ProcessEdgesWork>(&mut self, tls: VMWorkerThread, e: &mut E, nursery: bool) {
+    /// Find the dead candidates and make them ready for finalization.  Return the statistics of
+    /// this scan.
+    pub fn scan<E: ProcessEdgesWork>(
+        &mut self,
+        tls: VMWorkerThread,
+        e: &mut E,
+        nursery: bool,
+    ) -> FinalizationStats {
         let start = if nursery { self.nursery_index } else { 0 };
 
         // We should go through ready_for_finalize objects and keep them alive.
@@ -51,6 +63,12 @@ impl<F: Finalizable> FinalizableProcessor<F> {
         self.candidates.append(&mut self.ready_for_finalize);
         debug_assert!(self.ready_for_finalize.is_empty());
 
+        let stats = FinalizationStats {
+            registered: std::mem::take(&mut self.registered),
+            candidates: self.candidates.len() - start,
+            ready: 0,
+        };
+
         for mut f in self.candidates.drain(start..).collect::<Vec<F>>() {
             let reff = f.get_reference();
             trace!("Pop {:?} for finalization", reff);
@@ -77,6 +95,11 @@ impl<F: Finalizable> FinalizableProcessor<F> {
         self.nursery_index = self.candidates.len();
 
         <<E as ProcessEdgesWork>::VM as VMBinding>::VMCollection::schedule_finalization(tls);
+
+        FinalizationStats {
+            ready: self.ready_for_finalize.len(),
+            ..stats
+        }
     }
 
     pub fn forward_candidate<E: ProcessEdgesWork>(&mut self, e: &mut E, _nursery: bool) {
@@ -161,7 +184,9 @@ impl<E: ProcessEdgesWork> GCWork<E::VM> for Finalization<E> {
 
         let mut w = E::new(vec![], false, mmtk, WorkBucketStage::FinalRefClosure);
         w.set_worker(worker);
-        finalizable_processor.scan(worker.tls, &mut w, is_nursery_gc(mmtk.get_plan()));
+        let stats = finalizable_processor.scan(worker.tls, &mut w, is_nursery_gc(mmtk.get_plan()));
+        mmtk.weak_processing_accounting
+            .on_finalizables_scanned(stats);
         debug!(
             "Finished finalization, {} objects in candidates, {} objects ready to finalize",
             finalizable_processor.candidates.len(),
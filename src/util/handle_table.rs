@@ -0,0 +1,186 @@
+//! A table of global handles to objects, like JNI global and weak global references.
+//!
+//! A binding creates a [`Handle`] to an object with [`crate::memory_manager::create_handle`], and
+//! loads the object from the handle with [`crate::memory_manager::load_handle`] until it deletes
+//! the handle with [`crate::memory_manager::delete_handle`].  MMTk keeps the handles consistent
+//! across GCs, so the binding does not need to report them as roots or update them itself:
+//!
+//! * The objects of strong handles are roots.  They are traced at the start of the `Closure` stage,
+//!   and the handles are updated if the objects are moved.
+//! * Weak handles do not keep their objects alive.  After the transitive closure (in the
+//!   `VMUnloading` stage), a weak handle is cleared if its object is dead, and updated if its object
+//!   is moved.
+//!
+//! For plans that compute forwarding addresses after liveness (i.e. MarkCompact), the strong
+//! handles are traced again with the forwarding trace in the `SecondRoots` stage, and the weak
+//! handles are updated again in the `VMRefForwarding` stage.
+
+use crate::scheduler::{GCWork, GCWorker, ProcessEdgesWork, WorkBucketStage};
+use crate::util::ObjectReference;
+use crate::vm::VMBinding;
+use crate::MMTK;
+use std::marker::PhantomData;
+use std::sync::Mutex;
+
+/// A handle to an object.  See the module documentation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Handle(usize);
+
+/// The kind of a [`Handle`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HandleKind {
+    /// The handle keeps its object alive.
+    Strong,
+    /// The handle does not keep its object alive, and is cleared when the object dies.
+    Weak,
+}
+
+struct HandleEntry {
+    kind: HandleKind,
+    /// The object of the handle, or `None` if it is a weak handle that has been cleared.
+    object: Option<ObjectReference>,
+}
+
+#[derive(Default)]
+struct HandleTableInner {
+    /// The entries, indexed by handles.  Deleted entries are `None`.
+    entries: Vec<Option<HandleEntry>>,
+    /// The indices of deleted entries, which are reused for new handles.
+    free: Vec<usize>,
+}
+
+impl HandleTableInner {
+    fn entry(&self, handle: Handle) -> &HandleEntry {
+        self.entries[handle.0]
+            .as_ref()
+            .unwrap_or_else(|| panic!("{:?} has been deleted", handle))
+    }
+
+    fn live_entries(&mut self) -> impl Iterator<Item = &mut HandleEntry> {
+        self.entries.iter_mut().flatten()
+    }
+}
+
+/// The handles of an MMTk instance.
+#[derive(Default)]
+pub(crate) struct HandleTable {
+    inner: Mutex<HandleTableInner>,
+}
+
+impl HandleTable {
+    pub fn create(&self, object: ObjectReference, kind: HandleKind) -> Handle {
+        let mut inner = self.inner.lock().unwrap();
+        let entry = Some(HandleEntry {
+            kind,
+            object: Some(object),
+        });
+        if let Some(index) = inner.free.pop() {
+            inner.entries[index] = entry;
+            Handle(index)
+        } else {
+            inner.entries.push(entry);
+            Handle(inner.entries.len() - 1)
+        }
+    }
+
+    pub fn load(&self, handle: Handle) -> Option<ObjectReference> {
+        self.inner.lock().unwrap().entry(handle).object
+    }
+
+    pub fn delete(&self, handle: Handle) {
+        let mut inner = self.inner.lock().unwrap();
+        let entry = inner.entries[handle.0].take();
+        assert!(entry.is_some(), "{:?} has been deleted", handle);
+        inner.free.push(handle.0);
+    }
+
+    /// Trace the objects of strong handles as roots, and update the handles.
+    fn trace_strong_handles<E: ProcessEdgesWork>(&self, trace: &mut E) {
+        let mut inner = self.inner.lock().unwrap();
+        let mut count = 0;
+        for entry in inner.live_entries() {
+            if entry.kind == HandleKind::Strong {
+                entry.object = entry.object.map(|object| trace.trace_object(object));
+                count += 1;
+            }
+        }
+        debug!("Traced {} strong handles", count);
+    }
+
+    /// Update the handles after the transitive closure.  Clear the weak handles of dead objects, and
+    /// update the handles of objects that have been forwarded.
+    fn update(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        let mut cleared = 0;
+        for entry in inner.live_entries() {
+            let Some(object) = entry.object else {
+                continue;
+            };
+            if entry.kind == HandleKind::Weak && !object.is_live() {
+                entry.object = None;
+                cleared += 1;
+                continue;
+            }
+            debug_assert!(object.is_live(), "The object of a strong handle is dead");
+            if let Some(new_object) = object.get_forwarded_object() {
+                entry.object = Some(new_object);
+            }
+        }
+        debug!("Cleared {} weak handles", cleared);
+    }
+
+    /// Update the weak handles of objects that have been forwarded after their forwarding addresses
+    /// are computed.  The strong handles are not touched, as they have been updated when they were
+    /// traced with the forwarding trace, and no longer point to the old objects.
+    fn forward_weak_handles(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        for entry in inner.live_entries() {
+            if entry.kind != HandleKind::Weak {
+                continue;
+            }
+            if let Some(new_object) = entry.object.and_then(|o| o.get_forwarded_object()) {
+                entry.object = Some(new_object);
+            }
+        }
+    }
+}
+
+/// Trace the objects of strong handles.
+#[derive(Default)]
+pub(crate) struct ScanStrongHandles<E: ProcessEdgesWork>(PhantomData<E>);
+
+impl<E: ProcessEdgesWork> ScanStrongHandles<E> {
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<E: ProcessEdgesWork> GCWork<E::VM> for ScanStrongHandles<E> {
+    fn do_work(&mut self, worker: &mut GCWorker<E::VM>, mmtk: &'static MMTK<E::VM>) {
+        let mut w = E::new(vec![], true, mmtk, WorkBucketStage::Closure);
+        w.set_worker(worker);
+        mmtk.handle_table.trace_strong_handles(&mut w);
+        w.flush();
+    }
+}
+
+/// Clear the weak handles of dead objects, and update the handles of moved objects.
+#[derive(Default)]
+pub(crate) struct UpdateHandles<VM: VMBinding>(PhantomData<VM>);
+
+impl<VM: VMBinding> GCWork<VM> for UpdateHandles<VM> {
+    fn do_work(&mut self, _worker: &mut GCWorker<VM>, mmtk: &'static MMTK<VM>) {
+        mmtk.handle_table.update();
+    }
+}
+
+/// Update the weak handles of objects moved after their forwarding addresses are computed
+/// (mark-compact-only).
+#[derive(Default)]
+pub(crate) struct ForwardHandles<VM: VMBinding>(PhantomData<VM>);
+
+impl<VM: VMBinding> GCWork<VM> for ForwardHandles<VM> {
+    fn do_work(&mut self, _worker: &mut GCWorker<VM>, mmtk: &'static MMTK<VM>) {
+        mmtk.handle_table.forward_weak_handles();
+    }
+}
@@ -0,0 +1,150 @@
+//! Measurement windows for benchmark harnesses.
+//!
+//! A benchmark harness opens a named window before it runs a workload (or an iteration of a
+//! workload), and closes the window afterwards.  Windows may be nested, and a harness may run
+//! multiple workloads in one process, each in its own window.  MMTk gathers statistics while any
+//! window is open, and closing a window returns the statistics gathered during that window as a
+//! [`HarnessStats`].
+//!
+//! [`crate::memory_manager::harness_begin`] and [`crate::memory_manager::harness_end`] open and
+//! close the window named [`DEFAULT_WINDOW`], and print the statistics when the window is closed.
+
+use crate::util::opaque_pointer::VMMutatorThread;
+use crate::vm::VMBinding;
+use crate::MMTK;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// The name of the window used by `harness_begin` and `harness_end`.
+pub const DEFAULT_WINDOW: &str = "harness";
+
+/// The statistics gathered in a measurement window.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HarnessStats {
+    /// The name of the window.
+    pub name: String,
+    /// The number of windows that enclosed this window when it was opened.  It is 0 for an
+    /// outermost window.
+    pub depth: usize,
+    /// The wall-clock time from opening the window until closing it.
+    pub elapsed: Duration,
+    /// The number of GCs in the window.
+    pub gc_count: usize,
+    /// The values of the statistic counters in the window, keyed by the column names that MMTk
+    /// prints at `harness_end`, such as `time.other` and `time.stw` (in nanoseconds).
+    pub counters: BTreeMap<String, u64>,
+}
+
+struct OpenWindow {
+    name: String,
+    start: Instant,
+    gc_count: usize,
+    counters: BTreeMap<String, u64>,
+}
+
+#[derive(Default)]
+struct WindowsState {
+    /// The open windows, from the outermost to the innermost.
+    windows: Vec<OpenWindow>,
+    /// Whether the harness started gathering statistics.  If statistics were enabled by
+    /// `set_stats_enabled` before the first window was opened or while a window is open, the
+    /// harness leaves them enabled when the last window is closed.
+    started_stats: bool,
+}
+
+/// The open measurement windows of an MMTk instance.
+#[derive(Default)]
+pub(crate) struct HarnessWindows {
+    state: Mutex<WindowsState>,
+}
+
+/// A handle to the measurement windows of an MMTk instance.  See [`crate::MMTK::harness`].
+///
+/// The methods should be called by a mutator thread when GC is not in progress, so that the time
+/// is correctly accounted to the mutator and the GC.
+pub struct Harness<'a, VM: VMBinding> {
+    mmtk: &'a MMTK<VM>,
+}
+
+impl<'a, VM: VMBinding> Harness<'a, VM> {
+    pub(crate) fn new(mmtk: &'a MMTK<VM>) -> Self {
+        Self { mmtk }
+    }
+
+    /// Trigger a full-heap GC to clear any residual garbage before a measurement.
+    pub fn collect(&self, tls: VMMutatorThread) {
+        self.mmtk.handle_user_collection_request(tls, true, true);
+    }
+
+    /// Open a window of the given name.  MMTk starts gathering statistics when the first window is
+    /// opened.
+    pub fn begin(&self, name: &str) {
+        let stats = &self.mmtk.stats;
+        let mut state = self.mmtk.harness_windows.state.lock().unwrap();
+        if state.windows.is_empty() && !stats.get_gathering_stats() {
+            stats.start_all();
+            self.mmtk.scheduler.enable_stat();
+            state.started_stats = true;
+        }
+        state.windows.push(OpenWindow {
+            name: name.to_string(),
+            start: Instant::now(),
+            gc_count: stats.get_gc_count(),
+            counters: stats.snapshot(),
+        });
+    }
+
+    /// Close the most recently opened window of the given name, and return the statistics of the
+    /// window.  Windows nested in it stay open.  MMTk stops gathering statistics when the last
+    /// window is closed.  Return `None` if no window of that name is open.
+    pub fn end(&self, name: &str) -> Option<HarnessStats> {
+        let stats = &self.mmtk.stats;
+        let mut state = self.mmtk.harness_windows.state.lock().unwrap();
+        let depth = state.windows.iter().rposition(|w| w.name == name)?;
+        let counters = stats.snapshot();
+        let window = state.windows.remove(depth);
+        let result = HarnessStats {
+            name: window.name,
+            depth,
+            elapsed: window.start.elapsed(),
+            gc_count: stats.get_gc_count() - window.gc_count,
+            counters: counters
+                .into_iter()
+                .map(|(name, value)| {
+                    let start = window.counters.get(&name).copied().unwrap_or(0);
+                    (name, value - start)
+                })
+                .collect(),
+        };
+        if state.windows.is_empty() && std::mem::take(&mut state.started_stats) {
+            stats.stop_all_counters();
+        }
+        Some(result)
+    }
+
+    /// Enable or disable gathering statistics, whether or not any window is open.  After this is
+    /// called, closing the last window no longer stops gathering statistics.  This holds the lock
+    /// of the windows, so it does not race with opening or closing windows.
+    pub(crate) fn set_stats_enabled(&self, enabled: bool) {
+        let stats = &self.mmtk.stats;
+        let mut state = self.mmtk.harness_windows.state.lock().unwrap();
+        state.started_stats = false;
+        if enabled == stats.get_gathering_stats() {
+            return;
+        }
+        if enabled {
+            stats.start_all();
+            self.mmtk.scheduler.enable_stat();
+        } else {
+            self.mmtk.scheduler.disable_stat();
+            stats.stop_all_counters();
+        }
+    }
+
+    /// Get the names of the open windows, from the outermost to the innermost.
+    pub fn open_windows(&self) -> Vec<String> {
+        let state = self.mmtk.harness_windows.state.lock().unwrap();
+        state.windows.iter().map(|w| w.name.clone()).collect()
+    }
+}
@@ -177,6 +177,12 @@ impl<VM: VMBinding, B: Region> BlockPageResource<VM, B> {
         self.block_queue.flush_all()
         // TODO: For 32-bit space, we may want to free some contiguous chunks.
     }
+
+    /// Flush the thread-local queues, and sort the free blocks so that they are allocated in the
+    /// order of their addresses.  This is used by the option `deterministic_gc`.
+    pub fn flush_all_sorted(&self) {
+        self.block_queue.flush_all_sorted()
+    }
 }
 
 /// A block list that supports fast lock-free push/pop operations
@@ -395,6 +401,33 @@ impl<B: Region> BlockPool<B> {
         }
     }
 
+    /// Flush all thread-local queues to the global pool, and sort the blocks so that they are
+    /// popped in the order of their addresses, regardless of the order they were pushed.  This
+    /// must not be called when other threads may push or pop blocks.
+    pub fn flush_all_sorted(&self) {
+        self.flush_all();
+        let mut blocks = Vec::with_capacity(self.len());
+        self.iterate_blocks(&mut |block| blocks.push(block));
+        debug_assert_eq!(blocks.len(), self.len());
+        // Blocks are popped from the end of the last queue first.  Sort them in descending order,
+        // so the last queue ends with the block at the lowest address.
+        blocks.sort_unstable_by_key(|block| std::cmp::Reverse(block.start()));
+        let mut queues = vec![];
+        let mut queue = BlockQueue::new();
+        for block in blocks {
+            if let Err(block) = unsafe { queue.push_relaxed(block) } {
+                queues.push(std::mem::replace(&mut queue, BlockQueue::new()));
+                let result = unsafe { queue.push_relaxed(block) };
+                debug_assert!(result.is_ok());
+            }
+        }
+        if !queue.is_empty() {
+            queues.push(queue);
+        }
+        *self.head_global_freed_blocks.write() = None;
+        *self.global_freed_blocks.write() = queues;
+    }
+
     /// Get total number of blocks in the whole BlockQueue
     pub fn len(&self) -> usize {
         self.count.load(Ordering::SeqCst)
@@ -413,3 +446,34 @@ impl<B: Region> BlockPool<B> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::immix::block::Block;
+
+    #[test]
+    fn flush_all_sorted() {
+        let base = unsafe { Address::from_usize(BYTES_IN_CHUNK) };
+        let block = |i: usize| Block::from_aligned_address(base + i * Block::BYTES);
+        let pool = BlockPool::<Block>::new(1);
+        // More blocks than a queue can hold, pushed in an order unrelated to their addresses.
+        let num_blocks = LOCAL_BUFFER_SIZE * 2 + 1;
+        let mut queue = BlockQueue::new();
+        for i in (0..num_blocks).map(|i| (i * 7) % num_blocks) {
+            if let Err(b) = unsafe { queue.push_relaxed(block(i)) } {
+                pool.add_global_array(std::mem::replace(&mut queue, BlockQueue::new()));
+                assert!(unsafe { queue.push_relaxed(b) }.is_ok());
+            }
+        }
+        pool.add_global_array(queue);
+        assert_eq!(pool.len(), num_blocks);
+
+        pool.flush_all_sorted();
+        assert_eq!(pool.len(), num_blocks);
+        let popped: Vec<Block> = std::iter::from_fn(|| pool.pop()).collect();
+        let expected: Vec<Block> = (0..num_blocks).map(block).collect();
+        assert_eq!(popped, expected);
+        assert_eq!(pool.len(), 0);
+    }
+}
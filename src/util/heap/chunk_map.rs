@@ -1,85 +1,105 @@
-use crate::scheduler::GCWork;
+//! A registry of the chunks used by a space.
+//!
+//! Spaces that manage their memory in chunks (such as `ImmixSpace` and the native
+//! `MarkSweepSpace`) record the chunks they use in a [`ChunkMap`], and iterate through their
+//! chunks in parallel with the work packets generated by [`ChunkMap::generate_tasks`] or
+//! [`ChunkMap::for_each_chunk`]. The state of each chunk is kept in a global side metadata byte
+//! which also records the space that owns the chunk, so any space, including the large object
+//! space and discontiguous spaces that share chunks with others, can use a chunk map.
+
+use crate::scheduler::{GCWork, GCWorker};
 use crate::util::linear_scan::Region;
 use crate::util::linear_scan::RegionIterator;
 use crate::util::metadata::side_metadata::SideMetadataSpec;
 use crate::util::Address;
 use crate::vm::VMBinding;
+use crate::MMTK;
+use mmtk_macros::Region;
 use spin::Mutex;
+use std::marker::PhantomData;
 use std::ops::Range;
+use std::sync::Arc;
 
 /// Data structure to reference a MMTk 4 MB chunk.
 #[repr(transparent)]
-#[derive(Debug, Clone, Copy, PartialOrd, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialOrd, PartialEq, Eq, Region)]
+#[region(log_bytes = crate::util::heap::layout::vm_layout::LOG_BYTES_IN_CHUNK)]
 pub struct Chunk(Address);
 
-impl Region for Chunk {
-    const LOG_BYTES: usize = crate::util::heap::layout::vm_layout::LOG_BYTES_IN_CHUNK;
-
-    fn from_aligned_address(address: Address) -> Self {
-        debug_assert!(address.is_aligned_to(Self::BYTES));
-        Self(address)
-    }
-
-    fn start(&self) -> Address {
-        self.0
-    }
-}
-
 impl Chunk {
     /// Chunk constant with zero address
     // FIXME: We use this as an empty value. What if we actually use the first chunk?
     pub const ZERO: Self = Self(Address::ZERO);
-
-    /// Get an iterator for regions within this chunk.
-    pub fn iter_region<R: Region>(&self) -> RegionIterator<R> {
-        // R should be smaller than a chunk
-        debug_assert!(R::LOG_BYTES < Self::LOG_BYTES);
-        // R should be aligned to chunk boundary
-        debug_assert!(R::is_aligned(self.start()));
-        debug_assert!(R::is_aligned(self.end()));
-
-        let start = R::from_aligned_address(self.start());
-        let end = R::from_aligned_address(self.end());
-        RegionIterator::<R>::new(start, end)
-    }
 }
 
-/// Chunk allocation state
-#[repr(u8)]
+/// Chunk allocation state, as seen by the space that owns a chunk map.
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum ChunkState {
-    /// The chunk is not allocated.
-    Free = 0,
-    /// The chunk is allocated.
-    Allocated = 1,
+    /// The chunk is not allocated, or it is allocated by another space.
+    Free,
+    /// The chunk is allocated by the space.
+    Allocated,
 }
 
-/// A byte-map to record all the allocated chunks.
-/// A plan can use this to maintain records for the chunks that they used, and the states of the chunks.
-/// Any plan that uses the chunk map should include the `ALLOC_TABLE` spec in their local sidemetadata specs
+/// The bit set in the chunk map byte if the chunk is allocated. The other bits are the index of
+/// the space that allocated the chunk.
+const ALLOCATED_BIT: u8 = 0x80;
+
+/// A byte-map to record all the allocated chunks of a space.
+/// A space can use this to maintain records for the chunks that it used, and the states of the chunks.
+/// The byte-map (`ALLOC_TABLE`) is a global side metadata shared by all the spaces, and each byte
+/// records the space that owns the chunk.
 pub struct ChunkMap {
+    /// The index of the space that owns this chunk map.
+    space_index: usize,
     chunk_range: Mutex<Range<Chunk>>,
 }
 
 impl ChunkMap {
     /// Chunk alloc table
     pub const ALLOC_TABLE: SideMetadataSpec =
-        crate::util::metadata::side_metadata::spec_defs::CHUNK_MARK;
+        crate::util::metadata::side_metadata::spec_defs::CHUNK_MAP;
 
-    pub fn new() -> Self {
+    /// Create a chunk map for the space with the given index (see
+    /// [`crate::util::heap::space_descriptor::SpaceDescriptor::get_index`]).
+    pub fn new(space_index: u8) -> Self {
+        debug_assert!(space_index & ALLOCATED_BIT == 0);
         Self {
+            space_index: space_index as usize,
             chunk_range: Mutex::new(Chunk::ZERO..Chunk::ZERO),
         }
     }
 
+    /// Get the index of the space that allocated the chunk, or `None` if no space has allocated
+    /// the chunk.
+    pub fn owner(chunk: Chunk) -> Option<usize> {
+        let byte = unsafe { Self::ALLOC_TABLE.load::<u8>(chunk.start()) };
+        if byte & ALLOCATED_BIT != 0 {
+            Some((byte & !ALLOCATED_BIT) as usize)
+        } else {
+            None
+        }
+    }
+
     /// Set chunk state
     pub fn set(&self, chunk: Chunk, state: ChunkState) {
         // Do nothing if the chunk is already in the expected state.
         if self.get(chunk) == state {
             return;
         }
+        debug_assert!(
+            Self::owner(chunk).map_or(true, |owner| owner == self.space_index),
+            "Chunk {:?} is allocated by space {:?}, not space {}",
+            chunk,
+            Self::owner(chunk),
+            self.space_index
+        );
         // Update alloc byte
-        unsafe { Self::ALLOC_TABLE.store::<u8>(chunk.start(), state as u8) };
+        let byte = match state {
+            ChunkState::Free => 0,
+            ChunkState::Allocated => ALLOCATED_BIT | self.space_index as u8,
+        };
+        unsafe { Self::ALLOC_TABLE.store::<u8>(chunk.start(), byte) };
         // If this is a newly allcoated chunk, then expand the chunk range.
         if state == ChunkState::Allocated {
             debug_assert!(!chunk.start().is_zero());
@@ -96,40 +116,69 @@ impl ChunkMap {
         }
     }
 
-    /// Get chunk state
+    /// Get chunk state.  A chunk allocated by another space is `Free` for this space.
     pub fn get(&self, chunk: Chunk) -> ChunkState {
-        let byte = unsafe { Self::ALLOC_TABLE.load::<u8>(chunk.start()) };
-        match byte {
-            0 => ChunkState::Free,
-            1 => ChunkState::Allocated,
-            _ => unreachable!(),
+        if Self::owner(chunk) == Some(self.space_index) {
+            ChunkState::Allocated
+        } else {
+            ChunkState::Free
         }
     }
 
-    /// A range of all chunks in the heap.
+    /// A range of all chunks in the heap that may be allocated by this space.  Use
+    /// [`ChunkMap::get`] to tell whether each chunk is allocated by this space.
     pub fn all_chunks(&self) -> RegionIterator<Chunk> {
         let chunk_range = self.chunk_range.lock();
         RegionIterator::<Chunk>::new(chunk_range.start, chunk_range.end)
     }
 
+    /// Iterate through the chunks allocated by this space.
+    pub fn allocated_chunks(&self) -> impl Iterator<Item = Chunk> + '_ {
+        self.all_chunks()
+            .filter(|c| self.get(*c) == ChunkState::Allocated)
+    }
+
     /// Helper function to create per-chunk processing work packets for each allocated chunks.
     pub fn generate_tasks<VM: VMBinding>(
         &self,
         func: impl Fn(Chunk) -> Box<dyn GCWork<VM>>,
     ) -> Vec<Box<dyn GCWork<VM>>> {
         let mut work_packets: Vec<Box<dyn GCWork<VM>>> = vec![];
-        for chunk in self
-            .all_chunks()
-            .filter(|c| self.get(*c) == ChunkState::Allocated)
-        {
+        for chunk in self.allocated_chunks() {
             work_packets.push(func(chunk));
         }
         work_packets
     }
+
+    /// Create a work packet for each allocated chunk, which calls `func` with the chunk.  This is
+    /// a shorthand of [`ChunkMap::generate_tasks`] for work that does not need its own work packet
+    /// type.  All the work packets share `func`.
+    pub fn for_each_chunk<VM: VMBinding>(
+        &self,
+        func: impl Fn(Chunk, &mut GCWorker<VM>) + Send + Sync + 'static,
+    ) -> Vec<Box<dyn GCWork<VM>>> {
+        let func = Arc::new(func);
+        self.generate_tasks(|chunk| {
+            Box::new(ForEachChunk {
+                chunk,
+                func: func.clone(),
+                phantom: PhantomData,
+            })
+        })
+    }
+}
+
+/// The work packet created by [`ChunkMap::for_each_chunk`].
+struct ForEachChunk<VM: VMBinding, F: Fn(Chunk, &mut GCWorker<VM>) + Send + Sync + 'static> {
+    chunk: Chunk,
+    func: Arc<F>,
+    phantom: PhantomData<VM>,
 }
 
-impl Default for ChunkMap {
-    fn default() -> Self {
-        Self::new()
+impl<VM: VMBinding, F: Fn(Chunk, &mut GCWorker<VM>) + Send + Sync + 'static> GCWork<VM>
+    for ForEachChunk<VM, F>
+{
+    fn do_work(&mut self, worker: &mut GCWorker<VM>, _mmtk: &'static MMTK<VM>) {
+        (self.func)(self.chunk, worker)
     }
 }
@@ -77,6 +77,24 @@ impl<VM: VMBinding> ExternalPageResource<VM> {
         lock.push(pages);
     }
 
+    /// Remove the external pages that were added with `add_new_external_pages`.  `pages` must be
+    /// the same range that was added.
+    pub fn remove_external_pages(&self, pages: ExternalPages) {
+        let mut lock = self.ranges.lock().unwrap();
+        let index = lock
+            .iter()
+            .position(|ep| ep.start == pages.start && ep.end == pages.end)
+            .unwrap_or_else(|| {
+                panic!(
+                    "External pages ({}, {}) were not added",
+                    pages.start, pages.end
+                )
+            });
+        lock.swap_remove(index);
+        let n_pages = (pages.end - pages.start) >> LOG_BYTES_IN_PAGE;
+        self.common.accounting.release(n_pages);
+    }
+
     pub fn get_external_pages(&self) -> MutexGuard<Vec<ExternalPages>> {
         self.ranges.lock().unwrap()
     }
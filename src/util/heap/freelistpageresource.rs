@@ -1,3 +1,4 @@
+use std::collections::VecDeque;
 use std::sync::{Mutex, MutexGuard};
 
 use super::layout::vm_layout::PAGES_IN_CHUNK;
@@ -28,6 +29,12 @@ pub struct FreeListPageResource<VM: VMBinding> {
     _p: PhantomData<VM>,
     /// Protect memory on release, and unprotect on re-allocate.
     pub(crate) protect_memory_on_release: Option<memory::MmapProtection>,
+    /// If memory is protected on release, keep the released pages protected for this number of
+    /// GCs before they can be allocated again.  Zero means the pages can be reused immediately.
+    pub(crate) quarantine_gcs: usize,
+    /// The pages in quarantine, grouped by the GC in which they were released, from the oldest to
+    /// the newest.  Each entry is the first page and the number of pages.
+    quarantine: Mutex<VecDeque<Vec<(Address, usize)>>>,
 }
 
 unsafe impl<VM: VMBinding> Send for FreeListPageResource<VM> {}
@@ -169,6 +176,8 @@ impl<VM: VMBinding> FreeListPageResource<VM> {
             }),
             _p: PhantomData,
             protect_memory_on_release: None,
+            quarantine_gcs: 0,
+            quarantine: Mutex::new(VecDeque::new()),
         }
     }
 
@@ -207,6 +216,8 @@ impl<VM: VMBinding> FreeListPageResource<VM> {
             }),
             _p: PhantomData,
             protect_memory_on_release: None,
+            quarantine_gcs: 0,
+            quarantine: Mutex::new(VecDeque::new()),
         }
     }
 
@@ -324,6 +335,16 @@ impl<VM: VMBinding> FreeListPageResource<VM> {
         self.common.release_discontiguous_chunks(chunk);
     }
 
+    /// Get the number of pages allocated by `alloc_pages` starting at `first`.
+    ///
+    /// Warning: This method acquires the mutex `self.sync`, like `release_pages`.
+    pub fn get_allocated_pages(&self, first: Address) -> usize {
+        debug_assert!(conversions::is_page_aligned(first));
+        let sync = self.sync.lock().unwrap();
+        let page_offset = conversions::bytes_to_pages_up(first - sync.start);
+        sync.free_list.size(page_offset as _) as usize
+    }
+
     /// Release pages previously allocated by `alloc_pages`.
     ///
     /// Warning: This method acquires the mutex `self.sync`.  If multiple threads release pages
@@ -332,7 +353,54 @@ impl<VM: VMBinding> FreeListPageResource<VM> {
     /// large object space are recommended to use [`BlockPageResource`] whenever possible.
     ///
     /// [`BlockPageResource`]: crate::util::heap::blockpageresource::BlockPageResource
+    ///
+    /// If `quarantine_gcs` is not zero, the pages are protected and put in quarantine instead.  They
+    /// remain reserved until they are released by `release_expired_quarantine`.
     pub fn release_pages(&self, first: Address) {
+        if self.protect_memory_on_release.is_some() && self.quarantine_gcs > 0 {
+            let pages = self.get_allocated_pages(first);
+            self.mprotect(first, pages);
+            let mut quarantine = self.quarantine.lock().unwrap();
+            if quarantine.is_empty() {
+                quarantine.push_back(vec![]);
+            }
+            quarantine.back_mut().unwrap().push((first, pages));
+            return;
+        }
+        self.free_pages(first, self.protect_memory_on_release.is_some());
+    }
+
+    /// Called at the end of each GC.  Release the pages that have been in quarantine for
+    /// `quarantine_gcs` GCs, so that they can be allocated again.
+    pub fn release_expired_quarantine(&self) {
+        if self.quarantine_gcs == 0 {
+            return;
+        }
+        let mut expired = vec![];
+        {
+            let mut quarantine = self.quarantine.lock().unwrap();
+            while quarantine.len() > self.quarantine_gcs {
+                expired.extend(quarantine.pop_front().unwrap());
+            }
+            // Pages released in the next GC go to a new group.
+            quarantine.push_back(vec![]);
+        }
+        for (first, _) in expired {
+            // The pages were protected when they were put in quarantine.
+            self.free_pages(first, false);
+        }
+    }
+
+    /// Get the number of pages in quarantine.
+    pub fn get_quarantined_pages(&self) -> usize {
+        let quarantine = self.quarantine.lock().unwrap();
+        quarantine
+            .iter()
+            .flat_map(|group| group.iter().map(|(_, pages)| *pages))
+            .sum()
+    }
+
+    fn free_pages(&self, first: Address, protect: bool) {
         debug_assert!(conversions::is_page_aligned(first));
         let mut sync = self.sync.lock().unwrap();
         let page_offset = conversions::bytes_to_pages_up(first - sync.start);
@@ -341,7 +409,7 @@ impl<VM: VMBinding> FreeListPageResource<VM> {
         //     VM.memory.zero(false, first, Conversions.pagesToBytes(pages));
         debug_assert!(pages as usize <= self.common.accounting.get_committed_pages());
 
-        if self.protect_memory_on_release.is_some() {
+        if protect {
             self.mprotect(first, pages as _);
         }
 
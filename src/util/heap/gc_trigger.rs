@@ -4,9 +4,12 @@ use crate::global_state::GlobalState;
 use crate::plan::gc_requester::GCRequester;
 use crate::plan::Plan;
 use crate::policy::space::Space;
-use crate::util::constants::BYTES_IN_PAGE;
+use crate::util::alloc::AllocationErrorContext;
+use crate::util::constants::{BYTES_IN_PAGE, LOG_BYTES_IN_PAGE};
 use crate::util::conversions;
 use crate::util::options::{GCTriggerSelector, Options, DEFAULT_MAX_NURSERY, DEFAULT_MIN_NURSERY};
+use crate::util::pacer::{Pacer, PacingAction};
+use crate::util::VMMutatorThread;
 use crate::vm::VMBinding;
 use crate::MMTK;
 use std::mem::MaybeUninit;
@@ -26,6 +29,8 @@ pub struct GCTrigger<VM: VMBinding> {
     gc_requester: Arc<GCRequester<VM>>,
     options: Arc<Options>,
     state: Arc<GlobalState>,
+    /// Paces concurrent collection cycles against allocation.
+    pacer: Pacer,
 }
 
 impl<VM: VMBinding> GCTrigger<VM> {
@@ -60,6 +65,7 @@ impl<VM: VMBinding> GCTrigger<VM> {
             options,
             gc_requester,
             state,
+            pacer: Pacer::default(),
         }
     }
 
@@ -99,9 +105,92 @@ impl<VM: VMBinding> GCTrigger<VM> {
             self.gc_requester.request();
             return true;
         }
+        if self.pacing_action() == PacingAction::FinishInPause {
+            info!(
+                "[POLL] Mutators are outrunning concurrent marking. Finishing the cycle in a pause ({}/{} pages)",
+                plan.get_reserved_pages(),
+                plan.get_total_pages(),
+            );
+            self.gc_requester.request();
+            return true;
+        }
         false
     }
 
+    /// Start pacing a concurrent collection cycle.  `work` is the estimated bytes to mark in the
+    /// cycle.  The mutators may allocate until the heap is full before marking finishes.  See
+    /// [`crate::util::pacer`].
+    pub fn start_concurrent_cycle(&self, work: usize) {
+        let headroom_pages = self
+            .policy
+            .get_current_heap_size_in_pages()
+            .saturating_sub(self.plan().get_reserved_pages());
+        self.pacer
+            .start_cycle(work, headroom_pages << LOG_BYTES_IN_PAGE);
+    }
+
+    /// Stop pacing the current concurrent collection cycle, and return the bytes marked in it.  The
+    /// plan should call this in the pause that finishes marking.
+    pub fn end_concurrent_cycle(&self) -> usize {
+        self.pacer.end_cycle()
+    }
+
+    /// Return `true` if a concurrent collection cycle is being paced.
+    pub fn is_in_concurrent_cycle(&self) -> bool {
+        self.pacer.is_in_cycle()
+    }
+
+    /// Report that the collector marked objects of `bytes` bytes in the current concurrent cycle.
+    pub fn record_marked(&self, bytes: usize) {
+        self.pacer.record_marked(bytes);
+    }
+
+    /// Report that a mutator allocated `bytes` bytes.  This is called from the allocation slow
+    /// path, and only counts towards the current concurrent cycle, if any.
+    pub(crate) fn record_allocation(&self, bytes: usize) {
+        self.pacer.record_allocation(bytes);
+    }
+
+    /// Decide how the plan should keep up with the mutators in the current concurrent cycle, based
+    /// on the marking progress and the recent allocation rate.  This returns
+    /// [`PacingAction::None`] if no cycle is in progress.
+    pub fn pacing_action(&self) -> PacingAction {
+        self.pacer.evaluate(self.state.get_allocation_rate())
+    }
+
+    /// Check if the policy would trigger a GC now, without requesting a GC.  This is used when a
+    /// GC is not permitted, and we only need to know whether we would have triggered one.
+    ///
+    /// Arguments:
+    /// * `space`: The space that triggered the poll. This could `None` if the poll is not triggered by a space.
+    pub fn is_gc_required(&self, space: Option<&dyn Space<VM>>) -> bool {
+        self.policy
+            .is_gc_required(false, space.map(|s| SpaceStats::new(s)), self.plan())
+    }
+
+    /// Poll the policy while GC is disabled by a [`crate::GCDisabledScope`].  This never requests a
+    /// GC.  If the policy would have triggered a GC, the suppressed GC is recorded, and we warn and
+    /// inform the binding when the number of suppressed GCs reaches a power of two.
+    ///
+    /// Arguments:
+    /// * `tls`: The mutator that would have been blocked for the GC.
+    /// * `space`: The space that triggered the poll. This could `None` if the poll is not triggered by a space.
+    pub fn poll_while_gc_disabled(&self, tls: VMMutatorThread, space: Option<&dyn Space<VM>>) {
+        if !self.is_gc_required(space) {
+            return;
+        }
+        let suppressed = self.state.record_gc_suppressed();
+        if suppressed.is_power_of_two() {
+            warn!(
+                "A GC is required, but GC is disabled by a GC-disabled scope ({} GCs suppressed so far, {} pages reserved, {} pages in the heap)",
+                suppressed,
+                self.plan().get_reserved_pages(),
+                self.plan().get_total_pages(),
+            );
+            <VM::VMCollection as crate::vm::Collection<VM>>::gc_suppressed(tls, suppressed);
+        }
+    }
+
     pub fn should_do_stress_gc(&self) -> bool {
         Self::should_do_stress_gc_inner(&self.state, &self.options)
     }
@@ -118,6 +207,29 @@ impl<VM: VMBinding> GCTrigger<VM> {
         self.policy.is_heap_full(self.plan())
     }
 
+    /// Check if the heap would be full if `pages` more pages were reserved, i.e. if acquiring them
+    /// from a space would likely trigger a GC.
+    pub(crate) fn is_heap_full_after(&self, pages: usize) -> bool {
+        self.plan().get_reserved_pages() + pages > self.policy.get_current_heap_size_in_pages()
+    }
+
+    /// Create the context of an allocation error with the current heap usage and the heap size
+    /// limit.
+    pub(crate) fn allocation_error_context(
+        &self,
+        space: Option<&'static str>,
+        requested_bytes: usize,
+        emergency_collection_attempted: bool,
+    ) -> AllocationErrorContext {
+        AllocationErrorContext {
+            space,
+            requested_bytes,
+            used_bytes: Some(self.plan().get_reserved_pages() << LOG_BYTES_IN_PAGE),
+            max_bytes: Some(self.policy.get_max_heap_size_in_pages() << LOG_BYTES_IN_PAGE),
+            emergency_collection_attempted,
+        }
+    }
+
     /// Return upper bound of the nursery size (in number of bytes)
     pub fn get_max_nursery_bytes(&self) -> usize {
         use crate::util::options::NurserySize;
@@ -136,6 +248,16 @@ impl<VM: VMBinding> GCTrigger<VM> {
                     max_bytes
                 }
             }
+            NurserySize::AllocationRateBounded { min, max, millis } => {
+                let rate = self.state.get_allocation_rate();
+                if rate == 0f64 {
+                    // No allocation rate yet.
+                    max
+                } else {
+                    let bytes = (rate * millis as f64) as usize;
+                    conversions::raw_align_up(bytes, BYTES_IN_PAGE).clamp(min, max)
+                }
+            }
             NurserySize::Fixed(sz) => sz,
         }
     }
@@ -159,6 +281,7 @@ impl<VM: VMBinding> GCTrigger<VM> {
                     min_bytes
                 }
             }
+            NurserySize::AllocationRateBounded { min, .. } => min,
             NurserySize::Fixed(sz) => sz,
         }
     }
@@ -351,6 +474,7 @@ impl MemBalancerStats {
     // Collect mem stats for generational plans:
     // * We ignore nursery GCs.
     // * allocation = objects in mature space = promoted + pretentured = live pages in mature space before release - live pages at the end of last mature GC
+    // * Large objects count as mature once they survive a GC, i.e. when they are promoted.
     // * collection = live pages in mature space at the end of GC -  live pages in mature space before release
 
     fn generational_mem_stats_on_gc_start<VM: VMBinding>(
@@ -409,6 +533,19 @@ impl MemBalancerStats {
     // * collection = live pages at the end of GC - live pages before release
 
     fn non_generational_mem_stats_on_gc_start<VM: VMBinding>(&mut self, mmtk: &'static MMTK<VM>) {
+        let rate = mmtk.get_allocation_rate();
+        if rate > 0f64 {
+            // Estimate from the recent allocation rate rather than the average since the last GC,
+            // so the heap limit follows changes in the allocation behavior of the application.
+            self.allocation_pages = rate * self.allocation_time * 1000f64 / BYTES_IN_PAGE as f64;
+            trace!(
+                "allocated pages = rate {} bytes/ms * allocation time {} s = {}",
+                rate,
+                self.allocation_time,
+                self.allocation_pages
+            );
+            return;
+        }
         self.allocation_pages = mmtk
             .get_plan()
             .get_reserved_pages()
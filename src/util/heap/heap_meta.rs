@@ -1,9 +1,12 @@
 use crate::util::heap::layout::vm_layout::vm_layout;
+use crate::util::heap::space_index;
 use crate::util::Address;
 
 pub struct HeapMeta {
     pub heap_cursor: Address,
     pub heap_limit: Address,
+    /// The index for the next space.  See [`crate::util::heap::space_index`].
+    next_space_index: u8,
 }
 
 impl HeapMeta {
@@ -11,9 +14,23 @@ impl HeapMeta {
         HeapMeta {
             heap_cursor: vm_layout().heap_start,
             heap_limit: vm_layout().heap_end,
+            next_space_index: space_index::UNKNOWN_SPACE_INDEX + 1,
         }
     }
 
+    /// Get a unique index for a new space.  The indices are unique among the spaces of all the
+    /// MMTk instances, as the heap meta is shared by them.
+    pub fn new_space_index(&mut self) -> u8 {
+        let index = self.next_space_index;
+        assert!(
+            index <= space_index::MAX_SPACE_INDEX,
+            "Too many spaces: the space index cannot exceed {}",
+            space_index::MAX_SPACE_INDEX
+        );
+        self.next_space_index += 1;
+        index
+    }
+
     pub fn reserve(&mut self, extent: usize, top: bool) -> Address {
         let ret = if top {
             self.heap_limit -= extent;
@@ -1,12 +1,13 @@
 use super::mmapper::MapState;
 use super::Mmapper;
+use crate::util::heap_layout::MmapChunkRange;
 use crate::util::memory::MmapAnnotation;
 use crate::util::Address;
 
 use crate::util::constants::*;
 use crate::util::conversions::pages_to_bytes;
 use crate::util::heap::layout::vm_layout::*;
-use crate::util::memory::MmapStrategy;
+use crate::util::memory::{mmap_noreserve, munmap, MmapStrategy};
 use std::fmt;
 use std::sync::atomic::Ordering;
 use std::sync::Mutex;
@@ -45,6 +46,14 @@ impl Mmapper for ByteMapMmapper {
         }
     }
 
+    fn mark_as_unmapped(&self, start: Address, bytes: usize) {
+        let start_chunk = Self::address_to_mmap_chunks_down(start);
+        let end_chunk = Self::address_to_mmap_chunks_up(start + bytes) - 1;
+        for i in start_chunk..=end_chunk {
+            self.mapped[i].store(MapState::Unmapped, Ordering::Relaxed);
+        }
+    }
+
     fn ensure_mapped(
         &self,
         start: Address,
@@ -129,6 +138,33 @@ impl Mmapper for ByteMapMmapper {
             MapState::transition_to_protected(&self.mapped[chunk], mmap_start).unwrap();
         }
     }
+
+    fn release_quarantined_ranges(&self) -> Result<()> {
+        let _guard = self.lock.lock().unwrap();
+        MapState::for_each_quarantined_range(&self.mapped, Address::ZERO, |start, bytes| {
+            trace!("Releasing quarantined {} - {}", start, start + bytes);
+            munmap(start, bytes)
+        })
+    }
+
+    fn requarantine_released_ranges(
+        &self,
+        strategy: MmapStrategy,
+        anno: &MmapAnnotation,
+    ) -> Result<()> {
+        let _guard = self.lock.lock().unwrap();
+        MapState::for_each_quarantined_range(&self.mapped, Address::ZERO, |start, bytes| {
+            trace!("Re-quarantining {} - {}", start, start + bytes);
+            mmap_noreserve(start, bytes, strategy, anno)
+        })
+    }
+
+    fn chunk_ranges(&self) -> Vec<MmapChunkRange> {
+        let _guard = self.lock.lock().unwrap();
+        let mut ranges = vec![];
+        MapState::collect_chunk_ranges(&self.mapped, Address::ZERO, &mut ranges);
+        ranges
+    }
 }
 
 impl ByteMapMmapper {
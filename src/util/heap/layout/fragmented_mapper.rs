@@ -3,19 +3,23 @@ use super::Mmapper;
 use crate::util::constants::BYTES_IN_PAGE;
 use crate::util::conversions;
 use crate::util::heap::layout::vm_layout::*;
-use crate::util::memory::{MmapAnnotation, MmapStrategy};
+use crate::util::heap_layout::MmapChunkRange;
+use crate::util::memory::{self, MmapAnnotation, MmapStrategy};
 use crate::util::Address;
 use atomic::{Atomic, Ordering};
-use std::cell::UnsafeCell;
 use std::fmt;
 use std::io::Result;
-use std::sync::Mutex;
+use std::sync::atomic::AtomicUsize;
+use std::sync::{Mutex, OnceLock};
 
 const MMAP_NUM_CHUNKS: usize = 1 << (33 - LOG_MMAP_CHUNK_BYTES);
 
 // 36 = 128G - physical memory larger than this is uncommon
 // 40 = 2T. Increased to 2T. Though we probably won't use this much memory, we allow quarantine memory range,
 // and that is usually used to quarantine a large amount of memory.
+// This only sizes the first level of the slab table.  When it fills up, more levels are added, so
+// the mapper is not limited to this many bytes, and slabs can be anywhere in the address space,
+// including above 48 bits (e.g. with 5-level paging on x86_64).
 const LOG_MAPPABLE_BYTES: usize = 40;
 
 /*
@@ -28,35 +32,86 @@ const LOG_MMAP_SLAB_BYTES: usize = LOG_MMAP_CHUNKS_PER_SLAB + LOG_MMAP_CHUNK_BYT
 const MMAP_SLAB_EXTENT: usize = 1 << LOG_MMAP_SLAB_BYTES;
 const MMAP_SLAB_MASK: usize = (1 << LOG_MMAP_SLAB_BYTES) - 1;
 /**
- * Maximum number of slabs, which determines the maximum mappable address space.
+ * Maximum number of slabs in the first level of the slab table.
  */
 const LOG_MAX_SLABS: usize = LOG_MAPPABLE_BYTES - LOG_MMAP_CHUNK_BYTES - LOG_MMAP_CHUNKS_PER_SLAB;
-const MAX_SLABS: usize = 1 << LOG_MAX_SLABS;
 /**
- * Parameters for the slab table.  The hash function requires it to be
- * a power of 2.  Must be larger than MAX_SLABS for hashing to work,
+ * Parameters for the first level of the slab table.  The hash function requires it to be
+ * a power of 2.  Must be larger than the number of slabs in it for hashing to work,
  * and should be much larger for it to be efficient.
  */
 const LOG_SLAB_TABLE_SIZE: usize = 1 + LOG_MAX_SLABS;
-const HASH_MASK: usize = (1 << LOG_SLAB_TABLE_SIZE) - 1;
 const SLAB_TABLE_SIZE: usize = 1 << LOG_SLAB_TABLE_SIZE;
+/// Each level of the slab table is twice as large as the previous level.  With 16 levels, the
+/// mapper can track `2^16` times as many slabs as the first level, i.e. 128 PB of memory.
+const MAX_SLAB_TABLE_LEVELS: usize = 16;
 const SENTINEL: Address = Address::MAX;
 
 type Slab = [Atomic<MapState>; MMAP_NUM_CHUNKS];
 
 pub struct FragmentedMapper {
     lock: Mutex<()>,
-    inner: UnsafeCell<InnerFragmentedMapper>,
+    /// The levels of the slab table.  A slab is looked up in each initialized level in order.
+    /// Levels and slabs are only added while holding the lock, and are never moved or removed, so
+    /// they can be looked up without the lock.
+    levels: [OnceLock<SlabTableLevel>; MAX_SLAB_TABLE_LEVELS],
 }
 
-unsafe impl Send for FragmentedMapper {}
-unsafe impl Sync for FragmentedMapper {}
+/// One level of the slab table, which is an open-addressing hash table from slab base addresses to
+/// slabs.  Entries are never removed, so a lookup can stop at the first `SENTINEL`.
+struct SlabTableLevel {
+    log_size: usize,
+    /// The number of slabs in this level.  This is only updated while holding the lock.
+    used: AtomicUsize,
+    slab_table: Vec<OnceLock<Box<Slab>>>,
+    /// The base addresses of the slabs.  An entry is set after the slab is in `slab_table`.
+    slab_map: Vec<Atomic<Address>>,
+}
+
+impl SlabTableLevel {
+    fn new(log_size: usize) -> Self {
+        Self {
+            log_size,
+            used: AtomicUsize::new(0),
+            slab_table: (0..(1 << log_size)).map(|_| OnceLock::new()).collect(),
+            slab_map: (0..(1 << log_size))
+                .map(|_| Atomic::new(SENTINEL))
+                .collect(),
+        }
+    }
+
+    /// Return true if this level is too full to keep the hash table efficient.
+    fn is_full(&self) -> bool {
+        self.used.load(Ordering::Relaxed) * 2 >= self.slab_map.len()
+    }
+
+    /// Find the index of the slab with the given base, or the index of the free slot where the
+    /// slab should be inserted.
+    fn probe(&self, base: Address) -> usize {
+        let size = self.slab_map.len();
+        let hash = FragmentedMapper::hash(base, self.log_size);
+        let mut index = hash;
+        loop {
+            let entry = self.slab_map[index].load(Ordering::Acquire);
+            if entry == base || entry == SENTINEL {
+                return index;
+            }
+            index = (index + 1) % size;
+            assert!(index != hash, "MMAP slab table is full!");
+        }
+    }
 
-struct InnerFragmentedMapper {
-    free_slab_index: usize,
-    free_slabs: Vec<Option<Box<Slab>>>,
-    slab_table: Vec<Option<Box<Slab>>>,
-    slab_map: Vec<Address>,
+    /// Get the slab with the given base in this level, if any.
+    fn find(&self, base: Address) -> Option<&Slab> {
+        let index = self.probe(base);
+        if self.slab_map[index].load(Ordering::Acquire) == base {
+            let slab = self.slab_table[index].get();
+            debug_assert!(slab.is_some());
+            slab.map(|x| x as &Slab)
+        } else {
+            None
+        }
+    }
 }
 
 impl fmt::Debug for FragmentedMapper {
@@ -89,6 +144,29 @@ impl Mmapper for FragmentedMapper {
         }
     }
 
+    fn mark_as_unmapped(&self, mut start: Address, bytes: usize) {
+        let end = start + bytes;
+        // Iterate over the slabs covered
+        while start < end {
+            let high = if end > Self::slab_limit(start) && !Self::slab_limit(start).is_zero() {
+                Self::slab_limit(start)
+            } else {
+                end
+            };
+            let slab = Self::slab_align_down(start);
+            let start_chunk = Self::chunk_index(slab, start);
+            let end_chunk = Self::chunk_index(slab, conversions::mmap_chunk_align_up(high));
+
+            // If there is no slab for the address, nothing is mapped there.
+            if let Some(mapped) = self.slab_table(start) {
+                for entry in mapped.iter().take(end_chunk).skip(start_chunk) {
+                    entry.store(MapState::Unmapped, Ordering::Relaxed);
+                }
+            }
+            start = high;
+        }
+    }
+
     fn quarantine_address_range(
         &self,
         mut start: Address,
@@ -228,17 +306,54 @@ impl Mmapper for FragmentedMapper {
             start = high;
         }
     }
+
+    fn release_quarantined_ranges(&self) -> Result<()> {
+        let _guard = self.lock.lock().unwrap();
+        self.for_each_slab(|base, slab| {
+            MapState::for_each_quarantined_range(slab, base, |start, bytes| {
+                trace!("Releasing quarantined {} - {}", start, start + bytes);
+                memory::munmap(start, bytes)
+            })
+        })
+    }
+
+    fn requarantine_released_ranges(
+        &self,
+        strategy: MmapStrategy,
+        anno: &MmapAnnotation,
+    ) -> Result<()> {
+        let _guard = self.lock.lock().unwrap();
+        self.for_each_slab(|base, slab| {
+            MapState::for_each_quarantined_range(slab, base, |start, bytes| {
+                trace!("Re-quarantining {} - {}", start, start + bytes);
+                memory::mmap_noreserve(start, bytes, strategy, anno)
+            })
+        })
+    }
+
+    fn chunk_ranges(&self) -> Vec<MmapChunkRange> {
+        let _guard = self.lock.lock().unwrap();
+        let mut ranges = vec![];
+        self.for_each_slab(|base, slab| {
+            MapState::collect_chunk_ranges(slab, base, &mut ranges);
+            Ok(())
+        })
+        .unwrap();
+        ranges
+    }
 }
 
 impl FragmentedMapper {
     pub fn new() -> Self {
         Self {
             lock: Mutex::new(()),
-            inner: UnsafeCell::new(InnerFragmentedMapper {
-                free_slab_index: 0,
-                free_slabs: (0..MAX_SLABS).map(|_| Some(Self::new_slab())).collect(),
-                slab_table: (0..SLAB_TABLE_SIZE).map(|_| None).collect(),
-                slab_map: vec![SENTINEL; SLAB_TABLE_SIZE],
+            // Only the first level is created up front.
+            levels: std::array::from_fn(|i| {
+                if i == 0 {
+                    OnceLock::from(SlabTableLevel::new(LOG_SLAB_TABLE_SIZE))
+                } else {
+                    OnceLock::new()
+                }
             }),
         }
     }
@@ -261,12 +376,29 @@ impl FragmentedMapper {
         mapped
     }
 
-    fn hash(addr: Address) -> usize {
+    /// Call `f` with the base address and the chunk states of each allocated slab.  The caller
+    /// should hold the lock so that no slab is allocated concurrently.
+    fn for_each_slab(&self, mut f: impl FnMut(Address, &Slab) -> Result<()>) -> Result<()> {
+        for level in self.levels() {
+            for (base, slab) in level.slab_map.iter().zip(level.slab_table.iter()) {
+                if let Some(slab) = slab.get() {
+                    let base = base.load(Ordering::Relaxed);
+                    debug_assert_ne!(base, SENTINEL);
+                    f(base, slab)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Hash the slab address for a slab table level of `1 << log_table_size` entries.  All the
+    /// bits of the address are folded in, so any address in the 64-bit address space works.
+    fn hash(addr: Address, log_table_size: usize) -> usize {
         let mut initial = (addr & !MMAP_SLAB_MASK) >> LOG_MMAP_SLAB_BYTES;
         let mut hash = 0;
         while initial != 0 {
-            hash ^= initial & HASH_MASK;
-            initial >>= LOG_SLAB_TABLE_SIZE;
+            hash ^= initial & ((1 << log_table_size) - 1);
+            initial >>= log_table_size;
         }
         hash
     }
@@ -280,12 +412,9 @@ impl FragmentedMapper {
             .unwrap()
     }
 
-    fn inner(&self) -> &InnerFragmentedMapper {
-        unsafe { &*self.inner.get() }
-    }
-    #[allow(clippy::mut_from_ref)]
-    fn inner_mut(&self) -> &mut InnerFragmentedMapper {
-        unsafe { &mut *self.inner.get() }
+    /// The levels of the slab table that are in use.
+    fn levels(&self) -> impl Iterator<Item = &SlabTableLevel> {
+        self.levels.iter().map_while(|level| level.get())
     }
 
     fn get_or_optionally_allocate_slab_table(
@@ -295,65 +424,51 @@ impl FragmentedMapper {
     ) -> Option<&Slab> {
         debug_assert!(addr != SENTINEL);
         let base = unsafe { Address::from_usize(addr & !MMAP_SLAB_MASK) };
-        let hash = Self::hash(base);
-        let mut index = hash; // Use 'index' to iterate over the hash table so that we remember where we started
-        loop {
-            /* Check for a hash-table hit.  Should be the frequent case. */
-            if base == self.inner().slab_map[index] {
-                return self.slab_table_for(addr, index);
-            }
-            let _guard = self.lock.lock().unwrap();
-
-            /* Check whether another thread has allocated a slab while we were acquiring the lock */
-            if base == self.inner().slab_map[index] {
-                // drop(guard);
-                return self.slab_table_for(addr, index);
-            }
+        /* Check for a hash-table hit.  Should be the frequent case. */
+        if let Some(slab) = self.find_slab(base) {
+            return Some(slab);
+        }
+        if !allocate {
+            return None;
+        }
+        let _guard = self.lock.lock().unwrap();
 
-            /* Check for a free slot */
-            if self.inner().slab_map[index] == SENTINEL {
-                if !allocate {
-                    // drop(guard);
-                    return None;
-                }
-                unsafe {
-                    self.commit_free_slab(index);
-                }
-                self.inner_mut().slab_map[index] = base;
-                return self.slab_table_for(addr, index);
-            }
-            //   lock.release();
-            index += 1;
-            index %= SLAB_TABLE_SIZE;
-            assert!(index != hash, "MMAP slab table is full!");
+        /* Check whether another thread has allocated a slab while we were acquiring the lock */
+        if let Some(slab) = self.find_slab(base) {
+            return Some(slab);
         }
+        unsafe { Some(self.insert_slab(base)) }
     }
 
-    fn slab_table_for(&self, _addr: Address, index: usize) -> Option<&Slab> {
-        debug_assert!(self.inner().slab_table[index].is_some());
-        self.inner().slab_table[index].as_ref().map(|x| x as &Slab)
+    fn find_slab(&self, base: Address) -> Option<&Slab> {
+        self.levels().find_map(|level| level.find(base))
     }
 
     /**
-     * Take a free slab of chunks from the freeSlabs array, and insert it
-     * at the correct index in the slabTable.
-     * @param index slab table index
+     * Allocate a slab of chunks, and insert it into the last level of the slab table, adding a
+     * level if the last level is full.
+     * @param base The base address of the slab
      */
     /// # Safety
     ///
-    /// Caller must ensure that only one thread is calling this function at a time.
-    unsafe fn commit_free_slab(&self, index: usize) {
-        assert!(
-            self.inner().free_slab_index < MAX_SLABS,
-            "All free slabs used: virtual address space is exhausled."
-        );
-        debug_assert!(self.inner().slab_table[index].is_none());
-        debug_assert!(self.inner().free_slabs[self.inner().free_slab_index].is_some());
-        ::std::mem::swap(
-            &mut self.inner_mut().slab_table[index],
-            &mut self.inner_mut().free_slabs[self.inner().free_slab_index],
-        );
-        self.inner_mut().free_slab_index += 1;
+    /// Caller must hold the lock, and ensure that the slab is not in the slab table.
+    unsafe fn insert_slab(&self, base: Address) -> &Slab {
+        let num_levels = self.levels().count();
+        let mut level = self.levels[num_levels - 1].get().unwrap();
+        if level.is_full() {
+            assert!(
+                num_levels < MAX_SLAB_TABLE_LEVELS,
+                "All slab table levels used: virtual address space is exhausted."
+            );
+            let log_size = level.log_size + 1;
+            level = self.levels[num_levels].get_or_init(|| SlabTableLevel::new(log_size));
+        }
+        let index = level.probe(base);
+        let slab = level.slab_table[index].get_or_init(Self::new_slab);
+        level.used.fetch_add(1, Ordering::Relaxed);
+        // Publish the slab after it is in place, since readers check `slab_map` without the lock.
+        level.slab_map[index].store(base, Ordering::Release);
+        slab
     }
 
     fn chunk_index_to_address(base: Address, chunk: usize) -> Address {
@@ -425,23 +540,67 @@ mod tests {
         for i in 0..10 {
             unsafe {
                 let a = i << LOG_MMAP_SLAB_BYTES;
-                assert_eq!(FragmentedMapper::hash(Address::from_usize(a)), i);
+                assert_eq!(
+                    FragmentedMapper::hash(Address::from_usize(a), LOG_SLAB_TABLE_SIZE),
+                    i
+                );
 
                 let b = a + ((i + 1) << (LOG_MMAP_SLAB_BYTES + LOG_SLAB_TABLE_SIZE + 1));
                 assert_eq!(
-                    FragmentedMapper::hash(Address::from_usize(b)),
+                    FragmentedMapper::hash(Address::from_usize(b), LOG_SLAB_TABLE_SIZE),
                     i ^ ((i + 1) << 1)
                 );
 
                 let c = b + ((i + 2) << (LOG_MMAP_SLAB_BYTES + LOG_SLAB_TABLE_SIZE * 2 + 2));
                 assert_eq!(
-                    FragmentedMapper::hash(Address::from_usize(c)),
+                    FragmentedMapper::hash(Address::from_usize(c), LOG_SLAB_TABLE_SIZE),
                     i ^ ((i + 1) << 1) ^ ((i + 2) << 2)
                 );
             }
         }
     }
 
+    #[test]
+    fn high_addresses() {
+        // Only the chunk states are changed.  No memory is actually mapped.
+        let mmapper = FragmentedMapper::new();
+        // More slabs than the first level can hold, all above 48 bits.
+        let num_slabs = SLAB_TABLE_SIZE * 2;
+        let slab_address =
+            |i: usize| unsafe { Address::from_usize((1usize << 56) + (i << LOG_MMAP_SLAB_BYTES)) };
+        for i in 0..num_slabs {
+            mmapper.mark_as_mapped(slab_address(i), MMAP_CHUNK_BYTES);
+        }
+        assert!(mmapper.levels().count() > 1);
+        for i in 0..num_slabs {
+            assert!(mmapper.is_mapped_address(slab_address(i)));
+            assert!(!mmapper.is_mapped_address(slab_address(i) + MMAP_CHUNK_BYTES));
+        }
+        assert!(!mmapper.is_mapped_address(slab_address(num_slabs)));
+    }
+
+    #[test]
+    fn lookup_while_adding_levels() {
+        let mmapper = FragmentedMapper::new();
+        let num_slabs = SLAB_TABLE_SIZE * 2;
+        let slab_address =
+            |i: usize| unsafe { Address::from_usize((1usize << 56) + (i << LOG_MMAP_SLAB_BYTES)) };
+        mmapper.mark_as_mapped(slab_address(0), MMAP_CHUNK_BYTES);
+        std::thread::scope(|scope| {
+            // Look up the first slab without the lock while other slabs and levels are added.
+            let reader = scope.spawn(|| {
+                while mmapper.levels().count() == 1 {
+                    assert!(mmapper.is_mapped_address(slab_address(0)));
+                }
+            });
+            for i in 1..num_slabs {
+                mmapper.mark_as_mapped(slab_address(i), MMAP_CHUNK_BYTES);
+            }
+            reader.join().unwrap();
+        });
+        assert!((0..num_slabs).all(|i| mmapper.is_mapped_address(slab_address(i))));
+    }
+
     #[test]
     fn ensure_mapped_1page() {
         serial_test(|| {
@@ -617,4 +776,74 @@ mod tests {
             )
         })
     }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn release_and_requarantine() {
+        serial_test(|| {
+            with_cleanup(
+                || {
+                    // quarantine 2 chunks, and map the first one
+                    let mmapper = FragmentedMapper::new();
+                    let pages_per_chunk = MMAP_CHUNK_BYTES >> LOG_BYTES_IN_PAGE as usize;
+                    mmapper
+                        .quarantine_address_range(
+                            FIXED_ADDRESS,
+                            pages_per_chunk * 2,
+                            MmapStrategy::TEST,
+                            mmap_anno_test!(),
+                        )
+                        .unwrap();
+                    mmapper
+                        .ensure_mapped(
+                            FIXED_ADDRESS,
+                            pages_per_chunk,
+                            MmapStrategy::TEST,
+                            mmap_anno_test!(),
+                        )
+                        .unwrap();
+                    let quarantined = FIXED_ADDRESS + MMAP_CHUNK_BYTES;
+
+                    // The quarantined chunk is unmapped, but is still recorded as quarantined.
+                    mmapper.release_quarantined_ranges().unwrap();
+                    assert_eq!(
+                        get_chunk_map_state(&mmapper, FIXED_ADDRESS),
+                        Some(MapState::Mapped)
+                    );
+                    assert_eq!(
+                        get_chunk_map_state(&mmapper, quarantined),
+                        Some(MapState::Quarantined)
+                    );
+                    // MAP_FIXED_NOREPLACE succeeds only if nothing is mapped there.
+                    memory::mmap_noreserve(
+                        quarantined,
+                        MMAP_CHUNK_BYTES,
+                        MmapStrategy::TEST,
+                        mmap_anno_test!(),
+                    )
+                    .unwrap();
+                    memory::munmap(quarantined, MMAP_CHUNK_BYTES).unwrap();
+
+                    // The chunk is reserved again at the same address.
+                    mmapper
+                        .requarantine_released_ranges(MmapStrategy::TEST, mmap_anno_test!())
+                        .unwrap();
+                    assert_eq!(
+                        get_chunk_map_state(&mmapper, quarantined),
+                        Some(MapState::Quarantined)
+                    );
+                    assert!(memory::mmap_noreserve(
+                        quarantined,
+                        MMAP_CHUNK_BYTES,
+                        MmapStrategy::TEST,
+                        mmap_anno_test!(),
+                    )
+                    .is_err());
+                },
+                || {
+                    memory::munmap(FIXED_ADDRESS, MAX_BYTES).unwrap();
+                },
+            )
+        })
+    }
 }
@@ -300,6 +300,7 @@ impl Map32 {
                 debug!("Clear descriptor for Chunk {}", chunk_start);
                 self.mut_self().descriptor_map[index] = SpaceDescriptor::UNINITIALIZED;
                 SFT_MAP.clear(chunk_start);
+                crate::util::heap::space_index::clear(chunk_start);
             }
             chunks as _
         }
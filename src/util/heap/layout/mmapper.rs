@@ -1,4 +1,5 @@
 use crate::util::heap::layout::vm_layout::*;
+use crate::util::heap_layout::{MmapChunkRange, MmapChunkState};
 use crate::util::memory::*;
 use crate::util::rust_util::rev_group::RevisitableGroupByForIterator;
 use crate::util::Address;
@@ -25,6 +26,15 @@ pub trait Mmapper: Sync {
     /// * `bytes`: Number of bytes to ensure mapped
     fn mark_as_mapped(&self, start: Address, bytes: usize);
 
+    /// Mark a number of pages as unmapped, without making any request to the operating system.
+    /// Used to mark pages that the VM has mapped and marked with [`Mmapper::mark_as_mapped`], but
+    /// has unmapped (or will unmap) itself.
+    ///
+    /// Arguments:
+    /// * `start`: Address of the first page to be marked as unmapped
+    /// * `bytes`: Number of bytes to mark as unmapped
+    fn mark_as_unmapped(&self, start: Address, bytes: usize);
+
     /// Quarantine/reserve address range. We mmap from the OS with no reserve and with PROT_NONE,
     /// which should be little overhead. This ensures that we can reserve certain address range that
     /// we can use if needed. Quarantined memory needs to be mapped before it can be used.
@@ -76,6 +86,30 @@ pub trait Mmapper: Sync {
     /// * `start`: Address of the first page to be protected
     /// * `pages`: Number of pages to be protected
     fn protect(&self, start: Address, pages: usize);
+
+    /// Unmap all the quarantined address ranges, but still record them as quarantined.  This is
+    /// used before checkpointing the process so that the checkpoint does not include the large
+    /// address ranges that are reserved but not used.  The ranges need to be reserved again with
+    /// [`Mmapper::requarantine_released_ranges`] when the process is restored.  Mapping a released
+    /// chunk with [`Mmapper::ensure_mapped`] in the meantime is still allowed.
+    fn release_quarantined_ranges(&self) -> Result<()>;
+
+    /// Quarantine the address ranges released by [`Mmapper::release_quarantined_ranges`] again,
+    /// at their original addresses.
+    ///
+    /// Arguments:
+    /// * `strategy`: The mmap strategy.  The `prot` field is ignored because we always use
+    ///   `PROT_NONE`.
+    /// * `anno`: Human-readable annotation to apply to the re-quarantined memory ranges.
+    fn requarantine_released_ranges(
+        &self,
+        strategy: MmapStrategy,
+        anno: &MmapAnnotation,
+    ) -> Result<()>;
+
+    /// Get the ranges of consecutive mmap chunks that are in the same state, for describing the
+    /// heap layout.  Unmapped chunks are skipped.  The ranges are not sorted.
+    fn chunk_ranges(&self) -> Vec<MmapChunkRange>;
 }
 
 /// The mmap state of a mmap chunk.
@@ -221,6 +255,62 @@ impl MapState {
         Ok(())
     }
 
+    /// Call `f` with the start address and the size of each contiguous range of quarantined chunks
+    /// in `states`.  The chunk of `states[0]` starts at `mmap_start`.  Their states are not
+    /// changed.  The caller should hold a lock before invoking this method.
+    /// Append the ranges of consecutive chunks in the same state, except unmapped chunks, to
+    /// `ranges`.  The caller should hold a lock before invoking this method.
+    pub(super) fn collect_chunk_ranges(
+        states: &[Atomic<MapState>],
+        mmap_start: Address,
+        ranges: &mut Vec<MmapChunkRange>,
+    ) {
+        let mut start_index = 0;
+
+        for group in states
+            .iter()
+            .revisitable_group_by(|s| s.load(Ordering::Relaxed))
+        {
+            let end_index = start_index + group.len;
+            let state = match group.key {
+                MapState::Unmapped => None,
+                MapState::Quarantined => Some(MmapChunkState::Quarantined),
+                MapState::Mapped => Some(MmapChunkState::Mapped),
+                MapState::Protected => Some(MmapChunkState::Protected),
+            };
+            if let Some(state) = state {
+                ranges.push(MmapChunkRange {
+                    range: mmap_start + MMAP_CHUNK_BYTES * start_index
+                        ..mmap_start + MMAP_CHUNK_BYTES * end_index,
+                    state,
+                });
+            }
+            start_index = end_index;
+        }
+    }
+
+    pub(super) fn for_each_quarantined_range(
+        states: &[Atomic<MapState>],
+        mmap_start: Address,
+        mut f: impl FnMut(Address, usize) -> Result<()>,
+    ) -> Result<()> {
+        let mut start_index = 0;
+
+        for group in states
+            .iter()
+            .revisitable_group_by(|s| s.load(Ordering::Relaxed))
+        {
+            let end_index = start_index + group.len;
+            if group.key == MapState::Quarantined {
+                let start_addr = mmap_start + MMAP_CHUNK_BYTES * start_index;
+                f(start_addr, MMAP_CHUNK_BYTES * group.len)?;
+            }
+            start_index = end_index;
+        }
+
+        Ok(())
+    }
+
     /// Check the current MapState of the chunk, and transition the chunk to MapState::Protected.
     /// The caller should hold a lock before invoking this method.
     pub(super) fn transition_to_protected(
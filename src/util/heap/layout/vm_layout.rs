@@ -42,6 +42,86 @@ pub struct VMLayout {
     pub force_use_contiguous_spaces: bool,
 }
 
+/// Where the heap is placed for compressed pointers, and how the base of the encoding is chosen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompressedPointerMode {
+    /// The base is zero, so that an address is compressed with only a shift. The heap is placed in
+    /// the low `4GB << shift` of the address space.
+    ZeroBased {
+        /// The number of bits to shift.
+        shift: usize,
+    },
+    /// The base is chosen by MMTk, and the heap is placed right above the base.
+    HeapBased {
+        /// The number of bits to shift.
+        shift: usize,
+    },
+    /// The base is given by the binding, and the heap is placed right above the base. The base
+    /// must be chunk aligned.
+    Custom {
+        /// The base of the encoding.
+        base: Address,
+        /// The number of bits to shift.
+        shift: usize,
+    },
+}
+
+#[cfg(target_pointer_width = "64")]
+impl CompressedPointerMode {
+    /// The encoding of compressed pointers in this mode.  This can be queried before or after the
+    /// layout is set, so that the binding can emit matching code for compressing and decompressing
+    /// pointers.
+    pub fn encoding(&self) -> CompressedPointerEncoding {
+        let (base, shift) = match *self {
+            CompressedPointerMode::ZeroBased { shift } => (Address::ZERO, shift),
+            CompressedPointerMode::HeapBased { shift } => (
+                unsafe { Address::from_usize(VMLayout::HEAP_BASED_BASE) },
+                shift,
+            ),
+            CompressedPointerMode::Custom { base, shift } => (base, shift),
+        };
+        CompressedPointerEncoding { base, shift }
+    }
+}
+
+/// The parameters of compressed pointers. An address `a` is compressed to `(a - base) >> shift`,
+/// and a compressed pointer `c` is decompressed to `base + (c << shift)`. The heap starts above
+/// `base`, so the compressed pointer 0 always means null.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CompressedPointerEncoding {
+    /// The base of the encoding.
+    pub base: Address,
+    /// The number of bits to shift.
+    pub shift: usize,
+}
+
+impl CompressedPointerEncoding {
+    /// The number of bytes that compressed pointers can address above `base`.
+    pub const fn addressable_bytes(&self) -> usize {
+        (u32::MAX as usize + 1) << self.shift
+    }
+
+    /// Compress the address. The address must be in the range that the encoding can express and
+    /// aligned to `1 << shift`. The null address is compressed to 0.
+    pub fn compress(&self, addr: Address) -> u32 {
+        if addr.is_zero() {
+            return 0;
+        }
+        debug_assert!(addr.is_aligned_to(1 << self.shift));
+        debug_assert!(addr > self.base && addr - self.base < self.addressable_bytes());
+        ((addr - self.base) >> self.shift) as u32
+    }
+
+    /// Decompress the compressed pointer. The compressed pointer 0 is decompressed to the null
+    /// address.
+    pub fn decompress(&self, compressed: u32) -> Address {
+        if compressed == 0 {
+            return Address::ZERO;
+        }
+        self.base + ((compressed as usize) << self.shift)
+    }
+}
+
 impl VMLayout {
     #[cfg(target_pointer_width = "32")]
     /// The maximum virtual memory address space that can be used on the target.
@@ -150,6 +230,59 @@ impl VMLayout {
         layout64
     }
 
+    /// The lowest heap start for zero-based compressed pointers. The memory below it is often used
+    /// by the runtime itself, or not mappable at all.
+    #[cfg(target_pointer_width = "64")]
+    const ZERO_BASED_HEAP_START: usize = if cfg!(target_os = "macos") {
+        // It is impossible to map 0x4000_0000 on macOS.
+        0x2_0000_0000
+    } else {
+        0x4000_0000
+    };
+
+    /// The base that MMTk chooses for heap-based compressed pointers. This is where the default
+    /// 64-bit heap starts.
+    #[cfg(target_pointer_width = "64")]
+    const HEAP_BASED_BASE: usize = 0x0000_0200_0000_0000;
+
+    /// Configuration for a 64-bit heap with compressed pointers. MMTk places the heap inside the
+    /// range that the compressed pointers of the given mode can express, and above the base of the
+    /// encoding so that the compressed pointer 0 is never a heap address.  The encoding can be
+    /// queried with [`CompressedPointerMode::encoding`]. `max_heap_size` is the largest heap size the
+    /// binding will use. This panics if the heap does not fit.
+    #[cfg(target_pointer_width = "64")]
+    pub fn new_compressed(mode: CompressedPointerMode, max_heap_size: usize) -> Self {
+        let encoding = mode.encoding();
+        let base = encoding.base.as_usize();
+        let heap_start = match mode {
+            CompressedPointerMode::ZeroBased { .. } => Self::ZERO_BASED_HEAP_START,
+            CompressedPointerMode::HeapBased { .. } => base + BYTES_IN_CHUNK,
+            CompressedPointerMode::Custom { base, .. } => {
+                assert!(
+                    base.is_aligned_to(BYTES_IN_CHUNK),
+                    "The base for compressed pointers is not chunk aligned: {base}"
+                );
+                // Skip one chunk so that the compressed pointer 0 is not a heap address.
+                base.as_usize() + BYTES_IN_CHUNK
+            }
+        };
+        let heap_end = base + encoding.addressable_bytes();
+        assert!(
+            heap_start + max_heap_size <= heap_end,
+            "A heap of {max_heap_size} bytes does not fit in the range of {mode:?}. Use a larger shift."
+        );
+        let log_address_space = (usize::BITS - (heap_end - 1).leading_zeros()) as usize;
+        let layout = Self {
+            log_address_space,
+            heap_start: unsafe { Address::from_usize(heap_start) },
+            heap_end: chunk_align_down(unsafe { Address::from_usize(heap_end) }),
+            log_space_extent: usize::min(31, log_address_space),
+            force_use_contiguous_spaces: false,
+        };
+        layout.validate();
+        layout
+    }
+
     /// Custom VM layout constants. VM bindings may use this function for compressed or 39-bit heap support.
     /// This function must be called before MMTk::new()
     pub(crate) fn set_custom_vm_layout(constants: VMLayout) {
@@ -10,6 +10,7 @@ mod heap_meta;
 pub(crate) mod monotonepageresource;
 pub(crate) mod pageresource;
 pub(crate) mod space_descriptor;
+pub(crate) mod space_index;
 mod vmrequest;
 
 pub(crate) use self::accounting::PageAccounting;
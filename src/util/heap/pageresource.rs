@@ -21,6 +21,12 @@ pub trait PageResource<VM: VMBinding>: 'static {
         required_pages: usize,
         tls: VMThread,
     ) -> Result<PRAllocResult, PRAllocFail> {
+        #[cfg(feature = "fault_injection")]
+        if crate::util::fault_injection::should_fail(
+            crate::util::fault_injection::FaultPoint::PageResource,
+        ) {
+            return Err(PRAllocFail);
+        }
         self.alloc_pages(space_descriptor, reserved_pages, required_pages, tls)
     }
 
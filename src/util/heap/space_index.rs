@@ -0,0 +1,86 @@
+//! A one-byte-per-chunk side metadata that records the index of the space that owns each chunk.
+//!
+//! Each space gets a small index when it is created (see [`HeapMeta::new_space_index`]).  When a
+//! space acquires pages from its page resource, it records its index for the chunks of the pages,
+//! and the index is cleared when the chunks are returned to the VM map.  With the index, checking
+//! whether an object is in a space is a single load and compare instead of an address range
+//! check (or a VM map lookup for discontiguous spaces), and a plan can find the space of an
+//! object with one load instead of checking each space in turn.
+//!
+//! The metadata is mapped for the entire heap range when the first MMTk instance is created, so
+//! it can be read for any address in the heap range.  Addresses outside the heap range, and chunks
+//! of spaces that do not acquire pages from page resources (such as the VM space), have the index
+//! [`UNKNOWN_SPACE_INDEX`].  Callers have to fall back to the address range checks for them.
+//!
+//! [`HeapMeta::new_space_index`]: crate::util::heap::HeapMeta::new_space_index
+
+use crate::util::conversions;
+use crate::util::heap::layout::vm_layout::{vm_layout, BYTES_IN_CHUNK};
+use crate::util::memory::MmapStrategy;
+use crate::util::metadata::side_metadata::{SideMetadataContext, SideMetadataSpec};
+use crate::util::Address;
+use std::sync::atomic::Ordering;
+use std::sync::Once;
+
+/// The side metadata spec for the space index.
+pub(crate) const SPACE_INDEX: SideMetadataSpec =
+    crate::util::metadata::side_metadata::spec_defs::SPACE_INDEX;
+
+/// The index for chunks that are not known to be owned by any space.
+pub(crate) const UNKNOWN_SPACE_INDEX: u8 = 0;
+
+/// The largest space index.  The chunk map stores the space index in 7 bits.
+pub(crate) const MAX_SPACE_INDEX: u8 = 0x7f;
+
+/// Map the side metadata for the heap range.  Only the first call takes effect.
+pub(crate) fn initialize() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        let context = SideMetadataContext {
+            global: vec![SPACE_INDEX],
+            local: vec![],
+            mmap_strategy: MmapStrategy::SIDE_METADATA,
+        };
+        let start = vm_layout().heap_start;
+        let end = vm_layout().heap_end;
+        context
+            .try_map_metadata_space(start, end - start, "SpaceIndex")
+            .unwrap_or_else(|e| panic!("failed to mmap metadata memory for space index: {e}"));
+    });
+}
+
+fn in_heap_range(addr: Address) -> bool {
+    addr >= vm_layout().heap_start && addr < vm_layout().heap_end
+}
+
+/// Get the index of the space that owns the chunk of `addr`, or [`UNKNOWN_SPACE_INDEX`].
+#[inline(always)]
+pub(crate) fn get(addr: Address) -> u8 {
+    if !in_heap_range(addr) {
+        return UNKNOWN_SPACE_INDEX;
+    }
+    SPACE_INDEX.load_atomic::<u8>(addr, Ordering::Relaxed)
+}
+
+/// Record `space_index` for the chunks of the address range.  The range is ignored if it is
+/// outside the heap range.
+pub(crate) fn set(start: Address, bytes: usize, space_index: u8) {
+    if !in_heap_range(start) {
+        return;
+    }
+    let mut chunk = conversions::chunk_align_down(start);
+    let end = conversions::chunk_align_up(start + bytes);
+    while chunk < end {
+        if SPACE_INDEX.load_atomic::<u8>(chunk, Ordering::Relaxed) != space_index {
+            SPACE_INDEX.store_atomic::<u8>(chunk, space_index, Ordering::Relaxed);
+        }
+        chunk += BYTES_IN_CHUNK;
+    }
+}
+
+/// Forget the space that owned the chunk, as the chunk is returned to the VM map.
+pub(crate) fn clear(chunk_start: Address) {
+    if in_heap_range(chunk_start) {
+        SPACE_INDEX.store_atomic::<u8>(chunk_start, UNKNOWN_SPACE_INDEX, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,368 @@
+//! Back the heap and side metadata with files, so that the heap contents can be inspected after the
+//! process crashes.
+//!
+//! If the option `heap_file_dir` is set, MMTk maps the memory for spaces and side metadata with
+//! `MAP_SHARED` mappings of files in that directory, instead of anonymous memory. Each mapping gets
+//! its own file named `<sequence>-<start>-<end>.bin`, where a mapping with a larger sequence number
+//! replaces the older mappings of the same addresses. When MMTk is initialized, it also writes a
+//! small text index (`index.txt`) of the spaces and the bases of side metadata. The files survive a
+//! crash, and [`HeapFileReader`] reads them offline.
+//!
+//! This is for debugging only. Writes to the heap go to the page cache of the files, so the files
+//! should be on a fast file system such as `tmpfs`. File-backed memory does not use huge pages.
+
+use crate::util::memory::{MmapAnnotation, MmapProtection};
+use crate::util::metadata::side_metadata::{SideMetadataContext, SideMetadataSpec};
+use crate::util::Address;
+use std::fs::File;
+use std::io::{Error, ErrorKind, Read, Result, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+/// The name of the index file in the heap file directory.
+pub const INDEX_FILE_NAME: &str = "index.txt";
+
+/// The directory of the heap files. It is only set if the option `heap_file_dir` is set.
+static HEAP_FILE_DIR: OnceLock<PathBuf> = OnceLock::new();
+/// The sequence number for the next heap file.
+static NEXT_SEQUENCE: AtomicUsize = AtomicUsize::new(0);
+/// The lines of the index, recorded as the spaces are created.
+static INDEX: Mutex<Vec<String>> = Mutex::new(vec![]);
+
+/// Use the given directory for heap files. This is called with the option `heap_file_dir` before
+/// any space is created. If multiple MMTk instances are created, they share the first directory.
+pub(crate) fn init(dir: &str) -> Result<()> {
+    std::fs::create_dir_all(dir)?;
+    if HEAP_FILE_DIR.set(PathBuf::from(dir)).is_err() {
+        warn!(
+            "Heap files are already written to {:?}",
+            HEAP_FILE_DIR.get()
+        );
+    }
+    Ok(())
+}
+
+/// Return true if the heap is backed by files.
+pub(crate) fn is_enabled() -> bool {
+    HEAP_FILE_DIR.get().is_some()
+}
+
+/// Record a space and its side metadata in the index. `extent` is 0 for discontiguous spaces.
+pub(crate) fn record_space(
+    name: &str,
+    start: Address,
+    extent: usize,
+    metadata: &SideMetadataContext,
+) {
+    if !is_enabled() {
+        return;
+    }
+    let mut index = INDEX.lock().unwrap();
+    index.push(format!("space\t{name}\t{start}\t{extent}"));
+    for spec in metadata.global.iter().chain(metadata.local.iter()) {
+        // Chunked side metadata (local metadata on 32-bit targets) has no fixed base.
+        if spec.uses_contiguous_side_metadata() {
+            let line = metadata_line(spec);
+            if !index.contains(&line) {
+                index.push(line);
+            }
+        }
+    }
+}
+
+fn metadata_line(spec: &SideMetadataSpec) -> String {
+    format!(
+        "metadata\t{}\t{}\t{}\t{}",
+        spec.name,
+        spec.get_absolute_offset(),
+        spec.log_num_of_bits,
+        spec.log_bytes_in_region
+    )
+}
+
+/// Write the index of the spaces and side metadata to the heap file directory.
+pub(crate) fn write_index() -> Result<()> {
+    let Some(dir) = HEAP_FILE_DIR.get() else {
+        return Ok(());
+    };
+    let mut file = File::create(dir.join(INDEX_FILE_NAME))?;
+    for line in INDEX.lock().unwrap().iter() {
+        writeln!(file, "{line}")?;
+    }
+    file.sync_all()
+}
+
+/// Create the file that backs the given memory, if the memory should be backed by a file. Only the
+/// memory for spaces and side metadata is backed by files, and the memory that is only reserved is
+/// not.
+pub(crate) fn create_backing_file(
+    start: Address,
+    size: usize,
+    prot: MmapProtection,
+    anno: &MmapAnnotation,
+) -> Result<Option<File>> {
+    let Some(dir) = HEAP_FILE_DIR.get() else {
+        return Ok(None);
+    };
+    if matches!(prot, MmapProtection::NoAccess)
+        || !matches!(
+            anno,
+            MmapAnnotation::Space { .. } | MmapAnnotation::SideMeta { .. }
+        )
+    {
+        return Ok(None);
+    }
+    let sequence = NEXT_SEQUENCE.fetch_add(1, Ordering::SeqCst);
+    let path = dir.join(format!("{sequence:08}-{start}-{}.bin", start + size));
+    let file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)?;
+    // The file is sparse and reads as zeros, like anonymous memory.
+    file.set_len(size as u64)?;
+    Ok(Some(file))
+}
+
+/// A space in the index of the heap files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeapFileSpace {
+    /// The name of the space.
+    pub name: String,
+    /// The start of the space. It is zero for discontiguous spaces.
+    pub start: Address,
+    /// The size of the space in bytes. It is zero for discontiguous spaces.
+    pub extent: usize,
+}
+
+/// A side metadata in the index of the heap files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeapFileMetadata {
+    /// The name of the side metadata.
+    pub name: String,
+    /// The address of the side metadata for the address 0.
+    pub base: Address,
+    /// See [`SideMetadataSpec::log_num_of_bits`].
+    pub log_num_of_bits: usize,
+    /// See [`SideMetadataSpec::log_bytes_in_region`].
+    pub log_bytes_in_region: usize,
+}
+
+/// A file that backs a range of memory.
+struct HeapFileMapping {
+    sequence: usize,
+    start: Address,
+    end: Address,
+    path: PathBuf,
+}
+
+/// Read the heap files written with the option `heap_file_dir`, for example, after the process
+/// crashed. This does not require the MMTk instance that wrote the files.
+pub struct HeapFileReader {
+    spaces: Vec<HeapFileSpace>,
+    metadata: Vec<HeapFileMetadata>,
+    /// The mappings, sorted by the sequence number with the newest first.
+    mappings: Vec<HeapFileMapping>,
+}
+
+fn parse_address(s: &str) -> Result<Address> {
+    usize::from_str_radix(s.trim_start_matches("0x"), 16)
+        .map(|addr| unsafe { Address::from_usize(addr) })
+        .map_err(|e| Error::new(ErrorKind::InvalidData, format!("{s}: {e}")))
+}
+
+fn parse_usize(s: &str) -> Result<usize> {
+    s.parse()
+        .map_err(|e| Error::new(ErrorKind::InvalidData, format!("{s}: {e}")))
+}
+
+impl HeapFileReader {
+    /// Read the index and the list of heap files in the given directory.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+        let mut spaces = vec![];
+        let mut metadata = vec![];
+        for line in std::fs::read_to_string(dir.join(INDEX_FILE_NAME))?.lines() {
+            let fields: Vec<&str> = line.split('\t').collect();
+            match fields[..] {
+                ["space", name, start, extent] => spaces.push(HeapFileSpace {
+                    name: name.to_string(),
+                    start: parse_address(start)?,
+                    extent: parse_usize(extent)?,
+                }),
+                ["metadata", name, base, log_num_of_bits, log_bytes_in_region] => {
+                    metadata.push(HeapFileMetadata {
+                        name: name.to_string(),
+                        base: parse_address(base)?,
+                        log_num_of_bits: parse_usize(log_num_of_bits)?,
+                        log_bytes_in_region: parse_usize(log_bytes_in_region)?,
+                    })
+                }
+                _ => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!("Invalid line in the index: {line}"),
+                    ))
+                }
+            }
+        }
+
+        let mut mappings = vec![];
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            let Some(stem) = path
+                .extension()
+                .filter(|ext| *ext == "bin")
+                .and_then(|_| path.file_stem())
+                .and_then(|stem| stem.to_str())
+            else {
+                continue;
+            };
+            let fields: Vec<&str> = stem.split('-').collect();
+            if let [sequence, start, end] = fields[..] {
+                mappings.push(HeapFileMapping {
+                    sequence: parse_usize(sequence)?,
+                    start: parse_address(start)?,
+                    end: parse_address(end)?,
+                    path,
+                });
+            }
+        }
+        mappings.sort_by_key(|mapping| std::cmp::Reverse(mapping.sequence));
+
+        Ok(Self {
+            spaces,
+            metadata,
+            mappings,
+        })
+    }
+
+    /// The spaces in the index.
+    pub fn spaces(&self) -> &[HeapFileSpace] {
+        &self.spaces
+    }
+
+    /// The side metadata in the index.
+    pub fn metadata(&self) -> &[HeapFileMetadata] {
+        &self.metadata
+    }
+
+    /// Find the space that contains the address. Discontiguous spaces are not considered.
+    pub fn space_of(&self, addr: Address) -> Option<&HeapFileSpace> {
+        self.spaces
+            .iter()
+            .find(|space| space.start <= addr && addr < space.start + space.extent)
+    }
+
+    /// Read the memory at `addr` into `buf`. Return an error of the kind `NotFound` if any byte is
+    /// not backed by a heap file.
+    pub fn read(&self, addr: Address, buf: &mut [u8]) -> Result<()> {
+        let mut done = 0;
+        while done < buf.len() {
+            let cursor = addr + done;
+            let mapping = self
+                .mappings
+                .iter()
+                .find(|mapping| mapping.start <= cursor && cursor < mapping.end)
+                .ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::NotFound,
+                        format!("{cursor} is not in heap files"),
+                    )
+                })?;
+            let len = usize::min(buf.len() - done, mapping.end - cursor);
+            let mut file = File::open(&mapping.path)?;
+            file.seek(SeekFrom::Start((cursor - mapping.start) as u64))?;
+            file.read_exact(&mut buf[done..done + len])?;
+            done += len;
+        }
+        Ok(())
+    }
+
+    /// Read the word at `addr`.
+    pub fn read_word(&self, addr: Address) -> Result<usize> {
+        let mut buf = [0u8; std::mem::size_of::<usize>()];
+        self.read(addr, &mut buf)?;
+        Ok(usize::from_ne_bytes(buf))
+    }
+
+    /// Read the value of the side metadata of the given name for the data address `addr`.
+    pub fn read_metadata(&self, name: &str, addr: Address) -> Result<u64> {
+        let spec = self
+            .metadata
+            .iter()
+            .find(|spec| spec.name == name)
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, format!("No side metadata {name}")))?;
+        let log_bits = spec.log_num_of_bits;
+        let region = addr.as_usize() >> spec.log_bytes_in_region;
+        if log_bits >= 3 {
+            let bytes = 1 << (log_bits - 3);
+            let mut buf = [0u8; 8];
+            self.read(spec.base + region * bytes, &mut buf[..bytes])?;
+            Ok(match bytes {
+                1 => buf[0] as u64,
+                2 => u16::from_ne_bytes([buf[0], buf[1]]) as u64,
+                4 => u32::from_ne_bytes([buf[0], buf[1], buf[2], buf[3]]) as u64,
+                _ => u64::from_ne_bytes(buf),
+            })
+        } else {
+            let mut byte = [0u8];
+            self.read(spec.base + (region >> (3 - log_bits)), &mut byte)?;
+            let shift = (region & ((1 << (3 - log_bits)) - 1)) << log_bits;
+            let mask = (1u8 << (1 << log_bits)) - 1;
+            Ok(((byte[0] >> shift) & mask) as u64)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_heap_files() {
+        let dir = std::env::temp_dir().join(format!("mmtk-heap-file-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join(INDEX_FILE_NAME),
+            "space\timmix\t0x200000000000\t4194304\nmetadata\tmark\t0x100000000000\t0\t3\n",
+        )
+        .unwrap();
+        // An old mapping of the first page of the space, replaced by a newer mapping.
+        std::fs::write(
+            dir.join("00000000-0x200000000000-0x200000001000.bin"),
+            [0xffu8; 4096],
+        )
+        .unwrap();
+        let mut data = vec![0u8; 8192];
+        data[8..16].copy_from_slice(&42usize.to_ne_bytes());
+        std::fs::write(dir.join("00000001-0x200000000000-0x200000002000.bin"), data).unwrap();
+        // The mark bits for the words from 0x200000000000.
+        let mut meta = vec![0u8; 4096];
+        meta[0] = 0b10;
+        std::fs::write(dir.join("00000002-0x108000000000-0x108000001000.bin"), meta).unwrap();
+
+        let reader = HeapFileReader::open(&dir).unwrap();
+        let space_start = unsafe { Address::from_usize(0x2000_0000_0000) };
+        assert_eq!(reader.spaces().len(), 1);
+        assert_eq!(reader.space_of(space_start + 8usize).unwrap().name, "immix");
+        assert_eq!(reader.read_word(space_start).unwrap(), 0);
+        assert_eq!(reader.read_word(space_start + 8usize).unwrap(), 42);
+        assert_eq!(
+            reader
+                .read_word(space_start + 8192usize)
+                .unwrap_err()
+                .kind(),
+            ErrorKind::NotFound
+        );
+        assert_eq!(reader.read_metadata("mark", space_start).unwrap(), 0);
+        assert_eq!(
+            reader.read_metadata("mark", space_start + 8usize).unwrap(),
+            1
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,295 @@
+//! A description of the heap layout, for diagnosing conflicts between the address ranges that MMTk
+//! uses and the address ranges that the binding or other libraries use.
+//!
+//! [`crate::MMTK::dump_heap_layout`] describes the address ranges of all spaces, the side metadata
+//! for those ranges, and the state of the chunks in the mmapper.  The description can be printed in
+//! a machine-readable format (one tab-separated record per line), and
+//! [`HeapLayout::validate`] checks that the ranges do not overlap.
+//!
+//! The layout is a snapshot.  Discontiguous spaces only report the regions that they hold when the
+//! layout is taken, and the malloc space does not report any range, as its memory comes from malloc.
+
+use crate::plan::Plan;
+use crate::util::metadata::side_metadata::{
+    address_to_meta_address, SideMetadataContext, SideMetadataSpec,
+};
+use crate::util::Address;
+use crate::vm::VMBinding;
+use std::ops::Range;
+
+/// The address ranges of a space.
+#[derive(Clone, Debug)]
+pub struct SpaceLayout {
+    /// The name of the space.
+    pub name: &'static str,
+    /// The space index recorded for the chunks of the space, or 0 if the space does not record it.
+    pub index: u8,
+    /// Whether the space has a contiguous address range.
+    pub contiguous: bool,
+    /// The address ranges of the space.  For a contiguous space, this is the entire range of the
+    /// space.  For a discontiguous space, these are the regions the space holds now.
+    pub ranges: Vec<Range<Address>>,
+    /// The side metadata of the space.
+    pub(crate) metadata: Vec<SideMetadataSpec>,
+}
+
+impl SpaceLayout {
+    pub(crate) fn new(
+        name: &'static str,
+        index: u8,
+        contiguous: bool,
+        ranges: Vec<Range<Address>>,
+        metadata: &SideMetadataContext,
+    ) -> Self {
+        Self {
+            name,
+            index,
+            contiguous,
+            ranges: ranges.into_iter().filter(|r| !r.is_empty()).collect(),
+            metadata: metadata
+                .global
+                .iter()
+                .chain(metadata.local.iter())
+                .copied()
+                .collect(),
+        }
+    }
+}
+
+/// The memory of a side metadata for the address ranges of a space.
+#[derive(Clone, Debug)]
+pub struct SideMetadataLayout {
+    /// The name of the side metadata.
+    pub name: &'static str,
+    /// The name of the space that the metadata is for.  Global side metadata is listed once for
+    /// each space.
+    pub space: &'static str,
+    /// The address ranges of the metadata for the address ranges of the space.
+    pub ranges: Vec<Range<Address>>,
+}
+
+/// The state of mmap chunks in the mmapper.  Unmapped chunks are not reported.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MmapChunkState {
+    /// The address range is reserved with `PROT_NONE`, but not used yet.
+    Quarantined,
+    /// The memory is mapped and in use.
+    Mapped,
+    /// The memory is mapped but protected.
+    Protected,
+}
+
+/// A range of consecutive mmap chunks in the same state.
+#[derive(Clone, Debug)]
+pub struct MmapChunkRange {
+    /// The address range of the chunks.
+    pub range: Range<Address>,
+    /// The state of the chunks.
+    pub state: MmapChunkState,
+}
+
+/// A snapshot of the heap layout.  See [`crate::MMTK::dump_heap_layout`].
+///
+/// The `Display` implementation prints one tab-separated record per line:
+///
+/// ```text
+/// space       <name>  <index>  contiguous|discontiguous  [<start>  <end>]
+/// metadata    <name>  <space>  <start>  <end>
+/// mmap        quarantined|mapped|protected  <start>  <end>
+/// ```
+///
+/// A space or a metadata has one line for each of its ranges.  A space without any range has one
+/// line without the range.  All ranges are half-open.
+#[derive(Clone, Debug)]
+pub struct HeapLayout {
+    /// The spaces, in the order of the plan.
+    pub spaces: Vec<SpaceLayout>,
+    /// The contiguous side metadata for the ranges of each space.  Chunked side metadata (local
+    /// metadata on 32-bit targets) is not listed, as it does not have a fixed address.
+    pub side_metadata: Vec<SideMetadataLayout>,
+    /// The chunks that the mmapper has reserved or mapped, sorted by their addresses.
+    pub mmap_chunks: Vec<MmapChunkRange>,
+}
+
+impl HeapLayout {
+    pub(crate) fn new(spaces: Vec<SpaceLayout>, mut mmap_chunks: Vec<MmapChunkRange>) -> Self {
+        let side_metadata = spaces
+            .iter()
+            .flat_map(|space| {
+                space
+                    .metadata
+                    .iter()
+                    .filter(|spec| spec.uses_contiguous_side_metadata())
+                    .map(|spec| SideMetadataLayout {
+                        name: spec.name,
+                        space: space.name,
+                        ranges: space
+                            .ranges
+                            .iter()
+                            .map(|range| {
+                                address_to_meta_address(spec, range.start)
+                                    ..address_to_meta_address(spec, range.end - 1) + 1usize
+                            })
+                            .collect(),
+                    })
+            })
+            .filter(|metadata| !metadata.ranges.is_empty())
+            .collect();
+        mmap_chunks.sort_by_key(|chunks| chunks.range.start);
+        // Merge adjacent ranges in the same state, as the mmapper may report them separately.
+        mmap_chunks.dedup_by(|next, prev| {
+            let adjacent = prev.range.end == next.range.start && prev.state == next.state;
+            if adjacent {
+                prev.range.end = next.range.end;
+            }
+            adjacent
+        });
+        Self {
+            spaces,
+            side_metadata,
+            mmap_chunks,
+        }
+    }
+
+    /// Check that no two spaces overlap, and that the memory of different side metadata does not
+    /// overlap with each other or with any space.  The same side metadata for different spaces may
+    /// share memory if the spaces share a metadata region.  Return the overlapping ranges if any.
+    pub fn validate(&self) -> Result<(), String> {
+        // The range, the description, and the group.  Ranges in the same group may overlap.
+        let mut entries: Vec<(Range<Address>, String, String)> = vec![];
+        for space in self.spaces.iter() {
+            for range in space.ranges.iter() {
+                let group = format!("space {}", space.name);
+                entries.push((range.clone(), group.clone(), group));
+            }
+        }
+        for metadata in self.side_metadata.iter() {
+            for range in metadata.ranges.iter() {
+                entries.push((
+                    range.clone(),
+                    format!("metadata {} for {}", metadata.name, metadata.space),
+                    format!("metadata {}", metadata.name),
+                ));
+            }
+        }
+        entries.sort_by_key(|(range, _, _)| range.start);
+
+        let mut overlaps = vec![];
+        for (i, (range, desc, group)) in entries.iter().enumerate() {
+            for (other, other_desc, other_group) in entries[i + 1..].iter() {
+                if other.start >= range.end {
+                    break;
+                }
+                if group != other_group {
+                    overlaps.push(format!(
+                        "{desc} ({}-{}) overlaps with {other_desc} ({}-{})",
+                        range.start, range.end, other.start, other.end
+                    ));
+                }
+            }
+        }
+        if overlaps.is_empty() {
+            Ok(())
+        } else {
+            Err(overlaps.join("\n"))
+        }
+    }
+}
+
+/// Describe the layout of the spaces of the plan.  The mmapper is shared by all MMTk instances, so
+/// the mmap chunks include the chunks of other instances, if any.
+pub(crate) fn describe<VM: VMBinding>(plan: &dyn Plan<VM = VM>) -> HeapLayout {
+    let mut spaces = vec![];
+    plan.for_each_space(&mut |space| spaces.push(space.describe_layout()));
+    HeapLayout::new(spaces, crate::mmtk::MMAPPER.chunk_ranges())
+}
+
+impl std::fmt::Display for HeapLayout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for space in self.spaces.iter() {
+            let kind = if space.contiguous {
+                "contiguous"
+            } else {
+                "discontiguous"
+            };
+            if space.ranges.is_empty() {
+                writeln!(f, "space\t{}\t{}\t{kind}", space.name, space.index)?;
+            }
+            for range in space.ranges.iter() {
+                writeln!(
+                    f,
+                    "space\t{}\t{}\t{kind}\t{}\t{}",
+                    space.name, space.index, range.start, range.end
+                )?;
+            }
+        }
+        for metadata in self.side_metadata.iter() {
+            for range in metadata.ranges.iter() {
+                writeln!(
+                    f,
+                    "metadata\t{}\t{}\t{}\t{}",
+                    metadata.name, metadata.space, range.start, range.end
+                )?;
+            }
+        }
+        for chunks in self.mmap_chunks.iter() {
+            let state = match chunks.state {
+                MmapChunkState::Quarantined => "quarantined",
+                MmapChunkState::Mapped => "mapped",
+                MmapChunkState::Protected => "protected",
+            };
+            writeln!(
+                f,
+                "mmap\t{state}\t{}\t{}",
+                chunks.range.start, chunks.range.end
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::heap::space_index::SPACE_INDEX;
+    use crate::util::memory::MmapStrategy;
+
+    fn addr(a: usize) -> Address {
+        unsafe { Address::from_usize(a) }
+    }
+
+    fn space(name: &'static str, range: Range<Address>) -> SpaceLayout {
+        SpaceLayout::new(
+            name,
+            1,
+            true,
+            vec![range],
+            &SideMetadataContext {
+                global: vec![SPACE_INDEX],
+                local: vec![],
+                mmap_strategy: MmapStrategy::SIDE_METADATA,
+            },
+        )
+    }
+
+    #[test]
+    fn validate_overlaps() {
+        let chunk = crate::util::heap::layout::vm_layout::BYTES_IN_CHUNK;
+        let a = space("a", addr(0x4000_0000)..addr(0x4000_0000 + chunk));
+        let b = space(
+            "b",
+            addr(0x4000_0000 + chunk)..addr(0x4000_0000 + 2 * chunk),
+        );
+        let layout = HeapLayout::new(vec![a.clone(), b], vec![]);
+        assert!(layout.validate().is_ok(), "{:?}", layout.validate());
+        assert_eq!(layout.side_metadata.len(), 2);
+        assert!(layout.to_string().contains("metadata\tSPACE_INDEX\ta\t"));
+
+        let c = space("c", addr(0x4000_0000)..addr(0x4000_0000 + 2 * chunk));
+        let err = HeapLayout::new(vec![a, c], vec![]).validate().unwrap_err();
+        assert!(err.contains("space a"));
+        assert!(err.contains("space c"));
+        // The metadata of the two spaces overlap, but it is the same metadata.
+        assert!(!err.contains("metadata"));
+    }
+}
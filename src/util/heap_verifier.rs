@@ -0,0 +1,153 @@
+//! A heap verifier that checks every reference field in the heap against the side metadata.
+//!
+//! The sanity GC (feature "sanity") checks that reachable objects are sane by tracing the heap
+//! again. The heap verifier is complementary. It walks the heap linearly via the VO bits, scans
+//! every object, and checks each slot. This catches dangling references in objects that are
+//! considered live by the spaces (e.g. because a write barrier is missing), and reports the
+//! object that holds the slot.
+
+use crate::mmtk::SFT_MAP;
+use crate::scheduler::{GCWork, GCWorker};
+use crate::util::metadata::vo_bit;
+use crate::util::{ObjectReference, VMWorkerThread};
+use crate::vm::slot::Slot;
+use crate::vm::{Scanning, VMBinding};
+use crate::MMTK;
+use std::marker::PhantomData;
+
+/// A failure found by the heap verifier.
+struct VerificationFailure<SL: Slot> {
+    /// The object that holds the slot.
+    holder: ObjectReference,
+    /// The index of the slot among the slots of the holder, in the order they are visited.
+    /// This is `None` if the holder is scanned with `Scanning::scan_object_and_trace_edges`.
+    index: Option<usize>,
+    /// The slot, if the holder is scanned with `Scanning::scan_object`.
+    slot: Option<SL>,
+    /// The object referred to by the slot.
+    referent: ObjectReference,
+    /// What is wrong with the referent.
+    reason: &'static str,
+}
+
+impl<SL: Slot> std::fmt::Display for VerificationFailure<SL> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} -> {}: {}", self.holder, self.referent, self.reason)?;
+        if let (Some(index), Some(slot)) = (self.index, self.slot) {
+            write!(f, " (slot #{} {:?})", index, slot)?;
+        }
+        #[cfg(feature = "alloc_site")]
+        write!(f, "{}", crate::util::alloc_site::describe(self.holder))?;
+        Ok(())
+    }
+}
+
+/// Verify all the objects in the heap. The mutators must be stopped.
+///
+/// If `check_liveness` is true, we also check that every referent of a live object is considered
+/// live by its space. This is only valid right after a GC, before the mutators resume.
+///
+/// Return the number of slots that failed the verification. Each failure is reported with `error!`.
+pub(crate) fn verify_heap<VM: VMBinding>(
+    mmtk: &'static MMTK<VM>,
+    tls: VMWorkerThread,
+    check_liveness: bool,
+) -> usize {
+    let mut objects = vec![];
+    mmtk.enumerate_objects(|object| objects.push(object));
+
+    // MarkCompact clears the mark bits in the second trace, so the liveness of objects is unknown after GC.
+    let check_liveness =
+        check_liveness && !mmtk.get_plan().constraints().needs_forward_after_liveness;
+
+    let mut failures = 0;
+    let mut report = |failure: VerificationFailure<VM::VMSlot>| {
+        error!("Heap verification failed: {}", failure);
+        failures += 1;
+    };
+
+    for holder in objects {
+        // Objects that are not reachable may legitimately hold dangling references. For example,
+        // dead objects in an immortal space are never reclaimed.
+        if !SFT_MAP
+            .get_checked(holder.to_raw_address())
+            .is_reachable(holder)
+        {
+            continue;
+        }
+
+        if VM::VMScanning::support_slot_enqueuing(tls, holder) {
+            let mut index = 0;
+            VM::VMScanning::scan_object(tls, holder, &mut |slot: VM::VMSlot| {
+                if let Some(referent) = slot.load() {
+                    if let Some(reason) = check_referent(referent, check_liveness) {
+                        report(VerificationFailure {
+                            holder,
+                            index: Some(index),
+                            slot: Some(slot),
+                            referent,
+                            reason,
+                        });
+                    }
+                }
+                index += 1;
+            });
+        } else {
+            VM::VMScanning::scan_object_and_trace_edges(
+                tls,
+                holder,
+                &mut |referent: ObjectReference| {
+                    if let Some(reason) = check_referent(referent, check_liveness) {
+                        report(VerificationFailure {
+                            holder,
+                            index: None,
+                            slot: None,
+                            referent,
+                            reason,
+                        });
+                    }
+                    // Do not update the field.
+                    referent
+                },
+            );
+        }
+    }
+
+    failures
+}
+
+/// Check a referent. Return the reason if the check fails.
+fn check_referent(referent: ObjectReference, check_liveness: bool) -> Option<&'static str> {
+    let sft = SFT_MAP.get_checked(referent.to_raw_address());
+    if !sft.is_in_space(referent) {
+        return Some("the referent is not in any MMTk space");
+    }
+    if !vo_bit::is_vo_bit_set(referent) {
+        return Some("the referent does not have its VO bit set");
+    }
+    if check_liveness && !sft.is_live(referent) {
+        return Some("the referent is not live (not marked or not forwarded)");
+    }
+    None
+}
+
+/// A work packet that verifies the heap at the end of a GC. It panics if the verification fails.
+pub struct VerifyHeap<VM: VMBinding>(PhantomData<VM>);
+
+impl<VM: VMBinding> Default for VerifyHeap<VM> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<VM: VMBinding> GCWork<VM> for VerifyHeap<VM> {
+    fn do_work(&mut self, worker: &mut GCWorker<VM>, mmtk: &'static MMTK<VM>) {
+        info!("Verifying heap");
+        let failures = verify_heap(mmtk, worker.tls, true);
+        assert_eq!(
+            failures, 0,
+            "Heap verification found {} bad slots",
+            failures
+        );
+    }
+}
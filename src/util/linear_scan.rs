@@ -1,9 +1,11 @@
+use crate::util::heap::chunk_map::Chunk;
 use crate::util::metadata::vo_bit;
 use crate::util::Address;
 use crate::util::ObjectReference;
 use crate::vm::ObjectModel;
 use crate::vm::VMBinding;
 use std::marker::PhantomData;
+use std::num::NonZeroUsize;
 
 // FIXME: MarkCompact uses linear scanning to discover allocated objects in the MarkCompactSpace.
 // It should use a local metadata (specific to the MarkCompactSpace) for that purpose.
@@ -86,6 +88,10 @@ impl<VM: VMBinding> LinearScanObjectSize for DefaultObjectSize<VM> {
 
 /// Region represents a memory region with a properly aligned address as its start and a fixed size for the region.
 /// Region provides a set of utility methods, along with a RegionIterator that linearly scans at the step of a region.
+///
+/// Regions form a hierarchy: a chunk contains blocks, and a block may contain smaller regions such as lines.  A
+/// region declares its enclosing regions by implementing [`SubRegion`].  New region types can be defined with
+/// `#[derive(Region)]` (see the `mmtk-macros` crate) instead of implementing the trait manually.
 pub trait Region: Copy + PartialEq + PartialOrd {
     /// log2 of the size in bytes for the region.
     const LOG_BYTES: usize;
@@ -133,6 +139,66 @@ pub trait Region: Copy + PartialEq + PartialOrd {
     fn includes_address(&self, addr: Address) -> bool {
         Self::align(addr) == self.start()
     }
+    /// Return the chunk containing the region.
+    fn chunk(&self) -> Chunk {
+        Chunk::from_unaligned_address(self.start())
+    }
+    /// Get an iterator for the smaller regions within this region.
+    fn iter_region<R: SubRegion<Self>>(&self) -> RegionIterator<R> {
+        // R should be aligned to the boundary of this region
+        debug_assert!(R::is_aligned(self.start()));
+        debug_assert!(R::is_aligned(self.end()));
+
+        let start = R::from_aligned_address(self.start());
+        let end = R::from_aligned_address(self.end());
+        RegionIterator::<R>::new(start, end)
+    }
+}
+
+/// A region that is always contained in a larger region `P`, such as a line in a block, or a block in a chunk.
+pub trait SubRegion<P: Region>: Region {
+    /// The number of regions of this type in a `P`.
+    const PER_PARENT: usize = 1 << (P::LOG_BYTES - Self::LOG_BYTES);
+
+    /// Return the region `P` that contains this region.
+    fn parent(&self) -> P {
+        debug_assert!(Self::LOG_BYTES < P::LOG_BYTES);
+        P::from_unaligned_address(self.start())
+    }
+    /// Return the index of this region within its parent region.
+    fn index_in_parent(&self) -> usize {
+        self.start().get_extent(P::align(self.start())) >> Self::LOG_BYTES
+    }
+}
+
+/// The type of the field that holds the start address in a region type defined with `#[derive(Region)]`.
+pub(crate) trait RegionAddress: Copy {
+    /// Convert an aligned start address to the field.
+    fn from_address(address: Address) -> Self;
+    /// Convert the field to the start address.
+    fn to_address(self) -> Address;
+}
+
+impl RegionAddress for Address {
+    fn from_address(address: Address) -> Self {
+        address
+    }
+
+    fn to_address(self) -> Address {
+        self
+    }
+}
+
+/// A region that uses `NonZeroUsize` can be put in an `Option` without extra space.
+impl RegionAddress for NonZeroUsize {
+    fn from_address(address: Address) -> Self {
+        debug_assert!(!address.is_zero());
+        unsafe { NonZeroUsize::new_unchecked(address.as_usize()) }
+    }
+
+    fn to_address(self) -> Address {
+        unsafe { Address::from_usize(self.get()) }
+    }
 }
 
 /// An iterator for contiguous regions.
@@ -188,6 +254,29 @@ mod tests {
         }
     }
 
+    #[derive(Copy, Clone, Debug, PartialEq, PartialOrd, mmtk_macros::Region)]
+    #[region(log_bytes = LOG_BYTES_IN_PAGE as usize - 4, parent = Page)]
+    struct SubPage(Address);
+
+    #[test]
+    fn test_sub_region() {
+        let addr4k = unsafe { Address::from_usize(PAGE_SIZE) };
+        let page = Page::from_aligned_address(addr4k);
+
+        let sub_pages: Vec<SubPage> = page.iter_region::<SubPage>().collect();
+        assert_eq!(sub_pages.len(), <SubPage as SubRegion<Page>>::PER_PARENT);
+        assert_eq!(sub_pages.len(), 16);
+        for (i, sub_page) in sub_pages.iter().enumerate() {
+            assert_eq!(sub_page.start(), addr4k + i * SubPage::BYTES);
+            assert_eq!(sub_page.parent(), page);
+            assert_eq!(sub_page.index_in_parent(), i);
+        }
+        assert_eq!(
+            SubPage::from_unaligned_address(addr4k + 300usize).index_in_parent(),
+            1
+        );
+    }
+
     #[test]
     fn test_region_methods() {
         let addr4k = unsafe { Address::from_usize(PAGE_SIZE) };
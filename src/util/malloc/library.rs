@@ -7,11 +7,79 @@ pub use self::libc_malloc::*;
 #[cfg(feature = "malloc_mimalloc")]
 pub use self::mimalloc::*;
 
+use crate::util::options::MallocBackend;
+
 /// When we count page usage of library malloc, we assume they allocate in pages. For some malloc implementations,
 /// they may use a larger page (e.g. mimalloc's 64K page). For libraries that we are not sure, we assume they use
 /// normal 4k pages.
 pub const BYTES_IN_MALLOC_PAGE: usize = 1 << LOG_BYTES_IN_MALLOC_PAGE;
 
+/// The functions of a malloc library that is linked into MMTk. The malloc mark sweep space selects the library
+/// at run time with the option `malloc_backend`, while the manual malloc API in [`crate::util::malloc`] always
+/// uses the library exported by this module.
+pub struct MallocLibrary {
+    calloc: fn(usize, usize) -> *mut libc::c_void,
+    posix_memalign: fn(*mut *mut libc::c_void, usize, usize) -> libc::c_int,
+    malloc_usable_size: fn(*mut libc::c_void) -> usize,
+    free: fn(*mut libc::c_void),
+    free_bulk: fn(&[(*mut libc::c_void, usize)]),
+}
+
+impl MallocLibrary {
+    /// Get the functions of the given library. Panics if the library is not linked into MMTk, which the option
+    /// validator prevents.
+    pub fn get(backend: MallocBackend) -> &'static MallocLibrary {
+        match backend {
+            MallocBackend::Libc => &libc_malloc::LIBRARY,
+            #[cfg(feature = "malloc_mimalloc")]
+            MallocBackend::Mimalloc => &mimalloc::LIBRARY,
+            #[cfg(feature = "malloc_jemalloc")]
+            MallocBackend::Jemalloc => &jemalloc::LIBRARY,
+            #[allow(unreachable_patterns)]
+            _ => panic!("The malloc library {:?} is not linked into MMTk", backend),
+        }
+    }
+
+    /// # Safety
+    /// Same as `calloc` in C.
+    pub unsafe fn calloc(&self, num: usize, size: usize) -> *mut libc::c_void {
+        (self.calloc)(num, size)
+    }
+
+    /// # Safety
+    /// Same as `posix_memalign` in C.
+    pub unsafe fn posix_memalign(
+        &self,
+        memptr: *mut *mut libc::c_void,
+        align: usize,
+        size: usize,
+    ) -> libc::c_int {
+        (self.posix_memalign)(memptr, align, size)
+    }
+
+    /// # Safety
+    /// Same as `malloc_usable_size` in C.
+    pub unsafe fn malloc_usable_size(&self, ptr: *mut libc::c_void) -> usize {
+        (self.malloc_usable_size)(ptr)
+    }
+
+    /// # Safety
+    /// Same as `free` in C.
+    pub unsafe fn free(&self, ptr: *mut libc::c_void) {
+        (self.free)(ptr)
+    }
+
+    /// Free multiple pointers. Each pointer comes with its usable size, which some libraries use to free memory
+    /// without looking up the size class of the pointer.
+    ///
+    /// # Safety
+    /// Each pointer must be returned by this library and not freed yet, and each size must be the usable size
+    /// of the pointer.
+    pub unsafe fn free_bulk(&self, regions: &[(*mut libc::c_void, usize)]) {
+        (self.free_bulk)(regions)
+    }
+}
+
 // Different malloc libraries
 
 // TODO: We should conditinally include some methods in the module, such as posix extension and GNU extension.
@@ -26,6 +94,20 @@ mod jemalloc {
     pub use jemalloc_sys::posix_memalign;
     // GNU
     pub use jemalloc_sys::malloc_usable_size;
+
+    pub(super) const LIBRARY: super::MallocLibrary = super::MallocLibrary {
+        calloc: |num, size| unsafe { calloc(num, size) as _ },
+        posix_memalign: |memptr, align, size| unsafe { posix_memalign(memptr as _, align, size) },
+        malloc_usable_size: |ptr| unsafe { malloc_usable_size(ptr as _) },
+        free: |ptr| unsafe { free(ptr as _) },
+        // Sized deallocation skips looking up the size class of each pointer. Any size between the requested
+        // size and the usable size is accepted.
+        free_bulk: |regions| {
+            for (ptr, size) in regions {
+                unsafe { jemalloc_sys::sdallocx(*ptr as _, *size, 0) }
+            }
+        },
+    };
 }
 
 #[cfg(feature = "malloc_mimalloc")]
@@ -40,10 +122,22 @@ mod mimalloc {
     pub use mimalloc_sys::mi_posix_memalign as posix_memalign;
     // GNU
     pub use mimalloc_sys::mi_malloc_usable_size as malloc_usable_size;
+
+    pub(super) const LIBRARY: super::MallocLibrary = super::MallocLibrary {
+        calloc: |num, size| unsafe { calloc(num, size) as _ },
+        posix_memalign: |memptr, align, size| unsafe { posix_memalign(memptr as _, align, size) },
+        malloc_usable_size: |ptr| unsafe { malloc_usable_size(ptr as _) },
+        free: |ptr| unsafe { free(ptr as _) },
+        // Freeing to mimalloc is cheap for the thread that owns the heap, so we simply free each pointer.
+        free_bulk: |regions| {
+            for (ptr, _) in regions {
+                unsafe { free(*ptr as _) }
+            }
+        },
+    };
 }
 
-/// If no malloc lib is specified, use the libc implementation
-#[cfg(not(any(feature = "malloc_jemalloc", feature = "malloc_mimalloc",)))]
+/// The libc implementation. It is always available, and it is the default if no malloc lib is specified.
 mod libc_malloc {
     // Normal 4K page
     pub const LOG_BYTES_IN_MALLOC_PAGE: u8 = crate::util::constants::LOG_BYTES_IN_PAGE;
@@ -60,4 +154,17 @@ mod libc_malloc {
     }
     #[cfg(target_os = "macos")]
     pub use self::malloc_size as malloc_usable_size;
+
+    pub(super) const LIBRARY: super::MallocLibrary = super::MallocLibrary {
+        calloc: |num, size| unsafe { calloc(num, size) },
+        posix_memalign: |memptr, align, size| unsafe { posix_memalign(memptr, align, size) },
+        malloc_usable_size: |ptr| unsafe { malloc_usable_size(ptr) },
+        free: |ptr| unsafe { free(ptr) },
+        // libc malloc has no batched free.
+        free_bulk: |regions| {
+            for (ptr, _) in regions {
+                unsafe { free(*ptr) }
+            }
+        },
+    };
 }
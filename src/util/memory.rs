@@ -1,10 +1,14 @@
-use crate::util::alloc::AllocationError;
+use crate::util::alloc::{AllocationError, AllocationErrorContext};
 use crate::util::opaque_pointer::*;
+use crate::util::options::{NumaPolicy, Options, PretouchMode};
 use crate::util::Address;
 use crate::vm::{Collection, VMBinding};
 use bytemuck::NoUninit;
+#[cfg(unix)]
 use libc::{PROT_EXEC, PROT_NONE, PROT_READ, PROT_WRITE};
 use std::io::{Error, Result};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use strum_macros::EnumString;
 use sysinfo::MemoryRefreshKind;
 use sysinfo::{RefreshKind, System};
 
@@ -15,6 +19,16 @@ const MMAP_FLAGS: libc::c_int = libc::MAP_ANON | libc::MAP_PRIVATE | libc::MAP_F
 // MAP_FIXED is used instead of MAP_FIXED_NOREPLACE (which is not available on macOS). We are at the risk of overwriting pre-existing mappings.
 const MMAP_FLAGS: libc::c_int = libc::MAP_ANON | libc::MAP_PRIVATE | libc::MAP_FIXED;
 
+mod registry;
+pub use registry::{find_mmap_record, format_mmap_records, get_mmap_records, MmapRecord};
+
+#[cfg(windows)]
+mod windows;
+#[cfg(windows)]
+pub use windows::{
+    dzmmap, dzmmap_noreplace, mmap_noreserve, mprotect, mprotect_read_only, munmap, munprotect,
+};
+
 /// Strategy for performing mmap
 #[derive(Debug, Copy, Clone)]
 pub struct MmapStrategy {
@@ -22,6 +36,11 @@ pub struct MmapStrategy {
     pub huge_page: HugePageSupport,
     /// The protection flags for mmap
     pub prot: MmapProtection,
+    /// The NUMA policy for the mapped memory
+    pub numa: NumaPolicy,
+    /// Do we pre-touch the memory when it is mapped?  This has no effect on the memory mapped
+    /// with [`MmapProtection::NoAccess`].
+    pub populate: bool,
 }
 
 impl MmapStrategy {
@@ -34,6 +53,8 @@ impl MmapStrategy {
                 HugePageSupport::No
             },
             prot,
+            numa: NumaPolicy::FirstTouch,
+            populate: false,
         }
     }
 
@@ -41,11 +62,29 @@ impl MmapStrategy {
     pub const INTERNAL_MEMORY: Self = Self {
         huge_page: HugePageSupport::No,
         prot: MmapProtection::ReadWrite,
+        numa: NumaPolicy::FirstTouch,
+        populate: false,
     };
 
-    /// The strategy for MMTk side metadata
+    /// The strategy for MMTk side metadata, without huge pages. Use [`MmapStrategy::side_metadata`]
+    /// to also respect the option `transparent_hugepages_side_metadata`.
     pub const SIDE_METADATA: Self = Self::INTERNAL_MEMORY;
 
+    /// The strategy for the side metadata of the spaces of an MMTk instance with the given options.
+    /// Transparent huge pages are used if the option `transparent_hugepages_side_metadata` is set,
+    /// and the memory is pre-touched if the option `pretouch` is set.
+    pub fn side_metadata(options: &Options) -> Self {
+        Self {
+            huge_page: if *options.transparent_hugepages_side_metadata {
+                HugePageSupport::TransparentHugePages
+            } else {
+                HugePageSupport::No
+            },
+            populate: *options.pretouch != PretouchMode::No,
+            ..Self::SIDE_METADATA
+        }
+    }
+
     /// The strategy for MMTk's test memory
     #[cfg(test)]
     pub const TEST: Self = Self::INTERNAL_MEMORY;
@@ -59,16 +98,20 @@ pub enum MmapProtection {
     ReadWrite,
     /// Allow read + write + code execution
     ReadWriteExec,
+    /// Allow read + code execution
+    ReadExec,
     /// Do not allow any access
     NoAccess,
 }
 
+#[cfg(unix)]
 impl MmapProtection {
     /// Turn the protection enum into the native flags
     pub fn into_native_flags(self) -> libc::c_int {
         match self {
             Self::ReadWrite => PROT_READ | PROT_WRITE,
             Self::ReadWriteExec => PROT_READ | PROT_WRITE | PROT_EXEC,
+            Self::ReadExec => PROT_READ | PROT_EXEC,
             Self::NoAccess => PROT_NONE,
         }
     }
@@ -76,12 +119,99 @@ impl MmapProtection {
 
 /// Support for huge pages
 #[repr(u8)]
-#[derive(Debug, Copy, Clone, NoUninit)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, NoUninit, EnumString)]
 pub enum HugePageSupport {
     /// No support for huge page
     No,
     /// Enable transparent huge pages for the pages that are mapped. This option is only for linux.
     TransparentHugePages,
+    /// Map the pages with explicit huge pages from the hugetlbfs pool (`MAP_HUGETLB`). If the pool
+    /// does not have enough free huge pages, or the memory is not aligned to huge pages, fall back to
+    /// transparent huge pages. This option is only for linux.
+    HugeTLB,
+}
+
+/// Bytes that are mapped with explicit huge pages.
+static HUGETLB_BYTES: AtomicUsize = AtomicUsize::new(0);
+/// Bytes for which explicit huge pages are requested, but fell back to transparent huge pages.
+static HUGETLB_FALLBACK_BYTES: AtomicUsize = AtomicUsize::new(0);
+/// Bytes that are advised to use transparent huge pages, including the fallbacks.
+static THP_ADVISED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+/// Statistics about the huge pages used for the memory that MMTk maps.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct HugePageStats {
+    /// Bytes that are mapped with explicit huge pages ([`HugePageSupport::HugeTLB`]).
+    pub hugetlb_bytes: usize,
+    /// Bytes for which explicit huge pages are requested, but fell back to transparent huge pages.
+    pub hugetlb_fallback_bytes: usize,
+    /// Bytes that are advised to use transparent huge pages, including the fallbacks. The kernel
+    /// may not back all of them with huge pages.
+    pub thp_advised_bytes: usize,
+    /// Bytes of anonymous memory in the whole process that the kernel actually backs with
+    /// transparent huge pages (`AnonHugePages` in `/proc/self/smaps_rollup`). This includes memory
+    /// not mapped by MMTk. It is 0 if the information is not available.
+    pub thp_bytes: usize,
+}
+
+impl HugePageStats {
+    /// Return true if MMTk has requested any huge pages.
+    pub fn is_used(&self) -> bool {
+        self.hugetlb_bytes + self.hugetlb_fallback_bytes + self.thp_advised_bytes > 0
+    }
+}
+
+/// Get the statistics about the huge pages used for the memory that MMTk maps. The numbers are
+/// accumulated over the lifetime of the process, and memory that is later unmapped is not
+/// subtracted.
+pub fn get_huge_page_stats() -> HugePageStats {
+    HugePageStats {
+        hugetlb_bytes: HUGETLB_BYTES.load(Ordering::Relaxed),
+        hugetlb_fallback_bytes: HUGETLB_FALLBACK_BYTES.load(Ordering::Relaxed),
+        thp_advised_bytes: THP_ADVISED_BYTES.load(Ordering::Relaxed),
+        thp_bytes: get_anon_huge_pages_bytes(),
+    }
+}
+
+fn get_anon_huge_pages_bytes() -> usize {
+    #[cfg(target_os = "linux")]
+    if let Ok(rollup) = std::fs::read_to_string("/proc/self/smaps_rollup") {
+        // The line looks like "AnonHugePages:     2048 kB".
+        return rollup
+            .lines()
+            .find_map(|line| line.strip_prefix("AnonHugePages:"))
+            .and_then(|kb| {
+                kb.trim()
+                    .trim_end_matches("kB")
+                    .trim()
+                    .parse::<usize>()
+                    .ok()
+            })
+            .map_or(0, |kb| kb << 10);
+    }
+    0
+}
+
+/// Get the size of the default explicit huge pages (`Hugepagesize` in `/proc/meminfo`). Return
+/// `None` if explicit huge pages are not available.
+#[cfg(target_os = "linux")]
+fn get_hugetlb_page_size() -> Option<usize> {
+    static HUGETLB_PAGE_SIZE: std::sync::OnceLock<Option<usize>> = std::sync::OnceLock::new();
+    *HUGETLB_PAGE_SIZE.get_or_init(|| {
+        // The line looks like "Hugepagesize:       2048 kB".
+        std::fs::read_to_string("/proc/meminfo")
+            .ok()?
+            .lines()
+            .find_map(|line| line.strip_prefix("Hugepagesize:"))
+            .and_then(|kb| {
+                kb.trim()
+                    .trim_end_matches("kB")
+                    .trim()
+                    .parse::<usize>()
+                    .ok()
+            })
+            .map(|kb| kb << 10)
+    })
 }
 
 /// Annotation for an mmap entry.
@@ -90,7 +220,8 @@ pub enum HugePageSupport {
 /// require an annotation that indicates the purpose of the memory mapping.
 ///
 /// This is for debugging.  On Linux, mmtk-core will use `prctl` with `PR_SET_VMA` to set the
-/// human-readable name for the given mmap region.  The annotation is ignored on other platforms.
+/// human-readable name for the given mmap region.  On all platforms, the annotation is also kept in
+/// a registry that can be queried with [`find_mmap_record`] and [`get_mmap_records`].
 ///
 /// Note that when using `Map32` (even when running on 64-bit architectures), the discontiguous
 /// memory range is shared between different spaces. Spaces may use `mmap` to map new chunks, but
@@ -159,7 +290,7 @@ impl std::fmt::Display for MmapAnnotation<'_> {
 pub(crate) fn result_is_mapped(result: Result<()>) -> bool {
     match result {
         Ok(_) => false,
-        Err(err) => err.raw_os_error().unwrap() == libc::EEXIST,
+        Err(err) => err.kind() == std::io::ErrorKind::AlreadyExists,
     }
 }
 
@@ -175,6 +306,36 @@ pub fn set(start: Address, val: u8, len: usize) {
     }
 }
 
+/// The byte pattern to fill reclaimed memory with, if the option `poison_on_free` is set.
+/// A word filled with this pattern is not a valid address on common 64-bit architectures.
+pub const POISON_BYTE: u8 = 0xdf;
+
+/// Fill a range of memory with the poison pattern [`POISON_BYTE`].
+pub fn poison(start: Address, len: usize) {
+    set(start, POISON_BYTE, len);
+}
+
+/// Tell memory checkers (AddressSanitizer and Valgrind Memcheck) that a range of memory must not be
+/// accessed, e.g. when the memory is freed by GC. This does nothing unless the feature "sanitizer" is enabled.
+pub fn mark_noaccess(_start: Address, _len: usize) {
+    #[cfg(feature = "sanitizer")]
+    crate::util::sanitizer::annotate(_start, _len, crate::util::sanitizer::MemoryState::NoAccess);
+}
+
+/// Tell memory checkers that a range of memory is accessible but not initialized, e.g. when the memory
+/// is about to be allocated. This does nothing unless the feature "sanitizer" is enabled.
+pub fn mark_undefined(_start: Address, _len: usize) {
+    #[cfg(feature = "sanitizer")]
+    crate::util::sanitizer::annotate(_start, _len, crate::util::sanitizer::MemoryState::Undefined);
+}
+
+/// Tell memory checkers that a range of memory is accessible and initialized, e.g. for side metadata.
+/// This does nothing unless the feature "sanitizer" is enabled.
+pub fn mark_defined(_start: Address, _len: usize) {
+    #[cfg(feature = "sanitizer")]
+    crate::util::sanitizer::annotate(_start, _len, crate::util::sanitizer::MemoryState::Defined);
+}
+
 /// Demand-zero mmap:
 /// This function mmaps the memory and guarantees to zero all mapped memory.
 /// This function WILL overwrite existing memory mapping. The user of this function
@@ -184,6 +345,7 @@ pub fn set(start: Address, val: u8, len: usize) {
 /// This function WILL overwrite existing memory mapping if there is any. So only use this function if you know
 /// the memory has been reserved by mmtk (e.g. after the use of mmap_noreserve()). Otherwise using this function
 /// may corrupt others' data.
+#[cfg(unix)]
 #[allow(clippy::let_and_return)] // Zeroing is not neceesary for some OS/s
 pub unsafe fn dzmmap(
     start: Address,
@@ -203,6 +365,7 @@ pub unsafe fn dzmmap(
 /// Demand-zero mmap (no replace):
 /// This function mmaps the memory and guarantees to zero all mapped memory.
 /// This function will not overwrite existing memory mapping, and it will result Err if there is an existing mapping.
+#[cfg(unix)]
 #[allow(clippy::let_and_return)] // Zeroing is not neceesary for some OS/s
 pub fn dzmmap_noreplace(
     start: Address,
@@ -224,6 +387,7 @@ pub fn dzmmap_noreplace(
 /// This function does not reserve swap space for this mapping, which means there is no guarantee that writes to the
 /// mapping can always be successful. In case of out of physical memory, one may get a segfault for writing to the mapping.
 /// We can use this to reserve the address range, and then later overwrites the mapping with dzmmap().
+#[cfg(unix)]
 pub fn mmap_noreserve(
     start: Address,
     size: usize,
@@ -235,6 +399,7 @@ pub fn mmap_noreserve(
     mmap_fixed(start, size, flags, strategy, anno)
 }
 
+#[cfg(unix)]
 fn mmap_fixed(
     start: Address,
     size: usize,
@@ -242,12 +407,58 @@ fn mmap_fixed(
     strategy: MmapStrategy,
     _anno: &MmapAnnotation,
 ) -> Result<()> {
+    // Reserving address ranges is not a fault point.
+    #[cfg(feature = "fault_injection")]
+    if !matches!(strategy.prot, MmapProtection::NoAccess) {
+        use crate::util::fault_injection::{should_fail, FaultPoint};
+        let point = match _anno {
+            MmapAnnotation::SideMeta { .. } => FaultPoint::MetadataMmap,
+            _ => FaultPoint::Mmap,
+        };
+        if should_fail(point) {
+            return Err(Error::from_raw_os_error(libc::ENOMEM));
+        }
+    }
+
     let ptr = start.to_mut_ptr();
     let prot = strategy.prot.into_native_flags();
-    wrap_libc_call(
-        &|| unsafe { libc::mmap(start.to_mut_ptr(), size, prot, flags, -1, 0) },
-        ptr,
-    )?;
+    let populate = strategy.populate && !matches!(strategy.prot, MmapProtection::NoAccess);
+    #[cfg(target_os = "linux")]
+    let flags = if populate {
+        flags | libc::MAP_POPULATE
+    } else {
+        flags
+    };
+    // The memory that is only reserved (with `PROT_NONE`) will be mapped again before it is used, so
+    // we only use explicit huge pages when the memory is mapped for use.
+    let huge_page = if let Some(file) =
+        crate::util::heap_file::create_backing_file(start, size, strategy.prot, _anno)?
+    {
+        use std::os::fd::AsRawFd;
+        let flags = (flags & !(libc::MAP_ANON | libc::MAP_PRIVATE | libc::MAP_NORESERVE))
+            | libc::MAP_SHARED;
+        wrap_libc_call(
+            &|| unsafe { libc::mmap(start.to_mut_ptr(), size, prot, flags, file.as_raw_fd(), 0) },
+            ptr,
+        )?;
+        HugePageSupport::No
+    } else if strategy.huge_page == HugePageSupport::HugeTLB
+        && !matches!(strategy.prot, MmapProtection::NoAccess)
+    {
+        mmap_hugetlb(start, size, prot, flags)?
+    } else {
+        wrap_libc_call(
+            &|| unsafe { libc::mmap(start.to_mut_ptr(), size, prot, flags, -1, 0) },
+            ptr,
+        )?;
+        strategy.huge_page
+    };
+    registry::record(
+        start,
+        size,
+        _anno,
+        matches!(strategy.prot, MmapProtection::NoAccess),
+    );
 
     #[cfg(all(
         any(target_os = "linux", target_os = "android"),
@@ -279,15 +490,31 @@ fn mmap_fixed(
         }
     }
 
-    match strategy.huge_page {
-        HugePageSupport::No => Ok(()),
+    // The memory that is only reserved (with `PROT_NONE`) will be mapped again before it is used.
+    #[cfg(target_os = "linux")]
+    if !matches!(strategy.prot, MmapProtection::NoAccess) {
+        set_numa_policy(start, size, strategy.numa)?;
+    }
+
+    // `MAP_POPULATE` has populated the memory on Linux.
+    #[cfg(not(target_os = "linux"))]
+    if populate {
+        touch_pages(start, size);
+    }
+
+    match huge_page {
+        HugePageSupport::No | HugePageSupport::HugeTLB => Ok(()),
         HugePageSupport::TransparentHugePages => {
             #[cfg(target_os = "linux")]
             {
                 wrap_libc_call(
                     &|| unsafe { libc::madvise(start.to_mut_ptr(), size, libc::MADV_HUGEPAGE) },
                     0,
-                )
+                )?;
+                if !matches!(strategy.prot, MmapProtection::NoAccess) {
+                    THP_ADVISED_BYTES.fetch_add(size, Ordering::Relaxed);
+                }
+                Ok(())
             }
             // Setting the transparent hugepage option to true will not pass
             // the validation on non-Linux OSes
@@ -297,9 +524,157 @@ fn mmap_fixed(
     }
 }
 
+/// Pre-touch the given memory so that it is backed by physical memory, without changing its
+/// content.  This can be called while other threads are using the memory.  On Linux, this uses
+/// `madvise(MADV_POPULATE_WRITE)`, and silently gives up if the memory is protected or unmapped.
+/// Otherwise (or if the kernel does not support it), this writes to each page, which faults on
+/// protected pages.  The caller sets `may_be_protected` if some of the pages may be protected with
+/// `mprotect`, in which case this gives up instead of writing to the pages.
+pub(crate) fn pretouch_memory(start: Address, size: usize, may_be_protected: bool) {
+    #[cfg(target_os = "linux")]
+    {
+        // From <linux/mman.h>, available since Linux 5.14. The libc crate does not define it.
+        const MADV_POPULATE_WRITE: libc::c_int = 23;
+        let result = wrap_libc_call(
+            &|| unsafe { libc::madvise(start.to_mut_ptr(), size, MADV_POPULATE_WRITE) },
+            0,
+        );
+        match result {
+            Ok(()) => {}
+            // Older kernels do not support `MADV_POPULATE_WRITE`.
+            Err(e) if e.raw_os_error() == Some(libc::EINVAL) && !may_be_protected => {
+                touch_pages(start, size)
+            }
+            Err(e) => debug!("Failed to pre-touch {size} bytes at {start}: {e}"),
+        }
+    }
+    #[cfg(not(target_os = "linux"))]
+    if !may_be_protected {
+        touch_pages(start, size);
+    }
+}
+
+/// Write to every page of the given memory without changing its content.  The memory must be
+/// mapped and accessible.
+fn touch_pages(start: Address, size: usize) {
+    use crate::util::constants::BYTES_IN_PAGE;
+    let page_size = BYTES_IN_PAGE;
+    let mut page = start.align_down(page_size);
+    while page < start + size {
+        // An atomic read-modify-write does not race with other threads writing to the same word.
+        unsafe { page.as_ref::<AtomicUsize>() }.fetch_add(0, Ordering::Relaxed);
+        page += page_size;
+    }
+}
+
+/// Map the memory with explicit huge pages. If that fails, map the memory with normal pages, and
+/// return [`HugePageSupport::TransparentHugePages`] so that the caller advises the kernel to use
+/// transparent huge pages instead.
+#[cfg(target_os = "linux")]
+fn mmap_hugetlb(
+    start: Address,
+    size: usize,
+    prot: libc::c_int,
+    flags: libc::c_int,
+) -> Result<HugePageSupport> {
+    let ptr = start.to_mut_ptr();
+    // The kernel rounds the size up to huge pages, which would clobber the memory after the range.
+    let aligned = get_hugetlb_page_size()
+        .is_some_and(|page_size| start.is_aligned_to(page_size) && size % page_size == 0);
+    if aligned {
+        let result = wrap_libc_call(
+            &|| unsafe { libc::mmap(ptr, size, prot, flags | libc::MAP_HUGETLB, -1, 0) },
+            ptr,
+        );
+        match result {
+            Ok(()) => {
+                HUGETLB_BYTES.fetch_add(size, Ordering::Relaxed);
+                return Ok(HugePageSupport::HugeTLB);
+            }
+            Err(e) => debug!("Failed to mmap {size} bytes at {start} with MAP_HUGETLB: {e}"),
+        }
+    }
+    HUGETLB_FALLBACK_BYTES.fetch_add(size, Ordering::Relaxed);
+    wrap_libc_call(
+        &|| unsafe { libc::mmap(ptr, size, prot, flags, -1, 0) },
+        ptr,
+    )?;
+    Ok(HugePageSupport::TransparentHugePages)
+}
+
+// Setting explicit huge pages will not pass the validation on non-Linux OSes
+#[cfg(all(unix, not(target_os = "linux")))]
+fn mmap_hugetlb(
+    _start: Address,
+    _size: usize,
+    _prot: libc::c_int,
+    _flags: libc::c_int,
+) -> Result<HugePageSupport> {
+    unreachable!()
+}
+
+/// Set the NUMA policy for the given memory (in page granularity) with the `mbind` system call.
+#[cfg(target_os = "linux")]
+fn set_numa_policy(start: Address, size: usize, policy: NumaPolicy) -> Result<()> {
+    // From <numaif.h>. The libc crate does not define them.
+    const MPOL_BIND: libc::c_int = 2;
+    const MPOL_INTERLEAVE: libc::c_int = 3;
+    const MAX_NUMA_NODES: usize = 1024;
+    const BITS_PER_WORD: usize = libc::c_ulong::BITS as usize;
+
+    let mut nodemask = [0 as libc::c_ulong; MAX_NUMA_NODES / BITS_PER_WORD];
+    let (mode, nodes) = match policy {
+        NumaPolicy::FirstTouch => return Ok(()),
+        NumaPolicy::Interleave => (MPOL_INTERLEAVE, get_numa_nodes()),
+        NumaPolicy::Bind(node) => (MPOL_BIND, vec![node]),
+    };
+    for node in nodes {
+        assert!(node < MAX_NUMA_NODES, "NUMA node {node} is out of range");
+        nodemask[node / BITS_PER_WORD] |= 1 << (node % BITS_PER_WORD);
+    }
+    wrap_libc_call(
+        &|| unsafe {
+            libc::syscall(
+                libc::SYS_mbind,
+                start.to_mut_ptr::<libc::c_void>(),
+                size,
+                mode,
+                nodemask.as_ptr(),
+                MAX_NUMA_NODES + 1,
+                0,
+            )
+        },
+        0,
+    )
+}
+
+/// Get the IDs of the online NUMA nodes. Return `[0]` if the information is not available.
+pub(crate) fn get_numa_nodes() -> Vec<usize> {
+    #[cfg(target_os = "linux")]
+    if let Ok(online) = std::fs::read_to_string("/sys/devices/system/node/online") {
+        // The format is a list of node IDs and ranges, for example, "0-3,5".
+        let mut nodes = vec![];
+        for part in online.trim().split(',') {
+            let range = match part.split_once('-') {
+                Some((first, last)) => first.parse::<usize>().ok().zip(last.parse().ok()),
+                None => part.parse::<usize>().ok().map(|node| (node, node)),
+            };
+            match range {
+                Some((first, last)) => nodes.extend(first..=last),
+                None => return vec![0],
+            }
+        }
+        return nodes;
+    }
+    vec![0]
+}
+
 /// Unmap the given memory (in page granularity). This wraps the unsafe libc munmap call.
+#[cfg(unix)]
 pub fn munmap(start: Address, size: usize) -> Result<()> {
-    wrap_libc_call(&|| unsafe { libc::munmap(start.to_mut_ptr(), size) }, 0)
+    wrap_libc_call(&|| unsafe { libc::munmap(start.to_mut_ptr(), size) }, 0)?;
+    registry::forget(start, size);
+    Ok(())
 }
 
 /// Properly handle errors from a mmap Result, including invoking the binding code in the case of
@@ -314,13 +689,19 @@ pub fn handle_mmap_error<VM: VMBinding>(
 
     eprintln!("Failed to mmap {}, size {}", addr, bytes);
     eprintln!("{}", get_process_memory_maps());
+    eprintln!("Memory mapped by MMTk:\n{}", format_mmap_records());
 
     match error.kind() {
         // From Rust nightly 2021-05-12, we started to see Rust added this ErrorKind.
         ErrorKind::OutOfMemory => {
             // Signal `MmapOutOfMemory`. Expect the VM to abort immediately.
             trace!("Signal MmapOutOfMemory!");
-            VM::VMCollection::out_of_memory(tls, AllocationError::MmapOutOfMemory);
+            probe!(mmtk, out_of_memory, AllocationError::MmapOutOfMemory, bytes);
+            VM::VMCollection::out_of_memory(
+                tls,
+                AllocationError::MmapOutOfMemory,
+                &AllocationErrorContext::without_space(bytes),
+            );
             unreachable!()
         }
         // Before Rust had ErrorKind::OutOfMemory, this is how we capture OOM from OS calls.
@@ -332,7 +713,12 @@ pub fn handle_mmap_error<VM: VMBinding>(
                 if os_errno == libc::ENOMEM {
                     // Signal `MmapOutOfMemory`. Expect the VM to abort immediately.
                     trace!("Signal MmapOutOfMemory!");
-                    VM::VMCollection::out_of_memory(tls, AllocationError::MmapOutOfMemory);
+                    probe!(mmtk, out_of_memory, AllocationError::MmapOutOfMemory, bytes);
+                    VM::VMCollection::out_of_memory(
+                        tls,
+                        AllocationError::MmapOutOfMemory,
+                        &AllocationErrorContext::without_space(bytes),
+                    );
                     unreachable!()
                 }
             }
@@ -356,16 +742,7 @@ pub(crate) fn panic_if_unmapped(_start: Address, _size: usize, _anno: &MmapAnnot
     #[cfg(target_os = "linux")]
     {
         let flags = MMAP_FLAGS;
-        match mmap_fixed(
-            _start,
-            _size,
-            flags,
-            MmapStrategy {
-                huge_page: HugePageSupport::No,
-                prot: MmapProtection::ReadWrite,
-            },
-            _anno,
-        ) {
+        match mmap_fixed(_start, _size, flags, MmapStrategy::INTERNAL_MEMORY, _anno) {
             Ok(_) => panic!("{} of size {} is not mapped", _start, _size),
             Err(e) => {
                 assert!(
@@ -379,6 +756,7 @@ pub(crate) fn panic_if_unmapped(_start: Address, _size: usize, _anno: &MmapAnnot
 }
 
 /// Unprotect the given memory (in page granularity) to allow access (PROT_READ/WRITE/EXEC).
+#[cfg(unix)]
 pub fn munprotect(start: Address, size: usize, prot: MmapProtection) -> Result<()> {
     let prot = prot.into_native_flags();
     wrap_libc_call(
@@ -388,6 +766,7 @@ pub fn munprotect(start: Address, size: usize, prot: MmapProtection) -> Result<(
 }
 
 /// Protect the given memory (in page granularity) to forbid any access (PROT_NONE).
+#[cfg(unix)]
 pub fn mprotect(start: Address, size: usize) -> Result<()> {
     wrap_libc_call(
         &|| unsafe { libc::mprotect(start.to_mut_ptr(), size, PROT_NONE) },
@@ -395,6 +774,15 @@ pub fn mprotect(start: Address, size: usize) -> Result<()> {
     )
 }
 
+/// Protect the given memory (in page granularity) to forbid writing to it (PROT_READ).
+#[cfg(unix)]
+pub fn mprotect_read_only(start: Address, size: usize) -> Result<()> {
+    wrap_libc_call(
+        &|| unsafe { libc::mprotect(start.to_mut_ptr(), size, PROT_READ) },
+        0,
+    )
+}
+
 fn wrap_libc_call<T: PartialEq>(f: &dyn Fn() -> T, expect: T) -> Result<()> {
     let ret = f();
     if ret == expect {
@@ -502,6 +890,39 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_pretouch() {
+        serial_test(|| {
+            with_cleanup(
+                || {
+                    let strategy = MmapStrategy {
+                        populate: true,
+                        ..MmapStrategy::TEST
+                    };
+                    let res =
+                        dzmmap_noreplace(START, BYTES_IN_PAGE * 4, strategy, mmap_anno_test!());
+                    assert!(res.is_ok());
+
+                    // Pre-touching does not change the content of the memory.
+                    unsafe { START.store(42usize) };
+                    pretouch_memory(START, BYTES_IN_PAGE * 4, false);
+                    assert_eq!(unsafe { START.load::<usize>() }, 42);
+                    for i in 1..4 {
+                        assert_eq!(unsafe { (START + BYTES_IN_PAGE * i).load::<usize>() }, 0);
+                    }
+
+                    // Protected pages are skipped instead of faulting.
+                    assert!(mprotect(START + BYTES_IN_PAGE, BYTES_IN_PAGE).is_ok());
+                    pretouch_memory(START, BYTES_IN_PAGE * 4, true);
+                    assert_eq!(unsafe { START.load::<usize>() }, 42);
+                },
+                || {
+                    assert!(munmap(START, BYTES_IN_PAGE * 4).is_ok());
+                },
+            );
+        });
+    }
+
     #[test]
     fn test_munmap() {
         serial_test(|| {
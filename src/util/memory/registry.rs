@@ -0,0 +1,168 @@
+//! A registry of the memory that MMTk has mapped, and the purpose of each mapping.
+//!
+//! The registry records the [`MmapAnnotation`] of every successful mapping, and forgets the
+//! memory when it is unmapped. It answers the question "what is this address?" while debugging,
+//! and it is dumped when mmap fails or when MMTk runs out of memory. Unlike the `prctl`
+//! annotation, the registry works on every platform and does not depend on the kernel version.
+//!
+//! Like the annotation, a record reflects the mmap call that last mapped the memory. Spaces that
+//! share the discontiguous memory range may reuse a chunk that was first mapped by another space.
+
+use super::MmapAnnotation;
+use crate::util::Address;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+/// A range of memory mapped by MMTk and the purpose of the mapping.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MmapRecord {
+    /// The start address of the range (inclusive).
+    pub start: Address,
+    /// The end address of the range (exclusive).
+    pub end: Address,
+    /// The annotation of the mmap call, formatted as in the `prctl` annotation, such as
+    /// `mmtk:space:immix`.
+    pub annotation: String,
+    /// True if the memory is only reserved (quarantined) and not accessible yet.
+    pub reserved: bool,
+}
+
+impl std::fmt::Display for MmapRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{} {}", self.start, self.end, self.annotation)?;
+        if self.reserved {
+            write!(f, " (reserved)")?;
+        }
+        Ok(())
+    }
+}
+
+/// The records keyed by their start addresses. The records never overlap.
+static REGISTRY: Mutex<BTreeMap<Address, MmapRecord>> = Mutex::new(BTreeMap::new());
+
+/// Remove the given range from the records, and keep the parts of the records outside the range.
+fn remove_range(records: &mut BTreeMap<Address, MmapRecord>, start: Address, end: Address) {
+    // As the records do not overlap, their ends are sorted as their starts are.
+    let overlapping: Vec<Address> = records
+        .range(..end)
+        .rev()
+        .take_while(|(_, record)| record.end > start)
+        .map(|(key, _)| *key)
+        .collect();
+    for key in overlapping {
+        let record = records.remove(&key).unwrap();
+        if record.start < start {
+            records.insert(
+                record.start,
+                MmapRecord {
+                    end: start,
+                    ..record.clone()
+                },
+            );
+        }
+        if record.end > end {
+            records.insert(
+                end,
+                MmapRecord {
+                    start: end,
+                    ..record
+                },
+            );
+        }
+    }
+}
+
+/// Record that the given memory is mapped for the purpose in the annotation. This replaces the
+/// records of the memory if it was mapped before.
+pub(super) fn record(start: Address, size: usize, anno: &MmapAnnotation, reserved: bool) {
+    let end = start + size;
+    let mut records = REGISTRY.lock().unwrap();
+    remove_range(&mut records, start, end);
+    records.insert(
+        start,
+        MmapRecord {
+            start,
+            end,
+            annotation: anno.to_string(),
+            reserved,
+        },
+    );
+}
+
+/// Forget the records of the given memory, as it is unmapped.
+pub(super) fn forget(start: Address, size: usize) {
+    let mut records = REGISTRY.lock().unwrap();
+    remove_range(&mut records, start, start + size);
+}
+
+/// Find the record of the memory that contains the given address. Return `None` if the address
+/// is not mapped by MMTk.
+pub fn find_mmap_record(addr: Address) -> Option<MmapRecord> {
+    let records = REGISTRY.lock().unwrap();
+    records
+        .range(..=addr)
+        .next_back()
+        .map(|(_, record)| record)
+        .filter(|record| addr < record.end)
+        .cloned()
+}
+
+/// Get all the records of the memory mapped by MMTk, sorted by their addresses.
+pub fn get_mmap_records() -> Vec<MmapRecord> {
+    REGISTRY.lock().unwrap().values().cloned().collect()
+}
+
+/// Format all the records, one record per line, for printing.
+pub fn format_mmap_records() -> String {
+    // Do not panic if the lock is poisoned, as this is used while reporting other errors.
+    let records = match REGISTRY.lock() {
+        Ok(records) => records,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    records
+        .values()
+        .map(|record| format!("{record}\n"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mmap_anno_test;
+
+    // Those addresses are never mapped, so the records do not interfere with other tests.
+    const START: Address = unsafe { Address::from_usize(0x7f00_0000_0000) };
+    const SIZE: usize = 0x10000;
+
+    #[test]
+    fn record_and_forget() {
+        record(START, SIZE, mmap_anno_test!(), true);
+        let record = find_mmap_record(START + SIZE / 2).unwrap();
+        assert_eq!(record.start, START);
+        assert_eq!(record.end, START + SIZE);
+        assert!(record.reserved);
+        assert!(find_mmap_record(START + SIZE).is_none());
+
+        // Map the middle of the range again. It splits the record.
+        record(
+            START + 0x4000usize,
+            0x4000,
+            &MmapAnnotation::Misc { name: "middle" },
+            false,
+        );
+        let before = find_mmap_record(START).unwrap();
+        assert_eq!(before.end, START + 0x4000usize);
+        let middle = find_mmap_record(START + 0x4000usize).unwrap();
+        assert_eq!(middle.annotation, "mmtk:misc:middle");
+        assert!(!middle.reserved);
+        let after = find_mmap_record(START + 0x8000usize).unwrap();
+        assert_eq!(after.start, START + 0x8000usize);
+        assert_eq!(after.end, START + SIZE);
+        assert!(format_mmap_records().contains("mmtk:misc:middle"));
+
+        forget(START, SIZE);
+        assert!(find_mmap_record(START).is_none());
+        assert!(find_mmap_record(START + 0x4000usize).is_none());
+        assert!(find_mmap_record(START + 0x8000usize).is_none());
+    }
+}
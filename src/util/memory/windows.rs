@@ -0,0 +1,242 @@
+//! The Windows implementation of the memory mapping functions, based on `VirtualAlloc`,
+//! `VirtualFree` and `VirtualProtect`.
+//!
+//! Windows distinguishes reserving address space (`MEM_RESERVE`) from committing memory to it
+//! (`MEM_COMMIT`), which matches how MMTk quarantines memory with [`mmap_noreserve`] and maps it
+//! later with [`dzmmap`]. Unlike `mmap`, most of those calls must not span multiple reservations, so
+//! the functions here apply them to each region returned by `VirtualQuery` separately.
+//!
+//! Huge pages and NUMA policies are not supported on Windows, and the options that enable them do
+//! not pass the validation.
+
+use super::{registry, MmapAnnotation, MmapProtection, MmapStrategy};
+use crate::util::Address;
+use std::io::{Error, ErrorKind, Result};
+use windows_sys::Win32::System::Memory::{
+    VirtualAlloc, VirtualFree, VirtualProtect, VirtualQuery, MEMORY_BASIC_INFORMATION, MEM_COMMIT,
+    MEM_DECOMMIT, MEM_FREE, MEM_RELEASE, MEM_RESERVE, PAGE_EXECUTE_READ, PAGE_EXECUTE_READWRITE,
+    PAGE_NOACCESS, PAGE_PROTECTION_FLAGS, PAGE_READONLY, PAGE_READWRITE,
+};
+
+impl MmapProtection {
+    /// Turn the protection enum into the native flags
+    pub fn into_native_flags(self) -> PAGE_PROTECTION_FLAGS {
+        match self {
+            Self::ReadWrite => PAGE_READWRITE,
+            Self::ReadWriteExec => PAGE_EXECUTE_READWRITE,
+            Self::ReadExec => PAGE_EXECUTE_READ,
+            Self::NoAccess => PAGE_NOACCESS,
+        }
+    }
+}
+
+/// A range of pages with the same state from `VirtualQuery`. It never spans multiple reservations.
+struct Region {
+    start: Address,
+    size: usize,
+    info: MEMORY_BASIC_INFORMATION,
+}
+
+/// Call `f` for each region that overlaps with the given memory, clipped to the given memory.
+fn for_each_region(
+    start: Address,
+    size: usize,
+    mut f: impl FnMut(Region) -> Result<()>,
+) -> Result<()> {
+    let end = start + size;
+    let mut cursor = start;
+    while cursor < end {
+        let info = query(cursor)?;
+        let region_end = Address::from_mut_ptr(info.BaseAddress) + info.RegionSize;
+        let next = if region_end < end { region_end } else { end };
+        f(Region {
+            start: cursor,
+            size: next - cursor,
+            info,
+        })?;
+        cursor = next;
+    }
+    Ok(())
+}
+
+fn query(addr: Address) -> Result<MEMORY_BASIC_INFORMATION> {
+    let mut info = unsafe { std::mem::zeroed::<MEMORY_BASIC_INFORMATION>() };
+    let len = std::mem::size_of::<MEMORY_BASIC_INFORMATION>();
+    if unsafe { VirtualQuery(addr.to_ptr(), &mut info, len) } == len {
+        Ok(info)
+    } else {
+        Err(Error::last_os_error())
+    }
+}
+
+/// Return an `AlreadyExists` error if any page in the given memory is reserved or committed, like
+/// `mmap` with `MAP_FIXED_NOREPLACE`.
+fn check_free(start: Address, size: usize) -> Result<()> {
+    for_each_region(start, size, |region| {
+        if region.info.State == MEM_FREE {
+            Ok(())
+        } else {
+            Err(Error::from(ErrorKind::AlreadyExists))
+        }
+    })
+}
+
+fn virtual_alloc(
+    start: Address,
+    size: usize,
+    allocation_type: u32,
+    prot: MmapProtection,
+) -> Result<()> {
+    let ptr = unsafe {
+        VirtualAlloc(
+            start.to_ptr(),
+            size,
+            allocation_type,
+            prot.into_native_flags(),
+        )
+    };
+    if ptr.is_null() {
+        Err(Error::last_os_error())
+    } else {
+        debug_assert_eq!(Address::from_mut_ptr(ptr), start);
+        Ok(())
+    }
+}
+
+/// Demand-zero mmap. See the documentation of the Unix version.
+///
+/// # Safety
+/// This function WILL overwrite existing memory mapping if there is any. So only use this function if you know
+/// the memory has been reserved by mmtk (e.g. after the use of mmap_noreserve()). Otherwise using this function
+/// may corrupt others' data.
+pub unsafe fn dzmmap(
+    start: Address,
+    size: usize,
+    strategy: MmapStrategy,
+    anno: &MmapAnnotation,
+) -> Result<()> {
+    for_each_region(start, size, |region| {
+        if region.info.State == MEM_FREE {
+            virtual_alloc(
+                region.start,
+                region.size,
+                MEM_RESERVE | MEM_COMMIT,
+                strategy.prot,
+            )
+        } else {
+            // Committing pages that are already committed does not zero them.
+            let committed = region.info.State == MEM_COMMIT;
+            virtual_alloc(region.start, region.size, MEM_COMMIT, strategy.prot)?;
+            if committed && !matches!(strategy.prot, MmapProtection::NoAccess) {
+                super::zero(region.start, region.size);
+            }
+            Ok(())
+        }
+    })?;
+    registry::record(
+        start,
+        size,
+        anno,
+        matches!(strategy.prot, MmapProtection::NoAccess),
+    );
+    Ok(())
+}
+
+/// Demand-zero mmap (no replace). See the documentation of the Unix version.
+pub fn dzmmap_noreplace(
+    start: Address,
+    size: usize,
+    strategy: MmapStrategy,
+    anno: &MmapAnnotation,
+) -> Result<()> {
+    check_free(start, size)?;
+    virtual_alloc(start, size, MEM_RESERVE | MEM_COMMIT, strategy.prot)?;
+    registry::record(
+        start,
+        size,
+        anno,
+        matches!(strategy.prot, MmapProtection::NoAccess),
+    );
+    Ok(())
+}
+
+/// Reserve the address range without committing memory to it. See the documentation of the Unix
+/// version.
+pub fn mmap_noreserve(
+    start: Address,
+    size: usize,
+    _strategy: MmapStrategy,
+    anno: &MmapAnnotation,
+) -> Result<()> {
+    check_free(start, size)?;
+    virtual_alloc(start, size, MEM_RESERVE, MmapProtection::NoAccess)?;
+    registry::record(start, size, anno, true);
+    Ok(())
+}
+
+/// Unmap the given memory (in page granularity). The pages are decommitted, and the reservations
+/// that are entirely in the given memory are released. A reservation that is only partly in the
+/// given memory stays reserved, so the address range cannot be mapped with [`dzmmap_noreplace`] or
+/// [`mmap_noreserve`] again until the rest of the reservation is unmapped.
+pub fn munmap(start: Address, size: usize) -> Result<()> {
+    let end = start + size;
+    let mut releasable = vec![];
+    for_each_region(start, size, |region| {
+        if region.info.State == MEM_FREE {
+            return Ok(());
+        }
+        if region.info.State == MEM_COMMIT
+            && unsafe { VirtualFree(region.start.to_mut_ptr(), region.size, MEM_DECOMMIT) } == 0
+        {
+            return Err(Error::last_os_error());
+        }
+        let base = Address::from_mut_ptr(region.info.AllocationBase);
+        if base >= start && !releasable.contains(&base) {
+            releasable.push(base);
+        }
+        Ok(())
+    })?;
+    for base in releasable {
+        // Only release the reservation if it does not extend beyond the given memory.
+        let mut reservation_end = base;
+        loop {
+            let info = query(reservation_end)?;
+            if info.State == MEM_FREE || Address::from_mut_ptr(info.AllocationBase) != base {
+                break;
+            }
+            reservation_end = Address::from_mut_ptr(info.BaseAddress) + info.RegionSize;
+        }
+        if reservation_end <= end && unsafe { VirtualFree(base.to_mut_ptr(), 0, MEM_RELEASE) } == 0
+        {
+            return Err(Error::last_os_error());
+        }
+    }
+    registry::forget(start, size);
+    Ok(())
+}
+
+fn virtual_protect(start: Address, size: usize, prot: PAGE_PROTECTION_FLAGS) -> Result<()> {
+    for_each_region(start, size, |region| {
+        let mut old = 0;
+        if unsafe { VirtualProtect(region.start.to_ptr(), region.size, prot, &mut old) } == 0 {
+            Err(Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    })
+}
+
+/// Unprotect the given memory (in page granularity) to allow access.
+pub fn munprotect(start: Address, size: usize, prot: MmapProtection) -> Result<()> {
+    virtual_protect(start, size, prot.into_native_flags())
+}
+
+/// Protect the given memory (in page granularity) to forbid any access.
+pub fn mprotect(start: Address, size: usize) -> Result<()> {
+    virtual_protect(start, size, PAGE_NOACCESS)
+}
+
+/// Protect the given memory (in page granularity) to forbid writing to it.
+pub fn mprotect_read_only(start: Address, size: usize) -> Result<()> {
+    virtual_protect(start, size, PAGE_READONLY)
+}
@@ -0,0 +1,97 @@
+//! Describe the GC metadata of an object for debugging. See
+//! [`crate::memory_manager::describe_object`].
+//!
+//! Local metadata is only meaningful (and, if it is on the side, only mapped) for the policies that
+//! use it, so each policy lists the metadata it uses in [`crate::policy::sft::SFT::describe_object_metadata`]
+//! with the helpers here.
+
+use super::MetadataSpec;
+use crate::mmtk::SFT_MAP;
+use crate::util::object_forwarding;
+use crate::util::ObjectReference;
+use crate::vm::{ObjectModel, VMBinding};
+use crate::MMTK;
+use std::sync::atomic::Ordering;
+
+/// Describe the value of a metadata spec of at most 8 bits for an object, e.g. `mark bit: 1 (side)`.
+pub(crate) fn describe_spec<VM: VMBinding>(
+    name: &str,
+    spec: &MetadataSpec,
+    object: ObjectReference,
+) -> String {
+    let value = spec.load_atomic::<VM, u8>(object, None, Ordering::SeqCst);
+    let location = if spec.is_on_side() { "side" } else { "header" };
+    format!("{name}: {value} ({location})")
+}
+
+/// Describe the mark bit of an object.
+pub(crate) fn mark_bit<VM: VMBinding>(object: ObjectReference) -> String {
+    describe_spec::<VM>("mark bit", &VM::VMObjectModel::LOCAL_MARK_BIT_SPEC, object)
+}
+
+/// Describe the forwarding bits of an object, and the forwarding pointer if it is forwarded.
+pub(crate) fn forwarding<VM: VMBinding>(object: ObjectReference) -> String {
+    let bits = describe_spec::<VM>(
+        "forwarding bits",
+        &VM::VMObjectModel::LOCAL_FORWARDING_BITS_SPEC,
+        object,
+    );
+    let status = object_forwarding::get_forwarding_status::<VM>(object);
+    if object_forwarding::is_forwarded::<VM>(object) {
+        format!(
+            "{bits}, forwarded to {}",
+            object_forwarding::read_forwarding_pointer::<VM>(object)
+        )
+    } else if object_forwarding::state_is_being_forwarded(status) {
+        format!("{bits}, being forwarded")
+    } else {
+        format!("{bits}, not forwarded")
+    }
+}
+
+/// Describe the pinning bit of an object.
+#[cfg(feature = "object_pinning")]
+pub(crate) fn pin_bit<VM: VMBinding>(object: ObjectReference) -> String {
+    describe_spec::<VM>(
+        "pin bit",
+        &VM::VMObjectModel::LOCAL_PINNING_BIT_SPEC,
+        object,
+    )
+}
+
+/// Describe the mark and nursery bits of an object in the large object space.
+pub(crate) fn los_mark_nursery_bits<VM: VMBinding>(object: ObjectReference) -> String {
+    describe_spec::<VM>(
+        "LOS mark and nursery bits",
+        &VM::VMObjectModel::LOCAL_LOS_MARK_NURSERY_SPEC,
+        object,
+    )
+}
+
+/// Describe the space of an object and all the GC metadata of the object, one item per line.
+pub(crate) fn describe_object<VM: VMBinding>(mmtk: &MMTK<VM>, object: ObjectReference) -> String {
+    let sft = SFT_MAP.get_checked(object.to_raw_address());
+    if !sft.is_in_space(object) {
+        return format!("{object}: not in any MMTk space\n");
+    }
+    let mut lines = vec![format!(
+        "{object}: in space {} ({:?})",
+        sft.name(),
+        sft.policy_kind()
+    )];
+    // The log bit is only mapped if the plan uses it.
+    if mmtk.get_plan().constraints().needs_log_bit {
+        lines.push(describe_spec::<VM>(
+            "log bit",
+            &VM::VMObjectModel::GLOBAL_LOG_BIT_SPEC,
+            object,
+        ));
+    }
+    #[cfg(feature = "vo_bit")]
+    lines.push(format!(
+        "VO bit: {}",
+        crate::util::metadata::vo_bit::is_vo_bit_set(object) as u8
+    ));
+    lines.extend(sft.describe_object_metadata(object));
+    lines.iter().map(|line| format!("{line}\n")).collect()
+}
@@ -0,0 +1,60 @@
+use crate::util::constants::BYTES_IN_WORD;
+use crate::util::ObjectReference;
+use crate::vm::VMBinding;
+use crate::vm::VMGlobalHashStateSpec;
+use std::sync::atomic::Ordering;
+
+/// The object has not been hashed.
+pub(crate) const UNHASHED: u8 = 0;
+/// The object has been hashed, and its hash code is its current address.
+pub(crate) const HASHED: u8 = 1;
+/// The object has been hashed and then moved. Its hash code is stored in the last word of the
+/// object.
+pub(crate) const HASHED_AND_MOVED: u8 = 2;
+
+impl VMGlobalHashStateSpec {
+    /// Load the hash state of an object.
+    pub(crate) fn load_hash_state<VM: VMBinding>(&self, object: ObjectReference) -> u8 {
+        self.load_atomic::<VM, u8>(object, None, Ordering::SeqCst)
+    }
+
+    /// Store the hash state of an object.
+    pub(crate) fn store_hash_state<VM: VMBinding>(&self, object: ObjectReference, state: u8) {
+        self.store_atomic::<VM, u8>(object, state, None, Ordering::SeqCst)
+    }
+
+    /// Change the hash state from unhashed to hashed. Return the hash state after this operation.
+    pub(crate) fn mark_as_hashed<VM: VMBinding>(&self, object: ObjectReference) -> u8 {
+        match self.compare_exchange_metadata::<VM, u8>(
+            object,
+            UNHASHED,
+            HASHED,
+            None,
+            Ordering::SeqCst,
+            Ordering::SeqCst,
+        ) {
+            Ok(_) => HASHED,
+            Err(state) => state,
+        }
+    }
+
+    /// The number of bytes that the hash code takes in the object. The binding needs to add this
+    /// to the size returned by [`crate::vm::ObjectModel::get_current_size`].
+    pub fn hash_bytes_in_object<VM: VMBinding>(&self, object: ObjectReference) -> usize {
+        if self.load_hash_state::<VM>(object) == HASHED_AND_MOVED {
+            BYTES_IN_WORD
+        } else {
+            0
+        }
+    }
+
+    /// The number of bytes that the hash code takes in the copy of the object. The binding needs
+    /// to add this to the size returned by [`crate::vm::ObjectModel::get_size_when_copied`].
+    pub fn hash_bytes_when_copied<VM: VMBinding>(&self, object: ObjectReference) -> usize {
+        if self.load_hash_state::<VM>(object) == UNHASHED {
+            0
+        } else {
+            BYTES_IN_WORD
+        }
+    }
+}
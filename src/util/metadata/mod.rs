@@ -225,6 +225,9 @@ pub mod side_metadata;
 pub mod vo_bit;
 pub use metadata_val_traits::*;
 
+pub(crate) mod describe;
+#[cfg(feature = "address_based_hashing")]
+pub(crate) mod hash_state;
 pub(crate) mod log_bit;
 pub(crate) mod mark_bit;
 pub(crate) mod pin_bit;
@@ -1287,7 +1287,7 @@ impl SideMetadataOffset {
     /// Get an offset after a spec. This is used to layout another spec immediately after this one.
     pub const fn layout_after(spec: &SideMetadataSpec) -> SideMetadataOffset {
         // Some metadata may be so small that its size is not a multiple of byte size.  One example
-        // is `CHUNK_MARK`.  It is one byte per chunk.  However, on 32-bit architectures, we
+        // is `CHUNK_MAP`.  It is one byte per chunk.  However, on 32-bit architectures, we
         // allocate side metadata per chunk.  In that case, it will only occupy one byte.  If we
         // do not align the upper bound offset up, subsequent local metadata that need to be
         // accessed at, for example, word granularity will be misaligned.
@@ -1329,16 +1329,27 @@ pub(crate) struct SideMetadataContext {
     pub global: Vec<SideMetadataSpec>,
     // For policies
     pub local: Vec<SideMetadataSpec>,
+    // How the metadata memory is mapped
+    pub mmap_strategy: memory::MmapStrategy,
 }
 
 impl SideMetadataContext {
     #[allow(clippy::vec_init_then_push)] // allow this, as we conditionally push based on features.
     pub fn new_global_specs(specs: &[SideMetadataSpec]) -> Vec<SideMetadataSpec> {
-        let mut ret = vec![];
+        let mut ret = vec![
+            crate::util::heap::chunk_map::ChunkMap::ALLOC_TABLE,
+            crate::util::heap::space_index::SPACE_INDEX,
+        ];
 
         #[cfg(feature = "vo_bit")]
         ret.push(VO_BIT_SIDE_METADATA_SPEC);
 
+        #[cfg(feature = "analysis")]
+        ret.push(crate::util::analysis::lifetime::OBJ_BIRTH_EPOCH_SPEC);
+
+        #[cfg(feature = "alloc_site")]
+        ret.push(crate::util::alloc_site::ALLOC_SITE_SPEC);
+
         if let Some(spec) = crate::mmtk::SFT_MAP.get_side_metadata() {
             if spec.is_global {
                 ret.push(*spec);
@@ -1438,7 +1449,14 @@ impl SideMetadataContext {
                 space: space_name,
                 meta: spec.name,
             };
-            match try_mmap_contiguous_metadata_space(start, size, spec, no_reserve, &anno) {
+            match try_mmap_contiguous_metadata_space(
+                start,
+                size,
+                spec,
+                no_reserve,
+                self.mmap_strategy,
+                &anno,
+            ) {
                 Ok(_) => {}
                 Err(e) => return Result::Err(e),
             }
@@ -1465,7 +1483,14 @@ impl SideMetadataContext {
                     space: space_name,
                     meta: spec.name,
                 };
-                match try_mmap_contiguous_metadata_space(start, size, spec, no_reserve, &anno) {
+                match try_mmap_contiguous_metadata_space(
+                    start,
+                    size,
+                    spec,
+                    no_reserve,
+                    self.mmap_strategy,
+                    &anno,
+                ) {
                     Ok(_) => {}
                     Err(e) => return Result::Err(e),
                 }
@@ -1491,7 +1516,14 @@ impl SideMetadataContext {
                 space: space_name,
                 meta: "all",
             };
-            match try_map_per_chunk_metadata_space(start, size, lsize, no_reserve, &anno) {
+            match try_map_per_chunk_metadata_space(
+                start,
+                size,
+                lsize,
+                no_reserve,
+                self.mmap_strategy,
+                &anno,
+            ) {
                 Ok(_) => {}
                 Err(e) => return Result::Err(e),
             }
@@ -1612,6 +1644,7 @@ mod tests {
         let side_metadata = SideMetadataContext {
             global: vec![spec],
             local: vec![],
+            mmap_strategy: MmapStrategy::TEST,
         };
         assert_eq!(side_metadata.calculate_reserved_pages(0), 0);
         assert_eq!(side_metadata.calculate_reserved_pages(63), 1);
@@ -1641,6 +1674,7 @@ mod tests {
         let side_metadata = SideMetadataContext {
             global: vec![gspec],
             local: vec![lspec],
+            mmap_strategy: MmapStrategy::TEST,
         };
         assert_eq!(side_metadata.calculate_reserved_pages(1024), 16 + 1);
     }
@@ -1667,6 +1701,7 @@ mod tests {
             let context = SideMetadataContext {
                 global: vec![spec],
                 local: vec![],
+                mmap_strategy: MmapStrategy::TEST,
             };
             let mut sanity = SideMetadataSanity::new();
             sanity.verify_metadata_context("TestPolicy", &context);
@@ -126,6 +126,7 @@ pub(super) fn try_mmap_contiguous_metadata_space(
     size: usize,
     spec: &SideMetadataSpec,
     no_reserve: bool,
+    strategy: MmapStrategy,
     anno: &MmapAnnotation,
 ) -> Result<usize> {
     debug_assert!(start.is_aligned_to(BYTES_IN_PAGE));
@@ -139,17 +140,14 @@ pub(super) fn try_mmap_contiguous_metadata_space(
     let mmap_size = (metadata_start + metadata_size).align_up(BYTES_IN_PAGE) - mmap_start;
     if mmap_size > 0 {
         if !no_reserve {
-            MMAPPER.ensure_mapped(
-                mmap_start,
-                mmap_size >> LOG_BYTES_IN_PAGE,
-                MmapStrategy::SIDE_METADATA,
-                anno,
-            )
+            MMAPPER
+                .ensure_mapped(mmap_start, mmap_size >> LOG_BYTES_IN_PAGE, strategy, anno)
+                .map(|_| crate::util::memory::mark_defined(mmap_start, mmap_size))
         } else {
             MMAPPER.quarantine_address_range(
                 mmap_start,
                 mmap_size >> LOG_BYTES_IN_PAGE,
-                MmapStrategy::SIDE_METADATA,
+                strategy,
                 anno,
             )
         }
@@ -112,6 +112,7 @@ pub(super) fn try_map_per_chunk_metadata_space(
     size: usize,
     local_per_chunk: usize,
     no_reserve: bool,
+    strategy: memory::MmapStrategy,
     anno: &MmapAnnotation,
 ) -> Result<usize> {
     let mut aligned_start = start.align_down(BYTES_IN_CHUNK);
@@ -123,7 +124,8 @@ pub(super) fn try_map_per_chunk_metadata_space(
     let mut total_mapped = 0;
 
     while aligned_start < aligned_end {
-        let res = try_mmap_metadata_chunk(aligned_start, local_per_chunk, no_reserve, anno);
+        let res =
+            try_mmap_metadata_chunk(aligned_start, local_per_chunk, no_reserve, strategy, anno);
         if res.is_err() {
             if munmap_first_chunk.is_some() {
                 let mut munmap_start = if munmap_first_chunk.unwrap() {
@@ -176,6 +178,7 @@ pub(super) fn try_mmap_metadata_chunk(
     start: Address,
     local_per_chunk: usize,
     no_reserve: bool,
+    strategy: memory::MmapStrategy,
     anno: &MmapAnnotation,
 ) -> Result<()> {
     debug_assert!(start.is_aligned_to(BYTES_IN_CHUNK));
@@ -184,18 +187,8 @@ pub(super) fn try_mmap_metadata_chunk(
     let pages = crate::util::conversions::bytes_to_pages_up(local_per_chunk);
     if !no_reserve {
         // We have reserved the memory
-        MMAPPER.ensure_mapped(
-            policy_meta_start,
-            pages,
-            memory::MmapStrategy::SIDE_METADATA,
-            anno,
-        )
+        MMAPPER.ensure_mapped(policy_meta_start, pages, strategy, anno)
     } else {
-        MMAPPER.quarantine_address_range(
-            policy_meta_start,
-            pages,
-            memory::MmapStrategy::SIDE_METADATA,
-            anno,
-        )
+        MMAPPER.quarantine_address_range(policy_meta_start, pages, strategy, anno)
     }
 }
@@ -5,6 +5,7 @@ mod tests {
     use crate::util::constants;
     use crate::util::heap::layout::vm_layout;
     use crate::util::heap::layout::vm_layout::vm_layout;
+    use crate::util::memory::MmapStrategy;
     use crate::util::metadata::side_metadata::SideMetadataContext;
     use crate::util::metadata::side_metadata::SideMetadataSpec;
     use crate::util::metadata::side_metadata::*;
@@ -224,6 +225,7 @@ mod tests {
                     let metadata = SideMetadataContext {
                         global: vec![gspec],
                         local: vec![lspec],
+                        mmap_strategy: MmapStrategy::TEST,
                     };
 
                     let mut metadata_sanity = SideMetadataSanity::new();
@@ -250,6 +252,7 @@ mod tests {
                     let metadata = SideMetadataContext {
                         global: vec![gspec],
                         local: vec![lspec],
+                        mmap_strategy: MmapStrategy::TEST,
                     };
 
                     metadata_sanity.verify_metadata_context("NoPolicy", &metadata);
@@ -308,6 +311,7 @@ mod tests {
                     let metadata = SideMetadataContext {
                         global: vec![metadata_1_spec, metadata_2_spec],
                         local: vec![],
+                        mmap_strategy: MmapStrategy::TEST,
                     };
 
                     let mut metadata_sanity = SideMetadataSanity::new();
@@ -375,6 +379,7 @@ mod tests {
                     let metadata = SideMetadataContext {
                         global: vec![metadata_1_spec],
                         local: vec![],
+                        mmap_strategy: MmapStrategy::TEST,
                     };
 
                     let mut metadata_sanity = SideMetadataSanity::new();
@@ -432,6 +437,7 @@ mod tests {
                     let metadata = SideMetadataContext {
                         global: vec![metadata_1_spec],
                         local: vec![],
+                        mmap_strategy: MmapStrategy::TEST,
                     };
 
                     let mut metadata_sanity = SideMetadataSanity::new();
@@ -514,6 +520,7 @@ mod tests {
                     let metadata = SideMetadataContext {
                         global: vec![],
                         local: vec![metadata_1_spec, metadata_2_spec],
+                        mmap_strategy: MmapStrategy::TEST,
                     };
 
                     let mut metadata_sanity = SideMetadataSanity::new();
@@ -582,6 +589,7 @@ mod tests {
                     let metadata = SideMetadataContext {
                         global: vec![spec],
                         local: vec![],
+                        mmap_strategy: MmapStrategy::TEST,
                     };
 
                     let mut metadata_sanity = SideMetadataSanity::new();
@@ -639,6 +647,7 @@ mod tests {
                     let metadata = SideMetadataContext {
                         global: vec![spec],
                         local: vec![],
+                        mmap_strategy: MmapStrategy::TEST,
                     };
 
                     let mut metadata_sanity = SideMetadataSanity::new();
@@ -748,6 +757,7 @@ mod tests {
                     let metadata = SideMetadataContext {
                         global: vec![metadata_1_spec, metadata_2_spec],
                         local: vec![],
+                        mmap_strategy: MmapStrategy::TEST,
                     };
 
                     let mut metadata_sanity = SideMetadataSanity::new();
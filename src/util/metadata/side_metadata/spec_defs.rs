@@ -53,15 +53,64 @@ macro_rules! define_side_metadata_specs {
 
 // This defines all GLOBAL side metadata used by mmtk-core.
 define_side_metadata_specs!(
-    last_spec_as LAST_GLOBAL_SIDE_METADATA_SPEC,
+    last_spec_as LAST_COMMON_GLOBAL_SIDE_METADATA_SPEC,
     // Mark the start of an object
     VO_BIT       = (global: true, log_num_of_bits: 0, log_bytes_in_region: LOG_MIN_OBJECT_SIZE as usize),
     // Track chunks used by (malloc) marksweep
     MS_ACTIVE_CHUNK = (global: true, log_num_of_bits: 3, log_bytes_in_region: LOG_BYTES_IN_CHUNK),
+    // Record the chunks allocated by each space that uses a chunk map
+    CHUNK_MAP    = (global: true, log_num_of_bits: 3, log_bytes_in_region: crate::util::heap::chunk_map::Chunk::LOG_BYTES),
+    // Record the index of the space that owns each chunk in the heap range
+    SPACE_INDEX  = (global: true, log_num_of_bits: 3, log_bytes_in_region: LOG_BYTES_IN_CHUNK),
     // Track the index in SFT map for a chunk (only used for SFT sparse chunk map)
     SFT_DENSE_CHUNK_MAP_INDEX   = (global: true, log_num_of_bits: 3, log_bytes_in_region: LOG_BYTES_IN_CHUNK),
 );
 
+// The following GLOBAL side metadata is only defined if the features that use it are enabled.  It
+// takes a large part of the global side metadata address range, so it is laid out after the specs
+// above, and does not move the global side metadata of the VM if the features are disabled.
+
+// Record the GC epoch when an object is allocated (only used by the object lifetime analysis).
+// On 32 bits, we use a coarser granularity so the spec fits in the global side metadata address range.
+#[cfg(feature = "analysis")]
+pub const OBJ_BIRTH_EPOCH: SideMetadataSpec = SideMetadataSpec {
+    name: "OBJ_BIRTH_EPOCH",
+    is_global: true,
+    offset: SideMetadataOffset::layout_after(&LAST_COMMON_GLOBAL_SIDE_METADATA_SPEC),
+    log_num_of_bits: 3,
+    log_bytes_in_region: if cfg!(target_pointer_width = "64") {
+        LOG_MIN_OBJECT_SIZE as usize
+    } else {
+        5
+    },
+};
+
+#[cfg(feature = "analysis")]
+const LAST_ANALYSIS_GLOBAL_SIDE_METADATA_SPEC: SideMetadataSpec = OBJ_BIRTH_EPOCH;
+#[cfg(not(feature = "analysis"))]
+const LAST_ANALYSIS_GLOBAL_SIDE_METADATA_SPEC: SideMetadataSpec =
+    LAST_COMMON_GLOBAL_SIDE_METADATA_SPEC;
+
+// Record the allocation site tag of an object (only used with the feature "alloc_site").
+#[cfg(feature = "alloc_site")]
+pub const ALLOC_SITE: SideMetadataSpec = SideMetadataSpec {
+    name: "ALLOC_SITE",
+    is_global: true,
+    offset: SideMetadataOffset::layout_after(&LAST_ANALYSIS_GLOBAL_SIDE_METADATA_SPEC),
+    log_num_of_bits: 4,
+    log_bytes_in_region: if cfg!(target_pointer_width = "64") {
+        LOG_MIN_OBJECT_SIZE as usize
+    } else {
+        5
+    },
+};
+
+#[cfg(feature = "alloc_site")]
+pub const LAST_GLOBAL_SIDE_METADATA_SPEC: SideMetadataSpec = ALLOC_SITE;
+#[cfg(not(feature = "alloc_site"))]
+pub const LAST_GLOBAL_SIDE_METADATA_SPEC: SideMetadataSpec =
+    LAST_ANALYSIS_GLOBAL_SIDE_METADATA_SPEC;
+
 // This defines all LOCAL side metadata used by mmtk-core.
 define_side_metadata_specs!(
     last_spec_as LAST_LOCAL_SIDE_METADATA_SPEC,
@@ -75,8 +124,8 @@ define_side_metadata_specs!(
     IX_BLOCK_DEFRAG = (global: false, log_num_of_bits: 3, log_bytes_in_region: crate::policy::immix::block::Block::LOG_BYTES),
     // Mark blocks by immix
     IX_BLOCK_MARK   = (global: false, log_num_of_bits: 3, log_bytes_in_region: crate::policy::immix::block::Block::LOG_BYTES),
-    // Mark chunks (any plan that uses the chunk map should include this spec in their local sidemetadata specs)
-    CHUNK_MARK   = (global: false, log_num_of_bits: 3, log_bytes_in_region: crate::util::heap::chunk_map::Chunk::LOG_BYTES),
+    // Approximate live bytes of immix blocks in the last GC, used for selecting defrag sources
+    IX_BLOCK_LIVE_BYTES = (global: false, log_num_of_bits: 5, log_bytes_in_region: crate::policy::immix::block::Block::LOG_BYTES),
     // Mark blocks by (native mimalloc) marksweep
     MS_BLOCK_MARK   = (global: false, log_num_of_bits: 3, log_bytes_in_region: crate::policy::marksweepspace::native_ms::Block::LOG_BYTES),
     // Next block in list for native mimalloc
@@ -91,6 +140,10 @@ define_side_metadata_specs!(
     MS_BLOCK_TLS    = (global: false, log_num_of_bits: LOG_BITS_IN_ADDRESS, log_bytes_in_region: crate::policy::marksweepspace::native_ms::Block::LOG_BYTES),
     // First cell of free list in block for native mimalloc
     MS_FREE         = (global: false, log_num_of_bits: LOG_BITS_IN_ADDRESS, log_bytes_in_region: crate::policy::marksweepspace::native_ms::Block::LOG_BYTES),
+    // Number of cells marked in the last GC in block for native mimalloc, used for repacking
+    MS_BLOCK_LIVE   = (global: false, log_num_of_bits: 4, log_bytes_in_region: crate::policy::marksweepspace::native_ms::Block::LOG_BYTES),
+    // Mark blocks whose objects are moved out in a repacking GC by native mimalloc
+    MS_BLOCK_REPACK = (global: false, log_num_of_bits: 3, log_bytes_in_region: crate::policy::marksweepspace::native_ms::Block::LOG_BYTES),
     // The following specs are only used for manual malloc/free
     // First cell of local free list in block for native mimalloc
     MS_LOCAL_FREE   = (global: false, log_num_of_bits: LOG_BITS_IN_ADDRESS, log_bytes_in_region: crate::policy::marksweepspace::native_ms::Block::LOG_BYTES),
@@ -137,6 +137,15 @@ pub(crate) fn need_to_clear_vo_bits_before_tracing<VM: VMBinding>() -> bool {
     }
 }
 
+/// Return `true` if `on_region_swept` overwrites the VO bits of a region.  If so, a region must
+/// not be swept while mutators allocate objects in it.
+pub(crate) fn vo_bits_updated_when_sweeping<VM: VMBinding>() -> bool {
+    match strategy::<VM>() {
+        VOBitUpdateStrategy::ClearAndReconstruct => false,
+        VOBitUpdateStrategy::CopyFromMarkBits => true,
+    }
+}
+
 pub(crate) fn on_trace_object<VM: VMBinding>(object: ObjectReference) {
     if strategy::<VM>().vo_bit_available_during_tracing() {
         // If the VO bits are available during tracing,
@@ -19,8 +19,21 @@ pub mod constants;
 pub mod conversions;
 /// The copy allocators for a GC worker.
 pub mod copy;
+/// Deduplicating the values of objects in GC.
+pub mod deduplication;
+/// Simulating allocation failures for testing.
+#[cfg(feature = "fault_injection")]
+pub mod fault_injection;
+/// Global handles to objects, kept consistent across GCs.
+pub mod handle_table;
+/// Measurement windows for benchmark harnesses.
+pub mod harness;
 /// Heap implementation, including page resource, mmapper, etc.
 pub mod heap;
+/// Backing the heap with files for post-mortem inspection.
+pub mod heap_file;
+/// Describing and validating the heap layout.
+pub mod heap_layout;
 /// Checking if an address is an valid MMTk object.
 #[cfg(feature = "is_mmtk_object")]
 pub mod is_mmtk_object;
@@ -32,17 +45,37 @@ pub mod malloc;
 pub mod memory;
 /// Metadata (OnSide or InHeader) implementation.
 pub mod metadata;
+/// Address-based object hashing that is preserved when objects move.
+#[cfg(feature = "address_based_hashing")]
+pub mod object_hash;
+/// Stable object IDs that survive object movement.
+#[cfg(feature = "object_id")]
+pub mod object_id;
+/// A word of user data attached to objects.
+#[cfg(feature = "object_user_data")]
+pub mod object_user_data;
 /// Opaque pointers used in MMTk, e.g. VMThread.
 pub mod opaque_pointer;
 /// MMTk command line options.
 pub mod options;
+/// Pacing concurrent collection against allocation.
+pub mod pacer;
+/// Batches of pinning roots kept for a number of GCs.
+pub mod pinned_root_batches;
+/// Remembered sets for plans that collect a subset of the heap.
+pub mod remset;
 #[cfg(feature = "test_private")]
 pub mod test_private;
 /// Test utilities. We need this module for `MockVM` in criterion benches, which does not include code with `cfg(test)`.
 #[cfg(any(test, feature = "mock_test"))]
 pub mod test_util;
+/// Statistics of weak reference processing and finalization.
+pub mod weak_processing_stats;
 
 // The following modules are only public in the mmtk crate. They should only be used in MMTk core.
+/// Allocation site tracking for debugging.
+#[cfg(feature = "alloc_site")]
+pub(crate) mod alloc_site;
 /// An analysis framework for collecting data and profiling in GC.
 #[cfg(feature = "analysis")]
 pub(crate) mod analysis;
@@ -51,25 +84,42 @@ pub(crate) mod epilogue;
 pub(crate) mod erase_vm;
 /// Finalization implementation.
 pub(crate) mod finalizable_processor;
+/// A heap verifier that checks every reference field in the heap.
+#[cfg(feature = "heap_verifier")]
+pub(crate) mod heap_verifier;
 /// Logger initialization
 pub(crate) mod logger;
 pub(crate) mod object_enum;
 /// Forwarding word in object copying.
 pub(crate) mod object_forwarding;
+/// The registry of off-heap objects that participate in tracing.
+pub(crate) mod off_heap_objects;
 /// Reference processing implementation.
 pub(crate) mod reference_processor;
+/// Detecting references to objects that a GC has decided to be dead.
+pub(crate) mod resurrection;
 /// Utilities funcitons for Rust
 pub(crate) mod rust_util;
+/// Annotations for memory checkers such as AddressSanitizer and Valgrind.
+#[cfg(feature = "sanitizer")]
+pub(crate) mod sanitizer;
 /// Sanity checker for GC.
 #[cfg(feature = "sanity")]
 pub(crate) mod sanity;
+/// Shadow-heap verification for copying collectors.
+#[cfg(feature = "shadow_heap")]
+pub(crate) mod shadow_heap;
 /// Logging slots to check duplicated edges in GC.
 #[cfg(feature = "extreme_assertions")]
 pub(crate) mod slot_logger;
+/// The cache of stable root slots.
+pub(crate) mod stable_roots;
 /// Utils for collecting statistics.
 pub(crate) mod statistics;
 /// A treadmill implementation.
 pub(crate) mod treadmill;
+/// Processing weak slots reported by object scanning.
+pub(crate) mod weak_slot_processor;
 
 // These modules are private. They are only used by other util modules.
 
@@ -83,4 +133,6 @@ mod raw_memory_freelist;
 
 pub use self::address::Address;
 pub use self::address::ObjectReference;
+#[cfg(feature = "vo_bit")]
+pub use self::object_enum::EnumerationScope;
 pub use self::opaque_pointer::*;
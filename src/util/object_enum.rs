@@ -1,15 +1,23 @@
 //! Helper types for object enumeration
 
 use std::marker::PhantomData;
+#[cfg(feature = "vo_bit")]
+use std::ops::Range;
+#[cfg(feature = "vo_bit")]
+use std::sync::Arc;
 
+#[cfg(feature = "vo_bit")]
+use crate::scheduler::{GCWork, GCWorker};
 use crate::vm::VMBinding;
+#[cfg(feature = "vo_bit")]
+use crate::MMTK;
 
 use super::{
     heap::{
-        chunk_map::{ChunkMap, ChunkState},
+        chunk_map::{Chunk, ChunkMap, ChunkState},
         MonotonePageResource,
     },
-    linear_scan::Region,
+    linear_scan::{Region, SubRegion},
     metadata::{side_metadata::spec_defs::VO_BIT, vo_bit},
     Address, ObjectReference,
 };
@@ -64,6 +72,138 @@ where
     }
 }
 
+/// The objects to enumerate with [`crate::MMTK::enumerate_objects_in`].
+#[cfg(feature = "vo_bit")]
+#[derive(Clone, Debug)]
+pub enum EnumerationScope<'a> {
+    /// The objects in the space of the given name, such as `"nursery"`.
+    Space(&'a str),
+    /// The objects whose addresses (the raw addresses of the object references) are in the given
+    /// address range, such as an Immix block.  The range may span multiple spaces.
+    Region(Range<Address>),
+}
+
+/// An `ObjectEnumerator` that only forwards the objects in an address range to another enumerator.
+#[cfg(feature = "vo_bit")]
+pub(crate) struct RegionObjectEnumerator<'a> {
+    inner: &'a mut dyn ObjectEnumerator,
+    region: Range<Address>,
+}
+
+#[cfg(feature = "vo_bit")]
+impl<'a> RegionObjectEnumerator<'a> {
+    pub fn new(inner: &'a mut dyn ObjectEnumerator, region: Range<Address>) -> Self {
+        Self { inner, region }
+    }
+}
+
+#[cfg(feature = "vo_bit")]
+impl ObjectEnumerator for RegionObjectEnumerator<'_> {
+    fn visit_object(&mut self, object: ObjectReference) {
+        if self.region.contains(&object.to_raw_address()) {
+            self.inner.visit_object(object);
+        }
+    }
+
+    fn visit_address_range(&mut self, start: Address, end: Address) {
+        // Objects are aligned to the regions of VO bits.  Align the range so that the VO bit
+        // scanning does not find objects before the region.
+        let vo_region = 1 << VO_BIT.log_bytes_in_region;
+        let start = start.max(self.region.start).align_up(vo_region);
+        let end = end.min(self.region.end).align_up(vo_region);
+        if start < end {
+            self.inner.visit_address_range(start, end);
+        }
+    }
+}
+
+/// An `ObjectEnumerator` that splits the enumeration into work packets.  Each address range is
+/// scanned in its own packet, and individual objects are visited in batches.
+#[cfg(feature = "vo_bit")]
+pub(crate) struct WorkPacketObjectEnumerator<VM: VMBinding> {
+    callback: Arc<dyn Fn(ObjectReference) + Send + Sync>,
+    objects: Vec<ObjectReference>,
+    packets: Vec<Box<dyn GCWork<VM>>>,
+}
+
+#[cfg(feature = "vo_bit")]
+impl<VM: VMBinding> WorkPacketObjectEnumerator<VM> {
+    /// The number of individual objects visited in one packet.
+    const OBJECTS_PER_PACKET: usize = 4096;
+
+    pub fn new(callback: Arc<dyn Fn(ObjectReference) + Send + Sync>) -> Self {
+        Self {
+            callback,
+            objects: vec![],
+            packets: vec![],
+        }
+    }
+
+    fn flush_objects(&mut self) {
+        if !self.objects.is_empty() {
+            let work = EnumerateObjects::<VM> {
+                callback: self.callback.clone(),
+                work: EnumerateObjectsWork::Objects(std::mem::take(&mut self.objects)),
+                phantom_data: PhantomData,
+            };
+            self.packets.push(Box::new(work));
+        }
+    }
+
+    /// Get the work packets for all the objects and address ranges visited so far.
+    pub fn into_packets(mut self) -> Vec<Box<dyn GCWork<VM>>> {
+        self.flush_objects();
+        self.packets
+    }
+}
+
+#[cfg(feature = "vo_bit")]
+impl<VM: VMBinding> ObjectEnumerator for WorkPacketObjectEnumerator<VM> {
+    fn visit_object(&mut self, object: ObjectReference) {
+        self.objects.push(object);
+        if self.objects.len() >= Self::OBJECTS_PER_PACKET {
+            self.flush_objects();
+        }
+    }
+
+    fn visit_address_range(&mut self, start: Address, end: Address) {
+        let work = EnumerateObjects::<VM> {
+            callback: self.callback.clone(),
+            work: EnumerateObjectsWork::AddressRange(start..end),
+            phantom_data: PhantomData,
+        };
+        self.packets.push(Box::new(work));
+    }
+}
+
+#[cfg(feature = "vo_bit")]
+enum EnumerateObjectsWork {
+    Objects(Vec<ObjectReference>),
+    AddressRange(Range<Address>),
+}
+
+/// A work packet that calls the callback for some objects, or the objects in an address range.
+#[cfg(feature = "vo_bit")]
+struct EnumerateObjects<VM: VMBinding> {
+    callback: Arc<dyn Fn(ObjectReference) + Send + Sync>,
+    work: EnumerateObjectsWork,
+    phantom_data: PhantomData<VM>,
+}
+
+#[cfg(feature = "vo_bit")]
+impl<VM: VMBinding> GCWork<VM> for EnumerateObjects<VM> {
+    fn do_work(&mut self, _worker: &mut GCWorker<VM>, _mmtk: &'static MMTK<VM>) {
+        let callback = &*self.callback;
+        match &self.work {
+            EnumerateObjectsWork::Objects(objects) => objects.iter().for_each(|o| callback(*o)),
+            EnumerateObjectsWork::AddressRange(range) => {
+                ClosureObjectEnumerator::<_, VM>::new(callback)
+                    .visit_address_range(range.start, range.end)
+            }
+        }
+    }
+}
+
 /// Allow querying if a block may have objects. `MarkSweepSpace` and `ImmixSpace` use different
 /// `Block` types, and they have different block states. This trait lets both `Block` types provide
 /// the same `may_have_objects` method.
@@ -82,7 +222,7 @@ pub(crate) fn enumerate_blocks_from_chunk_map<B>(
     enumerator: &mut dyn ObjectEnumerator,
     chunk_map: &ChunkMap,
 ) where
-    B: BlockMayHaveObjects,
+    B: BlockMayHaveObjects + SubRegion<Chunk>,
 {
     for chunk in chunk_map.all_chunks() {
         if chunk_map.get(chunk) == ChunkState::Allocated {
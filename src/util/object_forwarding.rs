@@ -18,25 +18,46 @@ const FORWARDING_POINTER_MASK: usize = 0x00ff_ffff_ffff_fff8;
 #[cfg(target_pointer_width = "32")]
 const FORWARDING_POINTER_MASK: usize = 0xffff_fffc;
 
+// The forwarding state only goes from FORWARDING_NOT_TRIGGERED_YET to BEING_FORWARDED, and then to
+// FORWARDED (or back to FORWARDING_NOT_TRIGGERED_YET when it is cleared).  Each of the transitions
+// only sets or only clears bits, so we do them with a single `fetch_or` or `fetch_and`.  Unlike a
+// compare-and-swap loop, those never fail and retry when other workers change the forwarding bits
+// of neighbouring objects in the same byte of side metadata, which is common when many small
+// objects are copied in parallel.
+const_assert_eq!(BEING_FORWARDED | FORWARDED, FORWARDED);
+
+// With the feature "vm_forwarding", the binding keeps the forwarding state in its own header
+// layout, and we convert it to the forwarding bits used by the rest of this module.
+#[cfg(feature = "vm_forwarding")]
+fn forwarding_state_to_bits(state: crate::vm::ForwardingState) -> u8 {
+    use crate::vm::ForwardingState;
+    match state {
+        ForwardingState::NotForwarded => FORWARDING_NOT_TRIGGERED_YET,
+        ForwardingState::BeingForwarded => BEING_FORWARDED,
+        ForwardingState::Forwarded => FORWARDED,
+    }
+}
+
 /// Attempt to become the worker thread who will forward the object.
 /// The successful worker will set the object forwarding bits to BEING_FORWARDED, preventing other workers from forwarding the same object.
 pub fn attempt_to_forward<VM: VMBinding>(object: ObjectReference) -> u8 {
-    loop {
+    #[cfg(feature = "vm_forwarding")]
+    return forwarding_state_to_bits(VM::VMObjectModel::attempt_to_forward(object));
+
+    #[cfg(not(feature = "vm_forwarding"))]
+    {
+        // Avoid the atomic read-modify-write if the object has been claimed.
         let old_value = get_forwarding_status::<VM>(object);
-        if old_value != FORWARDING_NOT_TRIGGERED_YET
-            || VM::VMObjectModel::LOCAL_FORWARDING_BITS_SPEC
-                .compare_exchange_metadata::<VM, u8>(
-                    object,
-                    old_value,
-                    BEING_FORWARDED,
-                    None,
-                    Ordering::SeqCst,
-                    Ordering::Relaxed,
-                )
-                .is_ok()
-        {
+        if old_value != FORWARDING_NOT_TRIGGERED_YET {
             return old_value;
         }
+        // Setting the bit never changes the state of an object that another worker has claimed,
+        // so the old value tells us whether we won the race.
+        VM::VMObjectModel::LOCAL_FORWARDING_BITS_SPEC.fetch_or_metadata::<VM, u8>(
+            object,
+            BEING_FORWARDED,
+            Ordering::SeqCst,
+        )
     }
 }
 
@@ -96,8 +117,20 @@ pub fn forward_object<VM: VMBinding>(
     copy_context: &mut GCWorkerCopyContext<VM>,
     on_after_forwarding: impl FnOnce(ObjectReference),
 ) -> ObjectReference {
+    #[cfg(feature = "address_based_hashing")]
+    let hash_state = crate::util::object_hash::get_hash_state::<VM>(object);
     let new_object = VM::VMObjectModel::copy(object, semantics, copy_context);
+    copy_context.record_relocation(object, new_object);
+    #[cfg(feature = "address_based_hashing")]
+    crate::util::object_hash::on_object_moved::<VM>(object, hash_state, new_object);
+    #[cfg(feature = "alloc_site")]
+    crate::util::alloc_site::on_object_forwarded(object, new_object);
+    #[cfg(feature = "analysis")]
+    crate::util::analysis::lifetime::on_object_forwarded(object, new_object);
     on_after_forwarding(new_object);
+    #[cfg(feature = "vm_forwarding")]
+    VM::VMObjectModel::set_forwarded(object, new_object);
+    #[cfg(not(feature = "vm_forwarding"))]
     if let Some(shift) = forwarding_bits_offset_in_forwarding_pointer::<VM>() {
         VM::VMObjectModel::LOCAL_FORWARDING_POINTER_SPEC.store_atomic::<VM, usize>(
             object,
@@ -107,10 +140,9 @@ pub fn forward_object<VM: VMBinding>(
         )
     } else {
         write_forwarding_pointer::<VM>(object, new_object);
-        VM::VMObjectModel::LOCAL_FORWARDING_BITS_SPEC.store_atomic::<VM, u8>(
+        VM::VMObjectModel::LOCAL_FORWARDING_BITS_SPEC.fetch_or_metadata::<VM, u8>(
             object,
             FORWARDED,
-            None,
             Ordering::SeqCst,
         );
     }
@@ -119,6 +151,10 @@ pub fn forward_object<VM: VMBinding>(
 
 /// Return the forwarding bits for a given `ObjectReference`.
 pub fn get_forwarding_status<VM: VMBinding>(object: ObjectReference) -> u8 {
+    #[cfg(feature = "vm_forwarding")]
+    return forwarding_state_to_bits(VM::VMObjectModel::get_forwarding_state(object));
+
+    #[cfg(not(feature = "vm_forwarding"))]
     VM::VMObjectModel::LOCAL_FORWARDING_BITS_SPEC.load_atomic::<VM, u8>(
         object,
         None,
@@ -127,7 +163,12 @@ pub fn get_forwarding_status<VM: VMBinding>(object: ObjectReference) -> u8 {
 }
 
 pub fn is_forwarded<VM: VMBinding>(object: ObjectReference) -> bool {
-    get_forwarding_status::<VM>(object) == FORWARDED
+    #[cfg(feature = "vm_forwarding")]
+    return VM::VMObjectModel::is_forwarded(object);
+
+    #[cfg(not(feature = "vm_forwarding"))]
+    get_forwarding_status::<VM>(object)
+        == FORWARDED
 }
 
 fn is_being_forwarded<VM: VMBinding>(object: ObjectReference) -> bool {
@@ -149,12 +190,15 @@ pub fn state_is_being_forwarded(forwarding_bits: u8) -> bool {
 /// Zero the forwarding bits of an object.
 /// This function is used on new objects.
 pub fn clear_forwarding_bits<VM: VMBinding>(object: ObjectReference) {
-    VM::VMObjectModel::LOCAL_FORWARDING_BITS_SPEC.store_atomic::<VM, u8>(
+    #[cfg(feature = "vm_forwarding")]
+    VM::VMObjectModel::clear_forwarding_state(object);
+
+    #[cfg(not(feature = "vm_forwarding"))]
+    VM::VMObjectModel::LOCAL_FORWARDING_BITS_SPEC.fetch_and_metadata::<VM, u8>(
         object,
-        0,
-        None,
+        FORWARDING_NOT_TRIGGERED_YET,
         Ordering::SeqCst,
-    )
+    );
 }
 
 /// Read the forwarding pointer of an object.
@@ -166,7 +210,11 @@ pub fn read_forwarding_pointer<VM: VMBinding>(object: ObjectReference) -> Object
         object,
     );
 
+    #[cfg(feature = "vm_forwarding")]
+    return VM::VMObjectModel::read_forwarding_pointer(object);
+
     // We write the forwarding poiner. We know it is an object reference.
+    #[cfg(not(feature = "vm_forwarding"))]
     unsafe {
         // We use "unchecked" convertion becasue we guarantee the forwarding pointer we stored
         // previously is from a valid `ObjectReference` which is never zero.
@@ -210,6 +258,7 @@ pub fn write_forwarding_pointer<VM: VMBinding>(
 /// Otherwise, returns `Some(shift)`, where `shift` is the left shift needed on forwarding bits.
 ///
 #[cfg(target_endian = "little")]
+#[cfg_attr(feature = "vm_forwarding", allow(dead_code))]
 pub(super) fn forwarding_bits_offset_in_forwarding_pointer<VM: VMBinding>() -> Option<isize> {
     use std::ops::Deref;
     // if both forwarding bits and forwarding pointer are in-header
@@ -230,6 +279,7 @@ pub(super) fn forwarding_bits_offset_in_forwarding_pointer<VM: VMBinding>() -> O
 }
 
 #[cfg(target_endian = "big")]
+#[cfg_attr(feature = "vm_forwarding", allow(dead_code))]
 pub(super) fn forwarding_bits_offset_in_forwarding_pointer<VM: VMBinding>() -> Option<isize> {
     unimplemented!()
 }
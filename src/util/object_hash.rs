@@ -0,0 +1,78 @@
+//! Address-based object hashing that is preserved when objects move.
+//!
+//! Many languages give each object a hash code that never changes, and the cheapest hash code is
+//! the address of the object. A moving GC breaks that, so MMTk implements the classic protocol with
+//! three hash states, stored in [`crate::vm::ObjectModel::GLOBAL_HASH_STATE_SPEC`]:
+//!
+//! *   Unhashed: the object has never been hashed.
+//! *   Hashed: [`crate::memory_manager::object_hash`] has been called for the object, and returned
+//!     its address. The object has not moved since then.
+//! *   Hashed and moved: the object was moved after it was hashed. When the object was copied, the
+//!     GC appended a word to the copy, and stored the old address in it as the hash code.
+//!
+//! The binding is responsible for the size of the appended word, as MMTk does not know the layout
+//! of objects. [`crate::vm::ObjectModel::get_current_size`] needs to include
+//! [`crate::vm::VMGlobalHashStateSpec::hash_bytes_in_object`], and
+//! [`crate::vm::ObjectModel::get_size_when_copied`] needs to include
+//! [`crate::vm::VMGlobalHashStateSpec::hash_bytes_when_copied`]. The hash word is always the last
+//! word of the object, and MMTk writes it after the binding copies the object.
+
+use crate::util::constants::BYTES_IN_WORD;
+use crate::util::metadata::hash_state::{HASHED, HASHED_AND_MOVED, UNHASHED};
+use crate::util::metadata::side_metadata::SideMetadataSpec;
+use crate::util::metadata::MetadataSpec;
+use crate::util::{Address, ObjectReference};
+use crate::vm::{ObjectModel, VMBinding};
+
+/// Add the hash state to the global side metadata specs if the binding puts it on the side.
+pub(crate) fn extend_global_side_metadata_specs<VM: VMBinding>(specs: &mut Vec<SideMetadataSpec>) {
+    if let MetadataSpec::OnSide(spec) = *VM::VMObjectModel::GLOBAL_HASH_STATE_SPEC {
+        specs.push(spec);
+    }
+}
+
+/// The address of the word that holds the hash code of a hashed and moved object.
+fn hash_word_address<VM: VMBinding>(object: ObjectReference) -> Address {
+    object.to_object_start::<VM>() + VM::VMObjectModel::get_current_size(object) - BYTES_IN_WORD
+}
+
+/// Get the hash code of an object, and mark the object as hashed if it has not been hashed.
+pub(crate) fn object_hash<VM: VMBinding>(object: ObjectReference) -> usize {
+    match VM::VMObjectModel::GLOBAL_HASH_STATE_SPEC.mark_as_hashed::<VM>(object) {
+        HASHED => object.to_raw_address().as_usize(),
+        HASHED_AND_MOVED => unsafe { hash_word_address::<VM>(object).load::<usize>() },
+        state => panic!("Invalid hash state {} for object {}", state, object),
+    }
+}
+
+/// Get the hash state of an object before it is moved. The state is needed by
+/// [`on_object_moved`], and the object may be overwritten by the time it is called.
+pub(crate) fn get_hash_state<VM: VMBinding>(object: ObjectReference) -> u8 {
+    VM::VMObjectModel::GLOBAL_HASH_STATE_SPEC.load_hash_state::<VM>(object)
+}
+
+/// Set the hash state of the new copy of an object, and store the hash code in the copy if the
+/// object has been hashed but not moved before.
+pub(crate) fn on_object_moved<VM: VMBinding>(
+    from: ObjectReference,
+    from_state: u8,
+    to: ObjectReference,
+) {
+    let spec = &VM::VMObjectModel::GLOBAL_HASH_STATE_SPEC;
+    match from_state {
+        UNHASHED => spec.store_hash_state::<VM>(to, UNHASHED),
+        HASHED => {
+            // Set the state first, so the size of the copy includes the hash word.
+            spec.store_hash_state::<VM>(to, HASHED_AND_MOVED);
+            unsafe { hash_word_address::<VM>(to).store::<usize>(from.to_raw_address().as_usize()) };
+        }
+        // The hash word is copied with the object.
+        HASHED_AND_MOVED => spec.store_hash_state::<VM>(to, HASHED_AND_MOVED),
+        state => panic!("Invalid hash state {} for object {}", state, from),
+    }
+}
+
+/// Reset the hash state of a new object. The memory may have been used by another object.
+pub(crate) fn on_object_allocated<VM: VMBinding>(object: ObjectReference) {
+    VM::VMObjectModel::GLOBAL_HASH_STATE_SPEC.store_hash_state::<VM>(object, UNHASHED);
+}
@@ -0,0 +1,116 @@
+//! Stable object IDs that survive object movement.
+//!
+//! [`crate::memory_manager::object_id`] assigns a unique 64-bit ID to an object the first time it
+//! is called for the object, and returns the same ID afterwards, even if the object is moved by
+//! the GC.  [`crate::memory_manager::object_from_id`] finds the object with a given ID.  This is
+//! meant for debuggers, heap dumps and serialization, which need to identify objects across GCs.
+//!
+//! The IDs are kept in a table from objects to IDs and its reverse.  The table is weak: it does not
+//! keep objects alive.  After the transitive closure of each GC (in the
+//! [`crate::scheduler::WorkBucketStage::VMUnloading`] stage, after the binding has processed its
+//! weak references), the entries of dead objects are removed, and the entries of moved objects are
+//! updated.  For plans that compute forwarding addresses after liveness (i.e. MarkCompact), the
+//! entries are updated again in the [`crate::scheduler::WorkBucketStage::VMRefForwarding`] stage.
+//!
+//! The cost is proportional to the number of objects that have IDs, so this is not meant for
+//! giving every object an ID.  An ID is never reused, even if its object dies.
+
+use crate::scheduler::{GCWork, GCWorker};
+use crate::util::ObjectReference;
+use crate::vm::VMBinding;
+use crate::MMTK;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::Mutex;
+
+#[derive(Default)]
+struct ObjectIdTableInner {
+    /// The next ID to assign.  IDs start from 1.
+    next_id: u64,
+    ids: HashMap<ObjectReference, u64>,
+    objects: HashMap<u64, ObjectReference>,
+}
+
+/// The table of object IDs of an MMTk instance.
+#[derive(Default)]
+pub(crate) struct ObjectIdTable {
+    inner: Mutex<ObjectIdTableInner>,
+}
+
+impl ObjectIdTable {
+    /// Get the ID of an object, and assign a new ID if it does not have one.
+    pub fn object_id(&self, object: ObjectReference) -> u64 {
+        debug_assert!(
+            object.is_in_any_space(),
+            "Object {} is not in MMTk spaces",
+            object
+        );
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(id) = inner.ids.get(&object) {
+            return *id;
+        }
+        inner.next_id += 1;
+        let id = inner.next_id;
+        inner.ids.insert(object, id);
+        inner.objects.insert(id, object);
+        id
+    }
+
+    /// Get the object with an ID, or `None` if the object is dead or the ID has not been assigned.
+    pub fn object_from_id(&self, id: u64) -> Option<ObjectReference> {
+        self.inner.lock().unwrap().objects.get(&id).copied()
+    }
+
+    /// Update the table after the transitive closure of a GC.  If `remove_dead` is true, remove the
+    /// entries of dead objects.  Update the entries of objects that have been forwarded.
+    fn update(&self, remove_dead: bool) {
+        let mut inner = self.inner.lock().unwrap();
+        let ObjectIdTableInner { ids, objects, .. } = &mut *inner;
+        let mut dead = 0;
+        let mut moved = 0;
+        let old_ids = std::mem::take(ids);
+        for (object, id) in old_ids {
+            if remove_dead && !object.is_live() {
+                objects.remove(&id);
+                dead += 1;
+                continue;
+            }
+            let new_object = match object.get_forwarded_object() {
+                Some(new_object) => {
+                    objects.insert(id, new_object);
+                    moved += 1;
+                    new_object
+                }
+                None => object,
+            };
+            ids.insert(new_object, id);
+        }
+        debug!(
+            "Object IDs: {} live, {} dead, {} moved",
+            ids.len(),
+            dead,
+            moved
+        );
+    }
+}
+
+/// Remove the IDs of dead objects, and update the IDs of moved objects.
+#[derive(Default)]
+pub(crate) struct UpdateObjectIds<VM: VMBinding>(PhantomData<VM>);
+
+impl<VM: VMBinding> GCWork<VM> for UpdateObjectIds<VM> {
+    fn do_work(&mut self, _worker: &mut GCWorker<VM>, mmtk: &'static MMTK<VM>) {
+        mmtk.object_ids.update(true);
+    }
+}
+
+/// Update the IDs of objects moved after their forwarding addresses are computed
+/// (mark-compact-only).
+#[derive(Default)]
+pub(crate) struct ForwardObjectIds<VM: VMBinding>(PhantomData<VM>);
+
+impl<VM: VMBinding> GCWork<VM> for ForwardObjectIds<VM> {
+    fn do_work(&mut self, _worker: &mut GCWorker<VM>, mmtk: &'static MMTK<VM>) {
+        mmtk.object_ids.update(false);
+    }
+}
@@ -0,0 +1,112 @@
+//! A word of user data attached to objects.
+//!
+//! A binding may attach a word of data to any object with
+//! [`crate::memory_manager::set_object_user_data`], for example, to annotate objects for a
+//! profiler or a debugger, without changing the layout of objects.  MMTk keeps the data in a side
+//! table keyed by objects, and keeps the table consistent across GCs in the same way as the table of
+//! [`crate::util::object_id`]: after the transitive closure of each GC, the data of moved objects
+//! is moved to their new addresses, and the data of dead objects is removed.  If the binding sets a
+//! callback with [`crate::memory_manager::set_object_user_data_death_callback`], the callback is
+//! called with the data of each dead object, so that the binding can release what the data refers
+//! to.
+//!
+//! The table is a hash table, so this is meant for a small fraction of the objects.
+
+use crate::scheduler::{GCWork, GCWorker};
+use crate::util::ObjectReference;
+use crate::vm::VMBinding;
+use crate::MMTK;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::Mutex;
+
+/// A callback called with a dead object and its user data.  The object must not be accessed, as
+/// its memory may have been reclaimed.  It is called by a GC worker during a GC.
+pub type ObjectUserDataDeathCallback = Box<dyn FnMut(ObjectReference, usize) + Send>;
+
+/// The user data of objects in an MMTk instance.
+#[derive(Default)]
+pub(crate) struct ObjectUserDataTable {
+    data: Mutex<HashMap<ObjectReference, usize>>,
+    on_death: Mutex<Option<ObjectUserDataDeathCallback>>,
+}
+
+impl ObjectUserDataTable {
+    /// Set the user data of an object, and return the previous data, if any.
+    pub fn set(&self, object: ObjectReference, data: usize) -> Option<usize> {
+        debug_assert!(
+            object.is_in_any_space(),
+            "Object {} is not in MMTk spaces",
+            object
+        );
+        self.data.lock().unwrap().insert(object, data)
+    }
+
+    pub fn get(&self, object: ObjectReference) -> Option<usize> {
+        self.data.lock().unwrap().get(&object).copied()
+    }
+
+    /// Remove the user data of an object, and return it, if any.  The death callback is not called.
+    pub fn clear(&self, object: ObjectReference) -> Option<usize> {
+        self.data.lock().unwrap().remove(&object)
+    }
+
+    pub fn set_death_callback(&self, callback: ObjectUserDataDeathCallback) {
+        *self.on_death.lock().unwrap() = Some(callback);
+    }
+
+    /// Update the table after the transitive closure of a GC.  If `remove_dead` is true, remove the
+    /// data of dead objects, and call the death callback for them.  Move the data of objects that
+    /// have been forwarded.
+    fn update(&self, remove_dead: bool) {
+        let mut data = self.data.lock().unwrap();
+        let mut on_death = self.on_death.lock().unwrap();
+        let mut dead = 0;
+        let mut moved = 0;
+        let old_data = std::mem::take(&mut *data);
+        for (object, value) in old_data {
+            if remove_dead && !object.is_live() {
+                if let Some(callback) = on_death.as_mut() {
+                    callback(object, value);
+                }
+                dead += 1;
+                continue;
+            }
+            let new_object = match object.get_forwarded_object() {
+                Some(new_object) => {
+                    moved += 1;
+                    new_object
+                }
+                None => object,
+            };
+            data.insert(new_object, value);
+        }
+        debug!(
+            "Object user data: {} live, {} dead, {} moved",
+            data.len(),
+            dead,
+            moved
+        );
+    }
+}
+
+/// Remove the user data of dead objects, and move the user data of moved objects.
+#[derive(Default)]
+pub(crate) struct UpdateObjectUserData<VM: VMBinding>(PhantomData<VM>);
+
+impl<VM: VMBinding> GCWork<VM> for UpdateObjectUserData<VM> {
+    fn do_work(&mut self, _worker: &mut GCWorker<VM>, mmtk: &'static MMTK<VM>) {
+        mmtk.object_user_data.update(true);
+    }
+}
+
+/// Move the user data of objects moved after their forwarding addresses are computed
+/// (mark-compact-only).
+#[derive(Default)]
+pub(crate) struct ForwardObjectUserData<VM: VMBinding>(PhantomData<VM>);
+
+impl<VM: VMBinding> GCWork<VM> for ForwardObjectUserData<VM> {
+    fn do_work(&mut self, _worker: &mut GCWorker<VM>, mmtk: &'static MMTK<VM>) {
+        mmtk.object_user_data.update(false);
+    }
+}
@@ -0,0 +1,110 @@
+//! The registry of the kinds of off-heap objects that participate in tracing.  See
+//! [`crate::vm::OffHeapObjects`].
+
+use crate::plan::{is_nursery_gc, ObjectQueue};
+use crate::scheduler::{GCWork, GCWorker};
+use crate::util::statistics::counter::EventCounter;
+use crate::util::statistics::stats::Stats;
+use crate::util::ObjectReference;
+use crate::vm::ActivePlan;
+use crate::vm::{OffHeapObjects, VMBinding};
+use crate::MMTK;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+
+struct RegisteredKind<VM: VMBinding> {
+    kind: Box<dyn OffHeapObjects<VM>>,
+    /// The number of objects of this kind traced in the current GC.
+    traced: AtomicUsize,
+    counter: Arc<Mutex<EventCounter>>,
+}
+
+/// The kinds of off-heap objects registered by the binding.
+pub(crate) struct OffHeapObjectRegistry<VM: VMBinding> {
+    kinds: RwLock<Vec<RegisteredKind<VM>>>,
+}
+
+impl<VM: VMBinding> OffHeapObjectRegistry<VM> {
+    pub fn new() -> Self {
+        Self {
+            kinds: RwLock::new(vec![]),
+        }
+    }
+
+    pub fn register(&self, kind: Box<dyn OffHeapObjects<VM>>, stats: &Stats) {
+        let counter = stats.new_event_counter(&format!("offheap.{}", kind.name()), true, true);
+        self.kinds.write().unwrap().push(RegisteredKind {
+            kind,
+            traced: AtomicUsize::new(0),
+            counter,
+        });
+    }
+
+    /// Trace `object` if it is an off-heap object of a registered kind, and return `None` otherwise.
+    pub fn trace_object<Q: ObjectQueue>(
+        &self,
+        queue: &mut Q,
+        object: ObjectReference,
+    ) -> Option<ObjectReference> {
+        let kinds = self.kinds.read().unwrap();
+        let registered = kinds.iter().find(|k| k.kind.contains(object))?;
+        if registered.kind.test_and_mark(object) {
+            trace!("trace_object: {} object {}", registered.kind.name(), object);
+            registered.traced.fetch_add(1, Ordering::Relaxed);
+            queue.enqueue(object);
+        }
+        Some(object)
+    }
+
+    fn prepare(&self, worker: &GCWorker<VM>, full_heap: bool) {
+        for registered in self.kinds.read().unwrap().iter() {
+            registered.kind.prepare(worker.tls, full_heap);
+        }
+    }
+
+    fn release(&self, worker: &GCWorker<VM>, full_heap: bool) {
+        for registered in self.kinds.read().unwrap().iter() {
+            let traced = registered.traced.swap(0, Ordering::Relaxed);
+            debug!("Traced {} {} objects", traced, registered.kind.name());
+            registered.counter.lock().unwrap().inc_by(traced as u64);
+            registered.kind.release(worker.tls, full_heap);
+        }
+    }
+}
+
+/// Trace an object that is not in any MMTk space.  Trace it as an off-heap object if it belongs to
+/// a registered kind, or fall back to [`ActivePlan::vm_trace_object`] otherwise.
+pub(crate) fn trace_object_outside_spaces<VM: VMBinding, Q: ObjectQueue>(
+    queue: &mut Q,
+    object: ObjectReference,
+    worker: &mut GCWorker<VM>,
+) -> ObjectReference {
+    if let Some(object) = worker.mmtk.off_heap_objects.trace_object(queue, object) {
+        return object;
+    }
+    VM::VMActivePlan::vm_trace_object::<Q>(queue, object, worker)
+}
+
+/// Call [`OffHeapObjects::prepare`] for each registered kind of off-heap objects.
+#[derive(Default)]
+pub(crate) struct PrepareOffHeapObjects<VM: VMBinding>(PhantomData<VM>);
+
+impl<VM: VMBinding> GCWork<VM> for PrepareOffHeapObjects<VM> {
+    fn do_work(&mut self, worker: &mut GCWorker<VM>, mmtk: &'static MMTK<VM>) {
+        mmtk.off_heap_objects
+            .prepare(worker, !is_nursery_gc(mmtk.get_plan()));
+    }
+}
+
+/// Call [`OffHeapObjects::release`] for each registered kind of off-heap objects, and update the
+/// statistics.
+#[derive(Default)]
+pub(crate) struct ReleaseOffHeapObjects<VM: VMBinding>(PhantomData<VM>);
+
+impl<VM: VMBinding> GCWork<VM> for ReleaseOffHeapObjects<VM> {
+    fn do_work(&mut self, worker: &mut GCWorker<VM>, mmtk: &'static MMTK<VM>) {
+        mmtk.off_heap_objects
+            .release(worker, !is_nursery_gc(mmtk.get_plan()));
+    }
+}
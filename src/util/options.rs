@@ -1,5 +1,6 @@
 use crate::scheduler::affinity::{get_total_num_cpus, CoreId};
 use crate::util::constants::LOG_BYTES_IN_MBYTE;
+use crate::util::memory::HugePageSupport;
 use crate::util::Address;
 use std::default::Default;
 use std::fmt::Debug;
@@ -24,6 +25,69 @@ pub enum NurseryZeroingOptions {
     Adaptive,
 }
 
+/// When MMTk pre-touches the memory it maps for spaces and side metadata, so that the pages are backed
+/// by physical memory before they are first accessed, and the application does not take a storm of
+/// page faults when it starts using the heap.
+#[derive(Copy, Clone, EnumString, Debug, PartialEq, Eq)]
+pub enum PretouchMode {
+    /// Do not pre-touch memory. Pages are backed by physical memory when they are first accessed.
+    No,
+    /// Pre-touch the memory in the thread that maps it, when it is mapped (with `MAP_POPULATE` on Linux).
+    OnMap,
+    /// Let GC workers pre-touch the memory of spaces in the background after it is mapped (only Linux is
+    /// supported). Side metadata is pre-touched when it is mapped.
+    Background,
+}
+
+/// The malloc library used by the malloc mark sweep space (the feature `malloc_mark_sweep`).
+/// Libc malloc is always available. Other libraries are only available if they are linked with
+/// the corresponding cargo features, `malloc_mimalloc` or `malloc_jemalloc`.
+#[derive(Copy, Clone, EnumString, Debug, PartialEq, Eq)]
+pub enum MallocBackend {
+    /// The malloc in libc.
+    Libc,
+    /// mimalloc.
+    Mimalloc,
+    /// jemalloc.
+    Jemalloc,
+}
+
+impl MallocBackend {
+    /// The library linked with the cargo features, or libc if none is linked.
+    pub const DEFAULT: Self = if cfg!(feature = "malloc_jemalloc") {
+        MallocBackend::Jemalloc
+    } else if cfg!(feature = "malloc_mimalloc") {
+        MallocBackend::Mimalloc
+    } else {
+        MallocBackend::Libc
+    };
+
+    /// Is the library linked into MMTk?
+    pub fn is_available(&self) -> bool {
+        match self {
+            MallocBackend::Libc => true,
+            MallocBackend::Mimalloc => cfg!(feature = "malloc_mimalloc"),
+            MallocBackend::Jemalloc => cfg!(feature = "malloc_jemalloc"),
+        }
+    }
+}
+
+/// The order in which GC workers process the tracing work packets they create. The order of tracing is
+/// the order in which copying plans copy objects, which affects the locality of the mutators after a GC.
+/// Packets in the global work buckets and packets stolen from other workers are always processed in the
+/// order they are added.
+#[derive(Copy, Clone, EnumString, Debug, PartialEq, Eq)]
+pub enum TracingOrder {
+    /// Each worker processes the packets it creates in the order they are created. Siblings are copied
+    /// together, and a parent tends to be separated from its children. The work is spread quickly among the
+    /// workers, and the stealers take the oldest packets.
+    BreadthFirst,
+    /// Each worker processes the packet it created last first. Children tend to be copied right after their
+    /// parents, which usually suits the access patterns of the mutators better, at the cost of less
+    /// parallelism early in the closure, since the stealers still take the oldest packets.
+    DepthFirst,
+}
+
 /// Select a GC plan for MMTk.
 #[derive(Copy, Clone, EnumString, Debug, PartialEq, Eq)]
 pub enum PlanSelector {
@@ -300,6 +364,37 @@ impl Options {
         *self.stress_factor != DEFAULT_STRESS_FACTOR
             || *self.analysis_factor != DEFAULT_STRESS_FACTOR
     }
+
+    /// Get the huge page support for the memory of the space of the given name.  Explicit huge
+    /// pages cannot be protected at the granularity of normal pages, so a space that may protect
+    /// its pages (see [`Options::may_protect_pages`]) uses transparent huge pages instead.
+    pub fn huge_page_support(&self, space_name: &str) -> HugePageSupport {
+        let support = self
+            .huge_page_spaces
+            .get(space_name)
+            .unwrap_or(if *self.hugetlb {
+                HugePageSupport::HugeTLB
+            } else if *self.transparent_hugepages {
+                HugePageSupport::TransparentHugePages
+            } else {
+                HugePageSupport::No
+            });
+        if support == HugePageSupport::HugeTLB && self.may_protect_pages(space_name) {
+            HugePageSupport::TransparentHugePages
+        } else {
+            support
+        }
+    }
+
+    /// Return true if MMTk may change the protection of some pages of the space of the given name
+    /// with `mprotect`: in the PageProtect plan, with `protect_free_blocks`, for the code spaces
+    /// with `code_space_wx`, and for the read-only space which can be sealed.
+    pub fn may_protect_pages(&self, space_name: &str) -> bool {
+        *self.plan == PlanSelector::PageProtect
+            || *self.protect_free_blocks
+            || (*self.code_space_wx && matches!(space_name, "code_space" | "code_lo_space"))
+            || (cfg!(feature = "ro_space") && space_name == "ro_space")
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -396,6 +491,131 @@ impl FromStr for AffinityKind {
     }
 }
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// NumaPolicy describes where the memory of MMTk spaces is placed on a machine with multiple NUMA
+/// nodes. The policy is applied to the memory when MMTk mmaps it for a space, and all the blocks
+/// later acquired from that memory follow the same policy.
+pub enum NumaPolicy {
+    /// Use the default policy of the OS. Usually a page is placed on the node of the thread that
+    /// first touches it.
+    FirstTouch,
+    /// Interleave the pages over all the online NUMA nodes.
+    Interleave,
+    /// Place the pages on the given NUMA node only.
+    Bind(usize),
+}
+
+impl NumaPolicy {
+    /// Return true if the policy is supported on this platform, and the node (if any) is online.
+    pub fn validate(&self) -> bool {
+        match *self {
+            NumaPolicy::FirstTouch => true,
+            NumaPolicy::Interleave => cfg!(target_os = "linux"),
+            NumaPolicy::Bind(node) => {
+                cfg!(target_os = "linux") && crate::util::memory::get_numa_nodes().contains(&node)
+            }
+        }
+    }
+}
+
+impl FromStr for NumaPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            None if s == "FirstTouch" => Ok(NumaPolicy::FirstTouch),
+            None if s == "Interleave" => Ok(NumaPolicy::Interleave),
+            Some(("Bind", node)) => node
+                .parse::<usize>()
+                .map(NumaPolicy::Bind)
+                .map_err(|_| format!("Invalid NUMA node: {node}")),
+            _ => Err(format!("Invalid NUMA policy: {s}")),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+/// NumaSpacePolicies overrides the NUMA policy for some spaces. It is formatted as a list of
+/// `<space name>=<policy>` separated by commas, for example, `nursery=Bind:0,immix=Interleave`.
+pub struct NumaSpacePolicies {
+    /// The space names and their policies.
+    pub policies: Vec<(String, NumaPolicy)>,
+}
+
+impl NumaSpacePolicies {
+    /// Return the NUMA policy for the space of the given name, if it is overridden.
+    pub fn get(&self, space_name: &str) -> Option<NumaPolicy> {
+        self.policies
+            .iter()
+            .find(|(name, _)| name == space_name)
+            .map(|(_, policy)| *policy)
+    }
+
+    /// Return true if all the policies are valid.
+    pub fn validate(&self) -> bool {
+        self.policies.iter().all(|(_, policy)| policy.validate())
+    }
+}
+
+impl FromStr for NumaSpacePolicies {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut policies = vec![];
+        for entry in s.split(',').filter(|entry| !entry.is_empty()) {
+            let (name, policy) = entry
+                .split_once('=')
+                .ok_or_else(|| format!("Expect <space name>=<policy>, found {entry}"))?;
+            policies.push((name.to_string(), policy.parse::<NumaPolicy>()?));
+        }
+        Ok(NumaSpacePolicies { policies })
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+/// HugePageSpaces overrides the huge page support for some spaces. It is formatted as a list of
+/// `<space name>=<support>` separated by commas, where the support is `No`, `TransparentHugePages`
+/// or `HugeTLB`, for example, `immix=HugeTLB,los=No`.
+pub struct HugePageSpaces {
+    /// The space names and their huge page support.
+    pub spaces: Vec<(String, HugePageSupport)>,
+}
+
+impl HugePageSpaces {
+    /// Return the huge page support for the space of the given name, if it is overridden.
+    pub fn get(&self, space_name: &str) -> Option<HugePageSupport> {
+        self.spaces
+            .iter()
+            .find(|(name, _)| name == space_name)
+            .map(|(_, support)| *support)
+    }
+
+    /// Return true if the huge page support is available on this platform for all the spaces.
+    pub fn validate(&self) -> bool {
+        self.spaces
+            .iter()
+            .all(|(_, support)| *support == HugePageSupport::No || cfg!(target_os = "linux"))
+    }
+}
+
+impl FromStr for HugePageSpaces {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut spaces = vec![];
+        for entry in s.split(',').filter(|entry| !entry.is_empty()) {
+            let (name, support) = entry
+                .split_once('=')
+                .ok_or_else(|| format!("Expect <space name>=<huge page support>, found {entry}"))?;
+            let support = support
+                .parse::<HugePageSupport>()
+                .map_err(|_| format!("Invalid huge page support: {support}"))?;
+            spaces.push((name.to_string(), support));
+        }
+        Ok(HugePageSpaces { spaces })
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 /// An option that provides a min/max interface to MMTk and a Bounded/Fixed interface to the
 /// user/VM.
@@ -415,6 +635,18 @@ pub enum NurserySize {
         /// The upper bound of the nursery size as a proportion of the current heap size. Default to [`DEFAULT_PROPORTIONAL_MAX_NURSERY`].
         max: f64,
     },
+    /// A bounded nursery whose upper bound is the bytes that mutators allocate in the given time at
+    /// the recent allocation rate (see [`crate::MMTK::get_allocation_rate`]), so that nursery GCs
+    /// happen at roughly that interval.  The upper bound is clamped to `[min, max]`, and is `max`
+    /// before the allocation rate is known.
+    AllocationRateBounded {
+        /// The lower bound of the nursery size in bytes. Default to [`DEFAULT_MIN_NURSERY`].
+        min: usize,
+        /// The upper bound of the nursery size in bytes. Default to [`DEFAULT_MAX_NURSERY`].
+        max: usize,
+        /// The time in milliseconds.
+        millis: usize,
+    },
     /// A Fixed nursery has the same upper and lower bounds. The size controls both the upper and
     /// lower bounds. Note that this is considered less performant than a Bounded nursery since a
     /// Fixed nursery size can be too restrictive and cause more GCs.
@@ -429,6 +661,7 @@ impl NurserySize {
             NurserySize::ProportionalBounded { min, max } => {
                 0.0f64 < min && min <= max && max <= 1.0f64
             }
+            NurserySize::AllocationRateBounded { min, max, millis } => min <= max && millis > 0,
             NurserySize::Fixed(_) => true,
         }
     }
@@ -474,6 +707,18 @@ impl FromStr for NurserySize {
                     Err("ProportionalBounded requires two values".to_string())
                 }
             }
+            "AllocationRateBounded" => {
+                if values.len() == 3 {
+                    let min = default_or_parse(values[0], DEFAULT_MIN_NURSERY)?;
+                    let max = default_or_parse(values[1], DEFAULT_MAX_NURSERY)?;
+                    let millis = values[2]
+                        .parse::<usize>()
+                        .map_err(|_| "Invalid time value".to_string())?;
+                    Ok(NurserySize::AllocationRateBounded { min, max, millis })
+                } else {
+                    Err("AllocationRateBounded requires three values".to_string())
+                }
+            }
             "Fixed" => {
                 if values.len() == 1 {
                     let size = values[0]
@@ -572,6 +817,26 @@ mod nursery_size_parsing_tests {
             panic!("Failed: {:?}", result);
         }
     }
+
+    #[test]
+    fn test_allocation_rate_bounded() {
+        let result = "AllocationRateBounded:_,_,50"
+            .parse::<NurserySize>()
+            .unwrap();
+        if let NurserySize::AllocationRateBounded { min, max, millis } = result {
+            assert_eq!(min, DEFAULT_MIN_NURSERY);
+            assert_eq!(max, DEFAULT_MAX_NURSERY);
+            assert_eq!(millis, 50);
+        } else {
+            panic!("Failed: {:?}", result);
+        }
+
+        // The time is required.
+        assert!("AllocationRateBounded:1,2".parse::<NurserySize>().is_err());
+        assert!("AllocationRateBounded:1,2,_"
+            .parse::<NurserySize>()
+            .is_err());
+    }
 }
 
 /// Select a GC trigger for MMTk.
@@ -790,6 +1055,15 @@ options! {
     plan:                  PlanSelector         [env_var: true, command_line: true] [always_valid] = PlanSelector::GenImmix,
     /// Number of GC worker threads.
     threads:               usize                [env_var: true, command_line: true] [|v: &usize| *v > 0]    = num_cpus::get(),
+    /// Make GC deterministic, so that GC-dependent bugs can be reproduced. If this is true, we use a single GC worker regardless
+    /// of the `threads` option, so work packets are always executed in the same order, spaces that keep objects in hash sets
+    /// (e.g. the large object space) release the memory of dead objects in the order of their addresses, and the free blocks
+    /// of block-based spaces (Immix and mark-sweep) are allocated in the order of their addresses after each GC. The options
+    /// `concurrent_sweeping`, `concurrent_defrag_preparation`, `reclaim_idle_mutator_blocks` and `idle_mutator_flush_timeout`
+    /// are disabled, as they depend on the timing of mutators. For the GC to be fully deterministic, the binding should also
+    /// use a fixed heap size (`gc_trigger`), as the dynamic heap size depends on timing, and allocate from the mutators in
+    /// a deterministic order.
+    deterministic_gc:      bool                 [env_var: true, command_line: true]  [always_valid] = false,
     /// Enable an optimization that only scans the part of the stack that has changed since the last GC (not supported)
     use_short_stack_scans: bool                 [env_var: true, command_line: true]  [always_valid] = false,
     /// Enable a return barrier (not supported)
@@ -803,11 +1077,25 @@ options! {
     /// to have a Fixed nursery size of 8192 bytes, or 'ProportionalBounded:0.2,1.0' to have a nursery size
     /// between 20% and 100% of the heap size. You can omit lower bound and upper bound to use the default
     /// value for bounded nursery by using '_'. For example, 'ProportionalBounded:0.1,_' sets the min nursery
-    /// to 10% of the heap size while using the default value for max nursery.
+    /// to 10% of the heap size while using the default value for max nursery. 'AllocationRateBounded:_,_,100' limits
+    /// the nursery to the bytes allocated in 100 milliseconds at the recent allocation rate.
     nursery:               NurserySize          [env_var: true, command_line: true]  [|v: &NurserySize| v.validate()]
         = NurserySize::ProportionalBounded { min: DEFAULT_PROPORTIONAL_MIN_NURSERY, max: DEFAULT_PROPORTIONAL_MAX_NURSERY },
     /// Should a major GC be performed when a system GC is required?
     full_heap_system_gc:   bool                 [env_var: true, command_line: true]  [always_valid] = false,
+    /// Size the copy reserve of SemiSpace (and of the mature space of GenCopy) by the survival rates of recent GCs,
+    /// instead of reserving as many pages as the space uses. The pages not reserved can be used by other spaces.
+    /// If more objects survive than estimated, the heap may temporarily exceed its size while copying.
+    elastic_copy_reserve:  bool                 [env_var: true, command_line: true]  [always_valid] = false,
+    /// Repack the native mark sweep space every N GCs (0 disables repacking). In a repacking GC, the live objects in sparse blocks are
+    /// copied into fresh blocks of the same size class, so that the sparse blocks can be reclaimed as a whole. This moves objects, so it
+    /// must only be enabled if the binding supports moving objects (`ObjectModel::copy`, forwarding bits and updating references).
+    /// Objects reached from pinning roots are not moved. If this is set, the MarkSweep plan uses `MS_REPACK_CONSTRAINTS`, which sets `moves_objects`.
+    ms_repack_interval:    usize                [env_var: true, command_line: true]  [always_valid] = 0,
+    /// The malloc library for the malloc mark sweep space (the feature `malloc_mark_sweep`), which can be `Libc`, `Mimalloc` or `Jemalloc`.
+    /// Libraries other than libc must be linked with the cargo features `malloc_mimalloc` or `malloc_jemalloc`. The default is the linked
+    /// library, or libc if none is linked. The manual malloc API (`memory_manager::malloc` and others) always uses the default library.
+    malloc_backend:        MallocBackend        [env_var: true, command_line: true]  [|v: &MallocBackend| v.is_available()] = MallocBackend::DEFAULT,
     /// Should finalization be disabled?
     no_finalizer:          bool                 [env_var: true, command_line: true]  [always_valid] = false,
     /// Should reference type processing be disabled?
@@ -822,6 +1110,27 @@ options! {
     stress_factor:         usize                [env_var: true, command_line: true]  [always_valid] = DEFAULT_STRESS_FACTOR,
     /// How frequent (every X bytes) should we run analysis (a STW event that collects data)
     analysis_factor:       usize                [env_var: true, command_line: true]  [always_valid] = DEFAULT_STRESS_FACTOR,
+    /// Verify every reference field in the heap at the end of each GC. This is only used when the feature "heap_verifier" is enabled.
+    verify_heap:           bool                 [env_var: true, command_line: true]  [always_valid] = false,
+    /// Fill the memory reclaimed by GC with a poison pattern (`memory::POISON_BYTE`), so that dangling references
+    /// to dead objects are more likely to crash early. This covers dead large objects, free cells in the native mark sweep space,
+    /// free lines and blocks in the Immix space, and the from-space after copying. This is a debugging option and is slow.
+    poison_on_free:        bool                 [env_var: true, command_line: true]  [always_valid] = false,
+    /// Protect (with mprotect) the blocks in the Immix space and the native mark sweep space when they are entirely free,
+    /// and the pages of dead large objects, so that any access to them will cause a segmentation fault. The memory is unprotected
+    /// when it is acquired again.
+    /// This is a debugging option and is slow.
+    protect_free_blocks:   bool                 [env_var: true, command_line: true]  [always_valid] = false,
+    /// The number of GCs for which the pages of dead large objects stay protected before they can be allocated again.
+    /// This only takes effect if the pages are protected when released, i.e. in the PageProtect plan, or if `protect_free_blocks` is set.
+    /// The pages in quarantine still count towards the heap size, so a larger heap may be needed.
+    quarantine_gcs:        usize                [env_var: true, command_line: true]  [always_valid] = 0,
+    /// The number of types (with the most live bytes) to keep in the live object histogram of each GC, and in the leak report.
+    /// This is only used when the feature "analysis" is enabled.
+    live_demographics_top_n: usize              [env_var: true, command_line: true]  [|v: &usize| *v > 0] = 10,
+    /// The leak report only includes objects that have survived at least this number of full-heap GCs.
+    /// This is only used when the feature "analysis" is enabled.
+    leak_report_min_full_gcs: usize             [env_var: true, command_line: true]  [|v: &usize| *v > 0] = 3,
     /// Precise stress test. Trigger stress GCs exactly at X bytes if this is true. This is usually used to test the GC correctness
     /// and will significantly slow down the mutator performance. If this is false, stress GCs will only be triggered when an allocation reaches
     /// the slow path. This means we may have allocated more than X bytes or fewer than X bytes when we actually trigger a stress GC.
@@ -836,7 +1145,7 @@ options! {
     /// Semicolons are used to separate events
     /// Each event is in the format of event_name,pid,cpu (see man perf_event_open for what pid and cpu mean).
     /// For example, PERF_COUNT_HW_CPU_CYCLES,0,-1 measures the CPU cycles for the current process on all the CPU cores.
-    /// Measuring perf events for work packets. NOTE that be VERY CAREFUL when using this option, as this may greatly slowdown GC performance.
+    /// Measuring perf events for work packets, aggregated for each work packet type and for each work bucket stage. NOTE that be VERY CAREFUL when using this option, as this may greatly slowdown GC performance.
     // TODO: Ideally this option should only be included when the features 'perf_counter' and 'work_packet_stats' are enabled. The current macro does not allow us to do this.
     work_perf_events:       PerfEventOptions     [env_var: true, command_line: true] [|_| cfg!(all(feature = "perf_counter", feature = "work_packet_stats"))] = PerfEventOptions {events: vec![]},
     /// Measuring perf events for GC and mutators
@@ -865,8 +1174,81 @@ options! {
     /// Enable transparent hugepage support for MMTk spaces via madvise (only Linux is supported)
     /// This only affects the memory for MMTk spaces.
     transparent_hugepages: bool                  [env_var: true, command_line: true]  [|v: &bool| !v || cfg!(target_os = "linux")] = false,
+    /// Back MMTk spaces with explicit huge pages from the hugetlbfs pool (`MAP_HUGETLB`, only Linux is supported).
+    /// The memory falls back to transparent huge pages if the pool runs out of huge pages. The pool needs to be
+    /// reserved in advance, for example, with `/proc/sys/vm/nr_hugepages`. This takes precedence over `transparent_hugepages`.
+    /// Spaces whose pages may be protected with `mprotect` (e.g. with `protect_free_blocks`) use transparent huge pages instead.
+    hugetlb:               bool                  [env_var: true, command_line: true]  [|v: &bool| !v || cfg!(target_os = "linux")] = false,
+    /// Override the huge page support for some spaces, as a comma-separated list of `<space name>=<support>`,
+    /// where the support is `No`, `TransparentHugePages` or `HugeTLB`, for example, `immix=HugeTLB,los=No`.
+    huge_page_spaces:      HugePageSpaces        [env_var: true, command_line: true]  [|v: &HugePageSpaces| v.validate()] = HugePageSpaces::default(),
+    /// Enable transparent hugepage support for side metadata via madvise (only Linux is supported).
+    transparent_hugepages_side_metadata: bool    [env_var: true, command_line: true]  [|v: &bool| !v || cfg!(target_os = "linux")] = false,
+    /// The NUMA policy for the memory of MMTk spaces: `FirstTouch`, `Interleave` or `Bind:<node>`
+    /// (only Linux supports policies other than `FirstTouch`). This only affects the memory for MMTk spaces.
+    numa_policy:           NumaPolicy            [env_var: true, command_line: true]  [|v: &NumaPolicy| v.validate()] = NumaPolicy::FirstTouch,
+    /// Override `numa_policy` for some spaces, as a comma-separated list of `<space name>=<policy>`,
+    /// for example, `nursery=Bind:0,immix=Interleave`.
+    numa_space_policies:   NumaSpacePolicies     [env_var: true, command_line: true]  [|v: &NumaSpacePolicies| v.validate()] = NumaSpacePolicies::default(),
+    /// Back the memory for MMTk spaces and side metadata with files in this directory, so that the heap can be
+    /// inspected with `util::heap_file::HeapFileReader` after a crash (only Unix-like OSes are supported).
+    /// The directory should be on a fast file system such as `tmpfs`. An empty string disables this.
+    heap_file_dir:         String                [env_var: true, command_line: true]  [|v: &String| v.is_empty() || cfg!(unix)] = String::new(),
+    /// Pre-touch the memory of MMTk spaces and side metadata after mapping it: `No`, `OnMap` or `Background`.
+    /// See [`PretouchMode`]. A space that maps its whole memory when it is created pre-touches it at that time.
+    pretouch:              PretouchMode          [env_var: true, command_line: true]  [|v: &PretouchMode| *v != PretouchMode::Background || cfg!(target_os = "linux")] = PretouchMode::No,
+    /// Map the code spaces (feature `code_space`) without execution permission, and let the VM make parts of them
+    /// executable (and no longer writable) with `memory_manager::set_code_executable` (only Unix-like OSes are supported).
+    /// If this is false, the code spaces are always readable, writable and executable.
+    code_space_wx:         bool                  [env_var: true, command_line: true]  [|v: &bool| !v || (cfg!(unix) && !cfg!(feature = "exec_permission_on_all_spaces"))] = false,
+    /// Sweep the heap concurrently with mutators after a GC instead of in the GC pause. GC workers sweep the
+    /// Immix space (except in defrag GCs), the unswept blocks of the mark-sweep space and the dead large objects
+    /// after mutators resume, and the next GC starts after the sweeping is finished. Only `Immix` and `MarkSweep`
+    /// support this. With the features `eager_sweeping` or `malloc_mark_sweep`, `MarkSweep` still sweeps in the pause.
+    concurrent_sweeping:   bool                  [env_var: true, command_line: true] [always_valid] = false,
+    /// Select the blocks to defragment in the next GC concurrently with mutators after a GC (after the concurrent
+    /// sweeping, if `concurrent_sweeping` is set), using the statistics of that GC, instead of in the prepare stage of
+    /// the next GC. Only `Immix` supports this.
+    concurrent_defrag_preparation: bool          [env_var: true, command_line: true] [always_valid] = false,
     /// Count live bytes for objects in each space during a GC.
-    count_live_bytes_in_gc: bool                 [env_var: true, command_line: true] [always_valid] = false
+    count_live_bytes_in_gc: bool                 [env_var: true, command_line: true] [always_valid] = false,
+    /// Flush the mutators that have not allocated in the allocation slow path for this many milliseconds, and retire
+    /// their thread-local allocation buffers so that the memory can be reused by other mutators. The check is done in
+    /// the allocation slow path, using handshakes (see `Collection::request_handshake`). 0 disables this.
+    idle_mutator_flush_timeout: usize            [env_var: true, command_line: true] [always_valid] = 0,
+    /// When the Immix allocation slow path would trigger a GC, first allocate into the lines returned by idle mutators
+    /// (those that have not allocated in the allocation slow path since the last check) if possible. Otherwise, ask the
+    /// idle mutators to return the unused lines of their partially used blocks, using a handshake (see
+    /// `Collection::request_handshake`). The mutator does not wait for the handshake, and the handshake is started at
+    /// most once every 10 milliseconds.
+    reclaim_idle_mutator_blocks: bool            [env_var: true, command_line: true] [always_valid] = false,
+    /// The number of Immix blocks that mutators may acquire between two GCs in `StickyImmix`. This acts as a logical
+    /// nursery size: a nursery GC is triggered when the budget is used up, even if the heap is not full. The consumed
+    /// budget is reported as the counter `nurseryBlocks`, and by `MMTK::last_gc_immix_mutator_blocks`. 0 means no budget.
+    sticky_immix_nursery_blocks: usize           [env_var: true, command_line: true] [always_valid] = 0,
+    /// The budget for the tracing work packets queued in a work bucket, in bytes, estimated from the buffer size of each
+    /// packet. When the queued packets exceed the budget, new tracing packets are spilled to a more compact
+    /// representation (their entries only, without unused buffer capacity), and are added back to the bucket when the
+    /// workers run out of work. This reduces the memory used by tracing for pathological object graphs, but does not
+    /// bound it, as the spilled entries are still kept in memory. 0 means no limit.
+    tracing_queue_budget: usize                  [env_var: true, command_line: true] [always_valid] = 0,
+    /// The order in which GC workers process the tracing work packets they create. See `TracingOrder` for the
+    /// trade-offs.
+    tracing_order:         TracingOrder         [env_var: true, command_line: true] [always_valid] = TracingOrder::BreadthFirst,
+    /// Copy objects in a hierarchical order. When a GC worker scans an object, it traces the slots of the object
+    /// immediately, and scans the objects they point to next, so that copying plans (e.g. `SemiSpace`, `GenCopy`
+    /// and defrag GCs of `Immix`) place objects close to their parents and siblings in the to-space. This improves
+    /// the locality of the mutators after a GC, at the cost of less parallelism within a work packet.
+    hierarchical_copying:  bool                 [env_var: true, command_line: true] [always_valid] = false,
+    /// Let each GC worker skip the slots it has visited recently in the same GC, using a small per-worker filter. This
+    /// reduces redundant `trace_object` calls for plans that may trace a slot more than once (`MarkSweep`,
+    /// `StickyImmix` and generational plans with the object barrier), and has no effect for other plans. The numbers
+    /// of lookups and hits of the filters are reported as the counters `slotFilter.lookups` and `slotFilter.hits`.
+    deduplicate_slots:     bool                 [env_var: true, command_line: true] [always_valid] = false,
+    /// Record the ranges of memory moved by each GC, so that the binding can take them with
+    /// `memory_manager::take_relocations` after the GC to fix its external data structures in bulk. Only the objects
+    /// copied by GC workers are recorded, which excludes the objects moved by `MarkCompact`.
+    record_relocations:    bool                 [env_var: true, command_line: true] [always_valid] = false
 }
 
 #[cfg(test)]
@@ -1276,4 +1658,66 @@ mod tests {
             assert_eq!(*options.threads, threads);
         })
     }
+
+    #[test]
+    fn test_numa_policy() {
+        serial_test(|| {
+            assert_eq!("FirstTouch".parse(), Ok(NumaPolicy::FirstTouch));
+            assert_eq!("Interleave".parse(), Ok(NumaPolicy::Interleave));
+            assert_eq!("Bind:1".parse(), Ok(NumaPolicy::Bind(1)));
+            assert!("Bind".parse::<NumaPolicy>().is_err());
+            assert!("Bind:a".parse::<NumaPolicy>().is_err());
+            assert!("Interleave:0".parse::<NumaPolicy>().is_err());
+        })
+    }
+
+    #[test]
+    fn test_numa_space_policies() {
+        serial_test(|| {
+            let policies = "nursery=Bind:0,immix=Interleave"
+                .parse::<NumaSpacePolicies>()
+                .unwrap();
+            assert_eq!(policies.get("nursery"), Some(NumaPolicy::Bind(0)));
+            assert_eq!(policies.get("immix"), Some(NumaPolicy::Interleave));
+            assert_eq!(policies.get("los"), None);
+
+            assert_eq!(
+                "".parse::<NumaSpacePolicies>(),
+                Ok(NumaSpacePolicies::default())
+            );
+            assert!("nursery".parse::<NumaSpacePolicies>().is_err());
+            assert!("nursery=Bind".parse::<NumaSpacePolicies>().is_err());
+        })
+    }
+
+    #[test]
+    fn test_huge_page_spaces() {
+        serial_test(|| {
+            let spaces = "immix=HugeTLB,los=No".parse::<HugePageSpaces>().unwrap();
+            assert_eq!(spaces.get("immix"), Some(HugePageSupport::HugeTLB));
+            assert_eq!(spaces.get("los"), Some(HugePageSupport::No));
+            assert_eq!(spaces.get("nursery"), None);
+            assert!("immix=Huge".parse::<HugePageSpaces>().is_err());
+
+            let mut options = Options::default();
+            assert_eq!(options.huge_page_support("immix"), HugePageSupport::No);
+            if cfg!(target_os = "linux") {
+                assert!(options.set_from_string("transparent_hugepages", "true"));
+                assert!(options.set_from_string("huge_page_spaces", "immix=HugeTLB,los=No"));
+                assert_eq!(options.huge_page_support("immix"), HugePageSupport::HugeTLB);
+                assert_eq!(options.huge_page_support("los"), HugePageSupport::No);
+                assert_eq!(
+                    options.huge_page_support("nursery"),
+                    HugePageSupport::TransparentHugePages
+                );
+
+                // Explicit huge pages are not used if the pages may be protected.
+                assert!(options.set_from_string("protect_free_blocks", "true"));
+                assert_eq!(
+                    options.huge_page_support("immix"),
+                    HugePageSupport::TransparentHugePages
+                );
+            }
+        })
+    }
 }
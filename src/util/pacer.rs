@@ -0,0 +1,231 @@
+//! Pacing concurrent collection against allocation.
+//!
+//! A concurrent collector marks the heap while mutators keep allocating.  If the mutators allocate
+//! faster than the collector marks, the heap fills up before marking finishes.  A [`Pacer`] tracks
+//! the marking progress of a concurrent cycle against the allocation of the mutators, and tells
+//! the plan how to escalate when the mutators are outrunning the collector.
+//!
+//! When a cycle starts, the plan estimates the marking work (e.g. the live bytes of the last GC)
+//! and the headroom (the bytes that mutators may allocate before the heap is full).  GC workers
+//! report the bytes they mark, and mutators report the bytes they allocate.  The pacer compares
+//! the time needed to finish marking at the current marking rate with the time until the headroom
+//! runs out at the current allocation rate (see [`crate::MMTK::get_allocation_rate`]).
+//!
+//! The pacer is owned by the [`GCTrigger`].  A concurrent plan starts and ends its cycles with
+//! `GCTrigger::start_concurrent_cycle` and `GCTrigger::end_concurrent_cycle`, and reports marking
+//! progress with `GCTrigger::record_marked`.  Allocation is reported by the allocation slow path.
+//! The GC trigger finishes a cycle early by requesting a GC when the pacer decides
+//! [`PacingAction::FinishInPause`], and the plan acts on the other actions returned by
+//! `GCTrigger::pacing_action`.
+//!
+//! [`GCTrigger`]: crate::util::heap::gc_trigger::GCTrigger
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// What the plan should do to keep up with the mutators.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PacingAction {
+    /// The collector is keeping up with the mutators.
+    None,
+    /// The collector is falling behind.  Give the concurrent GC workers more time, for example, by
+    /// running more of them.
+    MoreWorkerTime,
+    /// The mutators are outrunning the collector even if the workers get more time.  Each mutator
+    /// should do `assist_ratio` bytes of marking work for each byte it allocates.
+    MutatorAssist { assist_ratio: f64 },
+    /// The headroom will run out long before marking finishes.  Stop the mutators and finish the
+    /// marking in a pause.
+    FinishInPause,
+}
+
+/// The estimates made when a cycle starts.
+struct Cycle {
+    start: Instant,
+    /// The estimated bytes to mark in the cycle.
+    work: usize,
+    /// The bytes that mutators may allocate before the heap is full.
+    headroom: usize,
+}
+
+/// Paces a concurrent collection cycle.  See the module documentation.
+#[derive(Default)]
+pub(crate) struct Pacer {
+    cycle: Mutex<Option<Cycle>>,
+    /// The bytes marked in the current cycle.
+    marked: AtomicUsize,
+    /// The bytes allocated by mutators in the current cycle.
+    allocated: AtomicUsize,
+}
+
+impl Pacer {
+    /// Escalate if the headroom lasts less than this many times the time to finish marking.
+    const MORE_WORKER_TIME_SLACK: f64 = 1.5;
+    /// Ask mutators to assist if the headroom runs out before marking finishes.
+    const MUTATOR_ASSIST_SLACK: f64 = 1.0;
+    /// Finish in a pause if the headroom lasts less than this fraction of the time to finish
+    /// marking.  Mutator assists cannot catch up from there.
+    const FINISH_IN_PAUSE_SLACK: f64 = 0.25;
+
+    /// Start a concurrent cycle with the estimated marking work and the allocation headroom, both
+    /// in bytes.
+    pub fn start_cycle(&self, work: usize, headroom: usize) {
+        self.marked.store(0, Ordering::Relaxed);
+        self.allocated.store(0, Ordering::Relaxed);
+        *self.cycle.lock().unwrap() = Some(Cycle {
+            start: Instant::now(),
+            work,
+            headroom,
+        });
+    }
+
+    /// Finish the current cycle, and return the bytes marked in it.
+    pub fn end_cycle(&self) -> usize {
+        *self.cycle.lock().unwrap() = None;
+        self.marked.load(Ordering::Relaxed)
+    }
+
+    /// Return `true` if a cycle is in progress.
+    pub fn is_in_cycle(&self) -> bool {
+        self.cycle.lock().unwrap().is_some()
+    }
+
+    /// Called by GC workers (or assisting mutators) after marking objects of `bytes` bytes.
+    pub fn record_marked(&self, bytes: usize) {
+        self.marked.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// Called by mutators after allocating `bytes` bytes during the cycle.
+    pub fn record_allocation(&self, bytes: usize) {
+        self.allocated.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// Decide how to keep up with the mutators.  `allocation_rate` is the recent allocation rate in
+    /// bytes per millisecond, or 0 if unknown, in which case the allocation rate in the current
+    /// cycle is used.
+    pub fn evaluate(&self, allocation_rate: f64) -> PacingAction {
+        self.evaluate_at(Instant::now(), allocation_rate)
+    }
+
+    fn evaluate_at(&self, now: Instant, allocation_rate: f64) -> PacingAction {
+        let cycle = self.cycle.lock().unwrap();
+        let Some(cycle) = cycle.as_ref() else {
+            return PacingAction::None;
+        };
+        let marked = self.marked.load(Ordering::Relaxed);
+        let allocated = self.allocated.load(Ordering::Relaxed);
+
+        let remaining_work = cycle.work.saturating_sub(marked);
+        let remaining_headroom = cycle.headroom.saturating_sub(allocated);
+        if remaining_work == 0 {
+            return PacingAction::None;
+        }
+        if remaining_headroom == 0 {
+            return PacingAction::FinishInPause;
+        }
+
+        let elapsed_ms = now.saturating_duration_since(cycle.start).as_secs_f64() * 1000.0;
+        let allocation_rate = if allocation_rate > 0.0 {
+            allocation_rate
+        } else {
+            allocated as f64 / elapsed_ms
+        };
+        let marking_rate = marked as f64 / elapsed_ms;
+        if !(allocation_rate > 0.0 && marking_rate > 0.0) {
+            // Not enough data to predict.  Compare the fractions of the work done and the headroom
+            // used, instead.
+            let work_done = marked as f64 / cycle.work as f64;
+            let headroom_used = allocated as f64 / cycle.headroom as f64;
+            return if headroom_used > work_done {
+                PacingAction::MoreWorkerTime
+            } else {
+                PacingAction::None
+            };
+        }
+
+        let time_to_finish = remaining_work as f64 / marking_rate;
+        let time_to_exhaust = remaining_headroom as f64 / allocation_rate;
+        let slack = time_to_exhaust / time_to_finish;
+        trace!(
+            "Pacer: marked {}/{} bytes, allocated {}/{} bytes, slack {:.2}",
+            marked,
+            cycle.work,
+            allocated,
+            cycle.headroom,
+            slack
+        );
+        if slack >= Self::MORE_WORKER_TIME_SLACK {
+            PacingAction::None
+        } else if slack >= Self::MUTATOR_ASSIST_SLACK {
+            PacingAction::MoreWorkerTime
+        } else if slack >= Self::FINISH_IN_PAUSE_SLACK {
+            PacingAction::MutatorAssist {
+                assist_ratio: remaining_work as f64 / remaining_headroom as f64,
+            }
+        } else {
+            PacingAction::FinishInPause
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    const MB: usize = 1 << 20;
+
+    fn pacer_after(ms: u64, marked: usize, allocated: usize) -> (Pacer, Instant) {
+        let pacer = Pacer::default();
+        pacer.start_cycle(100 * MB, 100 * MB);
+        pacer.record_marked(marked);
+        pacer.record_allocation(allocated);
+        let start = pacer.cycle.lock().unwrap().as_ref().unwrap().start;
+        (pacer, start + Duration::from_millis(ms))
+    }
+
+    #[test]
+    fn escalate() {
+        // Marking is twice as fast as allocation.
+        let (pacer, now) = pacer_after(100, 20 * MB, 10 * MB);
+        assert_eq!(pacer.evaluate_at(now, 0.0), PacingAction::None);
+
+        // A burst of allocation.  The headroom lasts a bit longer than the marking.
+        let rate = (MB / 5) as f64;
+        assert_eq!(pacer.evaluate_at(now, rate), PacingAction::MoreWorkerTime);
+
+        // The headroom runs out before marking finishes.
+        let rate = (MB * 9 / 20) as f64;
+        assert_eq!(
+            pacer.evaluate_at(now, rate),
+            PacingAction::MutatorAssist {
+                assist_ratio: 80.0 / 90.0
+            }
+        );
+
+        // Mutator assists cannot catch up.
+        let rate = (2 * MB) as f64;
+        assert_eq!(pacer.evaluate_at(now, rate), PacingAction::FinishInPause);
+    }
+
+    #[test]
+    fn edge_cases() {
+        // The headroom is used up.
+        let (pacer, now) = pacer_after(100, 20 * MB, 100 * MB);
+        assert_eq!(pacer.evaluate_at(now, 0.0), PacingAction::FinishInPause);
+
+        // The marking is finished.
+        let (pacer, now) = pacer_after(100, 100 * MB, 100 * MB);
+        assert_eq!(pacer.evaluate_at(now, 0.0), PacingAction::None);
+
+        // Nothing is marked, yet.
+        let (pacer, now) = pacer_after(100, 0, 10 * MB);
+        assert_eq!(pacer.evaluate_at(now, 0.0), PacingAction::MoreWorkerTime);
+
+        // Not in a cycle.
+        assert_eq!(pacer.end_cycle(), 0);
+        assert!(!pacer.is_in_cycle());
+        assert_eq!(pacer.evaluate_at(now, 0.0), PacingAction::None);
+    }
+}
@@ -0,0 +1,137 @@
+//! Batches of transitively pinning roots that are kept for a number of GCs.
+//!
+//! A binding hands a batch of objects to
+//! [`crate::vm::RootsWorkFactory::create_process_tpinning_roots_batch_work`] while scanning roots,
+//! together with the number of GCs the batch lasts.  The objects are transitively pinning roots in
+//! the current GC.  If the batch lasts more than one GC, MMTk keeps it, and reports the objects as
+//! transitively pinning roots again at the start of each following GC, until the batch expires at
+//! the end of its last GC.  The objects are not moved while the batch lasts, so the batch does not
+//! need to be updated.
+//!
+//! MMTk also counts the pinned roots in each GC, and the bytes of those that are in spaces that
+//! may move objects, i.e. the memory that the GC could have defragmented if the roots were not
+//! pinned.  Objects reachable from the pinned roots, which are also pinned, are not counted.
+
+use crate::util::statistics::counter::EventCounter;
+use crate::util::statistics::stats::Stats;
+use crate::util::ObjectReference;
+use crate::vm::{ObjectModel, VMBinding};
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+
+/// The pinned root batches in a GC.  See [`crate::MMTK::last_gc_pinned_roots_stats`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PinnedRootsStats {
+    /// The number of batches that pinned roots in the GC, including those kept from previous GCs.
+    pub batches: usize,
+    /// The number of objects in the batches.
+    pub objects: usize,
+    /// The bytes of the objects in the batches that are in spaces that may move objects.
+    pub movable_bytes: usize,
+}
+
+struct PinnedRootBatch {
+    nodes: Vec<ObjectReference>,
+    /// The number of GCs after the current GC that the batch still lasts.
+    remaining_gcs: usize,
+}
+
+#[derive(Default)]
+struct PinnedRootBatchesInner {
+    /// The batches kept from previous GCs.
+    kept: Vec<PinnedRootBatch>,
+    /// The batches added in the current GC that last more GCs.  They are kept at the end of the
+    /// current GC, so that they are not reported twice in the current GC.
+    added: Vec<PinnedRootBatch>,
+    /// The statistics of the current GC.
+    current: PinnedRootsStats,
+    /// The statistics of the last finished GC.
+    last_gc: PinnedRootsStats,
+}
+
+/// The pinned root batches of an MMTk instance.
+pub(crate) struct PinnedRootBatches<VM: VMBinding> {
+    inner: Mutex<PinnedRootBatchesInner>,
+    objects_counter: Arc<Mutex<EventCounter>>,
+    movable_bytes_counter: Arc<Mutex<EventCounter>>,
+    phantom: PhantomData<VM>,
+}
+
+impl<VM: VMBinding> PinnedRootBatches<VM> {
+    pub fn new(stats: &Stats) -> Self {
+        Self {
+            inner: Mutex::default(),
+            objects_counter: stats.new_event_counter("pinnedRoots.objects", true, true),
+            movable_bytes_counter: stats.new_event_counter("pinnedRoots.movableBytes", true, true),
+            phantom: PhantomData,
+        }
+    }
+
+    fn account(stats: &mut PinnedRootsStats, nodes: &[ObjectReference]) {
+        stats.batches += 1;
+        stats.objects += nodes.len();
+        stats.movable_bytes += nodes
+            .iter()
+            .filter(|object| object.is_movable())
+            .map(|object| VM::VMObjectModel::get_current_size(*object))
+            .sum::<usize>();
+    }
+
+    /// Add a batch handed by the binding in the current GC, which lasts `gcs` GCs including the
+    /// current GC.
+    pub fn add(&self, nodes: &[ObjectReference], gcs: usize) {
+        assert!(gcs > 0, "A pinned root batch must last at least one GC");
+        let mut inner = self.inner.lock().unwrap();
+        Self::account(&mut inner.current, nodes);
+        if gcs > 1 {
+            inner.added.push(PinnedRootBatch {
+                nodes: nodes.to_vec(),
+                remaining_gcs: gcs - 1,
+            });
+        }
+    }
+
+    /// Call `f` with the objects of each batch kept from previous GCs.
+    pub fn for_each_kept_batch(&self, mut f: impl FnMut(&[ObjectReference])) {
+        let mut inner = self.inner.lock().unwrap();
+        let PinnedRootBatchesInner { kept, current, .. } = &mut *inner;
+        for batch in kept.iter() {
+            Self::account(current, &batch.nodes);
+            f(&batch.nodes);
+        }
+    }
+
+    /// Called when all GC work is finished.  Drop the batches whose last GC is the current GC, and
+    /// keep the batches added in the current GC.
+    pub fn on_gc_end(&self) -> PinnedRootsStats {
+        let mut inner = self.inner.lock().unwrap();
+        let PinnedRootBatchesInner {
+            kept,
+            added,
+            current,
+            last_gc,
+        } = &mut *inner;
+        for batch in kept.iter_mut() {
+            batch.remaining_gcs -= 1;
+        }
+        kept.retain(|batch| batch.remaining_gcs > 0);
+        kept.append(added);
+
+        let stats = std::mem::take(current);
+        self.objects_counter
+            .lock()
+            .unwrap()
+            .inc_by(stats.objects as u64);
+        self.movable_bytes_counter
+            .lock()
+            .unwrap()
+            .inc_by(stats.movable_bytes as u64);
+        *last_gc = stats;
+        stats
+    }
+
+    /// Get the statistics of the last finished GC.
+    pub fn last_gc(&self) -> PinnedRootsStats {
+        self.inner.lock().unwrap().last_gc
+    }
+}
@@ -7,6 +7,7 @@ use std::vec::Vec;
 use crate::plan::is_nursery_gc;
 use crate::scheduler::ProcessEdgesWork;
 use crate::scheduler::WorkBucketStage;
+use crate::util::weak_processing_stats::ReferenceStats;
 use crate::util::ObjectReference;
 use crate::util::VMWorkerThread;
 use crate::vm::ReferenceGlue;
@@ -55,11 +56,16 @@ impl ReferenceProcessors {
 
     /// This will invoke enqueue for each reference processor, which will
     /// call back to the VM to enqueue references whose referents are cleared
-    /// in this GC.
-    pub fn enqueue_refs<VM: VMBinding>(&self, tls: VMWorkerThread) {
-        self.soft.enqueue::<VM>(tls);
-        self.weak.enqueue::<VM>(tls);
-        self.phantom.enqueue::<VM>(tls);
+    /// in this GC.  Return the statistics of soft, weak and phantom references in this GC.
+    pub fn enqueue_refs<VM: VMBinding>(
+        &self,
+        tls: VMWorkerThread,
+    ) -> (ReferenceStats, ReferenceStats, ReferenceStats) {
+        (
+            self.soft.enqueue::<VM>(tls),
+            self.weak.enqueue::<VM>(tls),
+            self.phantom.enqueue::<VM>(tls),
+        )
     }
 
     /// A separate reference forwarding step. Normally when we scan refs, we deal with forwarding.
@@ -167,6 +173,13 @@ struct ReferenceProcessorSync {
 
     /// Index into the references table for the start of nursery objects
     nursery_index: usize,
+
+    /// The number of references removed from the table in this GC, either because the reference is
+    /// dead, or because the referent is cleared.
+    removed: usize,
+
+    /// The statistics of this GC.  `candidates` is computed when the references are enqueued.
+    stats: ReferenceStats,
 }
 
 impl ReferenceProcessor {
@@ -176,6 +189,8 @@ impl ReferenceProcessor {
                 references: HashSet::with_capacity(INITIAL_SIZE),
                 enqueued_references: vec![],
                 nursery_index: 0,
+                removed: 0,
+                stats: ReferenceStats::default(),
             }),
             semantics,
             allow_new_candidate: AtomicBool::new(true),
@@ -251,7 +266,8 @@ impl ReferenceProcessor {
     }
 
     /// Inform the binding to enqueue the weak references whose referents were cleared in this GC.
-    pub fn enqueue<VM: VMBinding>(&self, tls: VMWorkerThread) {
+    /// Return the statistics of this GC.
+    pub fn enqueue<VM: VMBinding>(&self, tls: VMWorkerThread) -> ReferenceStats {
         let mut sync = self.sync.lock().unwrap();
 
         // This is the end of a GC. We do some assertions here to make sure our reference tables are correct.
@@ -283,7 +299,12 @@ impl ReferenceProcessor {
             sync.enqueued_references.clear();
         }
 
+        // Each reference processed in this GC is either removed from the table or still in it.
+        let mut stats = std::mem::take(&mut sync.stats);
+        stats.candidates = std::mem::take(&mut sync.removed) + sync.references.len();
+
         self.allow_new_candidate();
+        stats
     }
 
     /// Forward the reference tables in the reference processor. This is only needed if a plan does not forward
@@ -378,6 +399,9 @@ impl ReferenceProcessor {
             new_set.len(),
             enqueued_references.len()
         );
+        let removed = sync.references.len() - new_set.len();
+        sync.removed += removed;
+        sync.stats.enqueued += enqueued_references.len();
         sync.references = new_set;
         sync.enqueued_references.extend(enqueued_references);
 
@@ -391,7 +415,7 @@ impl ReferenceProcessor {
     fn retain<E: ProcessEdgesWork>(&self, trace: &mut E, _nursery: bool) {
         debug_assert!(self.semantics == Semantics::SOFT);
 
-        let sync = self.sync.lock().unwrap();
+        let mut sync = self.sync.lock().unwrap();
 
         debug!("Starting ReferenceProcessor.retain({:?})", self.semantics);
         trace!(
@@ -400,6 +424,7 @@ impl ReferenceProcessor {
             sync.references
         );
 
+        let mut retained = 0;
         for reference in sync.references.iter() {
             trace!("Processing reference: {:?}", reference);
 
@@ -413,8 +438,10 @@ impl ReferenceProcessor {
             {
                 Self::keep_referent_alive(trace, referent);
                 trace!(" ~> {:?} (retained)", referent);
+                retained += 1;
             }
         }
+        sync.stats.retained += retained;
 
         debug!("Ending ReferenceProcessor.retain({:?})", self.semantics);
     }
@@ -586,7 +613,9 @@ impl<E: ProcessEdgesWork> RefForwarding<E> {
 pub(crate) struct RefEnqueue<VM: VMBinding>(PhantomData<VM>);
 impl<VM: VMBinding> GCWork<VM> for RefEnqueue<VM> {
     fn do_work(&mut self, worker: &mut GCWorker<VM>, mmtk: &'static MMTK<VM>) {
-        mmtk.reference_processors.enqueue_refs::<VM>(worker.tls);
+        let (soft, weak, phantom) = mmtk.reference_processors.enqueue_refs::<VM>(worker.tls);
+        mmtk.weak_processing_accounting
+            .on_references_enqueued(soft, weak, phantom);
     }
 }
 impl<VM: VMBinding> RefEnqueue<VM> {
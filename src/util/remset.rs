@@ -0,0 +1,279 @@
+//! Remembered sets for plans that collect a subset of the heap.
+//!
+//! When a plan collects only part of the heap (the nursery, a set of regions, or an increment of
+//! the mature space), it needs to know the pointers from the rest of the heap into the collected
+//! part.  A [`RemSet`] remembers such entries, e.g. the objects or slots that may contain those
+//! pointers, and hands them out in chunks to be scanned by work packets during the GC.
+//!
+//! Entries are buffered before they are added to the remembered set, so that inserting an entry
+//! does not need synchronization.  Mutators (e.g. in write barriers) keep their own
+//! [`RemSetBuffer`], and GC workers (e.g. when they copy objects) use the per-worker buffers in the
+//! remembered set with [`RemSet::insert_from_worker`].  The generational barrier uses remembered
+//! sets for its modbufs (see [`crate::plan::generational::barrier::ModBufs`]).
+//!
+//! The statistics of a remembered set ([`RemSetStats`]) are reported with the event counters of
+//! MMTk's statistics, so that they show up in the harness output.
+
+use crate::scheduler::{current_worker_ordinal, GCWork};
+use crate::util::statistics::counter::EventCounter;
+use crate::util::statistics::stats::Stats;
+use crate::vm::VMBinding;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// The statistics of a remembered set.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RemSetStats {
+    /// The number of entries added to the remembered set.
+    pub inserted: usize,
+    /// The number of entries handed out for scanning.
+    pub scanned: usize,
+    /// The number of scanning work packets created.
+    pub scan_packets: usize,
+}
+
+/// A buffer of entries to be added to a [`RemSet`].  The owner of the buffer (usually a mutator)
+/// inserts entries without synchronization, and the buffer is flushed to the remembered set when
+/// it is full, or when [`RemSetBuffer::flush`] is called.
+pub struct RemSetBuffer<T> {
+    entries: Vec<T>,
+}
+
+impl<T> Default for RemSetBuffer<T> {
+    fn default() -> Self {
+        Self { entries: vec![] }
+    }
+}
+
+impl<T: Send> RemSetBuffer<T> {
+    /// Add an entry, and flush the buffer to `remset` if the buffer is full.
+    pub fn insert(&mut self, remset: &RemSet<T>, entry: T) {
+        if self.entries.is_empty() {
+            self.entries.reserve(remset.chunk_size);
+        }
+        self.entries.push(entry);
+        if self.entries.len() >= remset.chunk_size {
+            self.flush(remset);
+        }
+    }
+
+    /// Add the buffered entries to `remset`.
+    pub fn flush(&mut self, remset: &RemSet<T>) {
+        if !self.entries.is_empty() {
+            remset.add_chunk(std::mem::take(&mut self.entries));
+        }
+    }
+
+    /// Return `true` if the buffer has no entry.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// A remembered set.  See the module documentation.
+pub struct RemSet<T> {
+    /// Chunks of entries added from buffers.
+    chunks: Mutex<Vec<Vec<T>>>,
+    /// The number of entries in `chunks`.
+    len: AtomicUsize,
+    /// The buffers for GC workers, indexed by the worker ordinals.  Each is only accessed by its
+    /// worker, except when flushed.
+    worker_buffers: Vec<Mutex<RemSetBuffer<T>>>,
+    /// The number of entries in a buffer before it is flushed, and the number of entries each
+    /// scanning work packet scans.
+    chunk_size: usize,
+    inserted: AtomicUsize,
+    scanned: AtomicUsize,
+    scan_packets: AtomicUsize,
+}
+
+impl<T: Send> RemSet<T> {
+    /// The default number of entries in a chunk.
+    pub const DEFAULT_CHUNK_SIZE: usize = 4096;
+
+    /// Create a remembered set for `num_workers` GC workers, scanned in chunks of `chunk_size`
+    /// entries.
+    pub fn new(num_workers: usize, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0);
+        Self {
+            chunks: Mutex::new(vec![]),
+            len: AtomicUsize::new(0),
+            worker_buffers: (0..num_workers).map(|_| Default::default()).collect(),
+            chunk_size,
+            inserted: AtomicUsize::new(0),
+            scanned: AtomicUsize::new(0),
+            scan_packets: AtomicUsize::new(0),
+        }
+    }
+
+    /// Add a chunk of entries.  Usually called when a [`RemSetBuffer`] is flushed.
+    pub fn add_chunk(&self, chunk: Vec<T>) {
+        if chunk.is_empty() {
+            return;
+        }
+        self.len.fetch_add(chunk.len(), Ordering::Relaxed);
+        self.inserted.fetch_add(chunk.len(), Ordering::Relaxed);
+        self.chunks.lock().unwrap().push(chunk);
+    }
+
+    /// Add an entry from a GC worker thread.  The entry is added to the buffer of the worker, and
+    /// is not visible until the buffer is full or [`RemSet::flush_worker_buffers`] is called.
+    pub fn insert_from_worker(&self, entry: T) {
+        let mut buffer = self.worker_buffers[current_worker_ordinal()]
+            .lock()
+            .unwrap();
+        buffer.insert(self, entry);
+    }
+
+    /// Flush the buffers of all GC workers.  Call this when no GC worker inserts entries, e.g. in
+    /// a later stage of the GC.
+    pub fn flush_worker_buffers(&self) {
+        for buffer in self.worker_buffers.iter() {
+            buffer.lock().unwrap().flush(self);
+        }
+    }
+
+    /// Get the number of entries in the remembered set, excluding those still in buffers.
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Relaxed)
+    }
+
+    /// Return `true` if the remembered set has no entry, excluding those still in buffers.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Take all entries out of the remembered set, in chunks of at most `chunk_size` entries.
+    /// Small chunks (from buffers flushed before they are full) are merged.
+    pub fn take_chunks(&self) -> Vec<Vec<T>> {
+        let chunks = std::mem::take(&mut *self.chunks.lock().unwrap());
+        self.len.store(0, Ordering::Relaxed);
+
+        let mut result: Vec<Vec<T>> = vec![];
+        let mut partial: Vec<T> = vec![];
+        for mut chunk in chunks {
+            if chunk.len() >= self.chunk_size {
+                result.push(chunk);
+                continue;
+            }
+            let room = self.chunk_size - partial.len();
+            if chunk.len() > room {
+                partial.extend(chunk.drain(..room));
+                result.push(std::mem::replace(&mut partial, chunk));
+            } else {
+                partial.append(&mut chunk);
+            }
+            if partial.len() == self.chunk_size {
+                result.push(std::mem::take(&mut partial));
+            }
+        }
+        if !partial.is_empty() {
+            result.push(partial);
+        }
+        result
+    }
+
+    /// Take all entries out of the remembered set, and create a work packet to scan each chunk of
+    /// entries with `func`.
+    pub fn generate_scan_packets<VM: VMBinding>(
+        &self,
+        func: impl Fn(Vec<T>) -> Box<dyn GCWork<VM>>,
+    ) -> Vec<Box<dyn GCWork<VM>>> {
+        let chunks = self.take_chunks();
+        let entries: usize = chunks.iter().map(|chunk| chunk.len()).sum();
+        self.scanned.fetch_add(entries, Ordering::Relaxed);
+        self.scan_packets.fetch_add(chunks.len(), Ordering::Relaxed);
+        chunks.into_iter().map(func).collect()
+    }
+
+    /// Get the statistics since the remembered set was created or the statistics were last reset.
+    pub fn stats(&self) -> RemSetStats {
+        RemSetStats {
+            inserted: self.inserted.load(Ordering::Relaxed),
+            scanned: self.scanned.load(Ordering::Relaxed),
+            scan_packets: self.scan_packets.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Reset the statistics, and return the statistics since the remembered set was created or the
+    /// statistics were last reset.
+    pub fn reset_stats(&self) -> RemSetStats {
+        RemSetStats {
+            inserted: self.inserted.swap(0, Ordering::Relaxed),
+            scanned: self.scanned.swap(0, Ordering::Relaxed),
+            scan_packets: self.scan_packets.swap(0, Ordering::Relaxed),
+        }
+    }
+}
+
+/// The event counters that report the statistics of a remembered set to MMTk's statistics.
+pub(crate) struct RemSetCounters {
+    inserted: Arc<Mutex<EventCounter>>,
+    scanned: Arc<Mutex<EventCounter>>,
+    scan_packets: Arc<Mutex<EventCounter>>,
+}
+
+impl RemSetCounters {
+    /// Create the counters `<name>.inserted`, `<name>.scanned` and `<name>.scanPackets`.
+    pub fn new(stats: &Stats, name: &str) -> Self {
+        Self {
+            inserted: stats.new_event_counter(&format!("{}.inserted", name), true, true),
+            scanned: stats.new_event_counter(&format!("{}.scanned", name), true, true),
+            scan_packets: stats.new_event_counter(&format!("{}.scanPackets", name), true, true),
+        }
+    }
+
+    /// Reset the statistics of `remset`, and add them to the counters.
+    pub fn report<T: Send>(&self, remset: &RemSet<T>) {
+        let stats = remset.reset_stats();
+        self.inserted.lock().unwrap().inc_by(stats.inserted as u64);
+        self.scanned.lock().unwrap().inc_by(stats.scanned as u64);
+        self.scan_packets
+            .lock()
+            .unwrap()
+            .inc_by(stats.scan_packets as u64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buffered_insertion() {
+        let remset = RemSet::<usize>::new(1, 4);
+        let mut buffer = RemSetBuffer::default();
+        for i in 0..6 {
+            buffer.insert(&remset, i);
+        }
+        // The first 4 entries are flushed when the buffer is full.
+        assert_eq!(remset.len(), 4);
+        assert!(!buffer.is_empty());
+        buffer.flush(&remset);
+        assert!(buffer.is_empty());
+        assert_eq!(remset.len(), 6);
+        assert_eq!(remset.stats().inserted, 6);
+        assert_eq!(remset.reset_stats().inserted, 6);
+        assert_eq!(remset.stats().inserted, 0);
+        assert_eq!(remset.take_chunks(), vec![vec![0, 1, 2, 3], vec![4, 5]]);
+        assert!(remset.is_empty());
+    }
+
+    #[test]
+    fn merge_small_chunks() {
+        let remset = RemSet::<usize>::new(1, 4);
+        remset.add_chunk(vec![0, 1]);
+        remset.add_chunk(vec![2, 3, 4, 5]);
+        remset.add_chunk(vec![6, 7, 8]);
+        remset.add_chunk(vec![9]);
+        remset.add_chunk(vec![]);
+
+        let chunks = remset.take_chunks();
+        assert!(remset.is_empty());
+        assert!(chunks.iter().all(|chunk| chunk.len() <= 4));
+        assert_eq!(chunks.len(), 3);
+        let mut entries: Vec<usize> = chunks.into_iter().flatten().collect();
+        entries.sort();
+        assert_eq!(entries, (0..10).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,74 @@
+//! Detecting references to objects that a GC has already decided to be dead.
+//!
+//! After the transitive closure following strong references, an object that is not marked (or
+//! forwarded) is dead unless the binding resurrects it by tracing it with an `ObjectTracer`, for
+//! example, when handling finalizers or weak references.  If the binding stores a reference to a
+//! dead object into a slot without tracing it, the object will be reclaimed while the slot still
+//! points to it.  This usually shows up as memory corruption long after the GC.  The checks in
+//! this module let the binding catch such bugs at the slot where the reference is stored.
+
+use crate::mmtk::SFT_MAP;
+use crate::scheduler::WorkBucketStage;
+use crate::util::ObjectReference;
+use crate::vm::slot::Slot;
+use crate::vm::VMBinding;
+use crate::MMTK;
+use enum_map::Enum;
+
+/// Return true if the mark state of objects tells whether they are live.
+///
+/// That is true from the end of the strong closure until the spaces start releasing memory.
+/// MarkCompact clears the mark bits in its second trace, so it is only true until then.
+fn is_mark_state_reliable<VM: VMBinding>(mmtk: &MMTK<VM>) -> bool {
+    if !mmtk.gc_in_progress_proper() {
+        return false;
+    }
+    let stage = mmtk.scheduler.current_stage().into_usize();
+    let end = if mmtk.get_plan().constraints().needs_forward_after_liveness {
+        WorkBucketStage::SecondRoots
+    } else {
+        WorkBucketStage::Release
+    };
+    stage > WorkBucketStage::Closure.into_usize() && stage < end.into_usize()
+}
+
+/// Return true if MMTk knows that `object` is dead.  See
+/// [`crate::memory_manager::is_dead_object`].
+pub(crate) fn is_dead<VM: VMBinding>(mmtk: &MMTK<VM>, object: ObjectReference) -> bool {
+    let sft = SFT_MAP.get_checked(object.to_raw_address());
+    if !sft.is_in_space(object) {
+        // Not an object managed by MMTk.
+        return false;
+    }
+    if is_mark_state_reliable(mmtk) {
+        return !sft.is_live(object);
+    }
+    #[cfg(feature = "vo_bit")]
+    if !mmtk.gc_in_progress() {
+        // Spaces clear the VO bits of dead objects when they reclaim them.
+        return !crate::util::metadata::vo_bit::is_vo_bit_set(object);
+    }
+    false
+}
+
+/// Check the referent of `slot`.  Return the reason if the slot refers to a dead object, or to
+/// the old copy of an object that has been moved in the current GC.
+pub(crate) fn check_slot<VM: VMBinding>(mmtk: &MMTK<VM>, slot: VM::VMSlot) -> Option<&'static str> {
+    let referent = slot.load()?;
+    if is_dead(mmtk, referent) {
+        return Some("the referent is dead");
+    }
+    if mmtk.gc_in_progress_proper() {
+        let sft = SFT_MAP.get_checked(referent.to_raw_address());
+        if sft.is_in_space(referent) {
+            if let Some(forwarded) = sft.get_forwarded_object(referent) {
+                if forwarded != referent {
+                    return Some(
+                        "the referent has been moved, and the slot refers to its old copy",
+                    );
+                }
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,122 @@
+//! Client annotations for memory checkers (AddressSanitizer and Valgrind Memcheck).
+//!
+//! MMTk manages the heap memory itself, so memory checkers cannot tell which parts of the heap
+//! are in use. When the feature "sanitizer" is enabled, MMTk tells the memory checkers the state
+//! of the heap memory through the functions in [`crate::util::memory`]:
+//! *   freed lines, blocks, cells and pages are marked as no-access,
+//! *   freshly allocated memory is marked as undefined (accessible, but not initialized), and
+//! *   side metadata is marked as defined.
+//!
+//! The annotations are no-ops if the program is not running under the memory checkers. We look up
+//! the AddressSanitizer interface at run time, so MMTk does not need to be compiled with the
+//! sanitizer. Valgrind client requests are only implemented for x86_64.
+
+use crate::util::Address;
+
+/// The state of a range of memory, as understood by the memory checkers.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum MemoryState {
+    /// The memory must not be accessed.
+    NoAccess,
+    /// The memory is accessible, but its content is not initialized.
+    Undefined,
+    /// The memory is accessible and initialized.
+    Defined,
+}
+
+/// Annotate a range of memory for all the memory checkers.
+pub(crate) fn annotate(start: Address, len: usize, state: MemoryState) {
+    if len == 0 {
+        return;
+    }
+    asan::annotate(start, len, state);
+    valgrind::annotate(start, len, state);
+}
+
+mod asan {
+    use super::MemoryState;
+    use crate::util::Address;
+
+    type AsanFn = unsafe extern "C" fn(*const libc::c_void, usize);
+
+    /// The AddressSanitizer interface, if the program is running with AddressSanitizer.
+    struct AsanInterface {
+        poison: AsanFn,
+        unpoison: AsanFn,
+    }
+
+    fn lookup(name: &[u8]) -> Option<AsanFn> {
+        let sym = unsafe { libc::dlsym(libc::RTLD_DEFAULT, name.as_ptr() as *const libc::c_char) };
+        if sym.is_null() {
+            None
+        } else {
+            Some(unsafe { std::mem::transmute::<*mut libc::c_void, AsanFn>(sym) })
+        }
+    }
+
+    lazy_static! {
+        static ref ASAN: Option<AsanInterface> = {
+            let poison = lookup(b"__asan_poison_memory_region\0");
+            let unpoison = lookup(b"__asan_unpoison_memory_region\0");
+            match (poison, unpoison) {
+                (Some(poison), Some(unpoison)) => Some(AsanInterface { poison, unpoison }),
+                _ => None,
+            }
+        };
+    }
+
+    pub(super) fn annotate(start: Address, len: usize, state: MemoryState) {
+        if let Some(asan) = ASAN.as_ref() {
+            // AddressSanitizer does not track whether memory is initialized.
+            let f = match state {
+                MemoryState::NoAccess => asan.poison,
+                MemoryState::Undefined | MemoryState::Defined => asan.unpoison,
+            };
+            unsafe { f(start.to_ptr(), len) };
+        }
+    }
+}
+
+mod valgrind {
+    use super::MemoryState;
+    use crate::util::Address;
+
+    /// The base of Memcheck client request codes: `VG_USERREQ_TOOL_BASE('M', 'C')`.
+    const MEMCHECK_BASE: usize = ((b'M' as usize) << 24) | ((b'C' as usize) << 16);
+    const MAKE_MEM_NOACCESS: usize = MEMCHECK_BASE;
+    const MAKE_MEM_UNDEFINED: usize = MEMCHECK_BASE + 1;
+    const MAKE_MEM_DEFINED: usize = MEMCHECK_BASE + 2;
+
+    pub(super) fn annotate(start: Address, len: usize, state: MemoryState) {
+        let request = match state {
+            MemoryState::NoAccess => MAKE_MEM_NOACCESS,
+            MemoryState::Undefined => MAKE_MEM_UNDEFINED,
+            MemoryState::Defined => MAKE_MEM_DEFINED,
+        };
+        client_request(request, start.as_usize(), len);
+    }
+
+    /// Issue a Valgrind client request. This is the same instruction sequence as
+    /// `VALGRIND_DO_CLIENT_REQUEST_EXPR` in `valgrind.h`. It does nothing when the program is not
+    /// running under Valgrind.
+    #[cfg(target_arch = "x86_64")]
+    fn client_request(request: usize, arg1: usize, arg2: usize) {
+        let args: [usize; 6] = [request, arg1, arg2, 0, 0, 0];
+        let mut _result: usize = 0;
+        unsafe {
+            std::arch::asm!(
+                "rol rdi, 3",
+                "rol rdi, 13",
+                "rol rdi, 61",
+                "rol rdi, 51",
+                "xchg rbx, rbx",
+                in("rax") args.as_ptr(),
+                inout("rdx") _result,
+                options(nostack),
+            );
+        }
+    }
+
+    #[cfg(not(target_arch = "x86_64"))]
+    fn client_request(_request: usize, _arg1: usize, _arg2: usize) {}
+}
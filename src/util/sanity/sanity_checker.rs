@@ -171,6 +171,18 @@ impl<VM: VMBinding> DerefMut for SanityGCProcessEdges<VM> {
     }
 }
 
+/// Describe the allocation site of an object for the failure reports.
+#[cfg(feature = "alloc_site")]
+fn alloc_site_of(object: ObjectReference) -> String {
+    crate::util::alloc_site::describe(object)
+}
+
+/// The allocation site is not recorded without the feature "alloc_site".
+#[cfg(not(feature = "alloc_site"))]
+fn alloc_site_of(_object: ObjectReference) -> String {
+    String::new()
+}
+
 impl<VM: VMBinding> ProcessEdgesWork for SanityGCProcessEdges<VM> {
     type VM = VM;
     type ScanObjectsWorkType = ScanObjects<Self>;
@@ -192,20 +204,27 @@ impl<VM: VMBinding> ProcessEdgesWork for SanityGCProcessEdges<VM> {
         let mut sanity_checker = self.mmtk().sanity_checker.lock().unwrap();
         if !sanity_checker.refs.contains(&object) {
             // FIXME steveb consider VM-specific integrity check on reference.
-            assert!(object.is_sane(), "Invalid reference {:?}", object);
+            assert!(
+                object.is_sane(),
+                "Invalid reference {:?}{}",
+                object,
+                alloc_site_of(object)
+            );
 
             // Let plan check object
             assert!(
                 self.mmtk().get_plan().sanity_check_object(object),
-                "Invalid reference {:?}",
-                object
+                "Invalid reference {:?}{}",
+                object,
+                alloc_site_of(object)
             );
 
             // Let VM check object
             assert!(
                 VM::VMObjectModel::is_object_sane(object),
-                "Invalid reference {:?}",
-                object
+                "Invalid reference {:?}{}",
+                object,
+                alloc_site_of(object)
             );
 
             // Object is not "marked"
@@ -218,7 +237,7 @@ impl<VM: VMBinding> ProcessEdgesWork for SanityGCProcessEdges<VM> {
         // bit set when sanity GC starts.
         #[cfg(feature = "vo_bit")]
         if !crate::util::metadata::vo_bit::is_vo_bit_set(object) {
-            panic!("VO bit is not set: {}", object);
+            panic!("VO bit is not set: {}{}", object, alloc_site_of(object));
         }
 
         object
@@ -0,0 +1,277 @@
+//! Shadow-heap verification for copying collectors.
+//!
+//! Before a GC, we make a copy (the shadow) of every object in the heap. After the transitive
+//! closure, we find the new copy of every reachable object, and check that it is identical to its
+//! shadow, except for
+//! *   the reference fields, which must now refer to the forwarded referents (or be cleared), and
+//! *   the header metadata used by MMTk (forwarding bits, mark bits, etc.), which may change in GC.
+//!
+//! This catches bugs in copying and forwarding, such as races between GC workers that copy the same
+//! object, or objects that are copied with the wrong size.
+//!
+//! Limitations:
+//! *   Reference fields are located by comparing the words in the shadow with the addresses of the
+//!     referents. We assume reference fields hold `ObjectReference`s as word-aligned raw addresses.
+//!     Bindings that use compressed pointers or tagged references will see false positives.
+//! *   We do not verify plans that compute forwarding addresses after liveness (i.e. MarkCompact),
+//!     because the forwarding states are only available in the `Compact` stage.
+
+use crate::mmtk::SFT_MAP;
+use crate::scheduler::{GCWork, GCWorker};
+use crate::util::constants::BYTES_IN_WORD;
+use crate::util::metadata::MetadataSpec;
+use crate::util::{Address, ObjectReference, VMWorkerThread};
+use crate::vm::slot::Slot;
+use crate::vm::{ObjectModel, Scanning, VMBinding};
+use crate::MMTK;
+use std::collections::{HashMap, HashSet};
+use std::marker::PhantomData;
+
+/// A copy of an object before GC.
+struct ShadowObject {
+    /// The offset from the object start to the object reference.
+    ref_offset: usize,
+    /// The content of the object, starting from the object start.
+    bytes: Vec<u8>,
+    /// The referents of the object, in the order they are visited by `Scanning`.
+    referents: Vec<Option<ObjectReference>>,
+}
+
+/// The shadow heap, holding a copy of each object at the start of a GC.
+#[derive(Default)]
+pub(crate) struct ShadowHeap {
+    objects: HashMap<ObjectReference, ShadowObject>,
+}
+
+/// Return the referents of an object.
+fn scan_referents<VM: VMBinding>(
+    tls: VMWorkerThread,
+    object: ObjectReference,
+) -> Vec<Option<ObjectReference>> {
+    let mut referents = vec![];
+    if VM::VMScanning::support_slot_enqueuing(tls, object) {
+        VM::VMScanning::scan_object(tls, object, &mut |slot: VM::VMSlot| {
+            referents.push(slot.load());
+        });
+    } else {
+        VM::VMScanning::scan_object_and_trace_edges(
+            tls,
+            object,
+            &mut |referent: ObjectReference| {
+                referents.push(Some(referent));
+                // Do not update the field.
+                referent
+            },
+        );
+    }
+    referents
+}
+
+/// Return the content of an object, starting from the object start.
+fn object_bytes<VM: VMBinding>(object: ObjectReference) -> (Address, Vec<u8>) {
+    let start = object.to_object_start::<VM>();
+    let size = VM::VMObjectModel::get_current_size(object);
+    let bytes = unsafe { std::slice::from_raw_parts(start.to_ptr::<u8>(), size) }.to_vec();
+    (start, bytes)
+}
+
+/// Return the indices of the words (from the object start) that hold header metadata used by MMTk.
+fn header_metadata_words<VM: VMBinding>(
+    object: ObjectReference,
+    object_start: Address,
+) -> HashSet<usize> {
+    let specs = [
+        *VM::VMObjectModel::GLOBAL_LOG_BIT_SPEC.as_spec(),
+        *VM::VMObjectModel::LOCAL_FORWARDING_POINTER_SPEC.as_spec(),
+        *VM::VMObjectModel::LOCAL_FORWARDING_BITS_SPEC.as_spec(),
+        *VM::VMObjectModel::LOCAL_MARK_BIT_SPEC.as_spec(),
+        *VM::VMObjectModel::LOCAL_LOS_MARK_NURSERY_SPEC.as_spec(),
+        #[cfg(feature = "object_pinning")]
+        *VM::VMObjectModel::LOCAL_PINNING_BIT_SPEC.as_spec(),
+    ];
+
+    let mut words = HashSet::new();
+    // Only call `ref_to_header` if any metadata is in the header.
+    if !specs.iter().any(|spec| spec.is_in_header()) {
+        return words;
+    }
+    let header = VM::VMObjectModel::ref_to_header(object);
+    for spec in specs.iter() {
+        if let MetadataSpec::InHeader(spec) = spec {
+            let first_byte = header + spec.bit_offset.div_euclid(8);
+            let last_byte =
+                first_byte + ((spec.bit_offset.rem_euclid(8) as usize + spec.num_of_bits - 1) >> 3);
+            // The metadata may be before the object start.
+            if last_byte < object_start {
+                continue;
+            }
+            let first_word = first_byte.max(object_start) - object_start;
+            let last_word = last_byte - object_start;
+            for word in (first_word / BYTES_IN_WORD)..=(last_word / BYTES_IN_WORD) {
+                words.insert(word);
+            }
+        }
+    }
+    words
+}
+
+/// Return the object that `object` is forwarded to in the current GC, or `object` itself if it is not moved.
+fn forwarded(object: ObjectReference) -> ObjectReference {
+    SFT_MAP
+        .get_checked(object.to_raw_address())
+        .get_forwarded_object(object)
+        .unwrap_or(object)
+}
+
+impl ShadowHeap {
+    /// Make a copy of every object in the heap. The mutators must be stopped, and no object may
+    /// have been moved in the current GC.
+    fn snapshot<VM: VMBinding>(&mut self, mmtk: &'static MMTK<VM>, tls: VMWorkerThread) {
+        self.objects.clear();
+        let mut objects = vec![];
+        mmtk.enumerate_objects(|object| objects.push(object));
+
+        for object in objects {
+            let (start, bytes) = object_bytes::<VM>(object);
+            let shadow = ShadowObject {
+                ref_offset: object.to_raw_address() - start,
+                bytes,
+                referents: scan_referents::<VM>(tls, object),
+            };
+            self.objects.insert(object, shadow);
+        }
+    }
+
+    /// Compare every reachable object with its shadow. Return the number of objects that
+    /// failed the verification. Each failure is reported with `error!`.
+    fn verify<VM: VMBinding>(&mut self, tls: VMWorkerThread) -> usize {
+        let mut failures = 0;
+        for (old, shadow) in self.objects.drain() {
+            // Dead objects are not copied.
+            if !SFT_MAP.get_checked(old.to_raw_address()).is_reachable(old) {
+                continue;
+            }
+            let new = forwarded(old);
+            if let Err(reason) = Self::verify_object::<VM>(tls, old, new, &shadow) {
+                error!(
+                    "Shadow heap verification failed: {} -> {}: {}",
+                    old, new, reason
+                );
+                failures += 1;
+            }
+        }
+        failures
+    }
+
+    fn verify_object<VM: VMBinding>(
+        tls: VMWorkerThread,
+        old: ObjectReference,
+        new: ObjectReference,
+        shadow: &ShadowObject,
+    ) -> Result<(), String> {
+        let (new_start, new_bytes) = object_bytes::<VM>(new);
+        if new.to_raw_address() - new_start != shadow.ref_offset {
+            return Err(format!(
+                "the offset of the object reference changed from {} to {}",
+                shadow.ref_offset,
+                new.to_raw_address() - new_start
+            ));
+        }
+        // The object may grow when copied (e.g. to add a hash field), but it should not shrink.
+        if new_bytes.len() < shadow.bytes.len() {
+            return Err(format!(
+                "the size of the object shrank from {} to {}",
+                shadow.bytes.len(),
+                new_bytes.len()
+            ));
+        }
+
+        // Check the reference fields.
+        let new_referents = scan_referents::<VM>(tls, new);
+        if new_referents.len() != shadow.referents.len() {
+            return Err(format!(
+                "the number of reference fields changed from {} to {}",
+                shadow.referents.len(),
+                new_referents.len()
+            ));
+        }
+        for (i, (old_referent, new_referent)) in shadow
+            .referents
+            .iter()
+            .zip(new_referents.iter())
+            .enumerate()
+        {
+            let expected = old_referent.map(forwarded);
+            // A reference field may be cleared if it is a weak reference.
+            if new_referent.is_some() && *new_referent != expected {
+                return Err(format!(
+                    "reference field #{} is {:?}, but {:?} is expected",
+                    i, new_referent, expected
+                ));
+            }
+        }
+
+        // Check the other words.
+        let old_referent_addrs: HashSet<usize> = shadow
+            .referents
+            .iter()
+            .flatten()
+            .map(|referent| referent.to_raw_address().as_usize())
+            .collect();
+        let header_words =
+            header_metadata_words::<VM>(old, old.to_raw_address() - shadow.ref_offset);
+        for (i, (old_word, new_word)) in shadow
+            .bytes
+            .chunks(BYTES_IN_WORD)
+            .zip(new_bytes.chunks(BYTES_IN_WORD))
+            .enumerate()
+        {
+            if old_word == new_word || header_words.contains(&i) {
+                continue;
+            }
+            if old_word.len() == BYTES_IN_WORD {
+                let value = usize::from_ne_bytes(old_word.try_into().unwrap());
+                if old_referent_addrs.contains(&value) {
+                    // This is a reference field, checked above.
+                    continue;
+                }
+            }
+            return Err(format!(
+                "word #{} changed from {:?} to {:?}",
+                i, old_word, new_word
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Make a copy of every object before the transitive closure. This is called by `StopMutators`
+/// after all mutators are stopped, and before the `Prepare` bucket is opened, so that no object is
+/// prepared, moved or scanned when we take the snapshot.
+pub(crate) fn take_snapshot<VM: VMBinding>(mmtk: &'static MMTK<VM>, tls: VMWorkerThread) {
+    info!("Taking a snapshot of the heap");
+    mmtk.shadow_heap.lock().unwrap().snapshot(mmtk, tls);
+}
+
+/// A work packet that compares every reachable object with its copy made before the GC. It
+/// panics if the verification fails.
+pub struct ShadowHeapVerify<VM: VMBinding>(PhantomData<VM>);
+
+impl<VM: VMBinding> Default for ShadowHeapVerify<VM> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<VM: VMBinding> GCWork<VM> for ShadowHeapVerify<VM> {
+    fn do_work(&mut self, worker: &mut GCWorker<VM>, mmtk: &'static MMTK<VM>) {
+        info!("Verifying the heap against the snapshot");
+        let failures = mmtk.shadow_heap.lock().unwrap().verify::<VM>(worker.tls);
+        assert_eq!(
+            failures, 0,
+            "Shadow heap verification found {} bad objects",
+            failures
+        );
+    }
+}
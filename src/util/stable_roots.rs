@@ -0,0 +1,45 @@
+//! The cache of stable root slots.  See [`crate::vm::Scanning::scan_stable_roots`].
+
+use crate::util::VMWorkerThread;
+use crate::vm::{Scanning, VMBinding};
+use std::sync::Mutex;
+
+/// The stable root slots of each category, or `None` if a category has not been scanned since it
+/// was last invalidated.
+pub(crate) struct StableRoots<VM: VMBinding> {
+    categories: Mutex<Vec<Option<Vec<VM::VMSlot>>>>,
+}
+
+impl<VM: VMBinding> StableRoots<VM> {
+    pub fn new() -> Self {
+        Self {
+            categories: Mutex::new(vec![]),
+        }
+    }
+
+    /// Call `f` with the slots of each category.  Categories that are not cached are scanned.
+    pub fn for_each_category(&self, tls: VMWorkerThread, mut f: impl FnMut(&[VM::VMSlot])) {
+        let mut categories = self.categories.lock().unwrap();
+        categories.resize(VM::VMScanning::stable_root_categories(), None);
+        for (category, cached) in categories.iter_mut().enumerate() {
+            let slots = cached.get_or_insert_with(|| {
+                let mut slots = vec![];
+                VM::VMScanning::scan_stable_roots(tls, category, &mut slots);
+                debug!(
+                    "Scanned {} slots of stable root category {}",
+                    slots.len(),
+                    category
+                );
+                slots
+            });
+            f(slots);
+        }
+    }
+
+    /// Drop the cached slots of a category, so that it is scanned again in the next GC.
+    pub fn invalidate(&self, category: usize) {
+        if let Some(cached) = self.categories.lock().unwrap().get_mut(category) {
+            *cached = None;
+        }
+    }
+}
@@ -76,13 +76,14 @@ impl Counter for EventCounter {
             return;
         }
         debug_assert!(self.running);
-        self.count[self.stats.get_phase()] = self.current_count;
+        // Accumulate, as the counter may be stopped and started again in the same phase.
+        self.count[self.stats.get_phase()] += self.current_count;
         self.running = false;
     }
 
     fn phase_change(&mut self, old_phase: usize) {
         if self.running {
-            self.count[old_phase] = self.current_count;
+            self.count[old_phase] += self.current_count;
             self.current_count = 0;
         }
     }
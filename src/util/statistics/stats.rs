@@ -6,7 +6,7 @@ use crate::vm::VMBinding;
 
 #[cfg(feature = "perf_counter")]
 use pfm::Perfmon;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::sync::Mutex;
@@ -189,7 +189,26 @@ impl Stats {
         println!(
             "============================ MMTk Statistics Totals ============================"
         );
-        let scheduler_stat = mmtk.scheduler.statistics();
+        let mut scheduler_stat = mmtk.scheduler.statistics();
+        let huge_page_stats = crate::util::memory::get_huge_page_stats();
+        if huge_page_stats.is_used() {
+            scheduler_stat.insert(
+                "hugetlb.bytes".to_owned(),
+                huge_page_stats.hugetlb_bytes.to_string(),
+            );
+            scheduler_stat.insert(
+                "hugetlb.fallback.bytes".to_owned(),
+                huge_page_stats.hugetlb_fallback_bytes.to_string(),
+            );
+            scheduler_stat.insert(
+                "thp.advised.bytes".to_owned(),
+                huge_page_stats.thp_advised_bytes.to_string(),
+            );
+            scheduler_stat.insert(
+                "thp.bytes".to_owned(),
+                huge_page_stats.thp_bytes.to_string(),
+            );
+        }
         self.print_column_names(&scheduler_stat);
         print!("{}\t", self.get_phase() / 2);
         let counter = self.counters.lock().unwrap();
@@ -251,7 +270,9 @@ impl Stats {
         self.print_stats(mmtk);
     }
 
-    fn stop_all_counters(&self) {
+    /// Stop all the counters without printing the statistics. The counters can be started
+    /// again with `start_all`.
+    pub fn stop_all_counters(&self) {
         let counters = self.counters.lock().unwrap();
         for c in &(*counters) {
             c.lock().unwrap().stop();
@@ -263,6 +284,32 @@ impl Stats {
         self.shared.get_phase()
     }
 
+    /// Get the number of GCs, including the GCs when statistics are not gathered.
+    pub fn get_gc_count(&self) -> usize {
+        self.gc_count.load(Ordering::SeqCst)
+    }
+
+    /// Get the totals of all counters, keyed by the column names printed by `print_stats`.  The
+    /// values that running counters have counted so far in the current phase are included.
+    pub fn snapshot(&self) -> BTreeMap<String, u64> {
+        let phase = self.get_phase();
+        let counters = self.counters.lock().unwrap();
+        let mut result = BTreeMap::new();
+        for c in &(*counters) {
+            let mut c = c.lock().unwrap();
+            // Account the values of a running counter to the current phase without changing the
+            // phase, so they are included in the totals.
+            c.phase_change(phase);
+            if c.merge_phases() {
+                result.insert(c.name().clone(), c.get_total(None));
+            } else {
+                result.insert(format!("{}.other", c.name()), c.get_total(Some(true)));
+                result.insert(format!("{}.stw", c.name()), c.get_total(Some(false)));
+            }
+        }
+        result
+    }
+
     pub fn get_gathering_stats(&self) -> bool {
         self.shared.get_gathering_stats()
     }
@@ -239,6 +239,10 @@ impl FixtureContent for SingleObject {
 }
 
 impl SingleObject {
+    pub fn mmtk(&self) -> &'static MMTK<MockVM> {
+        self.mutator.mmtk()
+    }
+
     pub fn mutator(&self) -> &Mutator<MockVM> {
         &self.mutator.mutator
     }
@@ -0,0 +1,336 @@
+//! A harness that runs randomized sequences of allocations, mutations and GCs against
+//! [`super::mock_vm::MockVM`], and checks invariants after each GC.
+//!
+//! The harness runs one mutator in the test thread, and real GC worker threads.  It keeps a shadow
+//! copy of the object graph.  After each GC, it traverses the heap from the roots together with
+//! the shadow graph, and checks that
+//!
+//! * every object reachable in the shadow graph is still reachable from the same path, with the
+//!   same ID and the same number of fields,
+//! * every reachable object is in an MMTk space, and
+//! * every reachable object has its VO bit set (if the feature `vo_bit` is enabled).
+//!
+//! The objects and the GCs are provided by [`MockGC`], which supports non-moving plans and
+//! `SemiSpace`.
+
+use super::mock_gc::*;
+use crate::memory_manager;
+use crate::util::ObjectReference;
+use crate::AllocationSemantics;
+use crate::MMTKBuilder;
+
+use std::collections::{HashMap, HashSet};
+
+/// The configuration of a fuzzing run.
+#[derive(Clone, Debug)]
+pub struct FuzzerConfig {
+    /// The seed of the random number generator.  Runs with the same seed and the same options
+    /// perform the same operations in the mutator.
+    pub seed: u64,
+    /// The number of operations to perform.
+    pub steps: usize,
+    /// The number of root slots.
+    pub roots: usize,
+    /// The maximum number of reference fields of an object.
+    pub max_fields: usize,
+    /// The chance that an operation is an explicit GC request, in percent.
+    pub gc_percent: usize,
+    /// The heap size in bytes.  This is set before the builder is passed to the test, so the test
+    /// can override it.
+    pub heap_size: usize,
+}
+
+impl Default for FuzzerConfig {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            steps: 10000,
+            roots: 64,
+            max_fields: 8,
+            gc_percent: 1,
+            heap_size: 4 * 1024 * 1024,
+        }
+    }
+}
+
+/// The statistics of a fuzzing run.
+#[derive(Clone, Debug, Default)]
+pub struct FuzzerReport {
+    /// The number of objects allocated.
+    pub allocated: usize,
+    /// The number of GCs, including the GCs triggered by allocation.
+    pub gcs: usize,
+    /// The number of reachable objects checked, summed over all the GCs.
+    pub checked: usize,
+}
+
+/// A xorshift random number generator.  We do not need a good one, but we need the same sequence
+/// for the same seed on all platforms.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // The state must not be zero.
+        Self(seed ^ 0x9e37_79b9_7f4a_7c15)
+    }
+
+    fn next(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+
+    /// A random number in `0..n`.  `n` must not be zero.
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+struct ShadowObject {
+    object: ObjectReference,
+    fields: Vec<Option<u64>>,
+}
+
+/// The shadow copy of the object graph, keyed by object IDs.
+#[derive(Default)]
+struct ShadowGraph {
+    roots: Vec<Option<u64>>,
+    objects: HashMap<u64, ShadowObject>,
+}
+
+impl ShadowGraph {
+    /// The IDs of the objects reachable from the roots.
+    fn reachable(&self) -> Vec<u64> {
+        let mut visited = HashSet::new();
+        let mut result = vec![];
+        let mut stack: Vec<u64> = self.roots.iter().flatten().copied().collect();
+        while let Some(id) = stack.pop() {
+            if visited.insert(id) {
+                result.push(id);
+                stack.extend(self.objects[&id].fields.iter().flatten());
+            }
+        }
+        result
+    }
+
+    /// Check an edge in the heap against the edge in the shadow graph.  Update the address of the
+    /// object in the shadow graph, and return the ID of the object.
+    fn check_edge(
+        &mut self,
+        from: impl Fn() -> String,
+        expected: Option<u64>,
+        actual: Option<ObjectReference>,
+    ) -> Option<u64> {
+        let (id, object) = match (expected, actual) {
+            (None, None) => return None,
+            (Some(id), Some(object)) => (id, object),
+            _ => panic!(
+                "{}: expected object {:?}, found {:?}",
+                from(),
+                expected,
+                actual
+            ),
+        };
+        assert!(
+            memory_manager::is_in_mmtk_spaces(object),
+            "{}: object {} is not in MMTk spaces",
+            from(),
+            object
+        );
+        #[cfg(feature = "vo_bit")]
+        assert!(
+            crate::util::metadata::vo_bit::is_vo_bit_set(object),
+            "{}: object {} does not have the VO bit",
+            from(),
+            object
+        );
+        assert_eq!(object_id(object), id, "{}: object {}", from(), object);
+        let shadow = self.objects.get_mut(&id).unwrap();
+        assert_eq!(
+            num_fields(object),
+            shadow.fields.len(),
+            "{}: object {}",
+            from(),
+            object
+        );
+        shadow.object = object;
+        Some(id)
+    }
+
+    /// Traverse the heap from the roots together with the shadow graph, and check that they match.
+    /// Remove unreachable objects from the shadow graph.  Return the number of reachable objects.
+    fn check(&mut self, gc: &MockGC) -> usize {
+        let mut visited = HashSet::new();
+        let mut stack = vec![];
+        for i in 0..self.roots.len() {
+            let expected = self.roots[i];
+            if let Some(id) = self.check_edge(|| format!("root {}", i), expected, gc.load_root(i)) {
+                stack.push(id);
+            }
+        }
+        while let Some(id) = stack.pop() {
+            if !visited.insert(id) {
+                continue;
+            }
+            let object = self.objects[&id].object;
+            for i in 0..self.objects[&id].fields.len() {
+                let expected = self.objects[&id].fields[i];
+                let from = || format!("field {} of object {} ({})", i, id, object);
+                if let Some(child) = self.check_edge(from, expected, load_field(object, i)) {
+                    stack.push(child);
+                }
+            }
+        }
+        self.objects.retain(|id, _| visited.contains(id));
+        visited.len()
+    }
+}
+
+struct Fuzzer<'a> {
+    config: &'a FuzzerConfig,
+    gc: MockGC,
+    rng: Rng,
+    graph: ShadowGraph,
+    next_id: u64,
+    report: FuzzerReport,
+}
+
+impl Fuzzer<'_> {
+    /// Check the heap if any GC has happened since the last check.
+    fn check_after_gc(&mut self) {
+        let gcs = self.gc.gcs();
+        if gcs != self.report.gcs {
+            self.report.gcs = gcs;
+            self.report.checked += self.graph.check(&self.gc);
+        }
+    }
+
+    fn random_reachable(&mut self) -> Option<u64> {
+        let reachable = self.graph.reachable();
+        if reachable.is_empty() {
+            None
+        } else {
+            Some(reachable[self.rng.below(reachable.len())])
+        }
+    }
+
+    fn set_root(&mut self, index: usize, id: Option<u64>) {
+        let object = id.map(|id| self.graph.objects[&id].object);
+        self.gc.store_root(index, object);
+        self.graph.roots[index] = id;
+    }
+
+    fn set_field(&mut self, src_id: u64, index: usize, id: Option<u64>) {
+        let src = self.graph.objects[&src_id].object;
+        let target = id.map(|id| self.graph.objects[&id].object);
+        self.gc.store_field(src, index, target);
+        self.graph.objects.get_mut(&src_id).unwrap().fields[index] = id;
+    }
+
+    /// Allocate an object, and store it in a root or in a field of a reachable object.
+    fn allocate(&mut self) {
+        let num_fields = self.rng.below(self.config.max_fields + 1);
+        let id = self.next_id;
+        self.next_id += 1;
+        let object = self.gc.alloc(num_fields, id, AllocationSemantics::Default);
+        self.report.allocated += 1;
+
+        // The allocation may have triggered a GC.  Check it before the new object is reachable.
+        self.check_after_gc();
+        self.graph.objects.insert(
+            id,
+            ShadowObject {
+                object,
+                fields: vec![None; num_fields],
+            },
+        );
+
+        let src = self
+            .random_reachable()
+            .filter(|src| !self.graph.objects[src].fields.is_empty());
+        let to_field = self.rng.below(2) == 0;
+        match src {
+            Some(src) if to_field => {
+                let index = self.rng.below(self.graph.objects[&src].fields.len());
+                self.set_field(src, index, Some(id));
+            }
+            _ => {
+                let index = self.rng.below(self.config.roots);
+                self.set_root(index, Some(id));
+            }
+        }
+    }
+
+    /// Store a reachable object or null in a field of a reachable object.
+    fn mutate(&mut self) {
+        let Some(src) = self.random_reachable() else {
+            return;
+        };
+        let num_fields = self.graph.objects[&src].fields.len();
+        if num_fields == 0 {
+            return;
+        }
+        let index = self.rng.below(num_fields);
+        let target = if self.rng.below(4) == 0 {
+            None
+        } else {
+            self.random_reachable()
+        };
+        self.set_field(src, index, target);
+    }
+
+    fn step(&mut self) {
+        if self.rng.below(100) < self.config.gc_percent {
+            self.gc.gc();
+        } else {
+            match self.rng.below(10) {
+                0..=4 => self.allocate(),
+                5..=7 => self.mutate(),
+                8 => {
+                    let index = self.rng.below(self.config.roots);
+                    let id = self.random_reachable();
+                    self.set_root(index, id);
+                }
+                _ => {
+                    let index = self.rng.below(self.config.roots);
+                    self.set_root(index, None);
+                }
+            }
+        }
+        self.check_after_gc();
+    }
+}
+
+/// Run random allocations, mutations and GC requests, and check the heap after each GC.  See the
+/// module documentation.
+///
+/// This should be called in the test closure of [`super::mock_vm::with_mockvm`], and replaces the
+/// `MockVM`.  The MMTk instance is created by [`MockGC::new`] with the heap size in `config`, and
+/// the GC threads are shut down before this function returns.
+pub fn run_gc_fuzzer<F>(config: &FuzzerConfig, with_builder: F) -> FuzzerReport
+where
+    F: FnOnce(&mut MMTKBuilder),
+{
+    assert!(config.roots > 0);
+    info!("GC fuzzer: {:?}", config);
+
+    let mut fuzzer = Fuzzer {
+        config,
+        gc: MockGC::new(config.roots, config.heap_size, with_builder),
+        rng: Rng::new(config.seed),
+        graph: ShadowGraph {
+            roots: vec![None; config.roots],
+            objects: HashMap::new(),
+        },
+        next_id: 1,
+        report: FuzzerReport::default(),
+    };
+    for _ in 0..config.steps {
+        fuzzer.step();
+    }
+    info!("GC fuzzer: {:?}", fuzzer.report);
+    fuzzer.report.clone()
+}
@@ -0,0 +1,426 @@
+//! Running real GCs with [`MockVM`].
+//!
+//! [`MockGC`] sets up `MockVM` so that an MMTk instance can run GCs with real GC worker threads.
+//! It has one mutator which runs in the current thread, and a fixed number of root slots.  It
+//! defines a simple object model.  An object has an ID and a number of reference fields, and has
+//! the following layout, so that the mock methods can scan and copy objects without calling other
+//! mock methods:
+//!
+//! ```text
+//! object start:            the number of fields
+//! object reference:        the header word (the in-header metadata of `MockVM`)
+//! object reference + 8:    the ID of the object
+//! object reference + 16:   the reference fields
+//! ```
+//!
+//! `MockVM` places all the in-header metadata bits in the same bit of the header word.  This works
+//! as long as each object only uses one of them, which is the case for non-moving plans,
+//! `SemiSpace` and `MarkCompact`.  Other plans that move objects can only be used if no object is
+//! kept alive, i.e. no object is stored in a root slot.
+
+use super::mock_method::*;
+use super::mock_vm::*;
+use crate::memory_manager;
+use crate::util::constants::BYTES_IN_ADDRESS;
+use crate::util::opaque_pointer::*;
+use crate::util::options::{GCTriggerSelector, PlanSelector};
+use crate::util::{Address, ObjectReference};
+use crate::vm::GCThreadContext;
+use crate::AllocationSemantics;
+use crate::MMTKBuilder;
+use crate::Mutator;
+use crate::MMTK;
+
+use std::any::Any;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, OnceLock};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// A GC must finish in this time, or we consider it a hang.
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+const ID_OFFSET: usize = BYTES_IN_ADDRESS;
+const FIELDS_OFFSET: usize = 2 * BYTES_IN_ADDRESS;
+
+/// The size of an object with `num_fields` fields.
+pub fn object_size(num_fields: usize) -> usize {
+    DEFAULT_OBJECT_REF_OFFSET + FIELDS_OFFSET + num_fields * BYTES_IN_ADDRESS
+}
+
+/// The number of fields of an object.
+pub fn num_fields(object: ObjectReference) -> usize {
+    unsafe {
+        object
+            .to_raw_address()
+            .sub(DEFAULT_OBJECT_REF_OFFSET)
+            .load()
+    }
+}
+
+/// The ID of an object.
+pub fn object_id(object: ObjectReference) -> u64 {
+    unsafe { (object.to_raw_address() + ID_OFFSET).load() }
+}
+
+/// The slot of a field of an object.
+pub fn field_slot(object: ObjectReference, index: usize) -> Address {
+    object.to_raw_address() + FIELDS_OFFSET + index * BYTES_IN_ADDRESS
+}
+
+/// Load a field of an object.
+pub fn load_field(object: ObjectReference, index: usize) -> Option<ObjectReference> {
+    ObjectReference::from_raw_address(unsafe { field_slot(object, index).load() })
+}
+
+/// The state of an object watched with [`MockGC::watch`].  It is updated in each GC after the
+/// transitive closure.
+#[derive(Clone, Copy, Debug)]
+pub struct WatchedObject {
+    /// The current address of the object.  This is the address before the GC in which the object
+    /// died if the object is dead.
+    pub object: ObjectReference,
+    /// The object has been reachable in all the GCs since it was watched.
+    pub live: bool,
+    /// The object was moved in the last GC.
+    pub moved: bool,
+}
+
+struct MutatorPtr(*mut Mutator<MockVM>);
+
+// The mutator is only accessed by GC workers while it is blocked for GC.
+unsafe impl Send for MutatorPtr {}
+
+#[derive(Default)]
+struct HarnessSync {
+    mutator: Option<MutatorPtr>,
+    /// The mutator is in `block_for_gc`.
+    mutator_blocked: bool,
+    /// Mutators have been stopped and not resumed, yet.
+    mutators_stopped: bool,
+    /// The number of GCs finished.
+    gcs: usize,
+    join_handles: Vec<JoinHandle<()>>,
+}
+
+/// The states shared between the mutator and the mock methods called by GC workers.
+struct Shared {
+    mmtk: OnceLock<&'static MMTK<MockVM>>,
+    sync: Mutex<HarnessSync>,
+    cond: Condvar,
+    /// The root slots.  They hold raw addresses, and 0 for null.
+    roots: Box<[AtomicUsize]>,
+    watched: Mutex<Vec<WatchedObject>>,
+}
+
+impl Shared {
+    fn mmtk(&self) -> &'static MMTK<MockVM> {
+        self.mmtk.get().unwrap()
+    }
+
+    fn lock(&self) -> MutexGuard<HarnessSync> {
+        self.sync.lock().unwrap()
+    }
+
+    fn wait_while<'a>(
+        &self,
+        guard: MutexGuard<'a, HarnessSync>,
+        condition: impl FnMut(&mut HarnessSync) -> bool,
+    ) -> MutexGuard<'a, HarnessSync> {
+        let (guard, result) = self
+            .cond
+            .wait_timeout_while(guard, TIMEOUT, condition)
+            .unwrap();
+        assert!(!result.timed_out(), "Timed out waiting for GC");
+        guard
+    }
+
+    fn mutator(&self) -> &'static mut Mutator<MockVM> {
+        unsafe { &mut *self.lock().mutator.as_ref().unwrap().0 }
+    }
+
+    fn root_slots(&self) -> Vec<Address> {
+        self.roots.iter().map(Address::from_ref).collect()
+    }
+
+    /// Update the watched objects.  This is called after the transitive closure.
+    fn update_watched(&self) {
+        for watched in self.watched.lock().unwrap().iter_mut() {
+            watched.moved = false;
+            if !watched.live {
+                continue;
+            }
+            if !watched.object.is_reachable() {
+                watched.live = false;
+            } else if let Some(new_object) = watched.object.get_forwarded_object() {
+                watched.moved = new_object != watched.object;
+                watched.object = new_object;
+            }
+        }
+    }
+}
+
+/// Process weak references by updating the watched objects.  The arguments are ignored.
+struct UpdateWatched(Arc<Shared>);
+
+impl MockAny for UpdateWatched {
+    fn call_any(&mut self, _args: Box<dyn Any>) -> Box<dyn Any> {
+        self.0.update_watched();
+        Box::new(false)
+    }
+}
+
+/// Create a `MockVM` that runs GC with real GC worker threads, and scans and copies the objects
+/// and the roots of the `MockGC`.
+fn setup_mockvm(shared: &Arc<Shared>) -> MockVM {
+    let s_spawn = shared.clone();
+    let s_number_of_mutators = shared.clone();
+    let s_mutator = shared.clone();
+    let s_mutators = shared.clone();
+    let s_stop = shared.clone();
+    let s_resume = shared.clone();
+    let s_block = shared.clone();
+    let s_roots = shared.clone();
+    MockVM {
+        spawn_gc_thread: MockMethod::new_fixed(Box::new(move |(_, context)| {
+            let GCThreadContext::Worker(worker) = context;
+            let mmtk = s_spawn.mmtk();
+            let join_handle = std::thread::spawn(move || {
+                let tls = VMWorkerThread(VMThread(OpaquePointer::from_address(Address::ZERO)));
+                memory_manager::start_worker(mmtk, tls, worker);
+            });
+            s_spawn.lock().join_handles.push(join_handle);
+        })),
+        number_of_mutators: MockMethod::new_fixed(Box::new(move |_| {
+            usize::from(s_number_of_mutators.lock().mutator.is_some())
+        })),
+        mutator: MockMethod::new_fixed(Box::new(move |_| s_mutator.mutator())),
+        mutators: MockMethod::new_fixed(Box::new(move |_| {
+            let mutators: Box<dyn Iterator<Item = &'static mut Mutator<MockVM>>> =
+                Box::new(std::iter::once(s_mutators.mutator()));
+            mutators
+        })),
+        stop_all_mutators: MockMethod::new_fixed(Box::new(move |(_, mut visitor)| {
+            // Wait for the mutator to reach `block_for_gc` so that it does not run during GC.
+            let mut sync = s_stop.wait_while(s_stop.lock(), |sync| !sync.mutator_blocked);
+            sync.mutators_stopped = true;
+            drop(sync);
+            visitor(s_stop.mutator());
+        })),
+        resume_mutators: MockMethod::new_fixed(Box::new(move |_| {
+            let mut sync = s_resume.lock();
+            sync.mutators_stopped = false;
+            sync.gcs += 1;
+            s_resume.cond.notify_all();
+        })),
+        block_for_gc: MockMethod::new_fixed(Box::new(move |_| {
+            let mmtk = s_block.mmtk();
+            let mut sync = s_block.lock();
+            sync.mutator_blocked = true;
+            s_block.cond.notify_all();
+            // The request is cleared after the mutators are stopped.
+            let mut sync =
+                s_block.wait_while(sync, |sync| sync.mutators_stopped || mmtk.is_gc_requested());
+            sync.mutator_blocked = false;
+        })),
+        copy_object: MockMethod::new_fixed(Box::new(|(from, semantics, copy_context)| {
+            let size = object_size(num_fields(from));
+            let start = copy_context.alloc_copy(from, size, BYTES_IN_ADDRESS, 0, semantics);
+            let from_start = from.to_raw_address().sub(DEFAULT_OBJECT_REF_OFFSET);
+            unsafe {
+                std::ptr::copy_nonoverlapping::<u8>(from_start.to_ptr(), start.to_mut_ptr(), size)
+            };
+            let to = MockVM::object_start_to_ref(start);
+            copy_context.post_copy(to, size, semantics);
+            to
+        })),
+        copy_object_to: MockMethod::new_fixed(Box::new(|(from, to, _)| {
+            let size = object_size(num_fields(from));
+            let from_start = from.to_raw_address().sub(DEFAULT_OBJECT_REF_OFFSET);
+            let to_start = to.to_raw_address().sub(DEFAULT_OBJECT_REF_OFFSET);
+            // MarkCompact may move an object to an overlapping address.
+            unsafe { std::ptr::copy::<u8>(from_start.to_ptr(), to_start.to_mut_ptr(), size) };
+            to_start + size
+        })),
+        get_object_reference_when_copied_to: MockMethod::new_fixed(Box::new(|(_, to)| {
+            MockVM::object_start_to_ref(to)
+        })),
+        get_object_size: MockMethod::new_fixed(Box::new(|object| object_size(num_fields(object)))),
+        get_object_size_when_copied: MockMethod::new_fixed(Box::new(|object| {
+            object_size(num_fields(object))
+        })),
+        scan_object: MockMethod::new_fixed(Box::new(|(_, object, slot_visitor)| {
+            for i in 0..num_fields(object) {
+                slot_visitor.visit_slot(field_slot(object, i));
+            }
+        })),
+        scan_roots_in_mutator_thread: MockMethod::new_fixed(Box::new(
+            move |(_, _, mut factory)| {
+                factory.create_process_roots_work(s_roots.root_slots());
+            },
+        )),
+        scan_vm_specific_roots: MockMethod::new_default(),
+        notify_initial_thread_scan_complete: MockMethod::new_default(),
+        supports_return_barrier: MockMethod::new_fixed(Box::new(|_| false)),
+        process_weak_refs: Box::new(UpdateWatched(shared.clone())),
+        forward_weak_refs: Box::new(MockAnyDefault::<()>::default()),
+        ..MockVM::default()
+    }
+}
+
+/// An MMTk instance that runs real GCs with `MockVM`, and its only mutator.  See the module
+/// documentation.
+///
+/// This should be created in the test closure of [`with_mockvm`], as it replaces the `MockVM`.
+/// Dropping it shuts down the GC threads.
+pub struct MockGC {
+    shared: Arc<Shared>,
+    mutator: Box<Mutator<MockVM>>,
+}
+
+impl MockGC {
+    /// Create an MMTk instance with `roots` root slots.  The heap size is set to `heap_size`
+    /// before `with_builder` is called, and the other options are read from the environment
+    /// variables.
+    pub fn new<F>(roots: usize, heap_size: usize, with_builder: F) -> Self
+    where
+        F: FnOnce(&mut MMTKBuilder),
+    {
+        let shared = Arc::new(Shared {
+            mmtk: OnceLock::new(),
+            sync: Mutex::default(),
+            cond: Condvar::new(),
+            roots: (0..roots).map(|_| AtomicUsize::new(0)).collect(),
+            watched: Mutex::default(),
+        });
+        write_mockvm(|mock| *mock = setup_mockvm(&shared));
+
+        let mut builder = MMTKBuilder::new();
+        builder
+            .options
+            .gc_trigger
+            .set(GCTriggerSelector::FixedHeapSize(heap_size));
+        with_builder(&mut builder);
+        let mmtk: &'static MMTK<MockVM> = Box::leak(memory_manager::mmtk_init(&builder));
+        assert!(shared.mmtk.set(mmtk).is_ok());
+        memory_manager::initialize_collection(mmtk, VMThread::UNINITIALIZED);
+
+        let mut mutator =
+            memory_manager::bind_mutator(mmtk, VMMutatorThread(VMThread::UNINITIALIZED));
+        shared.lock().mutator = Some(MutatorPtr(&mut *mutator));
+        Self { shared, mutator }
+    }
+
+    pub fn mmtk(&self) -> &'static MMTK<MockVM> {
+        self.shared.mmtk()
+    }
+
+    pub fn mutator(&mut self) -> &mut Mutator<MockVM> {
+        &mut self.mutator
+    }
+
+    /// Allocate an object with `num_fields` null fields.  This may trigger GCs.
+    pub fn alloc(
+        &mut self,
+        num_fields: usize,
+        id: u64,
+        semantics: AllocationSemantics,
+    ) -> ObjectReference {
+        let size = object_size(num_fields);
+        let start = memory_manager::alloc(&mut self.mutator, size, BYTES_IN_ADDRESS, 0, semantics);
+        assert!(!start.is_zero());
+        crate::util::memory::zero(start, size);
+        let object = MockVM::object_start_to_ref(start);
+        unsafe {
+            start.store(num_fields);
+            (object.to_raw_address() + ID_OFFSET).store(id);
+        }
+        memory_manager::post_alloc(&mut self.mutator, object, size, semantics);
+        object
+    }
+
+    pub fn load_root(&self, index: usize) -> Option<ObjectReference> {
+        ObjectReference::from_raw_address(unsafe {
+            Address::from_usize(self.shared.roots[index].load(Ordering::SeqCst))
+        })
+    }
+
+    /// Store `object` in a root slot.  This panics if the plan moves objects other than by
+    /// `SemiSpace` or `MarkCompact`, as the objects kept alive may be moved by the plan.
+    pub fn store_root(&self, index: usize, object: Option<ObjectReference>) {
+        let plan = *self.mmtk().get_options().plan;
+        assert!(
+            object.is_none()
+                || !self.mmtk().get_plan().constraints().moves_objects
+                || matches!(plan, PlanSelector::SemiSpace | PlanSelector::MarkCompact),
+            "{:?} is not supported as it moves objects",
+            plan
+        );
+        let value = object.map_or(0, |o| o.to_raw_address().as_usize());
+        self.shared.roots[index].store(value, Ordering::SeqCst);
+    }
+
+    /// Store `target` in a field of `src`, with the write barrier.
+    pub fn store_field(
+        &mut self,
+        src: ObjectReference,
+        index: usize,
+        target: Option<ObjectReference>,
+    ) {
+        let slot = field_slot(src, index);
+        memory_manager::object_reference_write_pre(&mut self.mutator, src, slot, target);
+        unsafe { slot.store(target.map_or(Address::ZERO, |t| t.to_raw_address())) };
+        memory_manager::object_reference_write_post(&mut self.mutator, src, slot, target);
+    }
+
+    /// Request a GC, and wait until it finishes.  Return `false` if the request is ignored.
+    pub fn gc(&mut self) -> bool {
+        let tls = self.mutator.mutator_tls;
+        memory_manager::handle_user_collection_request(self.mmtk(), tls)
+    }
+
+    /// Request a GC that only collects the given spaces, and wait until it finishes.  Return
+    /// false if the request is ignored.
+    #[cfg(feature = "vo_bit")]
+    pub fn space_targeted_gc(&mut self, spaces: &[&str]) -> bool {
+        let tls = self.mutator.mutator_tls;
+        memory_manager::handle_user_space_targeted_collection_request(self.mmtk(), tls, spaces)
+    }
+
+    /// The number of GCs finished, including the GCs triggered by allocation.
+    pub fn gcs(&self) -> usize {
+        self.shared.lock().gcs
+    }
+
+    /// Watch the liveness and the movement of an object in the following GCs.  Return the index
+    /// for [`MockGC::watched`].  The movement is not tracked with `MarkCompact`, which moves the
+    /// objects after the binding processes weak references.
+    pub fn watch(&self, object: ObjectReference) -> usize {
+        let mut watched = self.shared.watched.lock().unwrap();
+        watched.push(WatchedObject {
+            object,
+            live: true,
+            moved: false,
+        });
+        watched.len() - 1
+    }
+
+    pub fn watched(&self, index: usize) -> WatchedObject {
+        self.shared.watched.lock().unwrap()[index]
+    }
+}
+
+impl Drop for MockGC {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            // The GC may not have finished.  Leave the GC threads running.
+            return;
+        }
+        self.mmtk().shutdown();
+        self.shared.lock().mutator = None;
+        let join_handles = std::mem::take(&mut self.shared.lock().join_handles);
+        for join_handle in join_handles {
+            join_handle.join().unwrap();
+        }
+    }
+}
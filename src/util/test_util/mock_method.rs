@@ -1,4 +1,5 @@
 use std::any::Any;
+use std::marker::PhantomData;
 
 /// `MockAny` hides any type information. It is useful when we want to create
 /// a mock method for methods with generic type parameters.
@@ -20,6 +21,22 @@ impl<I: 'static, R: 'static> MockAny for MockMethod<I, R> {
     }
 }
 
+/// A `MockAny` that ignores the arguments, and returns the default value of `R`. It can be used
+/// for a method when a test does not care about the types of its arguments.
+pub struct MockAnyDefault<R>(PhantomData<R>);
+
+impl<R> std::default::Default for MockAnyDefault<R> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<R: Default + 'static> MockAny for MockAnyDefault<R> {
+    fn call_any(&mut self, _args: Box<dyn Any>) -> Box<dyn Any> {
+        Box::new(R::default())
+    }
+}
+
 /// Mocking a method. The type parameters are the types of arguments
 /// and the return values of the method as tuples.
 pub struct MockMethod<I, R> {
@@ -2,19 +2,21 @@
 #![allow(clippy::type_complexity)]
 
 use crate::plan::ObjectQueue;
-use crate::scheduler::gc_work::ProcessEdgesWorkRootsWorkFactory;
 use crate::scheduler::gc_work::ProcessEdgesWorkTracerContext;
 use crate::scheduler::gc_work::SFTProcessEdges;
 use crate::scheduler::*;
-use crate::util::alloc::AllocationError;
+use crate::util::alloc::{AllocationError, AllocationErrorContext};
 use crate::util::copy::*;
 use crate::util::heap::gc_trigger::GCTriggerPolicy;
 use crate::util::opaque_pointer::*;
 use crate::util::{Address, ObjectReference};
 use crate::vm::object_model::specs::*;
+#[cfg(feature = "vm_forwarding")]
+use crate::vm::ForwardingState;
 use crate::vm::GCThreadContext;
 use crate::vm::ObjectTracer;
 use crate::vm::ObjectTracerContext;
+use crate::vm::RootsDescriptor;
 use crate::vm::RootsWorkFactory;
 use crate::vm::SlotVisitor;
 use crate::vm::VMBinding;
@@ -59,6 +61,20 @@ macro_rules! mock {
         write_mockvm(|mock| mock.$fn.call(($($arg),*)))
     };
 }
+/// Call `MockMethod` without holding the lock of the static `MockVM` instance during the call.
+/// This is needed for methods that block until other threads have called other mock methods, such
+/// as a mutator blocking for GC.  The same method must not be called concurrently.
+macro_rules! mock_unlocked {
+    ($fn: ident($($arg:expr),*)) => {{
+        let mut method = write_mockvm(|mock| std::mem::take(&mut mock.$fn));
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            method.call(($($arg),*))
+        }));
+        // Put the method back even if it panics, so that the test can still check its calls.
+        write_mockvm(|mock| mock.$fn = method);
+        result.unwrap_or_else(|e| std::panic::resume_unwind(e))
+    }};
+}
 /// Call `MockAny`.
 macro_rules! mock_any {
     ($fn: ident($($arg:expr),*)) => {
@@ -119,6 +135,24 @@ pub fn default_setup() -> MockVM {
 /// No extra clean up after the test.
 pub fn no_cleanup() {}
 
+/// Mock [`crate::vm::ActivePlan::mutators`] to return the given mutators.  The caller must keep
+/// the mutators alive while MMTk may call the method.
+pub fn mock_mutators(mutators: &mut [&mut Mutator<MockVM>]) {
+    let mutators: Vec<usize> = mutators
+        .iter_mut()
+        .map(|mutator| &mut **mutator as *mut Mutator<MockVM> as usize)
+        .collect();
+    write_mockvm(|mock| {
+        mock.mutators = MockMethod::new_fixed(Box::new(move |_| {
+            let iter = mutators
+                .clone()
+                .into_iter()
+                .map(|mutator| unsafe { &mut *(mutator as *mut Mutator<MockVM>) });
+            Box::new(iter)
+        }));
+    });
+}
+
 /// A struct that allows us to mock the behavior of a `VMBinding` and the VM traits for testing.
 /// For simplicity, we implement `VMBinding` as well as `ActivePlan`, `Collection`,
 /// `ObjectModel`, `ReferenceGlue`, `Scanning` on the `MockVM` type, and forward each
@@ -162,6 +196,10 @@ pub fn no_cleanup() {}
 /// `MockMethod<(&'static mut dyn ObjectQueue, ObjectReference, &'static mut GCWorker<MockVM>), ObjectReference>`
 /// for the method.
 ///
+/// [`crate::vm::RootsWorkFactory`] is not object safe as it requires `Clone`. We mock the factory
+/// in [`crate::vm::Scanning::scan_roots_in_mutator_thread`] and
+/// [`crate::vm::Scanning::scan_vm_specific_roots`] as `Box<dyn DynRootsWorkFactory>` instead.
+///
 /// ### Use `MockAny`
 ///
 /// For cases where we cannot use trait objects, we can use `MockAny`.
@@ -205,18 +243,20 @@ pub struct MockVM {
     pub resume_mutators: MockMethod<VMWorkerThread, ()>,
     pub block_for_gc: MockMethod<VMMutatorThread, ()>,
     pub spawn_gc_thread: MockMethod<(VMThread, GCThreadContext<MockVM>), ()>,
-    pub out_of_memory: MockMethod<(VMThread, AllocationError), ()>,
+    pub out_of_memory: MockMethod<(VMThread, AllocationError, AllocationErrorContext), ()>,
     pub schedule_finalization: MockMethod<VMWorkerThread, ()>,
     pub post_forwarding: MockMethod<VMWorkerThread, ()>,
     pub vm_live_bytes: MockMethod<(), usize>,
     pub is_collection_enabled: MockMethod<(), bool>,
+    pub gc_suppressed: MockMethod<(VMMutatorThread, usize), ()>,
     pub create_gc_trigger: MockMethod<(), Box<dyn GCTriggerPolicy<MockVM>>>,
+    pub request_handshake: MockMethod<VMThread, ()>,
     // object model
     pub copy_object: MockMethod<
         (
             ObjectReference,
             CopySemantics,
-            &'static GCWorkerCopyContext<MockVM>,
+            &'static mut GCWorkerCopyContext<MockVM>,
         ),
         ObjectReference,
     >,
@@ -254,8 +294,18 @@ pub struct MockVM {
         ),
         (),
     >,
-    pub scan_roots_in_mutator_thread: Box<dyn MockAny>,
-    pub scan_vm_specific_roots: Box<dyn MockAny>,
+    pub scan_roots_in_mutator_thread: MockMethod<
+        (
+            VMWorkerThread,
+            &'static mut Mutator<MockVM>,
+            Box<dyn DynRootsWorkFactory>,
+        ),
+        (),
+    >,
+    pub scan_vm_specific_roots: MockMethod<(VMWorkerThread, Box<dyn DynRootsWorkFactory>), ()>,
+    pub stable_root_categories: MockMethod<(), usize>,
+    pub scan_stable_roots: MockMethod<(VMWorkerThread, usize, &'static mut Vec<Address>), ()>,
+    pub clear_weak_slot: MockMethod<(VMWorkerThread, Address), ()>,
     pub notify_initial_thread_scan_complete: MockMethod<(bool, VMWorkerThread), ()>,
     pub supports_return_barrier: MockMethod<(), bool>,
     pub prepare_for_roots_re_scanning: MockMethod<(), ()>,
@@ -278,14 +328,16 @@ impl Default for MockVM {
             resume_mutators: MockMethod::new_unimplemented(),
             block_for_gc: MockMethod::new_unimplemented(),
             spawn_gc_thread: MockMethod::new_default(),
-            out_of_memory: MockMethod::new_fixed(Box::new(|(_, err)| {
+            out_of_memory: MockMethod::new_fixed(Box::new(|(_, err, _)| {
                 panic!("Out of memory with {:?}!", err)
             })),
             schedule_finalization: MockMethod::new_default(),
             post_forwarding: MockMethod::new_default(),
             vm_live_bytes: MockMethod::new_default(),
             is_collection_enabled: MockMethod::new_fixed(Box::new(|_| true)),
+            gc_suppressed: MockMethod::new_default(),
             create_gc_trigger: MockMethod::new_unimplemented(),
+            request_handshake: MockMethod::new_default(),
 
             copy_object: MockMethod::new_unimplemented(),
             copy_object_to: MockMethod::new_unimplemented(),
@@ -302,7 +354,6 @@ impl Default for MockVM {
             })),
             ref_to_header: MockMethod::new_fixed(Box::new(|object| object.to_raw_address())),
             dump_object: MockMethod::new_unimplemented(),
-
             weakref_clear_referent: MockMethod::new_unimplemented(),
             weakref_get_referent: MockMethod::new_unimplemented(),
             weakref_set_referent: MockMethod::new_unimplemented(),
@@ -311,7 +362,15 @@ impl Default for MockVM {
             support_slot_enqueuing: MockMethod::new_fixed(Box::new(|_| true)),
             scan_object: MockMethod::new_unimplemented(),
             scan_object_and_trace_edges: MockMethod::new_unimplemented(),
-            // We instantiate a `MockMethod` with the arguments as ProcessEdgesWorkRootsWorkFactory<..., SFTProcessEdges<MockVM>, ...>,
+            scan_roots_in_mutator_thread: MockMethod::new_unimplemented(),
+            scan_vm_specific_roots: MockMethod::new_unimplemented(),
+            stable_root_categories: MockMethod::new_fixed(Box::new(|_| 0)),
+            scan_stable_roots: MockMethod::new_unimplemented(),
+            clear_weak_slot: MockMethod::new_unimplemented(),
+            notify_initial_thread_scan_complete: MockMethod::new_unimplemented(),
+            supports_return_barrier: MockMethod::new_unimplemented(),
+            prepare_for_roots_re_scanning: MockMethod::new_unimplemented(),
+            // We instantiate a `MockMethod` with the arguments as ProcessEdgesWorkTracerContext<SFTProcessEdges<MockVM>>,
             // thus the mock method expects the actual call arguments to match the type.
             // In most cases, this won't work and this `MockMethod` is just a place holder. It is
             // fine as long as the method is not actually called.
@@ -319,34 +378,6 @@ impl Default for MockVM {
             // they are expected to provide their own
             // `MockMethod` that matches the argument types they will pass for the test case.
             // See the documents on the section about `MockAny` on the `MockVM` type.
-            scan_roots_in_mutator_thread: Box::new(MockMethod::<
-                (
-                    VMWorkerThread,
-                    &'static mut Mutator<MockVM>,
-                    ProcessEdgesWorkRootsWorkFactory<
-                        MockVM,
-                        SFTProcessEdges<MockVM>,
-                        SFTProcessEdges<MockVM>,
-                    >,
-                ),
-                (),
-            >::new_unimplemented()),
-            // Same here: the `MockMethod` is just a place holder. See the above comments.
-            scan_vm_specific_roots: Box::new(MockMethod::<
-                (
-                    VMWorkerThread,
-                    ProcessEdgesWorkRootsWorkFactory<
-                        MockVM,
-                        SFTProcessEdges<MockVM>,
-                        SFTProcessEdges<MockVM>,
-                    >,
-                ),
-                (),
-            >::new_unimplemented()),
-            notify_initial_thread_scan_complete: MockMethod::new_unimplemented(),
-            supports_return_barrier: MockMethod::new_unimplemented(),
-            prepare_for_roots_re_scanning: MockMethod::new_unimplemented(),
-            // Same here: the `MockMethod` is just a place holder. See the above comments.
             process_weak_refs: Box::new(MockMethod::<
                 (
                     &'static mut GCWorker<Self>,
@@ -366,6 +397,99 @@ impl Default for MockVM {
     }
 }
 
+/// The forwarding methods of `MockVM` with the feature "vm_forwarding".  They keep the forwarding
+/// state in the lowest two bits of the header word, and the forwarding pointer in the other bits.
+///
+/// Unlike other methods of `MockVM`, they are not mock methods, because `post_copy` clears the
+/// forwarding state of the new copy inside the `copy_object` mock method, which holds the lock of
+/// the `MockVM` instance.  Instead, each of them counts its calls in [`forwarding::CALLS`] so that
+/// tests can check that mmtk-core calls the binding.
+#[cfg(feature = "vm_forwarding")]
+pub mod forwarding {
+    use crate::util::{Address, ObjectReference};
+    use crate::vm::ForwardingState;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const STATE_MASK: usize = 0b11;
+    const BEING_FORWARDED: usize = 0b10;
+    const FORWARDED: usize = 0b11;
+
+    /// The number of calls of each forwarding method.
+    pub struct ForwardingCalls {
+        pub get_forwarding_state: AtomicUsize,
+        pub is_forwarded: AtomicUsize,
+        pub attempt_to_forward: AtomicUsize,
+        pub set_forwarded: AtomicUsize,
+        pub read_forwarding_pointer: AtomicUsize,
+        pub clear_forwarding_state: AtomicUsize,
+    }
+
+    pub static CALLS: ForwardingCalls = ForwardingCalls {
+        get_forwarding_state: AtomicUsize::new(0),
+        is_forwarded: AtomicUsize::new(0),
+        attempt_to_forward: AtomicUsize::new(0),
+        set_forwarded: AtomicUsize::new(0),
+        read_forwarding_pointer: AtomicUsize::new(0),
+        clear_forwarding_state: AtomicUsize::new(0),
+    };
+
+    fn count(calls: &AtomicUsize) {
+        calls.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn header(object: ObjectReference) -> &'static AtomicUsize {
+        unsafe { object.to_raw_address().as_ref::<AtomicUsize>() }
+    }
+
+    fn state(word: usize) -> ForwardingState {
+        match word & STATE_MASK {
+            0 => ForwardingState::NotForwarded,
+            BEING_FORWARDED => ForwardingState::BeingForwarded,
+            FORWARDED => ForwardingState::Forwarded,
+            _ => panic!("Invalid forwarding state in header word {:#x}", word),
+        }
+    }
+
+    pub fn get_forwarding_state(object: ObjectReference) -> ForwardingState {
+        count(&CALLS.get_forwarding_state);
+        state(header(object).load(Ordering::Acquire))
+    }
+
+    pub fn is_forwarded(object: ObjectReference) -> bool {
+        count(&CALLS.is_forwarded);
+        // No other thread forwards the object when this is called.
+        let word = unsafe { object.to_raw_address().load::<usize>() };
+        word & STATE_MASK == FORWARDED
+    }
+
+    pub fn attempt_to_forward(object: ObjectReference) -> ForwardingState {
+        count(&CALLS.attempt_to_forward);
+        let old_word = header(object).load(Ordering::Acquire);
+        if old_word & STATE_MASK != 0 {
+            return state(old_word);
+        }
+        state(header(object).fetch_or(BEING_FORWARDED, Ordering::AcqRel))
+    }
+
+    pub fn set_forwarded(object: ObjectReference, new_object: ObjectReference) {
+        count(&CALLS.set_forwarded);
+        let pointer = new_object.to_raw_address().as_usize();
+        debug_assert_eq!(pointer & STATE_MASK, 0);
+        header(object).store(pointer | FORWARDED, Ordering::Release);
+    }
+
+    pub fn read_forwarding_pointer(object: ObjectReference) -> ObjectReference {
+        count(&CALLS.read_forwarding_pointer);
+        let word = header(object).load(Ordering::Acquire);
+        ObjectReference::from_raw_address(Address::from_usize(word & !STATE_MASK)).unwrap()
+    }
+
+    pub fn clear_forwarding_state(object: ObjectReference) {
+        count(&CALLS.clear_forwarding_state);
+        header(object).fetch_and(!STATE_MASK, Ordering::SeqCst);
+    }
+}
+
 unsafe impl Sync for MockVM {}
 unsafe impl Send for MockVM {}
 
@@ -419,7 +543,8 @@ impl crate::vm::Collection<MockVM> for MockVM {
     where
         F: FnMut(&'static mut Mutator<MockVM>),
     {
-        mock!(stop_all_mutators(
+        // Stopping mutators may wait for mutators that call other mock methods before they block.
+        mock_unlocked!(stop_all_mutators(
             tls,
             lifetime!(Box::new(mutator_visitor) as Box<dyn FnMut(&'static mut Mutator<MockVM>)>)
         ))
@@ -430,15 +555,16 @@ impl crate::vm::Collection<MockVM> for MockVM {
     }
 
     fn block_for_gc(tls: VMMutatorThread) {
-        mock!(block_for_gc(tls))
+        // GC workers call other mock methods while the mutator is blocked.
+        mock_unlocked!(block_for_gc(tls))
     }
 
     fn spawn_gc_thread(tls: VMThread, ctx: GCThreadContext<MockVM>) {
         mock!(spawn_gc_thread(tls, ctx))
     }
 
-    fn out_of_memory(tls: VMThread, err_kind: AllocationError) {
-        mock!(out_of_memory(tls, err_kind))
+    fn out_of_memory(tls: VMThread, err_kind: AllocationError, context: &AllocationErrorContext) {
+        mock!(out_of_memory(tls, err_kind, context.clone()))
     }
 
     fn schedule_finalization(tls: VMWorkerThread) {
@@ -453,6 +579,10 @@ impl crate::vm::Collection<MockVM> for MockVM {
         mock!(is_collection_enabled())
     }
 
+    fn gc_suppressed(tls: VMMutatorThread, suppressed: usize) {
+        mock!(gc_suppressed(tls, suppressed))
+    }
+
     fn vm_live_bytes() -> usize {
         mock!(vm_live_bytes())
     }
@@ -460,6 +590,10 @@ impl crate::vm::Collection<MockVM> for MockVM {
     fn create_gc_trigger() -> Box<dyn GCTriggerPolicy<MockVM>> {
         mock!(create_gc_trigger())
     }
+
+    fn request_handshake(tls: VMThread) {
+        mock!(request_handshake(tls))
+    }
 }
 
 impl crate::vm::ObjectModel<MockVM> for MockVM {
@@ -475,6 +609,9 @@ impl crate::vm::ObjectModel<MockVM> for MockVM {
     #[cfg(feature = "object_pinning")]
     const LOCAL_PINNING_BIT_SPEC: VMLocalPinningBitSpec = VMLocalPinningBitSpec::in_header(0);
 
+    #[cfg(feature = "address_based_hashing")]
+    const GLOBAL_HASH_STATE_SPEC: VMGlobalHashStateSpec = VMGlobalHashStateSpec::side_first();
+
     const OBJECT_REF_OFFSET_LOWER_BOUND: isize = DEFAULT_OBJECT_REF_OFFSET as isize;
 
     fn copy(
@@ -525,6 +662,36 @@ impl crate::vm::ObjectModel<MockVM> for MockVM {
     fn dump_object(object: ObjectReference) {
         mock!(dump_object(object))
     }
+
+    #[cfg(feature = "vm_forwarding")]
+    fn get_forwarding_state(object: ObjectReference) -> ForwardingState {
+        forwarding::get_forwarding_state(object)
+    }
+
+    #[cfg(feature = "vm_forwarding")]
+    fn is_forwarded(object: ObjectReference) -> bool {
+        forwarding::is_forwarded(object)
+    }
+
+    #[cfg(feature = "vm_forwarding")]
+    fn attempt_to_forward(object: ObjectReference) -> ForwardingState {
+        forwarding::attempt_to_forward(object)
+    }
+
+    #[cfg(feature = "vm_forwarding")]
+    fn set_forwarded(object: ObjectReference, new_object: ObjectReference) {
+        forwarding::set_forwarded(object, new_object)
+    }
+
+    #[cfg(feature = "vm_forwarding")]
+    fn read_forwarding_pointer(object: ObjectReference) -> ObjectReference {
+        forwarding::read_forwarding_pointer(object)
+    }
+
+    #[cfg(feature = "vm_forwarding")]
+    fn clear_forwarding_state(object: ObjectReference) {
+        forwarding::clear_forwarding_state(object)
+    }
 }
 
 impl crate::vm::ReferenceGlue<MockVM> for MockVM {
@@ -576,17 +743,33 @@ impl crate::vm::Scanning<MockVM> for MockVM {
         mutator: &'static mut Mutator<Self>,
         factory: impl RootsWorkFactory<<MockVM as VMBinding>::VMSlot>,
     ) {
-        mock_any!(scan_roots_in_mutator_thread(
+        mock!(scan_roots_in_mutator_thread(
             tls,
             mutator,
-            Box::new(factory)
+            Box::new(factory) as Box<dyn DynRootsWorkFactory>
         ))
     }
     fn scan_vm_specific_roots(
         tls: VMWorkerThread,
         factory: impl RootsWorkFactory<<MockVM as VMBinding>::VMSlot>,
     ) {
-        mock_any!(scan_vm_specific_roots(tls, Box::new(factory)))
+        mock!(scan_vm_specific_roots(
+            tls,
+            Box::new(factory) as Box<dyn DynRootsWorkFactory>
+        ))
+    }
+    fn stable_root_categories() -> usize {
+        mock!(stable_root_categories())
+    }
+    fn scan_stable_roots(
+        tls: VMWorkerThread,
+        category: usize,
+        slots: &mut Vec<<MockVM as VMBinding>::VMSlot>,
+    ) {
+        mock!(scan_stable_roots(tls, category, lifetime!(slots)))
+    }
+    fn clear_weak_slot(tls: VMWorkerThread, slot: Address) {
+        mock!(clear_weak_slot(tls, slot))
     }
     fn notify_initial_thread_scan_complete(partial_scan: bool, tls: VMWorkerThread) {
         mock!(notify_initial_thread_scan_complete(partial_scan, tls))
@@ -613,6 +796,38 @@ impl crate::vm::Scanning<MockVM> for MockVM {
     }
 }
 
+/// An object-safe counterpart of [`RootsWorkFactory`] for mocking the root scanning methods.  Each
+/// method forwards to the method of the same name in [`RootsWorkFactory`].
+pub trait DynRootsWorkFactory {
+    fn create_process_roots_work(&mut self, slots: Vec<Address>);
+    fn create_process_roots_descriptor_work(&mut self, roots: Box<dyn RootsDescriptor<Address>>);
+    fn create_process_pinning_roots_work(&mut self, nodes: Vec<ObjectReference>);
+    fn create_process_tpinning_roots_work(&mut self, nodes: Vec<ObjectReference>);
+    fn create_process_tpinning_roots_batch_work(&mut self, nodes: Vec<ObjectReference>, gcs: usize);
+}
+
+impl<F: RootsWorkFactory<Address>> DynRootsWorkFactory for F {
+    fn create_process_roots_work(&mut self, slots: Vec<Address>) {
+        RootsWorkFactory::create_process_roots_work(self, slots)
+    }
+    fn create_process_roots_descriptor_work(&mut self, roots: Box<dyn RootsDescriptor<Address>>) {
+        RootsWorkFactory::create_process_roots_descriptor_work(self, roots)
+    }
+    fn create_process_pinning_roots_work(&mut self, nodes: Vec<ObjectReference>) {
+        RootsWorkFactory::create_process_pinning_roots_work(self, nodes)
+    }
+    fn create_process_tpinning_roots_work(&mut self, nodes: Vec<ObjectReference>) {
+        RootsWorkFactory::create_process_tpinning_roots_work(self, nodes)
+    }
+    fn create_process_tpinning_roots_batch_work(
+        &mut self,
+        nodes: Vec<ObjectReference>,
+        gcs: usize,
+    ) {
+        RootsWorkFactory::create_process_tpinning_roots_batch_work(self, nodes, gcs)
+    }
+}
+
 impl MockVM {
     pub fn object_start_to_ref(start: Address) -> ObjectReference {
         ObjectReference::from_raw_address(start + DEFAULT_OBJECT_REF_OFFSET).unwrap()
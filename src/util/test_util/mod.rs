@@ -9,9 +9,15 @@ use std::time::Duration;
 #[cfg(feature = "mock_test")]
 pub mod fixtures;
 #[cfg(feature = "mock_test")]
+pub mod gc_fuzzer;
+#[cfg(feature = "mock_test")]
+pub mod mock_gc;
+#[cfg(feature = "mock_test")]
 pub mod mock_method;
 #[cfg(feature = "mock_test")]
 pub mod mock_vm;
+#[cfg(feature = "mock_test")]
+pub mod scenario;
 
 // Sometimes we need to mmap for tests. We want to ensure that the mmapped addresses do not overlap
 // for different tests, so we organize them here.
@@ -0,0 +1,256 @@
+//! Deterministic GC scenario tests with [`super::mock_vm::MockVM`].
+//!
+//! A [`Scenario`] declares named objects, the references between them and the roots, runs a
+//! sequence of mutations and GCs, and asserts on the liveness, the movement and the spaces of the
+//! objects afterwards.  For example,
+//!
+//! ```ignore
+//! let mut s = Scenario::new(|builder| builder.options.plan.set(PlanSelector::SemiSpace));
+//! let a = s.object("a", 1);
+//! let b = s.object("b", 0);
+//! s.root(a).link(a, 0, b).gc().assert_live(&[a, b]).assert_moved(&[a, b]);
+//! s.unlink(a, 0).gc().assert_live(&[a]).assert_dead(&[b]);
+//! ```
+//!
+//! After each GC, the scenario also checks that each live object still has its contents, and that
+//! the roots and the fields of live objects refer to the current addresses of the objects they were
+//! linked to.  The objects and the GCs are provided by [`MockGC`], which supports non-moving plans
+//! and `SemiSpace`.
+
+use super::mock_gc::*;
+use crate::memory_manager;
+use crate::util::ObjectReference;
+use crate::AllocationSemantics;
+use crate::MMTKBuilder;
+
+/// The number of root slots of a scenario.
+const MAX_ROOTS: usize = 64;
+
+/// The heap size of a scenario.  Scenarios only allocate a few objects.
+const HEAP_SIZE: usize = 8 * 1024 * 1024;
+
+/// An object declared in a [`Scenario`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ScenarioObject(usize);
+
+struct DeclaredObject {
+    name: String,
+    /// The index for [`MockGC::watched`].
+    watch: usize,
+    fields: Vec<Option<ScenarioObject>>,
+}
+
+/// A GC scenario.  See the module documentation.
+///
+/// This should be created in the test closure of [`super::mock_vm::with_mockvm`], as it replaces
+/// the `MockVM`.
+pub struct Scenario {
+    gc: MockGC,
+    objects: Vec<DeclaredObject>,
+    roots: Vec<Option<ScenarioObject>>,
+    /// The number of GCs checked.
+    checked_gcs: usize,
+}
+
+impl Scenario {
+    /// Create a scenario with an MMTk instance.  `with_builder` can set the plan and other
+    /// options.
+    pub fn new<F>(with_builder: F) -> Self
+    where
+        F: FnOnce(&mut MMTKBuilder),
+    {
+        Self {
+            gc: MockGC::new(MAX_ROOTS, HEAP_SIZE, with_builder),
+            objects: vec![],
+            roots: vec![None; MAX_ROOTS],
+            checked_gcs: 0,
+        }
+    }
+
+    pub fn mock_gc(&mut self) -> &mut MockGC {
+        &mut self.gc
+    }
+
+    /// Allocate an object with `num_fields` null fields.
+    pub fn object(&mut self, name: &str, num_fields: usize) -> ScenarioObject {
+        self.object_with_semantics(name, num_fields, AllocationSemantics::Default)
+    }
+
+    /// Allocate an object with `num_fields` null fields and the given allocation semantics.
+    pub fn object_with_semantics(
+        &mut self,
+        name: &str,
+        num_fields: usize,
+        semantics: AllocationSemantics,
+    ) -> ScenarioObject {
+        let handle = ScenarioObject(self.objects.len());
+        let object = self.gc.alloc(num_fields, handle.0 as u64, semantics);
+        self.check_gcs();
+        self.objects.push(DeclaredObject {
+            name: name.to_string(),
+            watch: self.gc.watch(object),
+            fields: vec![None; num_fields],
+        });
+        handle
+    }
+
+    fn declared(&self, object: ScenarioObject) -> &DeclaredObject {
+        &self.objects[object.0]
+    }
+
+    fn live_address(&self, object: ScenarioObject) -> ObjectReference {
+        let watched = self.gc.watched(self.declared(object).watch);
+        assert!(
+            watched.live,
+            "Object {} is dead",
+            self.declared(object).name
+        );
+        watched.object
+    }
+
+    /// Store `object` in a free root slot.
+    pub fn root(&mut self, object: ScenarioObject) -> &mut Self {
+        let address = self.live_address(object);
+        let index = self.roots.iter().position(|r| r.is_none()).unwrap();
+        self.gc.store_root(index, Some(address));
+        self.roots[index] = Some(object);
+        self
+    }
+
+    /// Clear all the root slots that hold `object`.
+    pub fn unroot(&mut self, object: ScenarioObject) -> &mut Self {
+        for index in 0..self.roots.len() {
+            if self.roots[index] == Some(object) {
+                self.gc.store_root(index, None);
+                self.roots[index] = None;
+            }
+        }
+        self
+    }
+
+    /// Store `target` in the field `index` of `src`.
+    pub fn link(&mut self, src: ScenarioObject, index: usize, target: ScenarioObject) -> &mut Self {
+        self.store_field(src, index, Some(target))
+    }
+
+    /// Store null in the field `index` of `src`.
+    pub fn unlink(&mut self, src: ScenarioObject, index: usize) -> &mut Self {
+        self.store_field(src, index, None)
+    }
+
+    fn store_field(
+        &mut self,
+        src: ScenarioObject,
+        index: usize,
+        target: Option<ScenarioObject>,
+    ) -> &mut Self {
+        let src_address = self.live_address(src);
+        let target_address = target.map(|t| self.live_address(t));
+        self.gc.store_field(src_address, index, target_address);
+        self.objects[src.0].fields[index] = target;
+        self
+    }
+
+    /// Request a GC, and wait until it finishes.
+    pub fn gc(&mut self) -> &mut Self {
+        assert!(self.gc.gc(), "The GC request is ignored");
+        self.check_gcs();
+        self
+    }
+
+    /// Check the heap if any GC has happened since the last check.
+    fn check_gcs(&mut self) {
+        let gcs = self.gc.gcs();
+        if gcs == self.checked_gcs {
+            return;
+        }
+        self.checked_gcs = gcs;
+
+        for (index, root) in self.roots.iter().enumerate() {
+            let expected = root.map(|r| self.live_address(r));
+            assert_eq!(self.gc.load_root(index), expected, "Root {}", index);
+        }
+        for (i, declared) in self.objects.iter().enumerate() {
+            if !self.is_live(ScenarioObject(i)) {
+                continue;
+            }
+            let address = self.live_address(ScenarioObject(i));
+            assert_eq!(object_id(address), i as u64, "Object {}", declared.name);
+            assert_eq!(num_fields(address), declared.fields.len());
+            for (index, field) in declared.fields.iter().enumerate() {
+                let expected = field.map(|f| self.live_address(f));
+                assert_eq!(
+                    load_field(address, index),
+                    expected,
+                    "Field {} of object {}",
+                    index,
+                    declared.name
+                );
+            }
+        }
+    }
+
+    /// The current address of an object.  This is the address before the object died if it is
+    /// dead.
+    pub fn address(&self, object: ScenarioObject) -> ObjectReference {
+        self.gc.watched(self.declared(object).watch).object
+    }
+
+    /// Whether the object has survived all the GCs since it was allocated.
+    pub fn is_live(&self, object: ScenarioObject) -> bool {
+        self.gc.watched(self.declared(object).watch).live
+    }
+
+    /// Whether the object was moved in the last GC.
+    pub fn is_moved(&self, object: ScenarioObject) -> bool {
+        self.gc.watched(self.declared(object).watch).moved
+    }
+
+    /// The name of the space of a live object.
+    pub fn space_name(&self, object: ScenarioObject) -> &'static str {
+        let address = self.live_address(object);
+        memory_manager::object_space_info(self.gc.mmtk(), address)
+            .unwrap()
+            .space_name
+    }
+
+    pub fn assert_live(&mut self, objects: &[ScenarioObject]) -> &mut Self {
+        for &object in objects {
+            let name = &self.declared(object).name;
+            assert!(self.is_live(object), "Object {} is dead", name);
+        }
+        self
+    }
+
+    pub fn assert_dead(&mut self, objects: &[ScenarioObject]) -> &mut Self {
+        for &object in objects {
+            let name = &self.declared(object).name;
+            assert!(!self.is_live(object), "Object {} is live", name);
+        }
+        self
+    }
+
+    pub fn assert_moved(&mut self, objects: &[ScenarioObject]) -> &mut Self {
+        for &object in objects {
+            let name = &self.declared(object).name;
+            assert!(self.is_moved(object), "Object {} is not moved", name);
+        }
+        self
+    }
+
+    pub fn assert_not_moved(&mut self, objects: &[ScenarioObject]) -> &mut Self {
+        for &object in objects {
+            let name = &self.declared(object).name;
+            assert!(!self.is_moved(object), "Object {} is moved", name);
+        }
+        self
+    }
+
+    pub fn assert_in_space(&mut self, objects: &[ScenarioObject], space_name: &str) -> &mut Self {
+        for &object in objects {
+            let name = &self.declared(object).name;
+            assert_eq!(self.space_name(object), space_name, "Object {}", name);
+        }
+        self
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::HashMap;
 use std::mem::swap;
 use std::sync::Mutex;
 
@@ -6,11 +6,14 @@ use crate::util::ObjectReference;
 
 use super::object_enum::ObjectEnumerator;
 
+/// The large objects of a large object space.  Each object is kept with the number of pages
+/// allocated for it, so that the space can account the pages of an object without looking up its
+/// page resource.
 pub struct TreadMill {
-    from_space: Mutex<HashSet<ObjectReference>>,
-    to_space: Mutex<HashSet<ObjectReference>>,
-    collect_nursery: Mutex<HashSet<ObjectReference>>,
-    alloc_nursery: Mutex<HashSet<ObjectReference>>,
+    from_space: Mutex<HashMap<ObjectReference, usize>>,
+    to_space: Mutex<HashMap<ObjectReference, usize>>,
+    collect_nursery: Mutex<HashMap<ObjectReference, usize>>,
+    alloc_nursery: Mutex<HashMap<ObjectReference, usize>>,
 }
 
 impl std::fmt::Debug for TreadMill {
@@ -27,26 +30,26 @@ impl std::fmt::Debug for TreadMill {
 impl TreadMill {
     pub fn new() -> Self {
         TreadMill {
-            from_space: Mutex::new(HashSet::new()),
-            to_space: Mutex::new(HashSet::new()),
-            collect_nursery: Mutex::new(HashSet::new()),
-            alloc_nursery: Mutex::new(HashSet::new()),
+            from_space: Mutex::new(HashMap::new()),
+            to_space: Mutex::new(HashMap::new()),
+            collect_nursery: Mutex::new(HashMap::new()),
+            alloc_nursery: Mutex::new(HashMap::new()),
         }
     }
 
-    pub fn add_to_treadmill(&self, object: ObjectReference, nursery: bool) {
+    pub fn add_to_treadmill(&self, object: ObjectReference, nursery: bool, pages: usize) {
         if nursery {
             trace!("Adding {} to nursery", object);
-            self.alloc_nursery.lock().unwrap().insert(object);
+            self.alloc_nursery.lock().unwrap().insert(object, pages);
         } else {
             trace!("Adding {} to to_space", object);
-            self.to_space.lock().unwrap().insert(object);
+            self.to_space.lock().unwrap().insert(object, pages);
         }
     }
 
     pub fn collect_nursery(&self) -> Vec<ObjectReference> {
         let mut guard = self.collect_nursery.lock().unwrap();
-        let vals = guard.iter().copied().collect();
+        let vals = guard.keys().copied().collect();
         guard.clear();
         drop(guard);
         vals
@@ -54,31 +57,27 @@ impl TreadMill {
 
     pub fn collect(&self) -> Vec<ObjectReference> {
         let mut guard = self.from_space.lock().unwrap();
-        let vals = guard.iter().copied().collect();
+        let vals = guard.keys().copied().collect();
         guard.clear();
         drop(guard);
         vals
     }
 
-    pub fn copy(&self, object: ObjectReference, is_in_nursery: bool) {
-        if is_in_nursery {
+    /// Move a live object to the to-space, and return the number of pages of the object.
+    pub fn copy(&self, object: ObjectReference, is_in_nursery: bool) -> usize {
+        let pages = if is_in_nursery {
             let mut guard = self.collect_nursery.lock().unwrap();
-            debug_assert!(
-                guard.contains(&object),
-                "copy source object ({}) must be in collect_nursery",
-                object
-            );
-            guard.remove(&object);
+            guard.remove(&object).unwrap_or_else(|| {
+                panic!("copy source object ({}) must be in collect_nursery", object)
+            })
         } else {
             let mut guard = self.from_space.lock().unwrap();
-            debug_assert!(
-                guard.contains(&object),
-                "copy source object ({}) must be in from_space",
-                object
-            );
-            guard.remove(&object);
-        }
-        self.to_space.lock().unwrap().insert(object);
+            guard
+                .remove(&object)
+                .unwrap_or_else(|| panic!("copy source object ({}) must be in from_space", object))
+        };
+        self.to_space.lock().unwrap().insert(object, pages);
+        pages
     }
 
     pub fn is_to_space_empty(&self) -> bool {
@@ -103,9 +102,9 @@ impl TreadMill {
     }
 
     pub(crate) fn enumerate_objects(&self, enumerator: &mut dyn ObjectEnumerator) {
-        let mut visit_objects = |set: &Mutex<HashSet<ObjectReference>>| {
+        let mut visit_objects = |set: &Mutex<HashMap<ObjectReference, usize>>| {
             let set = set.lock().unwrap();
-            for object in set.iter() {
+            for object in set.keys() {
                 enumerator.visit_object(*object);
             }
         };
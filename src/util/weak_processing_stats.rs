@@ -0,0 +1,155 @@
+//! Statistics of weak reference processing and finalization in each GC.
+//!
+//! The reference processors and the finalizable processor count what they do in each GC, and
+//! report the counts to the global `WeakProcessingAccounting`.  When the GC finishes, the counts
+//! are combined with the time of the weak reference processing stages (see
+//! [`crate::scheduler::GCPhaseTimes`]), added to the harness statistics, and kept for
+//! [`crate::MMTK::last_gc_weak_processing_stats`].
+
+use crate::scheduler::{GCPhaseTimes, WorkBucketStage};
+use crate::util::statistics::counter::EventCounter;
+use crate::util::statistics::stats::Stats;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// What a reference processor did for the references of one strength in a GC.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ReferenceStats {
+    /// The number of reference objects in the reference table that were processed in the GC.
+    pub candidates: usize,
+    /// The number of reference objects whose referents were cleared, and are enqueued to the
+    /// binding.
+    pub enqueued: usize,
+    /// The number of referents kept alive because their reference objects were reachable.  This is
+    /// only non-zero for soft references, which are not retained in emergency GCs.
+    pub retained: usize,
+}
+
+/// What the finalizable processor did in a GC.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FinalizationStats {
+    /// The number of finalizable objects registered by the binding since the previous GC.
+    pub registered: usize,
+    /// The number of finalizable objects whose liveness was checked in the GC.  In nursery GCs,
+    /// only the objects registered since the previous GC and the objects not yet popped by the
+    /// binding are checked.
+    pub candidates: usize,
+    /// The number of finalizable objects found dead and made ready for finalization in the GC.
+    pub ready: usize,
+}
+
+/// The statistics of weak reference processing and finalization in a GC.  See
+/// [`crate::MMTK::last_gc_weak_processing_stats`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WeakProcessingStats {
+    pub soft: ReferenceStats,
+    pub weak: ReferenceStats,
+    pub phantom: ReferenceStats,
+    pub finalization: FinalizationStats,
+    /// The time of the `SoftRefClosure` stage, including the closure from retained referents.
+    pub soft_ref_time: Duration,
+    /// The time of the `WeakRefClosure` stage.
+    pub weak_ref_time: Duration,
+    /// The time of the `FinalRefClosure` stage, including the closure from finalizable objects.
+    pub final_ref_time: Duration,
+    /// The time of the `PhantomRefClosure` stage.
+    pub phantom_ref_time: Duration,
+    /// The time of the `VMRefClosure` and `VMUnloading` stages, in which the binding and MMTk
+    /// process other weak data structures.
+    pub vm_weak_time: Duration,
+}
+
+type StatsField = fn(&WeakProcessingStats) -> u64;
+
+/// The harness counters of the statistics.  Times are in microseconds.
+const COUNTERS: [(&str, StatsField); 15] = [
+    ("refs.soft.candidates", |s| s.soft.candidates as u64),
+    ("refs.soft.enqueued", |s| s.soft.enqueued as u64),
+    ("refs.soft.retained", |s| s.soft.retained as u64),
+    ("refs.weak.candidates", |s| s.weak.candidates as u64),
+    ("refs.weak.enqueued", |s| s.weak.enqueued as u64),
+    ("refs.phantom.candidates", |s| s.phantom.candidates as u64),
+    ("refs.phantom.enqueued", |s| s.phantom.enqueued as u64),
+    ("finalizer.registered", |s| s.finalization.registered as u64),
+    ("finalizer.candidates", |s| s.finalization.candidates as u64),
+    ("finalizer.ready", |s| s.finalization.ready as u64),
+    ("weak.softRefTime.us", |s| {
+        s.soft_ref_time.as_micros() as u64
+    }),
+    ("weak.weakRefTime.us", |s| {
+        s.weak_ref_time.as_micros() as u64
+    }),
+    ("weak.finalRefTime.us", |s| {
+        s.final_ref_time.as_micros() as u64
+    }),
+    ("weak.phantomRefTime.us", |s| {
+        s.phantom_ref_time.as_micros() as u64
+    }),
+    ("weak.vmWeakTime.us", |s| s.vm_weak_time.as_micros() as u64),
+];
+
+/// Collects the statistics of weak reference processing and finalization in the current GC.
+pub(crate) struct WeakProcessingAccounting {
+    /// The statistics of the current GC.
+    current: Mutex<WeakProcessingStats>,
+    /// The statistics of the last finished GC.
+    last_gc: Mutex<WeakProcessingStats>,
+    /// The counters for the harness statistics, in the order of `COUNTERS`.
+    counters: Vec<Arc<Mutex<EventCounter>>>,
+}
+
+impl WeakProcessingAccounting {
+    pub fn new(stats: &Stats) -> Self {
+        Self {
+            current: Mutex::default(),
+            last_gc: Mutex::default(),
+            counters: COUNTERS
+                .iter()
+                .map(|(name, _)| stats.new_event_counter(name, true, true))
+                .collect(),
+        }
+    }
+
+    /// Set the statistics of the reference processors.  Called when the references are enqueued.
+    pub fn on_references_enqueued(
+        &self,
+        soft: ReferenceStats,
+        weak: ReferenceStats,
+        phantom: ReferenceStats,
+    ) {
+        let mut current = self.current.lock().unwrap();
+        current.soft = soft;
+        current.weak = weak;
+        current.phantom = phantom;
+    }
+
+    /// Set the statistics of the finalizable processor.  Called after finalizable objects are
+    /// scanned.
+    pub fn on_finalizables_scanned(&self, finalization: FinalizationStats) {
+        self.current.lock().unwrap().finalization = finalization;
+    }
+
+    /// Called right before the mutators are resumed, with the phase times of the GC.
+    pub fn on_gc_end(&self, phase_times: &GCPhaseTimes) -> WeakProcessingStats {
+        let mut stats = std::mem::take(&mut *self.current.lock().unwrap());
+        let buckets = &phase_times.buckets;
+        stats.soft_ref_time = buckets[WorkBucketStage::SoftRefClosure];
+        stats.weak_ref_time = buckets[WorkBucketStage::WeakRefClosure];
+        stats.final_ref_time = buckets[WorkBucketStage::FinalRefClosure];
+        stats.phantom_ref_time = buckets[WorkBucketStage::PhantomRefClosure];
+        stats.vm_weak_time =
+            buckets[WorkBucketStage::VMRefClosure] + buckets[WorkBucketStage::VMUnloading];
+
+        for ((_, field), counter) in COUNTERS.iter().zip(self.counters.iter()) {
+            counter.lock().unwrap().inc_by(field(&stats));
+        }
+
+        *self.last_gc.lock().unwrap() = stats;
+        stats
+    }
+
+    /// Get the statistics of the last finished GC.
+    pub fn last_gc(&self) -> WeakProcessingStats {
+        *self.last_gc.lock().unwrap()
+    }
+}
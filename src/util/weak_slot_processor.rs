@@ -0,0 +1,101 @@
+//! Processing of weak slots, i.e. reference fields that do not keep their referents alive.
+//!
+//! A binding reports a weak slot by calling [`crate::vm::SlotVisitor::visit_weak_slot`] in
+//! [`crate::vm::Scanning::scan_object`].  MMTk does not trace through weak slots.  Instead, it
+//! records them, and processes them after the strong transitive closure is computed.  If the
+//! referent of a weak slot is live, MMTk updates the slot in case the referent is moved;
+//! otherwise, MMTk asks the binding to clear the slot with [`crate::vm::Scanning::clear_weak_slot`].
+//!
+//! The weak slots are processed in the [`WorkBucketStage::WeakRefClosure`] stage, i.e. at the
+//! same time as Java-style weak references.  Weak slots visited after that (for example, in
+//! objects resurrected by finalization) are treated as strong slots, so that their referents are
+//! kept alive and forwarded as usual.  This also means that in the second transitive closure of
+//! MarkCompact, weak slots that were not cleared are forwarded like strong slots.
+
+use crate::scheduler::{GCWork, GCWorker, WorkBucketStage};
+use crate::util::VMWorkerThread;
+use crate::vm::slot::Slot;
+use crate::vm::{Scanning, VMBinding};
+use crate::MMTK;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/// Record the weak slots found in the current GC, and process them after the strong closure.
+pub(crate) struct WeakSlotProcessor<SL: Slot> {
+    /// The weak slots visited in the current GC.
+    slots: Mutex<Vec<SL>>,
+    /// True if the weak slots of the current GC have not been processed yet.  Weak slots visited
+    /// when this is false should be treated as strong slots.
+    accepting: AtomicBool,
+}
+
+impl<SL: Slot> WeakSlotProcessor<SL> {
+    pub fn new() -> Self {
+        Self {
+            slots: Mutex::new(vec![]),
+            accepting: AtomicBool::new(true),
+        }
+    }
+
+    /// Start recording weak slots for a new GC.
+    pub fn prepare(&self) {
+        debug_assert!(self.slots.lock().unwrap().is_empty());
+        self.accepting.store(true, Ordering::Relaxed);
+    }
+
+    /// Return true if weak slots should be recorded with [`WeakSlotProcessor::add_slots`], or false
+    /// if they should be treated as strong slots.
+    pub fn is_accepting(&self) -> bool {
+        self.accepting.load(Ordering::Relaxed)
+    }
+
+    /// Record weak slots.
+    pub fn add_slots(&self, slots: &mut Vec<SL>) {
+        debug_assert!(self.is_accepting());
+        self.slots.lock().unwrap().append(slots);
+    }
+
+    /// Update the weak slots whose referents are live, and clear the other weak slots.
+    pub fn process<VM: VMBinding<VMSlot = SL>>(&self, tls: VMWorkerThread) {
+        self.accepting.store(false, Ordering::Relaxed);
+        let slots = std::mem::take(&mut *self.slots.lock().unwrap());
+        debug!("Processing {} weak slots", slots.len());
+
+        for slot in slots {
+            let Some(referent) = slot.load() else {
+                continue;
+            };
+            if referent.is_live() {
+                if let Some(new_referent) = referent.get_forwarded_object() {
+                    trace!(
+                        "Weak slot {:?}: {} forwarded to {}",
+                        slot,
+                        referent,
+                        new_referent
+                    );
+                    slot.store(new_referent);
+                }
+            } else {
+                trace!("Weak slot {:?}: {} is dead", slot, referent);
+                VM::VMScanning::clear_weak_slot(tls, slot);
+            }
+        }
+    }
+}
+
+/// Process the weak slots recorded in the current GC.
+#[derive(Default)]
+pub(crate) struct ProcessWeakSlots<VM: VMBinding>(PhantomData<VM>);
+
+impl<VM: VMBinding> GCWork<VM> for ProcessWeakSlots<VM> {
+    fn do_work(&mut self, worker: &mut GCWorker<VM>, mmtk: &'static MMTK<VM>) {
+        mmtk.weak_slot_processor.process::<VM>(worker.tls);
+    }
+}
+
+impl<VM: VMBinding> ProcessWeakSlots<VM> {
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
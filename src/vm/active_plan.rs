@@ -38,6 +38,8 @@ pub trait ActivePlan<VM: VMBinding> {
     /// copy an object if it is in 'from space', and enqueue the copied object for later scanning.
     ///
     /// If a binding would like to trace objects that are not allocated by MMTk and are not in any MMTk space, they can override this method.
+    /// Alternatively, they can register those objects with [`crate::memory_manager::register_off_heap_objects`], and MMTk only calls this
+    /// method for objects that do not belong to any registered kind of off-heap objects.
     /// They should check whether the object is encountered before in this current GC. If not, they should record the object as encountered themselves,
     /// and enqueue the object reference to the object queue provided by the argument. If a binding moves objects, they should do the copying in the method,
     /// and enqueue the new object reference instead.
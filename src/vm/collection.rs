@@ -1,4 +1,4 @@
-use crate::util::alloc::AllocationError;
+use crate::util::alloc::{AllocationError, AllocationErrorContext};
 use crate::util::heap::gc_trigger::GCTriggerPolicy;
 use crate::util::opaque_pointer::*;
 use crate::vm::VMBinding;
@@ -71,7 +71,9 @@ pub trait Collection<VM: VMBinding> {
     /// Arguments:
     /// * `tls`: The thread pointer for the mutator which failed the allocation and triggered the OOM.
     /// * `err_kind`: The type of OOM error that was encountered.
-    fn out_of_memory(_tls: VMThread, err_kind: AllocationError) {
+    /// * `context`: The context of the error, including the space that failed to allocate, the
+    ///   requested size, the heap usage and whether an emergency GC was attempted.
+    fn out_of_memory(_tls: VMThread, err_kind: AllocationError, _context: &AllocationErrorContext) {
         panic!("Out of memory with {:?}!", err_kind);
     }
 
@@ -150,6 +152,8 @@ pub trait Collection<VM: VMBinding> {
     /// `handle_user_collection_request()` calls this function, too.  If this function returns
     /// false, `handle_user_collection_request()` will not trigger GC, either. Note also that any synchronization
     /// involving enabling and disabling collections by mutator threads should be implemented by the VM.
+    /// Alternatively, a VM can use [`crate::memory_manager::gc_disabled_scope`], which counts
+    /// nested scopes and collects statistics.
     fn is_collection_enabled() -> bool {
         // By default, MMTk assumes that collections are always enabled, and the binding should define
         // this method if the VM supports disabling GC, or if the VM cannot safely trigger GC until some
@@ -157,9 +161,37 @@ pub trait Collection<VM: VMBinding> {
         true
     }
 
+    /// Inform the binding that MMTk would have triggered a GC, but GC is disabled by
+    /// [`crate::memory_manager::gc_disabled_scope`].  The heap may keep growing beyond its limit
+    /// until all the GC-disabled scopes end.  This is called when the number of suppressed GCs
+    /// reaches a power of two, i.e. less and less often as the count grows, so the binding may
+    /// escalate its response, e.g. by reporting a scope that is held for too long.
+    ///
+    /// Arguments:
+    /// * `tls`: The mutator thread that would have been blocked for the GC.
+    /// * `suppressed`: The number of GCs suppressed by GC-disabled scopes so far.
+    fn gc_suppressed(_tls: VMMutatorThread, _suppressed: usize) {}
+
     /// Ask the binding to create a [`GCTriggerPolicy`] if the option `gc_trigger` is set to
     /// `crate::util::options::GCTriggerSelector::Delegated`.
     fn create_gc_trigger() -> Box<dyn GCTriggerPolicy<VM>> {
         unimplemented!()
     }
+
+    /// Ask all mutators to call [`crate::memory_manager::handshake_yieldpoint`] at their next
+    /// yieldpoints, because a handshake is requested.  This method should return without waiting
+    /// for the mutators.  The VM may call `handshake_yieldpoint` on behalf of mutators that are
+    /// blocked and cannot reach yieldpoints in time.  See [`crate::scheduler::Handshake`].
+    ///
+    /// MMTk only requests handshakes if the binding calls
+    /// [`crate::memory_manager::request_handshake`] or
+    /// [`crate::memory_manager::flush_all_mutators`], or enables the options that flush or reclaim
+    /// the buffers of idle mutators (`idle_mutator_flush_timeout` and
+    /// `reclaim_idle_mutator_blocks`).  A VM that does either must implement this method.
+    ///
+    /// Arguments:
+    /// * `tls`: The thread pointer for the thread that requests the handshake.
+    fn request_handshake(_tls: VMThread) {
+        unimplemented!("request_handshake() must be implemented if the binding uses request_handshake() or flush_all_mutators(), or enables idle_mutator_flush_timeout or reclaim_idle_mutator_blocks.")
+    }
 }
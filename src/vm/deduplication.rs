@@ -0,0 +1,35 @@
+use crate::util::{Address, ObjectReference};
+use crate::vm::VMBinding;
+
+/// Deduplicates the values of objects in GC, like the string deduplication of HotSpot.  A binding
+/// registers an implementation with [`crate::memory_manager::register_deduplication`], and adds
+/// the objects whose values may be deduplicated (e.g. strings) as candidates with
+/// [`crate::memory_manager::add_deduplication_candidate`].
+///
+/// In each GC, after the binding processes its weak references, MMTk gets the
+/// [`value`](Deduplication::value) of each live candidate (e.g. the byte array of a string), and
+/// hashes its [`contents`](Deduplication::contents).  If MMTk has seen a value with the same
+/// contents, in the current GC or a previous GC, it calls [`set_value`](Deduplication::set_value)
+/// to redirect the candidate to that canonical value, and the original value is reclaimed in a
+/// later GC if nothing else refers to it.  Otherwise, the value becomes the canonical value for its
+/// contents.  MMTk does not keep canonical values alive, and forgets them when they die.  Each
+/// candidate is only processed in the first GC after it is added.
+///
+/// The methods are called by a GC worker.  They must not allocate objects, and the binding must
+/// not add candidates from them.  All the candidates of a GC are processed by one GC worker in
+/// one work packet, so the methods are never called concurrently, but the time they take adds
+/// to the GC pause.
+pub trait Deduplication<VM: VMBinding>: Send + Sync + 'static {
+    /// Return the value of a live candidate, or `None` if the candidate has no value to
+    /// deduplicate.  The fields of the candidate refer to the current addresses of their objects.
+    fn value(&self, candidate: ObjectReference) -> Option<ObjectReference>;
+
+    /// Return the start address and the size in bytes of the contents of a value.  Two values are
+    /// interchangeable if their contents are equal byte by byte, so the contents must not include
+    /// per-object data such as hash codes or locks.
+    fn contents(&self, value: ObjectReference) -> (Address, usize);
+
+    /// Redirect a candidate to `value`, which has the same contents as the current value of the
+    /// candidate.
+    fn set_value(&self, candidate: ObjectReference, value: ObjectReference);
+}
@@ -17,19 +17,29 @@
 
 mod active_plan;
 mod collection;
+mod deduplication;
 pub(crate) mod object_model;
+mod off_heap_objects;
+mod pointer_offsets;
 mod reference_glue;
 mod scanning;
 pub mod slot;
 pub use self::active_plan::ActivePlan;
 pub use self::collection::Collection;
 pub use self::collection::GCThreadContext;
+pub use self::deduplication::Deduplication;
 pub use self::object_model::specs::*;
+#[cfg(feature = "vm_forwarding")]
+pub use self::object_model::ForwardingState;
 pub use self::object_model::ObjectModel;
+pub use self::off_heap_objects::OffHeapObjects;
+pub use self::pointer_offsets::PointerOffsets;
 pub use self::reference_glue::Finalizable;
 pub use self::reference_glue::ReferenceGlue;
 pub use self::scanning::ObjectTracer;
 pub use self::scanning::ObjectTracerContext;
+pub use self::scanning::RootSlotTable;
+pub use self::scanning::RootsDescriptor;
 pub use self::scanning::RootsWorkFactory;
 pub use self::scanning::Scanning;
 pub use self::scanning::SlotVisitor;
@@ -123,6 +123,13 @@ pub trait ObjectModel<VM: VMBinding> {
     // TODO: Cleanup and place the LOS mark and nursery bits in the header. See here: https://github.com/mmtk/mmtk-core/issues/847
     const LOCAL_LOS_MARK_NURSERY_SPEC: VMLocalLOSMarkNurserySpec;
 
+    #[cfg(feature = "address_based_hashing")]
+    /// A global 2-bit metadata for the hash state of an object, used by
+    /// [`crate::memory_manager::object_hash`]. It can be in the header or in side metadata. If it is
+    /// in the header, the bits must be copied with the object. Note that the binding also needs to
+    /// include the hash word in the object sizes. See [`crate::util::object_hash`] for the protocol.
+    const GLOBAL_HASH_STATE_SPEC: VMGlobalHashStateSpec;
+
     /// Set this to true if the VM binding requires the valid object (VO) bits to be available
     /// during tracing. If this constant is set to `false`, it is undefined behavior if the binding
     /// attempts to access VO bits during tracing.
@@ -467,15 +474,138 @@ pub trait ObjectModel<VM: VMBinding> {
 
     /// Dump debugging information for an object.
     ///
+    /// With the feature "alloc_site", the binding may include the allocation site of the object,
+    /// which can be queried with [`crate::memory_manager::get_allocation_site`].
+    ///
     /// Arguments:
     /// * `object`: The object to be dumped.
     fn dump_object(object: ObjectReference);
 
+    /// Return the name of the type of an object. This is used by the live object demographics
+    /// analysis (with the feature "analysis") to classify live objects in a GC. A binding may
+    /// return any string that identifies the type (e.g. a class name), as long as the string lives
+    /// as long as the program. The default implementation returns `"unknown"` for all objects.
+    ///
+    /// Arguments:
+    /// * `object`: The object to be queried.
+    fn get_type_name(_object: ObjectReference) -> &'static str {
+        "unknown"
+    }
+
     /// Return if an object is valid from the runtime point of view. This is used
     /// to debug MMTk.
     fn is_object_sane(_object: ObjectReference) -> bool {
         true
     }
+
+    /// Return a descriptor of the reference fields of an object, or `None` if the object should be
+    /// scanned by [`crate::vm::Scanning::scan_object`] or
+    /// [`crate::vm::Scanning::scan_object_and_trace_edges`].
+    ///
+    /// If this returns `Some`, the `ScanObjects` work packet iterates the slots of the object by
+    /// itself, and creates each slot with [`crate::vm::Scanning::create_slot`].  This avoids calling
+    /// back to the VM for each object, and is efficient for VMs with simple object layouts.  The
+    /// binding still needs to implement `Scanning::scan_object` for all objects, because other
+    /// components (such as the heap verifiers) scan objects with it.
+    ///
+    /// This method is called for every object to be scanned, so it must be fast.  The default
+    /// implementation returns `None` for all objects.
+    ///
+    /// Arguments:
+    /// * `object`: The object to be scanned.
+    fn get_pointer_offsets(_object: ObjectReference) -> Option<crate::vm::PointerOffsets> {
+        None
+    }
+
+    // The following methods let the binding keep the forwarding state of objects by itself (with
+    // the feature "vm_forwarding").  mmtk-core does not access `LOCAL_FORWARDING_BITS_SPEC` or
+    // `LOCAL_FORWARDING_POINTER_SPEC` when the feature is enabled, and the binding should declare
+    // both of them `in_header` so that no side metadata is reserved for them.  The binding may
+    // store the state in any part of the header, such as a few bits of a byte that is shared with
+    // other runtime flags, and store the forwarding pointer elsewhere (e.g. over the type
+    // pointer).  It is fine to destroy the header of the old copy of an object after it is
+    // forwarded.
+    //
+    // The protocol is the same as the built-in forwarding bits.  An object starts `NotForwarded`.
+    // A GC worker claims it with `attempt_to_forward`, copies it, and publishes the new copy with
+    // `set_forwarded`.  Other workers that attempt to forward the same object see the old state
+    // `BeingForwarded` or `Forwarded`, wait until it becomes `Forwarded`, and read the new copy
+    // with `read_forwarding_pointer`.  Some policies may revert a claimed object to
+    // `NotForwarded` with `clear_forwarding_state` if they decide not to move the object.
+
+    /// Atomically load the forwarding state of an object.  This is called while other GC workers
+    /// may be forwarding the object, and its result must be consistent with the other methods.
+    ///
+    /// Arguments:
+    /// * `object`: The object to be queried.
+    #[cfg(feature = "vm_forwarding")]
+    fn get_forwarding_state(object: ObjectReference) -> ForwardingState;
+
+    /// Return if an object has been forwarded.  mmtk-core calls this method when checking the
+    /// liveness of objects or updating references, which multiple GC workers may do at the same
+    /// time, and which may happen while other GC workers are forwarding other objects in the same
+    /// space.  The implementation must be thread-safe, and must read the forwarding state
+    /// atomically, like [`Self::get_forwarding_state`].  The default implementation calls
+    /// [`Self::get_forwarding_state`].
+    ///
+    /// Arguments:
+    /// * `object`: The object to be queried.
+    #[cfg(feature = "vm_forwarding")]
+    fn is_forwarded(object: ObjectReference) -> bool {
+        Self::get_forwarding_state(object) == ForwardingState::Forwarded
+    }
+
+    /// Atomically set the forwarding state of an object to `BeingForwarded` if it is
+    /// `NotForwarded`, and return the state before the operation.  The current GC worker wins the
+    /// race to forward the object if and only if the returned state is `NotForwarded`.  This method
+    /// must not change the state if it is already `BeingForwarded` or `Forwarded`.
+    ///
+    /// Arguments:
+    /// * `object`: The object to be forwarded.
+    #[cfg(feature = "vm_forwarding")]
+    fn attempt_to_forward(object: ObjectReference) -> ForwardingState;
+
+    /// Record `new_object` as the forwarding pointer of `object`, and set its forwarding state to
+    /// `Forwarded`.  This is called by the GC worker that won the race to forward `object`.  The
+    /// state must be published with (at least) the release order, after the forwarding pointer,
+    /// so that other workers that see `Forwarded` also see the new copy.
+    ///
+    /// Arguments:
+    /// * `object`: The object being forwarded.
+    /// * `new_object`: The new copy of the object.
+    #[cfg(feature = "vm_forwarding")]
+    fn set_forwarded(object: ObjectReference, new_object: ObjectReference);
+
+    /// Return the forwarding pointer of an object that has been forwarded.  This is only called
+    /// after the current GC worker has seen the state `Forwarded` for the object.
+    ///
+    /// Arguments:
+    /// * `object`: The forwarded object.
+    #[cfg(feature = "vm_forwarding")]
+    fn read_forwarding_pointer(object: ObjectReference) -> ObjectReference;
+
+    /// Atomically set the forwarding state of an object to `NotForwarded`.  This is called on new
+    /// copies of objects (whose headers are copied from the forwarded objects), and on objects
+    /// that a policy decides not to move after claiming them.  It must not disturb other header
+    /// bits that may be updated concurrently.
+    ///
+    /// Arguments:
+    /// * `object`: The object to be cleared.
+    #[cfg(feature = "vm_forwarding")]
+    fn clear_forwarding_state(object: ObjectReference);
+}
+
+/// The forwarding state of an object, kept by the binding with the feature "vm_forwarding".  See
+/// the forwarding methods of [`ObjectModel`].
+#[cfg(feature = "vm_forwarding")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ForwardingState {
+    /// The object has not been claimed by any GC worker.
+    NotForwarded,
+    /// A GC worker has claimed the object, and is copying it.
+    BeingForwarded,
+    /// The object has been copied, and its forwarding pointer is available.
+    Forwarded,
 }
 
 pub mod specs {
@@ -588,6 +718,14 @@ pub mod specs {
         0,
         LOG_MIN_OBJECT_SIZE
     );
+    // Hash state: 2 bits per object, global
+    define_vm_metadata_spec!(
+        /// 2-bit global metadata for the hash state of an object: unhashed, hashed, or hashed and moved.
+        VMGlobalHashStateSpec,
+        true,
+        1,
+        LOG_MIN_OBJECT_SIZE
+    );
     // Forwarding pointer: word size per object, local
     define_vm_metadata_spec!(
         /// 1-word local metadata for spaces that may copy objects.
@@ -0,0 +1,65 @@
+use crate::util::ObjectReference;
+use crate::util::VMWorkerThread;
+use crate::vm::VMBinding;
+
+/// A kind of objects that the VM allocates outside MMTk spaces (for example, with `malloc`), but
+/// that participate in tracing like objects in MMTk spaces.  They may be referred to from MMTk
+/// objects, and may refer to MMTk objects.  A binding registers each kind with
+/// [`crate::memory_manager::register_off_heap_objects`].
+///
+/// When MMTk traces an object that is not in any MMTk space, it asks each registered kind in the
+/// order of registration whether the object [`contains`](OffHeapObjects::contains) it.  If a kind
+/// contains the object, MMTk marks the object with [`test_and_mark`](OffHeapObjects::test_and_mark),
+/// and scans it with [`crate::vm::Scanning::scan_object`] (or
+/// `Scanning::scan_object_and_trace_edges`) if it is marked for the first time, like an object in
+/// MMTk spaces.  Off-heap objects are never moved.  If no kind contains the object, MMTk falls back
+/// to [`crate::vm::ActivePlan::vm_trace_object`].
+///
+/// At the start of each GC, MMTk calls [`prepare`](OffHeapObjects::prepare) for each kind in a
+/// `PrepareOffHeapObjects` work packet, and at the end of each GC, it calls
+/// [`release`](OffHeapObjects::release) in a `ReleaseOffHeapObjects` work packet, where the binding
+/// may free the objects that are not marked.  MMTk counts the objects traced for each kind, and
+/// reports the count as the statistics counter `offheap.<name>`.
+///
+/// Off-heap objects are treated like mature objects in nursery GCs of generational plans.  MMTk
+/// does not trace them in a nursery GC, so they are not marked even if they are reachable.  Both
+/// `prepare` and `release` are told whether the GC is a full-heap GC, and the binding must only
+/// clear the marks and free the unmarked objects in full-heap GCs.  MMTk does not remember the
+/// references from off-heap objects to young objects, either.  The binding should report such
+/// references as roots in nursery GCs.
+///
+/// Like `vm_trace_object`, this is not supported if objects are traced through the SFT (i.e. by
+/// `SFTProcessEdges`).  Note also that MMTk does not know the liveness of off-heap objects in other places, such as
+/// [`crate::util::ObjectReference::is_live`].  They should not be the referents of weak references
+/// processed by MMTk.
+pub trait OffHeapObjects<VM: VMBinding>: Send + Sync + 'static {
+    /// The name of this kind of objects, used in logs and statistics.
+    fn name(&self) -> &str;
+
+    /// Return true if `object` is an object of this kind.  This is called for every object that
+    /// is traced and is not in any MMTk space, so it must be fast.
+    fn contains(&self, object: ObjectReference) -> bool;
+
+    /// Mark `object` as live in the current GC.  Return true if this call marked the object, or
+    /// false if the object was already marked in the current GC.  This may be called by multiple
+    /// GC workers at the same time, and exactly one of them should get `true` for each live object.
+    fn test_and_mark(&self, object: ObjectReference) -> bool;
+
+    /// Prepare for a GC, e.g. clear the marks of all the objects of this kind.  MarkCompact calls
+    /// this again before its second transitive closure, so that every live object is scanned again
+    /// to forward its reference fields.
+    ///
+    /// Arguments:
+    /// * `tls`: The VM-specific thread-local storage for the current worker.
+    /// * `full_heap`: Whether the GC is a full-heap GC.  Off-heap objects are not traced in a
+    ///   nursery GC, and their marks should be kept.
+    fn prepare(&self, _tls: VMWorkerThread, _full_heap: bool) {}
+
+    /// Release the objects of this kind that are not marked in the GC.
+    ///
+    /// Arguments:
+    /// * `tls`: The VM-specific thread-local storage for the current worker.
+    /// * `full_heap`: Whether the GC is a full-heap GC.  No object should be freed in a nursery
+    ///   GC, as the objects that are only reachable from mature objects are not marked.
+    fn release(&self, _tls: VMWorkerThread, _full_heap: bool) {}
+}
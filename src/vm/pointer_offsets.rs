@@ -0,0 +1,81 @@
+//! Descriptors of the reference fields of objects, for scanning objects without calling back to
+//! the VM.  See [`crate::vm::ObjectModel::get_pointer_offsets`].
+
+use crate::util::constants::{BITS_IN_WORD, BYTES_IN_WORD};
+use crate::util::Address;
+
+/// A descriptor of the locations of the reference fields (slots) in an object.  The locations are
+/// relative to the object start address ([`crate::vm::ObjectModel::ref_to_object_start`]).
+///
+/// A descriptor usually describes all the objects of the same type, so a binding can create one
+/// descriptor for each type and return it for every object of the type.  Objects whose layouts
+/// cannot be described statically (such as arrays of references) should not use a descriptor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PointerOffsets {
+    /// The object has no reference fields.
+    NoReferences,
+    /// A bitmap of the words in the object.  If the bit `i` (i.e. the bit `i % BITS_IN_WORD` of the
+    /// word `i / BITS_IN_WORD` in the slice) is set, the word at `i * BYTES_IN_WORD` bytes from the
+    /// object start is a slot.
+    Bitmap(&'static [usize]),
+    /// The offsets of the slots in bytes from the object start.
+    Offsets(&'static [usize]),
+}
+
+impl PointerOffsets {
+    /// Call `visitor` with the address of each slot of the object that starts at `object_start`.
+    pub fn visit_slot_addresses(&self, object_start: Address, mut visitor: impl FnMut(Address)) {
+        match *self {
+            PointerOffsets::NoReferences => {}
+            PointerOffsets::Bitmap(bitmap) => {
+                for (index, word) in bitmap.iter().copied().enumerate() {
+                    let mut bits = word;
+                    while bits != 0 {
+                        let bit = bits.trailing_zeros() as usize;
+                        bits &= bits - 1;
+                        let word_index = index * BITS_IN_WORD + bit;
+                        visitor(object_start + word_index * BYTES_IN_WORD);
+                    }
+                }
+            }
+            PointerOffsets::Offsets(offsets) => {
+                for offset in offsets.iter().copied() {
+                    visitor(object_start + offset);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const START: Address = unsafe { Address::from_usize(0x1000) };
+
+    fn collect(offsets: PointerOffsets) -> Vec<usize> {
+        let mut result = vec![];
+        offsets.visit_slot_addresses(START, |addr| result.push(addr - START));
+        result
+    }
+
+    #[test]
+    fn visit_bitmap() {
+        static BITMAP: [usize; 2] = [0b1010, 0b1];
+        assert_eq!(
+            collect(PointerOffsets::Bitmap(&BITMAP)),
+            vec![
+                BYTES_IN_WORD,
+                3 * BYTES_IN_WORD,
+                BITS_IN_WORD * BYTES_IN_WORD
+            ]
+        );
+    }
+
+    #[test]
+    fn visit_offsets() {
+        static OFFSETS: [usize; 3] = [8, 24, 16];
+        assert_eq!(collect(PointerOffsets::Offsets(&OFFSETS)), vec![8, 24, 16]);
+        assert!(collect(PointerOffsets::NoReferences).is_empty());
+    }
+}
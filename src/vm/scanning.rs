@@ -1,14 +1,30 @@
 use crate::plan::Mutator;
 use crate::scheduler::GCWorker;
+use crate::util::Address;
 use crate::util::ObjectReference;
 use crate::util::VMWorkerThread;
 use crate::vm::slot::Slot;
 use crate::vm::VMBinding;
+use std::ops::Range;
 
 /// Callback trait of scanning functions that report slots.
 pub trait SlotVisitor<SL: Slot> {
     /// Call this function for each slot.
     fn visit_slot(&mut self, slot: SL);
+
+    /// Call this function for each weak slot, i.e. a slot that does not keep its referent alive.
+    ///
+    /// When called during tracing, MMTk does not trace through the slot.  After the strong
+    /// transitive closure is computed, MMTk updates the slot if its referent is live and moved, or
+    /// calls [`Scanning::clear_weak_slot`] if its referent is dead.  This lets a VM have objects
+    /// with both strong and weak fields without handling the whole objects in
+    /// [`Scanning::process_weak_refs`].
+    ///
+    /// Visitors that do not distinguish weak slots, such as the heap verifiers, treat weak slots
+    /// as strong slots.  This is also the default implementation.
+    fn visit_weak_slot(&mut self, slot: SL) {
+        self.visit_slot(slot)
+    }
 }
 
 /// This lets us use closures as SlotVisitor.
@@ -81,6 +97,42 @@ pub trait ObjectTracerContext<VM: VMBinding>: Clone + Send + 'static {
         F: FnOnce(&mut Self::TracerType) -> R;
 }
 
+/// A class of roots, such as a range of memory or a global root table, that MMTk divides into
+/// work packets itself.  See [`RootsWorkFactory::create_process_roots_descriptor_work`].
+///
+/// The root slots are numbered from 0 to `num_slots() - 1`.  MMTk calls `slots` for disjoint
+/// ranges of them from different GC workers in parallel, so the descriptor only needs to locate
+/// the slots, and does not need to copy them out of the table beforehand.
+pub trait RootsDescriptor<SL: Slot>: Send + Sync + 'static {
+    /// The number of root slots.
+    fn num_slots(&self) -> usize;
+
+    /// Push the root slots numbered in `range` into `slots`.
+    fn slots(&self, range: Range<usize>, slots: &mut Vec<SL>);
+}
+
+/// A table of `count` root slots starting at address `start`, with `stride` bytes between the
+/// starts of adjacent slots.  Each slot is created from its address with the `slot` function.
+/// This describes a contiguous range of root words (where `stride` is the size of a word), or a
+/// field in an array of root table entries.
+pub struct RootSlotTable<SL: Slot> {
+    pub start: Address,
+    pub count: usize,
+    pub stride: usize,
+    pub slot: fn(Address) -> SL,
+}
+
+impl<SL: Slot> RootsDescriptor<SL> for RootSlotTable<SL> {
+    fn num_slots(&self) -> usize {
+        self.count
+    }
+
+    fn slots(&self, range: Range<usize>, slots: &mut Vec<SL>) {
+        debug_assert!(range.end <= self.count);
+        slots.extend(range.map(|i| (self.slot)(self.start + i * self.stride)));
+    }
+}
+
 /// Root-scanning methods use this trait to create work packets for processing roots.
 ///
 /// Notes on the required traits:
@@ -115,6 +167,22 @@ pub trait RootsWorkFactory<SL: Slot>: Clone + Send + 'static {
     /// * `slots`: A vector of slots.
     fn create_process_roots_work(&mut self, slots: Vec<SL>);
 
+    /// Create work packets to handle non-pinned roots described by `roots`.  MMTk divides the
+    /// roots into work packets of balanced sizes, and the GC workers get the slots from the
+    /// descriptor in parallel.  This is preferable to `create_process_roots_work` if the VM has
+    /// one huge root table that it cannot easily divide by itself.
+    ///
+    /// The default implementation gets all the slots, and hands them to
+    /// `create_process_roots_work`.
+    ///
+    /// Arguments:
+    /// * `roots`: The descriptor of the roots.
+    fn create_process_roots_descriptor_work(&mut self, roots: Box<dyn RootsDescriptor<SL>>) {
+        let mut slots = Vec::with_capacity(roots.num_slots());
+        roots.slots(0..roots.num_slots(), &mut slots);
+        self.create_process_roots_work(slots);
+    }
+
     /// Create work packets to handle non-transitively pinning roots.
     ///
     /// The work packet will prevent the objects in `nodes` from moving,
@@ -136,6 +204,30 @@ pub trait RootsWorkFactory<SL: Slot>: Clone + Send + 'static {
     /// Arguments:
     /// * `nodes`: A vector of references to objects pointed by edges from roots.
     fn create_process_tpinning_roots_work(&mut self, nodes: Vec<ObjectReference>);
+
+    /// Create work packets to handle a batch of transitively pinning roots that lasts `gcs` GCs,
+    /// including the current GC.  This is useful for large batches of objects that must not move
+    /// for a while, such as buffers in use by asynchronous I/O.
+    ///
+    /// The objects in `nodes` are handled like `create_process_tpinning_roots_work` in the current
+    /// GC.  MMTk keeps the batch, and reports the objects as transitively pinning roots again at
+    /// the start of each of the following `gcs - 1` GCs, so the VM does not need to report them
+    /// again.  After that, the objects are no longer pinned or kept alive by the batch.  See
+    /// [`crate::MMTK::last_gc_pinned_roots_stats`] for how much memory the batches pinned.
+    ///
+    /// The default implementation only handles the objects in the current GC, as if `gcs` is 1.
+    ///
+    /// Arguments:
+    /// * `nodes`: A vector of references to objects pointed by edges from roots.
+    /// * `gcs`: The number of GCs the batch lasts.  It must be at least 1.
+    fn create_process_tpinning_roots_batch_work(
+        &mut self,
+        nodes: Vec<ObjectReference>,
+        gcs: usize,
+    ) {
+        debug_assert!(gcs > 0);
+        self.create_process_tpinning_roots_work(nodes);
+    }
 }
 
 /// VM-specific methods for scanning roots/objects.
@@ -223,12 +315,40 @@ pub trait Scanning<VM: VMBinding> {
         unreachable!("scan_object_and_trace_edges() will not be called when support_slot_enqueuing() is always true.")
     }
 
+    /// Create a slot for a reference field at the given address.  MMTk calls this when scanning
+    /// objects described by [`crate::vm::ObjectModel::get_pointer_offsets`].  A binding that
+    /// returns `Some` from `get_pointer_offsets` must implement this method.
+    ///
+    /// Arguments:
+    /// * `address`: The address of the reference field.
+    fn create_slot(_address: Address) -> VM::VMSlot {
+        unreachable!(
+            "create_slot() will not be called when get_pointer_offsets() always returns None."
+        )
+    }
+
+    /// Clear a weak slot whose referent is dead.  MMTk calls this for each slot reported with
+    /// [`SlotVisitor::visit_weak_slot`] if the object it refers to is not reachable from strong
+    /// slots at the end of the strong transitive closure.  The VM shall overwrite the slot with a
+    /// value that does not refer to an object (such as a null pointer, or a VM-specific tombstone
+    /// value), and it may do any other bookkeeping for the dead referent.
+    ///
+    /// A binding that calls `visit_weak_slot` must implement this method.
+    ///
+    /// Arguments:
+    /// * `tls`: The VM-specific thread-local storage for the current worker.
+    /// * `slot`: The weak slot to be cleared.
+    fn clear_weak_slot(_tls: VMWorkerThread, _slot: VM::VMSlot) {
+        unreachable!("clear_weak_slot() will not be called when visit_weak_slot() is never used.")
+    }
+
     /// MMTk calls this method at the first time during a collection that thread's stacks
     /// have been scanned. This can be used (for example) to clean up
     /// obsolete compiled methods that are no longer being executed.
     ///
     /// Arguments:
-    /// * `partial_scan`: Whether the scan was partial or full-heap.
+    /// * `partial_scan`: Whether the stacks could be scanned partially (i.e. only above the stack
+    ///   watermarks) in this GC.
     /// * `tls`: The GC thread that is performing the thread scan.
     fn notify_initial_thread_scan_complete(partial_scan: bool, tls: VMWorkerThread);
 
@@ -259,6 +379,9 @@ pub trait Scanning<VM: VMBinding> {
     /// Scan VM-specific roots. The creation of all root scan tasks (except thread scanning)
     /// goes here.
     ///
+    /// For large root tables, the VM can describe the roots with a [`RootsDescriptor`] and call
+    /// [`RootsWorkFactory::create_process_roots_descriptor_work`], and let MMTk divide the work.
+    ///
     /// The `memory_manager::is_mmtk_object` function can be used in this function if
     /// -   the "is_mmtk_object" feature is enabled.
     ///
@@ -267,7 +390,38 @@ pub trait Scanning<VM: VMBinding> {
     /// * `factory`: The VM uses it to create work packets for scanning roots.
     fn scan_vm_specific_roots(tls: VMWorkerThread, factory: impl RootsWorkFactory<VM::VMSlot>);
 
-    /// Return whether the VM supports return barriers. This is unused at the moment.
+    /// Return the number of categories of stable roots.  See [`Scanning::scan_stable_roots`].
+    ///
+    /// The default implementation returns 0, i.e. the VM does not have stable roots.
+    fn stable_root_categories() -> usize {
+        0
+    }
+
+    /// Scan the stable roots of a category, and push their slots to `slots`.
+    ///
+    /// Stable roots are root slots that rarely change, such as the slots in the boot image, or
+    /// static final fields after the VM starts up.  MMTk caches the slots reported by this method
+    /// for each category, and reports them as roots in the following GCs without calling this
+    /// method again, until the binding calls [`crate::memory_manager::invalidate_stable_roots`] for
+    /// the category.  So the slots must remain valid until then, and the binding must invalidate
+    /// the category if slots are added to or removed from it.  Storing different objects into the
+    /// slots does not need invalidation, as MMTk loads the slots in each GC.  The slots are
+    /// processed as non-pinning roots (see [`RootsWorkFactory::create_process_roots_work`]), and
+    /// must not be reported again by other root scanning methods.
+    ///
+    /// Arguments:
+    /// * `tls`: The GC thread that is performing this scanning.
+    /// * `category`: The category, from 0 to [`Scanning::stable_root_categories`] (exclusive).
+    /// * `slots`: The VM pushes the slots of the stable roots to it.
+    fn scan_stable_roots(_tls: VMWorkerThread, _category: usize, _slots: &mut Vec<VM::VMSlot>) {}
+
+    /// Return whether the VM supports return barriers.
+    ///
+    /// If this returns true, MMTk allows the VM to scan only the stack frames above the stack
+    /// watermark of each mutator in nursery GCs.  The VM reports the watermark with
+    /// [`crate::memory_manager::set_stack_watermark`] after installing a return barrier, and gets
+    /// it with [`crate::memory_manager::get_stack_watermark`] in
+    /// [`Scanning::scan_roots_in_mutator_thread`].  See [`crate::plan::StackWatermark`].
     fn supports_return_barrier() -> bool;
 
     /// Prepare for another round of root scanning in the same GC. Some GC algorithms
@@ -347,6 +501,43 @@ pub trait Scanning<VM: VMBinding> {
         false
     }
 
+    /// Process the unloading of VM metadata, such as classes, shapes and inline caches, whose
+    /// liveness depends on the liveness of objects.
+    ///
+    /// This function is called after weak references are processed (i.e. after
+    /// [`Scanning::process_weak_refs`] returns `false`, and the transitive closure is complete).
+    /// At this time, the liveness of all objects is known.  The VM binding can use
+    /// `ObjectReference::is_reachable()` to check if the objects that keep the metadata alive
+    /// (such as class loaders or the instances of shapes) are reachable, and unload the metadata
+    /// that are no longer used.  The binding can update the references held by the remaining
+    /// metadata using `ObjectReference::get_forwarded_object()`.
+    ///
+    /// If unloading needs to keep more objects alive (for example, if unloading a class resurrects
+    /// the objects referred to by its static fields for a cleanup hook), the VM binding can trace
+    /// them with an `ObjectTracer` from `tracer_context`, like in `process_weak_refs`, and return
+    /// `true` to request this function to be called again after MMTk expands the transitive
+    /// closure from the objects newly traced.
+    ///
+    /// Implementation-wise, this function is called as the "sentinel" of the `VMUnloading` work
+    /// bucket.  The VM binding may add its own work packets (for example, to sweep metadata in
+    /// parallel) into the `VMUnloading` bucket, and they will have finished by the time this
+    /// function is called again.  When using the mark-compact GC algorithm, the VM binding shall
+    /// forward the references held by the remaining metadata in `forward_weak_refs`.
+    ///
+    /// Arguments:
+    /// * `worker`: The current GC worker.
+    /// * `tracer_context`: Use this to get access an `ObjectTracer` and use it to keep objects
+    ///   alive.
+    ///
+    /// This function shall return true if this function needs to be called again after the GC
+    /// finishes expanding the transitive closure from the objects kept alive.
+    fn process_unloading(
+        _worker: &mut GCWorker<VM>,
+        _tracer_context: impl ObjectTracerContext<VM>,
+    ) -> bool {
+        false
+    }
+
     /// Forward weak references.
     ///
     /// This function will only be called in the forwarding stage when using the mark-compact GC
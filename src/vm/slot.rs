@@ -151,6 +151,232 @@ impl Slot for Address {
     }
 }
 
+/// A word-sized slot that holds a derived pointer, i.e. a pointer into the middle of an object
+/// rather than the raw address of its `ObjectReference`.  Runtimes may have derived pointers in
+/// stack frames or registers, such as pointers to array elements or fields kept by JIT-compiled
+/// code, or interior pointers in languages like Go.
+///
+/// The slot remembers the offset of the derived pointer from the raw address of the base object
+/// reference.  [`Slot::load`] returns the base object, and [`Slot::store`] writes the new address
+/// of the base object plus the same offset, so the derived pointer still points to the same part
+/// of the object after the object is moved.
+///
+/// The offset is computed when the slot is created, so the slot must be created before the object
+/// moves, usually when the binding scans roots.  The binding can create it with
+/// [`DerivedPointerSlot::from_base`] if it knows the base object, or with
+/// [`DerivedPointerSlot::resolve`] which finds the base object with the VO bits.
+///
+/// A binding usually has other kinds of slots, too, and can use this as one variant of its slot
+/// type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DerivedPointerSlot {
+    slot_addr: *mut Atomic<Address>,
+    offset: isize,
+}
+
+impl DerivedPointerSlot {
+    /// Create a derived pointer slot whose base object is known.
+    ///
+    /// Arguments:
+    /// *   `address`: The address in memory where the derived pointer is stored.
+    /// *   `base`: The object that the derived pointer points into.
+    pub fn from_base(address: Address, base: ObjectReference) -> Self {
+        let derived = unsafe { address.load::<Address>() };
+        Self {
+            slot_addr: address.to_mut_ptr(),
+            offset: derived
+                .as_usize()
+                .wrapping_sub(base.to_raw_address().as_usize()) as isize,
+        }
+    }
+
+    /// Create a derived pointer slot, and find its base object with
+    /// [`crate::memory_manager::find_object_from_internal_pointer`].  Return `None` if the slot
+    /// does not point into an MMTk object.  It has the same requirements on the derived pointer as
+    /// `find_object_from_internal_pointer`, and searches at most `max_search_bytes` backwards for
+    /// the base object.
+    ///
+    /// Arguments:
+    /// *   `address`: The address in memory where the derived pointer is stored.
+    /// *   `max_search_bytes`: The maximum distance from the derived pointer to the base object.
+    #[cfg(feature = "is_mmtk_object")]
+    pub fn resolve(address: Address, max_search_bytes: usize) -> Option<Self> {
+        let derived = unsafe { address.load::<Address>() };
+        if derived.is_zero() {
+            return None;
+        }
+        crate::memory_manager::find_object_from_internal_pointer(derived, max_search_bytes)
+            .map(|base| Self::from_base(address, base))
+    }
+
+    /// Get the address of the slot.
+    pub fn as_address(&self) -> Address {
+        Address::from_mut_ptr(self.slot_addr)
+    }
+
+    /// Get the offset of the derived pointer from the raw address of the base object reference.
+    pub fn offset(&self) -> isize {
+        self.offset
+    }
+}
+
+unsafe impl Send for DerivedPointerSlot {}
+
+impl Slot for DerivedPointerSlot {
+    fn load(&self) -> Option<ObjectReference> {
+        let derived = unsafe { (*self.slot_addr).load(atomic::Ordering::Relaxed) };
+        if derived.is_zero() {
+            return None;
+        }
+        ObjectReference::from_raw_address(derived + -self.offset)
+    }
+
+    fn store(&self, object: ObjectReference) {
+        unsafe {
+            (*self.slot_addr).store(
+                object.to_raw_address() + self.offset,
+                atomic::Ordering::Relaxed,
+            )
+        }
+    }
+}
+
+/// A word-sized slot whose low `TAG_BITS` bits are a tag, and the other bits are the raw address of
+/// an `ObjectReference`.  This is common in dynamic languages that use the alignment bits of
+/// pointers to distinguish references from small integers and other immediate values.
+///
+/// The word holds a reference if its tag `t` satisfies `t & REF_TAG_MASK == REF_TAG` and the
+/// pointer is not zero.  For example, if all the words with the lowest bit `0` are references,
+/// use `LowBitTaggedSlot<TAG_BITS, 0b1, 0b0>`; if only the tag `0b01` is a reference in a two-bit
+/// tag, use `LowBitTaggedSlot<2, 0b11, 0b01>`.  [`Slot::store`] preserves the tag bits, so
+/// different tags that denote references (such as strong and weak references) survive moving.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct LowBitTaggedSlot<const TAG_BITS: u32, const REF_TAG_MASK: usize, const REF_TAG: usize> {
+    slot_addr: *mut Atomic<usize>,
+}
+
+impl<const TAG_BITS: u32, const REF_TAG_MASK: usize, const REF_TAG: usize>
+    LowBitTaggedSlot<TAG_BITS, REF_TAG_MASK, REF_TAG>
+{
+    const TAG_MASK: usize = (1 << TAG_BITS) - 1;
+
+    /// Create a tagged slot from an address.
+    ///
+    /// Arguments:
+    /// *   `address`: The address in memory where the tagged word is stored.
+    pub fn from_address(address: Address) -> Self {
+        Self {
+            slot_addr: address.to_mut_ptr(),
+        }
+    }
+
+    /// Get the address of the slot.
+    pub fn as_address(&self) -> Address {
+        Address::from_mut_ptr(self.slot_addr)
+    }
+}
+
+unsafe impl<const TAG_BITS: u32, const REF_TAG_MASK: usize, const REF_TAG: usize> Send
+    for LowBitTaggedSlot<TAG_BITS, REF_TAG_MASK, REF_TAG>
+{
+}
+
+impl<const TAG_BITS: u32, const REF_TAG_MASK: usize, const REF_TAG: usize> Slot
+    for LowBitTaggedSlot<TAG_BITS, REF_TAG_MASK, REF_TAG>
+{
+    fn load(&self) -> Option<ObjectReference> {
+        let word = unsafe { (*self.slot_addr).load(atomic::Ordering::Relaxed) };
+        if word & Self::TAG_MASK & REF_TAG_MASK != REF_TAG {
+            return None;
+        }
+        ObjectReference::from_raw_address(unsafe { Address::from_usize(word & !Self::TAG_MASK) })
+    }
+
+    fn store(&self, object: ObjectReference) {
+        let addr = object.to_raw_address().as_usize();
+        debug_assert_eq!(
+            addr & Self::TAG_MASK,
+            0,
+            "{object} overlaps with the tag bits"
+        );
+        let slot = unsafe { &*self.slot_addr };
+        let tag = slot.load(atomic::Ordering::Relaxed) & Self::TAG_MASK;
+        slot.store(addr | tag, atomic::Ordering::Relaxed)
+    }
+}
+
+/// A word-sized slot whose bits from `TAG_SHIFT` upwards are a tag, and the bits below are the raw
+/// address of an `ObjectReference`.  This suits runtimes that keep a type tag in the unused high
+/// bits of pointers.
+///
+/// The word holds a reference if its tag `t` (i.e. `word >> TAG_SHIFT`) satisfies
+/// `t & REF_TAG_MASK == REF_TAG` and the pointer is not zero.  [`Slot::store`] preserves the tag.
+/// See also [`NanBoxedSlot`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct HighBitTaggedSlot<const TAG_SHIFT: u32, const REF_TAG_MASK: usize, const REF_TAG: usize>
+{
+    slot_addr: *mut Atomic<usize>,
+}
+
+impl<const TAG_SHIFT: u32, const REF_TAG_MASK: usize, const REF_TAG: usize>
+    HighBitTaggedSlot<TAG_SHIFT, REF_TAG_MASK, REF_TAG>
+{
+    const POINTER_MASK: usize = (1 << TAG_SHIFT) - 1;
+
+    /// Create a tagged slot from an address.
+    ///
+    /// Arguments:
+    /// *   `address`: The address in memory where the tagged word is stored.
+    pub fn from_address(address: Address) -> Self {
+        Self {
+            slot_addr: address.to_mut_ptr(),
+        }
+    }
+
+    /// Get the address of the slot.
+    pub fn as_address(&self) -> Address {
+        Address::from_mut_ptr(self.slot_addr)
+    }
+}
+
+unsafe impl<const TAG_SHIFT: u32, const REF_TAG_MASK: usize, const REF_TAG: usize> Send
+    for HighBitTaggedSlot<TAG_SHIFT, REF_TAG_MASK, REF_TAG>
+{
+}
+
+impl<const TAG_SHIFT: u32, const REF_TAG_MASK: usize, const REF_TAG: usize> Slot
+    for HighBitTaggedSlot<TAG_SHIFT, REF_TAG_MASK, REF_TAG>
+{
+    fn load(&self) -> Option<ObjectReference> {
+        let word = unsafe { (*self.slot_addr).load(atomic::Ordering::Relaxed) };
+        if (word >> TAG_SHIFT) & REF_TAG_MASK != REF_TAG {
+            return None;
+        }
+        ObjectReference::from_raw_address(unsafe { Address::from_usize(word & Self::POINTER_MASK) })
+    }
+
+    fn store(&self, object: ObjectReference) {
+        let addr = object.to_raw_address().as_usize();
+        debug_assert_eq!(
+            addr & !Self::POINTER_MASK,
+            0,
+            "{object} overlaps with the tag bits"
+        );
+        let slot = unsafe { &*self.slot_addr };
+        let tag = slot.load(atomic::Ordering::Relaxed) & !Self::POINTER_MASK;
+        slot.store(addr | tag, atomic::Ordering::Relaxed)
+    }
+}
+
+/// A NaN-boxed value slot.  A NaN-boxed value is a 64-bit word that holds either a double, or a
+/// 48-bit payload in the low bits of a NaN whose top 16 bits are a tag.  The slot holds a reference
+/// if the top 16 bits are `POINTER_TAG`, and the low 48 bits are the raw address of the
+/// `ObjectReference`.  Doubles and other boxed values are not references.
+#[cfg(target_pointer_width = "64")]
+pub type NanBoxedSlot<const POINTER_TAG: usize> = HighBitTaggedSlot<48, 0xffff, POINTER_TAG>;
+
 #[test]
 fn a_simple_slot_should_have_the_same_size_as_a_pointer() {
     assert_eq!(
@@ -304,4 +530,81 @@ mod tests {
         MemorySlice::copy(&src_slice, &dst_slice);
         assert_eq!(dst.iter().sum::<u8>(), src.len() as u8);
     }
+
+    fn objref(addr: usize) -> ObjectReference {
+        ObjectReference::from_raw_address(unsafe { Address::from_usize(addr) }).unwrap()
+    }
+
+    #[test]
+    fn low_bit_tagged_slot_preserves_tags() {
+        // Two-bit tags where the lowest bit 1 denotes a reference, e.g. 0b01 for strong and
+        // 0b11 for weak references.
+        type Tagged = LowBitTaggedSlot<2, 0b1, 0b1>;
+        for tag in [0b01usize, 0b11] {
+            let mut word = 0x1000 | tag;
+            let slot = Tagged::from_address(Address::from_mut_ptr(&mut word));
+            assert_eq!(slot.load(), Some(objref(0x1000)));
+            // Pretend that a copying GC moved the object.
+            slot.store(objref(0x8000));
+            assert_eq!(word, 0x8000 | tag);
+            assert_eq!(slot.load(), Some(objref(0x8000)));
+        }
+
+        // An immediate value with the tag 0b10 is not a reference.
+        let mut word = 0x1000 | 0b10;
+        let slot = Tagged::from_address(Address::from_mut_ptr(&mut word));
+        assert_eq!(slot.load(), None);
+    }
+
+    #[test]
+    fn high_bit_tagged_slot_preserves_tags() {
+        #[cfg(target_pointer_width = "64")]
+        const TAG_SHIFT: u32 = 56;
+        #[cfg(target_pointer_width = "32")]
+        const TAG_SHIFT: u32 = 28;
+        type Tagged = HighBitTaggedSlot<TAG_SHIFT, 0x8, 0x8>;
+
+        let tag = 0xausize << TAG_SHIFT;
+        let mut word = 0x1000 | tag;
+        let slot = Tagged::from_address(Address::from_mut_ptr(&mut word));
+        assert_eq!(slot.load(), Some(objref(0x1000)));
+        slot.store(objref(0x8000));
+        assert_eq!(word, 0x8000 | tag);
+        assert_eq!(slot.load(), Some(objref(0x8000)));
+
+        let mut word = 0x1000 | (0x2usize << TAG_SHIFT);
+        let slot = Tagged::from_address(Address::from_mut_ptr(&mut word));
+        assert_eq!(slot.load(), None);
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn nan_boxed_slot() {
+        const POINTER_TAG: usize = 0xfffc;
+        type Boxed = NanBoxedSlot<POINTER_TAG>;
+
+        let mut word = (POINTER_TAG << 48) | 0x7f00_0000_1000;
+        let slot = Boxed::from_address(Address::from_mut_ptr(&mut word));
+        assert_eq!(slot.load(), Some(objref(0x7f00_0000_1000)));
+        slot.store(objref(0x7f00_0000_8000));
+        assert_eq!(word, (POINTER_TAG << 48) | 0x7f00_0000_8000);
+
+        // A double is not a reference.
+        let mut word = 1.5f64.to_bits() as usize;
+        let slot = Boxed::from_address(Address::from_mut_ptr(&mut word));
+        assert_eq!(slot.load(), None);
+    }
+
+    #[test]
+    fn derived_pointer_slot_keeps_offset() {
+        let base = objref(0x1000);
+        let moved = objref(0x8000);
+        let mut word = base.to_raw_address() + 0x18usize;
+        let slot = DerivedPointerSlot::from_base(Address::from_mut_ptr(&mut word), base);
+        assert_eq!(slot.offset(), 0x18);
+        assert_eq!(slot.load(), Some(base));
+        slot.store(moved);
+        assert_eq!(word, moved.to_raw_address() + 0x18usize);
+        assert_eq!(slot.load(), Some(moved));
+    }
 }
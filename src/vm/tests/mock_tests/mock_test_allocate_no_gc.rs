@@ -0,0 +1,75 @@
+// GITHUB-CI: MMTK_PLAN=all
+
+use super::mock_test_prelude::*;
+use crate::util::alloc::{AllocationOptions, OnAllocationFail};
+use crate::AllocationSemantics;
+
+const NO_GC: AllocationOptions = AllocationOptions {
+    on_fail: OnAllocationFail::ReturnFailure,
+};
+
+/// Allocations that do not allow GC succeed while the heap has room, and return a null address
+/// once the heap is full, in every space, without triggering a GC or reporting out of memory.
+#[test]
+pub fn allocate_no_gc() {
+    // 1MB heap
+    with_mockvm(
+        || -> MockVM {
+            MockVM {
+                block_for_gc: MockMethod::new_default(),
+                ..MockVM::default()
+            }
+        },
+        || {
+            const MB: usize = 1024 * 1024;
+            const KB: usize = 1024;
+            let mut fixture = MutatorFixture::create_with_heapsize(MB);
+            let mmtk = fixture.mmtk();
+            let tls = fixture.mutator.mutator_tls;
+
+            let mut alloc_no_gc = |size, semantics| {
+                memory_manager::alloc_with_options(
+                    &mut fixture.mutator,
+                    size,
+                    8,
+                    0,
+                    semantics,
+                    NO_GC,
+                )
+            };
+
+            // Fill up the heap.
+            let addr = alloc_no_gc(8 * KB, AllocationSemantics::Default);
+            assert!(!addr.is_zero());
+            let filled = (0..1024)
+                .position(|_| alloc_no_gc(8 * KB, AllocationSemantics::Default).is_zero())
+                .is_some();
+            assert!(filled);
+
+            // No space can satisfy the allocation without a GC.
+            for (size, semantics) in [
+                (8 * KB, AllocationSemantics::Default),
+                (8 * KB, AllocationSemantics::Immortal),
+                (8 * KB, AllocationSemantics::NonMoving),
+                (64 * KB, AllocationSemantics::Los),
+            ] {
+                assert!(alloc_no_gc(size, semantics).is_zero(), "{:?}", semantics);
+            }
+            // A request larger than the heap fails without reporting out of memory.
+            assert!(alloc_no_gc(2 * MB, AllocationSemantics::Los).is_zero());
+
+            assert!(!mmtk.is_gc_requested());
+            read_mockvm(|mock| {
+                assert!(!mock.block_for_gc.is_called());
+                assert!(!mock.out_of_memory.is_called());
+            });
+
+            // A mutator that allows GC would trigger a GC now.
+            memory_manager::gc_poll(mmtk, tls);
+            read_mockvm(|mock| {
+                assert!(mock.block_for_gc.is_called());
+            });
+        },
+        no_cleanup,
+    )
+}
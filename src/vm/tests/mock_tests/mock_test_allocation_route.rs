@@ -0,0 +1,55 @@
+// GITHUB-CI: MMTK_PLAN=Immix
+
+use super::mock_test_prelude::*;
+use crate::mmtk::SFT_MAP;
+use crate::AllocationSemantics;
+use crate::MMTKBuilder;
+
+#[test]
+pub fn route_immortal_to_los() {
+    with_mockvm(
+        default_setup,
+        || {
+            let mut fixture = MutatorFixture::create_with_builder(|builder| {
+                builder
+                    .set_allocation_route(AllocationSemantics::Immortal, AllocationSemantics::Los);
+            });
+            let mmtk = fixture.mmtk();
+            let los = memory_manager::get_allocator_mapping(mmtk, AllocationSemantics::Los);
+            assert_eq!(
+                memory_manager::get_allocator_mapping(mmtk, AllocationSemantics::Immortal),
+                los
+            );
+            assert_eq!(
+                fixture.mutator.config.allocator_mapping[AllocationSemantics::Immortal],
+                los
+            );
+
+            let addr = memory_manager::alloc(
+                &mut fixture.mutator,
+                40,
+                8,
+                0,
+                AllocationSemantics::Immortal,
+            );
+            assert!(!addr.is_zero());
+            assert_eq!(SFT_MAP.get_checked(addr).name(), "los");
+        },
+        no_cleanup,
+    )
+}
+
+#[test]
+#[should_panic(expected = "Invalid allocation route")]
+pub fn route_default() {
+    with_mockvm(
+        default_setup,
+        || {
+            let mut builder = MMTKBuilder::new();
+            builder
+                .set_allocation_route(AllocationSemantics::Default, AllocationSemantics::Immortal);
+            let _mmtk = builder.build::<MockVM>();
+        },
+        no_cleanup,
+    )
+}
@@ -0,0 +1,61 @@
+// GITHUB-CI: MMTK_PLAN=GenImmix
+
+use super::mock_test_prelude::*;
+use crate::util::options::PlanSelector;
+use crate::{AllocationSemantics, BarrierSourceHint};
+use atomic::Ordering;
+
+/// Check that `barrier_required_for` only allows eliding the barrier for new objects that the
+/// object barrier would not remember, i.e. the objects that are not unlogged when allocated.
+#[test]
+pub fn barrier_elision() {
+    with_mockvm(
+        default_setup,
+        || {
+            let mut fixture = MutatorFixture::create_with_builder(|builder| {
+                builder.options.plan.set(PlanSelector::GenImmix);
+                builder.options.gc_trigger.set(
+                    crate::util::options::GCTriggerSelector::FixedHeapSize(8 * 1024 * 1024),
+                );
+            });
+            let mmtk = fixture.mmtk();
+            let log_bit = &<MockVM as VMBinding>::VMObjectModel::GLOBAL_LOG_BIT_SPEC;
+
+            for semantics in [
+                AllocationSemantics::Default,
+                AllocationSemantics::Immortal,
+                AllocationSemantics::Los,
+                AllocationSemantics::NonMoving,
+            ] {
+                assert!(memory_manager::barrier_required_for(
+                    mmtk,
+                    BarrierSourceHint::Unknown,
+                    semantics
+                ));
+
+                let size = 40;
+                let addr = memory_manager::alloc(&mut fixture.mutator, size, 8, 0, semantics);
+                assert!(!addr.is_zero());
+                let object = MockVM::object_start_to_ref(addr);
+                memory_manager::post_alloc(&mut fixture.mutator, object, size, semantics);
+
+                assert_eq!(
+                    memory_manager::barrier_required_for(
+                        mmtk,
+                        BarrierSourceHint::NewlyAllocated,
+                        semantics
+                    ),
+                    log_bit.is_unlogged::<MockVM>(object, Ordering::SeqCst),
+                    "{:?}",
+                    semantics
+                );
+            }
+            assert!(!memory_manager::barrier_required_for(
+                mmtk,
+                BarrierSourceHint::NewlyAllocated,
+                AllocationSemantics::Default
+            ));
+        },
+        no_cleanup,
+    )
+}
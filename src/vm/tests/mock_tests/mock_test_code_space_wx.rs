@@ -0,0 +1,35 @@
+// GITHUB-CI: MMTK_PLAN=Immix
+// GITHUB-CI: FEATURES=code_space
+
+use super::mock_test_prelude::*;
+use crate::util::constants::BYTES_IN_PAGE;
+use crate::AllocationSemantics;
+
+#[test]
+pub fn set_code_executable() {
+    with_mockvm(
+        default_setup,
+        || {
+            let mut fixture = MutatorFixture::create_with_builder(|builder| {
+                assert!(builder.set_option("code_space_wx", "true"));
+            });
+            let mmtk = fixture.mmtk();
+
+            let addr =
+                memory_manager::alloc(&mut fixture.mutator, 64, 8, 0, AllocationSemantics::Code);
+            assert!(!addr.is_zero());
+            let page = addr.align_down(BYTES_IN_PAGE);
+
+            // The code space is writable, and the code can be read after it is made executable.
+            unsafe { addr.store(0xc0deusize) };
+            memory_manager::set_code_executable(mmtk, page, BYTES_IN_PAGE, true);
+            assert_eq!(unsafe { addr.load::<usize>() }, 0xc0de);
+
+            // Make the page writable again to patch the code.
+            memory_manager::set_code_executable(mmtk, page, BYTES_IN_PAGE, false);
+            unsafe { addr.store(0xbeefusize) };
+            assert_eq!(unsafe { addr.load::<usize>() }, 0xbeef);
+        },
+        no_cleanup,
+    )
+}
@@ -0,0 +1,70 @@
+// GITHUB-CI: MMTK_PLAN=SemiSpace
+
+use super::mock_test_prelude::*;
+use crate::policy::copyspace::CopySpace;
+use crate::policy::space::Space;
+use crate::util::copy::*;
+use crate::util::options::PlanSelector;
+use crate::util::test_util::mock_gc::*;
+use crate::util::{VMThread, VMWorkerThread};
+use crate::AllocationSemantics;
+use enum_map::enum_map;
+
+/// Check that a copy context can have more than one copy allocator for the same space, selected
+/// by the copy partition.
+#[test]
+pub fn copy_partitions() {
+    with_mockvm(
+        default_setup,
+        || {
+            let mut gc = MockGC::new(0, 8 * 1024 * 1024, |builder| {
+                builder.options.plan.set(PlanSelector::SemiSpace);
+            });
+            let mmtk = gc.mmtk();
+            let mut copyspace: Option<&'static dyn Space<MockVM>> = None;
+            mmtk.get_plan().for_each_space(&mut |space| {
+                if copyspace.is_none() && space.downcast_ref::<CopySpace<MockVM>>().is_some() {
+                    copyspace = Some(unsafe { &*(space as *const dyn Space<MockVM>) });
+                }
+            });
+            let copyspace = copyspace.unwrap();
+            let config = CopyConfig {
+                copy_mapping: enum_map! {
+                    CopySemantics::DefaultCopyPartition(CopyPartition::P0) => {
+                        CopySelector::CopySpace(0)
+                    }
+                    CopySemantics::DefaultCopyPartition(CopyPartition::P1) => {
+                        CopySelector::CopySpace(1)
+                    }
+                    _ => CopySelector::Unused,
+                },
+                space_mapping: vec![
+                    (CopySelector::CopySpace(0), copyspace),
+                    (CopySelector::CopySpace(1), copyspace),
+                ],
+                constraints: mmtk.get_plan().constraints(),
+                ..Default::default()
+            };
+            let mut copy =
+                GCWorkerCopyContext::new(VMWorkerThread(VMThread::UNINITIALIZED), mmtk, config);
+
+            let original = gc.alloc(0, 0, AllocationSemantics::Default);
+            let mut alloc = |partition| {
+                let semantics = CopySemantics::DefaultCopyPartition(partition);
+                copy.alloc_copy(original, 16, 8, 0, semantics)
+            };
+            let a0 = alloc(CopyPartition::P0);
+            let b0 = alloc(CopyPartition::P1);
+            let a1 = alloc(CopyPartition::P0);
+            let b1 = alloc(CopyPartition::P1);
+            // Each partition bumps its own thread-local buffer in the same space.
+            assert_eq!(a1, a0 + 16usize);
+            assert_eq!(b1, b0 + 16usize);
+            assert_ne!(b0, a0 + 16usize);
+            for address in [a0, b0, a1, b1] {
+                assert!(copyspace.address_in_space(address));
+            }
+        },
+        no_cleanup,
+    )
+}
@@ -0,0 +1,85 @@
+// GITHUB-CI: MMTK_PLAN=SemiSpace
+
+use super::mock_test_prelude::*;
+use crate::util::constants::BYTES_IN_ADDRESS;
+use crate::util::deduplication::DeduplicationStats;
+use crate::util::options::PlanSelector;
+use crate::util::test_util::mock_gc::*;
+use crate::util::{Address, ObjectReference};
+use crate::AllocationSemantics;
+
+/// A string is an object whose first field is its value.  The contents of a value are its fields.
+struct DeduplicateStrings;
+
+impl Deduplication<MockVM> for DeduplicateStrings {
+    fn value(&self, candidate: ObjectReference) -> Option<ObjectReference> {
+        load_field(candidate, 0)
+    }
+
+    fn contents(&self, value: ObjectReference) -> (Address, usize) {
+        (field_slot(value, 0), num_fields(value) * BYTES_IN_ADDRESS)
+    }
+
+    fn set_value(&self, candidate: ObjectReference, value: ObjectReference) {
+        unsafe { field_slot(candidate, 0).store(value) };
+    }
+}
+
+/// Check that live candidates with equal values are redirected to the same value, and the
+/// duplicated values are reclaimed afterwards.
+#[test]
+pub fn deduplication() {
+    with_mockvm(
+        default_setup,
+        || {
+            let mut gc = MockGC::new(3, 8 * 1024 * 1024, |builder| {
+                builder.options.plan.set(PlanSelector::SemiSpace);
+            });
+            let mmtk = gc.mmtk();
+            memory_manager::register_deduplication(mmtk, Box::new(DeduplicateStrings));
+
+            let x = gc.alloc(0, 0, AllocationSemantics::Default);
+            let string = |gc: &mut MockGC, id: u64, content: Option<ObjectReference>| {
+                let value = gc.alloc(1, id * 10, AllocationSemantics::Default);
+                gc.store_field(value, 0, content);
+                let string = gc.alloc(1, id, AllocationSemantics::Default);
+                gc.store_field(string, 0, Some(value));
+                memory_manager::add_deduplication_candidate(gc.mutator(), string);
+                string
+            };
+            let s1 = string(&mut gc, 1, Some(x));
+            let s2 = string(&mut gc, 2, Some(x));
+            let s3 = string(&mut gc, 3, None);
+            // Dead candidates are skipped.
+            string(&mut gc, 4, Some(x));
+            gc.store_root(0, Some(s1));
+            gc.store_root(1, Some(s2));
+            gc.store_root(2, Some(s3));
+            let v2 = gc.watch(load_field(s2, 0).unwrap());
+
+            assert!(gc.gc());
+            let s1 = gc.load_root(0).unwrap();
+            let s2 = gc.load_root(1).unwrap();
+            let s3 = gc.load_root(2).unwrap();
+            assert_eq!(load_field(s1, 0), load_field(s2, 0));
+            assert_ne!(load_field(s1, 0), load_field(s3, 0));
+            assert_eq!(
+                mmtk.last_gc_deduplication_stats(),
+                DeduplicationStats {
+                    candidates: 4,
+                    deduplicated: 1,
+                    bytes: BYTES_IN_ADDRESS,
+                }
+            );
+
+            // The duplicated value is not referenced any more.
+            assert!(gc.gc());
+            assert!(!gc.watched(v2).live);
+            assert_eq!(
+                mmtk.last_gc_deduplication_stats(),
+                DeduplicationStats::default()
+            );
+        },
+        no_cleanup,
+    )
+}
@@ -0,0 +1,33 @@
+// GITHUB-CI: MMTK_PLAN=Immix
+
+use super::mock_test_prelude::*;
+use crate::util::{Address, ObjectReference};
+
+lazy_static! {
+    static ref SINGLE_OBJECT: Fixture<SingleObject> = Fixture::new();
+}
+
+#[test]
+pub fn describe_object() {
+    with_mockvm(
+        default_setup,
+        || {
+            SINGLE_OBJECT.with_fixture(|fixture| {
+                let mmtk = fixture.mmtk();
+                let description = memory_manager::describe_object(mmtk, fixture.objref);
+                assert!(description.contains("in space immix (ImmixSpace)"));
+                assert!(description.contains("mark bit: "));
+                assert!(description.contains("not forwarded"));
+                assert!(description.contains("block "));
+
+                let outside = ObjectReference::from_raw_address(unsafe {
+                    Address::from_usize(DEFAULT_OBJECT_REF_OFFSET)
+                })
+                .unwrap();
+                let description = memory_manager::describe_object(mmtk, outside);
+                assert!(description.contains("not in any MMTk space"));
+            });
+        },
+        no_cleanup,
+    )
+}
@@ -0,0 +1,56 @@
+// GITHUB-CI: MMTK_PLAN=Immix
+// GITHUB-CI: FEATURES=fault_injection
+
+use super::mock_test_prelude::*;
+use crate::util::alloc::AllocationError;
+use crate::util::constants::BYTES_IN_ADDRESS;
+use crate::util::fault_injection::{self, FaultPoint};
+use crate::util::options::PlanSelector;
+use crate::util::test_util::mock_gc::*;
+use crate::AllocationSemantics;
+
+/// A page resource that fails a few times makes the mutator trigger GCs and retry the allocation.
+/// A page resource that keeps failing leads to emergency GCs, and then an out-of-memory error.
+#[test]
+pub fn fault_injection() {
+    with_mockvm(
+        default_setup,
+        || {
+            let mut gc = MockGC::new(0, 64 * 1024 * 1024, |builder| {
+                builder.options.plan.set(PlanSelector::Immix);
+            });
+            write_mockvm(|mock| {
+                mock.out_of_memory = MockMethod::new_fixed(Box::new(|(_, err, context)| {
+                    assert!(matches!(err, AllocationError::HeapOutOfMemory));
+                    assert!(context.emergency_collection_attempted);
+                }));
+            });
+            let size = object_size(0);
+
+            // The first allocation of the mutator needs a new block.
+            fault_injection::inject(FaultPoint::PageResource, 0, 2);
+            gc.alloc(0, 0, AllocationSemantics::Default);
+            assert_eq!(fault_injection::injected(FaultPoint::PageResource), 2);
+            assert_eq!(gc.gcs(), 2);
+            read_mockvm(|mock| assert!(!mock.out_of_memory.is_called()));
+
+            // After a GC, the mutator needs a new block again.
+            assert!(gc.gc());
+            fault_injection::inject(FaultPoint::PageResource, 0, usize::MAX);
+            let start = memory_manager::alloc(
+                gc.mutator(),
+                size,
+                BYTES_IN_ADDRESS,
+                0,
+                AllocationSemantics::Default,
+            );
+            assert!(start.is_zero());
+            read_mockvm(|mock| assert!(mock.out_of_memory.is_called()));
+
+            // The heap is usable again once the fault is cleared.
+            fault_injection::clear(FaultPoint::PageResource);
+            gc.alloc(0, 0, AllocationSemantics::Default);
+        },
+        fault_injection::clear_all,
+    )
+}
@@ -0,0 +1,74 @@
+use super::mock_test_prelude::*;
+use crate::AllocationSemantics;
+
+/// GC-disabled scopes are counted, so GC stays disabled until the outermost scope ends, even if
+/// the scopes end in a different order or on different threads.  Allocation keeps going past the
+/// heap limit while GC is disabled, and the suppressed GCs are reported to the binding less and
+/// less often.  Once all the scopes end, polling triggers a GC again.
+#[test]
+pub fn gc_disabled_scope() {
+    // 1MB heap
+    with_mockvm(
+        || -> MockVM {
+            MockVM {
+                block_for_gc: MockMethod::new_default(),
+                ..MockVM::default()
+            }
+        },
+        || {
+            const MB: usize = 1024 * 1024;
+            let mut fixture = MutatorFixture::create_with_heapsize(MB);
+            let mmtk = fixture.mmtk();
+            let tls = fixture.mutator.mutator_tls;
+            assert!(!memory_manager::is_gc_disabled(mmtk));
+
+            let outer = memory_manager::gc_disabled_scope(mmtk);
+            let inner = std::thread::scope(|scope| {
+                scope
+                    .spawn(|| memory_manager::gc_disabled_scope(mmtk))
+                    .join()
+                    .unwrap()
+            });
+            drop(outer);
+            assert!(memory_manager::is_gc_disabled(mmtk));
+
+            // Fill up the heap and keep allocating.  None of these allocations triggers a GC.
+            for _ in 0..16 {
+                let addr = memory_manager::alloc(
+                    &mut fixture.mutator,
+                    MB >> 2,
+                    8,
+                    0,
+                    AllocationSemantics::Default,
+                );
+                assert!(!addr.is_zero());
+            }
+            memory_manager::gc_poll(mmtk, tls);
+            assert!(!memory_manager::handle_user_collection_request(mmtk, tls));
+
+            let suppressed = mmtk.gc_suppressed_polls();
+            assert!(suppressed >= 8);
+            read_mockvm(|mock| {
+                assert!(!mock.block_for_gc.is_called());
+                assert_eq!(
+                    mock.gc_suppressed.call_count(),
+                    suppressed.ilog2() as usize + 1
+                );
+            });
+
+            drop(inner);
+            assert!(!memory_manager::is_gc_disabled(mmtk));
+            let disabled_time = mmtk.gc_disabled_time();
+            assert!(!disabled_time.is_zero());
+            assert_eq!(mmtk.gc_disabled_time(), disabled_time);
+
+            // GC is enabled again.
+            memory_manager::gc_poll(mmtk, tls);
+            assert_eq!(mmtk.gc_suppressed_polls(), suppressed);
+            read_mockvm(|mock| {
+                assert!(mock.block_for_gc.is_called());
+            });
+        },
+        no_cleanup,
+    )
+}
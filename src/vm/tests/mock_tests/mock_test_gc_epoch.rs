@@ -0,0 +1,58 @@
+// GITHUB-CI: MMTK_PLAN=SemiSpace
+
+use super::mock_test_prelude::*;
+use crate::memory_manager;
+use crate::util::options::PlanSelector;
+use crate::util::test_util::scenario::*;
+use crate::GcEpoch;
+
+/// Check that the GC epoch advances with each GC, and that the objects moved by the last GC are
+/// reported with the epoch.
+#[test]
+pub fn gc_epoch() {
+    with_mockvm(
+        default_setup,
+        || {
+            let mut s = Scenario::new(|builder| {
+                builder.options.plan.set(PlanSelector::SemiSpace);
+                builder.options.record_relocations.set(true);
+            });
+            let a = s.object("a", 0);
+            s.root(a);
+
+            let mmtk = s.mock_gc().mmtk();
+            assert_eq!(memory_manager::gc_epoch(mmtk), GcEpoch::default());
+            assert_eq!(
+                memory_manager::object_last_moved_epoch(mmtk, s.address(a)),
+                None
+            );
+
+            s.gc().assert_moved(&[a]);
+            // SemiSpace is not generational, so all its GCs are full-heap GCs.
+            let epoch = memory_manager::gc_epoch(mmtk);
+            assert_eq!(
+                epoch,
+                GcEpoch {
+                    nursery: 0,
+                    full: 1
+                }
+            );
+            assert_eq!(epoch.total(), 1);
+            assert_eq!(
+                memory_manager::object_last_moved_epoch(mmtk, s.address(a)),
+                Some(epoch)
+            );
+
+            // An object allocated after the GC has not been moved.
+            let b = s.object("b", 0);
+            assert_eq!(
+                memory_manager::object_last_moved_epoch(mmtk, s.address(b)),
+                None
+            );
+
+            s.gc();
+            assert_eq!(memory_manager::gc_epoch(mmtk).full, 2);
+        },
+        no_cleanup,
+    )
+}
@@ -0,0 +1,28 @@
+// GITHUB-CI: MMTK_PLAN=MarkSweep
+// GITHUB-CI: FEATURES=vo_bit
+
+use super::mock_test_prelude::*;
+use crate::util::options::PlanSelector;
+use crate::util::test_util::gc_fuzzer::*;
+
+/// Run random allocations, mutations and GCs with multiple GC workers, and check that no reachable
+/// object is lost after each GC.
+#[test]
+pub fn fuzz_mark_sweep() {
+    with_mockvm(
+        default_setup,
+        || {
+            let config = FuzzerConfig {
+                seed: 42,
+                ..Default::default()
+            };
+            let report = run_gc_fuzzer(&config, |builder| {
+                builder.options.plan.set(PlanSelector::MarkSweep);
+                builder.options.threads.set(4);
+            });
+            assert!(report.gcs > 0);
+            assert!(report.checked > 0);
+        },
+        no_cleanup,
+    )
+}
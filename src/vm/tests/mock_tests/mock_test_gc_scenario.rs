@@ -0,0 +1,57 @@
+// GITHUB-CI: MMTK_PLAN=SemiSpace
+
+use super::mock_test_prelude::*;
+use crate::util::options::PlanSelector;
+use crate::util::test_util::scenario::*;
+use crate::AllocationSemantics;
+
+/// Check liveness, forwarding and space residency in a small SemiSpace scenario.
+#[test]
+pub fn semispace_scenario() {
+    with_mockvm(
+        default_setup,
+        || {
+            let mut s = Scenario::new(|builder| {
+                builder.options.plan.set(PlanSelector::SemiSpace);
+                builder.options.threads.set(2);
+            });
+            let a = s.object("a", 2);
+            let b = s.object("b", 1);
+            let c = s.object("c", 0);
+            let garbage = s.object("garbage", 1);
+            s.assert_in_space(&[a, b, c, garbage], "copyspace0");
+
+            // a -> b -> c and a -> c.  garbage -> c, but nothing refers to garbage.
+            s.root(a)
+                .link(a, 0, b)
+                .link(b, 0, c)
+                .link(a, 1, c)
+                .link(garbage, 0, c);
+            s.gc()
+                .assert_live(&[a, b, c])
+                .assert_dead(&[garbage])
+                .assert_moved(&[a, b, c])
+                .assert_in_space(&[a, b, c], "copyspace1");
+
+            // c is still reachable from a.
+            s.unlink(a, 0)
+                .gc()
+                .assert_live(&[a, c])
+                .assert_dead(&[b])
+                .assert_in_space(&[a, c], "copyspace0");
+
+            // Objects in the LOS are not moved.
+            let large = s.object_with_semantics("large", 1, AllocationSemantics::Los);
+            s.root(large)
+                .link(large, 0, c)
+                .unroot(a)
+                .gc()
+                .assert_live(&[large, c])
+                .assert_dead(&[a])
+                .assert_not_moved(&[large])
+                .assert_moved(&[c])
+                .assert_in_space(&[large], "los");
+        },
+        no_cleanup,
+    )
+}
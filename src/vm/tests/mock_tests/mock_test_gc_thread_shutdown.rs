@@ -0,0 +1,59 @@
+// GITHUB-CI: MMTK_PLAN=NoGC
+
+use std::{sync::Mutex, thread::JoinHandle};
+
+use super::mock_test_prelude::*;
+use crate::{
+    util::{options::GCTriggerSelector, Address, OpaquePointer, VMThread, VMWorkerThread},
+    MMTKBuilder, MMTK,
+};
+
+static JOIN_HANDLES: Mutex<Vec<JoinHandle<()>>> = Mutex::new(Vec::new());
+
+const NUM_WORKER_THREADS: usize = 2;
+
+fn join_gc_threads() {
+    let join_handles = std::mem::take(&mut *JOIN_HANDLES.lock().unwrap());
+    assert_eq!(join_handles.len(), NUM_WORKER_THREADS);
+    for join_handle in join_handles {
+        join_handle.join().unwrap();
+    }
+}
+
+/// Test pausing, resuming and shutting down GC threads.
+#[test]
+pub fn test_pause_resume_and_shutdown_gc_threads() {
+    let mut builder = MMTKBuilder::new();
+    builder
+        .options
+        .gc_trigger
+        .set(GCTriggerSelector::FixedHeapSize(1024 * 1024));
+    builder.options.threads.set(NUM_WORKER_THREADS);
+    let mmtk: &'static mut MMTK<MockVM> = Box::leak(Box::new(builder.build::<MockVM>()));
+
+    let mock_vm = MockVM {
+        spawn_gc_thread: MockMethod::new_fixed(Box::new(|(_vm_thread, context)| {
+            let GCThreadContext::Worker(worker) = context;
+            let join_handle = std::thread::spawn(move || {
+                let tls = VMWorkerThread(VMThread(OpaquePointer::from_address(Address::ZERO)));
+                memory_manager::start_worker(mmtk, tls, worker);
+            });
+            JOIN_HANDLES.lock().unwrap().push(join_handle);
+        })),
+        ..Default::default()
+    };
+    write_mockvm(move |mock_vm_ref| *mock_vm_ref = mock_vm);
+
+    let tls = VMThread(OpaquePointer::from_address(Address::ZERO));
+    mmtk.initialize_collection(tls);
+
+    // All workers have exited when `pause_gc_threads` returns.
+    mmtk.pause_gc_threads();
+    join_gc_threads();
+
+    mmtk.resume_gc_threads(tls);
+
+    mmtk.shutdown();
+    join_gc_threads();
+    assert!(!mmtk.state.is_initialized());
+}
@@ -0,0 +1,59 @@
+// GITHUB-CI: MMTK_PLAN=SemiSpace
+
+use super::mock_test_prelude::*;
+use crate::memory_manager;
+use crate::util::handle_table::HandleKind;
+use crate::util::options::PlanSelector;
+use crate::util::test_util::scenario::*;
+
+/// Check that strong handles keep their objects alive, that weak handles are cleared when their
+/// objects die, and that both are updated when their objects are moved.
+#[test]
+pub fn handle_table() {
+    with_mockvm(
+        default_setup,
+        || {
+            let mut s = Scenario::new(|builder| {
+                builder.options.plan.set(PlanSelector::SemiSpace);
+            });
+            let a = s.object("a", 1);
+            let b = s.object("b", 0);
+            let c = s.object("c", 0);
+            s.link(a, 0, b);
+
+            let mmtk = s.mock_gc().mmtk();
+            let strong_a = memory_manager::create_handle(mmtk, s.address(a), HandleKind::Strong);
+            let weak_b = memory_manager::create_handle(mmtk, s.address(b), HandleKind::Weak);
+            let weak_c = memory_manager::create_handle(mmtk, s.address(c), HandleKind::Weak);
+            assert_eq!(
+                memory_manager::load_handle(mmtk, strong_a),
+                Some(s.address(a))
+            );
+
+            // `a` is only reachable from a strong handle.
+            s.gc()
+                .assert_live(&[a, b])
+                .assert_moved(&[a, b])
+                .assert_dead(&[c]);
+            assert_eq!(
+                memory_manager::load_handle(mmtk, strong_a),
+                Some(s.address(a))
+            );
+            assert_eq!(
+                memory_manager::load_handle(mmtk, weak_b),
+                Some(s.address(b))
+            );
+            assert_eq!(memory_manager::load_handle(mmtk, weak_c), None);
+
+            memory_manager::delete_handle(mmtk, strong_a);
+            s.gc().assert_dead(&[a, b]);
+            assert_eq!(memory_manager::load_handle(mmtk, weak_b), None);
+
+            // Deleted handles are reused.
+            let d = s.object("d", 0);
+            let strong_d = memory_manager::create_handle(mmtk, s.address(d), HandleKind::Strong);
+            assert_eq!(strong_d, strong_a);
+        },
+        no_cleanup,
+    )
+}
@@ -0,0 +1,135 @@
+// GITHUB-CI: MMTK_PLAN=all
+
+use super::mock_test_prelude::*;
+use crate::util::{VMMutatorThread, VMThread};
+use crate::AllocationSemantics;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+#[test]
+pub fn handshake_with_one_mutator() {
+    with_mockvm(
+        default_setup,
+        || {
+            let mut fixture = MutatorFixture::create();
+            let mmtk = fixture.mmtk();
+            mock_mutators(&mut [&mut *fixture.mutator]);
+
+            // No handshake is in progress.
+            assert!(!memory_manager::handshake_yieldpoint(
+                mmtk,
+                &mut fixture.mutator
+            ));
+
+            let executed = Arc::new(AtomicUsize::new(0));
+            let requester = {
+                let executed = executed.clone();
+                std::thread::spawn(move || {
+                    memory_manager::request_handshake(
+                        mmtk,
+                        VMThread::UNINITIALIZED,
+                        Arc::new(move |_mutator| {
+                            executed.fetch_add(1, Ordering::SeqCst);
+                        }),
+                    );
+                })
+            };
+
+            // Keep reaching yieldpoints until the mutator executes the action.
+            while !memory_manager::handshake_yieldpoint(mmtk, &mut fixture.mutator) {
+                std::thread::yield_now();
+            }
+            requester.join().unwrap();
+            assert_eq!(executed.load(Ordering::SeqCst), 1);
+
+            // The action is executed only once.
+            assert!(!memory_manager::handshake_yieldpoint(
+                mmtk,
+                &mut fixture.mutator
+            ));
+            assert_eq!(executed.load(Ordering::SeqCst), 1);
+        },
+        || {
+            read_mockvm(|mock| {
+                assert!(mock.request_handshake.is_called());
+            });
+        },
+    )
+}
+
+#[test]
+pub fn flush_all_mutators() {
+    with_mockvm(
+        default_setup,
+        || {
+            let mut fixture = MutatorFixture::create();
+            let mmtk = fixture.mmtk();
+            mock_mutators(&mut [&mut *fixture.mutator]);
+
+            let addr =
+                memory_manager::alloc(&mut fixture.mutator, 16, 8, 0, AllocationSemantics::Default);
+            assert!(!addr.is_zero());
+
+            let requester = std::thread::spawn(move || {
+                memory_manager::flush_all_mutators(mmtk, VMThread::UNINITIALIZED);
+            });
+            while !memory_manager::handshake_yieldpoint(mmtk, &mut fixture.mutator) {
+                std::thread::yield_now();
+            }
+            requester.join().unwrap();
+
+            // The mutator can still allocate after its buffers are retired.
+            let addr =
+                memory_manager::alloc(&mut fixture.mutator, 16, 8, 0, AllocationSemantics::Default);
+            assert!(!addr.is_zero());
+        },
+        no_cleanup,
+    )
+}
+
+#[test]
+pub fn handshake_with_destroyed_mutator() {
+    with_mockvm(
+        default_setup,
+        || {
+            let mut fixture = MutatorFixture::create();
+            let mmtk = fixture.mmtk();
+            let mut other =
+                memory_manager::bind_mutator(mmtk, VMMutatorThread(VMThread::UNINITIALIZED));
+            mock_mutators(&mut [&mut *fixture.mutator, &mut *other]);
+
+            let requester = std::thread::spawn(move || {
+                memory_manager::request_handshake(
+                    mmtk,
+                    VMThread::UNINITIALIZED,
+                    Arc::new(|_mutator| {}),
+                );
+            });
+            while !memory_manager::handshake_yieldpoint(mmtk, &mut fixture.mutator) {
+                std::thread::yield_now();
+            }
+            // The other mutator is destroyed without reaching a yieldpoint.  The handshake is
+            // still completed.
+            memory_manager::destroy_mutator(&mut other);
+            requester.join().unwrap();
+
+            // A mutator bound after a handshake starts does not take part in it.
+            let mut late =
+                memory_manager::bind_mutator(mmtk, VMMutatorThread(VMThread::UNINITIALIZED));
+            mock_mutators(&mut [&mut *fixture.mutator]);
+            let requester = std::thread::spawn(move || {
+                memory_manager::request_handshake(
+                    mmtk,
+                    VMThread::UNINITIALIZED,
+                    Arc::new(|_mutator| {}),
+                );
+            });
+            while !memory_manager::handshake_yieldpoint(mmtk, &mut fixture.mutator) {
+                assert!(!memory_manager::handshake_yieldpoint(mmtk, &mut late));
+                std::thread::yield_now();
+            }
+            requester.join().unwrap();
+        },
+        no_cleanup,
+    )
+}
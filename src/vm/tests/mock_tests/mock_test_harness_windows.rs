@@ -0,0 +1,60 @@
+// GITHUB-CI: MMTK_PLAN=NoGC
+
+use super::mock_test_prelude::*;
+use crate::AllocationSemantics;
+
+#[test]
+pub fn nested_harness_windows() {
+    with_mockvm(
+        default_setup,
+        || {
+            let mut fixture = MutatorFixture::create();
+            let mmtk = fixture.mmtk();
+            let harness = mmtk.harness();
+            assert!(!mmtk.stats.get_gathering_stats());
+
+            harness.begin("outer");
+            assert!(mmtk.stats.get_gathering_stats());
+            harness.begin("iteration");
+            let addr =
+                memory_manager::alloc(&mut fixture.mutator, 40, 8, 0, AllocationSemantics::Default);
+            assert!(!addr.is_zero());
+            assert_eq!(harness.open_windows(), vec!["outer", "iteration"]);
+
+            // A window that is not open.
+            assert!(harness.end("unknown").is_none());
+
+            let inner = harness.end("iteration").unwrap();
+            assert_eq!(inner.name, "iteration");
+            assert_eq!(inner.depth, 1);
+            assert_eq!(inner.gc_count, 0);
+            assert!(inner.counters.contains_key("time.other"));
+            assert!(inner.counters.contains_key("time.stw"));
+            // Statistics are still gathered for the outer window.
+            assert!(mmtk.stats.get_gathering_stats());
+
+            let outer = harness.end("outer").unwrap();
+            assert_eq!(outer.depth, 0);
+            assert!(outer.elapsed >= inner.elapsed);
+            assert!(outer.counters["time.other"] >= inner.counters["time.other"]);
+            assert!(harness.open_windows().is_empty());
+            assert!(!mmtk.stats.get_gathering_stats());
+
+            // Statistics enabled by `set_stats_enabled` stay enabled after the windows are closed.
+            mmtk.set_stats_enabled(true);
+            harness.begin("second");
+            assert!(harness.end("second").is_some());
+            assert!(mmtk.stats.get_gathering_stats());
+            mmtk.set_stats_enabled(false);
+
+            // Statistics enabled while a window is open also stay enabled.
+            harness.begin("third");
+            mmtk.set_stats_enabled(true);
+            assert!(harness.end("third").is_some());
+            assert!(mmtk.stats.get_gathering_stats());
+            mmtk.set_stats_enabled(false);
+            assert!(!mmtk.stats.get_gathering_stats());
+        },
+        no_cleanup,
+    )
+}
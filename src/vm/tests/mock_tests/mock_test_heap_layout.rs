@@ -0,0 +1,52 @@
+// GITHUB-CI: MMTK_PLAN=all
+
+use super::mock_test_prelude::*;
+use crate::util::heap_layout::MmapChunkState;
+use crate::AllocationSemantics;
+
+#[test]
+pub fn dump_heap_layout() {
+    with_mockvm(
+        default_setup,
+        || {
+            let mut fixture = MutatorFixture::create();
+
+            let size = 40;
+            let semantics = AllocationSemantics::Default;
+            let addr = memory_manager::alloc(&mut fixture.mutator, size, 8, 0, semantics);
+            assert!(!addr.is_zero());
+
+            let layout = fixture.mmtk().dump_heap_layout();
+            let dump = layout.to_string();
+            assert!(layout.validate().is_ok(), "{:?}", layout.validate());
+
+            // Every space is listed.
+            let mut names = vec![];
+            fixture
+                .mmtk()
+                .get_plan()
+                .for_each_space(&mut |space| names.push(space.get_name()));
+            for name in names {
+                assert!(layout.spaces.iter().any(|space| space.name == name));
+                assert!(dump.contains(&format!("space\t{name}\t")));
+            }
+
+            // The new object is in a space with side metadata, and in mapped memory, unless it is
+            // allocated by malloc.
+            if let Some(space) = layout
+                .spaces
+                .iter()
+                .find(|space| space.ranges.iter().any(|range| range.contains(&addr)))
+            {
+                assert!(layout
+                    .side_metadata
+                    .iter()
+                    .any(|metadata| metadata.space == space.name));
+                assert!(layout.mmap_chunks.iter().any(|chunks| {
+                    chunks.range.contains(&addr) && chunks.state == MmapChunkState::Mapped
+                }));
+            }
+        },
+        no_cleanup,
+    )
+}
@@ -0,0 +1,89 @@
+// GITHUB-CI: MMTK_PLAN=Immix
+
+use super::mock_test_prelude::*;
+use crate::plan::Mutator;
+use crate::policy::immix::block::Block;
+use crate::policy::immix::line::Line;
+use crate::util::linear_scan::Region;
+use crate::util::options::{GCTriggerSelector, PlanSelector};
+use crate::util::{VMMutatorThread, VMThread};
+use crate::AllocationSemantics;
+use crate::MMTK;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+static MMTK_PTR: AtomicUsize = AtomicUsize::new(0);
+static IDLE_MUTATOR: AtomicUsize = AtomicUsize::new(0);
+
+/// Check that a mutator that keeps allocating flushes an idle mutator after the timeout, and then
+/// allocates into the unused lines of the block the idle mutator was allocating into.
+#[test]
+pub fn idle_mutator_flush() {
+    with_mockvm(
+        || -> MockVM {
+            MockVM {
+                // Keep allocating after the heap is full.
+                is_collection_enabled: MockMethod::new_fixed(Box::new(|_| false)),
+                // The idle mutator is blocked, so the VM acknowledges handshakes on its behalf.
+                request_handshake: MockMethod::new_fixed(Box::new(|_| {
+                    let mmtk =
+                        unsafe { &*(MMTK_PTR.load(Ordering::SeqCst) as *const MMTK<MockVM>) };
+                    let mutator = unsafe {
+                        &mut *(IDLE_MUTATOR.load(Ordering::SeqCst) as *mut Mutator<MockVM>)
+                    };
+                    assert!(memory_manager::handshake_yieldpoint(mmtk, mutator));
+                })),
+                ..MockVM::default()
+            }
+        },
+        || {
+            const MB: usize = 1024 * 1024;
+            let mut fixture = MutatorFixture::create_with_builder(|builder| {
+                builder.options.plan.set(PlanSelector::Immix);
+                builder
+                    .options
+                    .gc_trigger
+                    .set(GCTriggerSelector::FixedHeapSize(MB));
+                builder.options.idle_mutator_flush_timeout.set(1);
+            });
+            let mmtk = fixture.mmtk();
+            let mut idle =
+                memory_manager::bind_mutator(mmtk, VMMutatorThread(VMThread::UNINITIALIZED));
+            MMTK_PTR.store(mmtk as *const MMTK<MockVM> as usize, Ordering::SeqCst);
+            IDLE_MUTATOR.store(
+                &mut *idle as *mut Mutator<MockVM> as usize,
+                Ordering::SeqCst,
+            );
+            mock_mutators(&mut [&mut *fixture.mutator, &mut *idle]);
+
+            // The idle mutator allocates an object into a clean block, and stops allocating.
+            let held = memory_manager::alloc(&mut idle, 16, 8, 0, AllocationSemantics::Default);
+            assert!(!held.is_zero());
+            let held_block = Block::from_unaligned_address(held);
+
+            // The other mutator allocates objects of a line, so that each allocation goes to the
+            // slow path.  The first handshake only finds that the idle mutator has allocated since
+            // the mutators were created.  The next handshake flushes the idle mutator.
+            let mut reclaimed = false;
+            for _ in 0..(2 * MB / Line::BYTES) {
+                std::thread::sleep(Duration::from_millis(2));
+                let addr = memory_manager::alloc(
+                    &mut fixture.mutator,
+                    Line::BYTES,
+                    8,
+                    0,
+                    AllocationSemantics::Default,
+                );
+                assert!(!addr.is_zero());
+                memory_manager::handshake_yieldpoint(mmtk, &mut fixture.mutator);
+                if Block::from_unaligned_address(addr) == held_block {
+                    assert!(addr > held);
+                    reclaimed = true;
+                    break;
+                }
+            }
+            assert!(reclaimed);
+        },
+        no_cleanup,
+    )
+}
@@ -0,0 +1,41 @@
+// GITHUB-CI: MMTK_PLAN=Immix
+
+use super::mock_test_prelude::*;
+use crate::util::options::PlanSelector;
+use crate::util::test_util::mock_gc::*;
+use crate::AllocationSemantics;
+
+/// Check that the live bytes histogram of Immix counts the bytes of the live objects rather than
+/// the lines they occupy.
+#[test]
+pub fn immix_live_bytes() {
+    with_mockvm(
+        default_setup,
+        || {
+            let mut gc = MockGC::new(4, 8 * 1024 * 1024, |builder| {
+                builder.options.plan.set(PlanSelector::Immix);
+            });
+            let histogram = gc.mmtk().last_gc_immix_live_bytes_histogram();
+            assert!(histogram.iter().all(|bytes| *bytes == 0));
+
+            // Small objects share lines.  Only the rooted ones are live.
+            for i in 0..8 {
+                let object = gc.alloc(2, i, AllocationSemantics::Default);
+                if i % 2 == 0 {
+                    gc.store_root(i as usize / 2, Some(object));
+                }
+            }
+
+            assert!(gc.gc());
+            let histogram = gc.mmtk().last_gc_immix_live_bytes_histogram();
+            assert_eq!(histogram.iter().sum::<usize>(), 4 * object_size(2));
+
+            gc.store_root(0, None);
+            gc.store_root(1, None);
+            assert!(gc.gc());
+            let histogram = gc.mmtk().last_gc_immix_live_bytes_histogram();
+            assert_eq!(histogram.iter().sum::<usize>(), 2 * object_size(2));
+        },
+        no_cleanup,
+    )
+}
@@ -0,0 +1,73 @@
+// GITHUB-CI: MMTK_PLAN=GenCopy,GenImmix,StickyImmix
+
+use super::mock_test_prelude::*;
+use crate::plan::{AllocationSemantics, VectorObjectQueue};
+use crate::policy::largeobjectspace::LargeObjectSpace;
+use crate::policy::space::Space;
+use crate::util::alloc::allocator::get_maximum_aligned_size;
+use crate::util::conversions::bytes_to_pages_up;
+use crate::util::ObjectReference;
+
+const SIZE: usize = 16 * 1024;
+
+fn alloc_large_object(fixture: &mut MutatorFixture) -> ObjectReference {
+    let addr = memory_manager::alloc(&mut fixture.mutator, SIZE, 8, 0, AllocationSemantics::Los);
+    assert!(!addr.is_zero());
+    let objref = MockVM::object_start_to_ref(addr);
+    memory_manager::post_alloc(&mut fixture.mutator, objref, SIZE, AllocationSemantics::Los);
+    objref
+}
+
+fn large_object_pages() -> usize {
+    bytes_to_pages_up(get_maximum_aligned_size::<MockVM>(SIZE, 8))
+}
+
+#[test]
+pub fn young_large_objects_are_in_the_nursery() {
+    with_mockvm(
+        default_setup,
+        || {
+            let mut fixture = MutatorFixture::create_with_heapsize(20 * 1024 * 1024);
+            let los = fixture.mmtk().get_plan().common().get_los();
+            assert_eq!(los.nursery_pages(), 0);
+            let mature_pages = los.mature_pages();
+
+            alloc_large_object(&mut fixture);
+
+            // The new object is young until it survives a GC.
+            assert_eq!(los.nursery_pages(), large_object_pages());
+            assert_eq!(los.mature_pages(), mature_pages);
+            assert_eq!(los.promoted_pages(), 0);
+        },
+        no_cleanup,
+    )
+}
+
+#[test]
+pub fn surviving_large_objects_are_promoted() {
+    with_mockvm(
+        default_setup,
+        || {
+            let mut fixture = MutatorFixture::create_with_heapsize(20 * 1024 * 1024);
+            let objref = alloc_large_object(&mut fixture);
+
+            // Run the steps of a nursery GC on the LOS, in which the object is reachable.
+            let plan = unsafe { fixture.mmtk().get_plan_mut() };
+            plan.for_each_space_mut(&mut |space| {
+                let Some(los) = space.downcast_mut::<LargeObjectSpace<MockVM>>() else {
+                    return;
+                };
+                let mature_pages = los.mature_pages();
+                los.prepare(false);
+                los.trace_object(&mut VectorObjectQueue::new(), objref);
+                assert_eq!(los.promoted_pages(), large_object_pages());
+                los.release(false);
+
+                assert_eq!(los.nursery_pages(), 0);
+                assert_eq!(los.promoted_pages(), large_object_pages());
+                assert_eq!(los.mature_pages(), mature_pages + large_object_pages());
+            });
+        },
+        no_cleanup,
+    )
+}
@@ -0,0 +1,38 @@
+// GITHUB-CI: MMTK_PLAN=MarkCompact
+
+use super::mock_test_prelude::*;
+use crate::util::handle_table::HandleKind;
+use crate::util::options::PlanSelector;
+use crate::util::test_util::mock_gc::*;
+use crate::AllocationSemantics;
+
+/// An object that is only reachable from a strong handle is traced again with the forwarding trace
+/// of MarkCompact, so the handle and the fields of the object are updated when the objects move.
+#[test]
+pub fn markcompact_handles() {
+    with_mockvm(
+        default_setup,
+        || {
+            let mut gc = MockGC::new(0, 64 * 1024 * 1024, |builder| {
+                builder.options.plan.set(PlanSelector::MarkCompact);
+            });
+            let mmtk = gc.mmtk();
+
+            // The dead object before the others makes them move.
+            gc.alloc(0, 0, AllocationSemantics::Default);
+            let parent = gc.alloc(1, 1, AllocationSemantics::Default);
+            let child = gc.alloc(0, 2, AllocationSemantics::Default);
+            gc.store_field(parent, 0, Some(child));
+            let handle = memory_manager::create_handle(mmtk, parent, HandleKind::Strong);
+
+            assert!(gc.gc());
+            let new_parent = memory_manager::load_handle(mmtk, handle).unwrap();
+            assert_ne!(new_parent, parent);
+            assert_eq!(object_id(new_parent), 1);
+            let new_child = load_field(new_parent, 0).unwrap();
+            assert_ne!(new_child, child);
+            assert_eq!(object_id(new_child), 2);
+        },
+        no_cleanup,
+    )
+}
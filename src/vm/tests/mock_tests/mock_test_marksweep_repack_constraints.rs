@@ -0,0 +1,28 @@
+// GITHUB-CI: MMTK_PLAN=MarkSweep
+
+use super::mock_test_prelude::*;
+use crate::util::options::PlanSelector;
+
+/// The MarkSweep plan reports that it moves objects if and only if the native mark sweep space is
+/// repacked.
+#[test]
+pub fn marksweep_repack_constraints() {
+    with_mockvm(
+        default_setup,
+        || {
+            for repack_interval in [0, 2] {
+                let fixture = MMTKFixture::create_with_builder(
+                    |builder| {
+                        builder.options.plan.set(PlanSelector::MarkSweep);
+                        builder.options.ms_repack_interval.set(repack_interval);
+                    },
+                    false,
+                );
+                let moves_objects = fixture.get_mmtk().get_plan().constraints().moves_objects;
+                let repacked = cfg!(not(feature = "malloc_mark_sweep")) && repack_interval != 0;
+                assert_eq!(moves_objects, repacked);
+            }
+        },
+        no_cleanup,
+    )
+}
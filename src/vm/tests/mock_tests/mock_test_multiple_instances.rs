@@ -0,0 +1,90 @@
+// GITHUB-CI: MMTK_PLAN=all
+
+use super::mock_test_prelude::*;
+
+use crate::plan::AllocationSemantics;
+use crate::util::Address;
+use crate::MMTK;
+use std::ops::Range;
+
+const MB: usize = 1024 * 1024;
+
+fn space_ranges(mmtk: &MMTK<MockVM>) -> Vec<Range<Address>> {
+    let mut ranges = vec![];
+    mmtk.get_plan().for_each_space(&mut |space| {
+        let common = space.common();
+        if common.contiguous && common.extent != 0 {
+            ranges.push(common.start..common.start + common.extent);
+        }
+    });
+    ranges
+}
+
+fn in_ranges(ranges: &[Range<Address>], addr: Address) -> bool {
+    ranges.iter().any(|range| range.contains(&addr))
+}
+
+#[test]
+pub fn two_instances() {
+    with_mockvm(
+        default_setup,
+        || {
+            let mut first = MutatorFixture::create_with_heapsize(MB);
+            let mut second = MutatorFixture::create_with_heapsize(MB);
+
+            // The spaces of the two instances do not overlap.
+            let first_ranges = space_ranges(first.mmtk());
+            let second_ranges = space_ranges(second.mmtk());
+            for a in first_ranges.iter() {
+                for b in second_ranges.iter() {
+                    assert!(
+                        Address::range_intersection(a, b).is_empty(),
+                        "{:?} overlaps with {:?}",
+                        a,
+                        b
+                    );
+                }
+            }
+
+            // Each instance allocates into its own spaces.
+            let first_addr =
+                memory_manager::alloc(&mut first.mutator, 16, 8, 0, AllocationSemantics::Default);
+            let first_obj = MockVM::object_start_to_ref(first_addr);
+            memory_manager::post_alloc(
+                &mut first.mutator,
+                first_obj,
+                16,
+                AllocationSemantics::Default,
+            );
+            let second_addr =
+                memory_manager::alloc(&mut second.mutator, 16, 8, 0, AllocationSemantics::Default);
+            let second_obj = MockVM::object_start_to_ref(second_addr);
+            memory_manager::post_alloc(
+                &mut second.mutator,
+                second_obj,
+                16,
+                AllocationSemantics::Default,
+            );
+
+            assert!(in_ranges(&first_ranges, first_addr));
+            assert!(!in_ranges(&second_ranges, first_addr));
+            assert!(in_ranges(&second_ranges, second_addr));
+            assert!(!in_ranges(&first_ranges, second_addr));
+
+            // The global SFT map and mmapper know about both instances.
+            assert!(memory_manager::is_in_mmtk_spaces(first_obj));
+            assert!(memory_manager::is_in_mmtk_spaces(second_obj));
+            assert!(memory_manager::is_mapped_address(first_addr));
+            assert!(memory_manager::is_mapped_address(second_addr));
+
+            // Allocating in one instance does not use the heap of the other.
+            let second_used = memory_manager::used_bytes(second.mmtk());
+            let large =
+                memory_manager::alloc(&mut first.mutator, MB / 4, 8, 0, AllocationSemantics::Los);
+            assert!(!large.is_zero());
+            assert!(in_ranges(&first_ranges, large));
+            assert_eq!(memory_manager::used_bytes(second.mmtk()), second_used);
+        },
+        no_cleanup,
+    )
+}
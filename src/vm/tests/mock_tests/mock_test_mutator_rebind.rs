@@ -0,0 +1,41 @@
+// GITHUB-CI: MMTK_PLAN=all
+
+use super::mock_test_prelude::*;
+use crate::util::alloc::Allocator;
+use crate::util::{Address, OpaquePointer, VMMutatorThread, VMThread};
+use crate::AllocationSemantics;
+
+#[test]
+pub fn rebind_and_park_mutator() {
+    with_mockvm(
+        default_setup,
+        || {
+            let mut fixture = MutatorFixture::create();
+            let mmtk = fixture.mmtk();
+            let selector =
+                memory_manager::get_allocator_mapping(mmtk, AllocationSemantics::Default);
+
+            let addr =
+                memory_manager::alloc(&mut fixture.mutator, 16, 8, 0, AllocationSemantics::Default);
+            assert!(!addr.is_zero());
+
+            // Migrate the mutator to another thread.
+            let new_tls = VMMutatorThread(VMThread(OpaquePointer::from_address(unsafe {
+                Address::from_usize(0x1000)
+            })));
+            memory_manager::rebind_mutator(&mut fixture.mutator, new_tls);
+            assert_eq!(fixture.mutator.mutator_tls, new_tls);
+            assert_eq!(
+                unsafe { fixture.mutator.allocator(selector) }.get_tls(),
+                new_tls.0
+            );
+
+            // Park the mutator. It can still allocate after being parked.
+            memory_manager::park_mutator(&mut fixture.mutator);
+            let addr =
+                memory_manager::alloc(&mut fixture.mutator, 16, 8, 0, AllocationSemantics::Default);
+            assert!(!addr.is_zero());
+        },
+        no_cleanup,
+    )
+}
@@ -0,0 +1,36 @@
+// GITHUB-CI: MMTK_PLAN=SemiSpace
+
+use super::mock_test_prelude::*;
+use crate::util::options::PlanSelector;
+use crate::util::test_util::scenario::*;
+use crate::AllocationSemantics;
+
+/// Check that a copying plan has a non-moving space whose objects are collected but not moved.
+#[test]
+pub fn nonmoving_space() {
+    with_mockvm(
+        default_setup,
+        || {
+            let mut s = Scenario::new(|builder| {
+                builder.options.plan.set(PlanSelector::SemiSpace);
+            });
+            let a = s.object_with_semantics("a", 1, AllocationSemantics::NonMoving);
+            let b = s.object("b", 1);
+            let c = s.object_with_semantics("c", 0, AllocationSemantics::NonMoving);
+            let d = s.object_with_semantics("d", 0, AllocationSemantics::NonMoving);
+            s.root(a).link(a, 0, b).link(b, 0, c);
+
+            s.gc()
+                .assert_live(&[a, b, c])
+                .assert_dead(&[d])
+                .assert_not_moved(&[a, c])
+                .assert_moved(&[b]);
+
+            // The space can still be allocated into after the GC.
+            let e = s.object_with_semantics("e", 0, AllocationSemantics::NonMoving);
+            s.link(b, 0, e);
+            s.gc().assert_live(&[a, b, e]).assert_not_moved(&[a, e]);
+        },
+        no_cleanup,
+    )
+}
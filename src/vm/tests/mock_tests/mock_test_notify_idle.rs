@@ -0,0 +1,53 @@
+// GITHUB-CI: MMTK_PLAN=all
+
+use std::{sync::Mutex, thread::JoinHandle};
+
+use super::mock_test_prelude::*;
+use crate::{
+    util::{options::GCTriggerSelector, Address, OpaquePointer, VMThread, VMWorkerThread},
+    MMTKBuilder, MMTK,
+};
+
+static JOIN_HANDLES: Mutex<Vec<JoinHandle<()>>> = Mutex::new(Vec::new());
+
+/// Test that GC workers respond to `notify_idle` without requesting a GC, and can still be shut
+/// down afterwards.
+#[test]
+pub fn test_notify_idle() {
+    let mut builder = MMTKBuilder::new();
+    builder
+        .options
+        .gc_trigger
+        .set(GCTriggerSelector::FixedHeapSize(1024 * 1024));
+    builder.options.threads.set(2);
+    let mmtk: &'static mut MMTK<MockVM> = Box::leak(Box::new(builder.build::<MockVM>()));
+
+    let mock_vm = MockVM {
+        spawn_gc_thread: MockMethod::new_fixed(Box::new(|(_vm_thread, context)| {
+            let GCThreadContext::Worker(worker) = context;
+            let join_handle = std::thread::spawn(move || {
+                let tls = VMWorkerThread(VMThread(OpaquePointer::from_address(Address::ZERO)));
+                memory_manager::start_worker(mmtk, tls, worker);
+            });
+            JOIN_HANDLES.lock().unwrap().push(join_handle);
+        })),
+        ..Default::default()
+    };
+    write_mockvm(move |mock_vm_ref| *mock_vm_ref = mock_vm);
+
+    // This does nothing before the GC workers are spawned.
+    memory_manager::notify_idle(mmtk);
+
+    let tls = VMThread(OpaquePointer::from_address(Address::ZERO));
+    mmtk.initialize_collection(tls);
+
+    memory_manager::notify_idle(mmtk);
+    memory_manager::notify_idle(mmtk);
+    assert!(!mmtk.is_gc_requested());
+
+    // Idle work never blocks shutting down GC threads.
+    mmtk.shutdown();
+    for join_handle in std::mem::take(&mut *JOIN_HANDLES.lock().unwrap()) {
+        join_handle.join().unwrap();
+    }
+}
@@ -0,0 +1,98 @@
+// GITHUB-CI: MMTK_PLAN=GenImmix
+
+use super::mock_test_prelude::*;
+use crate::util::options::PlanSelector;
+use crate::util::test_util::mock_gc::*;
+use crate::util::{Address, ObjectReference, VMWorkerThread};
+use crate::vm::OffHeapObjects;
+use crate::AllocationSemantics;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+struct State {
+    objects: Mutex<Vec<ObjectReference>>,
+    marked: Mutex<HashSet<ObjectReference>>,
+    /// The calls to `prepare` and `release`, and whether the GC was a full-heap GC.
+    calls: Mutex<Vec<(&'static str, bool)>>,
+}
+
+/// Off-heap objects allocated with `Box`, which are freed in full-heap GCs if they are not marked.
+struct BoxedObjects(Arc<State>);
+
+impl OffHeapObjects<MockVM> for BoxedObjects {
+    fn name(&self) -> &str {
+        "boxed"
+    }
+
+    fn contains(&self, object: ObjectReference) -> bool {
+        self.0.objects.lock().unwrap().contains(&object)
+    }
+
+    fn test_and_mark(&self, object: ObjectReference) -> bool {
+        self.0.marked.lock().unwrap().insert(object)
+    }
+
+    fn prepare(&self, _tls: VMWorkerThread, full_heap: bool) {
+        self.0.calls.lock().unwrap().push(("prepare", full_heap));
+        if full_heap {
+            self.0.marked.lock().unwrap().clear();
+        }
+    }
+
+    fn release(&self, _tls: VMWorkerThread, full_heap: bool) {
+        self.0.calls.lock().unwrap().push(("release", full_heap));
+        if full_heap {
+            let marked = self.0.marked.lock().unwrap();
+            self.0
+                .objects
+                .lock()
+                .unwrap()
+                .retain(|o| marked.contains(o));
+        }
+    }
+}
+
+/// An off-heap object that is only referred to by a mature object is not traced in a nursery GC,
+/// and the binding is told not to free it.
+#[test]
+pub fn nursery_off_heap_objects() {
+    with_mockvm(
+        default_setup,
+        || {
+            let mut gc = MockGC::new(0, 64 * 1024 * 1024, |builder| {
+                builder.options.plan.set(PlanSelector::GenImmix);
+                builder.options.full_heap_system_gc.set(false);
+            });
+
+            // An off-heap object with no fields, laid out like the objects of `MockGC`.
+            let storage = Box::leak(Box::new([0usize; 3]));
+            let object = ObjectReference::from_raw_address(
+                Address::from_mut_ptr(storage) + DEFAULT_OBJECT_REF_OFFSET,
+            )
+            .unwrap();
+            let state = Arc::new(State::default());
+            state.objects.lock().unwrap().push(object);
+            memory_manager::register_off_heap_objects(
+                gc.mmtk(),
+                Box::new(BoxedObjects(state.clone())),
+            );
+
+            // The immortal object is never in the nursery.
+            let referrer = gc.alloc(1, 1, AllocationSemantics::Immortal);
+            gc.store_field(referrer, 0, Some(object));
+
+            for _ in 0..2 {
+                assert!(gc.gc());
+                assert_eq!(
+                    std::mem::take(&mut *state.calls.lock().unwrap()),
+                    vec![("prepare", false), ("release", false)]
+                );
+                assert!(state.marked.lock().unwrap().is_empty());
+                assert_eq!(*state.objects.lock().unwrap(), vec![object]);
+                assert_eq!(load_field(referrer, 0), Some(object));
+            }
+        },
+        no_cleanup,
+    )
+}
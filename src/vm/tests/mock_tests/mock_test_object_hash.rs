@@ -0,0 +1,52 @@
+// GITHUB-CI: MMTK_PLAN=NoGC
+// GITHUB-CI: FEATURES=address_based_hashing
+
+use super::mock_test_prelude::*;
+use crate::util::constants::BYTES_IN_WORD;
+use crate::util::object_hash;
+
+lazy_static! {
+    static ref FIXTURE: Fixture<TwoObjects> = Fixture::new();
+}
+
+// The fixture allocates 128 bytes for each object. Leave a word for the hash code.
+const OBJECT_SIZE: usize = 128 - BYTES_IN_WORD;
+
+#[test]
+pub fn hash_code_is_preserved_on_move() {
+    with_mockvm(
+        || -> MockVM {
+            MockVM {
+                get_object_size: MockMethod::new_fixed(Box::new(|object| {
+                    OBJECT_SIZE
+                        + MockVM::GLOBAL_HASH_STATE_SPEC.hash_bytes_in_object::<MockVM>(object)
+                })),
+                ..MockVM::default()
+            }
+        },
+        || {
+            FIXTURE.with_fixture(|fixture| {
+                let spec = &MockVM::GLOBAL_HASH_STATE_SPEC;
+                let from = fixture.objref1;
+                let to = fixture.objref2;
+
+                // A new object is unhashed, and does not need a hash word when copied.
+                assert_eq!(spec.hash_bytes_when_copied::<MockVM>(from), 0);
+
+                // The hash code is the address of the object, and does not change.
+                let hash = memory_manager::object_hash::<MockVM>(from);
+                assert_eq!(hash, from.to_raw_address().as_usize());
+                assert_eq!(memory_manager::object_hash::<MockVM>(from), hash);
+                assert_eq!(spec.hash_bytes_in_object::<MockVM>(from), 0);
+                assert_eq!(spec.hash_bytes_when_copied::<MockVM>(from), BYTES_IN_WORD);
+
+                // Pretend that the GC copied `from` to `to`.
+                let state = object_hash::get_hash_state::<MockVM>(from);
+                object_hash::on_object_moved::<MockVM>(from, state, to);
+                assert_eq!(spec.hash_bytes_in_object::<MockVM>(to), BYTES_IN_WORD);
+                assert_eq!(memory_manager::object_hash::<MockVM>(to), hash);
+            });
+        },
+        no_cleanup,
+    )
+}
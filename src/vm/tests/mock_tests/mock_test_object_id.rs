@@ -0,0 +1,48 @@
+// GITHUB-CI: MMTK_PLAN=SemiSpace
+// GITHUB-CI: FEATURES=object_id
+
+use super::mock_test_prelude::*;
+use crate::memory_manager;
+use crate::util::options::PlanSelector;
+use crate::util::test_util::scenario::*;
+
+/// Check that object IDs survive object movement, and are removed when the objects die.
+#[test]
+pub fn object_id() {
+    with_mockvm(
+        default_setup,
+        || {
+            let mut s = Scenario::new(|builder| {
+                builder.options.plan.set(PlanSelector::SemiSpace);
+            });
+            let a = s.object("a", 1);
+            let b = s.object("b", 0);
+            s.root(a).link(a, 0, b);
+
+            let mmtk = s.mock_gc().mmtk();
+            let id_a = memory_manager::object_id(mmtk, s.address(a));
+            let id_b = memory_manager::object_id(mmtk, s.address(b));
+            assert_ne!(id_a, id_b);
+            assert_eq!(memory_manager::object_id(mmtk, s.address(a)), id_a);
+
+            s.gc().assert_moved(&[a, b]);
+            assert_eq!(memory_manager::object_id(mmtk, s.address(a)), id_a);
+            assert_eq!(
+                memory_manager::object_from_id(mmtk, id_a),
+                Some(s.address(a))
+            );
+            assert_eq!(
+                memory_manager::object_from_id(mmtk, id_b),
+                Some(s.address(b))
+            );
+
+            s.unlink(a, 0).gc().assert_dead(&[b]);
+            assert_eq!(
+                memory_manager::object_from_id(mmtk, id_a),
+                Some(s.address(a))
+            );
+            assert_eq!(memory_manager::object_from_id(mmtk, id_b), None);
+        },
+        no_cleanup,
+    )
+}
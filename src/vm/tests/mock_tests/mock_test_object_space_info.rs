@@ -0,0 +1,39 @@
+// GITHUB-CI: MMTK_PLAN=GenCopy
+
+use super::mock_test_prelude::*;
+use crate::policy::sft::{Generation, PolicyKind};
+use crate::util::{Address, ObjectReference};
+use crate::AllocationSemantics;
+
+#[test]
+pub fn object_space_info() {
+    with_mockvm(
+        default_setup,
+        || {
+            let mut fixture = MutatorFixture::create();
+
+            let size = 40;
+            let semantics = AllocationSemantics::Default;
+            let addr = memory_manager::alloc(&mut fixture.mutator, size, 8, 0, semantics);
+            assert!(!addr.is_zero());
+            let object = MockVM::object_start_to_ref(addr);
+            memory_manager::post_alloc(&mut fixture.mutator, object, size, semantics);
+
+            // A new object in GenCopy is in the nursery, which is a copy space.
+            let info = memory_manager::object_space_info(fixture.mmtk(), object).unwrap();
+            assert_eq!(info.space_name, "nursery");
+            assert_eq!(info.policy, PolicyKind::CopySpace);
+            assert!(info.movable);
+            assert!(!info.pinned);
+            assert_eq!(info.generation, Some(Generation::Nursery));
+
+            // An address that is not in any space.
+            let outside = ObjectReference::from_raw_address(unsafe {
+                Address::from_usize(DEFAULT_OBJECT_REF_OFFSET)
+            })
+            .unwrap();
+            assert!(memory_manager::object_space_info(fixture.mmtk(), outside).is_none());
+        },
+        no_cleanup,
+    )
+}
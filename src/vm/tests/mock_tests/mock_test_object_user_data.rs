@@ -0,0 +1,76 @@
+// GITHUB-CI: MMTK_PLAN=SemiSpace
+// GITHUB-CI: FEATURES=object_user_data
+
+use super::mock_test_prelude::*;
+use crate::memory_manager;
+use crate::util::options::PlanSelector;
+use crate::util::test_util::scenario::*;
+use std::sync::{Arc, Mutex};
+
+/// Check that the user data of objects moves with the objects, and is passed to the death callback
+/// when the objects die.
+#[test]
+pub fn object_user_data() {
+    with_mockvm(
+        default_setup,
+        || {
+            let mut s = Scenario::new(|builder| {
+                builder.options.plan.set(PlanSelector::SemiSpace);
+            });
+            let a = s.object("a", 1);
+            let b = s.object("b", 0);
+            s.root(a).link(a, 0, b);
+
+            let mmtk = s.mock_gc().mmtk();
+            let dead = Arc::new(Mutex::new(vec![]));
+            let dead_clone = dead.clone();
+            memory_manager::set_object_user_data_death_callback(
+                mmtk,
+                Box::new(move |_, data| dead_clone.lock().unwrap().push(data)),
+            );
+            assert_eq!(
+                memory_manager::set_object_user_data(mmtk, s.address(a), 1),
+                None
+            );
+            assert_eq!(
+                memory_manager::set_object_user_data(mmtk, s.address(b), 2),
+                None
+            );
+            assert_eq!(
+                memory_manager::set_object_user_data(mmtk, s.address(b), 3),
+                Some(2)
+            );
+
+            s.gc().assert_moved(&[a, b]);
+            assert_eq!(
+                memory_manager::get_object_user_data(mmtk, s.address(a)),
+                Some(1)
+            );
+            assert_eq!(
+                memory_manager::get_object_user_data(mmtk, s.address(b)),
+                Some(3)
+            );
+            assert!(dead.lock().unwrap().is_empty());
+
+            s.unlink(a, 0).gc().assert_dead(&[b]);
+            assert_eq!(
+                memory_manager::get_object_user_data(mmtk, s.address(a)),
+                Some(1)
+            );
+            assert_eq!(*dead.lock().unwrap(), vec![3]);
+
+            // Cleared data is not passed to the callback.
+            assert_eq!(
+                memory_manager::clear_object_user_data(mmtk, s.address(a)),
+                Some(1)
+            );
+            assert_eq!(
+                memory_manager::get_object_user_data(mmtk, s.address(a)),
+                None
+            );
+            s.unroot(a).gc().assert_dead(&[a]);
+            assert_eq!(*dead.lock().unwrap(), vec![3]);
+        },
+        no_cleanup,
+    )
+}
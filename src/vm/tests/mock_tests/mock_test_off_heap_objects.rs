@@ -0,0 +1,65 @@
+// GITHUB-CI: MMTK_PLAN=all
+
+use super::mock_test_prelude::*;
+use crate::plan::VectorObjectQueue;
+use crate::util::{Address, ObjectReference};
+use crate::vm::OffHeapObjects;
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+lazy_static! {
+    static ref SINGLE_OBJECT: Fixture<SingleObject> = Fixture::new();
+}
+
+/// Off-heap objects allocated with `Box`.
+struct BoxedObjects {
+    objects: Vec<ObjectReference>,
+    marked: Mutex<HashSet<ObjectReference>>,
+}
+
+impl OffHeapObjects<MockVM> for BoxedObjects {
+    fn name(&self) -> &str {
+        "boxed"
+    }
+
+    fn contains(&self, object: ObjectReference) -> bool {
+        self.objects.contains(&object)
+    }
+
+    fn test_and_mark(&self, object: ObjectReference) -> bool {
+        self.marked.lock().unwrap().insert(object)
+    }
+}
+
+#[test]
+pub fn trace_off_heap_objects() {
+    with_mockvm(
+        default_setup,
+        || {
+            SINGLE_OBJECT.with_fixture(|fixture| {
+                let storage = Box::leak(Box::new([0usize; 4]));
+                let object =
+                    ObjectReference::from_raw_address(Address::from_mut_ptr(storage)).unwrap();
+                memory_manager::register_off_heap_objects(
+                    fixture.mmtk(),
+                    Box::new(BoxedObjects {
+                        objects: vec![object],
+                        marked: Mutex::new(HashSet::new()),
+                    }),
+                );
+
+                let registry = &fixture.mmtk().off_heap_objects;
+                let mut queue = VectorObjectQueue::new();
+                // The first trace marks and enqueues the object.
+                assert_eq!(registry.trace_object(&mut queue, object), Some(object));
+                assert_eq!(queue.take(), vec![object]);
+                // The object is only enqueued once.
+                assert_eq!(registry.trace_object(&mut queue, object), Some(object));
+                assert!(queue.is_empty());
+                // Objects of other kinds are not traced.
+                assert_eq!(registry.trace_object(&mut queue, fixture.objref), None);
+            });
+        },
+        no_cleanup,
+    )
+}
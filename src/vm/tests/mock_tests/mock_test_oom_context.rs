@@ -0,0 +1,44 @@
+// GITHUB-CI: MMTK_PLAN=all
+
+use super::mock_test_prelude::*;
+
+use crate::plan::AllocationSemantics;
+
+const MB: usize = 1024 * 1024;
+
+#[test]
+#[should_panic(
+    expected = "HeapOutOfMemory in los: requested 1073741824 bytes, limit 1048576 bytes"
+)]
+pub fn allocate_larger_than_heap() {
+    with_mockvm(
+        || -> MockVM {
+            MockVM {
+                out_of_memory: MockMethod::new_fixed(Box::new(|(_, err, context)| {
+                    // The request is rejected before attempting a GC.
+                    assert!(!context.emergency_collection_attempted);
+                    assert!(context.used_bytes.unwrap() <= context.max_bytes.unwrap());
+                    panic!(
+                        "{:?} in {}: requested {} bytes, limit {} bytes",
+                        err,
+                        context.space.unwrap(),
+                        context.requested_bytes,
+                        context.max_bytes.unwrap()
+                    )
+                })),
+                ..MockVM::default()
+            }
+        },
+        || {
+            let mut fixture = MutatorFixture::create_with_heapsize(MB);
+            memory_manager::alloc(
+                &mut fixture.mutator,
+                1024 * MB,
+                8,
+                0,
+                AllocationSemantics::Los,
+            );
+        },
+        no_cleanup,
+    )
+}
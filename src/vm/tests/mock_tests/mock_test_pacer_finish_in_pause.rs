@@ -0,0 +1,49 @@
+use super::mock_test_prelude::*;
+use crate::AllocationSemantics;
+
+/// This test starts pacing a concurrent cycle, and allocates while the collector makes almost no
+/// marking progress.  The pacer decides that the mutators are outrunning the collector, and the GC
+/// trigger finishes the cycle in a pause long before the heap is full.  We haven't implemented
+/// `block_for_gc` so it will panic.
+#[test]
+#[should_panic(expected = "block_for_gc is called")]
+pub fn pacer_finish_in_pause() {
+    // 16MB heap
+    with_mockvm(
+        || -> MockVM {
+            MockVM {
+                block_for_gc: MockMethod::new_fixed(Box::new(|_| panic!("block_for_gc is called"))),
+                ..MockVM::default()
+            }
+        },
+        || {
+            const MB: usize = 1024 * 1024;
+            let mut fixture = MutatorFixture::create_with_heapsize(16 * MB);
+            let gc_trigger = &fixture.mmtk().gc_trigger;
+
+            // Marking the whole heap, and the collector has only marked one byte.
+            gc_trigger.start_concurrent_cycle(16 * MB);
+            gc_trigger.record_marked(1);
+            std::thread::sleep(std::time::Duration::from_millis(1));
+            assert!(gc_trigger.is_in_concurrent_cycle());
+
+            // Allocate a quarter of the heap.  The heap is not full, so only the pacer can trigger
+            // a GC.
+            for _ in 0..4 {
+                let addr = memory_manager::alloc(
+                    &mut fixture.mutator,
+                    MB,
+                    8,
+                    0,
+                    AllocationSemantics::Default,
+                );
+                assert!(!addr.is_zero());
+            }
+        },
+        || {
+            read_mockvm(|mock| {
+                assert!(mock.block_for_gc.is_called());
+            });
+        },
+    )
+}
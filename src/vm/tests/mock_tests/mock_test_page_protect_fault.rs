@@ -0,0 +1,45 @@
+// GITHUB-CI: MMTK_PLAN=all
+
+use super::mock_test_prelude::*;
+use crate::plan::PageProtect;
+use crate::util::test_util::mock_gc::*;
+use crate::util::Address;
+use crate::AllocationSemantics;
+
+/// Faults on the pages of dead objects in PageProtect are counted.  Faults outside MMTk spaces, and
+/// faults in other plans, are not.
+#[test]
+pub fn faults_on_dead_pages_are_counted() {
+    with_mockvm(
+        default_setup,
+        || {
+            let mut gc = MockGC::new(0, 20 * 1024 * 1024, |builder| {
+                builder.options.quarantine_gcs.set(1);
+            });
+            let object = gc.alloc(0, 0, AllocationSemantics::Default);
+            let addr = object.to_raw_address();
+            let mmtk = gc.mmtk();
+
+            // A fault outside MMTk spaces is not caused by MMTk.
+            assert!(!memory_manager::report_page_protect_fault(
+                mmtk,
+                Address::ZERO
+            ));
+
+            // The object is not reachable, and dies in the GC.  NoGC ignores the request.
+            let collected = gc.gc();
+            if let Some(plan) = mmtk.get_plan().downcast_ref::<PageProtect<MockVM>>() {
+                assert!(collected);
+                // Its pages are kept protected until the next GC.
+                assert!(plan.quarantined_pages() > 0);
+                assert!(memory_manager::report_page_protect_fault(mmtk, addr));
+                assert_eq!(memory_manager::page_protect_fault_count(mmtk), 1);
+            } else {
+                // Other plans do not count faults.
+                assert!(!memory_manager::report_page_protect_fault(mmtk, addr));
+                assert_eq!(memory_manager::page_protect_fault_count(mmtk), 0);
+            }
+        },
+        no_cleanup,
+    )
+}
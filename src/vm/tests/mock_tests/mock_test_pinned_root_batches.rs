@@ -0,0 +1,54 @@
+// GITHUB-CI: MMTK_PLAN=Immix
+
+use super::mock_test_prelude::*;
+use crate::util::options::PlanSelector;
+use crate::util::test_util::scenario::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Check that a pinned root batch keeps its objects alive and in place for the given number of
+/// GCs, and is dropped after that.
+#[test]
+pub fn pinned_root_batches() {
+    with_mockvm(
+        default_setup,
+        || {
+            let mut s = Scenario::new(|builder| {
+                builder.options.plan.set(PlanSelector::Immix);
+            });
+            let a = s.object("a", 1);
+            let b = s.object("b", 0);
+            s.link(a, 0, b);
+            let a_address = s.address(a);
+            let b_address = s.address(b);
+
+            // The binding only hands the batch to MMTk in the first GC.
+            let handed = AtomicBool::new(false);
+            write_mockvm(|mock| {
+                mock.scan_vm_specific_roots =
+                    MockMethod::new_fixed(Box::new(move |(_, mut factory)| {
+                        if !handed.swap(true, Ordering::SeqCst) {
+                            factory.create_process_tpinning_roots_batch_work(vec![a_address], 2);
+                        }
+                    }));
+            });
+            let pinned_roots_stats =
+                |s: &mut Scenario| s.mock_gc().mmtk().last_gc_pinned_roots_stats();
+
+            s.gc().assert_live(&[a, b]);
+            assert_eq!(s.address(a), a_address);
+            assert_eq!(s.address(b), b_address);
+            let stats = pinned_roots_stats(&mut s);
+            assert_eq!(stats.batches, 1);
+            assert_eq!(stats.objects, 1);
+
+            s.gc().assert_live(&[a, b]);
+            assert_eq!(s.address(a), a_address);
+            assert_eq!(s.address(b), b_address);
+            assert_eq!(pinned_roots_stats(&mut s).objects, 1);
+
+            s.gc().assert_dead(&[a, b]);
+            assert_eq!(pinned_roots_stats(&mut s), Default::default());
+        },
+        no_cleanup,
+    )
+}
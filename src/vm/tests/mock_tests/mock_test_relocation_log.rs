@@ -0,0 +1,38 @@
+// GITHUB-CI: MMTK_PLAN=SemiSpace
+
+use super::mock_test_prelude::*;
+use crate::memory_manager;
+use crate::util::copy::relocate_object;
+use crate::util::options::PlanSelector;
+use crate::util::test_util::scenario::*;
+
+/// Check that the relocations of a GC map the old addresses of the live objects to the new ones.
+#[test]
+pub fn relocation_log() {
+    with_mockvm(
+        default_setup,
+        || {
+            let mut s = Scenario::new(|builder| {
+                builder.options.plan.set(PlanSelector::SemiSpace);
+                builder.options.record_relocations.set(true);
+            });
+            let a = s.object("a", 1);
+            let b = s.object("b", 0);
+            let garbage = s.object("garbage", 0);
+            s.root(a).link(a, 0, b);
+            let old = [s.address(a), s.address(b), s.address(garbage)];
+
+            s.gc().assert_live(&[a, b]).assert_dead(&[garbage]);
+            let relocations = memory_manager::take_relocations(s.mock_gc().mmtk());
+            assert!(!relocations.is_empty());
+            assert!(relocations.windows(2).all(|w| w[0].from < w[1].from));
+            assert_eq!(relocate_object(&relocations, old[0]), Some(s.address(a)));
+            assert_eq!(relocate_object(&relocations, old[1]), Some(s.address(b)));
+            assert_eq!(relocate_object(&relocations, old[2]), None);
+
+            // The relocations are consumed.
+            assert!(memory_manager::take_relocations(s.mock_gc().mmtk()).is_empty());
+        },
+        no_cleanup,
+    )
+}
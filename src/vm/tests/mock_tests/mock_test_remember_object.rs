@@ -0,0 +1,47 @@
+// GITHUB-CI: MMTK_PLAN=GenImmix
+
+use super::mock_test_prelude::*;
+use crate::plan::BarrierSelector;
+use atomic::Ordering;
+
+lazy_static! {
+    static ref FIXTURE: Fixture<SingleObject> = Fixture::new();
+}
+
+/// Check that `remember_object` logs an unlogged object if the plan uses the object barrier, and
+/// does nothing otherwise.
+#[test]
+pub fn remember_object() {
+    with_mockvm(
+        default_setup,
+        || {
+            FIXTURE.with_fixture_mut(|fixture| {
+                let objref = fixture.objref;
+                let has_object_barrier = fixture.mmtk().get_plan().constraints().barrier
+                    == BarrierSelector::ObjectBarrier;
+                let log_bit = &<MockVM as VMBinding>::VMObjectModel::GLOBAL_LOG_BIT_SPEC;
+
+                // Pretend that the object is a mature object that has survived a GC.
+                log_bit.mark_as_unlogged::<MockVM>(objref, Ordering::SeqCst);
+                memory_manager::remember_object(fixture.mutator_mut(), objref);
+                assert_eq!(
+                    log_bit.is_unlogged::<MockVM>(objref, Ordering::SeqCst),
+                    !has_object_barrier
+                );
+
+                // Remembering the object again is harmless.
+                memory_manager::remember_object(fixture.mutator_mut(), objref);
+                assert_eq!(
+                    log_bit.is_unlogged::<MockVM>(objref, Ordering::SeqCst),
+                    !has_object_barrier
+                );
+
+                // Leave the header as it was.
+                if !has_object_barrier {
+                    log_bit.store_atomic::<MockVM, u8>(objref, 0, None, Ordering::SeqCst);
+                }
+            });
+        },
+        no_cleanup,
+    )
+}
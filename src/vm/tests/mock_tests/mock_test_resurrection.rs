@@ -0,0 +1,48 @@
+// GITHUB-CI: MMTK_PLAN=Immix
+// GITHUB-CI: FEATURES=vo_bit
+
+use super::mock_test_prelude::*;
+use crate::util::{Address, ObjectReference};
+use crate::vm::slot::Slot;
+
+lazy_static! {
+    static ref SINGLE_OBJECT: Fixture<SingleObject> = Fixture::new();
+}
+
+#[test]
+pub fn detect_dead_objects_between_gcs() {
+    with_mockvm(
+        default_setup,
+        || {
+            SINGLE_OBJECT.with_fixture(|fixture| {
+                let mmtk = fixture.mmtk();
+                assert!(!memory_manager::is_dead_object(mmtk, fixture.objref));
+
+                // No object has been allocated there, so it does not have the VO bit.
+                let dead =
+                    ObjectReference::from_raw_address(fixture.objref.to_raw_address() + 4096usize)
+                        .unwrap();
+                assert!(memory_manager::is_dead_object(mmtk, dead));
+
+                // Objects outside MMTk spaces are never considered dead.
+                let outside = ObjectReference::from_raw_address(unsafe {
+                    Address::from_usize(DEFAULT_OBJECT_REF_OFFSET)
+                })
+                .unwrap();
+                assert!(!memory_manager::is_dead_object(mmtk, outside));
+
+                let mut cell: usize = 0;
+                let slot = Address::from_mut_ptr(&mut cell);
+                slot.store(fixture.objref);
+                memory_manager::assert_not_resurrected(mmtk, slot);
+
+                slot.store(dead);
+                let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    memory_manager::assert_not_resurrected(mmtk, slot);
+                }));
+                assert!(result.is_err());
+            });
+        },
+        no_cleanup,
+    )
+}
@@ -0,0 +1,54 @@
+// GITHUB-CI: MMTK_PLAN=SemiSpace
+
+use super::mock_test_prelude::*;
+use crate::util::constants::BYTES_IN_ADDRESS;
+use crate::util::options::PlanSelector;
+use crate::util::test_util::scenario::*;
+use crate::util::Address;
+use crate::vm::slot::Slot;
+use crate::vm::RootSlotTable;
+
+/// Check that the roots in a root table bigger than a work packet keep their objects alive and are
+/// all updated.
+#[test]
+pub fn roots_descriptor() {
+    with_mockvm(
+        default_setup,
+        || {
+            let mut s = Scenario::new(|builder| {
+                builder.options.plan.set(PlanSelector::SemiSpace);
+            });
+            let a = s.object("a", 0);
+            let b = s.object("b", 0);
+
+            // A global root table outside the heap.  Every slot holds `a`, except the last one,
+            // which holds `b`.
+            const COUNT: usize = 10000;
+            let table: &'static mut [usize] = Box::leak(vec![0usize; COUNT].into_boxed_slice());
+            let start = Address::from_mut_ptr(table.as_mut_ptr());
+            let slot_at = move |i: usize| start + i * BYTES_IN_ADDRESS;
+            for i in 0..COUNT - 1 {
+                Slot::store(&slot_at(i), s.address(a));
+            }
+            Slot::store(&slot_at(COUNT - 1), s.address(b));
+            write_mockvm(|mock| {
+                mock.scan_vm_specific_roots =
+                    MockMethod::new_fixed(Box::new(move |(_, mut factory)| {
+                        factory.create_process_roots_descriptor_work(Box::new(RootSlotTable {
+                            start,
+                            count: COUNT,
+                            stride: BYTES_IN_ADDRESS,
+                            slot: |address| address,
+                        }));
+                    }));
+            });
+
+            s.gc().assert_live(&[a, b]).assert_moved(&[a, b]);
+            for i in 0..COUNT - 1 {
+                assert_eq!(Slot::load(&slot_at(i)), Some(s.address(a)));
+            }
+            assert_eq!(Slot::load(&slot_at(COUNT - 1)), Some(s.address(b)));
+        },
+        no_cleanup,
+    )
+}
@@ -0,0 +1,84 @@
+// GITHUB-CI: MMTK_PLAN=NoGC,MarkSweep,MarkCompact,SemiSpace,Immix,GenCopy
+// GITHUB-CI: FEATURES=vo_bit
+
+use std::collections::HashSet;
+
+use super::mock_test_prelude::*;
+
+use crate::util::{EnumerationScope, ObjectReference};
+use crate::AllocationSemantics;
+
+#[test]
+pub fn scoped_heap_traversal() {
+    with_mockvm(
+        default_setup,
+        || {
+            let mut fixture = MutatorFixture::create();
+
+            let mut objects = vec![];
+            for (size, semantics) in [
+                (40, AllocationSemantics::Default),
+                (64, AllocationSemantics::Default),
+                (96, AllocationSemantics::Immortal),
+                (64, AllocationSemantics::Immortal),
+                (112, AllocationSemantics::Default),
+            ] {
+                let start = memory_manager::alloc(&mut fixture.mutator, size, 8, 0, semantics);
+                let object = MockVM::object_start_to_ref(start);
+                memory_manager::post_alloc(&mut fixture.mutator, object, size, semantics);
+                objects.push(object);
+            }
+            let mmtk = fixture.mmtk();
+
+            // Enumerate each space that has objects.
+            let space_of = |object: ObjectReference| {
+                memory_manager::object_space_info(mmtk, object)
+                    .unwrap()
+                    .space_name
+            };
+            for name in objects.iter().map(|o| space_of(*o)) {
+                let expected: HashSet<_> = objects
+                    .iter()
+                    .copied()
+                    .filter(|o| space_of(*o) == name)
+                    .collect();
+                let mut visited = HashSet::new();
+                assert!(
+                    mmtk.enumerate_objects_in(EnumerationScope::Space(name), |o| {
+                        visited.insert(o);
+                    })
+                );
+                assert_eq!(visited, expected);
+
+                let packets = mmtk
+                    .enumerate_objects_in_parallel(EnumerationScope::Space(name), |_| {})
+                    .unwrap();
+                assert!(!packets.is_empty());
+            }
+
+            // Enumerate a region that covers the first two objects.
+            let (a, b) = (objects[0].to_raw_address(), objects[1].to_raw_address());
+            let region = a.min(b)..a.max(b) + 1usize;
+            let expected: HashSet<_> = objects
+                .iter()
+                .copied()
+                .filter(|o| region.contains(&o.to_raw_address()))
+                .collect();
+            assert!(expected.contains(&objects[0]) && expected.contains(&objects[1]));
+            let mut visited = HashSet::new();
+            assert!(
+                mmtk.enumerate_objects_in(EnumerationScope::Region(region), |o| {
+                    visited.insert(o);
+                })
+            );
+            assert_eq!(visited, expected);
+
+            // A space that does not exist.
+            assert!(!mmtk.enumerate_objects_in(EnumerationScope::Space("no such space"), |_| {}));
+            assert!(mmtk
+                .enumerate_objects_in_parallel(EnumerationScope::Space("no such space"), |_| {})
+                .is_none());
+        },
+        no_cleanup,
+    )
+}
@@ -0,0 +1,25 @@
+// GITHUB-CI: MMTK_PLAN=SemiSpace
+// GITHUB-CI: FEATURES=ro_space,vo_bit
+
+use super::mock_test_prelude::*;
+use crate::util::options::PlanSelector;
+
+/// The references in a sealed space cannot be updated, so a plan that cannot pin the objects they
+/// point to refuses to seal the space.
+#[test]
+#[should_panic(expected = "does not support pinning roots")]
+pub fn seal_ro_space_without_pinning() {
+    with_mockvm(
+        default_setup,
+        || {
+            let fixture = MMTKFixture::create_with_builder(
+                |builder| {
+                    builder.options.plan.set(PlanSelector::SemiSpace);
+                },
+                false,
+            );
+            memory_manager::seal_ro_space(fixture.get_mmtk());
+        },
+        no_cleanup,
+    )
+}
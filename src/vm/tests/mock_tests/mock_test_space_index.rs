@@ -0,0 +1,47 @@
+// GITHUB-CI: MMTK_PLAN=all
+
+use super::mock_test_prelude::*;
+use crate::util::heap::space_index;
+use crate::AllocationSemantics;
+
+#[test]
+pub fn space_index_of_new_object() {
+    with_mockvm(
+        default_setup,
+        || {
+            let mut fixture = MutatorFixture::create();
+
+            let size = 40;
+            let semantics = AllocationSemantics::Default;
+            let addr = memory_manager::alloc(&mut fixture.mutator, size, 8, 0, semantics);
+            assert!(!addr.is_zero());
+            let object = MockVM::object_start_to_ref(addr);
+            memory_manager::post_alloc(&mut fixture.mutator, object, size, semantics);
+
+            let mut indices = vec![];
+            let mut owners = vec![];
+            fixture.mmtk().get_plan().for_each_space(&mut |space| {
+                indices.push(space.space_index());
+                if space.in_space(object) {
+                    owners.push((space.get_name(), space.space_index()));
+                }
+            });
+
+            // Each space that gets memory from a page resource has a distinct index.
+            indices.retain(|index| *index != space_index::UNKNOWN_SPACE_INDEX);
+            let mut unique = indices.clone();
+            unique.sort();
+            unique.dedup();
+            assert_eq!(unique.len(), indices.len());
+
+            // The object is in exactly one space, and its chunk records that space, unless the
+            // space does not get memory from a page resource (e.g. the malloc space).
+            assert_eq!(owners.len(), 1, "{object} is in spaces {owners:?}");
+            let recorded = space_index::get(object.to_raw_address());
+            if recorded != space_index::UNKNOWN_SPACE_INDEX {
+                assert_eq!(recorded, owners[0].1);
+            }
+        },
+        no_cleanup,
+    )
+}
@@ -0,0 +1,44 @@
+// GITHUB-CI: MMTK_PLAN=SemiSpace
+// GITHUB-CI: FEATURES=vo_bit
+
+use super::mock_test_prelude::*;
+use crate::util::options::PlanSelector;
+use crate::util::test_util::scenario::*;
+use crate::AllocationSemantics;
+
+/// Check that a GC targeting the large object space only reclaims unreachable large objects, and
+/// keeps the large objects referenced from other spaces, even from unreachable objects there.
+#[test]
+pub fn space_targeted_gc() {
+    with_mockvm(
+        default_setup,
+        || {
+            let mut s = Scenario::new(|builder| {
+                builder.options.plan.set(PlanSelector::SemiSpace);
+            });
+            let a = s.object("a", 1);
+            let b = s.object("b", 0);
+            let large1 = s.object_with_semantics("large1", 1, AllocationSemantics::Los);
+            let large2 = s.object_with_semantics("large2", 0, AllocationSemantics::Los);
+            let large3 = s.object_with_semantics("large3", 0, AllocationSemantics::Los);
+            // `a` is unreachable, but it is not collected, so `large2` is kept alive.
+            s.root(large1).link(large1, 0, b).link(a, 0, large2);
+
+            assert!(s.mock_gc().space_targeted_gc(&["los"]));
+            s.assert_live(&[a, b, large1, large2])
+                .assert_dead(&[large3])
+                .assert_not_moved(&[a, b]);
+
+            // A full-heap GC collects the other spaces.
+            s.gc()
+                .assert_live(&[b, large1])
+                .assert_dead(&[a, large2])
+                .assert_moved(&[b]);
+
+            // SemiSpace cannot collect its copy spaces alone.
+            assert!(!s.mock_gc().space_targeted_gc(&["copyspace0"]));
+            assert!(!s.mock_gc().space_targeted_gc(&["no_such_space"]));
+        },
+        no_cleanup,
+    )
+}
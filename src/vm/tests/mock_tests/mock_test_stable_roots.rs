@@ -0,0 +1,47 @@
+// GITHUB-CI: MMTK_PLAN=SemiSpace
+
+use super::mock_test_prelude::*;
+use crate::util::options::PlanSelector;
+use crate::util::test_util::scenario::*;
+use crate::util::Address;
+use crate::vm::slot::Slot;
+
+/// Check that stable roots keep their objects alive and are updated in each GC, but are only
+/// scanned again after they are invalidated.
+#[test]
+pub fn stable_roots() {
+    with_mockvm(
+        default_setup,
+        || {
+            let mut s = Scenario::new(|builder| {
+                builder.options.plan.set(PlanSelector::SemiSpace);
+            });
+            let a = s.object("a", 0);
+
+            // A slot outside the heap, like a static field.
+            let cell: &'static mut usize = Box::leak(Box::new(0));
+            let slot = Address::from_mut_ptr(cell);
+            Slot::store(&slot, s.address(a));
+            write_mockvm(|mock| {
+                mock.stable_root_categories = MockMethod::new_fixed(Box::new(|_| 1));
+                mock.scan_stable_roots =
+                    MockMethod::new_fixed(Box::new(move |(_, category, slots)| {
+                        assert_eq!(category, 0);
+                        slots.push(slot);
+                    }));
+            });
+            let scans = || read_mockvm(|mock| mock.scan_stable_roots.call_count());
+
+            s.gc().assert_live(&[a]).assert_moved(&[a]);
+            assert_eq!(Slot::load(&slot), Some(s.address(a)));
+            s.gc().assert_live(&[a]).assert_moved(&[a]);
+            assert_eq!(Slot::load(&slot), Some(s.address(a)));
+            assert_eq!(scans(), 1);
+
+            memory_manager::invalidate_stable_roots(s.mock_gc().mmtk(), 0);
+            s.gc().assert_live(&[a]);
+            assert_eq!(scans(), 2);
+        },
+        no_cleanup,
+    )
+}
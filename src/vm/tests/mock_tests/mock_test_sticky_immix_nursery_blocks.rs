@@ -0,0 +1,42 @@
+// GITHUB-CI: MMTK_PLAN=StickyImmix
+
+use super::mock_test_prelude::*;
+use crate::policy::immix::block::Block;
+use crate::util::linear_scan::Region;
+use crate::util::options::PlanSelector;
+use crate::util::test_util::mock_gc::*;
+use crate::AllocationSemantics;
+
+const NURSERY_BLOCKS: usize = 4;
+
+/// Check that StickyImmix triggers a nursery GC when mutators have used up the block budget, even
+/// though the heap is far from full.
+#[test]
+pub fn sticky_immix_nursery_blocks() {
+    with_mockvm(
+        default_setup,
+        || {
+            let mut gc = MockGC::new(0, 64 * 1024 * 1024, |builder| {
+                builder.options.plan.set(PlanSelector::StickyImmix);
+                builder
+                    .options
+                    .sticky_immix_nursery_blocks
+                    .set(NURSERY_BLOCKS);
+            });
+            assert_eq!(gc.mmtk().last_gc_immix_mutator_blocks(), 0);
+
+            let size = object_size(6);
+            let mut allocated = 0;
+            while gc.gcs() == 0 {
+                assert!(allocated <= 4 * NURSERY_BLOCKS * Block::BYTES);
+                gc.alloc(6, 0, AllocationSemantics::Default);
+                allocated += size;
+            }
+            // The object that triggered the GC is allocated after the GC.
+            assert!(allocated - size <= NURSERY_BLOCKS * Block::BYTES);
+            assert!(allocated - size > (NURSERY_BLOCKS - 1) * Block::BYTES);
+            assert_eq!(gc.mmtk().last_gc_immix_mutator_blocks(), NURSERY_BLOCKS);
+        },
+        no_cleanup,
+    )
+}
@@ -0,0 +1,52 @@
+// GITHUB-CI: MMTK_PLAN=SemiSpace
+
+use super::mock_test_prelude::*;
+use crate::util::options::PlanSelector;
+use crate::util::test_util::mock_gc::*;
+use crate::AllocationSemantics;
+
+const LENGTH: usize = 10000;
+
+/// With a tiny `tracing_queue_budget`, almost every tracing packet is spilled and refilled.  A GC
+/// over a long linked list, where each node also refers to a leaf, still keeps every object alive
+/// and updates every reference.
+#[test]
+pub fn tracing_queue_budget() {
+    with_mockvm(
+        default_setup,
+        || {
+            let mut gc = MockGC::new(1, 64 * 1024 * 1024, |builder| {
+                builder.options.plan.set(PlanSelector::SemiSpace);
+                builder.options.threads.set(2);
+                builder.options.tracing_queue_budget.set(1);
+            });
+
+            // Build the list from the tail, so that each node is the head of the list so far.
+            let mut head = None;
+            for i in 0..LENGTH {
+                let node = gc.alloc(2, 2 * i as u64, AllocationSemantics::Default);
+                let leaf = gc.alloc(0, 2 * i as u64 + 1, AllocationSemantics::Default);
+                gc.store_field(node, 0, head);
+                gc.store_field(node, 1, Some(leaf));
+                head = Some(node);
+            }
+            // The list is built without a GC, so the references held by the test are valid.
+            assert_eq!(gc.gcs(), 0);
+            gc.store_root(0, head);
+
+            assert!(gc.gc());
+            let mut node = gc.load_root(0);
+            let mut count = 0;
+            while let Some(n) = node {
+                let i = LENGTH - 1 - count;
+                assert_eq!(object_id(n), 2 * i as u64);
+                let leaf = load_field(n, 1).unwrap();
+                assert_eq!(object_id(leaf), 2 * i as u64 + 1);
+                node = load_field(n, 0);
+                count += 1;
+            }
+            assert_eq!(count, LENGTH);
+        },
+        no_cleanup,
+    )
+}
@@ -0,0 +1,85 @@
+// GITHUB-CI: MMTK_PLAN=SemiSpace
+// GITHUB-CI: FEATURES=vm_forwarding
+
+use super::mock_test_prelude::*;
+use crate::util::object_forwarding;
+use crate::util::options::PlanSelector;
+use crate::util::test_util::scenario::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+fn calls(counter: &AtomicUsize) -> usize {
+    counter.load(Ordering::Relaxed)
+}
+
+/// Check the forwarding protocol with the forwarding state kept by the binding: the worker that
+/// wins the race copies the object, the workers that lose the race wait for the new copy, and the
+/// losers keep the object in place if the winner reverts its claim.  Then check that a copying GC
+/// forwards objects through the binding.
+#[test]
+pub fn vm_forwarding() {
+    with_mockvm(
+        default_setup,
+        || {
+            let mut s = Scenario::new(|builder| {
+                builder.options.plan.set(PlanSelector::SemiSpace);
+            });
+            let x = s.object("x", 0);
+            let y = s.object("y", 0);
+            let (x, y) = (s.address(x), s.address(y));
+
+            // Won, lost and reverted.
+            let won = object_forwarding::attempt_to_forward::<MockVM>(x);
+            assert!(!object_forwarding::state_is_forwarded_or_being_forwarded(
+                won
+            ));
+            let lost = object_forwarding::attempt_to_forward::<MockVM>(x);
+            assert!(object_forwarding::state_is_being_forwarded(lost));
+            object_forwarding::clear_forwarding_bits::<MockVM>(x);
+            assert_eq!(
+                object_forwarding::spin_and_get_forwarded_object::<MockVM>(x, lost),
+                x
+            );
+            assert_eq!(
+                forwarding::get_forwarding_state(x),
+                ForwardingState::NotForwarded
+            );
+
+            // Won, and a loser waits for the forwarding pointer in another thread.
+            let won = object_forwarding::attempt_to_forward::<MockVM>(x);
+            assert!(!object_forwarding::state_is_forwarded_or_being_forwarded(
+                won
+            ));
+            std::thread::scope(|scope| {
+                let loser = scope.spawn(|| {
+                    let lost = object_forwarding::attempt_to_forward::<MockVM>(x);
+                    assert!(object_forwarding::state_is_forwarded_or_being_forwarded(
+                        lost
+                    ));
+                    object_forwarding::spin_and_get_forwarded_object::<MockVM>(x, lost)
+                });
+                forwarding::set_forwarded(x, y);
+                assert_eq!(loser.join().unwrap(), y);
+            });
+            assert!(object_forwarding::is_forwarded::<MockVM>(x));
+            assert_eq!(object_forwarding::read_forwarding_pointer::<MockVM>(x), y);
+
+            // `b` is reachable from a root and from `a`, but is only copied once.
+            let a = s.object("a", 1);
+            let b = s.object("b", 0);
+            let attempts = calls(&forwarding::CALLS.attempt_to_forward);
+            let forwarded = calls(&forwarding::CALLS.set_forwarded);
+            s.root(a).root(b).link(a, 0, b);
+            s.gc().assert_live(&[a, b]).assert_moved(&[a, b]);
+            assert!(calls(&forwarding::CALLS.attempt_to_forward) - attempts >= 3);
+            assert_eq!(calls(&forwarding::CALLS.set_forwarded) - forwarded, 2);
+            // The new copies start with a clear forwarding state.
+            for object in [a, b] {
+                assert_eq!(
+                    forwarding::get_forwarding_state(s.address(object)),
+                    ForwardingState::NotForwarded
+                );
+            }
+        },
+        no_cleanup,
+    )
+}
@@ -0,0 +1,60 @@
+// GITHUB-CI: MMTK_PLAN=all
+
+use super::mock_test_prelude::*;
+use super::mock_test_vm_layout_default::test_with_vm_layout;
+use crate::util::heap::vm_layout::{CompressedPointerMode, VMLayout};
+use crate::util::Address;
+
+// This test only run on 64bits.
+
+#[test]
+fn test_vm_layout_compressed_modes() {
+    with_mockvm(
+        default_setup,
+        || {
+            const MAX_HEAP_SIZE: usize = 1024 * 1024;
+            let custom_base = unsafe { Address::from_usize(0x0000_1000_0000_0000) };
+            for mode in [
+                CompressedPointerMode::ZeroBased { shift: 3 },
+                CompressedPointerMode::HeapBased { shift: 3 },
+                CompressedPointerMode::Custom {
+                    base: custom_base,
+                    shift: 3,
+                },
+            ] {
+                let layout = VMLayout::new_compressed(mode, MAX_HEAP_SIZE);
+                let encoding = mode.encoding();
+                assert_eq!(encoding.shift, 3);
+                // The heap is above the base, and inside the range the encoding can express.
+                assert!(encoding.base < layout.heap_start);
+                assert!(layout.heap_end - encoding.base <= 32usize << 30);
+                assert!(layout.heap_end - layout.heap_start >= MAX_HEAP_SIZE);
+
+                let addr = layout.heap_start + 8usize;
+                assert_eq!(encoding.decompress(encoding.compress(addr)), addr);
+                assert_eq!(encoding.compress(Address::ZERO), 0);
+                assert_eq!(encoding.decompress(0), Address::ZERO);
+            }
+            assert_eq!(
+                CompressedPointerMode::ZeroBased { shift: 3 }
+                    .encoding()
+                    .base,
+                Address::ZERO
+            );
+            assert_eq!(
+                CompressedPointerMode::Custom {
+                    base: custom_base,
+                    shift: 3
+                }
+                .encoding()
+                .base,
+                custom_base
+            );
+
+            // The layout can only be set once in a process, so we only run MMTk with one of them.
+            let mode = CompressedPointerMode::HeapBased { shift: 3 };
+            test_with_vm_layout(Some(VMLayout::new_compressed(mode, MAX_HEAP_SIZE)));
+        },
+        no_cleanup,
+    )
+}
@@ -0,0 +1,67 @@
+// GITHUB-CI: MMTK_PLAN=Immix
+// GITHUB-CI: FEATURES=vm_space,vo_bit
+
+// This test only runs for 64bits.
+// It adds discontiguous regions to the VM space, one of them without VO bits and one of them not
+// page-aligned, and removes some of them. Like `mock_test_mmtk_julia_pr_143`, the regions are
+// outside the address range we use for spaces.
+
+use super::mock_test_prelude::*;
+use crate::memory_manager;
+use crate::util::{Address, ObjectReference};
+
+#[test]
+fn test_vm_space_regions() {
+    with_mockvm(
+        default_setup,
+        || {
+            let fixture = MMTKFixture::create();
+
+            // The regions are 1GB apart so that they do not share side metadata chunks.
+            let start1 = unsafe { Address::from_usize(0x7862_0000_0000) };
+            let start2 = unsafe { Address::from_usize(0x7862_4000_0000) };
+            let size = 0x10_0000;
+
+            memory_manager::add_vm_space_region::<MockVM>(fixture.get_mmtk(), start1, size, true);
+            memory_manager::add_vm_space_region::<MockVM>(fixture.get_mmtk(), start2, size, false);
+
+            let in_spaces = |addr: Address| {
+                memory_manager::is_in_mmtk_spaces(ObjectReference::from_raw_address(addr).unwrap())
+            };
+            assert!(in_spaces(start1));
+            assert!(in_spaces(start1 + size - 8usize));
+            assert!(in_spaces(start2));
+            assert!(in_spaces(start2 + size - 8usize));
+            // Between the two regions
+            assert!(!in_spaces(start1 + 0x2000_0000usize));
+
+            #[cfg(feature = "vo_bit")]
+            {
+                use crate::util::metadata::vo_bit::VO_BIT_SIDE_METADATA_SPEC;
+                assert!(VO_BIT_SIDE_METADATA_SPEC.is_mapped(start1));
+                assert!(!VO_BIT_SIDE_METADATA_SPEC.is_mapped(start2));
+            }
+
+            // Remove the second region.
+            memory_manager::remove_vm_space_region::<MockVM>(fixture.get_mmtk(), start2, size);
+            assert!(in_spaces(start1));
+            assert!(!in_spaces(start2));
+            assert!(!memory_manager::is_mapped_address(start2));
+
+            // Add it again at the same address.
+            memory_manager::add_vm_space_region::<MockVM>(fixture.get_mmtk(), start2, size, true);
+            assert!(in_spaces(start2));
+            assert!(memory_manager::is_mapped_address(start2));
+
+            // A region that is not page-aligned covers the pages it overlaps, and is removed with
+            // the same start and size.
+            let start3 = unsafe { Address::from_usize(0x7862_8000_0800) };
+            let size3 = 0x1000;
+            memory_manager::add_vm_space_region::<MockVM>(fixture.get_mmtk(), start3, size3, true);
+            assert!(in_spaces(start3 + size3 - 8usize));
+            memory_manager::remove_vm_space_region::<MockVM>(fixture.get_mmtk(), start3, size3);
+            assert!(!in_spaces(start3));
+        },
+        no_cleanup,
+    )
+}
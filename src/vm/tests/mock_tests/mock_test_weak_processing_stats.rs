@@ -0,0 +1,58 @@
+// GITHUB-CI: MMTK_PLAN=SemiSpace
+
+use super::mock_test_prelude::*;
+use crate::memory_manager;
+use crate::util::options::PlanSelector;
+use crate::util::test_util::scenario::*;
+use crate::util::weak_processing_stats::FinalizationStats;
+
+/// Check that the statistics of the last GC count the finalizable objects registered, checked and
+/// made ready.
+#[test]
+pub fn weak_processing_stats() {
+    with_mockvm(
+        default_setup,
+        || {
+            let mut s = Scenario::new(|builder| {
+                builder.options.plan.set(PlanSelector::SemiSpace);
+            });
+            let live = s.object("live", 0);
+            let dead = s.object("dead", 0);
+            s.root(live);
+
+            let mmtk = s.mock_gc().mmtk();
+            memory_manager::add_finalizer(mmtk, s.address(live));
+            memory_manager::add_finalizer(mmtk, s.address(dead));
+            assert_eq!(
+                mmtk.last_gc_weak_processing_stats().finalization,
+                FinalizationStats::default()
+            );
+
+            s.gc();
+            let stats = mmtk.last_gc_weak_processing_stats();
+            assert_eq!(
+                stats.finalization,
+                FinalizationStats {
+                    registered: 2,
+                    candidates: 2,
+                    ready: 1,
+                }
+            );
+            assert_eq!(stats.weak.enqueued, 0);
+
+            // The object ready for finalization is kept alive until it is popped.
+            assert!(memory_manager::get_finalized_object(mmtk).is_some());
+            s.gc();
+            let stats = mmtk.last_gc_weak_processing_stats();
+            assert_eq!(
+                stats.finalization,
+                FinalizationStats {
+                    registered: 0,
+                    candidates: 1,
+                    ready: 0,
+                }
+            );
+        },
+        no_cleanup,
+    )
+}
@@ -1,5 +1,6 @@
-// NOTE: MMTk will panic if MMTK is initialized more than once per process (this is a bug and we should fix it).
-// To work around the problem, we run each of the following modules in a separate test process
+// NOTE: Each MMTk instance reserves address ranges for its spaces, and never returns them to the heap,
+// so only a few instances can be created in a process. The VM layout also cannot be changed once an
+// instance is created. We run each of the following modules in a separate test process
 // if the test initializes an MMTk intance.
 
 // All the tests with prefix 'mock_test_' and with the feature 'mock_test' will use MockVM, and will initialize MMTk.
@@ -24,19 +25,40 @@ pub(crate) mod mock_test_prelude {
 }
 
 mod mock_test_allocate_align_offset;
+mod mock_test_allocate_no_gc;
 mod mock_test_allocate_with_disable_collection;
 mod mock_test_allocate_with_initialize_collection;
 mod mock_test_allocate_with_re_enable_collection;
 mod mock_test_allocate_without_initialize_collection;
+mod mock_test_allocation_route;
 mod mock_test_allocator_info;
+mod mock_test_barrier_elision;
 mod mock_test_barrier_slow_path_assertion;
+#[cfg(feature = "code_space")]
+mod mock_test_code_space_wx;
 #[cfg(feature = "is_mmtk_object")]
 mod mock_test_conservatism;
+mod mock_test_copy_partitions;
+mod mock_test_deduplication;
+mod mock_test_describe_object;
+#[cfg(feature = "fault_injection")]
+mod mock_test_fault_injection;
+mod mock_test_gc_disabled_scope;
+mod mock_test_gc_epoch;
+mod mock_test_gc_fuzzing;
+mod mock_test_gc_scenario;
+mod mock_test_gc_thread_shutdown;
 #[cfg(target_os = "linux")]
 mod mock_test_handle_mmap_conflict;
 mod mock_test_handle_mmap_oom;
+mod mock_test_handle_table;
+mod mock_test_handshake;
+mod mock_test_harness_windows;
+mod mock_test_heap_layout;
 #[cfg(feature = "vo_bit")]
 mod mock_test_heap_traversal;
+mod mock_test_idle_mutator_flush;
+mod mock_test_immix_live_bytes;
 mod mock_test_init_fork;
 #[cfg(feature = "is_mmtk_object")]
 mod mock_test_internal_ptr_before_object_ref;
@@ -51,19 +73,63 @@ mod mock_test_internal_ptr_normal_object;
 mod mock_test_is_in_mmtk_spaces;
 mod mock_test_issue139_allocate_non_multiple_of_min_alignment;
 mod mock_test_issue867_allocate_unrealistically_large_object;
+mod mock_test_los_nursery;
 #[cfg(feature = "malloc_counted_size")]
 mod mock_test_malloc_counted;
 mod mock_test_malloc_ms;
+mod mock_test_markcompact_handles;
+mod mock_test_marksweep_repack_constraints;
 #[cfg(all(target_pointer_width = "64", feature = "vm_space"))]
 mod mock_test_mmtk_julia_pr_143;
+#[cfg(all(target_pointer_width = "64", not(feature = "nogc_lock_free")))]
+mod mock_test_multiple_instances;
+mod mock_test_mutator_rebind;
 #[cfg(feature = "nogc_lock_free")]
 mod mock_test_nogc_lock_free;
+mod mock_test_nonmoving_space;
+mod mock_test_notify_idle;
+mod mock_test_nursery_off_heap_objects;
+#[cfg(feature = "address_based_hashing")]
+mod mock_test_object_hash;
+#[cfg(feature = "object_id")]
+mod mock_test_object_id;
+mod mock_test_object_space_info;
+#[cfg(feature = "object_user_data")]
+mod mock_test_object_user_data;
+mod mock_test_off_heap_objects;
+mod mock_test_oom_context;
+mod mock_test_pacer_finish_in_pause;
+mod mock_test_page_protect_fault;
+mod mock_test_pinned_root_batches;
+mod mock_test_reclaim_idle_mutator_blocks;
+mod mock_test_relocation_log;
+mod mock_test_remember_object;
+#[cfg(feature = "vo_bit")]
+mod mock_test_resurrection;
+mod mock_test_roots_descriptor;
+#[cfg(feature = "vo_bit")]
+mod mock_test_scoped_heap_traversal;
+#[cfg(all(feature = "ro_space", feature = "vo_bit"))]
+mod mock_test_seal_ro_space;
 mod mock_test_slots;
+mod mock_test_space_index;
+#[cfg(feature = "vo_bit")]
+mod mock_test_space_targeted_gc;
+mod mock_test_stable_roots;
+mod mock_test_sticky_immix_nursery_blocks;
+mod mock_test_tracing_queue_budget;
+#[cfg(feature = "vm_forwarding")]
+mod mock_test_vm_forwarding;
+#[cfg(target_pointer_width = "64")]
+mod mock_test_vm_layout_compressed_modes;
 #[cfg(target_pointer_width = "64")]
 mod mock_test_vm_layout_compressed_pointer;
 mod mock_test_vm_layout_default;
 mod mock_test_vm_layout_heap_start;
 mod mock_test_vm_layout_log_address_space;
+#[cfg(all(target_pointer_width = "64", feature = "vm_space"))]
+mod mock_test_vm_space_regions;
+mod mock_test_weak_processing_stats;
 
 mod mock_test_doc_avoid_resolving_allocator;
 mod mock_test_doc_mutator_storage;